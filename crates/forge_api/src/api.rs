@@ -1,14 +1,20 @@
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use anyhow::Result;
-use forge_app::{EnvironmentService, ForgeApp, FsSnapshotService, Infrastructure};
+use anyhow::{Context, Result};
+use forge_app::{
+    history_importer, EnvironmentService, ForgeApp, ForgeIndexer, FsSnapshotService, HistoryImport,
+    Infrastructure, SecretStore, TurnLock,
+};
 use forge_domain::*;
 use forge_infra::ForgeInfra;
-use forge_snaps::{SnapshotInfo, SnapshotMetadata};
+use forge_snaps::{SnapshotInfo, SnapshotMetadata, SnapshotSelector};
 use forge_stream::MpscStream;
 use serde_json::Value;
 
+use crate::config::{Config, CONFIG_FILE};
 use crate::executor::ForgeExecutorService;
 use crate::loader::ForgeLoaderService;
 use crate::suggestion::ForgeSuggestionService;
@@ -19,24 +25,62 @@ pub struct ForgeAPI<F> {
     executor_service: ForgeExecutorService<F>,
     suggestion_service: ForgeSuggestionService<F>,
     loader: ForgeLoaderService<F>,
+    info: InfoRegistry,
+    redactor: Arc<RwLock<Redactor>>,
 }
 
 impl<F: App + Infrastructure> ForgeAPI<F> {
     pub fn new(app: Arc<F>) -> Self {
+        let env = app.environment_service().get_environment();
+        let redactor = Arc::new(RwLock::new(
+            Redactor::default().secrets(
+                env.provider
+                    .key()
+                    .map(str::to_string)
+                    .into_iter()
+                    .chain(env.openai_key.clone())
+                    .chain(env.qdrant_key.clone()),
+            ),
+        ));
         Self {
             app: app.clone(),
-            executor_service: ForgeExecutorService::new(app.clone()),
+            executor_service: ForgeExecutorService::new(
+                app.clone(),
+                env.first_token_timeout,
+                env.question_timeout,
+                ConversationEnricher::new(env.cwd.clone(), ConversationEnricher::DEFAULT_DEBOUNCE),
+                redactor.clone(),
+                env.cwd.clone(),
+                env.turn_lock_path(),
+            ),
             suggestion_service: ForgeSuggestionService::new(app.clone()),
             loader: ForgeLoaderService::new(app.clone()),
+            info: crate::info::registry(&env),
+            redactor,
         }
     }
+
+    fn redact(&self, text: &str) -> Result<String> {
+        let redactor = self
+            .redactor
+            .read()
+            .map_err(|_| anyhow::anyhow!("Redactor is poisoned"))?;
+        Ok(redactor.redact(text))
+    }
+
+    /// Adds a section to `/info`
+    pub fn register_info(mut self, provider: impl InfoProvider + 'static) -> Self {
+        self.info = self.info.register(provider);
+        self
+    }
 }
 
 impl ForgeAPI<ForgeApp<ForgeInfra>> {
-    pub fn init(restricted: bool) -> Self {
-        let infra = Arc::new(ForgeInfra::new(restricted));
-        let app = Arc::new(ForgeApp::new(infra));
-        ForgeAPI::new(app)
+    /// Fails when the environment's network settings are invalid
+    pub fn init(restricted: bool, read_only: bool) -> Result<Self> {
+        let infra = Arc::new(ForgeInfra::new(restricted, read_only));
+        let app = Arc::new(ForgeApp::new(infra)?);
+        Ok(ForgeAPI::new(app))
     }
 }
 
@@ -70,6 +114,24 @@ impl<F: App + Infrastructure> API for ForgeAPI<F> {
             .await
     }
 
+    async fn restore_snapshot(&self, file_path: &Path, selector: &SnapshotSelector) -> Result<()> {
+        self.app
+            .file_snapshot_service()
+            .restore_snapshot(file_path, selector)
+            .await
+    }
+
+    async fn read_snapshot(
+        &self,
+        file_path: &Path,
+        selector: &SnapshotSelector,
+    ) -> Result<Vec<u8>> {
+        self.app
+            .file_snapshot_service()
+            .read_snapshot(file_path, selector)
+            .await
+    }
+
     async fn get_snapshot_by_timestamp(
         &self,
         file_path: &Path,
@@ -99,6 +161,13 @@ impl<F: App + Infrastructure> API for ForgeAPI<F> {
             .await
     }
 
+    async fn purge_scratch(&self, days: u32) -> Result<usize> {
+        self.app
+            .scratch_service()
+            .purge(Duration::from_secs(days as u64 * 24 * 60 * 60))
+            .await
+    }
+
     async fn suggestions(&self) -> Result<Vec<File>> {
         self.suggestion_service.suggestions().await
     }
@@ -111,6 +180,18 @@ impl<F: App + Infrastructure> API for ForgeAPI<F> {
         Ok(self.app.provider_service().models().await?)
     }
 
+    async fn raw_chat(
+        &self,
+        model: &ModelId,
+        context: forge_domain::Context,
+    ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
+        self.app.provider_service().chat(model, context).await
+    }
+
+    fn transformers(&self) -> Option<TransformerReport> {
+        self.app.provider_service().transformers()
+    }
+
     async fn chat(
         &self,
         chat: ChatRequest,
@@ -118,10 +199,18 @@ impl<F: App + Infrastructure> API for ForgeAPI<F> {
         Ok(self.executor_service.chat(chat).await?)
     }
 
-    async fn init(&self, workflow: Workflow) -> anyhow::Result<ConversationId> {
+    async fn init(&self, mut workflow: Workflow) -> anyhow::Result<ConversationId> {
+        workflow.resolve_variables()?;
         self.app.conversation_service().create(workflow).await
     }
 
+    async fn info_sections(&self) -> anyhow::Result<Vec<InfoSection>> {
+        // Providers may run commands such as git, which would block the
+        // runtime
+        let info = self.info.clone();
+        Ok(tokio::task::spawn_blocking(move || info.sections()).await?)
+    }
+
     fn environment(&self) -> Environment {
         self.app.environment_service().get_environment().clone()
     }
@@ -137,6 +226,38 @@ impl<F: App + Infrastructure> API for ForgeAPI<F> {
         self.app.conversation_service().get(conversation_id).await
     }
 
+    async fn conversations(
+        &self,
+        tag: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<Vec<Conversation>> {
+        let mut conversations = self.app.conversation_service().list().await?;
+        if let Some(tag) = tag {
+            conversations.retain(|conversation| conversation.has_tag(tag));
+        }
+        if let Some(query) = query {
+            conversations.retain(|conversation| conversation.mentions(query));
+        }
+        Ok(conversations)
+    }
+
+    async fn turn_timings(&self, conversation_id: &ConversationId) -> Result<Vec<TurnTiming>> {
+        Ok(self
+            .app
+            .conversation_service()
+            .get(conversation_id)
+            .await?
+            .map(|conversation| conversation.timings)
+            .unwrap_or_default())
+    }
+
+    async fn delete_conversation(&self, conversation_id: &ConversationId) -> Result<bool> {
+        self.app
+            .conversation_service()
+            .delete(conversation_id)
+            .await
+    }
+
     async fn get_variable(
         &self,
         conversation_id: &ConversationId,
@@ -159,4 +280,203 @@ impl<F: App + Infrastructure> API for ForgeAPI<F> {
             .set_variable(conversation_id, key, value)
             .await
     }
+
+    async fn add_tags(
+        &self,
+        conversation_id: &ConversationId,
+        tags: &[String],
+    ) -> anyhow::Result<Vec<String>> {
+        let mut added = Vec::new();
+        for tag in tags {
+            if self
+                .app
+                .conversation_service()
+                .add_tag(conversation_id, tag)
+                .await?
+            {
+                added.push(tag.clone());
+            }
+        }
+        Ok(added)
+    }
+
+    async fn interrupted(&self) -> anyhow::Result<Vec<Journal>> {
+        self.app.journal_service().pending().await
+    }
+
+    async fn restore(
+        &self,
+        mut workflow: Workflow,
+        journals: &[Journal],
+    ) -> anyhow::Result<ConversationId> {
+        workflow.resolve_variables()?;
+        let conversation_id = self.app.conversation_service().create(workflow).await?;
+        for journal in journals {
+            if let Some(context) = journal.recover() {
+                self.app
+                    .conversation_service()
+                    .set_context(&conversation_id, &journal.agent, context)
+                    .await?;
+            }
+        }
+
+        self.discard(journals).await?;
+        Ok(conversation_id)
+    }
+
+    async fn discard(&self, journals: &[Journal]) -> anyhow::Result<()> {
+        for journal in journals {
+            self.app
+                .journal_service()
+                .clear(&journal.conversation_id, &journal.agent)
+                .await?;
+        }
+        Ok(())
+    }
+
+    fn is_store_encrypted(&self) -> bool {
+        self.app.journal_service().is_encrypted()
+    }
+
+    async fn unlock_store(&self, passphrase: &str) -> anyhow::Result<()> {
+        self.app.journal_service().unlock(passphrase).await
+    }
+
+    async fn encrypt_store(&self, passphrase: &str) -> anyhow::Result<usize> {
+        self.app.journal_service().encrypt(passphrase).await
+    }
+
+    async fn decrypt_store(&self, passphrase: &str) -> anyhow::Result<usize> {
+        self.app.journal_service().decrypt(passphrase).await
+    }
+
+    async fn set_secret(&self, name: &str, value: &str) -> anyhow::Result<()> {
+        SecretStore::from_env(&self.environment()).set(name, value)
+    }
+
+    async fn secrets(&self) -> anyhow::Result<Vec<SecretInfo>> {
+        SecretStore::from_env(&self.environment()).list()
+    }
+
+    async fn remove_secret(&self, name: &str) -> anyhow::Result<bool> {
+        SecretStore::from_env(&self.environment()).remove(name)
+    }
+
+    async fn approve(&self, call_id: &ToolCallId, approved: bool) -> anyhow::Result<()> {
+        self.app.approval_service().respond(call_id, approved).await
+    }
+
+    async fn answer(&self, call_id: &ToolCallId, answer: UserAnswer) -> anyhow::Result<()> {
+        self.app
+            .interaction_service()
+            .respond(call_id, answer)
+            .await
+    }
+
+    async fn run_command(&self, command: &str) -> anyhow::Result<String> {
+        let call = ToolCallFull::new(ToolName::new("tool_forge_process_shell"))
+            .arguments(serde_json::json!({ "command": command, "cwd": self.environment().cwd }));
+        let result = self.app.tool_service().call(call).await;
+        // The output is sent to the model along with the message
+        let output = self.redact(&result.content.to_string())?;
+        if result.is_error {
+            anyhow::bail!(output.trim().to_string())
+        }
+        Ok(output)
+    }
+
+    fn stop_commands(&self) -> usize {
+        forge_app::stop_running_commands()
+    }
+
+    fn unprotect(&self, pattern: &str) -> anyhow::Result<()> {
+        self.app.protection_service().unprotect(pattern)
+    }
+
+    async fn update_provider_key(&self, key: String) -> anyhow::Result<()> {
+        self.app.provider_service().set_key(key.clone()).await?;
+        {
+            let mut redactor = self
+                .redactor
+                .write()
+                .map_err(|_| anyhow::anyhow!("Redactor is poisoned"))?;
+            *redactor = redactor.clone().secrets([key.clone()]);
+        }
+
+        let env = self.environment();
+        let path = env.credentials_path();
+        let mut credentials = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => Credentials::parse(&content),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Credentials::default(),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to read credentials {}", path.display()))
+            }
+        };
+        credentials.set(env.provider.key_var(), key);
+
+        // The file holds secrets, only the user may read it
+        forge_app::write_private(&path, &credentials.to_string())
+            .with_context(|| format!("Failed to write credentials {}", path.display()))
+    }
+
+    async fn export_config(&self) -> Result<String> {
+        Config::export(&self.environment().base_path.join(CONFIG_FILE))
+    }
+
+    async fn import_config(&self, data: &str) -> Result<Config> {
+        Config::import(&self.environment().base_path.join(CONFIG_FILE), data)
+    }
+
+    async fn set_config(&self, key: &str, value: &str) -> Result<Config> {
+        Config::set(&self.environment().base_path.join(CONFIG_FILE), key, value)
+    }
+
+    fn index(
+        &self,
+        request: IndexRequest,
+        cancelled: Arc<AtomicBool>,
+    ) -> MpscStream<Result<IndexProgress>> {
+        let indexer = ForgeIndexer::new(self.app.clone());
+        MpscStream::spawn(move |tx| async move {
+            let result = indexer
+                .run(&request, &cancelled, |event| {
+                    let tx = tx.clone();
+                    async move {
+                        let _ = tx.send(Ok(event)).await;
+                    }
+                })
+                .await;
+            if let Err(err) = result {
+                let _ = tx.send(Err(err)).await;
+            }
+        })
+    }
+
+    fn rebuild_index(&self, cancelled: Arc<AtomicBool>) -> MpscStream<Result<IndexProgress>> {
+        let indexer = ForgeIndexer::new(self.app.clone());
+        MpscStream::spawn(move |tx| async move {
+            let result = indexer
+                .rebuild(&cancelled, |event| {
+                    let tx = tx.clone();
+                    async move {
+                        let _ = tx.send(Ok(event)).await;
+                    }
+                })
+                .await;
+            if let Err(err) = result {
+                let _ = tx.send(Err(err)).await;
+            }
+        })
+    }
+
+    fn lock_turns(&self) -> Result<TurnLock> {
+        TurnLock::maintenance(&self.environment().turn_lock_path())
+    }
+
+    async fn import_history(&self, format: ImportFormat, path: &Path) -> Result<ImportReport> {
+        HistoryImport::new(self.app.journal_service())
+            .run(history_importer(format).as_ref(), path)
+            .await
+    }
 }
@@ -0,0 +1,493 @@
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use forge_domain::{CompactionPolicy, RefusalPolicy, ToolResultPolicy};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+use crate::config_key::{remove_unknown_keys, ConfigKey, CONFIG_KEYS};
+
+/// Name of the file in the config directory that holds the settings below
+pub const CONFIG_FILE: &str = "config.yaml";
+
+/// Settings of the terminal UI. Every key is optional and falls back to its
+/// default. Unknown keys are rejected on import, a config file with unknown
+/// keys, eg. written by a newer version, loads with a warning.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub tool_output: ToolOutput,
+    pub inline_commands: InlineCommands,
+    pub self_update: SelfUpdate,
+    pub queue: Queue,
+    pub markdown: Markdown,
+    /// How replies declining a request are recognized and whether they are
+    /// retried
+    pub refusal: RefusalPolicy,
+    /// Whether requests exceeding the model's context are compacted and sent
+    /// again
+    pub compaction: CompactionPolicy,
+    /// How tool results are cut and rewritten before they are sent to the
+    /// model
+    pub tool_results: ToolResultPolicy,
+    /// Keys of the config file that aren't settings. They are ignored, and
+    /// kept in the file when a setting is changed.
+    #[serde(skip)]
+    pub unknown_keys: Vec<String>,
+}
+
+/// How the output of tool calls is printed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolOutput {
+    /// Print tool outputs even when `--verbose` isn't passed
+    pub verbose: bool,
+    /// Number of lines of a tool output that are printed when `truncate` is
+    /// set
+    pub max_lines: usize,
+    /// Clip tool outputs that are longer than `max_lines`
+    pub truncate: bool,
+}
+
+impl Default for ToolOutput {
+    fn default() -> Self {
+        Self { verbose: false, max_lines: 50, truncate: true }
+    }
+}
+
+/// How !`command` in messages is replaced with the command's output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InlineCommands {
+    /// Run the commands, otherwise messages are sent as they were typed
+    pub enabled: bool,
+    /// Size of a command's output past which it is cut
+    pub max_output_bytes: usize,
+}
+
+impl Default for InlineCommands {
+    fn default() -> Self {
+        Self { enabled: true, max_output_bytes: 16 * 1024 }
+    }
+}
+
+/// Whether `forge upgrade` may replace the binary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SelfUpdate {
+    /// Set to false for installs managed by a package manager
+    pub enabled: bool,
+}
+
+impl Default for SelfUpdate {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// How messages typed while a turn runs are sent
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Queue {
+    /// Dollar cost of the conversation past which queued messages wait for
+    /// the user instead of being sent
+    pub max_cost: Option<f64>,
+}
+
+/// How the agent's replies are printed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Markdown {
+    /// Render headers, lists and emphasis, otherwise the markdown is printed
+    /// as the agent wrote it
+    pub enabled: bool,
+}
+
+impl Default for Markdown {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+impl Queue {
+    /// Whether another queued message may be sent. A conversation whose cost
+    /// is unknown is never held back.
+    pub fn within_budget(&self, cost: Option<f64>) -> bool {
+        match (self.max_cost, cost) {
+            (Some(max_cost), Some(cost)) => cost < max_cost,
+            _ => true,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config from the given file, using the defaults if it doesn't
+    /// exist. Unknown keys are left out and listed in `unknown_keys`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let document = Self::read(path)?;
+        Self::from_document(document)
+            .with_context(|| format!("Failed to parse config {}", path.display()))
+    }
+
+    /// Changes a setting of the config file at the given path. The value is
+    /// parsed as the type of the key, and the file keeps its unknown keys.
+    pub fn set(path: &Path, key: &str, value: &str) -> Result<Self> {
+        let key = ConfigKey::find(key)?;
+        let value = key
+            .kind
+            .parse(value)
+            .with_context(|| format!("Invalid value for {}", key.name))?;
+
+        let mut document = Self::read(path)?;
+        key.set(&mut document, value);
+        let config = Self::from_document(document.clone())?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_yaml::to_string(&document)?)
+            .with_context(|| format!("Failed to write config {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// The settings `/config set` can change, with their values
+    pub fn entries(&self) -> Result<Vec<ConfigEntry>> {
+        let current = serde_yaml::to_value(self)?;
+        let default = serde_yaml::to_value(Self::default())?;
+        Ok(CONFIG_KEYS
+            .iter()
+            .map(|key| {
+                let value = key.get(&current).cloned().unwrap_or_default();
+                let is_default = key.get(&default) == Some(&value);
+                ConfigEntry { key, value, is_default }
+            })
+            .collect())
+    }
+
+    /// The YAML document of the config file, an empty one when the file
+    /// doesn't exist
+    fn read(path: &Path) -> Result<Value> {
+        match std::fs::read_to_string(path) {
+            Ok(content) if content.trim().is_empty() => Ok(Value::Mapping(Mapping::new())),
+            Ok(content) => serde_yaml::from_str(&content)
+                .with_context(|| format!("Failed to parse config {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Value::Mapping(Mapping::new()))
+            }
+            Err(err) => {
+                Err(err).with_context(|| format!("Failed to read config {}", path.display()))
+            }
+        }
+    }
+
+    fn from_document(mut document: Value) -> Result<Self> {
+        let unknown_keys =
+            remove_unknown_keys(&mut document, &serde_yaml::to_value(Self::default())?);
+        let config: Self = serde_yaml::from_value(document)?;
+        Ok(Self { unknown_keys, ..config })
+    }
+
+    /// Parses the YAML of a config file. An empty document is the default
+    /// config.
+    pub fn parse(content: &str) -> Result<Self> {
+        if content.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        Ok(serde_yaml::from_str(content)?)
+    }
+
+    /// Serializes every setting, defaults included
+    pub fn to_yaml(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Serializes the config stored at the given path
+    pub fn export(path: &Path) -> Result<String> {
+        Self::load(path)?.to_yaml()
+    }
+
+    /// Replaces the config stored at the given path with `data`, once it
+    /// parsed without unknown or malformed keys. The file is left as it was
+    /// when `data` is invalid.
+    pub fn import(path: &Path, data: &str) -> Result<Self> {
+        let config = Self::parse(data).context("Invalid config")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, config.to_yaml()?)
+            .with_context(|| format!("Failed to write config {}", path.display()))?;
+        Ok(config)
+    }
+}
+
+/// A setting as `/config list` shows it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigEntry {
+    pub key: &'static ConfigKey,
+    pub value: Value,
+    /// Whether the value is the default one
+    pub is_default: bool,
+}
+
+impl Display for ConfigEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Value::Null => write!(f, "none")?,
+            Value::Bool(value) => write!(f, "{value}")?,
+            Value::Number(value) => write!(f, "{value}")?,
+            value => write!(f, "{value:?}")?,
+        }
+        write!(f, " ({}", self.key.kind)?;
+        if self.is_default {
+            write!(f, ", default")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl ToolOutput {
+    /// Clips the output to `max_lines` lines and tells how many were left out
+    pub fn clip(&self, output: &str) -> String {
+        let total = output.lines().count();
+        if !self.truncate || total <= self.max_lines {
+            return output.to_string();
+        }
+
+        let mut clipped = output
+            .lines()
+            .take(self.max_lines)
+            .collect::<Vec<_>>()
+            .join("\n");
+        clipped.push_str(&format!("\n…{} more lines", total - self.max_lines));
+        clipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_clip_short_output() {
+        let fixture = ToolOutput { max_lines: 3, ..Default::default() };
+
+        let actual = fixture.clip("one\ntwo\nthree");
+
+        let expected = "one\ntwo\nthree";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_clip_long_output() {
+        let fixture = ToolOutput { max_lines: 2, ..Default::default() };
+
+        let actual = fixture.clip("one\ntwo\nthree\nfour\nfive");
+
+        let expected = "one\ntwo\n…3 more lines";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_clip_disabled() {
+        let fixture = ToolOutput { max_lines: 2, truncate: false, ..Default::default() };
+
+        let actual = fixture.clip("one\ntwo\nthree");
+
+        let expected = "one\ntwo\nthree";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_queue_budget() {
+        let fixture = Queue { max_cost: Some(1.0) };
+
+        let actual = [
+            fixture.within_budget(Some(0.5)),
+            fixture.within_budget(Some(1.0)),
+            fixture.within_budget(None),
+            Queue::default().within_budget(Some(100.0)),
+        ];
+
+        assert_eq!(actual, [true, false, true, true]);
+    }
+
+    #[test]
+    fn test_load_partial_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        std::fs::write(&path, "tool_output:\n  max_lines: 10\n").unwrap();
+
+        let actual = Config::load(&path).unwrap();
+
+        let expected = Config {
+            tool_output: ToolOutput { max_lines: 10, ..Default::default() },
+            ..Default::default()
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let config = Config {
+            tool_output: ToolOutput { verbose: true, max_lines: 10, truncate: false },
+            inline_commands: InlineCommands { enabled: false, max_output_bytes: 1024 },
+            self_update: SelfUpdate { enabled: false },
+            queue: Queue { max_cost: Some(2.5) },
+            markdown: Markdown { enabled: false },
+            refusal: RefusalPolicy { retry: true, patterns: vec!["^no can do".to_string()] },
+            compaction: CompactionPolicy { auto: false },
+            tool_results: ToolResultPolicy { max_bytes: 4096, relative_paths: true },
+            unknown_keys: Vec::new(),
+        };
+        std::fs::write(source.path().join(CONFIG_FILE), config.to_yaml().unwrap()).unwrap();
+
+        let exported = Config::export(&source.path().join(CONFIG_FILE)).unwrap();
+        let imported = Config::import(&target.path().join(CONFIG_FILE), &exported).unwrap();
+
+        assert_eq!(imported, config);
+        assert_eq!(
+            Config::load(&target.path().join(CONFIG_FILE)).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_export_missing_config_has_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let exported = Config::export(&dir.path().join(CONFIG_FILE)).unwrap();
+
+        assert_eq!(Config::parse(&exported).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_import_rejects_unknown_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        std::fs::write(&path, "tool_output:\n  max_lines: 10\n").unwrap();
+
+        let actual = [
+            "tool_output:\n  max_line: 20\n",
+            "colors: true\n",
+            "tool_output:\n  max_lines: many\n",
+        ]
+        .map(|data| Config::import(&path, data).is_err());
+
+        assert_eq!(actual, [true, true, true]);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "tool_output:\n  max_lines: 10\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_can_be_disabled() {
+        let actual = Config::parse("markdown:\n  enabled: false\n").unwrap();
+
+        assert!(Config::default().markdown.enabled);
+        assert!(!actual.markdown.enabled);
+    }
+
+    #[test]
+    fn test_load_missing_config() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let actual = Config::load(&dir.path().join(CONFIG_FILE)).unwrap();
+
+        assert_eq!(actual, Config::default());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_ignored_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        std::fs::write(
+            &path,
+            "theme: dark\ntool_output:\n  max_lines: 10\n  wrap: true\n",
+        )
+        .unwrap();
+
+        let actual = Config::load(&path).unwrap();
+
+        let expected = Config {
+            tool_output: ToolOutput { max_lines: 10, ..Default::default() },
+            unknown_keys: vec!["theme".to_string(), "tool_output.wrap".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_set_keeps_unknown_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        std::fs::write(&path, "theme: dark\ntool_output:\n  wrap: true\n").unwrap();
+
+        let actual = Config::set(&path, "tool_output.max_lines", "10").unwrap();
+
+        assert_eq!(actual.tool_output.max_lines, 10);
+        let expected = "theme: dark\ntool_output:\n  wrap: true\n  max_lines: 10\n";
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+        assert_eq!(Config::load(&path).unwrap(), actual);
+    }
+
+    #[test]
+    fn test_invalid_set_leaves_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        std::fs::write(&path, "markdown:\n  enabled: false\n").unwrap();
+
+        let actual = [
+            ("markdown.enable", "true"),
+            ("tool_output.max_lines", "many"),
+        ]
+        .map(|(key, value)| format!("{:#}", Config::set(&path, key, value).unwrap_err()));
+
+        let expected = [
+            "Unknown key 'markdown.enable', did you mean 'markdown.enabled'?",
+            "Invalid value for tool_output.max_lines: expected a whole number, got 'many'",
+        ];
+        assert_eq!(actual, expected);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "markdown:\n  enabled: false\n"
+        );
+    }
+
+    #[test]
+    fn test_entries_tell_defaults_from_overrides() {
+        let fixture = Config {
+            tool_output: ToolOutput { max_lines: 10, ..Default::default() },
+            queue: Queue { max_cost: Some(2.5) },
+            ..Default::default()
+        };
+
+        let actual = fixture
+            .entries()
+            .unwrap()
+            .iter()
+            .map(|entry| format!("{} = {entry}", entry.key.name))
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            "tool_output.verbose = false (bool, default)",
+            "tool_output.max_lines = 10 (count)",
+            "tool_output.truncate = true (bool, default)",
+            "inline_commands.enabled = true (bool, default)",
+            "inline_commands.max_output_bytes = 16384 (count, default)",
+            "self_update.enabled = true (bool, default)",
+            "queue.max_cost = 2.5 (dollars or none)",
+            "markdown.enabled = true (bool, default)",
+            "refusal.retry = false (bool, default)",
+            "compaction.auto = true (bool, default)",
+            "tool_results.max_bytes = 65536 (count, default)",
+            "tool_results.relative_paths = false (bool, default)",
+        ];
+        assert_eq!(actual, expected);
+    }
+}
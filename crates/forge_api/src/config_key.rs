@@ -0,0 +1,279 @@
+use std::fmt::{self, Display, Formatter};
+
+use anyhow::{anyhow, bail, Result};
+use forge_domain::edit_distance;
+use serde_yaml::{Mapping, Value};
+
+/// Keys this many edits away from an unknown key are suggested for it
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+/// Type of a setting, which tells how a typed value is parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Bool,
+    /// A whole number, zero or more
+    Count,
+    /// An amount of dollars, or `none`
+    OptionalCost,
+}
+
+impl ValueKind {
+    /// Parses a typed value into the YAML of the setting
+    pub fn parse(self, value: &str) -> Result<Value> {
+        let value = value.trim();
+        match self {
+            ValueKind::Bool => match value {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => bail!("expected one of: true, false, got '{value}'"),
+            },
+            ValueKind::Count => value
+                .parse::<u64>()
+                .map(Value::from)
+                .map_err(|_| anyhow!("expected a whole number, got '{value}'")),
+            ValueKind::OptionalCost => match value {
+                "none" => Ok(Value::Null),
+                _ => value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|cost| cost.is_finite() && *cost >= 0.0)
+                    .map(Value::from)
+                    .ok_or_else(|| anyhow!("expected an amount of dollars or none, got '{value}'")),
+            },
+        }
+    }
+}
+
+impl Display for ValueKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueKind::Bool => write!(f, "bool"),
+            ValueKind::Count => write!(f, "count"),
+            ValueKind::OptionalCost => write!(f, "dollars or none"),
+        }
+    }
+}
+
+/// A setting of the config file that `/config set` can change, named by its
+/// dotted path in the file
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConfigKey {
+    pub name: &'static str,
+    pub kind: ValueKind,
+    pub description: &'static str,
+}
+
+/// Every setting `/config set` can change. Refusal patterns are lists of
+/// regexes, they are edited in the file.
+pub const CONFIG_KEYS: &[ConfigKey] = &[
+    ConfigKey {
+        name: "tool_output.verbose",
+        kind: ValueKind::Bool,
+        description: "Print tool outputs even when --verbose isn't passed",
+    },
+    ConfigKey {
+        name: "tool_output.max_lines",
+        kind: ValueKind::Count,
+        description: "Lines of a tool output printed when it's truncated",
+    },
+    ConfigKey {
+        name: "tool_output.truncate",
+        kind: ValueKind::Bool,
+        description: "Clip tool outputs longer than max_lines",
+    },
+    ConfigKey {
+        name: "inline_commands.enabled",
+        kind: ValueKind::Bool,
+        description: "Replace !`command` in messages with the command's output",
+    },
+    ConfigKey {
+        name: "inline_commands.max_output_bytes",
+        kind: ValueKind::Count,
+        description: "Size of a command's output past which it's cut",
+    },
+    ConfigKey {
+        name: "self_update.enabled",
+        kind: ValueKind::Bool,
+        description: "Let forge upgrade replace the binary",
+    },
+    ConfigKey {
+        name: "queue.max_cost",
+        kind: ValueKind::OptionalCost,
+        description: "Conversation cost past which queued messages wait",
+    },
+    ConfigKey {
+        name: "markdown.enabled",
+        kind: ValueKind::Bool,
+        description: "Render the markdown of the agent's replies",
+    },
+    ConfigKey {
+        name: "refusal.retry",
+        kind: ValueKind::Bool,
+        description: "Ask again once when the model declines a request",
+    },
+    ConfigKey {
+        name: "compaction.auto",
+        kind: ValueKind::Bool,
+        description: "Compact and resend requests exceeding the model's context",
+    },
+    ConfigKey {
+        name: "tool_results.max_bytes",
+        kind: ValueKind::Count,
+        description: "Size of a tool result past which it's cut for the model",
+    },
+    ConfigKey {
+        name: "tool_results.relative_paths",
+        kind: ValueKind::Bool,
+        description: "Show the model paths relative to the working directory",
+    },
+];
+
+impl ConfigKey {
+    /// Looks up a setting, suggesting the closest one for unknown keys
+    pub fn find(name: &str) -> Result<&'static ConfigKey> {
+        if let Some(key) = CONFIG_KEYS.iter().find(|key| key.name == name) {
+            return Ok(key);
+        }
+        let closest = CONFIG_KEYS
+            .iter()
+            .map(|key| (edit_distance(name, key.name), key.name))
+            .min();
+        match closest {
+            Some((distance, closest)) if distance <= MAX_SUGGESTION_DISTANCE => {
+                bail!("Unknown key '{name}', did you mean '{closest}'?")
+            }
+            _ => bail!("Unknown key '{name}', /config list shows the known keys"),
+        }
+    }
+
+    /// The value of the setting in a serialized config
+    pub(crate) fn get<'a>(&self, document: &'a Value) -> Option<&'a Value> {
+        self.name
+            .split('.')
+            .try_fold(document, |value, part| value.get(part))
+    }
+
+    /// Sets the value of the setting in a config file, adding the sections
+    /// it's missing
+    pub(crate) fn set(&self, document: &mut Value, value: Value) {
+        let mut current = document;
+        for part in self.name.split('.') {
+            if !current.is_mapping() {
+                *current = Value::Mapping(Mapping::new());
+            }
+            let Value::Mapping(mapping) = current else {
+                unreachable!("replaced with a mapping above")
+            };
+            current = mapping.entry(Value::from(part)).or_insert(Value::Null);
+        }
+        *current = value;
+    }
+}
+
+/// Removes the keys of a config file that aren't in `known`, the serialized
+/// default config, and returns their dotted paths
+pub(crate) fn remove_unknown_keys(document: &mut Value, known: &Value) -> Vec<String> {
+    fn remove(document: &mut Value, known: &Value, prefix: &str, unknown: &mut Vec<String>) {
+        let (Value::Mapping(document), Value::Mapping(known)) = (document, known) else {
+            return;
+        };
+        document.retain(|key, value| {
+            let name = key
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{key:?}"));
+            let path = format!("{prefix}{name}");
+            match known.get(key) {
+                Some(known) => {
+                    remove(value, known, &format!("{path}."), unknown);
+                    true
+                }
+                None => {
+                    unknown.push(path);
+                    false
+                }
+            }
+        });
+    }
+
+    let mut unknown = Vec::new();
+    remove(document, known, "", &mut unknown);
+    unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_bool() {
+        let actual = ["true", " false ", "yes"]
+            .map(|value| ValueKind::Bool.parse(value).map_err(|err| err.to_string()));
+
+        let expected = [
+            Ok(Value::Bool(true)),
+            Ok(Value::Bool(false)),
+            Err("expected one of: true, false, got 'yes'".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_count() {
+        let actual = ["20", "-1", "many"]
+            .map(|value| ValueKind::Count.parse(value).map_err(|err| err.to_string()));
+
+        let expected = [
+            Ok(Value::from(20u64)),
+            Err("expected a whole number, got '-1'".to_string()),
+            Err("expected a whole number, got 'many'".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_optional_cost() {
+        let actual = ["2.5", "none", "-3", "lots"].map(|value| {
+            ValueKind::OptionalCost
+                .parse(value)
+                .map_err(|err| err.to_string())
+        });
+
+        let expected = [
+            Ok(Value::from(2.5)),
+            Ok(Value::Null),
+            Err("expected an amount of dollars or none, got '-3'".to_string()),
+            Err("expected an amount of dollars or none, got 'lots'".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_unknown_key_suggests_the_closest() {
+        let actual = ["tool_output.max_line", "markdown.enable", "primary-model"]
+            .map(|name| ConfigKey::find(name).unwrap_err().to_string());
+
+        let expected = [
+            "Unknown key 'tool_output.max_line', did you mean 'tool_output.max_lines'?",
+            "Unknown key 'markdown.enable', did you mean 'markdown.enabled'?",
+            "Unknown key 'primary-model', /config list shows the known keys",
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_set_adds_missing_sections() {
+        let key = ConfigKey::find("tool_output.max_lines").unwrap();
+        let mut fixture: Value = serde_yaml::from_str("markdown:\n  enabled: false\n").unwrap();
+
+        key.set(&mut fixture, Value::from(10u64));
+
+        let expected: Value =
+            serde_yaml::from_str("markdown:\n  enabled: false\ntool_output:\n  max_lines: 10\n")
+                .unwrap();
+        assert_eq!(fixture, expected);
+        assert_eq!(key.get(&fixture), Some(&Value::from(10u64)));
+    }
+}
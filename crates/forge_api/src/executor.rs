@@ -1,14 +1,43 @@
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use forge_domain::{AgentMessage, App, ChatRequest, ChatResponse, Orchestrator};
+use forge_app::TurnLock;
+use forge_domain::{
+    AgentMessage, App, ChatRequest, ChatResponse, ConversationEnricher, FirstTokenTimeout,
+    Orchestrator, Redactor, RefusalDetector,
+};
 use forge_stream::MpscStream;
 
 pub struct ForgeExecutorService<F> {
     app: Arc<F>,
+    first_token_timeout: FirstTokenTimeout,
+    question_timeout: Duration,
+    enricher: Arc<ConversationEnricher>,
+    /// Shared with the API, which adds the keys set during the session
+    redactor: Arc<RwLock<Redactor>>,
+    cwd: PathBuf,
+    turn_lock: PathBuf,
 }
 impl<F: App> ForgeExecutorService<F> {
-    pub fn new(infra: Arc<F>) -> Self {
-        Self { app: infra }
+    pub fn new(
+        infra: Arc<F>,
+        first_token_timeout: FirstTokenTimeout,
+        question_timeout: Duration,
+        enricher: ConversationEnricher,
+        redactor: Arc<RwLock<Redactor>>,
+        cwd: PathBuf,
+        turn_lock: PathBuf,
+    ) -> Self {
+        Self {
+            app: infra,
+            first_token_timeout,
+            question_timeout,
+            enricher: Arc::new(enricher),
+            redactor,
+            cwd,
+            turn_lock,
+        }
     }
 }
 
@@ -18,14 +47,39 @@ impl<F: App> ForgeExecutorService<F> {
         request: ChatRequest,
     ) -> anyhow::Result<MpscStream<anyhow::Result<AgentMessage<ChatResponse>>>> {
         let app = self.app.clone();
+        let first_token_timeout = self.first_token_timeout;
+        let question_timeout = self.question_timeout;
+        let enricher = self.enricher.clone();
+        let redactor = self
+            .redactor
+            .read()
+            .map_err(|_| anyhow::anyhow!("Redactor is poisoned"))?
+            .clone();
+        let cwd = self.cwd.clone();
+        let refusal = RefusalDetector::new(&request.refusal)?;
+        // Held until the turn ends, so maintenance can't rewrite what it reads
+        let lock = TurnLock::turn(&self.turn_lock)?;
 
         Ok(MpscStream::spawn(move |tx| async move {
+            let _lock = lock;
             let tx = Arc::new(tx);
-            let orch = Orchestrator::new(app, request.conversation_id, Some(tx.clone()));
+            let orch = Orchestrator::new(app, request.conversation_id, Some(tx.clone()))
+                .dry_run(request.dry_run)
+                .iteration_limit_action(request.on_iteration_limit)
+                .first_token_timeout(first_token_timeout)
+                .question_timeout(question_timeout)
+                .redactor(redactor)
+                .refusal(refusal)
+                .compaction(request.compaction)
+                .tool_results(request.tool_results)
+                .cwd(cwd);
 
-            match orch.dispatch(&request.event).await {
-                Ok(_) => {}
-                Err(err) => tx.send(Err(err)).await.unwrap(),
+            let result = match orch.dispatch(&request.event).await {
+                Ok(_) => orch.suggest(&enricher).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                tx.send(Err(err)).await.unwrap()
             }
         }))
     }
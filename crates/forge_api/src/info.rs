@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+use forge_domain::{Environment, InfoProvider, InfoRegistry, InfoSection, Provider};
+
+/// Sections contributed by the API, shown by `/info` and in the banner
+pub fn registry(env: &Environment) -> InfoRegistry {
+    InfoRegistry::default()
+        .register(EnvironmentInfo(env.clone()))
+        .register(PathsInfo(env.clone()))
+        .register(ProviderInfo(env.provider.clone()))
+        .register(GitInfo(env.cwd.clone()))
+}
+
+struct EnvironmentInfo(Environment);
+
+impl InfoProvider for EnvironmentInfo {
+    fn info(&self) -> InfoSection {
+        let env = &self.0;
+        let mut section = InfoSection::new("Environment")
+            .order(10)
+            .row("OS", &env.os)
+            .row("PID", env.pid)
+            .highlight("Working Directory", env.cwd.display())
+            .row("Shell", &env.shell);
+
+        if env.restricted {
+            section = section.highlight("Restricted", "yes");
+        }
+        if env.read_only {
+            section = section.highlight("Read-only", "yes");
+        }
+        section
+    }
+}
+
+struct PathsInfo(Environment);
+
+impl InfoProvider for PathsInfo {
+    fn info(&self) -> InfoSection {
+        let env = &self.0;
+        InfoSection::new("Paths")
+            .order(20)
+            .row("Config", env.base_path.display())
+            .row("Logs", env.log_path().display())
+            .row("Database", env.db_path().display())
+            .row("History", env.history_path().display())
+    }
+}
+
+struct ProviderInfo(Provider);
+
+impl InfoProvider for ProviderInfo {
+    fn info(&self) -> InfoSection {
+        let provider = &self.0;
+        let name = if provider.is_antinomy() {
+            "Antinomy"
+        } else if provider.is_open_router() {
+            "OpenRouter"
+        } else if provider.is_open_ai() {
+            "OpenAI"
+        } else if matches!(provider, Provider::Anthropic { .. }) {
+            "Anthropic"
+        } else {
+            "OpenAI compatible"
+        };
+
+        InfoSection::new("Provider")
+            .order(30)
+            .highlight("Provider", name)
+            .row("URL", provider.to_base_url())
+    }
+}
+
+/// The branch checked out in the working directory, read every time the
+/// section is shown. The section is empty outside of a git repository.
+struct GitInfo(PathBuf);
+
+impl InfoProvider for GitInfo {
+    fn info(&self) -> InfoSection {
+        let section = InfoSection::new("Git").order(40);
+        let branch = Command::new("git")
+            .args(["rev-parse", "--abbrev-ref", "HEAD"])
+            .current_dir(&self.0)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+
+        match branch {
+            Some(branch) => section.highlight("Branch", branch),
+            None => section,
+        }
+    }
+}
@@ -1,13 +1,23 @@
 mod api;
+mod config;
+mod config_key;
 mod executor;
+mod info;
 mod loader;
+mod project_config;
 mod suggestion;
 
 use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 
 pub use api::*;
+pub use config::*;
+pub use config_key::*;
+pub use forge_app::{http_client, TurnLock};
 pub use forge_domain::*;
 use forge_stream::MpscStream;
+pub use project_config::*;
 use serde_json::Value;
 
 #[async_trait::async_trait]
@@ -27,6 +37,20 @@ pub trait API: Sync + Send {
     /// Restore a file from its previous snapshot
     async fn restore_previous(&self, file_path: &Path) -> anyhow::Result<()>;
 
+    /// Restore a file from the snapshot picked by the selector
+    async fn restore_snapshot(
+        &self,
+        file_path: &Path,
+        selector: &forge_snaps::SnapshotSelector,
+    ) -> anyhow::Result<()>;
+
+    /// Read the content of the snapshot picked by the selector
+    async fn read_snapshot(
+        &self,
+        file_path: &Path,
+        selector: &forge_snaps::SnapshotSelector,
+    ) -> anyhow::Result<Vec<u8>>;
+
     /// Get a snapshot by timestamp
     async fn get_snapshot_by_timestamp(
         &self,
@@ -44,6 +68,10 @@ pub trait API: Sync + Send {
     /// Purge snapshots older than specified days
     async fn purge_older_than(&self, days: u32) -> anyhow::Result<usize>;
 
+    /// Removes the scratch directories of conversations that weren't touched
+    /// in the given number of days
+    async fn purge_scratch(&self, days: u32) -> anyhow::Result<usize>;
+
     /// Provides a list of files in the current working directory for auto
     /// completion
     async fn suggestions(&self) -> anyhow::Result<Vec<File>>;
@@ -55,6 +83,19 @@ pub trait API: Sync + Send {
     /// Provides a list of models available in the current environment
     async fn models(&self) -> anyhow::Result<Vec<Model>>;
 
+    /// The provider's request transformers and what each did to the last
+    /// request, `None` when the provider sends requests as they are
+    fn transformers(&self) -> Option<TransformerReport>;
+
+    /// Streams the model's reply to the context as the provider sends it.
+    /// Tool calls are part of the reply but never executed, and nothing is
+    /// stored, for embedders running their own tool loop.
+    async fn raw_chat(
+        &self,
+        model: &ModelId,
+        context: Context,
+    ) -> ResultStream<ChatCompletionMessage, anyhow::Error>;
+
     /// Executes a chat request and returns a stream of responses
     async fn chat(
         &self,
@@ -64,7 +105,11 @@ pub trait API: Sync + Send {
     /// Returns the current environment
     fn environment(&self) -> Environment;
 
-    /// Creates a new conversation with the given workflow
+    /// Returns the sections shown by `/info`, in the order they are shown
+    async fn info_sections(&self) -> anyhow::Result<Vec<InfoSection>>;
+
+    /// Creates a new conversation with the given workflow. Fails if the
+    /// workflow's variables don't match its variable schema.
     async fn init(&self, workflow: Workflow) -> anyhow::Result<ConversationId>;
 
     /// Loads a workflow configuration from the given path, current directory's
@@ -78,6 +123,25 @@ pub trait API: Sync + Send {
         conversation_id: &ConversationId,
     ) -> anyhow::Result<Option<Conversation>>;
 
+    /// Returns the conversations of this session and the imported ones,
+    /// pinned first and then the most recently updated, keeping those with
+    /// the tag and those whose title or messages contain the query
+    async fn conversations(
+        &self,
+        tag: Option<&str>,
+        query: Option<&str>,
+    ) -> anyhow::Result<Vec<Conversation>>;
+
+    /// Returns the timing breakdown of the conversation's most recent turns,
+    /// oldest first
+    async fn turn_timings(
+        &self,
+        conversation_id: &ConversationId,
+    ) -> anyhow::Result<Vec<TurnTiming>>;
+
+    /// Deletes the conversation along with its scratch directory
+    async fn delete_conversation(&self, conversation_id: &ConversationId) -> anyhow::Result<bool>;
+
     /// Gets a variable from the conversation
     async fn get_variable(
         &self,
@@ -92,4 +156,116 @@ pub trait API: Sync + Send {
         key: String,
         value: Value,
     ) -> anyhow::Result<()>;
+
+    /// Adds tags to the conversation, returning the ones it didn't have yet
+    async fn add_tags(
+        &self,
+        conversation_id: &ConversationId,
+        tags: &[String],
+    ) -> anyhow::Result<Vec<String>>;
+
+    /// Returns the journals of turns that were interrupted before completion
+    async fn interrupted(&self) -> anyhow::Result<Vec<Journal>>;
+
+    /// Restores interrupted turns into a new conversation, up to the last
+    /// completed step of each turn
+    async fn restore(
+        &self,
+        workflow: Workflow,
+        journals: &[Journal],
+    ) -> anyhow::Result<ConversationId>;
+
+    /// Discards the recovery data of interrupted turns
+    async fn discard(&self, journals: &[Journal]) -> anyhow::Result<()>;
+
+    /// Whether the conversation store is encrypted, it must be unlocked
+    /// before chatting then
+    fn is_store_encrypted(&self) -> bool;
+
+    /// Unlocks the encrypted conversation store, fails on a wrong passphrase
+    async fn unlock_store(&self, passphrase: &str) -> anyhow::Result<()>;
+
+    /// Encrypts the conversation store with a key derived from the
+    /// passphrase, returns how many files were converted
+    async fn encrypt_store(&self, passphrase: &str) -> anyhow::Result<usize>;
+
+    /// Converts the encrypted conversation store back to plain text, returns
+    /// how many files were converted
+    async fn decrypt_store(&self, passphrase: &str) -> anyhow::Result<usize>;
+
+    /// Stores a secret that the shell tool passes to commands referring to
+    /// it as `secret:NAME`, replacing its value when it exists
+    async fn set_secret(&self, name: &str, value: &str) -> anyhow::Result<()>;
+
+    /// Names of the stored secrets and when they were set, never their values
+    async fn secrets(&self) -> anyhow::Result<Vec<SecretInfo>>;
+
+    /// Removes a stored secret, returns whether it existed
+    async fn remove_secret(&self, name: &str) -> anyhow::Result<bool>;
+
+    /// Approves or rejects a tool call that is waiting for the user's decision
+    async fn approve(&self, call_id: &ToolCallId, approved: bool) -> anyhow::Result<()>;
+
+    /// Answers a question the agent asked the user
+    async fn answer(&self, call_id: &ToolCallId, answer: UserAnswer) -> anyhow::Result<()>;
+
+    /// Runs a shell command in the working directory through the shell tool,
+    /// so that it goes through the same checks as the agent's commands.
+    /// Fails with the command's output if it didn't succeed.
+    async fn run_command(&self, command: &str) -> anyhow::Result<String>;
+
+    /// Stops the shell commands that are still running, along with every
+    /// process they started, waiting for them to exit. Returns how many
+    /// commands were stopped.
+    fn stop_commands(&self) -> usize;
+
+    /// Lets the agent modify paths matched by the given protection rule until
+    /// the session ends
+    fn unprotect(&self, pattern: &str) -> anyhow::Result<()>;
+
+    /// Replaces the provider's API key once the provider accepted it, and
+    /// stores it so that it is used in later sessions too
+    async fn update_provider_key(&self, key: String) -> anyhow::Result<()>;
+
+    /// Serializes the settings of the config file, defaults included, so
+    /// they can be moved to another machine
+    async fn export_config(&self) -> anyhow::Result<String>;
+
+    /// Validates the serialized settings and replaces the config file with
+    /// them, returns the settings to apply to the running session
+    async fn import_config(&self, data: &str) -> anyhow::Result<Config>;
+
+    /// Validates a setting and stores it in the config file, returns the
+    /// settings to apply to the running session
+    async fn set_config(&self, key: &str, value: &str) -> anyhow::Result<Config>;
+
+    /// Chunks and embeds the requested files into the document index,
+    /// skipping files that are unchanged since they were last indexed.
+    /// Setting `cancelled` stops the run after the file being indexed.
+    fn index(
+        &self,
+        request: IndexRequest,
+        cancelled: Arc<AtomicBool>,
+    ) -> MpscStream<anyhow::Result<IndexProgress>>;
+
+    /// Drops the document index and indexes again every directory indexed
+    /// so far, from the files as they are on disk. Setting `cancelled` stops
+    /// the run after the file being indexed.
+    fn rebuild_index(
+        &self,
+        cancelled: Arc<AtomicBool>,
+    ) -> MpscStream<anyhow::Result<IndexProgress>>;
+
+    /// Locks out chat turns while derived data is rebuilt, fails while a
+    /// turn is running. The lock is released once dropped.
+    fn lock_turns(&self) -> anyhow::Result<TurnLock>;
+
+    /// Imports the history of another agent tool from a file, or from the
+    /// history files found in a directory, as stored conversations.
+    /// Conversations that were imported before are left as they are.
+    async fn import_history(
+        &self,
+        format: ImportFormat,
+        path: &Path,
+    ) -> anyhow::Result<ImportReport>;
 }
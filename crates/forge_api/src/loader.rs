@@ -2,10 +2,12 @@ use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::Context;
-use forge_app::{FsReadService, Infrastructure};
+use forge_app::{EnvironmentService, FsReadService, Infrastructure};
 use forge_domain::Workflow;
 use merge::Merge;
 
+use crate::project_config::ProjectConfig;
+
 // Default forge.yaml content embedded in the binary
 const DEFAULT_FORGE_WORKFLOW: &str = include_str!("../../../forge.default.yaml");
 
@@ -38,7 +40,8 @@ impl<F: Infrastructure> ForgeLoaderService<F> {
     ///   - Falls back to embedded default if forge.yaml doesn't exist
     ///
     /// When merging, the project's forge.yaml values take precedence over
-    /// defaults.
+    /// defaults. Without an explicit path, the nearest `.forge/config.toml`
+    /// is applied on top.
     pub async fn load(&self, path: Option<&Path>) -> anyhow::Result<Workflow> {
         // Determine the workflow source
         let source = match path {
@@ -48,11 +51,17 @@ impl<F: Infrastructure> ForgeLoaderService<F> {
         };
 
         // Load the workflow based on its source
-        match source {
-            WorkflowSource::ExplicitPath(path) => self.load_from_explicit_path(path).await,
-            WorkflowSource::Default => self.load_default_workflow(),
-            WorkflowSource::ProjectConfig => self.load_with_project_config().await,
+        let mut workflow = match source {
+            WorkflowSource::ExplicitPath(path) => return self.load_from_explicit_path(path).await,
+            WorkflowSource::Default => self.load_default_workflow()?,
+            WorkflowSource::ProjectConfig => self.load_with_project_config().await?,
+        };
+
+        let cwd = self.0.environment_service().get_environment().cwd;
+        if let Some(project_config) = ProjectConfig::load(&cwd)? {
+            project_config.apply(&mut workflow);
         }
+        Ok(workflow)
     }
 
     /// Loads a workflow from a specific file path
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use forge_domain::{ModelId, VerifyPolicy, Workflow};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Directory holding the project's settings
+pub const PROJECT_CONFIG_DIR: &str = ".forge";
+/// File in [`PROJECT_CONFIG_DIR`] holding the settings below
+pub const PROJECT_CONFIG_FILE: &str = "config.toml";
+
+/// Settings of the project Forge runs in. They apply on top of the workflow,
+/// project values winning.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Model used by every agent of the workflow
+    pub model: Option<ModelId>,
+    /// Rules added to the system prompt of every agent
+    pub rules: Option<String>,
+    /// Workflow variables, replacing those of the same name
+    pub variables: HashMap<String, Value>,
+    /// Checks run once an agent finishes a turn that modified files
+    pub verify: Option<VerifyPolicy>,
+}
+
+impl ProjectConfig {
+    /// Finds the nearest `.forge/config.toml`, starting at `cwd` and walking
+    /// up to the root
+    pub fn find(cwd: &Path) -> Option<PathBuf> {
+        cwd.ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_DIR).join(PROJECT_CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Reads the nearest project config, `None` when there is none
+    pub fn load(cwd: &Path) -> anyhow::Result<Option<Self>> {
+        let Some(path) = Self::find(cwd) else {
+            return Ok(None);
+        };
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read project config {}", path.display()))?;
+        Self::parse(&content)
+            .with_context(|| format!("Failed to parse project config {}", path.display()))
+            .map(Some)
+    }
+
+    /// Applies the project's settings to every agent of the workflow
+    pub fn apply(self, workflow: &mut Workflow) {
+        for agent in workflow.agents.iter_mut() {
+            if let Some(model) = self.model.as_ref() {
+                agent.model = Some(model.clone());
+            }
+            if let Some(rules) = self.rules.as_ref() {
+                if !agent.project_rules.is_empty() {
+                    agent.project_rules.push('\n');
+                }
+                agent.project_rules.push_str(rules);
+            }
+        }
+
+        if !self.variables.is_empty() {
+            workflow
+                .variables
+                .get_or_insert_with(HashMap::new)
+                .extend(self.variables);
+        }
+
+        if let Some(verify) = self.verify {
+            workflow.verify = Some(verify);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_domain::VerifyCommand;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn workflow(yaml: &str) -> Workflow {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn write_config(dir: &Path, content: &str) -> PathBuf {
+        let path = dir.join(PROJECT_CONFIG_DIR).join(PROJECT_CONFIG_FILE);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_find_nearest_config() {
+        let root = tempfile::tempdir().unwrap();
+        let project = root.path().join("project");
+        let nested = project.join("crates").join("core").join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+        write_config(root.path(), "model = \"outer\"");
+        let expected = write_config(&project, "model = \"inner\"");
+
+        let actual = ProjectConfig::find(&nested);
+
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn test_find_without_config() {
+        let root = tempfile::tempdir().unwrap();
+
+        let actual = ProjectConfig::find(root.path());
+
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn test_load_nearest_config() {
+        let root = tempfile::tempdir().unwrap();
+        let nested = root.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        write_config(root.path(), "model = \"outer\"");
+        write_config(
+            &root.path().join("a"),
+            "model = \"inner\"\nrules = \"Use tabs\"",
+        );
+
+        let actual = ProjectConfig::load(&nested).unwrap();
+
+        let expected = ProjectConfig {
+            model: Some(ModelId::new("inner")),
+            rules: Some("Use tabs".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_keys() {
+        let actual = ProjectConfig::parse("modle = \"typo\"");
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn test_apply_project_values_win() {
+        let mut workflow = workflow(
+            r#"
+agents:
+  - id: developer
+    model: global-model
+    project_rules: Be concise
+variables:
+  user_name: Global
+  mode: ACT
+"#,
+        );
+        let fixture = ProjectConfig::parse(
+            r#"
+model = "project-model"
+rules = "Run cargo fmt before committing"
+
+[variables]
+user_name = "Project"
+"#,
+        )
+        .unwrap();
+
+        fixture.apply(&mut workflow);
+
+        let agent = &workflow.agents[0];
+        assert_eq!(agent.model, Some(ModelId::new("project-model")));
+        assert_eq!(
+            agent.project_rules,
+            "Be concise\nRun cargo fmt before committing"
+        );
+        assert_eq!(
+            workflow.variables,
+            Some(HashMap::from([
+                ("user_name".to_string(), json!("Project")),
+                ("mode".to_string(), json!("ACT")),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_apply_verify_commands() {
+        let mut workflow = workflow("agents:\n  - id: developer\n");
+        let fixture = ProjectConfig::parse(
+            r#"
+[verify]
+max_cycles = 2
+
+[[verify.commands]]
+command = "cargo check"
+
+[[verify.commands]]
+command = "cargo test -q"
+timeout = 600
+"#,
+        )
+        .unwrap();
+
+        fixture.apply(&mut workflow);
+
+        let expected = VerifyPolicy {
+            commands: vec![
+                VerifyCommand::new("cargo check"),
+                VerifyCommand { command: "cargo test -q".to_string(), timeout: Some(600) },
+            ],
+            max_cycles: Some(2),
+        };
+        assert_eq!(workflow.verify, Some(expected));
+    }
+
+    #[test]
+    fn test_apply_empty_config_keeps_workflow() {
+        let mut workflow = workflow("agents:\n  - id: developer\n    model: global-model\n");
+
+        ProjectConfig::default().apply(&mut workflow);
+
+        let agent = &workflow.agents[0];
+        assert_eq!(agent.model, Some(ModelId::new("global-model")));
+        assert_eq!(agent.project_rules, "");
+        assert_eq!(workflow.variables, None);
+    }
+}
@@ -2,12 +2,18 @@ use std::sync::Arc;
 
 use forge_domain::App;
 
+use crate::approval::ForgeApprovalService;
 use crate::attachment::ForgeChatRequest;
+use crate::cipher::StoreKey;
 use crate::conversation::ForgeConversationService;
+use crate::interaction::ForgeInteractionService;
+use crate::journal::ForgeJournalService;
+use crate::protection::ForgeProtectionService;
 use crate::provider::ForgeProviderService;
+use crate::scratch::ForgeScratchService;
 use crate::template::ForgeTemplateService;
 use crate::tool_service::ForgeToolService;
-use crate::Infrastructure;
+use crate::{EnvironmentService, Infrastructure};
 
 /// ForgeApp is the main application container that implements the App trait.
 /// It provides access to all core services required by the application.
@@ -22,19 +28,43 @@ pub struct ForgeApp<F> {
     conversation_service: ForgeConversationService,
     prompt_service: ForgeTemplateService<F, ForgeToolService>,
     attachment_service: ForgeChatRequest<F>,
+    journal_service: Arc<ForgeJournalService>,
+    approval_service: ForgeApprovalService,
+    interaction_service: ForgeInteractionService,
+    scratch_service: Arc<ForgeScratchService>,
+    protection_service: Arc<ForgeProtectionService>,
 }
 
 impl<F: Infrastructure> ForgeApp<F> {
-    pub fn new(infra: Arc<F>) -> Self {
-        let tool_service = Arc::new(ForgeToolService::new(infra.clone()));
-        Self {
+    /// Fails when the environment's network settings are invalid
+    pub fn new(infra: Arc<F>) -> anyhow::Result<Self> {
+        let env = infra.environment_service().get_environment();
+        let protection_service = Arc::new(ForgeProtectionService::load(&env));
+        let tool_service = Arc::new(ForgeToolService::new(
+            infra.clone(),
+            protection_service.clone(),
+        ));
+        let scratch_service = Arc::new(ForgeScratchService::new(env.scratch_path()));
+        let journal_service = Arc::new(ForgeJournalService::new(
+            env.recovery_path(),
+            env.imports_path(),
+            StoreKey::new(env.store_key_path()),
+        ));
+        Ok(Self {
             infra: infra.clone(),
-            provider_service: ForgeProviderService::new(infra.clone()),
-            conversation_service: ForgeConversationService::new(),
+            provider_service: ForgeProviderService::new(infra.clone())?,
+            conversation_service: ForgeConversationService::new()
+                .scratch(scratch_service.clone())
+                .imports(journal_service.clone()),
             prompt_service: ForgeTemplateService::new(infra.clone(), tool_service.clone()),
             tool_service,
             attachment_service: ForgeChatRequest::new(infra),
-        }
+            journal_service,
+            approval_service: ForgeApprovalService::default(),
+            interaction_service: ForgeInteractionService::default(),
+            scratch_service,
+            protection_service,
+        })
     }
 }
 
@@ -44,6 +74,11 @@ impl<F: Infrastructure> App for ForgeApp<F> {
     type ConversationService = ForgeConversationService;
     type TemplateService = ForgeTemplateService<F, ForgeToolService>;
     type AttachmentService = ForgeChatRequest<F>;
+    type JournalService = ForgeJournalService;
+    type ApprovalService = ForgeApprovalService;
+    type InteractionService = ForgeInteractionService;
+    type ScratchService = ForgeScratchService;
+    type ProtectionService = ForgeProtectionService;
 
     fn tool_service(&self) -> &Self::ToolService {
         &self.tool_service
@@ -64,6 +99,26 @@ impl<F: Infrastructure> App for ForgeApp<F> {
     fn attachment_service(&self) -> &Self::AttachmentService {
         &self.attachment_service
     }
+
+    fn journal_service(&self) -> &Self::JournalService {
+        &self.journal_service
+    }
+
+    fn approval_service(&self) -> &Self::ApprovalService {
+        &self.approval_service
+    }
+
+    fn interaction_service(&self) -> &Self::InteractionService {
+        &self.interaction_service
+    }
+
+    fn scratch_service(&self) -> &Self::ScratchService {
+        &self.scratch_service
+    }
+
+    fn protection_service(&self) -> &Self::ProtectionService {
+        &self.protection_service
+    }
 }
 
 impl<F: Infrastructure> Infrastructure for ForgeApp<F> {
@@ -71,10 +126,13 @@ impl<F: Infrastructure> Infrastructure for ForgeApp<F> {
     type FsReadService = F::FsReadService;
     type FsWriteService = F::FsWriteService;
     type VectorIndex = F::VectorIndex;
+    type DocumentIndex = F::DocumentIndex;
     type EmbeddingService = F::EmbeddingService;
     type FsMetaService = F::FsMetaService;
     type FsSnapshotService = F::FsSnapshotService;
     type FsRemoveService = F::FsRemoveService;
+    type FsMoveService = F::FsMoveService;
+    type FsCopyService = F::FsCopyService;
     type FsCreateDirsService = F::FsCreateDirsService;
 
     fn environment_service(&self) -> &Self::EnvironmentService {
@@ -93,6 +151,10 @@ impl<F: Infrastructure> Infrastructure for ForgeApp<F> {
         self.infra.vector_index()
     }
 
+    fn document_index(&self) -> &Self::DocumentIndex {
+        self.infra.document_index()
+    }
+
     fn embedding_service(&self) -> &Self::EmbeddingService {
         self.infra.embedding_service()
     }
@@ -109,6 +171,14 @@ impl<F: Infrastructure> Infrastructure for ForgeApp<F> {
         self.infra.file_remove_service()
     }
 
+    fn file_move_service(&self) -> &Self::FsMoveService {
+        self.infra.file_move_service()
+    }
+
+    fn file_copy_service(&self) -> &Self::FsCopyService {
+        self.infra.file_copy_service()
+    }
+
     fn create_dirs_service(&self) -> &Self::FsCreateDirsService {
         self.infra.create_dirs_service()
     }
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use forge_domain::{ApprovalService, ToolCallId};
+use tokio::sync::{oneshot, Mutex};
+
+/// Keeps the tool calls that are waiting for the user's approval in memory
+#[derive(Default)]
+pub struct ForgeApprovalService {
+    pending: Mutex<HashMap<ToolCallId, oneshot::Sender<bool>>>,
+}
+
+#[async_trait::async_trait]
+impl ApprovalService for ForgeApprovalService {
+    async fn register(&self, call_id: &ToolCallId) -> oneshot::Receiver<bool> {
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().await.insert(call_id.clone(), tx);
+        rx
+    }
+
+    async fn respond(&self, call_id: &ToolCallId, approved: bool) -> Result<()> {
+        let sender =
+            self.pending.lock().await.remove(call_id).ok_or_else(|| {
+                anyhow!("No tool call is waiting for approval: {}", call_id.as_str())
+            })?;
+
+        // The receiver is gone if the turn was cancelled in the meantime
+        let _ = sender.send(approved);
+        Ok(())
+    }
+
+    async fn cancel(&self, call_id: &ToolCallId) {
+        self.pending.lock().await.remove(call_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_respond_resolves_pending_call() {
+        let service = ForgeApprovalService::default();
+        let call_id = ToolCallId::new("call_1");
+
+        let decision = service.register(&call_id).await;
+        service.respond(&call_id, true).await.unwrap();
+
+        assert!(decision.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_respond_after_cancel() {
+        let service = ForgeApprovalService::default();
+        let call_id = ToolCallId::new("call_1");
+
+        let _decision = service.register(&call_id).await;
+        service.cancel(&call_id).await;
+
+        assert!(service.respond(&call_id, true).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_respond_without_pending_call() {
+        let service = ForgeApprovalService::default();
+        let actual = service.respond(&ToolCallId::new("unknown"), true).await;
+        assert!(actual.is_err());
+    }
+}
@@ -3,11 +3,14 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use base64::Engine;
-use forge_domain::{Attachment, AttachmentService, ContentType};
+use forge_domain::{Attachment, AttachmentService, ContentType, ReadProgress};
 
 use crate::{FsReadService, Infrastructure};
 // TODO: bring pdf support, pdf is just a collection of images.
 
+/// Files at least this large report their progress while they are read
+pub const READ_PROGRESS_THRESHOLD: u64 = 1024 * 1024;
+
 pub struct ForgeChatRequest<F> {
     infra: Arc<F>,
 }
@@ -17,12 +20,16 @@ impl<F: Infrastructure> ForgeChatRequest<F> {
         Self { infra }
     }
 
-    async fn prepare_attachments<T: AsRef<Path>>(&self, paths: HashSet<T>) -> Vec<Attachment> {
+    async fn prepare_attachments<T: AsRef<Path>>(
+        &self,
+        paths: HashSet<T>,
+        progress: &(dyn Fn(ReadProgress) + Send + Sync),
+    ) -> Vec<Attachment> {
         futures::future::join_all(
             paths
                 .into_iter()
                 .map(|v| v.as_ref().to_path_buf())
-                .map(|v| self.populate_attachments(v)),
+                .map(|v| self.populate_attachments(v, progress)),
         )
         .await
         .into_iter()
@@ -30,10 +37,23 @@ impl<F: Infrastructure> ForgeChatRequest<F> {
         .collect::<Vec<_>>()
     }
 
-    async fn populate_attachments(&self, path: PathBuf) -> anyhow::Result<Attachment> {
+    async fn populate_attachments(
+        &self,
+        path: PathBuf,
+        progress: &(dyn Fn(ReadProgress) + Send + Sync),
+    ) -> anyhow::Result<Attachment> {
         let extension = path.extension().map(|v| v.to_string_lossy().to_string());
-        let read = self.infra.file_read_service().read(path.as_path()).await?;
-        let path = path.to_string_lossy().to_string();
+        let name = path.to_string_lossy().to_string();
+        let read = self
+            .infra
+            .file_read_service()
+            .read_with_progress(path.as_path(), &|read, total| {
+                if total >= READ_PROGRESS_THRESHOLD {
+                    progress(ReadProgress { path: name.clone(), read, total });
+                }
+            })
+            .await?;
+        let path = name;
         if let Some(img_extension) = extension.and_then(|ext| match ext.as_str() {
             "jpeg" | "jpg" => Some("jpeg"),
             "png" => Some("png"),
@@ -52,8 +72,14 @@ impl<F: Infrastructure> ForgeChatRequest<F> {
 
 #[async_trait::async_trait]
 impl<F: Infrastructure> AttachmentService for ForgeChatRequest<F> {
-    async fn attachments(&self, url: &str) -> anyhow::Result<Vec<Attachment>> {
-        let attachments = self.prepare_attachments(Attachment::parse_all(url)).await;
+    async fn attachments(
+        &self,
+        url: &str,
+        progress: &(dyn Fn(ReadProgress) + Send + Sync),
+    ) -> anyhow::Result<Vec<Attachment>> {
+        let attachments = self
+            .prepare_attachments(Attachment::parse_all(url), progress)
+            .await;
         Ok(attachments)
     }
 }
@@ -68,15 +94,16 @@ pub mod tests {
     use base64::Engine;
     use bytes::Bytes;
     use forge_domain::{
-        AttachmentService, ContentType, Environment, Point, Provider, Query, Suggestion,
+        AttachmentService, ContentType, DocumentChunk, Environment, Point, PointId, Provider,
+        Query, ReadProgress, Suggestion,
     };
-    use forge_snaps::{SnapshotInfo, SnapshotMetadata};
+    use forge_snaps::{SnapshotInfo, SnapshotMetadata, SnapshotSelector};
 
-    use crate::attachment::ForgeChatRequest;
+    use crate::attachment::{ForgeChatRequest, READ_PROGRESS_THRESHOLD};
     use crate::{
-        EmbeddingService, EnvironmentService, FileRemoveService, FsCreateDirsService,
-        FsMetaService, FsReadService, FsSnapshotService, FsWriteService, Infrastructure,
-        VectorIndex,
+        EmbeddingService, EnvironmentService, FileRemoveService, FsCopyService,
+        FsCreateDirsService, FsMetaService, FsMoveService, FsReadService, FsSnapshotService,
+        FsWriteService, Infrastructure, VectorIndex,
     };
     #[derive(Debug)]
     pub struct MockEnvironmentService {}
@@ -95,6 +122,19 @@ pub mod tests {
                 base_path: PathBuf::from("/base"),
                 openai_key: None,
                 provider: Provider::open_router("test-key"),
+                workspace_roots: Vec::new(),
+                restricted: false,
+                read_only: false,
+                first_token_timeout: Default::default(),
+                question_timeout: Default::default(),
+                line_ending: None,
+                proxy: Default::default(),
+                tls: Default::default(),
+                http_timeout: Default::default(),
+                provider_preferences: None,
+                prompt_cache: false,
+                transformers: None,
+                scan_workers: None,
             }
         }
     }
@@ -154,7 +194,47 @@ pub mod tests {
         async fn search(&self, _query: Query) -> anyhow::Result<Vec<Point<Suggestion>>> {
             Ok(vec![])
         }
+
+        async fn delete(&self, _ids: Vec<PointId>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn clear(&self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+    #[derive(Debug, Default)]
+    pub struct MockDocumentIndex {
+        pub points: Mutex<Vec<Point<DocumentChunk>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl VectorIndex<DocumentChunk> for MockDocumentIndex {
+        async fn store(&self, point: Point<DocumentChunk>) -> anyhow::Result<()> {
+            let mut points = self.points.lock().unwrap();
+            points.retain(|p| p.id != point.id);
+            points.push(point);
+            Ok(())
+        }
+
+        async fn search(&self, _query: Query) -> anyhow::Result<Vec<Point<DocumentChunk>>> {
+            Ok(vec![])
+        }
+
+        async fn delete(&self, ids: Vec<PointId>) -> anyhow::Result<()> {
+            self.points
+                .lock()
+                .unwrap()
+                .retain(|point| !ids.contains(&point.id));
+            Ok(())
+        }
+
+        async fn clear(&self) -> anyhow::Result<()> {
+            self.points.lock().unwrap().clear();
+            Ok(())
+        }
     }
+
     #[derive(Debug)]
     pub struct MockEmbeddingService {}
 
@@ -170,6 +250,7 @@ pub mod tests {
         env_service: MockEnvironmentService,
         file_service: MockFileService,
         vector_index: MockVectorIndex,
+        pub document_index: MockDocumentIndex,
         embedding_service: MockEmbeddingService,
         file_snapshot_service: MockSnapService,
     }
@@ -180,6 +261,7 @@ pub mod tests {
                 env_service: MockEnvironmentService {},
                 file_service: MockFileService::new(),
                 vector_index: MockVectorIndex {},
+                document_index: Default::default(),
                 embedding_service: MockEmbeddingService {},
                 file_snapshot_service: MockSnapService,
             }
@@ -200,6 +282,68 @@ pub mod tests {
             self.files.lock().unwrap().retain(|(p, _)| p != path);
             Ok(())
         }
+
+        async fn remove_dir(&self, path: &Path, recursive: bool) -> anyhow::Result<()> {
+            if !self.exists(path).await? {
+                return Err(anyhow::anyhow!("Directory not found: {:?}", path));
+            }
+            let mut files = self.files.lock().unwrap();
+            let has_children = files.iter().any(|(p, _)| p != path && p.starts_with(path));
+            if has_children && !recursive {
+                return Err(anyhow::anyhow!(
+                    "Directory is not empty: {}, set recursive to true to remove it along with its contents",
+                    path.display()
+                ));
+            }
+            files.retain(|(p, _)| !p.starts_with(path));
+            Ok(())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl FsMoveService for MockFileService {
+        async fn rename(&self, from: &Path, to: &Path) -> anyhow::Result<()> {
+            if !self.exists(from).await? {
+                return Err(anyhow::anyhow!("File not found: {:?}", from));
+            }
+            for (path, _) in self.files.lock().unwrap().iter_mut() {
+                if path == from {
+                    *path = to.to_path_buf();
+                } else if let Ok(relative) = path.strip_prefix(from) {
+                    *path = to.join(relative);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl FsCopyService for MockFileService {
+        async fn copy(&self, from: &Path, to: &Path, recursive: bool) -> anyhow::Result<()> {
+            if !self.exists(from).await? {
+                return Err(anyhow::anyhow!("File not found: {:?}", from));
+            }
+            let copies = self
+                .files
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(path, _)| path == from || (recursive && path.starts_with(from)))
+                .map(|(path, content)| {
+                    let relative = path.strip_prefix(from).unwrap();
+                    let path = if relative.as_os_str().is_empty() {
+                        to.to_path_buf()
+                    } else {
+                        to.join(relative)
+                    };
+                    (path, content.clone())
+                })
+                .collect::<Vec<_>>();
+            for (path, content) in copies {
+                self.write(&path, content).await?;
+            }
+            Ok(())
+        }
     }
 
     #[async_trait::async_trait]
@@ -257,6 +401,14 @@ pub mod tests {
             unimplemented!()
         }
 
+        async fn restore_snapshot(&self, _: &Path, _: &SnapshotSelector) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn read_snapshot(&self, _: &Path, _: &SnapshotSelector) -> anyhow::Result<Vec<u8>> {
+            unimplemented!()
+        }
+
         async fn get_snapshot_by_timestamp(
             &self,
             _: &Path,
@@ -276,6 +428,10 @@ pub mod tests {
         async fn purge_older_than(&self, _: u32) -> anyhow::Result<usize> {
             unimplemented!()
         }
+
+        async fn rename_snapshots(&self, _: &Path, _: &Path) -> anyhow::Result<usize> {
+            unimplemented!()
+        }
     }
 
     #[async_trait::async_trait]
@@ -300,7 +456,10 @@ pub mod tests {
         type FsReadService = MockFileService;
         type FsWriteService = MockFileService;
         type FsRemoveService = MockFileService;
+        type FsMoveService = MockFileService;
+        type FsCopyService = MockFileService;
         type VectorIndex = MockVectorIndex;
+        type DocumentIndex = MockDocumentIndex;
         type EmbeddingService = MockEmbeddingService;
         type FsMetaService = MockFileService;
         type FsCreateDirsService = MockFileService;
@@ -322,6 +481,10 @@ pub mod tests {
             &self.vector_index
         }
 
+        fn document_index(&self) -> &Self::DocumentIndex {
+            &self.document_index
+        }
+
         fn embedding_service(&self) -> &Self::EmbeddingService {
             &self.embedding_service
         }
@@ -338,6 +501,14 @@ pub mod tests {
             &self.file_service
         }
 
+        fn file_move_service(&self) -> &Self::FsMoveService {
+            &self.file_service
+        }
+
+        fn file_copy_service(&self) -> &Self::FsCopyService {
+            &self.file_service
+        }
+
         fn create_dirs_service(&self) -> &Self::FsCreateDirsService {
             &self.file_service
         }
@@ -353,7 +524,7 @@ pub mod tests {
         let url = "@/test/file1.txt".to_string();
 
         // Execute
-        let attachments = chat_request.attachments(&url).await.unwrap();
+        let attachments = chat_request.attachments(&url, &|_| {}).await.unwrap();
 
         // Assert
         // Text files should be included in the attachments
@@ -374,7 +545,7 @@ pub mod tests {
         let url = "@/test/image.png".to_string();
 
         // Execute
-        let attachments = chat_request.attachments(&url).await.unwrap();
+        let attachments = chat_request.attachments(&url, &|_| {}).await.unwrap();
 
         // Assert
         assert_eq!(attachments.len(), 1);
@@ -402,7 +573,7 @@ pub mod tests {
         let url = "@\"/test/image with spaces.jpg\"".to_string();
 
         // Execute
-        let attachments = chat_request.attachments(&url).await.unwrap();
+        let attachments = chat_request.attachments(&url, &|_| {}).await.unwrap();
 
         // Assert
         assert_eq!(attachments.len(), 1);
@@ -435,7 +606,7 @@ pub mod tests {
         let url = "@/test/file1.txt @/test/file2.txt @/test/image.png".to_string();
 
         // Execute
-        let attachments = chat_request.attachments(&url).await.unwrap();
+        let attachments = chat_request.attachments(&url, &|_| {}).await.unwrap();
 
         // Assert
         // All files should be included in the attachments
@@ -467,7 +638,7 @@ pub mod tests {
         let url = "@/test/nonexistent.txt".to_string();
 
         // Execute
-        let attachments = chat_request.attachments(&url).await.unwrap();
+        let attachments = chat_request.attachments(&url, &|_| {}).await.unwrap();
 
         // Assert - nonexistent files should be ignored
         assert_eq!(attachments.len(), 0);
@@ -483,7 +654,7 @@ pub mod tests {
         let url = "".to_string();
 
         // Execute
-        let attachments = chat_request.attachments(&url).await.unwrap();
+        let attachments = chat_request.attachments(&url, &|_| {}).await.unwrap();
 
         // Assert - no attachments
         assert_eq!(attachments.len(), 0);
@@ -506,7 +677,7 @@ pub mod tests {
         let url = "@/test/unknown.xyz".to_string();
 
         // Execute
-        let attachments = chat_request.attachments(&url).await.unwrap();
+        let attachments = chat_request.attachments(&url, &|_| {}).await.unwrap();
 
         // Assert - should be treated as text
         assert_eq!(attachments.len(), 1);
@@ -515,4 +686,40 @@ pub mod tests {
         assert_eq!(attachment.content_type, ContentType::Text);
         assert_eq!(attachment.content, "Some content");
     }
+
+    async fn read_progress(infra: Arc<MockInfrastructure>, url: &str) -> Vec<ReadProgress> {
+        let events = Mutex::new(Vec::new());
+        ForgeChatRequest::new(infra)
+            .attachments(url, &|progress| events.lock().unwrap().push(progress))
+            .await
+            .unwrap();
+        events.into_inner().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_large_file_reports_progress() {
+        let infra = Arc::new(MockInfrastructure::new());
+        let size = READ_PROGRESS_THRESHOLD as usize * 2;
+        infra
+            .file_service
+            .add_file(PathBuf::from("/test/large.txt"), "a".repeat(size));
+
+        let actual = read_progress(infra, "@/test/large.txt").await;
+
+        let expected = vec![ReadProgress {
+            path: "/test/large.txt".to_string(),
+            read: size as u64,
+            total: size as u64,
+        }];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_small_file_reports_no_progress() {
+        let infra = Arc::new(MockInfrastructure::new());
+
+        let actual = read_progress(infra, "@/test/file1.txt").await;
+
+        assert_eq!(actual, vec![]);
+    }
 }
@@ -0,0 +1,315 @@
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+/// Version of the encrypted format, written as the first byte of every sealed
+/// record so the format can change without breaking existing stores
+const FORMAT_VERSION: u8 = 1;
+
+/// PBKDF2 rounds of the keys created from now on
+const ITERATIONS: u32 = 600_000;
+
+const SALT_LEN: usize = 16;
+
+/// Sealed into the key file so a wrong passphrase is noticed before any record
+/// is read
+const KEY_CHECK: &[u8] = b"forge conversation store";
+
+/// Encrypts the records of the conversation store with AES-256-GCM. Every
+/// record is sealed with its own random nonce.
+pub struct StoreCipher {
+    key: LessSafeKey,
+    rng: SystemRandom,
+}
+
+impl StoreCipher {
+    fn derive(passphrase: &str, salt: &[u8], iterations: u32) -> Result<Self> {
+        let iterations = NonZeroU32::new(iterations).context("Store key has no iterations")?;
+        let mut key = [0u8; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            salt,
+            passphrase.as_bytes(),
+            &mut key,
+        );
+        Self::from_key(&key)
+    }
+
+    /// Uses a key that was generated rather than derived from a passphrase
+    pub fn from_key(key: &[u8]) -> Result<Self> {
+        let key = UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow!("Invalid store key"))?;
+        Ok(Self { key: LessSafeKey::new(key), rng: SystemRandom::new() })
+    }
+
+    /// Encrypts the record, the result holds the format version, the nonce
+    /// and the ciphertext
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("Failed to generate a nonce"))?;
+
+        let mut data = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from([FORMAT_VERSION]),
+                &mut data,
+            )
+            .map_err(|_| anyhow!("Failed to encrypt the record"))?;
+
+        let mut sealed = Vec::with_capacity(1 + NONCE_LEN + data.len());
+        sealed.push(FORMAT_VERSION);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend(data);
+        Ok(sealed)
+    }
+
+    /// Decrypts a record sealed by [`StoreCipher::seal`]
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let (&version, rest) = sealed.split_first().context("Encrypted record is empty")?;
+        if version != FORMAT_VERSION {
+            bail!("Unsupported encrypted record version {version}");
+        }
+        if rest.len() < NONCE_LEN {
+            bail!("Encrypted record is truncated");
+        }
+
+        let (nonce, data) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| anyhow!("Encrypted record has an invalid nonce"))?;
+        let mut data = data.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::from([version]), &mut data)
+            .map_err(|_| anyhow!("Failed to decrypt the record, it is corrupt"))?;
+        Ok(plaintext.to_vec())
+    }
+
+    /// Seals a line of text, the result is base64 so it stays on one line
+    pub fn seal_line(&self, line: &str) -> Result<String> {
+        Ok(STANDARD.encode(self.seal(line.as_bytes())?))
+    }
+
+    /// Opens a line sealed by [`StoreCipher::seal_line`]
+    pub fn open_line(&self, line: &str) -> Result<String> {
+        let sealed = STANDARD
+            .decode(line.trim())
+            .context("Encrypted record isn't base64")?;
+        Ok(String::from_utf8(self.open(&sealed)?)?)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    version: u8,
+    salt: String,
+    iterations: u32,
+    check: String,
+}
+
+/// Key derived by [`StoreKey::prepare`] that isn't written yet
+pub struct NewStoreKey {
+    cipher: StoreCipher,
+    file: KeyFile,
+}
+
+impl NewStoreKey {
+    pub fn cipher(&self) -> &StoreCipher {
+        &self.cipher
+    }
+}
+
+/// Key file of an encrypted conversation store. The store is encrypted as
+/// long as the file exists, it holds what's needed to derive the key from the
+/// passphrase but never the key itself.
+pub struct StoreKey {
+    path: PathBuf,
+    iterations: u32,
+}
+
+impl StoreKey {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, iterations: ITERATIONS }
+    }
+
+    /// Sets the PBKDF2 rounds of the keys created from now on
+    pub fn iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    /// Creates the key of a newly encrypted store
+    pub fn create(&self, passphrase: &str) -> Result<StoreCipher> {
+        self.save(self.prepare(passphrase)?)
+    }
+
+    /// Derives the key of a store about to be encrypted without writing it,
+    /// so the store only counts as encrypted once its files are
+    pub fn prepare(&self, passphrase: &str) -> Result<NewStoreKey> {
+        if passphrase.is_empty() {
+            bail!("The passphrase can't be empty");
+        }
+        if self.exists() {
+            bail!("The conversation store is already encrypted");
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| anyhow!("Failed to generate a salt"))?;
+        let cipher = StoreCipher::derive(passphrase, &salt, self.iterations)?;
+        let file = KeyFile {
+            version: FORMAT_VERSION,
+            salt: STANDARD.encode(salt),
+            iterations: self.iterations,
+            check: STANDARD.encode(cipher.seal(KEY_CHECK)?),
+        };
+        Ok(NewStoreKey { cipher, file })
+    }
+
+    /// Writes a key made by [`StoreKey::prepare`], from then on the store is
+    /// encrypted
+    pub fn save(&self, key: NewStoreKey) -> Result<StoreCipher> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&key.file)?)
+            .with_context(|| format!("Failed to write store key {}", self.path.display()))?;
+        Ok(key.cipher)
+    }
+
+    /// Derives the key of the store from the passphrase, fails when the
+    /// passphrase isn't the one the store was encrypted with
+    pub fn unlock(&self, passphrase: &str) -> Result<StoreCipher> {
+        if !self.exists() {
+            bail!("The conversation store isn't encrypted");
+        }
+
+        let content = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read store key {}", self.path.display()))?;
+        let file: KeyFile = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse store key {}", self.path.display()))?;
+        if file.version != FORMAT_VERSION {
+            bail!("Unsupported store key version {}", file.version);
+        }
+
+        let salt = STANDARD
+            .decode(&file.salt)
+            .context("Store key has an invalid salt")?;
+        let check = STANDARD
+            .decode(&file.check)
+            .context("Store key has an invalid check")?;
+        let cipher = StoreCipher::derive(passphrase, &salt, file.iterations)?;
+        match cipher.open(&check) {
+            Ok(check) if check == KEY_CHECK => Ok(cipher),
+            _ => bail!("Wrong passphrase for the encrypted conversation store"),
+        }
+    }
+
+    /// Removes the key once the store was decrypted
+    pub fn remove(&self) -> Result<()> {
+        std::fs::remove_file(&self.path)
+            .with_context(|| format!("Failed to remove store key {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn fixture(dir: &tempfile::TempDir) -> StoreKey {
+        StoreKey::new(dir.path().join("store.key")).iterations(1_000)
+    }
+
+    #[test]
+    fn test_seal_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let cipher = fixture(&dir).create("hunter2").unwrap();
+
+        let sealed = cipher.seal_line(r#"{"type":"begin"}"#).unwrap();
+        let actual = cipher.open_line(&sealed).unwrap();
+
+        assert_eq!(actual, r#"{"type":"begin"}"#);
+        assert!(!sealed.contains("begin"));
+    }
+
+    #[test]
+    fn test_every_record_has_its_own_nonce() {
+        let dir = tempfile::tempdir().unwrap();
+        let cipher = fixture(&dir).create("hunter2").unwrap();
+
+        let actual = [cipher.seal(b"same").unwrap(), cipher.seal(b"same").unwrap()];
+
+        assert_ne!(actual[0], actual[1]);
+        assert_eq!(actual[0][0], FORMAT_VERSION);
+    }
+
+    #[test]
+    fn test_tampered_record_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let cipher = fixture(&dir).create("hunter2").unwrap();
+        let mut sealed = cipher.seal(b"secret source").unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+
+        let actual = cipher.open(&sealed);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn test_unlock_with_the_same_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let sealed = fixture(&dir)
+            .create("hunter2")
+            .unwrap()
+            .seal(b"hello")
+            .unwrap();
+
+        let actual = fixture(&dir)
+            .unlock("hunter2")
+            .unwrap()
+            .open(&sealed)
+            .unwrap();
+
+        assert_eq!(actual, b"hello");
+    }
+
+    #[test]
+    fn test_unlock_with_wrong_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        fixture(&dir).create("hunter2").unwrap();
+
+        let actual = fixture(&dir).unlock("hunter3").err().unwrap().to_string();
+
+        assert_eq!(
+            actual,
+            "Wrong passphrase for the encrypted conversation store"
+        );
+    }
+
+    #[test]
+    fn test_create_twice_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        fixture(&dir).create("hunter2").unwrap();
+
+        let actual = fixture(&dir).create("hunter2");
+
+        assert!(actual.is_err());
+    }
+}
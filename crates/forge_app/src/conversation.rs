@@ -2,14 +2,23 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
+use chrono::Utc;
 use forge_domain::{
-    AgentId, Context, Conversation, ConversationId, ConversationService, Event, Workflow,
+    AgentId, Context, Conversation, ConversationId, ConversationService, ConversationSuggestions,
+    Event, JournalService, ScratchService, TurnTiming, Workflow,
 };
 use serde_json::Value;
 use tokio::sync::Mutex;
 
+use crate::journal::ForgeJournalService;
+use crate::scratch::ForgeScratchService;
+
 pub struct ForgeConversationService {
     workflows: Arc<Mutex<HashMap<ConversationId, Conversation>>>,
+    /// Scratch directories that are removed along with their conversation
+    scratch: Option<Arc<ForgeScratchService>>,
+    /// Store of the imported conversations, listed along with the others
+    imports: Option<Arc<ForgeJournalService>>,
 }
 
 impl Default for ForgeConversationService {
@@ -20,7 +29,41 @@ impl Default for ForgeConversationService {
 
 impl ForgeConversationService {
     pub fn new() -> Self {
-        Self { workflows: Arc::new(Mutex::new(HashMap::new())) }
+        Self {
+            workflows: Arc::new(Mutex::new(HashMap::new())),
+            scratch: None,
+            imports: None,
+        }
+    }
+
+    pub fn scratch(mut self, scratch: Arc<ForgeScratchService>) -> Self {
+        self.scratch = Some(scratch);
+        self
+    }
+
+    pub fn imports(mut self, imports: Arc<ForgeJournalService>) -> Self {
+        self.imports = Some(imports);
+        self
+    }
+
+    /// The conversations of this session followed by the imported ones that
+    /// aren't loaded already
+    async fn all(&self) -> Result<Vec<Conversation>> {
+        let mut conversations = self
+            .workflows
+            .lock()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        if let Some(imports) = &self.imports {
+            for imported in imports.imports().await? {
+                if !conversations.iter().any(|c| c.id == imported.id) {
+                    conversations.push(imported);
+                }
+            }
+        }
+        Ok(conversations)
     }
 
     // Helper method for operations requiring mutable access to a conversation
@@ -48,7 +91,13 @@ impl ForgeConversationService {
 #[async_trait::async_trait]
 impl ConversationService for ForgeConversationService {
     async fn get(&self, id: &ConversationId) -> Result<Option<Conversation>> {
-        Ok(self.workflows.lock().await.get(id).cloned())
+        if let Some(conversation) = self.workflows.lock().await.get(id) {
+            return Ok(Some(conversation.clone()));
+        }
+        match &self.imports {
+            Some(imports) => Ok(imports.imports().await?.into_iter().find(|c| &c.id == id)),
+            None => Ok(None),
+        }
     }
 
     async fn create(&self, workflow: Workflow) -> Result<ConversationId> {
@@ -80,6 +129,7 @@ impl ConversationService for ForgeConversationService {
     async fn insert_event(&self, id: &ConversationId, event: Event) -> Result<()> {
         self.write(id, |c| {
             c.events.push(event);
+            c.updated_at = Utc::now();
         })
         .await
     }
@@ -98,4 +148,196 @@ impl ConversationService for ForgeConversationService {
     async fn delete_variable(&self, id: &ConversationId, key: &str) -> Result<bool> {
         self.write(id, |c| c.delete_variable(key)).await
     }
+
+    async fn set_suggestions(
+        &self,
+        id: &ConversationId,
+        suggestions: ConversationSuggestions,
+    ) -> Result<()> {
+        self.write(id, |c| {
+            c.suggestions = Some(suggestions);
+        })
+        .await
+    }
+
+    async fn add_tag(&self, id: &ConversationId, tag: &str) -> Result<bool> {
+        self.write(id, |c| c.add_tag(tag)).await
+    }
+
+    async fn remove_tag(&self, id: &ConversationId, tag: &str) -> Result<bool> {
+        self.write(id, |c| c.remove_tag(tag)).await
+    }
+
+    async fn list_by_tag(&self, tag: &str) -> Result<Vec<Conversation>> {
+        let mut conversations = self.all().await?;
+        conversations.retain(|c| c.has_tag(tag));
+        conversations.sort_by_key(|c| c.id.into_string());
+        Ok(conversations)
+    }
+
+    async fn set_pinned(&self, id: &ConversationId, pinned: bool) -> Result<()> {
+        self.write(id, |c| {
+            c.pinned = pinned;
+        })
+        .await
+    }
+
+    async fn add_turn_timing(&self, id: &ConversationId, timing: TurnTiming) -> Result<()> {
+        self.write(id, |c| c.add_turn_timing(timing)).await
+    }
+
+    async fn list(&self) -> Result<Vec<Conversation>> {
+        let mut conversations = self.all().await?;
+        Conversation::sort(&mut conversations);
+        Ok(conversations)
+    }
+
+    async fn delete(&self, id: &ConversationId) -> Result<bool> {
+        let deleted = self.workflows.lock().await.remove(id).is_some();
+        if let Some(scratch) = &self.scratch {
+            scratch.remove(id).await?;
+        }
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    async fn ids(service: &ForgeConversationService, tag: &str) -> Vec<ConversationId> {
+        service
+            .list_by_tag(tag)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_list_by_tag() {
+        let service = ForgeConversationService::new();
+        let first = service.create(Workflow::default()).await.unwrap();
+        let second = service.create(Workflow::default()).await.unwrap();
+        service.create(Workflow::default()).await.unwrap();
+
+        service.add_tag(&first, "forge").await.unwrap();
+        service.add_tag(&second, " forge ").await.unwrap();
+        service.add_tag(&second, "billing").await.unwrap();
+
+        let mut expected = vec![first.clone(), second.clone()];
+        expected.sort_by_key(|id| id.into_string());
+        assert_eq!(ids(&service, "forge").await, expected);
+        assert_eq!(ids(&service, "billing").await, vec![second]);
+        assert_eq!(ids(&service, "unknown").await, vec![]);
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_tag() {
+        let service = ForgeConversationService::new();
+        let id = service.create(Workflow::default()).await.unwrap();
+
+        assert!(service.add_tag(&id, "forge").await.unwrap());
+        assert!(!service.add_tag(&id, "forge").await.unwrap());
+        assert!(!service.add_tag(&id, "  ").await.unwrap());
+        assert!(service.remove_tag(&id, "forge").await.unwrap());
+        assert!(!service.remove_tag(&id, "forge").await.unwrap());
+
+        let actual = service.get(&id).await.unwrap().unwrap().tags;
+        assert_eq!(actual, Vec::<String>::new());
+        assert!(ids(&service, "forge").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_removes_scratch_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let scratch = Arc::new(ForgeScratchService::new(dir.path().to_path_buf()));
+        let service = ForgeConversationService::new().scratch(scratch.clone());
+        let id = service.create(Workflow::default()).await.unwrap();
+        let path = scratch.create(&id).await.unwrap();
+
+        assert!(service.delete(&id).await.unwrap());
+
+        assert!(!path.exists());
+        assert!(service.get(&id).await.unwrap().is_none());
+        assert!(!service.delete(&id).await.unwrap());
+    }
+
+    async fn listed(service: &ForgeConversationService) -> Vec<ConversationId> {
+        service
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_list_orders_most_recent_first() {
+        let service = ForgeConversationService::new();
+        let older = service.create(Workflow::default()).await.unwrap();
+        let newer = service.create(Workflow::default()).await.unwrap();
+        service
+            .insert_event(&newer, Event::new("user_task_init", "hi"))
+            .await
+            .unwrap();
+
+        assert_eq!(listed(&service).await, vec![newer, older]);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_conversations_are_listed_first() {
+        let service = ForgeConversationService::new();
+        let pinned = service.create(Workflow::default()).await.unwrap();
+        let recent = service.create(Workflow::default()).await.unwrap();
+        service
+            .insert_event(&recent, Event::new("user_task_init", "hi"))
+            .await
+            .unwrap();
+
+        service.set_pinned(&pinned, true).await.unwrap();
+        assert_eq!(listed(&service).await, vec![pinned.clone(), recent.clone()]);
+
+        service.set_pinned(&pinned, false).await.unwrap();
+        assert_eq!(listed(&service).await, vec![recent, pinned]);
+    }
+
+    #[tokio::test]
+    async fn test_imported_conversations_are_listed() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Arc::new(ForgeJournalService::new(
+            dir.path().join("recovery"),
+            dir.path().join("imports"),
+            crate::cipher::StoreKey::new(dir.path().join("store.key")),
+        ));
+        let mut imported = Conversation::new(ConversationId::generate(), Workflow::default());
+        imported.add_tag("imported:aider");
+        journal.save_import("hash", &imported).await.unwrap();
+        let service = ForgeConversationService::new().imports(journal);
+        let id = service.create(Workflow::default()).await.unwrap();
+
+        let mut expected = vec![id, imported.id.clone()];
+        expected.sort_by_key(|id| id.into_string());
+        let mut actual = listed(&service).await;
+        actual.sort_by_key(|id| id.into_string());
+        assert_eq!(actual, expected);
+        assert_eq!(
+            ids(&service, "imported:aider").await,
+            vec![imported.id.clone()]
+        );
+        assert!(service.get(&imported.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tag_unknown_conversation() {
+        let service = ForgeConversationService::new();
+
+        let actual = service.add_tag(&ConversationId::generate(), "forge").await;
+
+        assert!(actual.is_err());
+    }
 }
@@ -0,0 +1,227 @@
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use forge_domain::{
+    Context, ContextMessage, HistoryImporter, ImportFormat, ImportedConversation, ParsedHistory,
+    Role, SkippedEntry,
+};
+
+use super::title;
+
+const HISTORY_FILE: &str = ".aider.chat.history.md";
+
+/// Starts every session of the history, followed by its local start time
+const SESSION_HEADER: &str = "# aider chat started at ";
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Reads the `.aider.chat.history.md` file aider keeps in a repository, each
+/// session of it being a conversation. User messages are the lines starting
+/// with `####`, aider's own output the lines starting with `>` and the rest
+/// is what the model replied.
+///
+/// Aider writes the local time a session started at without its offset, it's
+/// taken as UTC.
+pub struct AiderImporter;
+
+struct Session {
+    started_at: DateTime<Utc>,
+    messages: Vec<(Role, String)>,
+    /// Lines of aider's own output, which have no equivalent in a context
+    output_lines: usize,
+}
+
+impl Session {
+    fn new(started_at: DateTime<Utc>) -> Self {
+        Self { started_at, messages: Vec::new(), output_lines: 0 }
+    }
+
+    fn add(&mut self, line: &str) {
+        if line.starts_with('>') {
+            self.output_lines += 1;
+            return;
+        }
+        let (role, text) = if line == "####" {
+            (Role::User, "")
+        } else if let Some(text) = line.strip_prefix("#### ") {
+            (Role::User, text)
+        } else {
+            (Role::Assistant, line)
+        };
+
+        // A message goes on until the other side speaks, blank lines only
+        // matter within one
+        match self.messages.last_mut() {
+            Some((last, content)) if *last == role => {
+                content.push('\n');
+                content.push_str(text);
+            }
+            _ if text.trim().is_empty() => {}
+            _ => self.messages.push((role, text.to_string())),
+        }
+    }
+
+    /// The conversation of the session, `None` when the user said nothing
+    fn finish(self) -> Option<ImportedConversation> {
+        let title = self
+            .messages
+            .iter()
+            .find(|(role, _)| *role == Role::User)
+            .and_then(|(_, content)| title(content))?;
+
+        let mut context = Context::default();
+        for (role, content) in self.messages {
+            let content = content.trim();
+            context = context.add_message(match role {
+                Role::User => ContextMessage::user(content),
+                _ => ContextMessage::assistant(content, None),
+            });
+        }
+        let mut notes = Vec::new();
+        if self.output_lines > 0 {
+            notes.push(format!(
+                "Left out {} lines of aider's own output, such as notices and command output",
+                self.output_lines
+            ));
+        }
+        Some(ImportedConversation {
+            title,
+            started_at: Some(self.started_at),
+            ended_at: None,
+            context,
+            notes,
+        })
+    }
+}
+
+impl HistoryImporter for AiderImporter {
+    fn format(&self) -> ImportFormat {
+        ImportFormat::Aider
+    }
+
+    fn accepts(&self, path: &Path) -> bool {
+        path.file_name().is_some_and(|name| name == HISTORY_FILE)
+    }
+
+    fn parse(&self, content: &str) -> ParsedHistory {
+        let mut history = ParsedHistory::default();
+        let mut session: Option<Session> = None;
+        // Whether the lines outside a session are being left out, they were
+        // reported already
+        let mut leaving_out = false;
+
+        for (index, line) in content.lines().enumerate() {
+            let number = index + 1;
+            if let Some(stamp) = line.strip_prefix(SESSION_HEADER) {
+                history
+                    .conversations
+                    .extend(session.take().and_then(Session::finish));
+                match NaiveDateTime::parse_from_str(stamp.trim(), TIME_FORMAT) {
+                    Ok(time) => session = Some(Session::new(Utc.from_utc_datetime(&time))),
+                    Err(_) => {
+                        history.skipped.push(SkippedEntry::new(
+                            number,
+                            format!(
+                                "invalid start time '{}', the session is left out",
+                                stamp.trim()
+                            ),
+                        ));
+                        leaving_out = true;
+                    }
+                }
+                continue;
+            }
+
+            match session.as_mut() {
+                Some(session) => session.add(line),
+                None if !leaving_out && !line.trim().is_empty() => {
+                    history.skipped.push(SkippedEntry::new(
+                        number,
+                        "text before the first session header is left out",
+                    ));
+                    leaving_out = true;
+                }
+                None => {}
+            }
+        }
+
+        history
+            .conversations
+            .extend(session.and_then(Session::finish));
+        history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const FIXTURE: &str = include_str!("fixtures/aider.chat.history.md");
+
+    #[test]
+    fn test_session_maps_onto_a_context() {
+        let actual = AiderImporter.parse(FIXTURE);
+
+        let conversation = &actual.conversations[0];
+        let expected = Context::default()
+            .add_message(ContextMessage::user(
+                "Add an argument parser to cli.py\nthat accepts a --name option",
+            ))
+            .add_message(ContextMessage::assistant(
+                "Here are the changes to `cli.py`:\n\n```diff\n+import argparse\n```",
+                None,
+            ))
+            .add_message(ContextMessage::user("Thanks"))
+            .add_message(ContextMessage::assistant("You're welcome!", None));
+        assert_eq!(conversation.context, expected);
+        assert_eq!(conversation.title, "Add an argument parser to cli.py");
+        assert_eq!(
+            conversation.started_at.unwrap().to_rfc3339(),
+            "2024-05-01T10:23:45+00:00"
+        );
+        assert_eq!(
+            conversation.notes,
+            vec![
+                "Left out 5 lines of aider's own output, such as notices and command output"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sessions_without_user_messages_are_left_out() {
+        let actual = AiderImporter.parse(FIXTURE);
+
+        let titles = actual
+            .conversations
+            .iter()
+            .map(|conversation| conversation.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec![
+                "Add an argument parser to cli.py",
+                "Now add a --verbose flag"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_text_before_the_first_session_is_skipped() {
+        let fixture =
+            "Some notes\nmore notes\n# aider chat started at 2024-05-01 10:23:45\n#### Hi\nHello!";
+
+        let actual = AiderImporter.parse(fixture);
+
+        assert_eq!(
+            actual.skipped,
+            vec![SkippedEntry::new(
+                1,
+                "text before the first session header is left out"
+            )]
+        );
+        assert_eq!(actual.conversations.len(), 1);
+    }
+}
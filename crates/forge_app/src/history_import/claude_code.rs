@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use forge_domain::{
+    ContentMessage, Context, ContextMessage, HistoryImporter, ImportFormat, ImportedConversation,
+    ParsedHistory, Role, SkippedEntry, ToolCallFull, ToolCallId, ToolName, ToolResult,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::title;
+
+/// Reads the session files Claude Code keeps in `~/.claude/projects`, a JSON
+/// entry per line, each file being a conversation. Thinking and image blocks
+/// are left out, as are entries other than messages and summaries.
+pub struct ClaudeCodeImporter;
+
+#[derive(Deserialize)]
+struct Entry {
+    #[serde(rename = "type")]
+    kind: String,
+    message: Option<Message>,
+    summary: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    content: MessageContent,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MessageContent {
+    Text(String),
+    Blocks(Vec<Block>),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Block {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        #[serde(default)]
+        input: Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        #[serde(default)]
+        content: Value,
+        #[serde(default)]
+        is_error: bool,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Default)]
+struct Session {
+    summary: Option<String>,
+    started_at: Option<DateTime<Utc>>,
+    ended_at: Option<DateTime<Utc>>,
+    context: Context,
+    /// Names of the tools called so far, by call id
+    calls: HashMap<String, String>,
+    dropped_blocks: usize,
+    dropped_entries: usize,
+}
+
+impl Session {
+    fn add(&mut self, line: usize, entry: Entry) -> Result<(), SkippedEntry> {
+        match entry.kind.as_str() {
+            "summary" => {
+                // The first summary describes the whole session
+                if self.summary.is_none() {
+                    self.summary = entry.summary;
+                }
+                return Ok(());
+            }
+            "user" | "assistant" => {}
+            _ => {
+                self.dropped_entries += 1;
+                return Ok(());
+            }
+        }
+
+        let Some(message) = entry.message else {
+            return Err(SkippedEntry::new(
+                line,
+                format!("'{}' entry without a message", entry.kind),
+            ));
+        };
+        if let Some(timestamp) = entry.timestamp {
+            self.started_at = Some(self.started_at.map_or(timestamp, |t| t.min(timestamp)));
+            self.ended_at = Some(self.ended_at.map_or(timestamp, |t| t.max(timestamp)));
+        }
+        let blocks = match message.content {
+            MessageContent::Text(text) => vec![Block::Text { text }],
+            MessageContent::Blocks(blocks) => blocks,
+        };
+        if entry.kind == "user" {
+            self.add_user(blocks);
+        } else {
+            self.add_assistant(blocks);
+        }
+        Ok(())
+    }
+
+    fn add_user(&mut self, blocks: Vec<Block>) {
+        let mut texts = Vec::new();
+        for block in blocks {
+            match block {
+                Block::Text { text } => texts.push(text),
+                Block::ToolResult { tool_use_id, content, is_error } => {
+                    let name = self
+                        .calls
+                        .get(&tool_use_id)
+                        .map_or_else(|| ToolName::new("unknown"), ToolName::new);
+                    let mut result = ToolResult::new(name)
+                        .call_id(ToolCallId::new(tool_use_id))
+                        .success(result_text(&content));
+                    result.is_error = is_error;
+                    self.push(ContextMessage::tool_result(result));
+                }
+                Block::ToolUse { .. } | Block::Other => self.dropped_blocks += 1,
+            }
+        }
+        if !texts.is_empty() {
+            self.push(ContextMessage::user(texts.join("\n\n")));
+        }
+    }
+
+    fn add_assistant(&mut self, blocks: Vec<Block>) {
+        let mut texts = Vec::new();
+        let mut calls = Vec::new();
+        for block in blocks {
+            match block {
+                Block::Text { text } => texts.push(text),
+                Block::ToolUse { id, name, input } => {
+                    self.calls.insert(id.clone(), name.clone());
+                    calls.push(
+                        ToolCallFull::new(ToolName::new(name))
+                            .call_id(ToolCallId::new(id))
+                            .arguments(input),
+                    );
+                }
+                Block::ToolResult { .. } | Block::Other => self.dropped_blocks += 1,
+            }
+        }
+        if texts.is_empty() && calls.is_empty() {
+            return;
+        }
+
+        // Claude Code writes the blocks of a reply as entries of their own,
+        // they make up a single assistant message
+        if let Some(ContextMessage::ContentMessage(last)) = self.context.messages.last_mut() {
+            if last.role == Role::Assistant {
+                texts.insert(0, std::mem::take(&mut last.content));
+                last.content = join(texts);
+                last.tool_calls.get_or_insert_with(Vec::new).extend(calls);
+                if last.tool_calls.as_ref().is_some_and(Vec::is_empty) {
+                    last.tool_calls = None;
+                }
+                return;
+            }
+        }
+        self.push(ContextMessage::assistant(join(texts), Some(calls)));
+    }
+
+    fn push(&mut self, message: ContextMessage) {
+        self.context.messages.push(message);
+    }
+
+    fn finish(self) -> Option<ImportedConversation> {
+        let first_user_message = self
+            .context
+            .messages
+            .iter()
+            .find_map(|message| match message {
+                ContextMessage::ContentMessage(ContentMessage {
+                    role: Role::User,
+                    content,
+                    ..
+                }) => Some(content.as_str()),
+                _ => None,
+            });
+        let title = self
+            .summary
+            .as_deref()
+            .and_then(title)
+            .or_else(|| first_user_message.and_then(title))?;
+
+        let mut notes = Vec::new();
+        if self.dropped_blocks > 0 {
+            notes.push(format!(
+                "Left out {} thinking, image or misplaced block(s)",
+                self.dropped_blocks
+            ));
+        }
+        if self.dropped_entries > 0 {
+            notes.push(format!(
+                "Left out {} entries other than messages, such as system notices",
+                self.dropped_entries
+            ));
+        }
+        Some(ImportedConversation {
+            title,
+            started_at: self.started_at,
+            ended_at: self.ended_at,
+            context: self.context,
+            notes,
+        })
+    }
+}
+
+fn join(texts: Vec<String>) -> String {
+    texts
+        .into_iter()
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// The text of a tool result, which is either text or a list of blocks
+fn result_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+impl HistoryImporter for ClaudeCodeImporter {
+    fn format(&self) -> ImportFormat {
+        ImportFormat::ClaudeCode
+    }
+
+    fn accepts(&self, path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|extension| extension == "jsonl")
+    }
+
+    fn parse(&self, content: &str) -> ParsedHistory {
+        let mut history = ParsedHistory::default();
+        let mut session = Session::default();
+
+        for (index, line) in content.lines().enumerate() {
+            let number = index + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str::<Value>(line)
+                .map_err(|err| format!("invalid JSON, {err}"))
+                .and_then(|value| {
+                    serde_json::from_value::<Entry>(value)
+                        .map_err(|err| format!("unexpected entry, {err}"))
+                });
+            let result = match entry {
+                Ok(entry) => session.add(number, entry),
+                Err(reason) => Err(SkippedEntry::new(number, reason)),
+            };
+            if let Err(skipped) = result {
+                history.skipped.push(skipped);
+            }
+        }
+
+        history.conversations.extend(session.finish());
+        history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    const FIXTURE: &str = include_str!("fixtures/claude_code_session.jsonl");
+
+    #[test]
+    fn test_session_maps_onto_a_context() {
+        let actual = ClaudeCodeImporter.parse(FIXTURE);
+
+        let conversation = &actual.conversations[0];
+        let bash = ToolCallFull::new(ToolName::new("Bash"))
+            .call_id(ToolCallId::new("toolu_01"))
+            .arguments(json!({"command": "cargo test login"}));
+        let read = ToolCallFull::new(ToolName::new("Read"))
+            .call_id(ToolCallId::new("toolu_02"))
+            .arguments(json!({"file_path": "src/login.rs"}));
+        let mut failed = ToolResult::new(ToolName::new("Bash"))
+            .call_id(ToolCallId::new("toolu_01"))
+            .success("test login ... FAILED");
+        failed.is_error = true;
+        let expected = Context::default()
+            .add_message(ContextMessage::user(
+                "The login test fails, can you fix it?",
+            ))
+            .add_message(ContextMessage::assistant(
+                "Let me run the test.",
+                Some(vec![bash]),
+            ))
+            .add_tool_results(vec![failed])
+            .add_message(ContextMessage::assistant("", Some(vec![read])))
+            .add_tool_results(vec![ToolResult::new(ToolName::new("Read"))
+                .call_id(ToolCallId::new("toolu_02"))
+                .success("fn login() {}")])
+            .add_message(ContextMessage::assistant(
+                "Fixed the check in `login`.",
+                None,
+            ));
+        assert_eq!(conversation.context, expected);
+        assert_eq!(conversation.title, "Fix the failing login test");
+        assert_eq!(
+            conversation.notes,
+            vec![
+                "Left out 1 thinking, image or misplaced block(s)".to_string(),
+                "Left out 1 entries other than messages, such as system notices".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_session_keeps_its_timestamps() {
+        let actual = ClaudeCodeImporter.parse(FIXTURE);
+
+        let conversation = &actual.conversations[0];
+        assert_eq!(
+            conversation.started_at.unwrap().to_rfc3339(),
+            "2025-06-01T10:00:00+00:00"
+        );
+        assert_eq!(
+            conversation.ended_at.unwrap().to_rfc3339(),
+            "2025-06-01T10:01:30+00:00"
+        );
+    }
+
+    #[test]
+    fn test_malformed_entries_are_skipped() {
+        let actual = ClaudeCodeImporter.parse(FIXTURE);
+
+        let lines = actual
+            .skipped
+            .iter()
+            .map(|skipped| skipped.line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![6, 10]);
+        assert!(actual.skipped[0].reason.starts_with("invalid JSON"));
+        assert_eq!(actual.skipped[1].reason, "'user' entry without a message");
+        assert_eq!(actual.conversations.len(), 1);
+    }
+
+    #[test]
+    fn test_title_falls_back_to_the_first_user_message() {
+        let fixture = r#"{"type":"user","message":{"role":"user","content":[{"type":"text","text":"Rename the crate\nand its modules"}]}}"#;
+
+        let actual = ClaudeCodeImporter.parse(fixture);
+
+        assert_eq!(actual.conversations[0].title, "Rename the crate");
+        assert_eq!(actual.conversations[0].started_at, None);
+    }
+}
@@ -0,0 +1,297 @@
+mod aider;
+mod claude_code;
+
+use std::path::{Path, PathBuf};
+
+pub use aider::AiderImporter;
+use anyhow::Context as _;
+use chrono::Utc;
+pub use claude_code::ClaudeCodeImporter;
+use forge_domain::{
+    AgentId, AgentState, Conversation, ConversationId, FileImport, HistoryImporter, ImportFormat,
+    ImportReport, ImportSource, ImportedConversation, JournalService, Workflow,
+};
+
+/// Agent the context of an imported conversation is kept under
+pub const IMPORTED_AGENT: &str = "imported";
+
+/// Longest title given to an imported conversation, in characters
+const MAX_TITLE_CHARS: usize = 60;
+
+/// The importer of a history format
+pub fn history_importer(format: ImportFormat) -> Box<dyn HistoryImporter> {
+    match format {
+        ImportFormat::Aider => Box::new(AiderImporter),
+        ImportFormat::ClaudeCode => Box::new(ClaudeCodeImporter),
+    }
+}
+
+/// Imports the history of other agent tools as conversations kept by the
+/// conversation store, each under the content hash of its messages, so
+/// importing the same history again leaves it as it is.
+pub struct HistoryImport<'a, S> {
+    store: &'a S,
+}
+
+impl<'a, S: JournalService> HistoryImport<'a, S> {
+    pub fn new(store: &'a S) -> Self {
+        Self { store }
+    }
+
+    /// Imports a history file, or every history file found in a directory
+    pub async fn run(
+        &self,
+        importer: &dyn HistoryImporter,
+        path: &Path,
+    ) -> anyhow::Result<ImportReport> {
+        let files = if path.is_dir() {
+            history_files(importer, path)
+                .await
+                .with_context(|| format!("Failed to read '{}'", path.display()))?
+        } else {
+            vec![path.to_path_buf()]
+        };
+        if files.is_empty() {
+            anyhow::bail!(
+                "No {} history found in '{}'",
+                importer.format(),
+                path.display()
+            );
+        }
+
+        let mut report = ImportReport::default();
+        for file in files {
+            report.files.push(self.import_file(importer, file).await);
+        }
+        Ok(report)
+    }
+
+    async fn import_file(&self, importer: &dyn HistoryImporter, path: PathBuf) -> FileImport {
+        let mut report = FileImport { path, ..Default::default() };
+        let content = match tokio::fs::read_to_string(&report.path).await {
+            Ok(content) => content,
+            Err(err) => {
+                report.error = Some(err.to_string());
+                return report;
+            }
+        };
+
+        let parsed = importer.parse(&content);
+        report.skipped = parsed.skipped;
+        for conversation in parsed.conversations {
+            let result = self
+                .store(importer.format(), &report.path, conversation)
+                .await;
+            match result {
+                Ok(true) => report.imported += 1,
+                Ok(false) => report.duplicates += 1,
+                Err(err) => {
+                    report.error = Some(format!("{err:#}"));
+                    break;
+                }
+            }
+        }
+        report
+    }
+
+    /// Stores the conversation, returns false when it was imported before
+    async fn store(
+        &self,
+        format: ImportFormat,
+        path: &Path,
+        imported: ImportedConversation,
+    ) -> anyhow::Result<bool> {
+        let hash = blake3::hash(serde_json::to_string(&imported.context.messages)?.as_bytes())
+            .to_hex()
+            .to_string();
+        let mut conversation = Conversation::new(ConversationId::generate(), Workflow::default());
+        conversation.add_tag(&format.tag());
+        conversation.updated_at = imported
+            .ended_at
+            .or(imported.started_at)
+            .unwrap_or_else(Utc::now);
+        conversation.state.insert(
+            AgentId::new(IMPORTED_AGENT),
+            AgentState { turn_count: 0, context: Some(imported.context) },
+        );
+        conversation.imported = Some(ImportSource {
+            format,
+            path: path.to_path_buf(),
+            title: imported.title,
+            started_at: imported.started_at,
+            ended_at: imported.ended_at,
+            hash,
+            notes: imported.notes,
+        });
+
+        self.store.save_import(&hash, &conversation).await
+    }
+}
+
+/// The files of the directory and its subdirectories that the importer
+/// accepts
+async fn history_files(
+    importer: &dyn HistoryImporter,
+    dir: &Path,
+) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+            } else if importer.accepts(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The first line of the text, cut to a length that fits a title
+fn title(text: &str) -> Option<String> {
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+    if line.chars().count() <= MAX_TITLE_CHARS {
+        return Some(line.to_string());
+    }
+    let cut = line.chars().take(MAX_TITLE_CHARS - 1).collect::<String>();
+    Some(format!("{}…", cut.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_domain::{ContextMessage, SkippedEntry};
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::cipher::StoreKey;
+    use crate::journal::ForgeJournalService;
+
+    fn store(dir: &TempDir) -> ForgeJournalService {
+        ForgeJournalService::new(
+            dir.path().join("recovery"),
+            dir.path().join("imports"),
+            StoreKey::new(dir.path().join("store.key")),
+        )
+    }
+
+    async fn stored(store: &ForgeJournalService) -> Vec<Conversation> {
+        let mut conversations = store.imports().await.unwrap();
+        Conversation::sort(&mut conversations);
+        conversations
+    }
+
+    #[tokio::test]
+    async fn test_import_stores_conversations_with_their_source() {
+        let source = TempDir::new().unwrap();
+        let path = source.path().join(".aider.chat.history.md");
+        tokio::fs::write(&path, include_str!("fixtures/aider.chat.history.md"))
+            .await
+            .unwrap();
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+
+        let actual = HistoryImport::new(&store)
+            .run(&AiderImporter, source.path())
+            .await
+            .unwrap();
+
+        assert_eq!(actual.imported(), 2);
+        assert_eq!(actual.files[0].path, path);
+        assert_eq!(
+            actual.files[0].skipped,
+            vec![SkippedEntry::new(
+                27,
+                "invalid start time 'yesterday at noon', the session is left out"
+            )]
+        );
+
+        let conversations = stored(&store).await;
+        let latest = &conversations[0];
+        let source = latest.imported.as_ref().unwrap();
+        assert_eq!(source.format, ImportFormat::Aider);
+        assert_eq!(source.title, "Now add a --verbose flag");
+        assert_eq!(
+            latest.updated_at.to_rfc3339(),
+            "2024-05-02T09:15:00+00:00".to_string()
+        );
+        assert!(latest.has_tag("imported:aider"));
+        let context = latest.context(&AgentId::new(IMPORTED_AGENT)).unwrap();
+        assert_eq!(
+            context.messages[0],
+            ContextMessage::user("Now add a --verbose flag")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_again_skips_imported_conversations() {
+        let source = TempDir::new().unwrap();
+        let path = source.path().join("session.jsonl");
+        tokio::fs::write(&path, include_str!("fixtures/claude_code_session.jsonl"))
+            .await
+            .unwrap();
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+        let import = HistoryImport::new(&store);
+
+        let first = import.run(&ClaudeCodeImporter, &path).await.unwrap();
+        let second = import.run(&ClaudeCodeImporter, &path).await.unwrap();
+
+        assert_eq!((first.imported(), first.duplicates()), (1, 0));
+        assert_eq!((second.imported(), second.duplicates()), (0, 1));
+        assert_eq!(stored(&store).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_reports_unreadable_files() {
+        let source = TempDir::new().unwrap();
+        let path = source.path().join("session.jsonl");
+        tokio::fs::write(&path, [0xff, 0xfe, 0x00]).await.unwrap();
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+
+        let actual = HistoryImport::new(&store)
+            .run(&ClaudeCodeImporter, &path)
+            .await
+            .unwrap();
+
+        assert_eq!(actual.imported(), 0);
+        assert!(actual.files[0].error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_import_fails_without_history() {
+        let source = TempDir::new().unwrap();
+        tokio::fs::write(source.path().join("notes.md"), "# Notes")
+            .await
+            .unwrap();
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+
+        let actual = HistoryImport::new(&store)
+            .run(&AiderImporter, source.path())
+            .await;
+
+        assert!(actual
+            .unwrap_err()
+            .to_string()
+            .starts_with("No aider history found in"));
+    }
+
+    #[test]
+    fn test_title() {
+        assert_eq!(
+            title("\n  Fix the login bug \nmore"),
+            Some("Fix the login bug".to_string())
+        );
+        assert_eq!(
+            title(&"a".repeat(80)),
+            Some(format!("{}…", "a".repeat(MAX_TITLE_CHARS - 1)))
+        );
+        assert_eq!(title("  \n"), None);
+    }
+}
@@ -0,0 +1,559 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::Context;
+use forge_domain::{
+    chunk_text, DocumentChunk, IndexProgress, IndexRequest, IndexState, IndexSummary, Point,
+    PointId,
+};
+use forge_walker::Walker;
+
+use crate::{EmbeddingService, EnvironmentService, Infrastructure, VectorIndex};
+
+/// Files larger than this are not indexed
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Ids are derived from the absolute path of the file and the position of
+/// the chunk, so the chunks of a file can be found again without searching
+fn chunk_id(key: &str, index: usize) -> PointId {
+    let digest = blake3::hash(format!("{key}#{index}").as_bytes());
+    let mut id = [0; 16];
+    id.copy_from_slice(&digest.as_bytes()[..16]);
+    PointId::from_bytes(id)
+}
+
+/// Chunks files and stores their embeddings in the document index
+pub struct ForgeIndexer<F> {
+    infra: Arc<F>,
+}
+
+impl<F: Infrastructure> ForgeIndexer<F> {
+    pub fn new(infra: Arc<F>) -> Self {
+        Self { infra }
+    }
+
+    /// Lists the files to index, ignore files and the size cap are respected
+    async fn files(&self, request: &IndexRequest) -> anyhow::Result<Vec<(PathBuf, String)>> {
+        let glob = request
+            .glob
+            .as_deref()
+            .map(|pattern| {
+                glob::Pattern::new(pattern)
+                    .with_context(|| format!("Invalid glob pattern '{}'", pattern))
+            })
+            .transpose()?;
+
+        let mut files = Walker::max_all()
+            .cwd(request.path.clone())
+            .max_file_size(MAX_FILE_SIZE)
+            .skip_binary(true)
+            .get()
+            .await
+            .with_context(|| format!("Failed to walk '{}'", request.path.display()))?
+            .into_iter()
+            .filter(|file| !file.is_dir())
+            .filter(|file| glob.as_ref().is_none_or(|glob| glob.matches(&file.path)))
+            .map(|file| (request.path.join(&file.path), file.path))
+            .collect::<Vec<_>>();
+
+        files.sort();
+        Ok(files)
+    }
+
+    /// Indexes the requested files, skipping the ones that didn't change
+    /// since they were recorded in `state`. Setting `cancelled` stops the run
+    /// once the file being indexed is complete, so that `state` only ever
+    /// records files whose chunks were all stored.
+    pub async fn index<P, Fut>(
+        &self,
+        request: &IndexRequest,
+        state: &mut IndexState,
+        cancelled: &AtomicBool,
+        progress: P,
+    ) -> anyhow::Result<IndexSummary>
+    where
+        P: Fn(IndexProgress) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let files = self.files(request).await?;
+        progress(IndexProgress::Started { total: files.len() }).await;
+
+        let mut summary = IndexSummary::default();
+        for (path, relative) in files {
+            if cancelled.load(Ordering::SeqCst) {
+                summary.cancelled = true;
+                break;
+            }
+
+            // Binary and unreadable files can't be embedded
+            let Ok(content) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+
+            let key = path.to_string_lossy().to_string();
+            let hash = blake3::hash(content.as_bytes()).to_hex().to_string();
+            if state.is_unchanged(&key, &hash) {
+                summary.skipped += 1;
+                progress(IndexProgress::Skipped { path: relative }).await;
+                continue;
+            }
+
+            // A file that changed may now have fewer chunks, none of the
+            // previous ones may be left behind
+            let stale = (0..state.chunk_count(&key))
+                .map(|index| chunk_id(&key, index))
+                .collect::<Vec<_>>();
+            self.infra.document_index().delete(stale).await?;
+
+            let chunks = chunk_text(&content, &request.chunk);
+            for (index, chunk) in chunks.iter().enumerate() {
+                let embedding = self.infra.embedding_service().embed(&chunk.content).await?;
+
+                let mut tags = request.tags.clone();
+                tags.push(relative.clone());
+                let document = DocumentChunk {
+                    path: key.clone(),
+                    start_line: chunk.start_line,
+                    end_line: chunk.end_line,
+                    content: chunk.content.clone(),
+                    tags,
+                };
+                let point = Point { id: chunk_id(&key, index), ..Point::new(document, embedding) };
+                self.infra.document_index().store(point).await?;
+            }
+
+            state.record(key, hash, chunks.len());
+            summary.indexed += 1;
+            summary.chunks += chunks.len();
+            progress(IndexProgress::Indexed { path: relative, chunks: chunks.len() }).await;
+        }
+
+        progress(IndexProgress::Finished(summary.clone())).await;
+        Ok(summary)
+    }
+
+    /// Indexes the requested files using the state stored in the forge
+    /// directory, which is saved again once the run ends, even when it was
+    /// cancelled.
+    pub async fn run<P, Fut>(
+        &self,
+        request: &IndexRequest,
+        cancelled: &AtomicBool,
+        progress: P,
+    ) -> anyhow::Result<IndexSummary>
+    where
+        P: Fn(IndexProgress) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let path = self
+            .infra
+            .environment_service()
+            .get_environment()
+            .index_path();
+        let mut state = Self::load_state(&path).await?;
+        state.record_request(request);
+        let result = self.index(request, &mut state, cancelled, progress).await;
+        Self::save_state(&path, &state).await?;
+        result
+    }
+
+    /// Drops the document index and indexes again every directory recorded
+    /// in `state`, from the files as they are on disk. Returns the state of
+    /// the new index, which only records the files indexed before a
+    /// cancellation.
+    pub async fn reindex<P, Fut>(
+        &self,
+        state: &IndexState,
+        cancelled: &AtomicBool,
+        progress: P,
+    ) -> anyhow::Result<(IndexState, IndexSummary)>
+    where
+        P: Fn(IndexProgress) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        if state.requests.is_empty() && !state.files.is_empty() {
+            anyhow::bail!(
+                "The index was built before forge recorded what it indexed, run `forge index` again"
+            );
+        }
+
+        self.infra.document_index().clear().await?;
+        let mut rebuilt = IndexState { requests: state.requests.clone(), ..Default::default() };
+        let mut summary = IndexSummary::default();
+        for request in &state.requests {
+            // Each request finishes on its own, only the rebuild as a whole
+            // is reported as finished
+            let part = self
+                .index(request, &mut rebuilt, cancelled, |event| {
+                    let event = (!matches!(event, IndexProgress::Finished(_))).then_some(event);
+                    let sent = event.map(&progress);
+                    async move {
+                        if let Some(sent) = sent {
+                            sent.await
+                        }
+                    }
+                })
+                .await?;
+            summary.indexed += part.indexed;
+            summary.chunks += part.chunks;
+            summary.cancelled = part.cancelled;
+            if part.cancelled {
+                break;
+            }
+        }
+
+        progress(IndexProgress::Finished(summary.clone())).await;
+        Ok((rebuilt, summary))
+    }
+
+    /// Rebuilds the index recorded in the forge directory, see
+    /// [`ForgeIndexer::reindex`]
+    pub async fn rebuild<P, Fut>(
+        &self,
+        cancelled: &AtomicBool,
+        progress: P,
+    ) -> anyhow::Result<IndexSummary>
+    where
+        P: Fn(IndexProgress) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let path = self
+            .infra
+            .environment_service()
+            .get_environment()
+            .index_path();
+        let state = Self::load_state(&path).await?;
+        let (state, summary) = self.reindex(&state, cancelled, progress).await?;
+        Self::save_state(&path, &state).await?;
+        Ok(summary)
+    }
+
+    async fn load_state(path: &Path) -> anyhow::Result<IndexState> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse index state {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(IndexState::default()),
+            Err(err) => {
+                Err(err).with_context(|| format!("Failed to read index state {}", path.display()))
+            }
+        }
+    }
+
+    async fn save_state(path: &Path, state: &IndexState) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, serde_json::to_string_pretty(state)?)
+            .await
+            .with_context(|| format!("Failed to write index state {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use forge_domain::ChunkConfig;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+    use tokio::fs;
+
+    use super::*;
+    use crate::attachment::tests::MockInfrastructure;
+
+    async fn fixture(dir: &std::path::Path) {
+        fs::create_dir(dir.join("docs")).await.unwrap();
+        fs::write(dir.join("docs/a.md"), "# A\n\nalpha\n")
+            .await
+            .unwrap();
+        fs::write(dir.join("docs/b.md"), "# B\n\nbeta\n")
+            .await
+            .unwrap();
+        fs::write(dir.join("docs/c.md"), "# C\n\ngamma\n")
+            .await
+            .unwrap();
+        fs::write(dir.join("main.rs"), "fn main() {}\n")
+            .await
+            .unwrap();
+    }
+
+    fn request(dir: &std::path::Path) -> IndexRequest {
+        IndexRequest {
+            path: dir.to_path_buf(),
+            glob: Some("**/*.md".to_string()),
+            tags: vec!["docs".to_string()],
+            chunk: ChunkConfig::default(),
+        }
+    }
+
+    /// Paths of the stored chunks, relative to `dir`
+    fn stored_paths(infra: &MockInfrastructure, dir: &std::path::Path) -> Vec<String> {
+        let mut paths = infra
+            .document_index
+            .points
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|point| {
+                std::path::Path::new(&point.content.path)
+                    .strip_prefix(dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+
+    #[tokio::test]
+    async fn test_index_matching_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fixture(temp_dir.path()).await;
+        let infra = Arc::new(MockInfrastructure::new());
+        let mut state = IndexState::default();
+
+        let actual = ForgeIndexer::new(infra.clone())
+            .index(
+                &request(temp_dir.path()),
+                &mut state,
+                &AtomicBool::new(false),
+                |_| async {},
+            )
+            .await
+            .unwrap();
+
+        let expected = IndexSummary { indexed: 3, skipped: 0, chunks: 3, cancelled: false };
+        assert_eq!(actual, expected);
+        assert_eq!(
+            stored_paths(&infra, temp_dir.path()),
+            vec!["docs/a.md", "docs/b.md", "docs/c.md"]
+        );
+
+        let point = infra.document_index.points.lock().unwrap()[0].clone();
+        assert_eq!(
+            point.content.tags,
+            vec!["docs".to_string(), "docs/a.md".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_index_skips_unchanged_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fixture(temp_dir.path()).await;
+        let infra = Arc::new(MockInfrastructure::new());
+        let indexer = ForgeIndexer::new(infra.clone());
+        let request = request(temp_dir.path());
+        let mut state = IndexState::default();
+        let cancelled = AtomicBool::new(false);
+
+        indexer
+            .index(&request, &mut state, &cancelled, |_| async {})
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("docs/b.md"), "# B\n\nchanged\n")
+            .await
+            .unwrap();
+
+        let actual = indexer
+            .index(&request, &mut state, &cancelled, |_| async {})
+            .await
+            .unwrap();
+
+        let expected = IndexSummary { indexed: 1, skipped: 2, chunks: 1, cancelled: false };
+        assert_eq!(actual, expected);
+
+        // Chunks of the changed file replace the previous ones
+        assert_eq!(infra.document_index.points.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_index_removes_chunks_a_file_no_longer_has() {
+        let temp_dir = TempDir::new().unwrap();
+        fixture(temp_dir.path()).await;
+        let infra = Arc::new(MockInfrastructure::new());
+        let indexer = ForgeIndexer::new(infra.clone());
+        let request = IndexRequest {
+            chunk: ChunkConfig { size: 8, overlap: 0 },
+            ..request(temp_dir.path())
+        };
+        let mut state = IndexState::default();
+        let cancelled = AtomicBool::new(false);
+        let long = (0..10).map(|n| format!("line {n}\n\n")).collect::<String>();
+        fs::write(temp_dir.path().join("docs/a.md"), long)
+            .await
+            .unwrap();
+        indexer
+            .index(&request, &mut state, &cancelled, |_| async {})
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("docs/a.md"), "short\n")
+            .await
+            .unwrap();
+
+        indexer
+            .index(&request, &mut state, &cancelled, |_| async {})
+            .await
+            .unwrap();
+
+        let actual = stored_paths(&infra, temp_dir.path())
+            .into_iter()
+            .filter(|path| path == "docs/a.md")
+            .count();
+        assert_eq!(actual, 1);
+    }
+
+    #[tokio::test]
+    async fn test_files_of_different_directories_keep_their_chunks() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["one", "two"] {
+            fs::create_dir(temp_dir.path().join(name)).await.unwrap();
+            fixture(&temp_dir.path().join(name)).await;
+        }
+        let infra = Arc::new(MockInfrastructure::new());
+        let indexer = ForgeIndexer::new(infra.clone());
+        let mut state = IndexState::default();
+
+        for name in ["one", "two"] {
+            indexer
+                .index(
+                    &request(&temp_dir.path().join(name)),
+                    &mut state,
+                    &AtomicBool::new(false),
+                    |_| async {},
+                )
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(stored_paths(&infra, temp_dir.path()).len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_index_cancellation_keeps_a_consistent_partial_index() {
+        let temp_dir = TempDir::new().unwrap();
+        fixture(temp_dir.path()).await;
+        let infra = Arc::new(MockInfrastructure::new());
+        let indexer = ForgeIndexer::new(infra.clone());
+        let request = request(temp_dir.path());
+        let mut state = IndexState::default();
+        let cancelled = AtomicBool::new(false);
+        let events = Mutex::new(Vec::new());
+
+        // Cancel as soon as the first file is indexed
+        let actual = indexer
+            .index(&request, &mut state, &cancelled, |event| {
+                if matches!(event, IndexProgress::Indexed { .. }) {
+                    cancelled.store(true, Ordering::SeqCst);
+                }
+                events.lock().unwrap().push(event);
+                async {}
+            })
+            .await
+            .unwrap();
+
+        let expected = IndexSummary { indexed: 1, skipped: 0, chunks: 1, cancelled: true };
+        assert_eq!(actual, expected);
+        assert_eq!(stored_paths(&infra, temp_dir.path()), vec!["docs/a.md"]);
+        assert_eq!(
+            state.files.keys().cloned().collect::<Vec<_>>(),
+            vec![temp_dir
+                .path()
+                .join("docs/a.md")
+                .to_string_lossy()
+                .to_string()]
+        );
+        assert_eq!(
+            events.into_inner().unwrap().last(),
+            Some(&IndexProgress::Finished(expected))
+        );
+
+        // Running again picks up where the cancelled run stopped
+        cancelled.store(false, Ordering::SeqCst);
+        let actual = indexer
+            .index(&request, &mut state, &cancelled, |_| async {})
+            .await
+            .unwrap();
+
+        let expected = IndexSummary { indexed: 2, skipped: 1, chunks: 2, cancelled: false };
+        assert_eq!(actual, expected);
+        assert_eq!(
+            stored_paths(&infra, temp_dir.path()),
+            vec!["docs/a.md", "docs/b.md", "docs/c.md"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reindex_matches_the_files_on_disk() {
+        let temp_dir = TempDir::new().unwrap();
+        fixture(temp_dir.path()).await;
+        let infra = Arc::new(MockInfrastructure::new());
+        let indexer = ForgeIndexer::new(infra.clone());
+        let request = request(temp_dir.path());
+        let mut state = IndexState::default();
+        state.record_request(&request);
+        indexer
+            .index(&request, &mut state, &AtomicBool::new(false), |_| async {})
+            .await
+            .unwrap();
+        fs::remove_file(temp_dir.path().join("docs/b.md"))
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("docs/d.md"), "# D\n\ndelta\n")
+            .await
+            .unwrap();
+        let events = Mutex::new(Vec::new());
+
+        let (actual, summary) = indexer
+            .reindex(&state, &AtomicBool::new(false), |event| {
+                events.lock().unwrap().push(event);
+                async {}
+            })
+            .await
+            .unwrap();
+
+        let expected = IndexSummary { indexed: 3, skipped: 0, chunks: 3, cancelled: false };
+        assert_eq!(summary, expected);
+        assert_eq!(
+            stored_paths(&infra, temp_dir.path()),
+            vec!["docs/a.md", "docs/c.md", "docs/d.md"]
+        );
+        assert_eq!(actual.requests, vec![request]);
+        assert_eq!(actual.files.len(), 3);
+        let events = events.into_inner().unwrap();
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, IndexProgress::Finished(_)))
+                .collect::<Vec<_>>(),
+            vec![&IndexProgress::Finished(expected)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reindex_refuses_a_state_without_requests() {
+        let infra = Arc::new(MockInfrastructure::new());
+        let point = Point::new(
+            DocumentChunk {
+                path: "docs/a.md".to_string(),
+                start_line: 1,
+                end_line: 3,
+                content: "alpha".to_string(),
+                tags: vec![],
+            },
+            vec![0.1],
+        );
+        infra.document_index.points.lock().unwrap().push(point);
+        let mut fixture = IndexState::default();
+        fixture.record("/repo/docs/a.md", "1", 1);
+
+        let actual = ForgeIndexer::new(infra.clone())
+            .reindex(&fixture, &AtomicBool::new(false), |_| async {})
+            .await;
+
+        assert!(actual.is_err());
+        assert_eq!(infra.document_index.points.lock().unwrap().len(), 1);
+    }
+}
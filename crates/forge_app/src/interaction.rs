@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use forge_domain::{InteractionService, ToolCallId, UserAnswer, UserInteraction};
+use tokio::sync::{oneshot, Mutex};
+
+/// A question waiting for the user's answer
+struct Pending {
+    interaction: UserInteraction,
+    sender: oneshot::Sender<UserAnswer>,
+}
+
+/// Keeps the questions that are waiting for the user's answer in memory
+#[derive(Default)]
+pub struct ForgeInteractionService {
+    pending: Mutex<HashMap<ToolCallId, Pending>>,
+}
+
+#[async_trait::async_trait]
+impl InteractionService for ForgeInteractionService {
+    async fn register(
+        &self,
+        call_id: &ToolCallId,
+        interaction: &UserInteraction,
+    ) -> oneshot::Receiver<UserAnswer> {
+        let (sender, rx) = oneshot::channel();
+        self.pending.lock().await.insert(
+            call_id.clone(),
+            Pending { interaction: interaction.clone(), sender },
+        );
+        rx
+    }
+
+    async fn respond(&self, call_id: &ToolCallId, answer: UserAnswer) -> Result<()> {
+        let mut pending = self.pending.lock().await;
+        let question = pending
+            .get(call_id)
+            .ok_or_else(|| anyhow!("No question is waiting for an answer: {}", call_id.as_str()))?;
+
+        // An invalid answer leaves the question waiting, so the user can retry
+        question.interaction.validate(&answer)?;
+
+        if let Some(question) = pending.remove(call_id) {
+            // The receiver is gone if the turn was cancelled in the meantime
+            let _ = question.sender.send(answer);
+        }
+        Ok(())
+    }
+
+    async fn cancel(&self, call_id: &ToolCallId) {
+        self.pending.lock().await.remove(call_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_domain::Error;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn select() -> UserInteraction {
+        UserInteraction::Select {
+            prompt: "Which file did you mean?".to_string(),
+            options: vec!["src/main.rs".to_string(), "src/lib.rs".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_respond_resolves_pending_question() {
+        let service = ForgeInteractionService::default();
+        let call_id = ToolCallId::new("call_1");
+
+        let answer = service.register(&call_id, &select()).await;
+        service
+            .respond(&call_id, UserAnswer::Selected(1))
+            .await
+            .unwrap();
+
+        assert_eq!(answer.await.unwrap(), UserAnswer::Selected(1));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_answer_is_rejected_and_keeps_waiting() {
+        let service = ForgeInteractionService::default();
+        let call_id = ToolCallId::new("call_1");
+        let fixture = UserInteraction::Confirm { prompt: "Overwrite the file?".to_string() };
+
+        let answer = service.register(&call_id, &fixture).await;
+        let actual = service
+            .respond(&call_id, UserAnswer::Text("maybe".to_string()))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            actual.downcast_ref::<Error>(),
+            Some(Error::InvalidAnswer(_))
+        ));
+        assert!(actual.to_string().contains("answer 'yes' or 'no'"));
+
+        service
+            .respond(&call_id, UserAnswer::Text("yes".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(answer.await.unwrap(), UserAnswer::Text("yes".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_releases_pending_question() {
+        let service = ForgeInteractionService::default();
+        let call_id = ToolCallId::new("call_1");
+
+        let answer = service.register(&call_id, &select()).await;
+        service.cancel(&call_id).await;
+
+        assert!(answer.await.is_err());
+        let actual = service.respond(&call_id, UserAnswer::Selected(0)).await;
+        assert!(actual.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_respond_without_pending_question() {
+        let service = ForgeInteractionService::default();
+        let actual = service
+            .respond(&ToolCallId::new("unknown"), UserAnswer::Selected(0))
+            .await;
+        assert!(actual.is_err());
+    }
+}
@@ -0,0 +1,650 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use anyhow::{bail, Context as _, Result};
+use forge_domain::{AgentId, Conversation, ConversationId, Journal, JournalEntry, JournalService};
+use tokio::io::AsyncWriteExt;
+
+use crate::cipher::{StoreCipher, StoreKey};
+
+/// Stores the journal of each in-flight turn as an append-only JSON lines file
+/// at `<dir>/<conversation id>/<agent id>.jsonl`, and each imported
+/// conversation at `<imports>/<hash>.json`. Once the store is encrypted every
+/// journal line and imported conversation is sealed on its own, and nothing
+/// is read or written until the store is unlocked.
+pub struct ForgeJournalService {
+    dir: PathBuf,
+    imports: PathBuf,
+    key: StoreKey,
+    cipher: RwLock<Option<Arc<StoreCipher>>>,
+}
+
+/// A journal found on disk, known from its path alone
+struct JournalFile {
+    conversation_id: ConversationId,
+    agent: AgentId,
+    path: PathBuf,
+}
+
+impl ForgeJournalService {
+    pub fn new(dir: PathBuf, imports: PathBuf, key: StoreKey) -> Self {
+        Self { dir, imports, key, cipher: RwLock::new(None) }
+    }
+
+    fn path(&self, id: &ConversationId, agent: &AgentId) -> PathBuf {
+        self.dir
+            .join(id.into_string())
+            .join(format!("{}.jsonl", agent.as_str()))
+    }
+
+    /// The cipher of an unlocked store, `None` when the store isn't encrypted.
+    /// Fails while an encrypted store is locked.
+    fn cipher(&self) -> Result<Option<Arc<StoreCipher>>> {
+        let cipher = self
+            .cipher
+            .read()
+            .map_err(|_| anyhow::anyhow!("Store cipher is poisoned"))?
+            .clone();
+        if cipher.is_none() && self.key.exists() {
+            bail!("The conversation store is encrypted and must be unlocked with its passphrase");
+        }
+        Ok(cipher)
+    }
+
+    fn set_cipher(&self, cipher: Option<StoreCipher>) -> Result<()> {
+        *self
+            .cipher
+            .write()
+            .map_err(|_| anyhow::anyhow!("Store cipher is poisoned"))? = cipher.map(Arc::new);
+        Ok(())
+    }
+
+    async fn read(path: &Path, cipher: Option<&StoreCipher>) -> Result<Vec<JournalEntry>> {
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read journal {}", path.display()))?;
+
+        // A crash in the middle of a write can leave a partial last line behind, so
+        // only the entries that parse cleanly are recovered.
+        Ok(content
+            .lines()
+            .map_while(|line| match cipher {
+                Some(cipher) => serde_json::from_str(&cipher.open_line(line).ok()?).ok(),
+                None => serde_json::from_str(line).ok(),
+            })
+            .collect())
+    }
+
+    /// Lists the journals on disk from their paths, without reading them
+    async fn files(&self) -> Result<Vec<JournalFile>> {
+        let mut journals = Vec::new();
+        if !self.dir.exists() {
+            return Ok(journals);
+        }
+
+        let mut conversations = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(conversation) = conversations.next_entry().await? {
+            let Ok(conversation_id) =
+                ConversationId::parse(conversation.file_name().to_string_lossy())
+            else {
+                continue;
+            };
+
+            if !conversation.file_type().await?.is_dir() {
+                continue;
+            }
+
+            let mut files = tokio::fs::read_dir(conversation.path()).await?;
+            while let Some(file) = files.next_entry().await? {
+                let path = file.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+                    continue;
+                }
+
+                let Some(agent) = path.file_stem().map(|stem| stem.to_string_lossy()) else {
+                    continue;
+                };
+
+                journals.push(JournalFile {
+                    conversation_id: conversation_id.clone(),
+                    agent: AgentId::new(agent),
+                    path,
+                });
+            }
+        }
+
+        Ok(journals)
+    }
+
+    /// Lists the imported conversations on disk, without reading them
+    async fn import_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        if !self.imports.exists() {
+            return Ok(files);
+        }
+
+        let mut entries = tokio::fs::read_dir(&self.imports).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Converts every line of a journal. Only a last line cut short by a
+    /// crash, one without a line break, is dropped when it doesn't convert;
+    /// any other line that doesn't fails the whole rewrite.
+    fn convert(
+        file: &JournalFile,
+        content: &str,
+        convert: &impl Fn(&str) -> Result<String>,
+    ) -> Result<String> {
+        let torn = !content.is_empty() && !content.ends_with('\n');
+        let count = content.lines().count();
+        let mut converted = String::new();
+        for (index, line) in content.lines().enumerate() {
+            match convert(line) {
+                Ok(line) => {
+                    converted.push_str(&line);
+                    converted.push('\n');
+                }
+                Err(_) if torn && index + 1 == count => {}
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!(
+                            "Failed to convert line {} of journal {}",
+                            index + 1,
+                            file.path.display()
+                        )
+                    })
+                }
+            }
+        }
+        Ok(converted)
+    }
+
+    /// Converts every line of the journals, and each imported conversation as
+    /// a whole. Nothing is replaced until all of them converted, then each
+    /// file is replaced atomically.
+    async fn rewrite(
+        files: &[JournalFile],
+        imports: &[PathBuf],
+        convert: impl Fn(&str) -> Result<String>,
+    ) -> Result<()> {
+        let mut staged = Vec::new();
+        let result = async {
+            for file in files {
+                let content = tokio::fs::read_to_string(&file.path)
+                    .await
+                    .with_context(|| format!("Failed to read journal {}", file.path.display()))?;
+                let converted = Self::convert(file, &content, &convert)?;
+
+                let temp = file.path.with_extension("jsonl.tmp");
+                tokio::fs::write(&temp, converted).await?;
+                staged.push((temp, &file.path));
+            }
+            for path in imports {
+                let content = tokio::fs::read_to_string(path).await.with_context(|| {
+                    format!("Failed to read imported conversation {}", path.display())
+                })?;
+                let converted = convert(content.trim()).with_context(|| {
+                    format!("Failed to convert imported conversation {}", path.display())
+                })?;
+
+                let temp = path.with_extension("json.tmp");
+                tokio::fs::write(&temp, format!("{converted}\n")).await?;
+                staged.push((temp, path));
+            }
+            anyhow::Ok(())
+        }
+        .await;
+
+        if let Err(err) = result {
+            for (temp, _) in &staged {
+                let _ = tokio::fs::remove_file(temp).await;
+            }
+            return Err(err);
+        }
+
+        for (temp, path) in &staged {
+            tokio::fs::rename(temp, path)
+                .await
+                .with_context(|| format!("Failed to replace {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl JournalService for ForgeJournalService {
+    async fn append(
+        &self,
+        id: &ConversationId,
+        agent: &AgentId,
+        entry: JournalEntry,
+    ) -> Result<()> {
+        let path = self.path(id, agent);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let mut line = serde_json::to_string(&entry)?;
+        if let Some(cipher) = self.cipher()? {
+            line = cipher.seal_line(&line)?;
+        }
+        line.push('\n');
+
+        // A turn starts the file over, whatever a turn that failed to clear it
+        // left behind
+        let restart = matches!(entry, JournalEntry::Begin { .. });
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(!restart)
+            .truncate(restart)
+            .open(&path)
+            .await
+            .with_context(|| format!("Failed to open journal {}", path.display()))?;
+        file.write_all(line.as_bytes()).await?;
+
+        // Only step boundaries are made durable, everything else is cheap to lose
+        if matches!(entry, JournalEntry::Step { .. }) {
+            file.sync_data().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn clear(&self, id: &ConversationId, agent: &AgentId) -> Result<()> {
+        let path = self.path(id, agent);
+        if path.exists() {
+            tokio::fs::remove_file(&path).await?;
+        }
+
+        if let Some(parent) = path.parent() {
+            // Remove the conversation directory once the last journal is gone
+            let _ = tokio::fs::remove_dir(parent).await;
+        }
+
+        Ok(())
+    }
+
+    async fn pending(&self) -> Result<Vec<Journal>> {
+        let cipher = self.cipher()?;
+        let mut journals = Vec::new();
+        for file in self.files().await? {
+            let entries = Self::read(&file.path, cipher.as_deref()).await?;
+            if !entries.is_empty() {
+                journals.push(Journal {
+                    conversation_id: file.conversation_id,
+                    agent: file.agent,
+                    entries,
+                });
+            }
+        }
+
+        Ok(journals)
+    }
+
+    fn is_encrypted(&self) -> bool {
+        self.key.exists()
+    }
+
+    async fn unlock(&self, passphrase: &str) -> Result<()> {
+        self.set_cipher(Some(self.key.unlock(passphrase)?))
+    }
+
+    async fn encrypt(&self, passphrase: &str) -> Result<usize> {
+        let files = self.files().await?;
+        let imports = self.import_files().await?;
+        // The key is written last, a key next to journals that failed to
+        // encrypt would make them unreadable
+        let key = self.key.prepare(passphrase)?;
+        Self::rewrite(&files, &imports, |line| key.cipher().seal_line(line)).await?;
+        let cipher = self.key.save(key)?;
+        self.set_cipher(Some(cipher))?;
+        Ok(files.len() + imports.len())
+    }
+
+    async fn decrypt(&self, passphrase: &str) -> Result<usize> {
+        let cipher = self.key.unlock(passphrase)?;
+        let files = self.files().await?;
+        let imports = self.import_files().await?;
+        Self::rewrite(&files, &imports, |line| cipher.open_line(line)).await?;
+        self.key.remove()?;
+        self.set_cipher(None)?;
+        Ok(files.len() + imports.len())
+    }
+
+    async fn save_import(&self, hash: &str, conversation: &Conversation) -> Result<bool> {
+        let cipher = self.cipher()?;
+        let path = self.imports.join(format!("{hash}.json"));
+        if tokio::fs::try_exists(&path).await? {
+            return Ok(false);
+        }
+
+        let mut content = serde_json::to_string(conversation)?;
+        if let Some(cipher) = cipher {
+            content = cipher.seal_line(&content)?;
+        }
+        tokio::fs::create_dir_all(&self.imports)
+            .await
+            .with_context(|| format!("Failed to create '{}'", self.imports.display()))?;
+        tokio::fs::write(&path, format!("{content}\n"))
+            .await
+            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+        Ok(true)
+    }
+
+    async fn imports(&self) -> Result<Vec<Conversation>> {
+        let cipher = self.cipher()?;
+        let mut conversations = Vec::new();
+        for path in self.import_files().await? {
+            let mut content = tokio::fs::read_to_string(&path).await.with_context(|| {
+                format!("Failed to read imported conversation {}", path.display())
+            })?;
+            if let Some(cipher) = &cipher {
+                content = cipher.open_line(&content)?;
+            }
+            conversations.push(serde_json::from_str(&content).with_context(|| {
+                format!("Failed to parse imported conversation {}", path.display())
+            })?);
+        }
+        Ok(conversations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_domain::{Context, ContextMessage, INTERRUPTED_TURN_NOTE};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn service(dir: &tempfile::TempDir) -> ForgeJournalService {
+        ForgeJournalService::new(
+            dir.path().to_path_buf(),
+            dir.path().join("imports"),
+            StoreKey::new(dir.path().join("store.key")).iterations(1_000),
+        )
+    }
+
+    fn begin() -> JournalEntry {
+        JournalEntry::Begin { context: Context::default() }
+    }
+
+    fn user(content: &str) -> JournalEntry {
+        JournalEntry::UserMessage { content: content.to_string() }
+    }
+
+    async fn entries(service: &ForgeJournalService) -> Vec<(AgentId, Vec<JournalEntry>)> {
+        service
+            .pending()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|journal| (journal.agent, journal.entries))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_pending_recovers_from_partial_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = ConversationId::generate();
+        let agent = AgentId::new("developer");
+
+        // Simulate a crash that left a torn line after the last completed step
+        let begin = JournalEntry::Begin { context: Context::default() };
+        let user = JournalEntry::UserMessage { content: "fix the bug".to_string() };
+        let content = format!(
+            "{}\n{}\n{{\"type\":\"step\",\"cont",
+            serde_json::to_string(&begin).unwrap(),
+            serde_json::to_string(&user).unwrap()
+        );
+        let path = dir.path().join(id.into_string());
+        tokio::fs::create_dir_all(&path).await.unwrap();
+        tokio::fs::write(path.join("developer.jsonl"), content)
+            .await
+            .unwrap();
+
+        let service = service(&dir);
+        let journals = service.pending().await.unwrap();
+
+        let actual = journals
+            .iter()
+            .map(|journal| (journal.agent.clone(), journal.recover()))
+            .collect::<Vec<_>>();
+        let expected = vec![(
+            agent,
+            Some(
+                Context::default()
+                    .add_message(ContextMessage::user("fix the bug"))
+                    .add_message(ContextMessage::user(INTERRUPTED_TURN_NOTE)),
+            ),
+        )];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_clear_removes_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = ConversationId::generate();
+        let agent = AgentId::new("developer");
+        let service = service(&dir);
+
+        service.append(&id, &agent, begin()).await.unwrap();
+        assert_eq!(service.pending().await.unwrap().len(), 1);
+
+        service.clear(&id, &agent).await.unwrap();
+
+        assert!(service.pending().await.unwrap().is_empty());
+        assert!(!dir.path().join(id.into_string()).exists());
+    }
+
+    fn raw(dir: &tempfile::TempDir, id: &ConversationId) -> String {
+        std::fs::read_to_string(dir.path().join(id.into_string()).join("developer.jsonl")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_journal_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = ConversationId::generate();
+        let agent = AgentId::new("developer");
+        let service = service(&dir);
+        assert_eq!(service.encrypt("hunter2").await.unwrap(), 0);
+
+        service.append(&id, &agent, begin()).await.unwrap();
+        service
+            .append(&id, &agent, user("fn secret() {}"))
+            .await
+            .unwrap();
+
+        let actual = entries(&service).await;
+        let expected = vec![(agent, vec![begin(), user("fn secret() {}")])];
+        assert_eq!(actual, expected);
+        assert!(!raw(&dir, &id).contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_and_decrypt_existing_journals() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = ConversationId::generate();
+        let agent = AgentId::new("developer");
+        service(&dir).append(&id, &agent, begin()).await.unwrap();
+        service(&dir)
+            .append(&id, &agent, user("fn secret() {}"))
+            .await
+            .unwrap();
+        let expected = vec![(agent, vec![begin(), user("fn secret() {}")])];
+
+        assert_eq!(service(&dir).encrypt("hunter2").await.unwrap(), 1);
+        assert!(!raw(&dir, &id).contains("secret"));
+        let unlocked = service(&dir);
+        unlocked.unlock("hunter2").await.unwrap();
+        assert_eq!(entries(&unlocked).await, expected);
+
+        assert_eq!(service(&dir).decrypt("hunter2").await.unwrap(), 1);
+        assert!(raw(&dir, &id).contains("secret"));
+        let plain = service(&dir);
+        assert!(!plain.is_encrypted());
+        assert_eq!(entries(&plain).await, expected);
+    }
+
+    fn imported(dir: &tempfile::TempDir) -> String {
+        std::fs::read_to_string(dir.path().join("imports").join("hash.json")).unwrap()
+    }
+
+    fn conversation() -> Conversation {
+        let mut conversation = Conversation::new(
+            ConversationId::generate(),
+            forge_domain::Workflow::default(),
+        );
+        conversation.add_tag("secret");
+        conversation
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_import_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(&dir);
+        service.encrypt("hunter2").await.unwrap();
+        let fixture = conversation();
+
+        assert!(service.save_import("hash", &fixture).await.unwrap());
+        assert!(!service.save_import("hash", &fixture).await.unwrap());
+
+        let actual = service.imports().await.unwrap();
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].id, fixture.id);
+        assert!(!imported(&dir).contains("secret"));
+        assert!(service(&dir).imports().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_and_decrypt_existing_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixture = conversation();
+        // Imports used to be written as pretty printed JSON
+        let imports = dir.path().join("imports");
+        std::fs::create_dir_all(&imports).unwrap();
+        std::fs::write(
+            imports.join("hash.json"),
+            serde_json::to_string_pretty(&fixture).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(service(&dir).encrypt("hunter2").await.unwrap(), 1);
+        assert!(!imported(&dir).contains("secret"));
+        let unlocked = service(&dir);
+        unlocked.unlock("hunter2").await.unwrap();
+        assert_eq!(unlocked.imports().await.unwrap()[0].id, fixture.id);
+
+        assert_eq!(service(&dir).decrypt("hunter2").await.unwrap(), 1);
+        assert!(imported(&dir).contains("secret"));
+        assert_eq!(service(&dir).imports().await.unwrap()[0].id, fixture.id);
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_fails_on_a_damaged_line_and_keeps_the_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = ConversationId::generate();
+        let agent = AgentId::new("developer");
+        let encrypted = service(&dir);
+        encrypted.encrypt("hunter2").await.unwrap();
+        encrypted.append(&id, &agent, begin()).await.unwrap();
+        encrypted
+            .append(&id, &agent, user("fn secret() {}"))
+            .await
+            .unwrap();
+        let path = dir.path().join(id.into_string()).join("developer.jsonl");
+        let damaged = format!("garbage\n{}", raw(&dir, &id));
+        std::fs::write(&path, &damaged).unwrap();
+
+        let actual = service(&dir).decrypt("hunter2").await;
+
+        assert!(actual.is_err());
+        assert_eq!(raw(&dir, &id), damaged);
+        assert!(service(&dir).is_encrypted());
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_drops_a_torn_last_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = ConversationId::generate();
+        let agent = AgentId::new("developer");
+        let encrypted = service(&dir);
+        encrypted.encrypt("hunter2").await.unwrap();
+        encrypted.append(&id, &agent, begin()).await.unwrap();
+        let path = dir.path().join(id.into_string()).join("developer.jsonl");
+        std::fs::write(&path, format!("{}AbC", raw(&dir, &id))).unwrap();
+
+        service(&dir).decrypt("hunter2").await.unwrap();
+
+        let actual = entries(&service(&dir)).await;
+        let expected = vec![(agent, vec![begin()])];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_wrong_passphrase_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        service(&dir).encrypt("hunter2").await.unwrap();
+        let service = service(&dir);
+
+        let actual = service.unlock("hunter3").await.err().unwrap().to_string();
+
+        assert_eq!(
+            actual,
+            "Wrong passphrase for the encrypted conversation store"
+        );
+        assert!(service.pending().await.is_err());
+        assert!(service.decrypt("hunter3").await.is_err());
+        assert!(service.is_encrypted());
+    }
+
+    #[tokio::test]
+    async fn test_locked_store_fails_closed() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = ConversationId::generate();
+        let agent = AgentId::new("developer");
+        let encrypted = service(&dir);
+        encrypted.encrypt("hunter2").await.unwrap();
+        encrypted.append(&id, &agent, begin()).await.unwrap();
+        let before = raw(&dir, &id);
+        let service = service(&dir);
+
+        let actual = [
+            service
+                .append(&id, &agent, user("fn secret() {}"))
+                .await
+                .is_err(),
+            service.pending().await.is_err(),
+        ];
+
+        assert_eq!(actual, [true, true]);
+        assert_eq!(raw(&dir, &id), before);
+    }
+
+    #[tokio::test]
+    async fn test_listing_does_not_need_the_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = ConversationId::generate();
+        let agent = AgentId::new("developer");
+        let encrypted = service(&dir);
+        encrypted.encrypt("hunter2").await.unwrap();
+        encrypted.append(&id, &agent, begin()).await.unwrap();
+
+        let actual = service(&dir)
+            .files()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|file| (file.conversation_id, file.agent))
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, vec![(id, agent)]);
+    }
+}
@@ -1,18 +1,34 @@
 mod app;
+mod approval;
 mod attachment;
+mod cipher;
 mod conversation;
+mod history_import;
+mod indexer;
+mod interaction;
+mod journal;
+mod protection;
 mod provider;
+mod scratch;
+mod secrets;
 mod template;
 mod tool_service;
 mod tools;
+mod turn_lock;
 
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 pub use app::*;
 use bytes::Bytes;
-use forge_domain::{Point, Query, Suggestion};
-use forge_snaps::{SnapshotInfo, SnapshotMetadata};
+use forge_domain::{DocumentChunk, Point, PointId, Query, Suggestion};
+pub use forge_open_router::http_client;
+use forge_snaps::{SnapshotInfo, SnapshotMetadata, SnapshotSelector};
+pub use history_import::*;
+pub use indexer::*;
+pub use secrets::*;
+pub use tools::stop_running_commands;
+pub use turn_lock::TurnLock;
 
 /// Repository for accessing system environment information
 #[async_trait::async_trait]
@@ -33,6 +49,18 @@ pub trait EnvironmentService {
 pub trait FsReadService: Send + Sync {
     /// Reads the content of a file at the specified path.
     async fn read(&self, path: &Path) -> anyhow::Result<Bytes>;
+
+    /// Reads the file like [`FsReadService::read`], calling `progress` with the
+    /// bytes read so far and the size of the file as it goes.
+    async fn read_with_progress(
+        &self,
+        path: &Path,
+        progress: &(dyn Fn(u64, u64) + Send + Sync),
+    ) -> anyhow::Result<Bytes> {
+        let content = self.read(path).await?;
+        progress(content.len() as u64, content.len() as u64);
+        Ok(content)
+    }
 }
 
 #[async_trait::async_trait]
@@ -45,12 +73,34 @@ pub trait FsWriteService: Send + Sync {
 pub trait FileRemoveService: Send + Sync {
     /// Removes a file at the specified path.
     async fn remove(&self, path: &Path) -> anyhow::Result<()>;
+    /// Removes a directory. Unless `recursive` is set the directory must be
+    /// empty.
+    async fn remove_dir(&self, path: &Path, recursive: bool) -> anyhow::Result<()>;
+}
+
+#[async_trait::async_trait]
+pub trait FsMoveService: Send + Sync {
+    /// Moves a file or directory, falling back to copy and remove when the
+    /// destination is on a different device.
+    async fn rename(&self, from: &Path, to: &Path) -> anyhow::Result<()>;
+}
+
+#[async_trait::async_trait]
+pub trait FsCopyService: Send + Sync {
+    /// Copies a file, or a directory tree when `recursive` is set. Existing
+    /// files at the destination are overwritten.
+    async fn copy(&self, from: &Path, to: &Path, recursive: bool) -> anyhow::Result<()>;
 }
 
 #[async_trait::async_trait]
 pub trait VectorIndex<T>: Send + Sync {
     async fn store(&self, point: Point<T>) -> anyhow::Result<()>;
     async fn search(&self, query: Query) -> anyhow::Result<Vec<Point<T>>>;
+    /// Removes the points with the given ids, ids that aren't stored are
+    /// ignored
+    async fn delete(&self, ids: Vec<PointId>) -> anyhow::Result<()>;
+    /// Removes every point of the index
+    async fn clear(&self) -> anyhow::Result<()>;
 }
 
 #[async_trait::async_trait]
@@ -90,6 +140,13 @@ pub trait FsSnapshotService: Send + Sync {
     // Convenient method to restore previous version
     async fn restore_previous(&self, file_path: &Path) -> Result<()>;
 
+    // Restores the snapshot picked by the selector
+    async fn restore_snapshot(&self, file_path: &Path, selector: &SnapshotSelector) -> Result<()>;
+
+    // Content of the snapshot picked by the selector, the file is untouched
+    async fn read_snapshot(&self, file_path: &Path, selector: &SnapshotSelector)
+        -> Result<Vec<u8>>;
+
     // Metadata access
     async fn get_snapshot_by_timestamp(
         &self,
@@ -104,6 +161,9 @@ pub trait FsSnapshotService: Send + Sync {
 
     // Global purge operation
     async fn purge_older_than(&self, days: u32) -> Result<usize>;
+
+    // Moves the snapshots of a renamed file or directory to its new path
+    async fn rename_snapshots(&self, from: &Path, to: &Path) -> Result<usize>;
 }
 
 pub trait Infrastructure: Send + Sync + 'static {
@@ -112,9 +172,12 @@ pub trait Infrastructure: Send + Sync + 'static {
     type FsMetaService: FsMetaService;
     type FsReadService: FsReadService;
     type FsRemoveService: FileRemoveService;
+    type FsMoveService: FsMoveService;
+    type FsCopyService: FsCopyService;
     type FsSnapshotService: FsSnapshotService;
     type FsWriteService: FsWriteService;
     type VectorIndex: VectorIndex<Suggestion>;
+    type DocumentIndex: VectorIndex<DocumentChunk>;
     type FsCreateDirsService: FsCreateDirsService;
 
     fn embedding_service(&self) -> &Self::EmbeddingService;
@@ -122,8 +185,11 @@ pub trait Infrastructure: Send + Sync + 'static {
     fn file_meta_service(&self) -> &Self::FsMetaService;
     fn file_read_service(&self) -> &Self::FsReadService;
     fn file_remove_service(&self) -> &Self::FsRemoveService;
+    fn file_move_service(&self) -> &Self::FsMoveService;
+    fn file_copy_service(&self) -> &Self::FsCopyService;
     fn file_snapshot_service(&self) -> &Self::FsSnapshotService;
     fn file_write_service(&self) -> &Self::FsWriteService;
     fn vector_index(&self) -> &Self::VectorIndex;
+    fn document_index(&self) -> &Self::DocumentIndex;
     fn create_dirs_service(&self) -> &Self::FsCreateDirsService;
 }
@@ -0,0 +1,531 @@
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+
+use anyhow::{bail, Context, Result};
+use forge_domain::{Environment, Error, ProtectionService, ToolCallFull};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use serde_json::Value;
+
+use crate::tools::{files_to_replace, FSReplaceInput, LintInput, RunTestsInput};
+
+/// Name of the files that list protected paths, one gitignore-style pattern
+/// per line. It is read from the global config directory first and then from
+/// the project root, so that the project can whitelist paths with `!pattern`.
+pub const PROTECT_FILE: &str = ".forgeprotect";
+
+/// Commands whose arguments are all paths they modify
+const MUTATING_COMMANDS: &[&str] = &["rm", "rmdir", "unlink", "mv", "tee", "truncate", "shred"];
+
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: String,
+    origin: PathBuf,
+}
+
+struct State {
+    unprotected: HashSet<String>,
+    matcher: Gitignore,
+}
+
+/// Keeps tools from modifying paths matched by the rules in the
+/// [`PROTECT_FILE`]s. Rules are matched relative to the project root and can
+/// be lifted with [`ProtectionService::unprotect`] until the process exits.
+pub struct ForgeProtectionService {
+    root: PathBuf,
+    rules: Vec<Rule>,
+    state: RwLock<State>,
+}
+
+impl ForgeProtectionService {
+    pub fn new(root: PathBuf, files: &[PathBuf]) -> Self {
+        let root = canonicalize(&root);
+        let rules = files
+            .iter()
+            .filter_map(|file| Some((file, std::fs::read_to_string(file).ok()?)))
+            .flat_map(|(file, content)| {
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(|line| Rule { pattern: line.to_string(), origin: file.clone() })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let matcher = build(&root, &rules, &HashSet::new());
+        Self {
+            root,
+            rules,
+            state: RwLock::new(State { unprotected: HashSet::new(), matcher }),
+        }
+    }
+
+    /// Loads the global rules from the config directory and the project's
+    /// rules from the working directory
+    pub fn load(env: &Environment) -> Self {
+        Self::new(
+            env.cwd.clone(),
+            &[env.base_path.join(PROTECT_FILE), env.cwd.join(PROTECT_FILE)],
+        )
+    }
+
+    /// Fails if the tool call would modify a protected path. Paths are taken
+    /// from the arguments of the tools that write, move or remove files, from
+    /// the files a replacement rewrites, and from redirections and file
+    /// removing commands in shell commands.
+    pub async fn check_call(&self, call: &ToolCallFull) -> Result<()> {
+        if self.rules.is_empty() {
+            return Ok(());
+        }
+
+        let targets = match call.name.as_str() {
+            "tool_forge_fs_replace" => replace_targets(call).await?,
+            _ => targets(call),
+        };
+        for path in targets {
+            self.check(&path)?;
+        }
+        Ok(())
+    }
+
+    fn check_entry(&self, state: &State, path: &Path) -> Result<()> {
+        let is_dir = path.is_dir();
+        // The project root itself is only protected through its content
+        let relative = path
+            .strip_prefix(&self.root)
+            .ok()
+            .filter(|relative| !relative.as_os_str().is_empty());
+        if let Some(relative) = relative {
+            if let Match::Ignore(glob) = state.matcher.matched_path_or_any_parents(relative, is_dir)
+            {
+                return Err(Error::ProtectedPath(
+                    path.to_path_buf(),
+                    glob.original().to_string(),
+                    glob.from().map(Path::to_path_buf).unwrap_or_default(),
+                )
+                .into());
+            }
+        }
+
+        // Removing, moving or rewriting a directory touches everything in it
+        if is_dir && !path.is_symlink() {
+            for entry in std::fs::read_dir(path)? {
+                self.check_entry(state, &entry?.path())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn build(root: &Path, rules: &[Rule], unprotected: &HashSet<String>) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for rule in rules
+        .iter()
+        .filter(|rule| !unprotected.contains(&rule.pattern))
+    {
+        if let Err(error) = builder.add_line(Some(rule.origin.clone()), &rule.pattern) {
+            tracing::warn!(%error, pattern = %rule.pattern, "Skipping invalid protection rule");
+        }
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+/// Resolves symlinks and `.`/`..` components of the part of the path that
+/// exists and normalizes the rest lexically, so that a protected path can't be
+/// reached through another name for it.
+fn canonicalize(path: &Path) -> PathBuf {
+    let mut existing = path.to_path_buf();
+    let mut rest: Vec<OsString> = Vec::new();
+    while !existing.exists() {
+        match existing.components().next_back() {
+            Some(component) => {
+                rest.push(component.as_os_str().to_owned());
+                existing.pop();
+            }
+            None => break,
+        }
+    }
+
+    let mut canonical = existing.canonicalize().unwrap_or(existing);
+    for component in rest.iter().rev() {
+        match Path::new(component).components().next() {
+            Some(Component::ParentDir) => {
+                canonical.pop();
+            }
+            Some(Component::CurDir) | None => {}
+            Some(_) => canonical.push(component),
+        }
+    }
+    canonical
+}
+
+/// Paths that a tool call would modify
+fn targets(call: &ToolCallFull) -> Vec<PathBuf> {
+    let str_arg = |key: &str| call.arguments.get(key).and_then(Value::as_str);
+    let arg = |key: &str| str_arg(key).map(PathBuf::from);
+    match call.name.as_str() {
+        "tool_forge_fs_create" | "tool_forge_fs_remove" | "tool_forge_fs_patch" => {
+            arg("path").into_iter().collect()
+        }
+        "tool_forge_fs_move" => arg("source")
+            .into_iter()
+            .chain(arg("destination"))
+            .collect(),
+        "tool_forge_fs_copy" => arg("destination").into_iter().collect(),
+        "tool_forge_process_shell" => match (str_arg("command"), arg("cwd")) {
+            (Some(command), Some(cwd)) => shell_targets(command, &cwd),
+            _ => Vec::new(),
+        },
+        "tool_forge_process_test" => {
+            serde_json::from_value::<RunTestsInput>(call.arguments.clone())
+                .map(|input| input.written_paths())
+                .unwrap_or_default()
+        }
+        "tool_forge_process_lint" => serde_json::from_value::<LintInput>(call.arguments.clone())
+            .map(|input| input.written_paths())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Files that a replacement would rewrite, none for a dry run. Fails when
+/// the files can't be found, since the call can't be checked then.
+async fn replace_targets(call: &ToolCallFull) -> Result<Vec<PathBuf>> {
+    match serde_json::from_value::<FSReplaceInput>(call.arguments.clone()) {
+        Ok(input) if !input.dry_run => files_to_replace(&input)
+            .await
+            .context("Failed to find the files the replacement would rewrite"),
+        // Arguments the tool can't parse either don't modify anything
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Best effort detection of the files a shell command writes to or removes:
+/// the targets of output redirections and the arguments of commands such as
+/// `rm` and `mv`. Anything more involved, eg. paths built from variables,
+/// isn't detected.
+fn shell_targets(command: &str, cwd: &Path) -> Vec<PathBuf> {
+    let mut targets = Vec::new();
+    for segment in command.split(['\n', ';', '|', '&']) {
+        let words = segment
+            .split_whitespace()
+            .map(|word| word.trim_matches(|c| c == '"' || c == '\''))
+            .collect::<Vec<_>>();
+
+        let mut words_iter = words.iter();
+        while let Some(word) = words_iter.next() {
+            let redirect = word.trim_start_matches(|c: char| c.is_ascii_digit());
+            if let Some(target) = redirect.strip_prefix('>') {
+                let target = target.trim_start_matches('>');
+                match target {
+                    "" => targets.extend(words_iter.next().map(|word| word.to_string())),
+                    target => targets.push(target.to_string()),
+                }
+            }
+        }
+
+        let program = words
+            .iter()
+            .skip_while(|word| word.contains('=') || **word == "sudo")
+            .collect::<Vec<_>>();
+        if let Some((name, args)) = program.split_first() {
+            let name = name.rsplit('/').next().unwrap_or(name);
+            if MUTATING_COMMANDS.contains(&name) {
+                targets.extend(
+                    args.iter()
+                        .take_while(|arg| !arg.starts_with('>'))
+                        .filter(|arg| !arg.starts_with('-'))
+                        .map(|arg| arg.to_string()),
+                );
+            }
+        }
+    }
+
+    targets
+        .into_iter()
+        .filter(|target| !target.is_empty() && target != "/dev/null" && !target.starts_with('&'))
+        .map(|target| cwd.join(target))
+        .collect()
+}
+
+impl ProtectionService for ForgeProtectionService {
+    fn check(&self, path: &Path) -> Result<()> {
+        let state = self
+            .state
+            .read()
+            .map_err(|_| anyhow::anyhow!("Protection rules are poisoned"))?;
+        self.check_entry(&state, &canonicalize(path))
+    }
+
+    fn unprotect(&self, pattern: &str) -> Result<()> {
+        let pattern = pattern.trim();
+        if !self.rules.iter().any(|rule| rule.pattern == pattern) {
+            bail!("No protection rule matches '{}'", pattern);
+        }
+
+        let mut state = self
+            .state
+            .write()
+            .map_err(|_| anyhow::anyhow!("Protection rules are poisoned"))?;
+        state.unprotected.insert(pattern.to_string());
+        state.matcher = build(&self.root, &self.rules, &state.unprotected);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_domain::ToolName;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn fixture(rules: &str) -> (tempfile::TempDir, ForgeProtectionService) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(PROTECT_FILE), rules).unwrap();
+        let service =
+            ForgeProtectionService::new(dir.path().to_path_buf(), &[dir.path().join(PROTECT_FILE)]);
+        (dir, service)
+    }
+
+    /// The rule that blocked the call, if any
+    fn rule_of(result: Result<()>) -> Option<String> {
+        match result.err()?.downcast::<Error>().ok()? {
+            Error::ProtectedPath(_, rule, _) => Some(rule),
+            _ => None,
+        }
+    }
+
+    fn call(name: &str, arguments: Value) -> ToolCallFull {
+        ToolCallFull::new(ToolName::new(name)).arguments(arguments)
+    }
+
+    #[test]
+    fn test_nested_paths_are_protected() {
+        let (dir, service) = fixture("# secrets\n.env\nmigrations/\n");
+        std::fs::create_dir_all(dir.path().join("db/migrations")).unwrap();
+
+        let actual = (
+            rule_of(service.check(&dir.path().join("app/.env"))),
+            rule_of(service.check(&dir.path().join("db/migrations/001.sql"))),
+            rule_of(service.check(&dir.path().join("src/main.rs"))),
+        );
+
+        let expected = (
+            Some(".env".to_string()),
+            Some("migrations/".to_string()),
+            None,
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_directory_containing_protected_path_is_protected() {
+        let (dir, service) = fixture(".env\n");
+        std::fs::create_dir_all(dir.path().join("config")).unwrap();
+        std::fs::write(dir.path().join("config/.env"), "KEY=1").unwrap();
+
+        let actual = rule_of(service.check(&dir.path().join("config")));
+
+        assert_eq!(actual, Some(".env".to_string()));
+    }
+
+    #[test]
+    fn test_unprotect_lifts_only_that_rule() {
+        let (dir, service) = fixture("*.lock\nCargo.lock\n.env\n");
+        let path = dir.path().join("Cargo.lock");
+
+        service.unprotect("Cargo.lock").unwrap();
+        let after_one = rule_of(service.check(&path));
+        service.unprotect("*.lock").unwrap();
+        let after_both = rule_of(service.check(&path));
+        let env = rule_of(service.check(&dir.path().join(".env")));
+
+        assert_eq!(after_one, Some("*.lock".to_string()));
+        assert_eq!(after_both, None);
+        assert_eq!(env, Some(".env".to_string()));
+    }
+
+    #[test]
+    fn test_unprotect_is_scoped_to_the_session() {
+        let (dir, service) = fixture(".env\n");
+        service.unprotect(".env").unwrap();
+
+        let next_session =
+            ForgeProtectionService::new(dir.path().to_path_buf(), &[dir.path().join(PROTECT_FILE)]);
+        let actual = rule_of(next_session.check(&dir.path().join(".env")));
+
+        assert_eq!(actual, Some(".env".to_string()));
+    }
+
+    #[test]
+    fn test_unprotect_unknown_pattern_fails() {
+        let (_dir, service) = fixture(".env\n");
+
+        assert!(service.unprotect("*.env").is_err());
+    }
+
+    #[test]
+    fn test_parent_components_dont_bypass_rules() {
+        let (dir, service) = fixture("/secrets/\n");
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+
+        let actual = rule_of(service.check(&dir.path().join("src/../secrets/key")));
+
+        assert_eq!(actual, Some("/secrets/".to_string()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_dont_bypass_rules() {
+        let (dir, service) = fixture(".env\n");
+        std::fs::write(dir.path().join(".env"), "KEY=1").unwrap();
+        std::os::unix::fs::symlink(dir.path().join(".env"), dir.path().join("settings")).unwrap();
+
+        let actual = rule_of(service.check(&dir.path().join("settings")));
+
+        assert_eq!(actual, Some(".env".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_reads_are_not_checked() {
+        let (dir, service) = fixture(".env\n");
+        let path = dir.path().join(".env").to_string_lossy().to_string();
+
+        let read = service
+            .check_call(&call("tool_forge_fs_read", json!({"path": path})))
+            .await;
+        let write = service
+            .check_call(&call(
+                "tool_forge_fs_create",
+                json!({"path": path, "content": ""}),
+            ))
+            .await;
+
+        assert!(read.is_ok());
+        assert_eq!(rule_of(write), Some(".env".to_string()));
+    }
+
+    #[test]
+    fn test_shell_targets() {
+        let cwd = Path::new("/project");
+
+        let actual = shell_targets(
+            "cargo build 2>&1 > build.log; sudo rm -rf target .env && echo hi >>notes.txt | tee out.txt",
+            cwd,
+        );
+
+        let expected = vec![
+            cwd.join("build.log"),
+            cwd.join("target"),
+            cwd.join(".env"),
+            cwd.join("notes.txt"),
+            cwd.join("out.txt"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_shell_command_writing_protected_path() {
+        let (dir, service) = fixture(".env\n");
+        let cwd = dir.path().to_string_lossy().to_string();
+
+        let actual = service
+            .check_call(&call(
+                "tool_forge_process_shell",
+                json!({"command": "echo KEY=1 > .env", "cwd": cwd}),
+            ))
+            .await;
+
+        assert_eq!(rule_of(actual), Some(".env".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_tests_updating_protected_lock_file() {
+        let (dir, service) = fixture("Cargo.lock\n");
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        let path = dir.path().to_string_lossy().to_string();
+
+        let actual = service
+            .check_call(&call("tool_forge_process_test", json!({"path": path})))
+            .await;
+
+        assert_eq!(rule_of(actual), Some("Cargo.lock".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_lint_fix_in_project_with_protected_path() {
+        let (dir, service) = fixture(".env\n");
+        std::fs::write(dir.path().join(".env"), "KEY=1").unwrap();
+        let path = dir.path().to_string_lossy().to_string();
+
+        let check = service
+            .check_call(&call("tool_forge_process_lint", json!({"path": path})))
+            .await;
+        let fix = service
+            .check_call(&call(
+                "tool_forge_process_lint",
+                json!({"path": path, "args": "--fix"}),
+            ))
+            .await;
+
+        assert!(check.is_ok());
+        assert_eq!(rule_of(fix), Some(".env".to_string()));
+    }
+
+    fn replace(dir: &Path, file_pattern: &str, dry_run: bool) -> ToolCallFull {
+        call(
+            "tool_forge_fs_replace",
+            json!({
+                "path": dir.to_string_lossy(),
+                "regex": "old_name",
+                "replacement": "new_name",
+                "file_pattern": file_pattern,
+                "dry_run": dry_run,
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_replace_checks_only_the_files_it_rewrites() {
+        let (dir, service) = fixture(".git/\n");
+        std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+        std::fs::write(dir.path().join(".git/config"), "old_name").unwrap();
+        std::fs::write(dir.path().join("main.rs"), "fn old_name() {}").unwrap();
+
+        let actual = service
+            .check_call(&replace(dir.path(), "*.rs", false))
+            .await;
+
+        assert!(actual.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_replace_in_protected_file_is_refused_unless_dry_run() {
+        let (dir, service) = fixture("*.lock\n");
+        std::fs::write(dir.path().join("Cargo.lock"), "old_name").unwrap();
+
+        let write = service
+            .check_call(&replace(dir.path(), "*.lock", false))
+            .await;
+        let dry_run = service
+            .check_call(&replace(dir.path(), "*.lock", true))
+            .await;
+
+        assert_eq!(rule_of(write), Some("*.lock".to_string()));
+        assert!(dry_run.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_replace_that_cant_be_checked_is_refused() {
+        let (dir, service) = fixture("*.lock\n");
+
+        // An invalid file pattern leaves the files the call rewrites unknown
+        let actual = service.check_call(&replace(dir.path(), "[", false)).await;
+
+        assert!(actual.is_err());
+    }
+}
@@ -1,27 +1,57 @@
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use anyhow::{Context, Result};
 use forge_domain::{
-    ChatCompletionMessage, Context as ChatContext, Model, ModelId, ProviderService, ResultStream,
+    ChatCompletionMessage, Context as ChatContext, HttpTimeout, Model, ModelId, Provider,
+    ProviderService, ProxyConfig, ResultStream, TlsConfig, TransformerReport,
 };
 use forge_open_router::Client;
 
 use crate::{EnvironmentService, Infrastructure};
 
 pub struct ForgeProviderService {
-    // The provider service implementation
-    client: Client,
+    // The provider service implementation, replaced when the key changes
+    client: RwLock<Arc<Client>>,
+    provider: RwLock<Provider>,
+    proxy: ProxyConfig,
+    tls: TlsConfig,
+    timeout: HttpTimeout,
+    preferences: Option<String>,
+    transformers: Option<String>,
+    prompt_cache: bool,
 }
 
 impl ForgeProviderService {
-    pub fn new<F: Infrastructure>(infra: Arc<F>) -> Self {
+    /// Fails when the proxy, TLS or provider settings of the environment are
+    /// invalid
+    pub fn new<F: Infrastructure>(infra: Arc<F>) -> Result<Self> {
         let infra = infra.clone();
-        let provider = infra
-            .environment_service()
-            .get_environment()
-            .provider
-            .clone();
-        Self { client: Client::new(provider).unwrap() }
+        let env = infra.environment_service().get_environment();
+        let provider = env.provider.clone();
+        let client = Client::new(
+            provider.clone(),
+            &env.proxy,
+            &env.tls,
+            &env.http_timeout,
+            env.provider_preferences.as_deref(),
+            env.transformers.as_deref(),
+            env.prompt_cache,
+        )
+        .context("Failed to set up the provider's client")?;
+        Ok(Self {
+            client: RwLock::new(Arc::new(client)),
+            provider: RwLock::new(provider),
+            proxy: env.proxy,
+            tls: env.tls,
+            timeout: env.http_timeout,
+            preferences: env.provider_preferences,
+            transformers: env.transformers,
+            prompt_cache: env.prompt_cache,
+        })
+    }
+
+    fn client(&self) -> Arc<Client> {
+        self.client.read().unwrap().clone()
     }
 }
 
@@ -32,13 +62,40 @@ impl ProviderService for ForgeProviderService {
         model: &ModelId,
         request: ChatContext,
     ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
-        self.client
+        self.client()
             .chat(model, request)
             .await
             .with_context(|| format!("Failed to chat with model: {}", model))
     }
 
     async fn models(&self) -> Result<Vec<Model>> {
-        self.client.models().await
+        self.client().models().await
+    }
+
+    fn transformers(&self) -> Option<TransformerReport> {
+        self.client().transformers()
+    }
+
+    async fn set_key(&self, key: String) -> Result<()> {
+        let provider = self.provider.read().unwrap().with_key(key);
+        let client = Client::new(
+            provider.clone(),
+            &self.proxy,
+            &self.tls,
+            &self.timeout,
+            self.preferences.as_deref(),
+            self.transformers.as_deref(),
+            self.prompt_cache,
+        )?;
+
+        // Listing the models is the cheapest request that needs a valid key
+        client
+            .models()
+            .await
+            .context("The provider rejected the new API key")?;
+
+        *self.client.write().unwrap() = Arc::new(client);
+        *self.provider.write().unwrap() = provider;
+        Ok(())
     }
 }
@@ -0,0 +1,189 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context as _, Result};
+use forge_domain::{ConversationId, ScratchService};
+
+/// Whitelists everything in a scratch directory, so that files the agent put
+/// there aren't hidden by the user's global gitignore when they are listed or
+/// searched
+const IGNORE_FILE: (&str, &str) = (".ignore", "!*\n");
+
+/// Keeps the scratch directory of each conversation at
+/// `<dir>/<conversation id>`.
+pub struct ForgeScratchService {
+    dir: PathBuf,
+}
+
+impl ForgeScratchService {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+/// The most recent modification time of the directory or anything in it
+fn last_modified(path: &Path) -> std::io::Result<SystemTime> {
+    let metadata = path.symlink_metadata()?;
+    let mut modified = metadata.modified()?;
+    if metadata.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            modified = modified.max(last_modified(&entry?.path())?);
+        }
+    }
+    Ok(modified)
+}
+
+#[async_trait::async_trait]
+impl ScratchService for ForgeScratchService {
+    fn path(&self, id: &ConversationId) -> PathBuf {
+        self.dir.join(id.into_string())
+    }
+
+    async fn create(&self, id: &ConversationId) -> Result<PathBuf> {
+        let path = self.path(id);
+        if !path.exists() {
+            tokio::fs::create_dir_all(&path).await.with_context(|| {
+                format!("Failed to create scratch directory {}", path.display())
+            })?;
+            let (name, content) = IGNORE_FILE;
+            tokio::fs::write(path.join(name), content).await?;
+        }
+        Ok(path)
+    }
+
+    async fn write(&self, id: &ConversationId, relative: &Path, content: &str) -> Result<PathBuf> {
+        let path = self.create(id).await?.join(relative);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, content)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    async fn remove(&self, id: &ConversationId) -> Result<bool> {
+        let path = self.path(id);
+        if !path.exists() {
+            return Ok(false);
+        }
+        tokio::fs::remove_dir_all(&path)
+            .await
+            .with_context(|| format!("Failed to remove scratch directory {}", path.display()))?;
+        Ok(true)
+    }
+
+    async fn purge(&self, max_age: Duration) -> Result<usize> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+
+        let cutoff = SystemTime::now()
+            .checked_sub(max_age)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut removed = 0;
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if !path.is_dir() || last_modified(&path)? >= cutoff {
+                continue;
+            }
+            tokio::fs::remove_dir_all(&path).await.with_context(|| {
+                format!("Failed to remove scratch directory {}", path.display())
+            })?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn set_modified(path: &Path, modified: SystemTime) {
+        std::fs::File::open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_created_on_first_use() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = ForgeScratchService::new(dir.path().join("scratch"));
+        let id = ConversationId::generate();
+
+        let path = service.path(&id);
+        assert!(!path.exists());
+
+        let actual = service.create(&id).await.unwrap();
+        assert_eq!(actual, path);
+        assert!(path.is_dir());
+
+        // Creating it again keeps what's in it
+        tokio::fs::write(path.join("run.sh"), "echo").await.unwrap();
+        service.create(&id).await.unwrap();
+        assert!(path.join("run.sh").exists());
+    }
+
+    #[tokio::test]
+    async fn test_write_creates_the_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = ForgeScratchService::new(dir.path().to_path_buf());
+        let id = ConversationId::generate();
+
+        let actual = service
+            .write(&id, Path::new("tool-outputs/call_1.txt"), "output")
+            .await
+            .unwrap();
+
+        assert_eq!(actual, service.path(&id).join("tool-outputs/call_1.txt"));
+        assert_eq!(std::fs::read_to_string(&actual).unwrap(), "output");
+        assert!(service.path(&id).join(".ignore").exists());
+    }
+
+    #[tokio::test]
+    async fn test_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = ForgeScratchService::new(dir.path().to_path_buf());
+        let id = ConversationId::generate();
+        let path = service.create(&id).await.unwrap();
+        tokio::fs::write(path.join("run.sh"), "echo").await.unwrap();
+
+        assert!(service.remove(&id).await.unwrap());
+        assert!(!path.exists());
+        assert!(!service.remove(&id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_purge_removes_abandoned_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = ForgeScratchService::new(dir.path().to_path_buf());
+        let abandoned = service.create(&ConversationId::generate()).await.unwrap();
+        let active = service.create(&ConversationId::generate()).await.unwrap();
+
+        let old = SystemTime::now() - Duration::from_secs(3 * 24 * 60 * 60);
+        for path in [&abandoned, &active] {
+            tokio::fs::write(path.join("run.sh"), "echo").await.unwrap();
+            set_modified(&path.join("run.sh"), old);
+            set_modified(&path.join(".ignore"), old);
+            set_modified(path, old);
+        }
+        // A file that was touched recently keeps the directory around
+        tokio::fs::write(active.join("output.txt"), "done")
+            .await
+            .unwrap();
+
+        let actual = service
+            .purge(Duration::from_secs(24 * 60 * 60))
+            .await
+            .unwrap();
+
+        assert_eq!(actual, 1);
+        assert!(!abandoned.exists());
+        assert!(active.exists());
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use forge_domain::{is_valid_secret_name, secret_ref, Environment, SecretInfo};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::cipher::StoreCipher;
+
+const KEY_LEN: usize = 32;
+
+#[derive(Serialize, Deserialize)]
+struct SecretEntry {
+    /// The value sealed by the store's cipher
+    value: String,
+    updated_at: DateTime<Utc>,
+}
+
+/// Named secrets kept in a file under the base path, every value encrypted
+/// with AES-256-GCM. The key is generated on the first secret and kept in a
+/// file of its own that only the user can read, so the secrets don't leak
+/// with a copy of the secrets file alone.
+pub struct SecretStore {
+    path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl SecretStore {
+    pub fn new(path: PathBuf, key_path: PathBuf) -> Self {
+        Self { path, key_path }
+    }
+
+    pub fn from_env(env: &Environment) -> Self {
+        Self::new(env.secrets_path(), env.secrets_key_path())
+    }
+
+    /// Stores the secret, replacing its value when it exists
+    pub fn set(&self, name: &str, value: &str) -> Result<()> {
+        if !is_valid_secret_name(name) {
+            bail!("Invalid secret name '{name}', use letters, digits and underscores");
+        }
+        if value.is_empty() {
+            bail!("The value of secret '{name}' is empty");
+        }
+
+        let cipher = self.cipher(true)?;
+        let mut entries = self.load()?;
+        entries.insert(
+            name.to_string(),
+            SecretEntry { value: cipher.seal_line(value)?, updated_at: Utc::now() },
+        );
+        self.save(&entries)
+    }
+
+    pub fn get(&self, name: &str) -> Result<String> {
+        let entries = self.load()?;
+        let Some(entry) = entries.get(name) else {
+            bail!("Unknown secret '{name}', add it with `forge secret set {name}`");
+        };
+        self.cipher(false)?
+            .open_line(&entry.value)
+            .with_context(|| format!("Failed to decrypt secret '{name}'"))
+    }
+
+    /// The names of the stored secrets and when they were set, by name
+    pub fn list(&self) -> Result<Vec<SecretInfo>> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .map(|(name, entry)| SecretInfo { name, updated_at: entry.updated_at })
+            .collect())
+    }
+
+    /// Returns whether the secret existed
+    pub fn remove(&self, name: &str) -> Result<bool> {
+        let mut entries = self.load()?;
+        let removed = entries.remove(name).is_some();
+        if removed {
+            self.save(&entries)?;
+        }
+        Ok(removed)
+    }
+
+    /// Replaces the values of the variables that refer to a secret with the
+    /// secret. Returns the variables along with the secrets used, by name.
+    pub fn resolve(
+        &self,
+        vars: HashMap<String, String>,
+    ) -> Result<(HashMap<String, String>, Vec<(String, String)>)> {
+        let mut secrets = Vec::new();
+        let mut resolved = HashMap::with_capacity(vars.len());
+        for (key, value) in vars {
+            let value = match secret_ref(&value) {
+                Some(name) => {
+                    let secret = self.get(name)?;
+                    secrets.push((name.to_string(), secret.clone()));
+                    secret
+                }
+                None => value,
+            };
+            resolved.insert(key, value);
+        }
+        Ok((resolved, secrets))
+    }
+
+    fn cipher(&self, create: bool) -> Result<StoreCipher> {
+        let key = match std::fs::read_to_string(&self.key_path) {
+            Ok(key) => STANDARD
+                .decode(key.trim())
+                .context("Secrets key isn't base64")?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && create => {
+                let mut key = vec![0u8; KEY_LEN];
+                SystemRandom::new()
+                    .fill(&mut key)
+                    .map_err(|_| anyhow::anyhow!("Failed to generate the secrets key"))?;
+                write_private(&self.key_path, &STANDARD.encode(&key))?;
+                key
+            }
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Failed to read secrets key {}", self.key_path.display())
+                })
+            }
+        };
+        StoreCipher::from_key(&key)
+    }
+
+    fn load(&self) -> Result<BTreeMap<String, SecretEntry>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse secrets {}", self.path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(err) => {
+                Err(err).with_context(|| format!("Failed to read secrets {}", self.path.display()))
+            }
+        }
+    }
+
+    fn save(&self, entries: &BTreeMap<String, SecretEntry>) -> Result<()> {
+        write_private(&self.path, &serde_json::to_string_pretty(entries)?)
+    }
+}
+
+/// Writes a file only the user may read. The content goes to a temporary
+/// file that is created with those permissions and then replaces the file,
+/// so it's never readable by others nor left half written.
+pub fn write_private(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    // A temporary file left behind by an earlier failure keeps its own
+    // permissions, it's created afresh
+    let _ = std::fs::remove_file(&temp);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&temp)
+        .with_context(|| format!("Failed to create {}", temp.display()))?;
+    file.write_all(content.as_bytes())
+        .and_then(|_| file.sync_all())
+        .with_context(|| format!("Failed to write {}", temp.display()))?;
+
+    std::fs::rename(&temp, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn fixture(dir: &tempfile::TempDir) -> SecretStore {
+        SecretStore::new(
+            dir.path().join("secrets.json"),
+            dir.path().join("secrets.key"),
+        )
+    }
+
+    #[test]
+    fn test_set_get_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        fixture(&dir).set("NPM_TOKEN", "npm_abc123").unwrap();
+
+        let actual = fixture(&dir).get("NPM_TOKEN").unwrap();
+
+        assert_eq!(actual, "npm_abc123");
+        let stored = std::fs::read_to_string(dir.path().join("secrets.json")).unwrap();
+        assert!(stored.contains("NPM_TOKEN"));
+        assert!(!stored.contains("npm_abc123"));
+    }
+
+    #[test]
+    fn test_set_replaces_the_value() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = fixture(&dir);
+        store.set("DB_URL", "postgres://old").unwrap();
+        store.set("DB_URL", "postgres://new").unwrap();
+
+        let actual = store.get("DB_URL").unwrap();
+
+        assert_eq!(actual, "postgres://new");
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        fixture(&dir).set("NPM_TOKEN", "npm_abc123").unwrap();
+        fixture(&dir).set("NPM_TOKEN", "npm_def456").unwrap();
+
+        let mode = |name: &str| {
+            let metadata = std::fs::metadata(dir.path().join(name)).unwrap();
+            metadata.permissions().mode() & 0o777
+        };
+        let actual = (mode("secrets.json"), mode("secrets.key"));
+
+        assert_eq!(actual, (0o600, 0o600));
+        assert!(!dir.path().join("secrets.json.tmp").exists());
+    }
+
+    #[test]
+    fn test_unknown_secret() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let actual = fixture(&dir).get("NPM_TOKEN").unwrap_err().to_string();
+
+        assert_eq!(
+            actual,
+            "Unknown secret 'NPM_TOKEN', add it with `forge secret set NPM_TOKEN`"
+        );
+    }
+
+    #[test]
+    fn test_list_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = fixture(&dir);
+        store.set("NPM_TOKEN", "npm_abc123").unwrap();
+        store.set("DB_URL", "postgres://localhost").unwrap();
+
+        assert!(store.remove("NPM_TOKEN").unwrap());
+        assert!(!store.remove("NPM_TOKEN").unwrap());
+        let actual = store
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|secret| secret.name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, vec!["DB_URL".to_string()]);
+    }
+
+    #[test]
+    fn test_invalid_name_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let actual = fixture(&dir).set("DB-URL", "postgres://localhost");
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = fixture(&dir);
+        store.set("NPM_TOKEN", "npm_abc123").unwrap();
+        let vars = HashMap::from([
+            (
+                "NODE_AUTH_TOKEN".to_string(),
+                "secret:NPM_TOKEN".to_string(),
+            ),
+            ("CI".to_string(), "true".to_string()),
+        ]);
+
+        let (vars, secrets) = store.resolve(vars).unwrap();
+
+        assert_eq!(vars["NODE_AUTH_TOKEN"], "npm_abc123");
+        assert_eq!(vars["CI"], "true");
+        assert_eq!(
+            secrets,
+            vec![("NPM_TOKEN".to_string(), "npm_abc123".to_string())]
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
 use forge_domain::{
     Agent, Event, EventContext, Query, SystemContext, Template, TemplateService, ToolService,
+    SCRATCH_DIR_VARIABLE,
 };
 use forge_walker::Walker;
 use handlebars::Handlebars;
@@ -27,15 +29,18 @@ pub struct ForgeTemplateService<F, T> {
 
 impl<F, T> ForgeTemplateService<F, T> {
     pub fn new(infra: Arc<F>, tool_service: Arc<T>) -> Self {
-        let mut hb = Handlebars::new();
-        hb.set_strict_mode(true);
-        hb.register_escape_fn(|str| str.to_string());
+        Self { hb: registry(), infra, tool_service }
+    }
+}
 
-        // Register all partial templates
-        hb.register_embed_templates::<Templates>().unwrap();
+fn registry() -> Handlebars<'static> {
+    let mut hb = Handlebars::new();
+    hb.set_strict_mode(true);
+    hb.register_escape_fn(|str| str.to_string());
 
-        Self { hb, infra, tool_service }
-    }
+    // Register all partial templates
+    hb.register_embed_templates::<Templates>().unwrap();
+    hb
 }
 
 #[async_trait::async_trait]
@@ -44,8 +49,11 @@ impl<F: Infrastructure, T: ToolService> TemplateService for ForgeTemplateService
         &self,
         agent: &Agent,
         prompt: &Template<SystemContext>,
+        scratch_dir: &Path,
+        variables: &HashMap<String, Value>,
     ) -> anyhow::Result<String> {
         let env = self.infra.environment_service().get_environment();
+        let scratch_dir = scratch_dir.display().to_string();
 
         // Build the walker, only setting max_depth if a value was provided
         let mut walker = Walker::max_all();
@@ -67,11 +75,17 @@ impl<F: Infrastructure, T: ToolService> TemplateService for ForgeTemplateService
         // Create the context with README content for all agents
         let ctx = SystemContext {
             env: Some(env),
-            tool_information: Some(self.tool_service.usage_prompt()),
+            tool_information: Some(
+                self.tool_service
+                    .usage_prompt()
+                    .replace(SCRATCH_DIR_VARIABLE, &scratch_dir),
+            ),
             tool_supported: agent.tool_supported,
             files,
             readme: README_CONTENT.to_string(),
             project_rules: agent.project_rules.clone(),
+            scratch_dir: Some(scratch_dir),
+            variables: variables.clone(),
         };
 
         // Render the template with the context
@@ -123,3 +137,37 @@ impl<F: Infrastructure, T: ToolService> TemplateService for ForgeTemplateService
             .render_template(prompt.template.as_str(), &event_context)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(variables: Value) -> String {
+        let variables = serde_json::from_value(variables).unwrap();
+        let ctx = SystemContext::default().variables(variables);
+        registry()
+            .render_template("{{> partial-user-preferences.hbs }}", &ctx)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_configured_variables_are_rendered() {
+        let actual = render(json!({
+            "user_name": "Ada",
+            "project_guidelines": "Prefer small pull requests.",
+        }));
+
+        assert!(actual.contains("You are working with Ada."));
+        assert!(actual.contains("Prefer small pull requests."));
+    }
+
+    #[test]
+    fn test_missing_variables_render_empty() {
+        let actual = render(json!({}));
+
+        assert_eq!(actual.trim(), "");
+    }
+}
@@ -1,22 +1,44 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use forge_display::PathFormat;
 use forge_domain::{Tool, ToolCallFull, ToolDefinition, ToolName, ToolResult, ToolService};
 use tokio::time::{timeout, Duration};
 use tracing::{debug, error};
 
-use crate::Infrastructure;
+use crate::protection::ForgeProtectionService;
+use crate::tools::display_paths;
+use crate::{EnvironmentService, Infrastructure};
 
 // Timeout duration for tool calls
 const TOOL_CALL_TIMEOUT: Duration = Duration::from_secs(300);
 
 pub struct ForgeToolService {
     tools: HashMap<ToolName, Tool>,
+    /// Makes the paths in successful tool results relative, for turns whose
+    /// tool result policy asks for it
+    paths: PathFormat,
+    /// When set, calls that would modify a protected path fail without
+    /// running the tool.
+    protection: Option<Arc<ForgeProtectionService>>,
 }
 
 impl ForgeToolService {
-    pub fn new<F: Infrastructure>(infra: Arc<F>) -> Self {
+    pub fn new<F: Infrastructure>(infra: Arc<F>, protection: Arc<ForgeProtectionService>) -> Self {
+        let env = infra.environment_service().get_environment();
         ForgeToolService::from_iter(crate::tools::tools(infra.clone()))
+            .protection(protection)
+            .paths(display_paths(&env))
+    }
+
+    fn paths(mut self, paths: PathFormat) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    fn protection(mut self, protection: Arc<ForgeProtectionService>) -> Self {
+        self.protection = Some(protection);
+        self
     }
 }
 
@@ -27,7 +49,7 @@ impl FromIterator<Tool> for ForgeToolService {
             .map(|tool| (tool.definition.name.clone(), tool))
             .collect::<HashMap<_, _>>();
 
-        Self { tools }
+        Self { tools, paths: PathFormat::default(), protection: None }
     }
 }
 
@@ -44,10 +66,22 @@ impl ToolService for ForgeToolService {
             .collect::<Vec<_>>();
 
         available_tools.sort();
+        let protected = match &self.protection {
+            Some(protection) => protection.check_call(&call).await,
+            None => Ok(()),
+        };
+        // Read before the call runs, a file that doesn't exist yet is empty
+        let before = match call.written_path() {
+            Some(path) if protected.is_ok() => {
+                Some(tokio::fs::read_to_string(path).await.unwrap_or_default())
+            }
+            _ => None,
+        };
         let output = match self.tools.get(&name) {
+            Some(_) if protected.is_err() => protected,
             Some(tool) => {
                 // Wrap tool call with timeout
-                match timeout(TOOL_CALL_TIMEOUT, tool.executable.call(input)).await {
+                match timeout(TOOL_CALL_TIMEOUT, tool.executable.call_content(input)).await {
                     Ok(result) => result,
                     Err(_) => Err(anyhow::anyhow!(
                         "Tool '{}' timed out after {} minutes",
@@ -63,18 +97,26 @@ impl ToolService for ForgeToolService {
             )),
         };
 
-        let result = match output {
+        let mut result = match output {
             Ok(output) => ToolResult::from(call).success(output),
             Err(output) => {
                 error!(error = ?output, "Tool call failed");
                 ToolResult::from(call).failure(output)
             }
         };
+        result.before = before;
 
         debug!(result = ?result, "Tool call result");
         result
     }
 
+    fn relative_paths(&self, mut result: ToolResult) -> ToolResult {
+        if !result.is_error {
+            result.content = result.content.map_text(|text| self.paths.relativize(text));
+        }
+        result
+    }
+
     fn list(&self) -> Vec<ToolDefinition> {
         let mut tools: Vec<_> = self
             .tools
@@ -109,6 +151,7 @@ impl ToolService for ForgeToolService {
 mod test {
     use anyhow::bail;
     use forge_domain::{Tool, ToolCallId, ToolDefinition};
+    use pretty_assertions::assert_eq;
     use serde_json::{json, Value};
     use tokio::time;
 
@@ -143,6 +186,7 @@ mod test {
                 description: "A test tool that always succeeds".to_string(),
                 input_schema: schemars::schema_for!(serde_json::Value),
                 output_schema: Some(schemars::schema_for!(String)),
+                examples: Vec::new(),
             },
             executable: Box::new(SuccessTool),
         };
@@ -153,6 +197,7 @@ mod test {
                 description: "A test tool that always fails".to_string(),
                 input_schema: schemars::schema_for!(serde_json::Value),
                 output_schema: Some(schemars::schema_for!(String)),
+                examples: Vec::new(),
             },
             executable: Box::new(FailureTool),
         };
@@ -222,6 +267,7 @@ mod test {
                 description: "A test tool that takes too long".to_string(),
                 input_schema: schemars::schema_for!(serde_json::Value),
                 output_schema: Some(schemars::schema_for!(String)),
+                examples: Vec::new(),
             },
             executable: Box::new(SlowTool),
         };
@@ -239,11 +285,122 @@ mod test {
         let result = service.call(call).await;
 
         // Assert that the result contains a timeout error message
-        let content_str = &result.content;
+        let content_str = result.content.to_string();
         assert!(
             content_str.contains("timed out"),
             "Expected timeout error message"
         );
         assert!(result.is_error, "Expected error result for timeout");
     }
+
+    // Mock tool that overwrites the file at `path`
+    struct OverwriteTool;
+    #[async_trait::async_trait]
+    impl forge_domain::ExecutableTool for OverwriteTool {
+        type Input = Value;
+
+        async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+            let path = input["path"].as_str().unwrap_or_default();
+            tokio::fs::write(path, "after").await?;
+            Ok(format!("Wrote {path}"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_result_keeps_the_written_file_as_it_was_before() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "before").unwrap();
+        let service = ForgeToolService::from_iter(vec![Tool {
+            definition: ToolDefinition {
+                name: ToolName::new("tool_forge_fs_create"),
+                description: "A test tool that overwrites a file".to_string(),
+                input_schema: schemars::schema_for!(serde_json::Value),
+                output_schema: Some(schemars::schema_for!(String)),
+                examples: Vec::new(),
+            },
+            executable: Box::new(OverwriteTool),
+        }]);
+        let call = ToolCallFull {
+            name: ToolName::new("tool_forge_fs_create"),
+            arguments: json!({"path": path}),
+            call_id: Some(ToolCallId::new("test")),
+        };
+
+        let actual = service.call(call).await;
+
+        assert_eq!(actual.before, Some("before".to_string()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "after");
+    }
+
+    #[tokio::test]
+    async fn test_tool_result_keeps_absolute_paths_by_default() {
+        let service = new_tool_service();
+        let call = ToolCallFull {
+            name: ToolName::new("success_tool"),
+            arguments: json!("/project/src/main.rs"),
+            call_id: Some(ToolCallId::new("test")),
+        };
+
+        let actual = service.call(call).await.content.to_string();
+        let expected = "Success with input: \"/project/src/main.rs\"";
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_tool_result_with_relative_paths() {
+        let service = ForgeToolService::from_iter(vec![Tool {
+            definition: ToolDefinition {
+                name: ToolName::new("success_tool"),
+                description: "A test tool that always succeeds".to_string(),
+                input_schema: schemars::schema_for!(serde_json::Value),
+                output_schema: Some(schemars::schema_for!(String)),
+                examples: Vec::new(),
+            },
+            executable: Box::new(SuccessTool),
+        }])
+        .paths(PathFormat::new("/project"));
+        let call = ToolCallFull {
+            name: ToolName::new("success_tool"),
+            arguments: json!("/project/src/main.rs"),
+            call_id: Some(ToolCallId::new("test")),
+        };
+
+        let actual = service
+            .relative_paths(service.call(call).await)
+            .content
+            .to_string();
+        let expected = "Success with input: \"src/main.rs\"";
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_protected_path_fails_without_running_the_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules = dir.path().join(crate::protection::PROTECT_FILE);
+        std::fs::write(&rules, ".env\n").unwrap();
+        let protection = ForgeProtectionService::new(dir.path().to_path_buf(), &[rules]);
+        let service = ForgeToolService::from_iter(vec![Tool {
+            definition: ToolDefinition {
+                name: ToolName::new("tool_forge_fs_create"),
+                description: "A test tool that always succeeds".to_string(),
+                input_schema: schemars::schema_for!(serde_json::Value),
+                output_schema: Some(schemars::schema_for!(String)),
+                examples: Vec::new(),
+            },
+            executable: Box::new(SuccessTool),
+        }])
+        .protection(Arc::new(protection));
+        let call = ToolCallFull {
+            name: ToolName::new("tool_forge_fs_create"),
+            arguments: json!({"path": dir.path().join(".env"), "content": "KEY=1"}),
+            call_id: Some(ToolCallId::new("test")),
+        };
+
+        let actual = service.call(call).await;
+
+        assert!(actual.is_error);
+        assert!(actual.content.to_string().contains("/unprotect .env"));
+        assert!(!actual.content.to_string().contains("Success"));
+    }
 }
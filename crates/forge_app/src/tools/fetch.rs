@@ -1,6 +1,8 @@
 use anyhow::{anyhow, Context, Result};
 use forge_display::TitleFormat;
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription};
+use forge_domain::{
+    ExecutableTool, HttpTimeout, NamedTool, ProxyConfig, TlsConfig, ToolDescription,
+};
 use forge_tool_macros::ToolDescription;
 use reqwest::{Client, Url};
 use schemars::JsonSchema;
@@ -13,7 +15,8 @@ use serde::Deserialize;
 /// know that.
 #[derive(Debug, ToolDescription)]
 pub struct Fetch {
-    client: Client,
+    /// The error of an invalid network config, reported by every call
+    client: std::result::Result<Client, String>,
 }
 
 impl NamedTool for Fetch {
@@ -22,9 +25,21 @@ impl NamedTool for Fetch {
     }
 }
 
-impl Default for Fetch {
-    fn default() -> Self {
-        Self { client: Client::new() }
+impl Fetch {
+    /// Fetches through the configured proxies, trusting the configured
+    /// certificates and with the configured timeouts. With an invalid config
+    /// every call fails, nothing is sent around the proxies.
+    pub fn new(proxy: &ProxyConfig, tls: &TlsConfig, timeout: &HttpTimeout) -> Self {
+        Self {
+            client: forge_open_router::http_client(proxy, tls, timeout)
+                .map_err(|err| format!("{err:#}")),
+        }
+    }
+
+    fn client(&self) -> Result<&Client> {
+        self.client
+            .as_ref()
+            .map_err(|err| anyhow!("Invalid network config: {err}"))
     }
 }
 
@@ -55,7 +70,7 @@ pub struct FetchInput {
 impl Fetch {
     async fn check_robots_txt(&self, url: &Url) -> Result<()> {
         let robots_url = format!("{}://{}/robots.txt", url.scheme(), url.authority());
-        let robots_response = self.client.get(&robots_url).send().await;
+        let robots_response = self.client()?.get(&robots_url).send().await;
 
         if let Ok(robots) = robots_response {
             if robots.status().is_success() {
@@ -91,13 +106,13 @@ impl Fetch {
         self.check_robots_txt(url).await?;
 
         let response = self
-            .client
+            .client()?
             .get(url.as_str())
             .send()
             .await
             .map_err(|e| anyhow!("Failed to fetch URL {}: {}", url, e))?;
 
-        println!(
+        forge_display::echo!(
             "{}",
             TitleFormat::execute(format!("GET {}", response.status()))
                 .sub_title(url.as_str())
@@ -185,7 +200,7 @@ mod tests {
 
     async fn setup() -> (Fetch, mockito::ServerGuard) {
         let server = mockito::Server::new_async().await;
-        let fetch = Fetch { client: Client::new() };
+        let fetch = Fetch { client: Ok(Client::new()) };
         (fetch, server)
     }
 
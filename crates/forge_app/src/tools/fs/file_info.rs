@@ -1,3 +1,4 @@
+use std::fmt;
 use std::path::Path;
 
 use anyhow::Context;
@@ -5,6 +6,7 @@ use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::Deserialize;
+use tokio::io::AsyncReadExt;
 
 use crate::tools::utils::assert_absolute_path;
 
@@ -16,9 +18,12 @@ pub struct FSFileInfoInput {
 
 /// Request to retrieve detailed metadata about a file or directory at the
 /// specified path. Returns comprehensive information including size, creation
-/// time, last modified time, permissions, and type. Path must be absolute. Use
-/// this when you need to understand file characteristics without reading the
-/// actual content.
+/// time, last modified time, permissions, and type. For files it also tells
+/// whether the content is binary along with a guessed MIME type, the line
+/// count of text files and, inside a git repository, the file's status
+/// (untracked, modified, ignored or clean). Path must be absolute. Use this
+/// when you need to understand file characteristics without reading the
+/// actual content, eg. to avoid reading binary files as text.
 #[derive(ToolDescription)]
 pub struct FSFileInfo;
 
@@ -39,12 +44,192 @@ impl ExecutableTool for FSFileInfo {
         let meta = tokio::fs::metadata(&input.path)
             .await
             .with_context(|| format!("Failed to get metadata for '{}'", input.path))?;
-        Ok(format!("{:?}", meta))
+
+        let mut info = format!("{:?}", meta);
+        if meta.is_file() {
+            // The metadata is still worth returning when the content can't be
+            // read, eg. because of its permissions
+            match sample(path).await {
+                Ok(sample) => {
+                    let is_binary = is_binary(&sample);
+                    info.push_str(&format!("\nis_binary: {is_binary}"));
+                    info.push_str(&format!(
+                        "\nmime_type: {}",
+                        guess_mime(path, &sample, is_binary)
+                    ));
+
+                    if !is_binary {
+                        if let Some(line_count) = line_count(path).await {
+                            info.push_str(&format!("\nline_count: {line_count}"));
+                        }
+                    }
+                }
+                Err(err) => info.push_str(&format!("\nreadable: false ({err})")),
+            }
+            if let Some(status) = git_status(path).await {
+                info.push_str(&format!("\ngit_status: {status}"));
+            }
+        }
+        Ok(info)
+    }
+}
+
+/// Status of a file in the git repository that contains it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GitStatus {
+    Untracked,
+    Modified,
+    Ignored,
+    Clean,
+}
+
+impl fmt::Display for GitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            GitStatus::Untracked => "untracked",
+            GitStatus::Modified => "modified",
+            GitStatus::Ignored => "ignored",
+            GitStatus::Clean => "clean",
+        };
+        write!(f, "{status}")
+    }
+}
+
+/// Number of bytes looked at to tell text from binary content
+const SAMPLE_SIZE: u64 = 8192;
+
+/// File signatures of common binary formats, checked before the extension
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF8", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"\x7fELF", "application/x-executable"),
+    (b"\0asm", "application/wasm"),
+];
+
+/// MIME types by file extension
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("rs", "text/x-rust"),
+    ("py", "text/x-python"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("ts", "text/x-typescript"),
+    ("tsx", "text/x-typescript"),
+    ("go", "text/x-go"),
+    ("java", "text/x-java"),
+    ("c", "text/x-c"),
+    ("h", "text/x-c"),
+    ("cpp", "text/x-c++"),
+    ("rb", "text/x-ruby"),
+    ("sh", "application/x-sh"),
+    ("md", "text/markdown"),
+    ("txt", "text/plain"),
+    ("html", "text/html"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("json", "application/json"),
+    ("toml", "application/toml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("xml", "application/xml"),
+    ("svg", "image/svg+xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("wasm", "application/wasm"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+];
+
+/// Reads the start of a file
+async fn sample(path: &Path) -> std::io::Result<Vec<u8>> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut sample = Vec::new();
+    file.take(SAMPLE_SIZE).read_to_end(&mut sample).await?;
+    Ok(sample)
+}
+
+/// Content is binary when it has null bytes or isn't valid UTF-8
+fn is_binary(sample: &[u8]) -> bool {
+    if sample.contains(&0) {
+        return true;
+    }
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => false,
+        // A character cut at the end of the sample is still valid text
+        Err(err) => err.error_len().is_some(),
     }
 }
 
+/// Guesses the MIME type from the content's signature, then from the
+/// extension, falling back to generic text or binary
+fn guess_mime(path: &Path, sample: &[u8], is_binary: bool) -> &'static str {
+    let by_signature = SIGNATURES
+        .iter()
+        .find(|(signature, _)| sample.starts_with(signature))
+        .map(|(_, mime)| *mime);
+
+    let by_extension = || {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        EXTENSIONS
+            .iter()
+            .find(|(known, _)| *known == extension)
+            .map(|(_, mime)| *mime)
+    };
+
+    by_signature.or_else(by_extension).unwrap_or(if is_binary {
+        "application/octet-stream"
+    } else {
+        "text/plain"
+    })
+}
+
+/// Number of lines of a text file, `None` for binary or unreadable files
+async fn line_count(path: &Path) -> Option<usize> {
+    let content = tokio::fs::read_to_string(path).await.ok()?;
+    Some(content.lines().count())
+}
+
+/// Status of the file in git, `None` when it isn't inside a repository or git
+/// isn't available
+async fn git_status(path: &Path) -> Option<GitStatus> {
+    let dir = path.parent()?;
+    let name = path.file_name()?;
+    let output = tokio::process::Command::new("git")
+        .args(["status", "--porcelain", "--ignored", "--"])
+        .arg(name)
+        .current_dir(dir)
+        .output()
+        .await
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let status = match stdout.lines().next() {
+        None => GitStatus::Clean,
+        Some(line) if line.starts_with("??") => GitStatus::Untracked,
+        Some(line) if line.starts_with("!!") => GitStatus::Ignored,
+        Some(_) => GitStatus::Modified,
+    };
+    Some(status)
+}
+
 #[cfg(test)]
 mod test {
+    use pretty_assertions::assert_eq;
     use tokio::fs;
 
     use super::*;
@@ -67,6 +252,171 @@ mod test {
         assert!(result.contains("modified"));
     }
 
+    #[tokio::test]
+    async fn test_fs_file_info_line_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "one\ntwo\nthree\n").await.unwrap();
+
+        let result = FSFileInfo
+            .call(FSFileInfoInput { path: file_path.to_string_lossy().to_string() })
+            .await
+            .unwrap();
+
+        assert!(result.contains("line_count: 3"));
+    }
+
+    #[tokio::test]
+    async fn test_fs_file_info_binary_has_no_line_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("image.bin");
+        fs::write(&file_path, [0xff, 0xfe, 0x00, 0x0a])
+            .await
+            .unwrap();
+
+        let result = FSFileInfo
+            .call(FSFileInfoInput { path: file_path.to_string_lossy().to_string() })
+            .await
+            .unwrap();
+
+        assert!(!result.contains("line_count"));
+    }
+
+    #[tokio::test]
+    async fn test_fs_file_info_text_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("notes.md");
+        fs::write(&file_path, "# Notes\n\nHéllo wörld ✓\n")
+            .await
+            .unwrap();
+
+        let result = FSFileInfo
+            .call(FSFileInfoInput { path: file_path.to_string_lossy().to_string() })
+            .await
+            .unwrap();
+
+        assert!(result.contains("is_binary: false"));
+        assert!(result.contains("mime_type: text/markdown"));
+    }
+
+    #[tokio::test]
+    async fn test_fs_file_info_binary_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("data");
+        fs::write(&file_path, b"abc\0def\0").await.unwrap();
+
+        let result = FSFileInfo
+            .call(FSFileInfoInput { path: file_path.to_string_lossy().to_string() })
+            .await
+            .unwrap();
+
+        assert!(result.contains("is_binary: true"));
+        assert!(result.contains("mime_type: application/octet-stream"));
+        assert!(!result.contains("line_count"));
+    }
+
+    #[test]
+    fn test_is_binary() {
+        assert!(!is_binary(b""));
+        assert!(!is_binary("plain ✓".as_bytes()));
+        assert!(is_binary(b"nul\0byte"));
+        assert!(is_binary(&[0xff, 0xfe, 0x41]));
+
+        // A character cut in the middle is not mistaken for binary content
+        let text = "✓".as_bytes();
+        assert!(!is_binary(&text[..2]));
+    }
+
+    #[test]
+    fn test_guess_mime_prefers_signature() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0";
+        assert_eq!(
+            guess_mime(Path::new("/a/image.txt"), png, true),
+            "image/png"
+        );
+        assert_eq!(
+            guess_mime(Path::new("/a/main.RS"), b"fn main", false),
+            "text/x-rust"
+        );
+        assert_eq!(
+            guess_mime(Path::new("/a/README"), b"hello", false),
+            "text/plain"
+        );
+    }
+
+    async fn git(dir: &Path, args: &[&str]) {
+        let status = tokio::process::Command::new("git")
+            .args([
+                "-c",
+                "user.name=forge",
+                "-c",
+                "user.email=forge@example.com",
+            ])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .await
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[tokio::test]
+    async fn test_fs_file_info_git_status() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path();
+        let file_path = repo.join("main.rs");
+        let input = || FSFileInfoInput { path: file_path.to_string_lossy().to_string() };
+
+        // Outside of a repository the status is omitted
+        fs::write(&file_path, "fn main() {}\n").await.unwrap();
+        let result = FSFileInfo.call(input()).await.unwrap();
+        assert!(!result.contains("git_status"));
+
+        git(&repo, &["init", "-q"]).await;
+        let result = FSFileInfo.call(input()).await.unwrap();
+        assert!(result.contains("git_status: untracked"));
+
+        git(&repo, &["add", "main.rs"]).await;
+        git(&repo, &["commit", "-q", "-m", "init"]).await;
+        let result = FSFileInfo.call(input()).await.unwrap();
+        assert!(result.contains("git_status: clean"));
+
+        fs::write(&file_path, "fn main() {\n    run();\n}\n")
+            .await
+            .unwrap();
+        let result = FSFileInfo.call(input()).await.unwrap();
+        assert!(result.contains("git_status: modified"));
+        assert!(result.contains("line_count: 3"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_fs_file_info_unreadable_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("secret.txt");
+        fs::write(&file_path, "hidden").await.unwrap();
+        fs::set_permissions(&file_path, std::fs::Permissions::from_mode(0o000))
+            .await
+            .unwrap();
+
+        // Permissions don't apply to root, there is nothing to check then
+        if fs::read(&file_path).await.is_ok() {
+            return;
+        }
+
+        let result = FSFileInfo
+            .call(FSFileInfoInput { path: file_path.to_string_lossy().to_string() })
+            .await
+            .unwrap();
+
+        assert!(result.contains("FileType"));
+        assert!(result.contains("readable: false"));
+        assert!(!result.contains("is_binary"));
+    }
+
     #[tokio::test]
     async fn test_fs_file_info_on_directory() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,227 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_tool_macros::ToolDescription;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::tools::utils::{assert_absolute_path, assert_in_workspace};
+use crate::{
+    EnvironmentService, FsCopyService, FsCreateDirsService, FsMetaService, Infrastructure,
+};
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FSCopyInput {
+    /// The path of the file or directory to copy (absolute path required)
+    pub source: String,
+    /// The path to copy to (absolute path required). Missing parent
+    /// directories are created.
+    pub destination: String,
+    /// If set to true, an existing destination is overwritten. Otherwise an
+    /// error is returned when the destination exists.
+    #[serde(default)]
+    pub overwrite: bool,
+    /// Set to true to copy a directory along with all its contents.
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+/// Copies a file, or a directory with all its contents when recursive is set.
+/// Both paths must be absolute and within the workspace. Missing parent
+/// directories of the destination are created. An existing destination is
+/// only overwritten when overwrite is set.
+#[derive(ToolDescription)]
+pub struct FSCopy<F>(Arc<F>);
+
+impl<F: Infrastructure> FSCopy<F> {
+    pub fn new(f: Arc<F>) -> Self {
+        Self(f)
+    }
+}
+
+impl<F> NamedTool for FSCopy<F> {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_fs_copy")
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Infrastructure> ExecutableTool for FSCopy<F> {
+    type Input = FSCopyInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        let source = Path::new(&input.source);
+        let destination = Path::new(&input.destination);
+        assert_absolute_path(source)?;
+        assert_absolute_path(destination)?;
+
+        let env = self.0.environment_service().get_environment();
+        assert_in_workspace(source, &env)?;
+        assert_in_workspace(destination, &env)?;
+
+        if !self.0.file_meta_service().exists(source).await? {
+            return Err(anyhow::anyhow!("File not found: {}", input.source));
+        }
+
+        if !input.recursive && !self.0.file_meta_service().is_file(source).await? {
+            return Err(anyhow::anyhow!(
+                "Source is a directory: {}, set recursive to true to copy it along with its contents",
+                input.source
+            ));
+        }
+
+        if !input.overwrite && self.0.file_meta_service().exists(destination).await? {
+            return Err(anyhow::anyhow!(
+                "Destination already exists: {}. If you need to overwrite it, set overwrite to true.",
+                input.destination
+            ));
+        }
+
+        if let Some(parent) = destination.parent() {
+            if !self.0.file_meta_service().exists(parent).await? {
+                self.0.create_dirs_service().create_dirs(parent).await?;
+            }
+        }
+
+        self.0
+            .file_copy_service()
+            .copy(source, destination, input.recursive)
+            .await?;
+
+        Ok(format!(
+            "Successfully copied {} to {}",
+            input.source, input.destination
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockInfrastructure;
+    use crate::{FsReadService, FsWriteService};
+
+    async fn write(infra: &MockInfrastructure, path: &str, content: &'static str) {
+        infra
+            .file_write_service()
+            .write(Path::new(path), Bytes::from(content))
+            .await
+            .unwrap();
+    }
+
+    async fn read(infra: &MockInfrastructure, path: &str) -> Option<Bytes> {
+        infra.file_read_service().read(Path::new(path)).await.ok()
+    }
+
+    #[tokio::test]
+    async fn test_fs_copy_file() {
+        let infra = Arc::new(MockInfrastructure::new());
+        write(&infra, "/test/a.txt", "content").await;
+
+        let actual = FSCopy::new(infra.clone())
+            .call(FSCopyInput {
+                source: "/test/a.txt".to_string(),
+                destination: "/test/sub/b.txt".to_string(),
+                overwrite: false,
+                recursive: false,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(actual, "Successfully copied /test/a.txt to /test/sub/b.txt");
+        assert_eq!(
+            read(&infra, "/test/a.txt").await,
+            Some(Bytes::from("content"))
+        );
+        assert_eq!(
+            read(&infra, "/test/sub/b.txt").await,
+            Some(Bytes::from("content"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fs_copy_refuses_overwrite() {
+        let infra = Arc::new(MockInfrastructure::new());
+        write(&infra, "/test/a.txt", "new").await;
+        write(&infra, "/test/b.txt", "old").await;
+
+        let result = FSCopy::new(infra.clone())
+            .call(FSCopyInput {
+                source: "/test/a.txt".to_string(),
+                destination: "/test/b.txt".to_string(),
+                overwrite: false,
+                recursive: false,
+            })
+            .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("set overwrite to true"));
+        assert_eq!(read(&infra, "/test/b.txt").await, Some(Bytes::from("old")));
+
+        FSCopy::new(infra.clone())
+            .call(FSCopyInput {
+                source: "/test/a.txt".to_string(),
+                destination: "/test/b.txt".to_string(),
+                overwrite: true,
+                recursive: false,
+            })
+            .await
+            .unwrap();
+        assert_eq!(read(&infra, "/test/b.txt").await, Some(Bytes::from("new")));
+    }
+
+    #[tokio::test]
+    async fn test_fs_copy_directory_recursively() {
+        let infra = Arc::new(MockInfrastructure::new());
+        infra
+            .create_dirs_service()
+            .create_dirs(Path::new("/test/src"))
+            .await
+            .unwrap();
+        write(&infra, "/test/src/a.txt", "a").await;
+        write(&infra, "/test/src/nested/b.txt", "b").await;
+
+        let fs_copy = FSCopy::new(infra.clone());
+        let result = fs_copy
+            .call(FSCopyInput {
+                source: "/test/src".to_string(),
+                destination: "/test/dst".to_string(),
+                overwrite: false,
+                recursive: false,
+            })
+            .await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("set recursive to true"));
+
+        fs_copy
+            .call(FSCopyInput {
+                source: "/test/src".to_string(),
+                destination: "/test/dst".to_string(),
+                overwrite: false,
+                recursive: true,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            read(&infra, "/test/dst/a.txt").await,
+            Some(Bytes::from("a"))
+        );
+        assert_eq!(
+            read(&infra, "/test/dst/nested/b.txt").await,
+            Some(Bytes::from("b"))
+        );
+        assert_eq!(
+            read(&infra, "/test/src/a.txt").await,
+            Some(Bytes::from("a"))
+        );
+    }
+}
@@ -1,14 +1,14 @@
 use std::collections::HashSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
-use forge_display::{GrepFormat, Kind, TitleFormat};
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_display::{GrepFormat, Kind, PathFormat, TitleFormat};
+use forge_domain::{ExecutableTool, NamedTool, ToolContent, ToolDescription, ToolName};
 use forge_tool_macros::ToolDescription;
 use forge_walker::Walker;
 use regex::Regex;
 use schemars::JsonSchema;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::tools::utils::assert_absolute_path;
 
@@ -22,14 +22,99 @@ pub struct FSSearchInput {
     /// Glob pattern to filter files (e.g., '*.ts' for TypeScript files). If not
     /// provided, it will search all files (*).
     pub file_pattern: Option<String>,
+    /// Format of the results. 'text' (default) returns one
+    /// 'path:line:column:content' entry per match, 'json' returns an array
+    /// of objects with 'path', 'line', 'column' and 'text' fields.
+    #[serde(default)]
+    pub output: SearchOutput,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchOutput {
+    #[default]
+    Text,
+    Json,
+}
+
+/// A single match of the search pattern. Line and column are 1-based, the
+/// column being the character offset of the match within the line. A line
+/// with several matches produces one entry per match.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SearchMatch {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
 }
 
 /// Request to perform a regex search on the content across files in a specified
 /// directory, providing context-rich results. This tool searches for patterns
 /// or specific content across multiple files, displaying each match with
 /// encapsulating context. The path must be absolute.
-#[derive(ToolDescription)]
-pub struct FSSearch;
+#[derive(Default, ToolDescription)]
+#[example = r#"{"path": "/home/user/project/src", "regex": "fn\\s+parse_", "file_pattern": "*.rs"}"#]
+pub struct FSSearch {
+    paths: PathFormat,
+}
+
+impl FSSearch {
+    pub fn new(paths: PathFormat) -> Self {
+        Self { paths }
+    }
+}
+
+/// Walks `dir` recursively and returns the files whose name matches the
+/// optional glob pattern. Shared by the tools that operate on search results.
+pub(crate) async fn search_files(
+    dir: &Path,
+    file_pattern: Option<&str>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let glob = file_pattern
+        .map(|pattern| {
+            glob::Pattern::new(pattern)
+                .with_context(|| format!("Invalid glob pattern '{}'", pattern))
+        })
+        .transpose()?;
+
+    // TODO: Current implementation is extremely slow and inefficient.
+    // It should ideally be taking in a stream of files and processing them
+    // concurrently.
+    let walker = Walker::max_all().cwd(dir.to_path_buf());
+
+    let files = walker
+        .get()
+        .await
+        .with_context(|| format!("Failed to walk directory '{}'", dir.display()))?;
+
+    let mut seen_paths = HashSet::new();
+    let mut paths = Vec::new();
+
+    for file in files {
+        if file.is_dir() {
+            continue;
+        }
+
+        let path = Path::new(&file.path);
+        let full_path = dir.join(path);
+
+        // Apply file pattern filter if provided
+        if let Some(ref glob) = glob {
+            if let Some(filename) = path.file_name().unwrap_or(path.as_os_str()).to_str() {
+                if !glob.matches(filename) {
+                    continue;
+                }
+            }
+        }
+
+        // Skip if we've already processed this file
+        if seen_paths.insert(full_path.clone()) {
+            paths.push(full_path);
+        }
+    }
+
+    Ok(paths)
+}
 
 impl From<&FSSearchInput> for TitleFormat {
     fn from(input: &FSSearchInput) -> Self {
@@ -55,6 +140,10 @@ impl ExecutableTool for FSSearch {
     type Input = FSSearchInput;
 
     async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        Ok(self.call_content(input).await?.to_string())
+    }
+
+    async fn call_content(&self, input: Self::Input) -> anyhow::Result<ToolContent> {
         let dir = Path::new(&input.path);
         assert_absolute_path(dir)?;
 
@@ -67,48 +156,13 @@ impl ExecutableTool for FSSearch {
         let regex = Regex::new(&pattern)
             .with_context(|| format!("Invalid regex pattern: {}", input.regex))?;
 
-        // TODO: Current implementation is extremely slow and inefficient.
-        // It should ideally be taking in a stream of files and processing them
-        // concurrently.
-        let walker = Walker::max_all().cwd(dir.to_path_buf());
-
-        let files = walker
-            .get()
-            .await
-            .with_context(|| format!("Failed to walk directory '{}'", dir.display()))?;
+        let files = search_files(dir, input.file_pattern.as_deref()).await?;
 
         let mut matches = Vec::new();
-        let mut seen_paths = HashSet::new();
-
-        for file in files {
-            if file.is_dir() {
-                continue;
-            }
-
-            let path = Path::new(&file.path);
-            let full_path = dir.join(path);
-
-            // Apply file pattern filter if provided
-            if let Some(ref pattern) = input.file_pattern {
-                let glob = glob::Pattern::new(pattern).with_context(|| {
-                    format!(
-                        "Invalid glob pattern '{}' for file '{}'",
-                        pattern,
-                        full_path.display(),
-                    )
-                })?;
-                if let Some(filename) = path.file_name().unwrap_or(path.as_os_str()).to_str() {
-                    if !glob.matches(filename) {
-                        continue;
-                    }
-                }
-            }
-
-            // Skip if we've already processed this file
-            if !seen_paths.insert(full_path.clone()) {
-                continue;
-            }
+        let mut structured_matches = Vec::new();
+        let mut display_matches = Vec::new();
 
+        for full_path in files {
             // Try to read the file content
             let content = match tokio::fs::read_to_string(&full_path).await {
                 Ok(content) => content,
@@ -116,6 +170,11 @@ impl ExecutableTool for FSSearch {
                     // Skip binary or unreadable files silently
                     if e.kind() != std::io::ErrorKind::InvalidData {
                         matches.push(format!("Error reading {:?}: {}", full_path.display(), e));
+                        display_matches.push(format!(
+                            "Error reading {:?}: {}",
+                            self.paths.format(&full_path),
+                            e
+                        ));
                     }
                     continue;
                 }
@@ -123,21 +182,61 @@ impl ExecutableTool for FSSearch {
 
             // Process the file line by line
             for (line_num, line) in content.lines().enumerate() {
-                if regex.is_match(line) {
-                    // Format match in ripgrep style: filepath:line_num:content
-                    matches.push(format!("{}:{}:{}", full_path.display(), line_num + 1, line));
+                let column_of = |start: usize| line[..start].chars().count() + 1;
+                let mut found = regex.find_iter(line).peekable();
+                let Some(first_column) = found.peek().map(|found| column_of(found.start())) else {
+                    continue;
+                };
+
+                for found in found {
+                    let column = column_of(found.start());
+                    structured_matches.push(SearchMatch {
+                        path: full_path.display().to_string(),
+                        line: line_num + 1,
+                        column,
+                        text: line.to_string(),
+                    });
+
+                    // Format match in ripgrep style: filepath:line_num:column:content
+                    matches.push(format!(
+                        "{}:{}:{}:{}",
+                        full_path.display(),
+                        line_num + 1,
+                        column,
+                        line
+                    ));
                 }
+
+                // Matching lines are only displayed once, at their first match,
+                // however many matches they contain
+                display_matches.push(format!(
+                    "{}:{}:{}:{}",
+                    self.paths.format(&full_path),
+                    line_num + 1,
+                    first_column,
+                    line
+                ));
             }
         }
 
         // Print title
-        println!("{}", TitleFormat::from(&input).format());
+        forge_display::echo!(
+            "{}",
+            TitleFormat::from(&input)
+                .sub_title(self.paths.format(&input.path))
+                .format()
+        );
 
         // Print results using GrepFormat for all cases
-        let formatted_output = GrepFormat::new(matches.clone()).format(&regex);
-        println!("{}", formatted_output);
-
-        Ok(matches.join("\n"))
+        let formatted_output = GrepFormat::new(display_matches).format(&regex);
+        forge_display::echo!("{}", formatted_output);
+
+        match input.output {
+            SearchOutput::Text => Ok(ToolContent::Text(matches.join("\n"))),
+            SearchOutput::Json => Ok(ToolContent::json(serde_json::to_value(
+                &structured_matches,
+            )?)),
+        }
     }
 }
 
@@ -163,12 +262,13 @@ mod test {
             .await
             .unwrap();
 
-        let fs_search = FSSearch;
+        let fs_search = FSSearch::default();
         let result = fs_search
             .call(FSSearchInput {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "test".to_string(),
                 file_pattern: None,
+                output: SearchOutput::Text,
             })
             .await
             .unwrap();
@@ -190,12 +290,13 @@ mod test {
             .await
             .unwrap();
 
-        let fs_search = FSSearch;
+        let fs_search = FSSearch::default();
         let result = fs_search
             .call(FSSearchInput {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "test".to_string(),
                 file_pattern: Some("*.rs".to_string()),
+                output: SearchOutput::Text,
             })
             .await
             .unwrap();
@@ -214,12 +315,13 @@ mod test {
             .await
             .unwrap();
 
-        let fs_search = FSSearch;
+        let fs_search = FSSearch::default();
         let result = fs_search
             .call(FSSearchInput {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "test".to_string(),
                 file_pattern: None,
+                output: SearchOutput::Text,
             })
             .await
             .unwrap();
@@ -246,12 +348,13 @@ mod test {
             .await
             .unwrap();
 
-        let fs_search = FSSearch;
+        let fs_search = FSSearch::default();
         let result = fs_search
             .call(FSSearchInput {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "test".to_string(),
                 file_pattern: None,
+                output: SearchOutput::Text,
             })
             .await
             .unwrap();
@@ -274,12 +377,13 @@ mod test {
         .await
         .unwrap();
 
-        let fs_search = FSSearch;
+        let fs_search = FSSearch::default();
         let result = fs_search
             .call(FSSearchInput {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "test".to_string(),
                 file_pattern: None,
+                output: SearchOutput::Text,
             })
             .await
             .unwrap();
@@ -298,12 +402,13 @@ mod test {
             .await
             .unwrap();
 
-        let fs_search = FSSearch;
+        let fs_search = FSSearch::default();
         let result = fs_search
             .call(FSSearchInput {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "nonexistent".to_string(),
                 file_pattern: None,
+                output: SearchOutput::Text,
             })
             .await
             .unwrap();
@@ -315,12 +420,13 @@ mod test {
     async fn test_fs_search_invalid_regex() {
         let temp_dir = TempDir::new().unwrap();
 
-        let fs_search = FSSearch;
+        let fs_search = FSSearch::default();
         let result = fs_search
             .call(FSSearchInput {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "[invalid".to_string(),
                 file_pattern: None,
+                output: SearchOutput::Text,
             })
             .await;
 
@@ -333,12 +439,13 @@ mod test {
 
     #[tokio::test]
     async fn test_fs_search_relative_path() {
-        let fs_search = FSSearch;
+        let fs_search = FSSearch::default();
         let result = fs_search
             .call(FSSearchInput {
                 path: "relative/path".to_string(),
                 regex: "test".to_string(),
                 file_pattern: None,
+                output: SearchOutput::Text,
             })
             .await;
 
@@ -348,4 +455,160 @@ mod test {
             .to_string()
             .contains("Path must be absolute"));
     }
+
+    #[tokio::test]
+    async fn test_fs_search_json_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("test.txt");
+
+        fs::write(&file, "first line\n  let x = test();\nno match")
+            .await
+            .unwrap();
+
+        let fs_search = FSSearch::default();
+        let result = fs_search
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "test".to_string(),
+                file_pattern: None,
+                output: SearchOutput::Json,
+            })
+            .await
+            .unwrap();
+
+        let actual: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let expected = serde_json::json!([{
+            "path": file.display().to_string(),
+            "line": 2,
+            "column": 11,
+            "text": "  let x = test();",
+        }]);
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_json_column_counts_characters() {
+        let temp_dir = TempDir::new().unwrap();
+
+        fs::write(temp_dir.path().join("test.txt"), "héllo test")
+            .await
+            .unwrap();
+
+        let fs_search = FSSearch::default();
+        let result = fs_search
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "test".to_string(),
+                file_pattern: None,
+                output: SearchOutput::Json,
+            })
+            .await
+            .unwrap();
+
+        let actual: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(actual[0]["column"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_content_follows_output() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("test.txt"), "test")
+            .await
+            .unwrap();
+        let input = |output| FSSearchInput {
+            path: temp_dir.path().to_string_lossy().to_string(),
+            regex: "test".to_string(),
+            file_pattern: None,
+            output,
+        };
+
+        let fs_search = FSSearch::default();
+        let actual = [
+            fs_search
+                .call_content(input(SearchOutput::Text))
+                .await
+                .unwrap(),
+            fs_search
+                .call_content(input(SearchOutput::Json))
+                .await
+                .unwrap(),
+        ];
+
+        assert!(!actual[0].is_json());
+        assert!(actual[1].is_json());
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_reports_column() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("test.txt");
+
+        fs::write(&file, "first\n  test here").await.unwrap();
+
+        let fs_search = FSSearch::default();
+        let actual = fs_search
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "test".to_string(),
+                file_pattern: None,
+                output: SearchOutput::Text,
+            })
+            .await
+            .unwrap();
+
+        let expected = format!("{}:2:3:  test here", file.display());
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_multiple_matches_per_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("test.txt");
+
+        fs::write(&file, "test and test").await.unwrap();
+
+        let fs_search = FSSearch::default();
+        let actual = fs_search
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "test".to_string(),
+                file_pattern: None,
+                output: SearchOutput::Text,
+            })
+            .await
+            .unwrap();
+
+        let expected = format!(
+            "{0}:1:1:test and test\n{0}:1:10:test and test",
+            file.display()
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_respects_forgeignore() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join(forge_walker::FORGE_IGNORE), "*.pem\n")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("server.pem"), "-----BEGIN KEY-----")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "BEGIN KEY rotation")
+            .await
+            .unwrap();
+
+        let result = FSSearch::default()
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "BEGIN KEY".to_string(),
+                file_pattern: None,
+                output: SearchOutput::Text,
+            })
+            .await
+            .unwrap();
+
+        assert!(result.contains("notes.txt"));
+        assert!(!result.contains("server.pem"));
+    }
 }
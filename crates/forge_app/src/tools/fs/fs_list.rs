@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+use std::fmt::Write;
 use std::path::Path;
 
 use anyhow::Context;
+use forge_display::format_size;
 use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
 use forge_tool_macros::ToolDescription;
 use forge_walker::Walker;
@@ -16,17 +19,45 @@ pub struct FSListInput {
     /// Whether to list files recursively. Use true for recursive listing, false
     /// or omit for top-level only.
     pub recursive: Option<bool>,
+    /// How the listing is presented. 'flat' (default) lists every path with
+    /// the sizes of files, 'tree' renders an indented tree with file sizes, 'summary' lists only
+    /// directories with their entry counts and total sizes. Directories end
+    /// with a '/'.
+    #[serde(default)]
+    pub output: ListOutput,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ListOutput {
+    #[default]
+    Flat,
+    Tree,
+    Summary,
+}
+
+/// Maximum number of entries rendered per directory in tree mode, the rest
+/// is collapsed into a "+N more" marker
+const MAX_TREE_ENTRIES_PER_DIR: usize = 50;
+
 /// Request to list files and directories within the specified directory. If
 /// recursive is true, it will list all files and directories recursively. If
 /// recursive is false or not provided, it will only list the top-level
 /// contents. The path must be absolute. Do not use this tool to confirm the
 /// existence of files you may have created, as the user will let you know if
-/// the files were created successfully or not.
-#[derive(Default, ToolDescription)]
+/// the files were created successfully or not. Use output 'tree' to see the
+/// structure and file sizes at a glance, or 'summary' for per-directory file
+/// counts and sizes of large trees.
+#[derive(ToolDescription)]
 pub struct FSList {
     sorted: bool,
+    max_entries_per_dir: usize,
+}
+
+impl Default for FSList {
+    fn default() -> Self {
+        Self { sorted: false, max_entries_per_dir: MAX_TREE_ENTRIES_PER_DIR }
+    }
 }
 
 impl NamedTool for FSList {
@@ -60,6 +91,26 @@ impl ExecutableTool for FSList {
             .await
             .with_context(|| format!("Failed to read directory contents from '{}'", input.path))?;
 
+        match input.output {
+            ListOutput::Flat => {}
+            ListOutput::Tree => {
+                let tree = FileTree::new(&files);
+                return Ok(format!(
+                    "<file_tree path=\"{}\">\n{}</file_tree>",
+                    input.path,
+                    tree.render(self.max_entries_per_dir)
+                ));
+            }
+            ListOutput::Summary => {
+                let tree = FileTree::new(&files);
+                return Ok(format!(
+                    "<file_summary path=\"{}\">\n{}</file_summary>",
+                    input.path,
+                    tree.summary()
+                ));
+            }
+        }
+
         // Sort the files for consistent snapshots
         if self.sorted {
             files.sort_by(|a, b| a.path.cmp(&b.path));
@@ -75,7 +126,11 @@ impl ExecutableTool for FSList {
                 if entry.is_dir() {
                     paths.push(format!(r#"<dir path="{}">"#, entry.path));
                 } else {
-                    paths.push(format!(r#"<file path="{}">"#, entry.path));
+                    paths.push(format!(
+                        r#"<file path="{}" size="{}">"#,
+                        entry.path,
+                        format_size(entry.size)
+                    ));
                 };
             }
         }
@@ -88,9 +143,197 @@ impl ExecutableTool for FSList {
     }
 }
 
+struct Node {
+    name: String,
+    is_dir: bool,
+    /// Size of the file, or the total size of all files within a directory
+    size: u64,
+    files: usize,
+    dirs: usize,
+    children: Vec<usize>,
+}
+
+impl Node {
+    fn display_name(&self) -> String {
+        if self.is_dir {
+            format!("{}/", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+/// A pending line of the rendered tree
+enum TreeItem {
+    Node { index: usize, depth: usize },
+    More { count: usize, depth: usize },
+}
+
+/// Directory tree built from the walker's entries. Nodes are stored in a flat
+/// arena, the root being the first one.
+struct FileTree {
+    nodes: Vec<Node>,
+}
+
+impl FileTree {
+    fn new(files: &[forge_walker::File]) -> Self {
+        let mut nodes = vec![Node {
+            name: String::new(),
+            is_dir: true,
+            size: 0,
+            files: 0,
+            dirs: 0,
+            children: Vec::new(),
+        }];
+        let mut parents = vec![0];
+        let mut dirs = HashMap::from([(String::new(), 0)]);
+
+        for file in files {
+            let is_dir = file.is_dir();
+            let path = file.path.trim_end_matches('/');
+            if path.is_empty() {
+                continue;
+            }
+
+            let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+            // The walker yields directories before their contents
+            let parent = dirs.get(parent).copied().unwrap_or(0);
+            let index = nodes.len();
+            nodes.push(Node {
+                name: name.to_string(),
+                is_dir,
+                size: if is_dir { 0 } else { file.size },
+                files: 0,
+                dirs: 0,
+                children: Vec::new(),
+            });
+            parents.push(parent);
+            nodes[parent].children.push(index);
+            if is_dir {
+                nodes[parent].dirs += 1;
+                dirs.insert(path.to_string(), index);
+            } else {
+                nodes[parent].files += 1;
+            }
+        }
+
+        // Children always come after their parent, so a reverse pass
+        // accumulates the directory sizes bottom-up
+        for index in (1..nodes.len()).rev() {
+            let size = nodes[index].size;
+            nodes[parents[index]].size += size;
+        }
+
+        // Directories first, then case-insensitive name, falling back to the
+        // exact name so that the order is stable
+        for index in 0..nodes.len() {
+            let mut children = std::mem::take(&mut nodes[index].children);
+            children.sort_by(|a, b| {
+                let (a, b) = (&nodes[*a], &nodes[*b]);
+                b.is_dir
+                    .cmp(&a.is_dir)
+                    .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+                    .then_with(|| a.name.cmp(&b.name))
+            });
+            nodes[index].children = children;
+        }
+
+        Self { nodes }
+    }
+
+    /// Pushes the children of a directory onto the render stack, in reverse
+    /// so that they are popped in order
+    fn push_children(
+        &self,
+        stack: &mut Vec<TreeItem>,
+        index: usize,
+        depth: usize,
+        max_entries: usize,
+    ) {
+        let children = &self.nodes[index].children;
+        if children.len() > max_entries {
+            stack.push(TreeItem::More { count: children.len() - max_entries, depth });
+        }
+        stack.extend(
+            children
+                .iter()
+                .take(max_entries)
+                .rev()
+                .map(|index| TreeItem::Node { index: *index, depth }),
+        );
+    }
+
+    /// Renders an indented tree, showing at most `max_entries` entries per
+    /// directory. The output is built iteratively into a single buffer so
+    /// that deep trees stay linear.
+    fn render(&self, max_entries: usize) -> String {
+        let mut output = String::new();
+        let mut stack = Vec::new();
+        self.push_children(&mut stack, 0, 0, max_entries);
+
+        while let Some(item) = stack.pop() {
+            match item {
+                TreeItem::Node { index, depth } => {
+                    let node = &self.nodes[index];
+                    let _ = writeln!(
+                        output,
+                        "{:indent$}{} ({})",
+                        "",
+                        node.display_name(),
+                        format_size(node.size),
+                        indent = depth * 2
+                    );
+                    if node.is_dir {
+                        self.push_children(&mut stack, index, depth + 1, max_entries);
+                    }
+                }
+                TreeItem::More { count, depth } => {
+                    let _ = writeln!(output, "{:indent$}+{count} more", "", indent = depth * 2);
+                }
+            }
+        }
+
+        output
+    }
+
+    /// Lists every directory with the number of files and directories it
+    /// directly contains and the total size of all the files below it
+    fn summary(&self) -> String {
+        let mut output = String::new();
+        let mut stack = vec![(0, String::from("./"))];
+
+        while let Some((index, path)) = stack.pop() {
+            let node = &self.nodes[index];
+            let _ = writeln!(
+                output,
+                "{} (files: {}, dirs: {}, size: {})",
+                path,
+                node.files,
+                node.dirs,
+                format_size(node.size)
+            );
+
+            let prefix = if index == 0 { "" } else { path.as_str() };
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .filter(|child| self.nodes[**child].is_dir)
+                    .map(|child| {
+                        let name = self.nodes[*child].display_name();
+                        (*child, format!("{prefix}{name}"))
+                    }),
+            );
+        }
+
+        output
+    }
+}
+
 #[cfg(test)]
 mod test {
     use insta::assert_snapshot;
+    use pretty_assertions::assert_eq;
     use tokio::fs;
 
     use super::*;
@@ -98,7 +341,7 @@ mod test {
 
     impl FSList {
         fn new(sorted: bool) -> Self {
-            Self { sorted }
+            Self { sorted, ..Default::default() }
         }
     }
 
@@ -111,6 +354,7 @@ mod test {
             .call(FSListInput {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 recursive: None,
+                output: ListOutput::Flat,
             })
             .await
             .unwrap();
@@ -136,6 +380,7 @@ mod test {
             .call(FSListInput {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 recursive: None,
+                output: ListOutput::Flat,
             })
             .await
             .unwrap();
@@ -153,6 +398,7 @@ mod test {
             .call(FSListInput {
                 path: nonexistent_dir.to_string_lossy().to_string(),
                 recursive: None,
+                output: ListOutput::Flat,
             })
             .await;
 
@@ -178,6 +424,7 @@ mod test {
             .call(FSListInput {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 recursive: None,
+                output: ListOutput::Flat,
             })
             .await
             .unwrap();
@@ -213,6 +460,7 @@ mod test {
             .call(FSListInput {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 recursive: Some(true),
+                output: ListOutput::Flat,
             })
             .await
             .unwrap();
@@ -224,7 +472,11 @@ mod test {
     async fn test_fs_list_relative_path() {
         let fs_list = FSList::new(true);
         let result = fs_list
-            .call(FSListInput { path: "relative/path".to_string(), recursive: None })
+            .call(FSListInput {
+                path: "relative/path".to_string(),
+                recursive: None,
+                output: ListOutput::Flat,
+            })
             .await;
 
         assert!(result.is_err());
@@ -233,4 +485,124 @@ mod test {
             .to_string()
             .contains("Path must be absolute"));
     }
+
+    async fn tree_fixture(dir: &Path) {
+        fs::create_dir_all(dir.join("src/nested/deep"))
+            .await
+            .unwrap();
+        fs::create_dir(dir.join("Docs")).await.unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {}")
+            .await
+            .unwrap();
+        fs::write(dir.join("src/Lib.rs"), "").await.unwrap();
+        fs::write(dir.join("src/nested/deep/x.rs"), "x".repeat(2048))
+            .await
+            .unwrap();
+        fs::write(dir.join("README.md"), "# readme").await.unwrap();
+        fs::write(dir.join("a.txt"), "abc").await.unwrap();
+    }
+
+    async fn list(fs_list: &FSList, dir: &Path, output: ListOutput) -> String {
+        let result = fs_list
+            .call(FSListInput {
+                path: dir.to_string_lossy().to_string(),
+                recursive: Some(true),
+                output,
+            })
+            .await
+            .unwrap();
+        TempDir::normalize(&result)
+    }
+
+    #[tokio::test]
+    async fn test_fs_list_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        tree_fixture(temp_dir.path()).await;
+
+        let actual = list(&FSList::new(true), temp_dir.path(), ListOutput::Tree).await;
+        let expected = [
+            "<file_tree path=\"[TEMP_DIR]\">",
+            "Docs/ (0 B)",
+            "src/ (2.0 KB)",
+            "  nested/ (2.0 KB)",
+            "    deep/ (2.0 KB)",
+            "      x.rs (2.0 KB)",
+            "  Lib.rs (0 B)",
+            "  main.rs (12 B)",
+            "a.txt (3 B)",
+            "README.md (8 B)",
+            "</file_tree>",
+        ]
+        .join("\n");
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_list_tree_caps_entries_per_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        tree_fixture(temp_dir.path()).await;
+
+        let fs_list = FSList { sorted: true, max_entries_per_dir: 2 };
+        let actual = list(&fs_list, temp_dir.path(), ListOutput::Tree).await;
+        let expected = [
+            "<file_tree path=\"[TEMP_DIR]\">",
+            "Docs/ (0 B)",
+            "src/ (2.0 KB)",
+            "  nested/ (2.0 KB)",
+            "    deep/ (2.0 KB)",
+            "      x.rs (2.0 KB)",
+            "  Lib.rs (0 B)",
+            "  +1 more",
+            "+2 more",
+            "</file_tree>",
+        ]
+        .join("\n");
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_list_summary() {
+        let temp_dir = TempDir::new().unwrap();
+        tree_fixture(temp_dir.path()).await;
+
+        let actual = list(&FSList::new(true), temp_dir.path(), ListOutput::Summary).await;
+        let expected = [
+            "<file_summary path=\"[TEMP_DIR]\">",
+            "./ (files: 2, dirs: 2, size: 2.0 KB)",
+            "Docs/ (files: 0, dirs: 0, size: 0 B)",
+            "src/ (files: 2, dirs: 1, size: 2.0 KB)",
+            "src/nested/ (files: 0, dirs: 1, size: 2.0 KB)",
+            "src/nested/deep/ (files: 1, dirs: 0, size: 2.0 KB)",
+            "</file_summary>",
+        ]
+        .join("\n");
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_list_respects_forgeignore() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join(forge_walker::FORGE_IGNORE), "*.pem\n")
+            .await
+            .unwrap();
+        fs::create_dir(temp_dir.path().join("certs")).await.unwrap();
+        fs::write(temp_dir.path().join("certs/server.pem"), "key")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("certs/README.md"), "readme")
+            .await
+            .unwrap();
+
+        let result = FSList::new(true)
+            .call(FSListInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                recursive: Some(true),
+                output: ListOutput::Flat,
+            })
+            .await
+            .unwrap();
+
+        assert!(result.contains("README.md"));
+        assert!(!result.contains("server.pem"));
+    }
 }
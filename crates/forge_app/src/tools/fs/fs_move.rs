@@ -0,0 +1,186 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_tool_macros::ToolDescription;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::tools::utils::{assert_absolute_path, assert_in_workspace};
+use crate::{
+    EnvironmentService, FsCreateDirsService, FsMetaService, FsMoveService, Infrastructure,
+};
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FSMoveInput {
+    /// The path of the file or directory to move (absolute path required)
+    pub source: String,
+    /// The new path of the file or directory (absolute path required). Missing
+    /// parent directories are created.
+    pub destination: String,
+}
+
+/// Moves or renames a file or directory. Use this instead of writing a copy
+/// and removing the original, so that the file keeps its history. Both paths
+/// must be absolute and within the workspace, and the destination must not
+/// already exist. Missing parent directories of the destination are created.
+#[derive(ToolDescription)]
+pub struct FSMove<F>(Arc<F>);
+
+impl<F: Infrastructure> FSMove<F> {
+    pub fn new(f: Arc<F>) -> Self {
+        Self(f)
+    }
+}
+
+impl<F> NamedTool for FSMove<F> {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_fs_move")
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Infrastructure> ExecutableTool for FSMove<F> {
+    type Input = FSMoveInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        let source = Path::new(&input.source);
+        let destination = Path::new(&input.destination);
+        assert_absolute_path(source)?;
+        assert_absolute_path(destination)?;
+
+        let env = self.0.environment_service().get_environment();
+        assert_in_workspace(source, &env)?;
+        assert_in_workspace(destination, &env)?;
+
+        if !self.0.file_meta_service().exists(source).await? {
+            return Err(anyhow::anyhow!("File not found: {}", input.source));
+        }
+
+        if self.0.file_meta_service().exists(destination).await? {
+            return Err(anyhow::anyhow!(
+                "Destination already exists: {}",
+                input.destination
+            ));
+        }
+
+        if let Some(parent) = destination.parent() {
+            if !self.0.file_meta_service().exists(parent).await? {
+                self.0.create_dirs_service().create_dirs(parent).await?;
+            }
+        }
+
+        self.0
+            .file_move_service()
+            .rename(source, destination)
+            .await?;
+
+        Ok(format!(
+            "Successfully moved {} to {}",
+            input.source, input.destination
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::attachment::tests::MockInfrastructure;
+    use crate::{FsReadService, FsWriteService};
+
+    async fn fixture() -> Arc<MockInfrastructure> {
+        let infra = Arc::new(MockInfrastructure::new());
+        infra
+            .file_write_service()
+            .write(Path::new("/test/old.txt"), Bytes::from("content"))
+            .await
+            .unwrap();
+        infra
+    }
+
+    async fn exists(infra: &MockInfrastructure, path: &str) -> bool {
+        infra
+            .file_meta_service()
+            .exists(Path::new(path))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fs_move_rename_in_same_directory() {
+        let infra = fixture().await;
+
+        let actual = FSMove::new(infra.clone())
+            .call(FSMoveInput {
+                source: "/test/old.txt".to_string(),
+                destination: "/test/new.txt".to_string(),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(actual, "Successfully moved /test/old.txt to /test/new.txt");
+        assert!(!exists(&infra, "/test/old.txt").await);
+        let content = infra
+            .file_read_service()
+            .read(Path::new("/test/new.txt"))
+            .await
+            .unwrap();
+        assert_eq!(content, Bytes::from("content"));
+    }
+
+    #[tokio::test]
+    async fn test_fs_move_into_new_subdirectory() {
+        let infra = fixture().await;
+
+        FSMove::new(infra.clone())
+            .call(FSMoveInput {
+                source: "/test/old.txt".to_string(),
+                destination: "/test/sub/dir/old.txt".to_string(),
+            })
+            .await
+            .unwrap();
+
+        assert!(!exists(&infra, "/test/old.txt").await);
+        assert!(exists(&infra, "/test/sub/dir").await);
+        assert!(exists(&infra, "/test/sub/dir/old.txt").await);
+    }
+
+    #[tokio::test]
+    async fn test_fs_move_existing_destination() {
+        let infra = fixture().await;
+
+        let result = FSMove::new(infra.clone())
+            .call(FSMoveInput {
+                source: "/test/old.txt".to_string(),
+                destination: "/test/file1.txt".to_string(),
+            })
+            .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Destination already exists"));
+        assert!(exists(&infra, "/test/old.txt").await);
+    }
+
+    #[tokio::test]
+    async fn test_fs_move_outside_workspace() {
+        let infra = fixture().await;
+
+        let result = FSMove::new(infra.clone())
+            .call(FSMoveInput {
+                source: "/test/old.txt".to_string(),
+                destination: "/tmp/old.txt".to_string(),
+            })
+            .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("outside the workspace"));
+        assert!(exists(&infra, "/test/old.txt").await);
+    }
+}
@@ -21,6 +21,7 @@ pub struct FSReadInput {
 /// PDF and DOCX files. May not be suitable for other types of binary files, as
 /// it returns the raw content as a string.
 #[derive(ToolDescription)]
+#[example = r#"{"path": "/home/user/project/src/main.rs"}"#]
 pub struct FSRead;
 
 impl NamedTool for FSRead {
@@ -6,18 +6,23 @@ use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::Deserialize;
 
-use crate::tools::utils::assert_absolute_path;
-use crate::{FileRemoveService, FsMetaService, Infrastructure};
+use crate::tools::utils::{assert_absolute_path, assert_in_workspace};
+use crate::{EnvironmentService, FileRemoveService, FsMetaService, Infrastructure};
 
 #[derive(Deserialize, JsonSchema)]
 pub struct FSRemoveInput {
-    /// The path of the file to remove (absolute path required)
+    /// The path of the file or directory to remove (absolute path required)
     pub path: String,
+    /// Set to remove a directory. When false only an empty directory is
+    /// removed, when true the directory is removed along with its contents.
+    pub recursive: Option<bool>,
 }
 
-/// Request to remove a file at the specified path. Use this when you need to
-/// delete an existing file. The path must be absolute. This operation cannot
-/// be undone, so use it carefully.
+/// Request to remove a file or directory at the specified path. Use this when
+/// you need to delete an existing file. To remove a directory set recursive:
+/// false removes it only if it is empty, true removes it with all its
+/// contents. The workspace root, the home directory and anything outside the
+/// workspace can't be removed. The path must be absolute. Use it carefully.
 #[derive(ToolDescription)]
 pub struct FSRemove<T>(Arc<T>);
 
@@ -33,6 +38,29 @@ impl<T> NamedTool for FSRemove<T> {
     }
 }
 
+impl<T: Infrastructure> FSRemove<T> {
+    /// Refuses to remove directories that would take the workspace or the
+    /// user's home with them, or that lie outside the workspace
+    fn assert_removable_dir(&self, path: &Path) -> anyhow::Result<()> {
+        let env = self.0.environment_service().get_environment();
+        let roots = std::iter::once(&env.cwd)
+            .chain(env.workspace_roots.iter())
+            .collect::<Vec<_>>();
+
+        let protected = path.parent().is_none()
+            || roots.iter().any(|root| path == root.as_path())
+            || env.home.as_deref() == Some(path);
+        if protected {
+            return Err(anyhow::anyhow!(
+                "Refusing to remove protected directory: {}",
+                path.display()
+            ));
+        }
+
+        assert_in_workspace(path, &env)
+    }
+}
+
 #[async_trait::async_trait]
 impl<T: Infrastructure> ExecutableTool for FSRemove<T> {
     type Input = FSRemoveInput;
@@ -48,7 +76,17 @@ impl<T: Infrastructure> ExecutableTool for FSRemove<T> {
 
         // Check if it's a file
         if !self.0.file_meta_service().is_file(path).await? {
-            return Err(anyhow::anyhow!("Path is not a file: {}", input.path));
+            let Some(recursive) = input.recursive else {
+                return Err(anyhow::anyhow!("Path is not a file: {}", input.path));
+            };
+
+            self.assert_removable_dir(path)?;
+            self.0
+                .file_remove_service()
+                .remove_dir(path, recursive)
+                .await?;
+
+            return Ok(format!("Successfully removed directory: {}", input.path));
         }
 
         // Remove the file
@@ -61,6 +99,7 @@ impl<T: Infrastructure> ExecutableTool for FSRemove<T> {
 #[cfg(test)]
 mod test {
     use bytes::Bytes;
+    use pretty_assertions::assert_eq;
 
     use super::*;
     use crate::attachment::tests::MockInfrastructure;
@@ -87,7 +126,10 @@ mod test {
 
         let fs_remove = FSRemove::new(infra.clone());
         let result = fs_remove
-            .call(FSRemoveInput { path: file_path.to_string_lossy().to_string() })
+            .call(FSRemoveInput {
+                path: file_path.to_string_lossy().to_string(),
+                recursive: None,
+            })
             .await
             .unwrap();
 
@@ -103,7 +145,10 @@ mod test {
 
         let fs_remove = FSRemove::new(infra);
         let result = fs_remove
-            .call(FSRemoveInput { path: nonexistent_file.to_string_lossy().to_string() })
+            .call(FSRemoveInput {
+                path: nonexistent_file.to_string_lossy().to_string(),
+                recursive: None,
+            })
             .await;
 
         assert!(result.is_err());
@@ -130,7 +175,10 @@ mod test {
 
         let fs_remove = FSRemove::new(infra.clone());
         let result = fs_remove
-            .call(FSRemoveInput { path: dir_path.to_string_lossy().to_string() })
+            .call(FSRemoveInput {
+                path: dir_path.to_string_lossy().to_string(),
+                recursive: None,
+            })
             .await;
 
         assert!(result.is_err());
@@ -150,7 +198,7 @@ mod test {
         let infra = Arc::new(MockInfrastructure::new());
         let fs_remove = FSRemove::new(infra);
         let result = fs_remove
-            .call(FSRemoveInput { path: "relative/path.txt".to_string() })
+            .call(FSRemoveInput { path: "relative/path.txt".to_string(), recursive: None })
             .await;
 
         assert!(result.is_err());
@@ -159,4 +207,105 @@ mod test {
             .to_string()
             .contains("Path must be absolute"));
     }
+
+    async fn create_dir(infra: &MockInfrastructure, path: &str) {
+        infra
+            .create_dirs_service()
+            .create_dirs(Path::new(path))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fs_remove_empty_directory() {
+        let infra = Arc::new(MockInfrastructure::new());
+        create_dir(&infra, "/test/empty").await;
+
+        let result = FSRemove::new(infra.clone())
+            .call(FSRemoveInput { path: "/test/empty".to_string(), recursive: Some(false) })
+            .await
+            .unwrap();
+
+        assert_eq!(result, "Successfully removed directory: /test/empty");
+        assert!(!infra
+            .file_meta_service()
+            .exists(Path::new("/test/empty"))
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_fs_remove_non_empty_directory_recursive() {
+        let infra = Arc::new(MockInfrastructure::new());
+        create_dir(&infra, "/test/full").await;
+        infra
+            .file_write_service()
+            .write(Path::new("/test/full/a.txt"), Bytes::from("a"))
+            .await
+            .unwrap();
+
+        let result = FSRemove::new(infra.clone())
+            .call(FSRemoveInput { path: "/test/full".to_string(), recursive: Some(true) })
+            .await
+            .unwrap();
+
+        assert_eq!(result, "Successfully removed directory: /test/full");
+        for path in ["/test/full", "/test/full/a.txt"] {
+            assert!(!infra
+                .file_meta_service()
+                .exists(Path::new(path))
+                .await
+                .unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fs_remove_non_empty_directory_requires_recursive() {
+        let infra = Arc::new(MockInfrastructure::new());
+        create_dir(&infra, "/test/full").await;
+        infra
+            .file_write_service()
+            .write(Path::new("/test/full/a.txt"), Bytes::from("a"))
+            .await
+            .unwrap();
+
+        let result = FSRemove::new(infra.clone())
+            .call(FSRemoveInput { path: "/test/full".to_string(), recursive: Some(false) })
+            .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("set recursive to true"));
+        assert!(infra
+            .file_meta_service()
+            .exists(Path::new("/test/full/a.txt"))
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_fs_remove_protected_directories() {
+        let infra = Arc::new(MockInfrastructure::new());
+        create_dir(&infra, "/test").await;
+        create_dir(&infra, "/home/test").await;
+        create_dir(&infra, "/other/dir").await;
+
+        for (path, expected) in [
+            ("/test", "protected directory"),
+            ("/home/test", "protected directory"),
+            ("/other/dir", "outside the workspace"),
+        ] {
+            let result = FSRemove::new(infra.clone())
+                .call(FSRemoveInput { path: path.to_string(), recursive: Some(true) })
+                .await;
+
+            assert!(result.unwrap_err().to_string().contains(expected), "{path}");
+        }
+        assert!(infra
+            .file_meta_service()
+            .exists(Path::new("/test"))
+            .await
+            .unwrap());
+    }
 }
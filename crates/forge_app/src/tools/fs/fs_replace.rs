@@ -0,0 +1,335 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use bytes::Bytes;
+use forge_display::{DiffFormat, TitleFormat};
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_tool_macros::ToolDescription;
+use regex::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use super::search_files;
+use crate::tools::utils::{assert_absolute_path, display_paths, TextFormat};
+use crate::{EnvironmentService, FsWriteService, Infrastructure};
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FSReplaceInput {
+    /// The path of the directory to replace in (absolute path required). This
+    /// directory will be recursively searched.
+    pub path: String,
+    /// The regular expression pattern to replace. Uses Rust regex syntax and
+    /// is case-sensitive.
+    pub regex: String,
+    /// The replacement text. Capture groups can be referenced as $1 or
+    /// ${name}.
+    pub replacement: String,
+    /// Glob pattern to filter files (e.g., '*.ts' for TypeScript files). If not
+    /// provided, all files are considered.
+    pub file_pattern: Option<String>,
+    /// If set to true, reports the replacements that would be made without
+    /// modifying any file.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Files under the input's directory that pass its file pattern and contain a
+/// match, ie. the files a replacement rewrites
+pub(crate) async fn files_to_replace(input: &FSReplaceInput) -> anyhow::Result<Vec<PathBuf>> {
+    let regex = Regex::new(&input.regex)
+        .with_context(|| format!("Invalid regex pattern: {}", input.regex))?;
+
+    let mut files = Vec::new();
+    for path in search_files(Path::new(&input.path), input.file_pattern.as_deref()).await? {
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) if regex.is_match(TextFormat::strip_bom(&content)) => files.push(path),
+            _ => {}
+        }
+    }
+    Ok(files)
+}
+
+/// Replaces every match of a regex across all files in a directory in a
+/// single operation, eg. to rename a symbol throughout a project. Files can be
+/// filtered by a glob pattern. Use dry_run first to review which files would
+/// change. Each modified file is snapshotted so that it can be restored. The
+/// replacement is written with each file's line endings and its byte order
+/// mark is kept. The path must be absolute.
+#[derive(ToolDescription)]
+pub struct FSReplace<F>(Arc<F>);
+
+impl<F: Infrastructure> FSReplace<F> {
+    pub fn new(f: Arc<F>) -> Self {
+        Self(f)
+    }
+}
+
+impl<F> NamedTool for FSReplace<F> {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_fs_replace")
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Infrastructure> ExecutableTool for FSReplace<F> {
+    type Input = FSReplaceInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        let dir = Path::new(&input.path);
+        assert_absolute_path(dir)?;
+
+        if !dir.exists() {
+            return Err(anyhow::anyhow!("Directory '{}' does not exist", input.path));
+        }
+
+        let regex = Regex::new(&input.regex)
+            .with_context(|| format!("Invalid regex pattern: {}", input.regex))?;
+
+        let env = self.0.environment_service().get_environment();
+        let paths = display_paths(&env);
+        let title = if input.dry_run {
+            "replace (dry-run)"
+        } else {
+            "replace"
+        };
+
+        let mut changes = Vec::new();
+        let mut converted = Vec::new();
+        for path in search_files(dir, input.file_pattern.as_deref()).await? {
+            // Binary and unreadable files can't contain textual matches
+            let Ok(old_content) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+
+            // The byte order mark is left out of the matching, so that `^`
+            // matches at the start of the text
+            let format = TextFormat::detect(&old_content);
+            let text = TextFormat::strip_bom(&old_content);
+            let count = regex.find_iter(text).count();
+            if count == 0 {
+                continue;
+            }
+
+            let mut changed_endings = false;
+            let replaced = regex.replace_all(text, |captures: &Captures| {
+                let mut replacement = String::new();
+                captures.expand(&input.replacement, &mut replacement);
+                let replacement_converted = format.convert_line_endings(&replacement);
+                changed_endings |= replacement_converted != replacement;
+                replacement_converted
+            });
+            let new_content = format.restore_bom(&replaced);
+            if changed_endings {
+                converted.push(format.name());
+            }
+            if !input.dry_run {
+                self.0
+                    .file_write_service()
+                    .write(&path, Bytes::from(new_content.clone()))
+                    .await?;
+            }
+
+            let display_path = paths.format(&path);
+            forge_display::echo!(
+                "{}",
+                DiffFormat::format(title, display_path.into(), &old_content, &new_content)
+            );
+            changes.push((path, count));
+        }
+
+        let total = changes.iter().map(|(_, count)| count).sum::<usize>();
+        let summary = format!(
+            "{} {} occurrence(s) in {} file(s)",
+            if input.dry_run {
+                "Would replace"
+            } else {
+                "Replaced"
+            },
+            total,
+            changes.len()
+        );
+
+        forge_display::echo!(
+            "{}",
+            TitleFormat::success(title).sub_title(&summary).format()
+        );
+
+        let mut result = summary;
+        for (path, count) in changes {
+            result.push_str(&format!("\n{}: {}", path.display(), count));
+        }
+        if !converted.is_empty() {
+            converted.sort_unstable();
+            converted.dedup();
+            result.push_str(&format!(
+                "\nNote: line endings of the replacement were converted to {} to match the files",
+                converted.join(" and ")
+            ));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use tokio::fs;
+
+    use super::*;
+    use crate::attachment::tests::MockInfrastructure;
+    use crate::tools::utils::TempDir;
+    use crate::FsReadService;
+
+    async fn fixture(dir: &Path) {
+        fs::write(dir.join("a.rs"), "fn old_name() {}\nold_name();")
+            .await
+            .unwrap();
+        fs::write(dir.join("b.rs"), "use crate::old_name;")
+            .await
+            .unwrap();
+        fs::write(dir.join("c.rs"), "// calls old_name")
+            .await
+            .unwrap();
+        fs::write(dir.join("d.txt"), "old_name").await.unwrap();
+    }
+
+    async fn read(infra: &MockInfrastructure, path: &Path) -> Option<String> {
+        infra
+            .file_read_service()
+            .read(path)
+            .await
+            .ok()
+            .map(|bytes| String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_fs_replace_across_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fixture(temp_dir.path()).await;
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let result = FSReplace::new(infra.clone())
+            .call(FSReplaceInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "old_name".to_string(),
+                replacement: "new_name".to_string(),
+                file_pattern: Some("*.rs".to_string()),
+                dry_run: false,
+            })
+            .await
+            .unwrap();
+
+        assert!(result.starts_with("Replaced 4 occurrence(s) in 3 file(s)"));
+
+        let actual = read(&infra, &temp_dir.path().join("a.rs")).await;
+        let expected = Some("fn new_name() {}\nnew_name();".to_string());
+        assert_eq!(actual, expected);
+
+        let actual = read(&infra, &temp_dir.path().join("b.rs")).await;
+        let expected = Some("use crate::new_name;".to_string());
+        assert_eq!(actual, expected);
+
+        let actual = read(&infra, &temp_dir.path().join("d.txt")).await;
+        assert_eq!(actual, None);
+    }
+
+    #[tokio::test]
+    async fn test_fs_replace_dry_run_changes_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        fixture(temp_dir.path()).await;
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let result = FSReplace::new(infra.clone())
+            .call(FSReplaceInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "old_name".to_string(),
+                replacement: "new_name".to_string(),
+                file_pattern: Some("*.rs".to_string()),
+                dry_run: true,
+            })
+            .await
+            .unwrap();
+
+        assert!(result.starts_with("Would replace 4 occurrence(s) in 3 file(s)"));
+
+        let actual = read(&infra, &temp_dir.path().join("a.rs")).await;
+        assert_eq!(actual, None);
+
+        let actual = fs::read_to_string(temp_dir.path().join("a.rs"))
+            .await
+            .unwrap();
+        assert_eq!(actual, "fn old_name() {}\nold_name();");
+    }
+
+    #[tokio::test]
+    async fn test_fs_replace_capture_groups() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.rs"), "let x = foo(1);")
+            .await
+            .unwrap();
+
+        let infra = Arc::new(MockInfrastructure::new());
+        FSReplace::new(infra.clone())
+            .call(FSReplaceInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: r"foo\((\d+)\)".to_string(),
+                replacement: "bar($1, 0)".to_string(),
+                file_pattern: None,
+                dry_run: false,
+            })
+            .await
+            .unwrap();
+
+        let actual = read(&infra, &temp_dir.path().join("a.rs")).await;
+        let expected = Some("let x = bar(1, 0);".to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_replace_keeps_crlf_and_bom() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("a.bat"),
+            "\u{feff}@echo off\r\nexit\r\n",
+        )
+        .await
+        .unwrap();
+
+        let infra = Arc::new(MockInfrastructure::new());
+        let result = FSReplace::new(infra.clone())
+            .call(FSReplaceInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "^@echo off".to_string(),
+                replacement: "@echo off\nsetlocal".to_string(),
+                file_pattern: None,
+                dry_run: false,
+            })
+            .await
+            .unwrap();
+
+        let actual = read(&infra, &temp_dir.path().join("a.bat")).await;
+        let expected = Some("\u{feff}@echo off\r\nsetlocal\r\nexit\r\n".to_string());
+        assert_eq!(actual, expected);
+        assert!(result.contains("converted to CRLF"));
+    }
+
+    #[tokio::test]
+    async fn test_fs_replace_relative_path() {
+        let infra = Arc::new(MockInfrastructure::new());
+        let result = FSReplace::new(infra)
+            .call(FSReplaceInput {
+                path: "relative/path".to_string(),
+                regex: "a".to_string(),
+                replacement: "b".to_string(),
+                file_pattern: None,
+                dry_run: false,
+            })
+            .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Path must be absolute"));
+    }
+}
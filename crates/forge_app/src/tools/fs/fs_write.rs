@@ -3,15 +3,15 @@ use std::sync::Arc;
 
 use anyhow::Context;
 use bytes::Bytes;
-use forge_display::DiffFormat;
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_display::{format_size, DiffFormat};
+use forge_domain::{ExecutableTool, LineEnding, NamedTool, ToolDescription, ToolName};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::tools::syn;
-use crate::tools::utils::assert_absolute_path;
-use crate::{FsMetaService, FsReadService, FsWriteService, Infrastructure};
+use crate::tools::utils::{assert_absolute_path, display_paths, TextFormat};
+use crate::{EnvironmentService, FsMetaService, FsReadService, FsWriteService, Infrastructure};
 
 #[derive(Deserialize, JsonSchema)]
 pub struct FSWriteInput {
@@ -26,6 +26,11 @@ pub struct FSWriteInput {
     /// existing file.
     #[serde(default)]
     pub overwrite: bool,
+    /// Line ending to write the file with: `lf`, `crlf`, or `preserve` to
+    /// write the content's line endings as they are. Leave unset to keep the
+    /// line endings of an existing file.
+    #[serde(default)]
+    pub line_ending: Option<LineEnding>,
 }
 
 /// Use it to create a new file at a specified path with the provided content.
@@ -33,8 +38,11 @@ pub struct FSWriteInput {
 /// automatically handles the creation of any missing intermediary directories
 /// in the specified path.
 /// IMPORTANT: DO NOT attempt to use this tool to move or rename files, use the
-/// shell tool instead.
+/// move tool instead. Intermediate artifacts that don't belong in the project
+/// go to the scratch directory ({{scratch_dir}}), paths starting with
+/// `scratch:` are relative to it, e.g. `scratch:convert.py`.
 #[derive(ToolDescription)]
+#[example = r#"{"path": "/home/user/project/src/config.rs", "content": "pub const RETRIES: u32 = 3;\n"}"#]
 pub struct FSWrite<F>(Arc<F>);
 
 impl<F: Infrastructure> FSWrite<F> {
@@ -92,17 +100,32 @@ impl<F: Infrastructure> ExecutableTool for FSWrite<F> {
             "".to_string()
         };
 
+        // Existing files keep their line endings and byte order mark
+        let env = self.0.environment_service().get_environment();
+        let format = if file_exists {
+            TextFormat::detect(&old_content)
+        } else {
+            TextFormat::new_file(env.line_ending)
+        };
+        let (content, conversion) = format.line_ending(input.line_ending).apply(&input.content);
+
         // Write file only after validation passes and directories are created
         self.0
             .file_write_service()
-            .write(Path::new(&input.path), Bytes::from(input.content.clone()))
+            .write(Path::new(&input.path), Bytes::from(content.clone()))
             .await?;
 
+        // The size in bytes is kept for the agent
         let mut result = format!(
-            "Successfully wrote {} bytes to {}",
-            input.content.len(),
+            "Successfully wrote {} ({} bytes) to {}",
+            format_size(content.len() as u64),
+            content.len(),
             input.path
         );
+        if let Some(conversion) = conversion {
+            result.push_str("\nNote: ");
+            result.push_str(&conversion);
+        }
         if let Some(warning) = syntax_warning {
             result.push_str("\nWarning: ");
             result.push_str(&warning.to_string());
@@ -111,8 +134,9 @@ impl<F: Infrastructure> ExecutableTool for FSWrite<F> {
         // record the file content after they're modified
         let new_content = String::from_utf8(self.0.file_read_service().read(path).await?.to_vec())?;
         let title = if file_exists { "overwrite" } else { "create" };
-        let diff = DiffFormat::format(title, path.to_path_buf(), &old_content, &new_content);
-        println!("{}", diff);
+        let display_path = display_paths(&env).format(path);
+        let diff = DiffFormat::format(title, display_path.into(), &old_content, &new_content);
+        forge_display::echo!("{}", diff);
 
         Ok(result)
     }
@@ -155,6 +179,7 @@ mod test {
                 path: file_path.to_string_lossy().to_string(),
                 content: content.to_string(),
                 overwrite: false,
+                line_ending: None,
             })
             .await
             .unwrap();
@@ -188,6 +213,7 @@ mod test {
                 path: file_path.to_string_lossy().to_string(),
                 content: "fn main() { let x = ".to_string(),
                 overwrite: false,
+                line_ending: None,
             })
             .await;
 
@@ -208,6 +234,7 @@ mod test {
                 path: file_path.to_string_lossy().to_string(),
                 content: content.to_string(),
                 overwrite: false,
+                line_ending: None,
             })
             .await;
 
@@ -243,6 +270,7 @@ mod test {
                 path: nested_path.to_string_lossy().to_string(),
                 content: content.to_string(),
                 overwrite: false,
+                line_ending: None,
             })
             .await
             .unwrap();
@@ -283,6 +311,7 @@ mod test {
                 path: deep_path.to_string_lossy().to_string(),
                 content: content.to_string(),
                 overwrite: false,
+                line_ending: None,
             })
             .await
             .unwrap();
@@ -325,6 +354,7 @@ mod test {
                 path: path_str,
                 content: content.to_string(),
                 overwrite: false,
+                line_ending: None,
             })
             .await
             .unwrap();
@@ -361,6 +391,7 @@ mod test {
                 path: "relative/path/file.txt".to_string(),
                 content: "test content".to_string(),
                 overwrite: false,
+                line_ending: None,
             })
             .await;
 
@@ -392,6 +423,7 @@ mod test {
                 path: file_path.to_string_lossy().to_string(),
                 content: "New content".to_string(),
                 overwrite: false,
+                line_ending: None,
             })
             .await;
 
@@ -440,6 +472,7 @@ mod test {
                 path: file_path.to_string_lossy().to_string(),
                 content: new_content.to_string(),
                 overwrite: true,
+                line_ending: None,
             })
             .await;
 
@@ -462,4 +495,81 @@ mod test {
         .unwrap();
         assert_eq!(content, new_content);
     }
+
+    /// Overwrites a file holding `original` with `content` and returns the
+    /// tool's output along with the bytes written
+    async fn overwrite(
+        original: &str,
+        content: &str,
+        line_ending: Option<LineEnding>,
+    ) -> (String, String) {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("script.bat");
+        let infra = Arc::new(MockInfrastructure::new());
+        infra
+            .file_write_service()
+            .write(&file_path, Bytes::from(original.to_string()))
+            .await
+            .unwrap();
+
+        let output = FSWrite::new(infra.clone())
+            .call(FSWriteInput {
+                path: file_path.to_string_lossy().to_string(),
+                content: content.to_string(),
+                overwrite: true,
+                line_ending,
+            })
+            .await
+            .unwrap();
+        let written = infra.file_read_service().read(&file_path).await.unwrap();
+        (output, String::from_utf8(written.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_fs_write_keeps_crlf_line_endings() {
+        let (output, actual) = overwrite("@echo off\r\nexit\r\n", "@echo on\nexit\n", None).await;
+
+        assert_eq!(actual, "@echo on\r\nexit\r\n");
+        assert!(output.contains("Note: line endings were converted to CRLF"));
+    }
+
+    #[tokio::test]
+    async fn test_fs_write_keeps_byte_order_mark() {
+        let (output, actual) = overwrite("\u{feff}old\n", "new\n", None).await;
+
+        assert_eq!(actual, "\u{feff}new\n");
+        assert!(output.contains("byte order mark was kept"));
+    }
+
+    #[tokio::test]
+    async fn test_fs_write_line_ending_input_wins() {
+        let (_, lf) = overwrite("a\r\nb\r\n", "a\nc\n", Some(LineEnding::Lf)).await;
+        let (output, preserved) =
+            overwrite("a\r\nb\r\n", "a\nc\r\n", Some(LineEnding::Preserve)).await;
+
+        assert_eq!(lf, "a\nc\n");
+        assert_eq!(preserved, "a\nc\r\n");
+        assert!(!output.contains("Note:"));
+    }
+
+    #[tokio::test]
+    async fn test_fs_write_new_file_uses_platform_line_ending() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("new.txt");
+        let infra = Arc::new(MockInfrastructure::new());
+
+        FSWrite::new(infra.clone())
+            .call(FSWriteInput {
+                path: file_path.to_string_lossy().to_string(),
+                content: "a\nb\n".to_string(),
+                overwrite: false,
+                line_ending: None,
+            })
+            .await
+            .unwrap();
+
+        let actual = infra.file_read_service().read(&file_path).await.unwrap();
+        let expected = TextFormat::new_file(None).apply("a\nb\n").0;
+        assert_eq!(String::from_utf8(actual.to_vec()).unwrap(), expected);
+    }
 }
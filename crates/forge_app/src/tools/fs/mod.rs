@@ -1,13 +1,19 @@
 mod file_info;
+mod fs_copy;
 mod fs_find;
 mod fs_list;
+mod fs_move;
 mod fs_read;
 mod fs_remove;
+mod fs_replace;
 mod fs_write;
 
 pub use file_info::*;
+pub use fs_copy::*;
 pub use fs_find::*;
 pub use fs_list::*;
+pub use fs_move::*;
 pub use fs_read::*;
 pub use fs_remove::*;
+pub use fs_replace::*;
 pub use fs_write::*;
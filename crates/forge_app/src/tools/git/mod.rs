@@ -0,0 +1,415 @@
+mod parse;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use forge_display::{PathFormat, TitleFormat};
+use forge_domain::{ExecutableTool, NamedTool, ToolContent, ToolDescription, ToolName};
+use forge_tool_macros::ToolDescription;
+pub use parse::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::tools::utils::assert_absolute_path;
+
+/// Runs git in the directory, failing with what git printed when it doesn't
+/// succeed
+async fn git(dir: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        // Fail instead of waiting for credentials or an editor
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_EDITOR", "true")
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("Failed to run git, is it installed?")?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // `git commit` explains why there's nothing to commit on stdout
+        let message = if stderr.trim().is_empty() {
+            stdout.trim()
+        } else {
+            stderr.trim()
+        };
+        bail!("git {} failed: {}", args[0], message);
+    }
+    Ok(stdout)
+}
+
+fn repository(path: &str) -> anyhow::Result<PathBuf> {
+    let path = PathBuf::from(path);
+    assert_absolute_path(&path)?;
+    Ok(path)
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GitStatusInput {
+    /// Absolute path of the repository, or of a directory inside it
+    pub path: String,
+}
+
+/// Shows the git status of a repository: the current branch, how far it is
+/// ahead of or behind its upstream, and each changed file with its staged and
+/// unstaged change (added, modified, deleted, renamed, copied, type_changed,
+/// unmerged or untracked). Prefer it over running `git status` with the shell
+/// tool.
+#[derive(ToolDescription)]
+pub struct GitStatus {
+    paths: PathFormat,
+}
+
+impl GitStatus {
+    pub fn new(paths: PathFormat) -> Self {
+        Self { paths }
+    }
+
+    async fn run(&self, input: &GitStatusInput) -> anyhow::Result<Status> {
+        let path = repository(&input.path)?;
+        forge_display::echo!(
+            "{}",
+            TitleFormat::execute("git status")
+                .sub_title(self.paths.format(&path))
+                .format()
+        );
+
+        let output = git(&path, &["status", "--porcelain=v1", "--branch", "-z"]).await?;
+        Ok(parse_status(&output))
+    }
+}
+
+impl NamedTool for GitStatus {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_git_status")
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecutableTool for GitStatus {
+    type Input = GitStatusInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        Ok(self.call_content(input).await?.to_string())
+    }
+
+    async fn call_content(&self, input: Self::Input) -> anyhow::Result<ToolContent> {
+        let status = self.run(&input).await?;
+        Ok(ToolContent::json(serde_json::to_value(&status)?))
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GitDiffInput {
+    /// Absolute path of the repository, or of a directory inside it
+    pub path: String,
+    /// Diff the changes staged for the next commit instead of the unstaged
+    /// ones
+    pub staged: Option<bool>,
+    /// Only diff these files, relative to `path`
+    pub files: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GitDiffOutput {
+    pub files: Vec<FileDiff>,
+}
+
+/// Shows the changes of a git repository that aren't staged, or the staged
+/// ones when `staged` is set. Returns each changed file with the number of
+/// lines added and removed and its hunks, every hunk with its line numbers and
+/// lines prefixed with ` `, `+` or `-`. Untracked files aren't part of the
+/// diff. Prefer it over running `git diff` with the shell tool.
+#[derive(ToolDescription)]
+pub struct GitDiff {
+    paths: PathFormat,
+}
+
+impl GitDiff {
+    pub fn new(paths: PathFormat) -> Self {
+        Self { paths }
+    }
+
+    async fn run(&self, input: &GitDiffInput) -> anyhow::Result<GitDiffOutput> {
+        let path = repository(&input.path)?;
+        forge_display::echo!(
+            "{}",
+            TitleFormat::execute("git diff")
+                .sub_title(self.paths.format(&path))
+                .format()
+        );
+
+        let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+        if input.staged.unwrap_or_default() {
+            args.push("--cached");
+        }
+        if let Some(files) = &input.files {
+            args.push("--");
+            args.extend(files.iter().map(String::as_str));
+        }
+        let output = git(&path, &args).await?;
+        Ok(GitDiffOutput { files: parse_diff(&output) })
+    }
+}
+
+impl NamedTool for GitDiff {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_git_diff")
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecutableTool for GitDiff {
+    type Input = GitDiffInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        Ok(self.call_content(input).await?.to_string())
+    }
+
+    async fn call_content(&self, input: Self::Input) -> anyhow::Result<ToolContent> {
+        let diff = self.run(&input).await?;
+        Ok(ToolContent::json(serde_json::to_value(&diff)?))
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GitCommitInput {
+    /// Absolute path of the repository, or of a directory inside it
+    pub path: String,
+    /// The commit message, a short summary line optionally followed by a
+    /// blank line and a longer description
+    pub message: String,
+    /// Stage every modified and deleted tracked file before committing, like
+    /// `git commit -a`. Untracked files are never added.
+    pub all: Option<bool>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct GitCommitOutput {
+    /// Hash of the new commit
+    pub commit: String,
+    pub summary: String,
+    /// Files the commit changed
+    pub files: Vec<String>,
+}
+
+/// Commits the staged changes of a git repository with the given message, or
+/// every change to tracked files when `all` is set. Fails when there's
+/// nothing to commit. Returns the hash of the commit and the files it
+/// changed.
+#[derive(ToolDescription)]
+pub struct GitCommit;
+
+impl GitCommit {
+    async fn run(&self, input: &GitCommitInput) -> anyhow::Result<GitCommitOutput> {
+        let path = repository(&input.path)?;
+        if input.message.trim().is_empty() {
+            bail!("The commit message is empty");
+        }
+        forge_display::echo!(
+            "{}",
+            TitleFormat::execute("git commit")
+                .sub_title(input.message.lines().next().unwrap_or_default())
+                .format()
+        );
+
+        let mut args = vec!["commit", "--message", input.message.as_str()];
+        if input.all.unwrap_or_default() {
+            args.push("--all");
+        }
+        git(&path, &args).await?;
+
+        let output = git(&path, &["show", "--name-only", "--format=%H%n%s", "HEAD"]).await?;
+        let mut lines = output.lines();
+        Ok(GitCommitOutput {
+            commit: lines.next().unwrap_or_default().to_string(),
+            summary: lines.next().unwrap_or_default().to_string(),
+            files: lines
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+        })
+    }
+}
+
+impl NamedTool for GitCommit {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_git_commit")
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecutableTool for GitCommit {
+    type Input = GitCommitInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        Ok(self.call_content(input).await?.to_string())
+    }
+
+    async fn call_content(&self, input: Self::Input) -> anyhow::Result<ToolContent> {
+        let commit = self.run(&input).await?;
+        Ok(ToolContent::json(serde_json::to_value(&commit)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::tools::utils::TempDir;
+
+    /// A repository with a committed `lib.rs`
+    async fn fixture() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        git(&dir, &["init", "--quiet"]).await.unwrap();
+        git(&dir, &["config", "user.name", "forge"]).await.unwrap();
+        git(&dir, &["config", "user.email", "forge@example.com"])
+            .await
+            .unwrap();
+        git(&dir, &["config", "commit.gpgsign", "false"])
+            .await
+            .unwrap();
+        tokio::fs::write(dir.join("lib.rs"), "fn add() {\n    1 - 1\n}\n")
+            .await
+            .unwrap();
+        git(&dir, &["add", "lib.rs"]).await.unwrap();
+        git(&dir, &["commit", "--quiet", "-m", "Add lib.rs"])
+            .await
+            .unwrap();
+        temp_dir
+    }
+
+    fn path(temp_dir: &TempDir) -> String {
+        temp_dir.path().display().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_status_lists_changed_files() {
+        let temp_dir = fixture().await;
+        let dir = temp_dir.path();
+        tokio::fs::write(dir.join("lib.rs"), "fn add() {\n    1 + 1\n}\n")
+            .await
+            .unwrap();
+        tokio::fs::write(dir.join("notes.md"), "todo")
+            .await
+            .unwrap();
+
+        let actual = GitStatus::new(PathFormat::default())
+            .run(&GitStatusInput { path: path(&temp_dir) })
+            .await
+            .unwrap()
+            .files;
+
+        let expected = vec![
+            ChangedFile {
+                path: "lib.rs".to_string(),
+                from: None,
+                staged: None,
+                unstaged: Some(Change::Modified),
+            },
+            ChangedFile {
+                path: "notes.md".to_string(),
+                from: None,
+                staged: None,
+                unstaged: Some(Change::Untracked),
+            },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_diff_of_staged_changes() {
+        let temp_dir = fixture().await;
+        let dir = temp_dir.path();
+        tokio::fs::write(dir.join("lib.rs"), "fn add() {\n    1 + 1\n}\n")
+            .await
+            .unwrap();
+        git(&dir, &["add", "lib.rs"]).await.unwrap();
+        let input =
+            |staged| GitDiffInput { path: path(&temp_dir), staged: Some(staged), files: None };
+
+        let unstaged = GitDiff::new(PathFormat::default())
+            .run(&input(false))
+            .await
+            .unwrap()
+            .files;
+        let actual = GitDiff::new(PathFormat::default())
+            .run(&input(true))
+            .await
+            .unwrap()
+            .files;
+
+        assert!(unstaged.is_empty());
+        let expected = vec![FileDiff {
+            path: "lib.rs".to_string(),
+            added: 1,
+            removed: 1,
+            hunks: vec![Hunk {
+                header: "@@ -1,3 +1,3 @@".to_string(),
+                old_start: 1,
+                old_lines: 3,
+                new_start: 1,
+                new_lines: 3,
+                lines: vec![
+                    " fn add() {".to_string(),
+                    "-    1 - 1".to_string(),
+                    "+    1 + 1".to_string(),
+                    " }".to_string(),
+                ],
+            }],
+            ..Default::default()
+        }];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_commit() {
+        let temp_dir = fixture().await;
+        let dir = temp_dir.path();
+        tokio::fs::write(dir.join("lib.rs"), "fn add() {\n    1 + 1\n}\n")
+            .await
+            .unwrap();
+
+        let actual = GitCommit
+            .run(&GitCommitInput {
+                path: path(&temp_dir),
+                message: "Fix add\n\nIt subtracted.".to_string(),
+                all: Some(true),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(actual.summary, "Fix add");
+        assert_eq!(actual.files, vec!["lib.rs".to_string()]);
+        let head = git(&dir, &["rev-parse", "HEAD"]).await.unwrap();
+        assert_eq!(actual.commit, head.trim());
+        let status = GitStatus::new(PathFormat::default())
+            .run(&GitStatusInput { path: path(&temp_dir) })
+            .await
+            .unwrap();
+        assert!(status.files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_commit_without_changes() {
+        let temp_dir = fixture().await;
+
+        let actual = GitCommit
+            .run(&GitCommitInput {
+                path: path(&temp_dir),
+                message: "Nothing".to_string(),
+                all: None,
+            })
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert!(actual.starts_with("git commit failed: "), "{actual}");
+        assert!(actual.contains("nothing to commit"), "{actual}");
+    }
+}
@@ -0,0 +1,360 @@
+use regex::Regex;
+use serde::Serialize;
+
+/// How a file changed, in the index or in the working tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Added,
+    #[default]
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChanged,
+    Unmerged,
+    Untracked,
+}
+
+impl Change {
+    fn from_code(code: char) -> Option<Self> {
+        match code {
+            'A' => Some(Change::Added),
+            'M' => Some(Change::Modified),
+            'D' => Some(Change::Deleted),
+            'R' => Some(Change::Renamed),
+            'C' => Some(Change::Copied),
+            'T' => Some(Change::TypeChanged),
+            'U' => Some(Change::Unmerged),
+            '?' => Some(Change::Untracked),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ChangedFile {
+    pub path: String,
+    /// Path the file was renamed or copied from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Change that is staged for the next commit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub staged: Option<Change>,
+    /// Change in the working tree that isn't staged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unstaged: Option<Change>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Status {
+    /// `None` on a detached HEAD
+    pub branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub files: Vec<ChangedFile>,
+}
+
+/// Parses the output of `git status --porcelain=v1 --branch -z`
+pub fn parse_status(output: &str) -> Status {
+    let mut status = Status::default();
+    let mut entries = output.split('\0').filter(|entry| !entry.is_empty());
+    while let Some(entry) = entries.next() {
+        if let Some(branch) = entry.strip_prefix("## ") {
+            parse_branch(branch, &mut status);
+            continue;
+        }
+        if entry.len() < 4 {
+            continue;
+        }
+
+        let mut codes = entry.chars();
+        let index = codes.next().unwrap_or(' ');
+        let worktree = codes.next().unwrap_or(' ');
+        let path = entry[3..].to_string();
+        // The source of a rename or copy follows as an entry of its own
+        let from = if matches!(index, 'R' | 'C') {
+            entries.next().map(str::to_string)
+        } else {
+            None
+        };
+
+        let (staged, unstaged) = match (index, worktree) {
+            ('?', '?') => (None, Some(Change::Untracked)),
+            // Both sides of a merge conflict, eg. `UU` or `AA`
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => {
+                (Some(Change::Unmerged), Some(Change::Unmerged))
+            }
+            (index, worktree) => (Change::from_code(index), Change::from_code(worktree)),
+        };
+        status
+            .files
+            .push(ChangedFile { path, from, staged, unstaged });
+    }
+    status
+}
+
+/// Parses the branch line, eg. `main...origin/main [ahead 1, behind 2]`
+fn parse_branch(line: &str, status: &mut Status) {
+    let (names, tracking) = match line.split_once(" [") {
+        Some((names, tracking)) => (names, tracking.trim_end_matches(']')),
+        None => (line, ""),
+    };
+    let (branch, upstream) = match names.split_once("...") {
+        Some((branch, upstream)) => (branch, Some(upstream.to_string())),
+        None => (names, None),
+    };
+
+    status.branch = match branch {
+        "HEAD (no branch)" => None,
+        // A repository without commits yet
+        branch => Some(
+            branch
+                .strip_prefix("No commits yet on ")
+                .unwrap_or(branch)
+                .to_string(),
+        ),
+    };
+    status.upstream = upstream;
+    for part in tracking.split(", ") {
+        if let Some(count) = part.strip_prefix("ahead ") {
+            status.ahead = count.parse().unwrap_or_default();
+        } else if let Some(count) = part.strip_prefix("behind ") {
+            status.behind = count.parse().unwrap_or_default();
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Hunk {
+    /// The `@@ -1,4 +1,5 @@` line, with the enclosing function when git found
+    /// one
+    pub header: String,
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    /// Lines of the hunk, prefixed with ` `, `+` or `-`
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// Path before a rename, or `None` when the path didn't change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub change: Change,
+    pub binary: bool,
+    pub added: usize,
+    pub removed: usize,
+    pub hunks: Vec<Hunk>,
+}
+
+/// Parses the unified diff printed by `git diff`
+pub fn parse_diff(output: &str) -> Vec<FileDiff> {
+    let file_header = Regex::new(r"^diff --git a/(.+) b/(.+)$").unwrap();
+    let hunk_header = Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@").unwrap();
+
+    let mut files: Vec<FileDiff> = Vec::new();
+    for line in output.lines() {
+        if let Some(captures) = file_header.captures(line) {
+            files.push(FileDiff { path: captures[2].to_string(), ..Default::default() });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if let Some(captures) = hunk_header.captures(line) {
+            let number = |group: usize| {
+                captures
+                    .get(group)
+                    .map_or(1, |count| count.as_str().parse().unwrap_or_default())
+            };
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                old_start: number(1),
+                old_lines: number(2),
+                new_start: number(3),
+                new_lines: number(4),
+                lines: Vec::new(),
+            });
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            match line.chars().next() {
+                Some('+') => file.added += 1,
+                Some('-') => file.removed += 1,
+                _ => {}
+            }
+            hunk.lines.push(line.to_string());
+        } else if line.starts_with("new file mode") {
+            file.change = Change::Added;
+        } else if line.starts_with("deleted file mode") {
+            file.change = Change::Deleted;
+        } else if let Some(from) = line.strip_prefix("rename from ") {
+            file.change = Change::Renamed;
+            file.from = Some(from.to_string());
+        } else if let Some(from) = line.strip_prefix("copy from ") {
+            file.change = Change::Copied;
+            file.from = Some(from.to_string());
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            file.binary = true;
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_status() {
+        let fixture = [
+            "## main...origin/main [ahead 2, behind 1]",
+            "M  src/lib.rs",
+            " M README.md",
+            "MM Cargo.toml",
+            "R  src/new.rs",
+            "src/old.rs",
+            "UU src/conflict.rs",
+            "?? notes file.md",
+            "",
+        ]
+        .join("\0");
+
+        let actual = parse_status(&fixture);
+
+        let file = |path: &str, staged, unstaged| ChangedFile {
+            path: path.to_string(),
+            from: None,
+            staged,
+            unstaged,
+        };
+        let expected = Status {
+            branch: Some("main".to_string()),
+            upstream: Some("origin/main".to_string()),
+            ahead: 2,
+            behind: 1,
+            files: vec![
+                file("src/lib.rs", Some(Change::Modified), None),
+                file("README.md", None, Some(Change::Modified)),
+                file("Cargo.toml", Some(Change::Modified), Some(Change::Modified)),
+                ChangedFile {
+                    from: Some("src/old.rs".to_string()),
+                    ..file("src/new.rs", Some(Change::Renamed), None)
+                },
+                file(
+                    "src/conflict.rs",
+                    Some(Change::Unmerged),
+                    Some(Change::Unmerged),
+                ),
+                file("notes file.md", None, Some(Change::Untracked)),
+            ],
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_status_branch_without_upstream() {
+        let actual = [
+            "## feature\0",
+            "## No commits yet on main\0",
+            "## HEAD (no branch)\0",
+        ]
+        .map(|fixture| parse_status(fixture).branch);
+
+        assert_eq!(
+            actual,
+            [Some("feature".to_string()), Some("main".to_string()), None]
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let fixture = r#"diff --git a/src/lib.rs b/src/lib.rs
+index 3b18e51..a5c1966 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@ pub fn add(a: u32, b: u32) -> u32 {
+ pub fn add(a: u32, b: u32) -> u32 {
+-    a - b
++    // Fixed
++    a + b
+ }
+diff --git a/logo.png b/logo.png
+new file mode 100644
+index 0000000..e69de29
+Binary files /dev/null and b/logo.png differ
+diff --git a/old.txt b/new.txt
+similarity 100%
+rename from old.txt
+rename to new.txt
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+index ce01362..0000000
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-hello
+"#;
+
+        let actual = parse_diff(fixture);
+
+        let expected = vec![
+            FileDiff {
+                path: "src/lib.rs".to_string(),
+                from: None,
+                change: Change::Modified,
+                binary: false,
+                added: 2,
+                removed: 1,
+                hunks: vec![Hunk {
+                    header: "@@ -1,3 +1,4 @@ pub fn add(a: u32, b: u32) -> u32 {".to_string(),
+                    old_start: 1,
+                    old_lines: 3,
+                    new_start: 1,
+                    new_lines: 4,
+                    lines: vec![
+                        " pub fn add(a: u32, b: u32) -> u32 {".to_string(),
+                        "-    a - b".to_string(),
+                        "+    // Fixed".to_string(),
+                        "+    a + b".to_string(),
+                        " }".to_string(),
+                    ],
+                }],
+            },
+            FileDiff {
+                path: "logo.png".to_string(),
+                change: Change::Added,
+                binary: true,
+                ..Default::default()
+            },
+            FileDiff {
+                path: "new.txt".to_string(),
+                from: Some("old.txt".to_string()),
+                change: Change::Renamed,
+                ..Default::default()
+            },
+            FileDiff {
+                path: "gone.txt".to_string(),
+                change: Change::Deleted,
+                removed: 1,
+                hunks: vec![Hunk {
+                    header: "@@ -1 +0,0 @@".to_string(),
+                    old_start: 1,
+                    old_lines: 1,
+                    new_start: 0,
+                    new_lines: 0,
+                    lines: vec!["-hello".to_string()],
+                }],
+                ..Default::default()
+            },
+        ];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,256 @@
+mod parse;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use forge_display::TitleFormat;
+use forge_domain::{
+    Environment, ExecutableTool, NamedTool, ToolContent, ToolDescription, ToolName,
+};
+use forge_tool_macros::ToolDescription;
+pub use parse::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::tools::shell::CommandExecutor;
+use crate::tools::utils::{assert_absolute_path, display_command, display_paths, split_args};
+
+/// Diagnostics returned at most, errors first
+const MAX_DIAGNOSTICS: usize = 100;
+
+/// Lines of output returned when it has no diagnostics but the command failed
+const OUTPUT_TAIL_LINES: usize = 40;
+
+/// Seconds after which the command is stopped when the call sets no timeout
+const DEFAULT_TIMEOUT_SECS: u64 = 10 * 60;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Linter {
+    /// `cargo clippy`
+    #[default]
+    Clippy,
+    /// `cargo check`, faster but without clippy's lints
+    Check,
+}
+
+impl Linter {
+    /// The program and its arguments, see [`split_args`]
+    fn command(self, args: Option<&str>) -> anyhow::Result<Vec<String>> {
+        let subcommand = match self {
+            Linter::Clippy => "clippy",
+            Linter::Check => "check",
+        };
+        let mut command = ["cargo", subcommand, "--message-format=json"]
+            .map(String::from)
+            .to_vec();
+        command.extend(split_args(args)?);
+        Ok(command)
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LintInput {
+    /// Absolute path of the Rust project's directory
+    pub path: String,
+    /// 'clippy' (the default) or 'check'
+    pub linter: Option<Linter>,
+    /// Arguments added to the command, e.g. `--all-targets` or `-p my_crate`.
+    /// They are passed to cargo as is, not through a shell.
+    pub args: Option<String>,
+    /// Optional number of seconds after which the command is stopped, 600 by
+    /// default
+    pub timeout: Option<u64>,
+}
+
+impl LintInput {
+    /// Paths the command modifies, checked against the protected paths: the
+    /// Cargo.lock cargo may update, and the whole project with `--fix`
+    pub(crate) fn written_paths(&self) -> Vec<PathBuf> {
+        let path = PathBuf::from(&self.path);
+        let fix = split_args(self.args.as_deref())
+            .unwrap_or_default()
+            .iter()
+            .any(|arg| arg == "--fix");
+        let mut paths = vec![path.join("Cargo.lock")];
+        if fix {
+            paths.push(path);
+        }
+        paths
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LintOutput {
+    pub command: String,
+    /// Whether the command exited successfully, it fails on errors
+    pub success: bool,
+    /// Seconds after which the command was stopped, when it didn't finish
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timed_out_after: Option<u64>,
+    pub errors: usize,
+    pub warnings: usize,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Diagnostics left out over the limit
+    #[serde(skip_serializing_if = "is_zero")]
+    pub omitted: usize,
+    /// End of the output when the command failed without diagnostics, e.g.
+    /// because Cargo.toml is invalid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// Checks a Rust project with `cargo clippy`, or `cargo check` when asked,
+/// and returns the compiler's errors and warnings as records of file, line,
+/// column, severity, code and message, errors first. Run it after editing
+/// Rust code to find what needs fixing before running the tests.
+#[derive(ToolDescription)]
+pub struct Lint {
+    env: Environment,
+}
+
+impl Lint {
+    pub fn new(env: Environment) -> Self {
+        Self { env }
+    }
+
+    async fn run(&self, input: &LintInput) -> anyhow::Result<LintOutput> {
+        let path = PathBuf::from(&input.path);
+        assert_absolute_path(&path)?;
+        let command = input
+            .linter
+            .unwrap_or_default()
+            .command(input.args.as_deref())?;
+        let (program, args) = command.split_first().expect("the command has a program");
+        let display = display_command(&command);
+
+        forge_display::echo!(
+            "{}",
+            TitleFormat::execute("lint")
+                .sub_title(format!(
+                    "{} in {}",
+                    display,
+                    display_paths(&self.env).format(&path)
+                ))
+                .format()
+        );
+
+        let mut process = Command::new(program);
+        process
+            .args(args)
+            .current_dir(&path)
+            .env("CARGO_TERM_COLOR", "never")
+            .kill_on_drop(true);
+        let timeout = input.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS);
+        // The JSON messages are parsed, they aren't echoed to the terminal
+        let output = CommandExecutor::new(process)
+            .stdin("")
+            .silent()
+            .timeout(Duration::from_secs(timeout))
+            .execute()
+            .await
+            .with_context(|| format!("Failed to run {display}"))?;
+
+        let mut diagnostics = parse_cargo_json(&output.stdout);
+        diagnostics.sort_by_key(|diagnostic| diagnostic.severity != Severity::Error);
+        let count = |severity| {
+            diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == severity)
+                .count()
+        };
+        let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+        let omitted = diagnostics.len().saturating_sub(MAX_DIAGNOSTICS);
+        diagnostics.truncate(MAX_DIAGNOSTICS);
+
+        let success = output.success;
+        let output_tail = (!success && diagnostics.is_empty()).then(|| {
+            let lines = output.stderr.trim_end().lines().collect::<Vec<_>>();
+            lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..].join("\n")
+        });
+
+        Ok(LintOutput {
+            command: display,
+            success,
+            timed_out_after: output.cancelled_after.map(|_| timeout),
+            errors,
+            warnings,
+            diagnostics,
+            omitted,
+            output: output_tail,
+        })
+    }
+}
+
+impl NamedTool for Lint {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_process_lint")
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecutableTool for Lint {
+    type Input = LintInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        Ok(self.call_content(input).await?.to_string())
+    }
+
+    async fn call_content(&self, input: Self::Input) -> anyhow::Result<ToolContent> {
+        let output = self.run(&input).await?;
+        Ok(ToolContent::json(serde_json::to_value(&output)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        let actual = [
+            Linter::Clippy.command(None).unwrap(),
+            Linter::Check.command(Some("--all-targets")).unwrap(),
+            Linter::Clippy.command(Some("  ")).unwrap(),
+        ];
+
+        let expected = [
+            vec!["cargo", "clippy", "--message-format=json"],
+            vec!["cargo", "check", "--message-format=json", "--all-targets"],
+            vec!["cargo", "clippy", "--message-format=json"],
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_fix_writes_the_project() {
+        let input = |args: &str| LintInput {
+            path: "/project".to_string(),
+            linter: None,
+            args: Some(args.to_string()),
+            timeout: None,
+        };
+
+        let actual = [
+            input("--all-targets").written_paths(),
+            input("--fix --allow-dirty").written_paths(),
+        ];
+
+        let expected = [
+            vec![PathBuf::from("/project/Cargo.lock")],
+            vec![
+                PathBuf::from("/project/Cargo.lock"),
+                PathBuf::from("/project"),
+            ],
+        ];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+
+impl Severity {
+    fn from_level(level: &str) -> Self {
+        match level {
+            "warning" => Severity::Warning,
+            "note" | "failure-note" => Severity::Note,
+            "help" => Severity::Help,
+            // `error` and `error: internal compiler error`
+            _ => Severity::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    /// The lint or error code, eg. `unused_variables` or `E0308`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<CompilerMessage>,
+}
+
+#[derive(Deserialize)]
+struct CompilerMessage {
+    message: String,
+    level: String,
+    code: Option<DiagnosticCode>,
+    spans: Vec<Span>,
+}
+
+#[derive(Deserialize)]
+struct DiagnosticCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct Span {
+    file_name: String,
+    line_start: usize,
+    column_start: usize,
+    is_primary: bool,
+}
+
+/// Parses the diagnostics out of the output of a cargo command run with
+/// `--message-format=json`. Lines that aren't JSON, eg. cargo's progress, are
+/// skipped, and so are diagnostics without a location, like the count of
+/// warnings emitted. A diagnostic reported for several targets of a crate is
+/// kept once.
+pub fn parse_cargo_json(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for line in output.lines().filter(|line| line.starts_with('{')) {
+        let Ok(CargoMessage { reason, message: Some(message) }) = serde_json::from_str(line) else {
+            continue;
+        };
+        if reason != "compiler-message" {
+            continue;
+        }
+        let Some(span) = message.spans.iter().find(|span| span.is_primary) else {
+            continue;
+        };
+
+        let diagnostic = Diagnostic {
+            file: span.file_name.clone(),
+            line: span.line_start,
+            column: span.column_start,
+            severity: Severity::from_level(&message.level),
+            code: message.code.map(|code| code.code),
+            message: message.message,
+        };
+        if !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const CARGO_CLIPPY: &str = r#"    Checking calc v0.1.0 (/home/user/calc)
+{"reason":"compiler-artifact","package_id":"path+file:///home/user/calc#0.1.0","target":{"kind":["lib"],"name":"calc"},"profile":{"test":false},"features":[],"filenames":[],"fresh":false}
+{"reason":"compiler-message","package_id":"path+file:///home/user/calc#0.1.0","manifest_path":"/home/user/calc/Cargo.toml","target":{"kind":["lib"],"name":"calc"},"message":{"rendered":"warning: unused variable: `x`\n","$message_type":"diagnostic","children":[{"children":[],"code":null,"level":"help","message":"if this is intentional, prefix it with an underscore: `_x`","rendered":null,"spans":[]}],"code":{"code":"unused_variables","explanation":null},"level":"warning","message":"unused variable: `x`","spans":[{"byte_end":52,"byte_start":51,"column_end":10,"column_start":9,"expansion":null,"file_name":"src/lib.rs","is_primary":true,"label":null,"line_end":3,"line_start":3,"suggested_replacement":null,"suggestion_applicability":null,"text":[]}]}}
+{"reason":"compiler-message","package_id":"path+file:///home/user/calc#0.1.0","manifest_path":"/home/user/calc/Cargo.toml","target":{"kind":["lib"],"name":"calc"},"message":{"rendered":"warning: this `if` has identical blocks\n","$message_type":"diagnostic","children":[],"code":{"code":"clippy::if_same_then_else","explanation":null},"level":"warning","message":"this `if` has identical blocks","spans":[{"byte_end":140,"byte_start":120,"column_end":6,"column_start":5,"expansion":null,"file_name":"src/lib.rs","is_primary":false,"label":null,"line_end":9,"line_start":7,"suggested_replacement":null,"suggestion_applicability":null,"text":[]},{"byte_end":180,"byte_start":150,"column_end":6,"column_start":12,"expansion":null,"file_name":"src/lib.rs","is_primary":true,"label":null,"line_end":11,"line_start":9,"suggested_replacement":null,"suggestion_applicability":null,"text":[]}]}}
+{"reason":"compiler-message","package_id":"path+file:///home/user/calc#0.1.0","manifest_path":"/home/user/calc/Cargo.toml","target":{"kind":["test"],"name":"calc"},"message":{"rendered":"warning: unused variable: `x`\n","$message_type":"diagnostic","children":[],"code":{"code":"unused_variables","explanation":null},"level":"warning","message":"unused variable: `x`","spans":[{"byte_end":52,"byte_start":51,"column_end":10,"column_start":9,"expansion":null,"file_name":"src/lib.rs","is_primary":true,"label":null,"line_end":3,"line_start":3,"suggested_replacement":null,"suggestion_applicability":null,"text":[]}]}}
+{"reason":"compiler-message","package_id":"path+file:///home/user/calc#0.1.0","manifest_path":"/home/user/calc/Cargo.toml","target":{"kind":["bin"],"name":"calc"},"message":{"rendered":"error[E0308]: mismatched types\n","$message_type":"diagnostic","children":[],"code":{"code":"E0308","explanation":"Expected type did not match the received type.\n"},"level":"error","message":"mismatched types","spans":[{"byte_end":30,"byte_start":25,"column_end":10,"column_start":5,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":"expected `u32`, found `&str`","line_end":2,"line_start":2,"suggested_replacement":null,"suggestion_applicability":null,"text":[]}]}}
+{"reason":"compiler-message","package_id":"path+file:///home/user/calc#0.1.0","manifest_path":"/home/user/calc/Cargo.toml","target":{"kind":["bin"],"name":"calc"},"message":{"rendered":"error: aborting due to 1 previous error\n","$message_type":"diagnostic","children":[],"code":null,"level":"error","message":"aborting due to 1 previous error","spans":[]}}
+{"reason":"build-finished","success":false}
+error: could not compile `calc` (bin "calc") due to 1 previous error
+"#;
+
+    #[test]
+    fn test_cargo_json_diagnostics() {
+        let actual = parse_cargo_json(CARGO_CLIPPY);
+
+        let expected = vec![
+            Diagnostic {
+                file: "src/lib.rs".to_string(),
+                line: 3,
+                column: 9,
+                severity: Severity::Warning,
+                code: Some("unused_variables".to_string()),
+                message: "unused variable: `x`".to_string(),
+            },
+            Diagnostic {
+                file: "src/lib.rs".to_string(),
+                line: 9,
+                column: 12,
+                severity: Severity::Warning,
+                code: Some("clippy::if_same_then_else".to_string()),
+                message: "this `if` has identical blocks".to_string(),
+            },
+            Diagnostic {
+                file: "src/main.rs".to_string(),
+                line: 2,
+                column: 5,
+                severity: Severity::Error,
+                code: Some("E0308".to_string()),
+                message: "mismatched types".to_string(),
+            },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_output_without_json() {
+        let fixture = "error: could not find `Cargo.toml` in `/home/user` or any parent directory";
+
+        assert_eq!(parse_cargo_json(fixture), vec![]);
+    }
+}
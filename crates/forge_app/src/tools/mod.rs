@@ -1,37 +1,84 @@
 mod fetch;
 mod fs;
+mod git;
+mod lint;
 mod patch;
 mod shell;
+mod symbol;
 mod syn;
+mod test_runner;
 mod think;
 mod utils;
 
 use std::sync::Arc;
 
 use fetch::Fetch;
-use forge_domain::Tool;
+use forge_domain::{NamedTool, Tool};
 use fs::*;
+pub(crate) use fs::{files_to_replace, FSReplaceInput};
+use git::{GitCommit, GitDiff, GitStatus};
+use lint::Lint;
+pub(crate) use lint::LintInput;
 use patch::*;
+pub use shell::stop_running_commands;
 use shell::Shell;
+use symbol::SymbolSearch;
+use test_runner::RunTests;
+pub(crate) use test_runner::RunTestsInput;
 use think::Think;
+pub use utils::display_paths;
 
 use crate::{EnvironmentService, Infrastructure};
 
 pub fn tools<F: Infrastructure>(infra: Arc<F>) -> Vec<Tool> {
     let env = infra.environment_service().get_environment();
-    vec![
+    let tools: Vec<Tool> = vec![
         FSRead.into(),
         FSWrite::new(infra.clone()).into(),
         FSRemove::new(infra.clone()).into(),
+        FSMove::new(infra.clone()).into(),
+        FSCopy::new(infra.clone()).into(),
         FSList::default().into(),
-        FSSearch.into(),
+        FSSearch::new(display_paths(&env)).into(),
+        FSReplace::new(infra.clone()).into(),
         FSFileInfo.into(),
+        SymbolSearch::new(
+            env.cwd.clone(),
+            env.scan_workers.unwrap_or_else(symbol::default_workers),
+        )
+        .into(),
         // ApplyPatch::new(infra.clone()).into(),
         ApplyPatchJson::new(infra).into(),
         Shell::new(env.clone()).into(),
+        RunTests::new(env.clone()).into(),
+        Lint::new(env.clone()).into(),
+        GitStatus::new(display_paths(&env)).into(),
+        GitDiff::new(display_paths(&env)).into(),
+        GitCommit.into(),
         Think::default().into(),
-        Fetch::default().into(),
-    ]
+        Fetch::new(&env.proxy, &env.tls, &env.http_timeout).into(),
+    ];
+    if !env.read_only {
+        return tools;
+    }
+
+    // Read-only mode leaves out the tools that change files or run commands
+    let mutating = [
+        FSWrite::<F>::tool_name(),
+        FSRemove::<F>::tool_name(),
+        FSMove::<F>::tool_name(),
+        FSCopy::<F>::tool_name(),
+        FSReplace::<F>::tool_name(),
+        ApplyPatchJson::<F>::tool_name(),
+        Shell::tool_name(),
+        RunTests::tool_name(),
+        Lint::tool_name(),
+        GitCommit::tool_name(),
+    ];
+    tools
+        .into_iter()
+        .filter(|tool| !mutating.contains(&tool.definition.name))
+        .collect()
 }
 
 #[cfg(test)]
@@ -39,13 +86,16 @@ mod tests {
     use std::path::{Path, PathBuf};
 
     use bytes::Bytes;
-    use forge_domain::{Environment, Point, Provider, Query, Suggestion};
-    use forge_snaps::{SnapshotInfo, SnapshotMetadata};
+    use forge_domain::{
+        DocumentChunk, Environment, Point, PointId, Provider, Query, Suggestion, ToolName,
+    };
+    use forge_snaps::{SnapshotInfo, SnapshotMetadata, SnapshotSelector};
+    use pretty_assertions::assert_eq;
 
     use super::*;
     use crate::{
-        EmbeddingService, FileRemoveService, FsCreateDirsService, FsMetaService, FsReadService,
-        FsSnapshotService, FsWriteService, VectorIndex,
+        EmbeddingService, FileRemoveService, FsCopyService, FsCreateDirsService, FsMetaService,
+        FsMoveService, FsReadService, FsSnapshotService, FsWriteService, VectorIndex,
     };
 
     /// Create a default test environment
@@ -66,6 +116,19 @@ mod tests {
                 pid: std::process::id(),
                 openai_key: Default::default(),
                 provider: Provider::anthropic("test-key"),
+                workspace_roots: Vec::new(),
+                restricted: false,
+                read_only: false,
+                first_token_timeout: Default::default(),
+                question_timeout: Default::default(),
+                line_ending: None,
+                proxy: Default::default(),
+                tls: Default::default(),
+                http_timeout: Default::default(),
+                provider_preferences: None,
+                prompt_cache: false,
+                transformers: None,
+                scan_workers: None,
             },
         }
     }
@@ -109,6 +172,33 @@ mod tests {
         async fn search(&self, _query: Query) -> anyhow::Result<Vec<Point<Suggestion>>> {
             unimplemented!()
         }
+
+        async fn delete(&self, _ids: Vec<PointId>) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn clear(&self) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl VectorIndex<DocumentChunk> for Stub {
+        async fn store(&self, _information: Point<DocumentChunk>) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn search(&self, _query: Query) -> anyhow::Result<Vec<Point<DocumentChunk>>> {
+            unimplemented!()
+        }
+
+        async fn delete(&self, _ids: Vec<PointId>) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn clear(&self) -> anyhow::Result<()> {
+            unimplemented!()
+        }
     }
 
     #[async_trait::async_trait]
@@ -137,6 +227,14 @@ mod tests {
             unimplemented!()
         }
 
+        async fn restore_snapshot(&self, _: &Path, _: &SnapshotSelector) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn read_snapshot(&self, _: &Path, _: &SnapshotSelector) -> anyhow::Result<Vec<u8>> {
+            unimplemented!()
+        }
+
         async fn get_snapshot_by_timestamp(
             &self,
             _: &Path,
@@ -156,6 +254,10 @@ mod tests {
         async fn purge_older_than(&self, _: u32) -> anyhow::Result<usize> {
             unimplemented!()
         }
+
+        async fn rename_snapshots(&self, _: &Path, _: &Path) -> anyhow::Result<usize> {
+            unimplemented!()
+        }
     }
 
     #[async_trait::async_trait]
@@ -174,6 +276,24 @@ mod tests {
         async fn remove(&self, _: &Path) -> anyhow::Result<()> {
             unimplemented!()
         }
+
+        async fn remove_dir(&self, _: &Path, _: bool) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl FsMoveService for Stub {
+        async fn rename(&self, _: &Path, _: &Path) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl FsCopyService for Stub {
+        async fn copy(&self, _: &Path, _: &Path, _: bool) -> anyhow::Result<()> {
+            unimplemented!()
+        }
     }
 
     #[async_trait::async_trait]
@@ -189,7 +309,10 @@ mod tests {
         type FsReadService = Stub;
         type FsWriteService = Stub;
         type FsRemoveService = Stub;
+        type FsMoveService = Stub;
+        type FsCopyService = Stub;
         type VectorIndex = Stub;
+        type DocumentIndex = Stub;
         type EmbeddingService = Stub;
         type FsMetaService = Stub;
         type FsSnapshotService = Stub;
@@ -211,6 +334,10 @@ mod tests {
             self
         }
 
+        fn document_index(&self) -> &Self::DocumentIndex {
+            self
+        }
+
         fn embedding_service(&self) -> &Self::EmbeddingService {
             self
         }
@@ -226,21 +353,71 @@ mod tests {
             self
         }
 
+        fn file_move_service(&self) -> &Self::FsMoveService {
+            self
+        }
+
+        fn file_copy_service(&self) -> &Self::FsCopyService {
+            self
+        }
+
         fn create_dirs_service(&self) -> &Self::FsCreateDirsService {
             self
         }
     }
 
+    fn names(tools: Vec<Tool>) -> Vec<ToolName> {
+        tools.into_iter().map(|tool| tool.definition.name).collect()
+    }
+
+    #[test]
+    fn test_read_only_mode_leaves_out_mutating_tools() {
+        let mut fixture = stub();
+        fixture.env.read_only = true;
+
+        let actual = names(tools(Arc::new(fixture)));
+
+        let expected = vec![
+            FSRead::tool_name(),
+            FSList::tool_name(),
+            FSSearch::tool_name(),
+            FSFileInfo::tool_name(),
+            SymbolSearch::tool_name(),
+            GitStatus::tool_name(),
+            GitDiff::tool_name(),
+            Think::tool_name(),
+            Fetch::tool_name(),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_mutating_tools_are_available_by_default() {
+        let actual = names(tools(Arc::new(stub())));
+
+        for name in [
+            FSWrite::<Stub>::tool_name(),
+            FSRemove::<Stub>::tool_name(),
+            ApplyPatchJson::<Stub>::tool_name(),
+            Shell::tool_name(),
+            RunTests::tool_name(),
+            Lint::tool_name(),
+            GitCommit::tool_name(),
+        ] {
+            assert!(actual.contains(&name), "{} is missing", name.as_str());
+        }
+    }
+
     #[test]
     fn test_tool_description_length() {
         const MAX_DESCRIPTION_LENGTH: usize = 1024;
 
-        println!("\nTool description lengths:");
+        println!("\nTool description lengths, examples included:");
 
         let mut any_exceeded = false;
         let stub = Arc::new(stub());
         for tool in tools(stub.clone()) {
-            let desc_len = tool.definition.description.len();
+            let desc_len = tool.definition.description_with_examples().len();
             println!(
                 "{:?}: {} chars {}",
                 tool.definition.name,
@@ -263,4 +440,34 @@ mod tests {
             MAX_DESCRIPTION_LENGTH
         );
     }
+
+    #[test]
+    fn test_tool_examples_match_schema() {
+        let stub = Arc::new(stub());
+        for tool in tools(stub) {
+            if let Err(err) = tool.definition.check_examples() {
+                panic!("{err}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_tools_with_examples() {
+        let stub = Arc::new(stub());
+
+        let actual = tools(stub)
+            .into_iter()
+            .filter(|tool| !tool.definition.examples.is_empty())
+            .map(|tool| tool.definition.name.into_string())
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            "tool_forge_fs_read",
+            "tool_forge_fs_create",
+            "tool_forge_fs_search",
+            "tool_forge_fs_patch",
+            "tool_forge_process_shell",
+        ];
+        assert_eq!(actual, expected);
+    }
 }
@@ -213,7 +213,7 @@ impl<T: Infrastructure> ExecutableTool for ApplyPatch<T> {
 
         // Generate diff between old and new content
         let diff = DiffFormat::format("patch", path.to_path_buf(), &old_content, &new_content);
-        println!("{}", diff);
+        forge_display::echo!("{}", diff);
 
         Ok(result)
     }
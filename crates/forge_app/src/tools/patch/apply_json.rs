@@ -3,7 +3,7 @@ use std::sync::Arc;
 
 use bytes::Bytes;
 use forge_display::DiffFormat;
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_domain::{ExecutableTool, LineEnding, NamedTool, ToolDescription, ToolName};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -13,8 +13,8 @@ use tokio::fs;
 
 // No longer using dissimilar for fuzzy matching
 use crate::tools::syn;
-use crate::tools::utils::assert_absolute_path;
-use crate::{FsWriteService, Infrastructure};
+use crate::tools::utils::{assert_absolute_path, display_paths, TextFormat};
+use crate::{EnvironmentService, FsWriteService, Infrastructure};
 
 // Removed fuzzy matching threshold as we only use exact matching now
 
@@ -204,12 +204,18 @@ pub struct Input {
 
     /// List of patch operations to apply in sequence
     pub patches: Vec<ApplyPatchJsonInput>,
+
+    /// Line ending of the inserted content: `lf`, `crlf`, or `preserve` to
+    /// insert it as it is. Leave unset to match the file's line endings.
+    #[serde(default)]
+    pub line_ending: Option<LineEnding>,
 }
 
 /// Performs a single text operation (prepend, append, replace, swap, delete) on
 /// matched text in a file. The operation is applied to the first match found in
 /// the text.
 #[derive(ToolDescription)]
+#[example = r#"{"path": "/home/user/project/src/main.rs", "patches": [{"search": "let retries = 3;", "operation": "replace", "content": "let retries = 5;"}]}"#]
 pub struct ApplyPatchJson<F>(Arc<F>);
 
 impl<F: Infrastructure> NamedTool for ApplyPatchJson<F> {
@@ -253,23 +259,40 @@ impl<F: Infrastructure> ExecutableTool for ApplyPatchJson<F> {
             .await
             .map_err(Error::FileOperation)?;
 
+        let env = self.0.environment_service().get_environment();
+        let display_path = display_paths(&env).format(path);
+
+        // The search text matches whatever the line endings, the content is
+        // inserted with the file's
+        let format = TextFormat::detect(&current_content);
+        let insert_format = format.line_ending(input.line_ending);
+        let mut converted = false;
+
         // Apply each patch sequentially
         for patch in input.patches {
             // Save the old content before modification for diff generation
             let old_content = current_content.clone();
 
+            let search = if current_content.contains(&patch.search) {
+                patch.search
+            } else {
+                format.convert_line_endings(&patch.search)
+            };
+            let content = insert_format.convert_line_endings(&patch.content);
+            converted |= content != patch.content;
+
             // Apply the replacement
-            current_content = apply_replacement(
-                current_content,
-                &patch.search,
-                &patch.operation,
-                &patch.content,
-            )?;
+            current_content =
+                apply_replacement(current_content, &search, &patch.operation, &content)?;
 
             // Generate diff between old and new content
-            let diff =
-                DiffFormat::format("patch", path.to_path_buf(), &old_content, &current_content);
-            println!("{}", diff);
+            let diff = DiffFormat::format(
+                "patch",
+                display_path.clone().into(),
+                &old_content,
+                &current_content,
+            );
+            forge_display::echo!("{}", diff);
         }
 
         // Write final content to file after all patches are applied
@@ -282,11 +305,17 @@ impl<F: Infrastructure> ExecutableTool for ApplyPatchJson<F> {
         let warning = syn::validate(path, &current_content).map(|e| e.to_string());
 
         // Format the output
-        let result = format_output(
+        let mut result = format_output(
             path.to_string_lossy().as_ref(),
             &current_content,
             warning.as_deref(),
         );
+        if converted {
+            result.push_str(&format!(
+                "Note: line endings of the inserted content were converted to {}\n",
+                insert_format.name()
+            ));
+        }
 
         // Return the final result
         Ok(result)
@@ -295,8 +324,12 @@ impl<F: Infrastructure> ExecutableTool for ApplyPatchJson<F> {
 
 #[cfg(test)]
 mod test {
+    use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::attachment::tests::MockInfrastructure;
+    use crate::tools::utils::TempDir;
+    use crate::FsReadService;
 
     // Enhanced test helper for running multiple operations
     #[derive(Debug)]
@@ -458,4 +491,63 @@ mod test {
     }
 
     // The previous individual tests are removed since they're now consolidated
+
+    /// Patches a file holding `original` on disk and returns the tool's
+    /// output along with the bytes written
+    async fn patch_file(
+        original: &str,
+        search: &str,
+        content: &str,
+        line_ending: Option<LineEnding>,
+    ) -> (String, String) {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("main.rs");
+        fs::write(&path, original).await.unwrap();
+        let infra = Arc::new(MockInfrastructure::new());
+
+        let output = ApplyPatchJson::new(infra.clone())
+            .call(Input {
+                path: path.to_string_lossy().to_string(),
+                patches: vec![ApplyPatchJsonInput {
+                    search: search.to_string(),
+                    operation: Operation::Replace,
+                    content: content.to_string(),
+                }],
+                line_ending,
+            })
+            .await
+            .unwrap();
+        let written = infra.file_read_service().read(&path).await.unwrap();
+        (output, String::from_utf8(written.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_lf_search_matches_crlf_file() {
+        let (output, actual) = patch_file(
+            "fn main() {\r\n    run();\r\n}\r\n",
+            "    run();\n}",
+            "    run();\n    stop();\n}",
+            None,
+        )
+        .await;
+
+        assert_eq!(actual, "fn main() {\r\n    run();\r\n    stop();\r\n}\r\n");
+        assert!(output.contains("converted to CRLF"));
+    }
+
+    #[tokio::test]
+    async fn test_crlf_search_matches_lf_file() {
+        let (_, actual) = patch_file("a\nb\nc\n", "a\r\nb\r\n", "a\r\nB\r\n", None).await;
+
+        assert_eq!(actual, "a\nB\nc\n");
+    }
+
+    #[tokio::test]
+    async fn test_patch_line_ending_input_wins() {
+        let (output, actual) =
+            patch_file("a\r\nb\r\n", "b", "b\nc", Some(LineEnding::Preserve)).await;
+
+        assert_eq!(actual, "a\r\nb\nc\r\n");
+        assert!(!output.contains("Note:"));
+    }
 }
@@ -1,25 +1,72 @@
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
+use std::time::Duration;
 
-use tokio::io::AsyncRead;
+use tokio::io::{AsyncRead, AsyncWriteExt};
 use tokio::process::Command;
 
+use super::process::{self, ProcessGroup, GRACE_PERIOD};
+
 /// A command executor that handles command creation and execution
 #[derive(Debug)]
 pub struct CommandExecutor {
     command: Command,
+    stdin: Option<String>,
+    timeout: Option<Duration>,
+    /// Names and values of the secrets passed to the command
+    secrets: Vec<(String, String)>,
+    silent: bool,
 }
 
 pub struct Output {
     pub stdout: String,
     pub stderr: String,
     pub success: bool,
+    /// `None` when the command was ended by a signal
+    pub exit_code: Option<i32>,
+    /// Number of bytes that were piped to the command's stdin, if any
+    pub stdin_bytes: Option<usize>,
+    /// How long the command ran before it was stopped, `None` when it
+    /// finished by itself
+    pub cancelled_after: Option<Duration>,
 }
 
 impl CommandExecutor {
     /// Create a new command executor with the specified command and working
     /// directory
     pub fn new(command: Command) -> Self {
-        Self { command }
+        Self {
+            command,
+            stdin: None,
+            timeout: None,
+            secrets: Vec::new(),
+            silent: false,
+        }
+    }
+
+    /// Pipe the given content to the command's stdin instead of inheriting it
+    pub fn stdin(mut self, content: impl Into<String>) -> Self {
+        self.stdin = Some(content.into());
+        self
+    }
+
+    /// Stop the command, along with every process it started, once it ran
+    /// for the given duration
+    pub fn timeout(mut self, duration: Duration) -> Self {
+        self.timeout = Some(duration);
+        self
+    }
+
+    /// Hide the values of the secrets passed to the command wherever it
+    /// prints them, in the echoed output as well as in the returned one
+    pub fn secrets(mut self, secrets: Vec<(String, String)>) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// Don't echo the output of the command, eg. when it's meant to be parsed
+    pub fn silent(mut self) -> Self {
+        self.silent = true;
+        self
     }
 
     /// Enable colored output for the command. bydefault it's disabled.
@@ -31,9 +78,15 @@ impl CommandExecutor {
     fn configure_pipes(&mut self) {
         // in order to stream the output of the command to stdout and stderr,
         // we need to set it to piped. but to pass the input to the child process
-        // we need to set the stdin to inherit.
+        // we need to set the stdin to inherit, unless the input is provided
+        // upfront.
+        let stdin = if self.stdin.is_some() {
+            std::process::Stdio::piped()
+        } else {
+            std::process::Stdio::inherit()
+        };
         self.command
-            .stdin(std::process::Stdio::inherit())
+            .stdin(stdin)
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
     }
@@ -42,39 +95,126 @@ impl CommandExecutor {
     /// stderr and it returns the captured output.
     pub async fn execute(mut self) -> anyhow::Result<Output> {
         self.configure_pipes();
+        let reads_terminal = self.stdin.is_none() && io::stdin().is_terminal();
+        let isolated = process::isolate(&mut self.command, reads_terminal);
 
         let mut child = self.command.spawn()?;
+        let mut group = ProcessGroup::register(child.id(), isolated);
+
+        // Write the input concurrently with reading the output, otherwise a
+        // command that fills its output pipe before consuming all of its input
+        // would never finish.
+        let stdin_bytes = self.stdin.as_ref().map(String::len);
+        let stdin_writer = match (self.stdin.take(), child.stdin.take()) {
+            (Some(content), Some(mut pipe)) => Some(tokio::spawn(async move {
+                let result = pipe.write_all(content.as_bytes()).await;
+                // Closing the pipe signals EOF to the child
+                drop(pipe);
+                match result {
+                    // The child is free to exit without reading all of its input
+                    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                    result => result,
+                }
+            })),
+            _ => None,
+        };
+
         let mut stdout_pipe = child.stdout.take();
         let mut stderr_pipe = child.stderr.take();
 
-        // stream the output of the command to stdout and stderr.
-        let (status, stdout, stderr) = tokio::try_join!(
-            child.wait(),
-            stream(&mut stdout_pipe, io::stdout()),
-            stream(&mut stderr_pipe, io::stderr())
-        )?;
+        // stream the output of the command to stdout and stderr, unless tool
+        // output isn't echoed.
+        let echo = !self.silent && forge_display::is_echo_enabled();
+        let (result, cancelled_after) = {
+            let run = async {
+                tokio::try_join!(
+                    child.wait(),
+                    stream(&mut stdout_pipe, io::stdout(), echo, &self.secrets),
+                    stream(&mut stderr_pipe, io::stderr(), echo, &self.secrets)
+                )
+            };
+            tokio::pin!(run);
+            let limit = self.timeout;
+            let timeout = async move {
+                match limit {
+                    Some(duration) => tokio::time::sleep(duration).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            let finished = tokio::select! {
+                result = &mut run => Some(result),
+                _ = timeout => None,
+            };
+            match finished {
+                Some(result) => (result, None),
+                None => {
+                    let elapsed = group.elapsed();
+                    // The output is read until the stopped processes close their
+                    // pipes, unless one of them left the group and keeps them open
+                    let (result, _) = tokio::join!(
+                        tokio::time::timeout(GRACE_PERIOD * 2, &mut run),
+                        group.stop()
+                    );
+                    let result = result.unwrap_or_else(|_| {
+                        Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "The stopped command didn't close its output",
+                        ))
+                    });
+                    (result, Some(elapsed))
+                }
+            }
+        };
+        let (status, stdout, stderr) = result?;
+        group.finish();
 
         // Drop happens after `try_join` due to <https://github.com/tokio-rs/tokio/issues/4309>
         drop(stdout_pipe);
         drop(stderr_pipe);
 
+        if let Some(writer) = stdin_writer {
+            writer.await??;
+        }
+
         // Helper function to process output bytes into string.
-        let process_output = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+        let process_output = |bytes: &[u8]| scrub(&String::from_utf8_lossy(bytes), &self.secrets);
 
         Ok(Output {
             success: status.success(),
+            exit_code: status.code(),
             stdout: process_output(&stdout),
             stderr: process_output(&stderr),
+            stdin_bytes,
+            cancelled_after,
         })
     }
 }
 
-/// reads the output from A and writes it to W
+/// Replaces the values of the secrets wherever they appear in the text.
+/// Unlike [`forge_domain::Redactor`] short values are replaced too, they are
+/// known to be secret.
+fn scrub(text: &str, secrets: &[(String, String)]) -> String {
+    secrets
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .fold(text.to_string(), |text, (name, value)| {
+            text.replace(value.as_str(), &format!("[secret:{name}]"))
+        })
+}
+
+/// reads the output from A and writes it to W when `echo` is set. With
+/// secrets, only complete lines are echoed, scrubbed, so that no value is
+/// split between two reads.
 async fn stream<A: AsyncRead + Unpin, W: Write>(
     io: &mut Option<A>,
     mut writer: W,
+    echo: bool,
+    secrets: &[(String, String)],
 ) -> io::Result<Vec<u8>> {
     let mut output = Vec::new();
+    // Start of the line that wasn't echoed yet
+    let mut echoed = 0;
     use tokio::io::AsyncReadExt;
     if let Some(io) = io.as_mut() {
         let mut buff = [0; 1024];
@@ -83,11 +223,60 @@ async fn stream<A: AsyncRead + Unpin, W: Write>(
             if n == 0 {
                 break;
             }
-            writer.write_all(&buff[..n])?;
-            // note: flush is necessary else we get the cursor could not be found error.
-            writer.flush()?;
             output.extend_from_slice(&buff[..n]);
+            if echo && secrets.is_empty() {
+                writer.write_all(&buff[..n])?;
+                // note: flush is necessary else we get the cursor could not be found error.
+                writer.flush()?;
+            } else if echo {
+                if let Some(end) = output[echoed..].iter().rposition(|byte| *byte == b'\n') {
+                    let lines = String::from_utf8_lossy(&output[echoed..=echoed + end]);
+                    writer.write_all(scrub(&lines, secrets).as_bytes())?;
+                    writer.flush()?;
+                    echoed += end + 1;
+                }
+            }
         }
     }
+    if echo && !secrets.is_empty() && echoed < output.len() {
+        let rest = String::from_utf8_lossy(&output[echoed..]);
+        writer.write_all(scrub(&rest, secrets).as_bytes())?;
+        writer.flush()?;
+    }
     Ok(output)
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_echo_hides_secrets_split_between_reads() {
+        let secrets = [("NPM_TOKEN".to_string(), "npm_abc".to_string())];
+        let mut reader = Some((&b"token=npm_"[..]).chain(&b"abc\nagain npm_abc"[..]));
+        let mut echoed = Vec::new();
+
+        let output = stream(&mut reader, &mut echoed, true, &secrets)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(echoed).unwrap(),
+            "token=[secret:NPM_TOKEN]\nagain [secret:NPM_TOKEN]"
+        );
+        assert_eq!(output, b"token=npm_abc\nagain npm_abc");
+    }
+
+    #[tokio::test]
+    async fn test_echo_without_secrets_is_unchanged() {
+        let mut reader = Some(&b"partial line"[..]);
+        let mut echoed = Vec::new();
+
+        stream(&mut reader, &mut echoed, true, &[]).await.unwrap();
+
+        assert_eq!(echoed, b"partial line");
+    }
+}
@@ -1,4 +1,7 @@
 mod executor;
+mod process;
 mod shell_tool;
 
+pub use executor::CommandExecutor;
+pub use process::stop_running_commands;
 pub use shell_tool::*;
@@ -0,0 +1,295 @@
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::process::Command;
+
+/// How long the processes of a stopped command get to exit after SIGTERM
+/// before they are killed
+pub const GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Commands that are running or being stopped
+static GROUPS: Mutex<BTreeSet<Target>> = Mutex::new(BTreeSet::new());
+
+/// What is signalled to stop a command
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Target {
+    /// Id of the command's process, which leads its group when it has one
+    id: u32,
+    /// Whether the command runs in a process group of its own
+    group: bool,
+}
+
+/// Runs the command in a process group of its own, so that it can be stopped
+/// along with every process it started. A command that reads the terminal
+/// stays in forge's group instead, since the terminal stops background
+/// groups that read it; only its own process can be stopped then. Returns
+/// whether the command was isolated.
+pub fn isolate(command: &mut Command, reads_terminal: bool) -> bool {
+    if reads_terminal {
+        return false;
+    }
+    #[cfg(unix)]
+    command.process_group(0);
+    // Windows has no process groups to signal, the process tree of the
+    // command is killed instead
+    #[cfg(not(unix))]
+    let _ = command;
+    true
+}
+
+/// The process group of a running command. Dropping it before
+/// [`ProcessGroup::finish`] stops the group in the background, which is what
+/// happens when the turn running the command is cancelled.
+pub struct ProcessGroup {
+    id: Option<Target>,
+    started: Instant,
+}
+
+impl ProcessGroup {
+    /// Registers the spawned command, along with its group when it was
+    /// isolated. `id` is `None` when the command already exited.
+    pub fn register(id: Option<u32>, isolated: bool) -> Self {
+        let id = id.map(|id| Target { id, group: isolated });
+        if let Some(id) = id {
+            lock().insert(id);
+        }
+        Self { id, started: Instant::now() }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Stops every process of the group, waiting up to the grace period for
+    /// them to exit before killing them
+    pub async fn stop(&mut self) {
+        if let Some(id) = self.id.take() {
+            let _ = tokio::task::spawn_blocking(move || terminate(&[id])).await;
+        }
+    }
+
+    /// Unregisters the group of a command that exited by itself
+    pub fn finish(mut self) {
+        if let Some(id) = self.id.take() {
+            lock().remove(&id);
+        }
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            // Signalled right away, the escalation can't block the runtime
+            signal(id, Signal::Terminate);
+            std::thread::spawn(move || terminate(&[id]));
+        }
+    }
+}
+
+/// Stops the commands of the shell tool that are still running, along with
+/// every process they started, so that none outlives forge. Returns how many
+/// commands were stopped.
+pub fn stop_running_commands() -> usize {
+    let ids = lock().iter().copied().collect::<Vec<_>>();
+    terminate(&ids);
+    ids.len()
+}
+
+/// Sends SIGTERM to the groups and SIGKILL to those still alive after the
+/// grace period, then unregisters them. The leaders are reaped by whoever
+/// waits on them, the processes they started by init once they are orphaned.
+fn terminate(ids: &[Target]) {
+    let mut alive = ids
+        .iter()
+        .copied()
+        .filter(|id| signal(*id, Signal::Terminate))
+        .collect::<Vec<_>>();
+    let deadline = Instant::now() + GRACE_PERIOD;
+    while !alive.is_empty() && Instant::now() < deadline {
+        std::thread::sleep(POLL_INTERVAL);
+        alive.retain(|id| is_alive(*id));
+    }
+    for id in alive {
+        signal(id, Signal::Kill);
+    }
+
+    let mut groups = lock();
+    for id in ids {
+        groups.remove(id);
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, BTreeSet<Target>> {
+    GROUPS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[derive(Clone, Copy)]
+enum Signal {
+    Terminate,
+    Kill,
+}
+
+/// Signals every process of the group, or the command's own process when it
+/// has no group, returns whether any was found
+#[cfg(unix)]
+fn signal(target: Target, signal: Signal) -> bool {
+    let signal = match signal {
+        Signal::Terminate => libc::SIGTERM,
+        Signal::Kill => libc::SIGKILL,
+    };
+    let id = target.id as libc::pid_t;
+    // SAFETY: killpg and kill have no memory safety requirements
+    unsafe {
+        if target.group {
+            libc::killpg(id, signal) == 0
+        } else {
+            libc::kill(id, signal) == 0
+        }
+    }
+}
+
+/// Kills the process tree of the command, Windows has no graceful
+/// equivalent of SIGTERM for console processes
+#[cfg(not(unix))]
+fn signal(target: Target, _signal: Signal) -> bool {
+    let _ = std::process::Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &target.id.to_string()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+    false
+}
+
+/// Whether any process of the target still exists, including zombies that
+/// weren't reaped yet
+#[cfg(unix)]
+fn is_alive(target: Target) -> bool {
+    let id = target.id as libc::pid_t;
+    // SAFETY: signal 0 only checks whether the target can be signalled
+    unsafe {
+        if target.group {
+            libc::killpg(id, 0) == 0
+        } else {
+            libc::kill(id, 0) == 0
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn is_alive(_target: Target) -> bool {
+    false
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::tools::shell::CommandExecutor;
+
+    fn command(script: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]).kill_on_drop(true);
+        command
+    }
+
+    fn group(id: u32) -> Target {
+        Target { id, group: true }
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool, within: Duration) -> bool {
+        let deadline = Instant::now() + within;
+        while !condition() && Instant::now() < deadline {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        condition()
+    }
+
+    #[tokio::test]
+    async fn test_timeout_stops_the_process_group() {
+        let started = Instant::now();
+
+        let actual = CommandExecutor::new(command("echo $$; sleep 30 & sleep 30"))
+            .stdin("")
+            .timeout(Duration::from_millis(300))
+            .execute()
+            .await
+            .unwrap();
+
+        let id: u32 = actual.stdout.trim().parse().unwrap();
+        let cancelled_after = actual.cancelled_after.unwrap();
+        assert!(cancelled_after >= Duration::from_millis(300));
+        assert!(!actual.success);
+        assert!(!is_alive(group(id)));
+        assert!(started.elapsed() < GRACE_PERIOD);
+        assert!(!lock().contains(&group(id)));
+    }
+
+    #[tokio::test]
+    async fn test_processes_ignoring_sigterm_are_killed() {
+        let actual = CommandExecutor::new(command("trap '' TERM; echo $$; sleep 30"))
+            .stdin("")
+            .timeout(Duration::from_millis(300))
+            .execute()
+            .await
+            .unwrap();
+
+        let id: u32 = actual.stdout.trim().parse().unwrap();
+        assert!(actual.cancelled_after.is_some());
+        assert!(wait_until(|| !is_alive(group(id)), Duration::from_secs(1)).await);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_command_is_stopped() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let script = format!("echo $$ > {}; sleep 30 & sleep 30", pid_file.display());
+        let task = tokio::spawn(CommandExecutor::new(command(&script)).stdin("").execute());
+
+        let mut id = None;
+        while id.is_none() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            id = std::fs::read_to_string(&pid_file)
+                .ok()
+                .and_then(|pid| pid.trim().parse::<u32>().ok());
+        }
+        let id = id.unwrap();
+        assert!(lock().contains(&group(id)));
+
+        task.abort();
+
+        assert!(wait_until(|| !is_alive(group(id)), GRACE_PERIOD).await);
+        assert!(wait_until(|| !lock().contains(&group(id)), GRACE_PERIOD * 2).await);
+    }
+
+    #[tokio::test]
+    async fn test_command_reading_the_terminal_stays_in_the_foreground_group() {
+        let mut fixture = command("sleep 30");
+
+        let isolated = isolate(&mut fixture, true);
+        let mut child = fixture.spawn().unwrap();
+        // SAFETY: getpgid and getpgrp have no memory safety requirements
+        let actual = unsafe { libc::getpgid(child.id().unwrap() as libc::pid_t) };
+        child.kill().await.unwrap();
+
+        assert!(!isolated);
+        assert_eq!(actual, unsafe { libc::getpgrp() });
+    }
+
+    #[tokio::test]
+    async fn test_command_without_a_group_is_stopped_alone() {
+        let mut fixture = command("sleep 30");
+        let isolated = isolate(&mut fixture, true);
+        let mut child = fixture.spawn().unwrap();
+        let id = child.id().unwrap();
+        let mut group = ProcessGroup::register(Some(id), isolated);
+
+        let (status, _) = tokio::join!(child.wait(), group.stop());
+
+        assert!(!status.unwrap().success());
+        assert!(!lock().contains(&Target { id, group: false }));
+    }
+}
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::bail;
+use forge_display::format_size;
 use forge_domain::{Environment, ExecutableTool, NamedTool, ToolDescription, ToolName};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
@@ -9,6 +12,7 @@ use tokio::process::Command;
 
 use super::executor::Output;
 use crate::tools::shell::executor::CommandExecutor;
+use crate::SecretStore;
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ShellInput {
@@ -16,13 +20,44 @@ pub struct ShellInput {
     pub command: String,
     /// The working directory where the command should be executed.
     pub cwd: PathBuf,
+    /// Optional content piped to the command's standard input. Prefer this
+    /// over heredocs for passing generated content to a command.
+    pub stdin: Option<String>,
+    /// Optional environment variables for this invocation only, layered over
+    /// the inherited environment. A value of `secret:NAME` is replaced with
+    /// the user's stored secret NAME, whose value is never shown.
+    pub env: Option<HashMap<String, String>>,
+    /// Optional number of seconds after which the command is stopped, along
+    /// with every process it started. Set it for commands that may not exit
+    /// by themselves, such as servers and watchers.
+    pub timeout: Option<u64>,
+}
+
+/// Maximum size of the content that can be piped to a command's stdin
+const MAX_STDIN_BYTES: usize = 1024 * 1024;
+
+/// Variables that can't be overridden in restricted mode, as they change
+/// which programs or libraries get executed.
+const BLOCKED_ENV_VARS: &[&str] = &["PATH", "BASH_ENV", "ENV", "SHELL", "SHELLOPTS", "IFS"];
+
+/// Prefixes of the variables that can't be overridden in restricted mode:
+/// the dynamic loaders' settings and bash's exported functions.
+const BLOCKED_ENV_PREFIXES: &[&str] = &["LD_", "DYLD_", "BASH_FUNC_"];
+
+fn is_blocked_env_var(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    BLOCKED_ENV_VARS.contains(&key.as_str())
+        || BLOCKED_ENV_PREFIXES
+            .iter()
+            .any(|prefix| key.starts_with(prefix))
 }
 
 /// Formats command output by wrapping non-empty stdout/stderr in XML tags.
 /// stderr is commonly used for warnings and progress info, so success is
 /// determined by exit status, not stderr presence. Returns Ok(output) on
 /// success or Err(output) on failure, with a status message if both streams are
-/// empty.
+/// empty. A failure ends with the command's exit code, or with how long it ran
+/// when it was stopped.
 fn format_output(output: Output) -> anyhow::Result<String> {
     let mut formatted_output = String::new();
 
@@ -37,7 +72,7 @@ fn format_output(output: Output) -> anyhow::Result<String> {
         formatted_output.push_str(&format!("<stderr>{}</stderr>", output.stderr));
     }
 
-    let result = if formatted_output.is_empty() {
+    let mut result = if formatted_output.is_empty() {
         if output.success {
             "Command executed successfully with no output.".to_string()
         } else {
@@ -47,27 +82,45 @@ fn format_output(output: Output) -> anyhow::Result<String> {
         formatted_output
     };
 
+    if let Some(bytes) = output.stdin_bytes {
+        result.push_str(&format!("\n<stdin_bytes>{}</stdin_bytes>", bytes));
+    }
+
+    if let Some(elapsed) = output.cancelled_after {
+        result.push_str(&format!(
+            "\n<cancelled>The command was stopped after running for {:.1}s</cancelled>",
+            elapsed.as_secs_f64()
+        ));
+        return Err(anyhow::anyhow!(result));
+    }
     if output.success {
-        Ok(result)
-    } else {
-        Err(anyhow::anyhow!(result))
+        return Ok(result);
+    }
+    if let Some(exit_code) = output.exit_code {
+        result.push_str(&format!("\n<exit_code>{}</exit_code>", exit_code));
     }
+    Err(anyhow::anyhow!(result))
 }
 
 /// Execute shell commands with safety checks and validation. By default, uses
 /// restricted bash (rbash) for enhanced security, preventing potentially
 /// dangerous operations like absolute path execution and directory changes.
 /// When a command requires unrestricted access, suggest the user to run the
-/// forge CLI with the `-u` flag.
+/// forge CLI with the `-u` flag. A `cwd` of `scratch:` runs the command in the
+/// conversation's scratch directory ({{scratch_dir}}). Credentials the
+/// command needs are passed as `env` values of `secret:NAME`, never written
+/// into the command; an unknown name fails the call.
 #[derive(ToolDescription)]
+#[example = r#"{"command": "cargo test --package core", "cwd": "/home/user/project"}"#]
 pub struct Shell {
     env: Environment,
+    secrets: SecretStore,
 }
 
 impl Shell {
     /// Create a new Shell with environment configuration
     pub fn new(env: Environment) -> Self {
-        Self { env }
+        Self { secrets: SecretStore::from_env(&env), env }
     }
 }
 
@@ -87,6 +140,40 @@ impl ExecutableTool for Shell {
             bail!("Command string is empty or contains only whitespace".to_string());
         }
 
+        if let Some(stdin) = &input.stdin {
+            if stdin.len() > MAX_STDIN_BYTES {
+                bail!(
+                    "stdin is {} ({} bytes) which exceeds the limit of {}",
+                    format_size(stdin.len() as u64),
+                    stdin.len(),
+                    format_size(MAX_STDIN_BYTES as u64)
+                );
+            }
+        }
+
+        if self.env.restricted {
+            let blocked = input
+                .env
+                .iter()
+                .flatten()
+                .map(|(key, _)| key)
+                .filter(|key| is_blocked_env_var(key))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !blocked.is_empty() {
+                bail!(
+                    "Setting {} is not allowed in restricted mode",
+                    blocked.join(", ")
+                );
+            }
+        }
+
+        // Secrets are resolved last so that nothing before needs their values
+        let (vars, secrets) = match input.env {
+            Some(vars) => self.secrets.resolve(vars)?,
+            None => Default::default(),
+        };
+
         let parameter = if cfg!(target_os = "windows") {
             "/C"
         } else {
@@ -97,7 +184,7 @@ impl ExecutableTool for Shell {
         {
             use forge_display::TitleFormat;
 
-            println!(
+            forge_display::echo!(
                 "\n{}",
                 TitleFormat::execute(format!(
                     "{} {} {}",
@@ -116,7 +203,17 @@ impl ExecutableTool for Shell {
         // Kill the command when the handler is dropped
         command.kill_on_drop(true);
 
-        format_output(CommandExecutor::new(command).colored().execute().await?)
+        command.envs(vars);
+
+        let mut executor = CommandExecutor::new(command).colored().secrets(secrets);
+        if let Some(stdin) = input.stdin {
+            executor = executor.stdin(stdin);
+        }
+        if let Some(timeout) = input.timeout {
+            executor = executor.timeout(Duration::from_secs(timeout));
+        }
+
+        format_output(executor.execute().await?)
     }
 }
 
@@ -146,6 +243,19 @@ mod tests {
             qdrant_cluster: None,
             pid: std::process::id(),
             openai_key: None,
+            workspace_roots: Vec::new(),
+            restricted: false,
+            read_only: false,
+            first_token_timeout: Default::default(),
+            question_timeout: Default::default(),
+            line_ending: None,
+            proxy: Default::default(),
+            tls: Default::default(),
+            http_timeout: Default::default(),
+            provider_preferences: None,
+            prompt_cache: false,
+            transformers: None,
+            scan_workers: None,
         }
     }
 
@@ -170,6 +280,9 @@ mod tests {
             .call(ShellInput {
                 command: "echo 'Hello, World!'".to_string(),
                 cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: None,
+                timeout: None,
             })
             .await
             .unwrap();
@@ -188,6 +301,9 @@ mod tests {
                     "echo 'to stderr' >&2; echo 'to stdout'".to_string()
                 },
                 cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: None,
+                timeout: None,
             })
             .await
             .unwrap();
@@ -205,6 +321,9 @@ mod tests {
             .call(ShellInput {
                 command: "echo 'to stdout' && echo 'to stderr' >&2".to_string(),
                 cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: None,
+                timeout: None,
             })
             .await
             .unwrap();
@@ -228,6 +347,9 @@ mod tests {
                     "pwd".to_string()
                 },
                 cwd: temp_dir.clone(),
+                stdin: None,
+                env: None,
+                timeout: None,
             })
             .await
             .unwrap();
@@ -241,6 +363,9 @@ mod tests {
             .call(ShellInput {
                 command: "non_existent_command".to_string(),
                 cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: None,
+                timeout: None,
             })
             .await;
 
@@ -263,7 +388,13 @@ mod tests {
     async fn test_shell_empty_command() {
         let shell = Shell::new(test_env());
         let result = shell
-            .call(ShellInput { command: "".to_string(), cwd: env::current_dir().unwrap() })
+            .call(ShellInput {
+                command: "".to_string(),
+                cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: None,
+                timeout: None,
+            })
             .await;
         assert!(result.is_err());
         assert_eq!(
@@ -289,6 +420,9 @@ mod tests {
                     "pwd".to_string()
                 },
                 cwd: current_dir.clone(),
+                stdin: None,
+                env: None,
+                timeout: None,
             })
             .await
             .unwrap();
@@ -306,6 +440,9 @@ mod tests {
             .call(ShellInput {
                 command: "echo 'first' && echo 'second'".to_string(),
                 cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: None,
+                timeout: None,
             })
             .await
             .unwrap();
@@ -319,6 +456,9 @@ mod tests {
             .call(ShellInput {
                 command: "true".to_string(),
                 cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: None,
+                timeout: None,
             })
             .await
             .unwrap();
@@ -334,6 +474,9 @@ mod tests {
             .call(ShellInput {
                 command: "echo ''".to_string(),
                 cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: None,
+                timeout: None,
             })
             .await
             .unwrap();
@@ -349,6 +492,9 @@ mod tests {
             .call(ShellInput {
                 command: "echo $PATH".to_string(),
                 cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: None,
+                timeout: None,
             })
             .await
             .unwrap();
@@ -368,7 +514,13 @@ mod tests {
         };
 
         let result = shell
-            .call(ShellInput { command: cmd.to_string(), cwd: env::current_dir().unwrap() })
+            .call(ShellInput {
+                command: cmd.to_string(),
+                cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: None,
+                timeout: None,
+            })
             .await;
 
         // In rbash, this would fail with a permission error
@@ -378,4 +530,206 @@ mod tests {
             "Full path commands should work in normal shell"
         );
     }
+
+    #[tokio::test]
+    async fn test_shell_stdin_is_piped() {
+        let shell = Shell::new(test_env());
+        let result = shell
+            .call(ShellInput {
+                command: if cfg!(target_os = "windows") {
+                    "findstr .*".to_string()
+                } else {
+                    "cat".to_string()
+                },
+                cwd: env::current_dir().unwrap(),
+                stdin: Some("piped content\n".to_string()),
+                env: None,
+                timeout: None,
+            })
+            .await
+            .unwrap();
+
+        assert!(result.contains("piped content"));
+        assert!(result.ends_with("<stdin_bytes>14</stdin_bytes>"));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[tokio::test]
+    async fn test_shell_timeout_reports_the_cancellation() {
+        let shell = Shell::new(test_env());
+        let result = shell
+            .call(ShellInput {
+                command: "echo started; sleep 30".to_string(),
+                cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: None,
+                timeout: Some(1),
+            })
+            .await;
+
+        let actual = result.unwrap_err().to_string();
+        assert!(
+            actual.starts_with(
+                "<stdout>started\n</stdout>\n<cancelled>The command was stopped after running for 1."
+            ),
+            "{actual}"
+        );
+        assert!(actual.ends_with("s</cancelled>"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_stdin_size_cap() {
+        let shell = Shell::new(test_env());
+        let result = shell
+            .call(ShellInput {
+                command: "cat".to_string(),
+                cwd: env::current_dir().unwrap(),
+                stdin: Some("a".repeat(MAX_STDIN_BYTES + 1)),
+                env: None,
+                timeout: None,
+            })
+            .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("exceeds the limit"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_env_is_visible_to_child() {
+        let shell = Shell::new(test_env());
+        let result = shell
+            .call(ShellInput {
+                command: if cfg!(target_os = "windows") {
+                    "echo %FORGE_TEST_VAR%".to_string()
+                } else {
+                    "echo $FORGE_TEST_VAR".to_string()
+                },
+                cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: Some(HashMap::from([(
+                    "FORGE_TEST_VAR".to_string(),
+                    "forge-value".to_string(),
+                )])),
+                timeout: None,
+            })
+            .await
+            .unwrap();
+
+        assert!(result.contains("forge-value"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_env_blocklist_in_restricted_mode() {
+        let shell = Shell::new(Environment { restricted: true, ..test_env() });
+        let result = shell
+            .call(ShellInput {
+                command: "echo hello".to_string(),
+                cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: Some(HashMap::from([(
+                    "LD_PRELOAD".to_string(),
+                    "/tmp/evil.so".to_string(),
+                )])),
+                timeout: None,
+            })
+            .await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Setting LD_PRELOAD is not allowed in restricted mode"
+        );
+    }
+
+    #[test]
+    fn test_env_vars_blocked_by_prefix() {
+        let actual = [
+            "LD_BIND_NOW",
+            "dyld_framework_path",
+            "BASH_FUNC_ls%%",
+            "LDFLAGS",
+        ]
+        .map(is_blocked_env_var);
+        assert_eq!(actual, [true, true, true, false]);
+    }
+
+    #[tokio::test]
+    async fn test_shell_secret_is_resolved_into_child_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let environment = Environment { base_path: dir.path().to_path_buf(), ..test_env() };
+        SecretStore::from_env(&environment)
+            .set("DB_URL", "postgres://reader:hunter2@db")
+            .unwrap();
+        let shell = Shell::new(environment);
+
+        let result = shell
+            .call(ShellInput {
+                command: r#"test "$DATABASE_URL" = "postgres://reader:hunter2@db" && echo matched"#
+                    .to_string(),
+                cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: Some(HashMap::from([(
+                    "DATABASE_URL".to_string(),
+                    "secret:DB_URL".to_string(),
+                )])),
+                timeout: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(result, "<stdout>matched\n</stdout>");
+    }
+
+    #[tokio::test]
+    async fn test_shell_secret_is_scrubbed_from_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let environment = Environment { base_path: dir.path().to_path_buf(), ..test_env() };
+        SecretStore::from_env(&environment)
+            .set("NPM_TOKEN", "npm_abc")
+            .unwrap();
+        let shell = Shell::new(environment);
+
+        let result = shell
+            .call(ShellInput {
+                command: "echo token=$TOKEN; echo $TOKEN >&2; exit 1".to_string(),
+                cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: Some(HashMap::from([(
+                    "TOKEN".to_string(),
+                    "secret:NPM_TOKEN".to_string(),
+                )])),
+                timeout: None,
+            })
+            .await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "<stdout>token=[secret:NPM_TOKEN]\n</stdout>\n<stderr>[secret:NPM_TOKEN]\n</stderr>\n<exit_code>1</exit_code>"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shell_unknown_secret() {
+        let dir = tempfile::tempdir().unwrap();
+        let shell = Shell::new(Environment { base_path: dir.path().to_path_buf(), ..test_env() });
+
+        let result = shell
+            .call(ShellInput {
+                command: "echo $TOKEN".to_string(),
+                cwd: env::current_dir().unwrap(),
+                stdin: None,
+                env: Some(HashMap::from([(
+                    "TOKEN".to_string(),
+                    "secret:NPM_TOKEN".to_string(),
+                )])),
+                timeout: None,
+            })
+            .await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Unknown secret 'NPM_TOKEN', add it with `forge secret set NPM_TOKEN`"
+        );
+    }
 }
@@ -0,0 +1,544 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Language, Parser, Query, QueryCursor};
+
+/// Kind of the symbols that reference a definition
+pub const REFERENCE: &str = "reference";
+
+/// A definition or reference of a symbol within a file. Lines are 1-based and
+/// inclusive.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    /// The kind of definition, e.g. `function` or `struct`, or `reference`
+    pub kind: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The first line of the definition, or the line of the reference
+    pub signature: String,
+}
+
+impl Symbol {
+    pub fn is_definition(&self) -> bool {
+        self.kind != REFERENCE
+    }
+}
+
+/// The symbols of a single file
+#[derive(Debug)]
+pub struct FileSymbols {
+    pub symbols: Vec<Symbol>,
+    /// Whether the language has reference queries, without them only
+    /// definitions are found
+    pub references: bool,
+}
+
+/// Grammar and symbol query of a language. Definition patterns capture the
+/// whole item as `@definition.<kind>` and its name as `@name`, reference
+/// patterns capture identifiers as `@reference`.
+struct SymbolLanguage {
+    name: &'static str,
+    language: Language,
+    query: &'static str,
+}
+
+impl SymbolLanguage {
+    fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        let (name, language, query): (_, Language, _) = match ext.as_str() {
+            "rs" => (
+                "rust",
+                tree_sitter_rust::LANGUAGE.into(),
+                include_str!("queries/rust.rkt"),
+            ),
+            "ts" | "js" => (
+                "typescript",
+                tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+                include_str!("queries/typescript.rkt"),
+            ),
+            "tsx" | "jsx" => (
+                "tsx",
+                tree_sitter_typescript::LANGUAGE_TSX.into(),
+                include_str!("queries/typescript.rkt"),
+            ),
+            "py" => (
+                "python",
+                tree_sitter_python::LANGUAGE.into(),
+                include_str!("queries/python.rkt"),
+            ),
+            "go" => (
+                "go",
+                tree_sitter_go::LANGUAGE.into(),
+                include_str!("queries/go.rkt"),
+            ),
+            _ => return None,
+        };
+        Some(Self { name, language, query })
+    }
+}
+
+/// Number of threads parsing files when none is configured
+pub fn default_workers() -> usize {
+    std::thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1)
+}
+
+struct CachedFile {
+    modified: SystemTime,
+    symbols: Arc<FileSymbols>,
+}
+
+/// The symbols of the supported files of a scan
+#[derive(Debug)]
+pub struct Scan {
+    /// Files in the order they were given
+    pub files: Vec<(PathBuf, Arc<FileSymbols>)>,
+    /// Files that weren't cached and had to be parsed
+    pub parsed: usize,
+    /// Largest number of files that were being read and parsed at the same
+    /// time
+    pub peak_workers: usize,
+    pub duration: Duration,
+}
+
+/// A file to parse, along with what's needed to cache it
+struct Pending {
+    position: usize,
+    path: PathBuf,
+    modified: SystemTime,
+    language: SymbolLanguage,
+}
+
+/// Symbols of the files that were searched, a file is only parsed again once
+/// its modification time changed
+#[derive(Default)]
+pub struct SymbolIndex {
+    files: Mutex<HashMap<PathBuf, CachedFile>>,
+}
+
+impl SymbolIndex {
+    /// Returns the symbols of the file, `None` when its language isn't
+    /// supported
+    pub async fn symbols(&self, path: &Path) -> Result<Option<Arc<FileSymbols>>> {
+        let scan = self.scan(&[path.to_path_buf()], 1).await?;
+        Ok(scan.files.into_iter().next().map(|(_, symbols)| symbols))
+    }
+
+    /// Returns the symbols of the supported files among `paths`, in the same
+    /// order. Files that changed since they were cached are read and parsed
+    /// by up to `workers` threads.
+    pub async fn scan(&self, paths: &[PathBuf], workers: usize) -> Result<Scan> {
+        let started = Instant::now();
+        let mut files = Vec::new();
+        let mut pending = Vec::new();
+        for path in paths {
+            let Some(language) = SymbolLanguage::from_path(path) else {
+                continue;
+            };
+
+            let modified = tokio::fs::metadata(path)
+                .await
+                .and_then(|metadata| metadata.modified())
+                .with_context(|| format!("Failed to read metadata of {}", path.display()))?;
+
+            let cached = self.cached(path, modified);
+            if cached.is_none() {
+                pending.push(Pending {
+                    position: files.len(),
+                    path: path.clone(),
+                    modified,
+                    language,
+                });
+            }
+            files.push((path.clone(), cached));
+        }
+
+        let parsed = pending.len();
+        let (results, peak_workers) = if pending.is_empty() {
+            (Vec::new(), 0)
+        } else {
+            tokio::task::spawn_blocking(move || parse_all(pending, workers)).await?
+        };
+        for (pending, symbols) in results {
+            let symbols = Arc::new(symbols?);
+            self.lock().insert(
+                pending.path,
+                CachedFile { modified: pending.modified, symbols: symbols.clone() },
+            );
+            files[pending.position].1 = Some(symbols);
+        }
+
+        let files = files
+            .into_iter()
+            .map(|(path, symbols)| (path, symbols.expect("every file was cached or parsed")))
+            .collect();
+        Ok(Scan { files, parsed, peak_workers, duration: started.elapsed() })
+    }
+
+    fn cached(&self, path: &Path, modified: SystemTime) -> Option<Arc<FileSymbols>> {
+        self.lock()
+            .get(path)
+            .filter(|cached| cached.modified == modified)
+            .map(|cached| cached.symbols.clone())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, CachedFile>> {
+        self.files.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Reads and parses the files on up to `workers` threads, which take the next
+/// file as soon as they're done with one. Results are in the order of
+/// `pending`, along with the largest number of files that were being parsed
+/// at once.
+fn parse_all(
+    pending: Vec<Pending>,
+    workers: usize,
+) -> (Vec<(Pending, Result<FileSymbols>)>, usize) {
+    let next = AtomicUsize::new(0);
+    let busy = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    let workers = workers.clamp(1, pending.len().max(1));
+
+    let mut parsed = std::thread::scope(|scope| {
+        let (pending, next, busy, peak) = (&pending, &next, &busy, &peak);
+        let handles = (0..workers)
+            .map(|_| {
+                scope.spawn(move || {
+                    let mut parsers = Parsers::default();
+                    let mut done = Vec::new();
+                    while let Some(file) = pending.get(next.fetch_add(1, Ordering::SeqCst)) {
+                        // Counts the workers actually parsing, not the ones started
+                        peak.fetch_max(busy.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                        let symbols = std::fs::read_to_string(&file.path)
+                            .with_context(|| format!("Failed to read {}", file.path.display()))
+                            .and_then(|content| {
+                                parsers.parse(&file.language, &content).with_context(|| {
+                                    format!("Failed to parse {}", file.path.display())
+                                })
+                            });
+                        busy.fetch_sub(1, Ordering::SeqCst);
+                        done.push((file.position, symbols));
+                    }
+                    done
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("symbol parser panicked"))
+            .collect::<HashMap<_, _>>()
+    });
+
+    let results = pending
+        .into_iter()
+        .map(|file| {
+            let symbols = parsed
+                .remove(&file.position)
+                .expect("every file was parsed");
+            (file, symbols)
+        })
+        .collect();
+    (results, peak.into_inner())
+}
+
+/// Parsers and compiled queries of a worker, created once per language
+#[derive(Default)]
+struct Parsers {
+    languages: HashMap<&'static str, (Parser, Query)>,
+}
+
+impl Parsers {
+    fn parse(&mut self, language: &SymbolLanguage, content: &str) -> Result<FileSymbols> {
+        let (parser, query) = match self.languages.entry(language.name) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut parser = Parser::new();
+                parser.set_language(&language.language)?;
+                let query = Query::new(&language.language, language.query)?;
+                entry.insert((parser, query))
+            }
+        };
+        parse(parser, query, content)
+    }
+}
+
+fn parse(parser: &mut Parser, query: &Query, content: &str) -> Result<FileSymbols> {
+    let tree = parser
+        .parse(content, None)
+        .ok_or_else(|| anyhow!("Failed to parse the file"))?;
+    let names = query.capture_names();
+    let lines = content.lines().collect::<Vec<_>>();
+    let line = |row: usize| lines.get(row).map(|line| line.trim()).unwrap_or_default();
+
+    // A name can be matched by several definition patterns, e.g. a variable
+    // holding an arrow function, the pattern listed first wins
+    let mut definitions: HashMap<Range<usize>, (usize, Symbol)> = HashMap::new();
+    let mut references = Vec::new();
+
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), content.as_bytes());
+    while let Some(m) = matches.next() {
+        let mut item = None;
+        let mut name = None;
+        for capture in m.captures {
+            match names[capture.index as usize] {
+                "name" => name = Some(capture.node),
+                REFERENCE => references.push(capture.node),
+                capture_name => {
+                    if let Some(kind) = capture_name.strip_prefix("definition.") {
+                        item = Some((kind, capture.node));
+                    }
+                }
+            }
+        }
+
+        let (Some((kind, item)), Some(name)) = (item, name) else {
+            continue;
+        };
+        let symbol = Symbol {
+            name: name.utf8_text(content.as_bytes())?.to_string(),
+            kind: kind.to_string(),
+            start_line: item.start_position().row + 1,
+            end_line: item.end_position().row + 1,
+            signature: line(item.start_position().row).to_string(),
+        };
+        match definitions.get(&name.byte_range()) {
+            Some((pattern, _)) if *pattern <= m.pattern_index => {}
+            _ => {
+                definitions.insert(name.byte_range(), (m.pattern_index, symbol));
+            }
+        }
+    }
+
+    let has_references = names.contains(&REFERENCE);
+    let mut symbols = references
+        .into_iter()
+        // The name of a definition isn't a reference to it
+        .filter(|node| !definitions.contains_key(&node.byte_range()))
+        .map(|node| {
+            let row = node.start_position().row;
+            Ok(Symbol {
+                name: node.utf8_text(content.as_bytes())?.to_string(),
+                kind: REFERENCE.to_string(),
+                start_line: row + 1,
+                end_line: node.end_position().row + 1,
+                signature: line(row).to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    symbols.extend(definitions.into_values().map(|(_, symbol)| symbol));
+    symbols.sort_by(|a, b| {
+        (a.start_line, !a.is_definition(), &a.name).cmp(&(
+            b.start_line,
+            !b.is_definition(),
+            &b.name,
+        ))
+    });
+
+    Ok(FileSymbols { symbols, references: has_references })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::tools::utils::TempDir;
+
+    fn kinds(symbols: &FileSymbols, name: &str) -> Vec<(String, usize)> {
+        symbols
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.name == name)
+            .map(|symbol| (symbol.kind.clone(), symbol.start_line))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_rust_definitions_and_references() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("lib.rs");
+        let fixture = "pub struct Repository {\n    id: u32,\n}\n\nimpl Repository {\n    pub fn new() -> Repository {\n        Repository { id: 0 }\n    }\n}\n";
+        tokio::fs::write(&path, fixture).await.unwrap();
+
+        let actual = SymbolIndex::default()
+            .symbols(&path)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let expected = vec![
+            ("struct".to_string(), 1),
+            ("reference".to_string(), 5),
+            ("reference".to_string(), 6),
+            ("reference".to_string(), 7),
+        ];
+        assert_eq!(kinds(&actual, "Repository"), expected);
+        let definition = &actual.symbols[0];
+        assert_eq!(
+            (definition.end_line, definition.signature.as_str()),
+            (3, "pub struct Repository {")
+        );
+        assert_eq!(kinds(&actual, "new"), vec![("function".to_string(), 6)]);
+        assert_eq!(kinds(&actual, "id")[0], ("field".to_string(), 2));
+        assert!(actual.references);
+    }
+
+    #[tokio::test]
+    async fn test_typescript_definitions_and_references() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("repository.ts");
+        let fixture = "export interface Repository {\n  find(id: string): string;\n}\n\nexport const open = (repo: Repository) => repo.find(\"1\");\nconst limit = 10;\n";
+        tokio::fs::write(&path, fixture).await.unwrap();
+
+        let actual = SymbolIndex::default()
+            .symbols(&path)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            kinds(&actual, "Repository"),
+            vec![("interface".to_string(), 1), ("reference".to_string(), 5)]
+        );
+        assert_eq!(
+            kinds(&actual, "find"),
+            vec![("method".to_string(), 2), ("reference".to_string(), 5)]
+        );
+        assert_eq!(kinds(&actual, "open"), vec![("function".to_string(), 5)]);
+        assert_eq!(kinds(&actual, "limit"), vec![("variable".to_string(), 6)]);
+    }
+
+    #[tokio::test]
+    async fn test_language_without_references() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("main.py");
+        tokio::fs::write(&path, "class Repository:\n    pass\n\nRepository()\n")
+            .await
+            .unwrap();
+
+        let actual = SymbolIndex::default()
+            .symbols(&path)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(kinds(&actual, "Repository"), vec![("class".to_string(), 1)]);
+        assert!(!actual.references);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_language() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.txt");
+        tokio::fs::write(&path, "Repository").await.unwrap();
+
+        let actual = SymbolIndex::default().symbols(&path).await.unwrap();
+
+        assert!(actual.is_none());
+    }
+
+    /// A tree of Rust files large enough to keep several workers busy
+    async fn tree(files: usize) -> (TempDir, Vec<PathBuf>) {
+        let temp_dir = TempDir::new().unwrap();
+        let content = (0..200)
+            .map(|i| format!("pub fn function_{i}(value: u32) -> u32 {{\n    value + {i}\n}}\n"))
+            .collect::<String>();
+        let mut paths = Vec::new();
+        for i in 0..files {
+            let path = temp_dir.path().join(format!("module_{i:03}.rs"));
+            tokio::fs::write(&path, &content).await.unwrap();
+            paths.push(path);
+        }
+        (temp_dir, paths)
+    }
+
+    #[tokio::test]
+    async fn test_scan_runs_workers_in_parallel() {
+        let (_temp_dir, paths) = tree(64).await;
+        let index = SymbolIndex::default();
+
+        let actual = index.scan(&paths, 4).await.unwrap();
+
+        assert_eq!(actual.parsed, 64);
+        assert!(actual.peak_workers > 1, "{}", actual.peak_workers);
+        assert!(actual.peak_workers <= 4, "{}", actual.peak_workers);
+        let scanned = actual
+            .files
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(scanned, paths);
+    }
+
+    #[tokio::test]
+    async fn test_scan_matches_sequential_parsing() {
+        let (_temp_dir, mut paths) = tree(16).await;
+        paths.reverse();
+
+        let sequential = SymbolIndex::default().scan(&paths, 1).await.unwrap();
+        let parallel = SymbolIndex::default().scan(&paths, 8).await.unwrap();
+
+        let symbols = |scan: &Scan| {
+            scan.files
+                .iter()
+                .map(|(path, symbols)| (path.clone(), symbols.symbols.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sequential.peak_workers, 1);
+        assert_eq!(symbols(&parallel), symbols(&sequential));
+    }
+
+    #[tokio::test]
+    async fn test_scan_skips_cached_files() {
+        let (_temp_dir, mut paths) = tree(3).await;
+        paths.push(paths[0].with_file_name("notes.txt"));
+        tokio::fs::write(&paths[3], "notes").await.unwrap();
+        let index = SymbolIndex::default();
+        index.scan(&paths, 2).await.unwrap();
+
+        let actual = index.scan(&paths, 2).await.unwrap();
+
+        assert_eq!((actual.parsed, actual.peak_workers), (0, 0));
+        assert_eq!(actual.files.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_symbols_are_cached_until_modified() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("lib.rs");
+        tokio::fs::write(&path, "fn first() {}\n").await.unwrap();
+        let index = SymbolIndex::default();
+
+        let first = index.symbols(&path).await.unwrap().unwrap();
+        let cached = index.symbols(&path).await.unwrap().unwrap();
+        assert!(Arc::ptr_eq(&first, &cached));
+
+        tokio::fs::write(&path, "fn second() {}\n").await.unwrap();
+        let modified = SystemTime::now() + Duration::from_secs(10);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let actual = index.symbols(&path).await.unwrap().unwrap();
+        assert!(!Arc::ptr_eq(&first, &actual));
+        assert_eq!(actual.symbols[0].name, "second");
+    }
+}
@@ -0,0 +1,382 @@
+mod index;
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use forge_display::TitleFormat;
+use forge_domain::{ExecutableTool, NamedTool, ToolContent, ToolDescription, ToolName};
+use forge_tool_macros::ToolDescription;
+pub use index::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::tools::fs::search_files;
+use crate::tools::utils::assert_absolute_path;
+
+/// Maximum number of matches returned when no limit is given
+const DEFAULT_LIMIT: usize = 50;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolRole {
+    Definition,
+    Reference,
+    #[default]
+    Any,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NameMatch {
+    /// The symbol's name equals the searched name
+    #[default]
+    Exact,
+    /// The symbol's name contains the searched name, ignoring case
+    Contains,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SymbolSearchInput {
+    /// Name of the symbol, e.g. `ConversationRepository`
+    pub name: String,
+    /// Whether to find definitions, references or both (default).
+    pub kind: Option<SymbolRole>,
+    /// Absolute path of the file or directory to search, defaults to the
+    /// current working directory.
+    pub path: Option<String>,
+    /// 'exact' (default) or 'contains' for a case-insensitive substring match
+    #[serde(default)]
+    pub name_match: NameMatch,
+    /// Maximum number of matches, defaults to 50. Definitions are listed
+    /// before references.
+    pub limit: Option<usize>,
+}
+
+/// A symbol found in a file
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SymbolMatch {
+    pub path: String,
+    #[serde(flatten)]
+    pub symbol: Symbol,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SymbolSearchOutput {
+    pub matches: Vec<SymbolMatch>,
+    /// Matches left out because of the limit
+    pub omitted: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    /// How long reading and parsing the files took
+    pub duration_ms: u128,
+}
+
+/// Finds where a symbol is defined or referenced using the syntax tree,
+/// unlike a text search it tells definitions apart from mentions. Returns the
+/// file, line range, kind and signature line of each match. Supports Rust,
+/// TypeScript and JavaScript; Python and Go support definitions only.
+#[derive(Default, ToolDescription)]
+pub struct SymbolSearch {
+    cwd: PathBuf,
+    index: SymbolIndex,
+    /// Threads parsing the files of a search
+    workers: usize,
+}
+
+impl SymbolSearch {
+    pub fn new(cwd: PathBuf, workers: usize) -> Self {
+        Self { cwd, index: SymbolIndex::default(), workers }
+    }
+
+    async fn search(&self, input: &SymbolSearchInput) -> anyhow::Result<SymbolSearchOutput> {
+        let root = input
+            .path
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.cwd.clone());
+        assert_absolute_path(&root)?;
+
+        let files = if root.is_dir() {
+            search_files(&root, None).await?
+        } else if root.exists() {
+            vec![root.clone()]
+        } else {
+            anyhow::bail!("Path '{}' does not exist", root.display())
+        };
+
+        let role = input.kind.unwrap_or_default();
+        let needle = input.name.to_lowercase();
+        let matches_name = |name: &str| match input.name_match {
+            NameMatch::Exact => name == input.name,
+            NameMatch::Contains => name.to_lowercase().contains(&needle),
+        };
+
+        let scan = self
+            .index
+            .scan(&files, self.workers)
+            .await
+            .context("Failed to index symbols")?;
+
+        let mut matches = Vec::new();
+        let mut without_references = BTreeSet::new();
+        for (file, symbols) in &scan.files {
+            if role != SymbolRole::Definition && !symbols.references {
+                if let Some(ext) = file.extension() {
+                    without_references.insert(ext.to_string_lossy().to_string());
+                }
+            }
+
+            matches.extend(
+                symbols
+                    .symbols
+                    .iter()
+                    .filter(|symbol| match role {
+                        SymbolRole::Definition => symbol.is_definition(),
+                        SymbolRole::Reference => !symbol.is_definition(),
+                        SymbolRole::Any => true,
+                    })
+                    .filter(|symbol| matches_name(&symbol.name))
+                    .map(|symbol| SymbolMatch {
+                        path: file.display().to_string(),
+                        symbol: symbol.clone(),
+                    }),
+            );
+        }
+
+        matches.sort_by(|a, b| {
+            (!a.symbol.is_definition(), &a.path, a.symbol.start_line).cmp(&(
+                !b.symbol.is_definition(),
+                &b.path,
+                b.symbol.start_line,
+            ))
+        });
+
+        let limit = input.limit.unwrap_or(DEFAULT_LIMIT);
+        let omitted = matches.len().saturating_sub(limit);
+        matches.truncate(limit);
+
+        let notes = without_references
+            .into_iter()
+            .map(|ext| {
+                format!(
+                    "References aren't supported for .{ext} files, only definitions were searched"
+                )
+            })
+            .collect();
+
+        Ok(SymbolSearchOutput {
+            matches,
+            omitted,
+            notes,
+            duration_ms: scan.duration.as_millis(),
+        })
+    }
+}
+
+impl NamedTool for SymbolSearch {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_symbol_search")
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecutableTool for SymbolSearch {
+    type Input = SymbolSearchInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        Ok(self.call_content(input).await?.to_string())
+    }
+
+    async fn call_content(&self, input: Self::Input) -> anyhow::Result<ToolContent> {
+        let output = self.search(&input).await?;
+
+        let path = input.path.as_deref().map(Path::new).unwrap_or(&self.cwd);
+        forge_display::echo!(
+            "{}",
+            TitleFormat::execute(format!("symbol '{}'", input.name))
+                .sub_title(format!(
+                    "{} ({} matches in {}ms)",
+                    path.display(),
+                    output.matches.len(),
+                    output.duration_ms
+                ))
+                .format()
+        );
+
+        Ok(ToolContent::json(serde_json::to_value(&output)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::tools::utils::TempDir;
+
+    /// A project with a Rust, a TypeScript and a Python file that define and
+    /// use `Repository`
+    async fn fixture() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src");
+        tokio::fs::create_dir_all(&src).await.unwrap();
+        tokio::fs::write(
+            src.join("repository.rs"),
+            "pub trait Repository {\n    fn get(&self) -> u32;\n}\n",
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(
+            src.join("service.rs"),
+            "use crate::Repository;\n\npub fn load(repo: &dyn Repository) -> u32 {\n    repo.get()\n}\n",
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(
+            src.join("client.ts"),
+            "export class Repository {}\nconst repository = new Repository();\n",
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(src.join("model.py"), "class Repository:\n    pass\n")
+            .await
+            .unwrap();
+        tokio::fs::write(src.join("notes.md"), "Repository")
+            .await
+            .unwrap();
+        temp_dir
+    }
+
+    fn input(name: &str, kind: Option<SymbolRole>) -> SymbolSearchInput {
+        SymbolSearchInput {
+            name: name.to_string(),
+            kind,
+            path: None,
+            name_match: NameMatch::Exact,
+            limit: None,
+        }
+    }
+
+    fn locations(output: &SymbolSearchOutput) -> Vec<(String, String, usize)> {
+        output
+            .matches
+            .iter()
+            .map(|m| {
+                let file = Path::new(&m.path).file_name().unwrap().to_string_lossy();
+                (file.to_string(), m.symbol.kind.clone(), m.symbol.start_line)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_definitions() {
+        let temp_dir = fixture().await;
+        let search = SymbolSearch::new(temp_dir.path().to_path_buf(), 2);
+
+        let actual = search
+            .search(&input("Repository", Some(SymbolRole::Definition)))
+            .await
+            .unwrap();
+
+        let expected = vec![
+            ("client.ts".to_string(), "class".to_string(), 1),
+            ("model.py".to_string(), "class".to_string(), 1),
+            ("repository.rs".to_string(), "trait".to_string(), 1),
+        ];
+        assert_eq!(locations(&actual), expected);
+        assert_eq!(actual.matches[2].symbol.signature, "pub trait Repository {");
+        assert_eq!(actual.matches[2].symbol.end_line, 3);
+        assert!(actual.notes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_references() {
+        let temp_dir = fixture().await;
+        let search = SymbolSearch::new(temp_dir.path().to_path_buf(), 2);
+
+        let actual = search
+            .search(&input("Repository", Some(SymbolRole::Reference)))
+            .await
+            .unwrap();
+
+        let expected = vec![
+            ("client.ts".to_string(), "reference".to_string(), 2),
+            ("service.rs".to_string(), "reference".to_string(), 1),
+            ("service.rs".to_string(), "reference".to_string(), 3),
+        ];
+        assert_eq!(locations(&actual), expected);
+        assert_eq!(
+            actual.notes,
+            vec!["References aren't supported for .py files, only definitions were searched"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_contains_ignores_case() {
+        let temp_dir = fixture().await;
+        let search = SymbolSearch::new(temp_dir.path().to_path_buf(), 2);
+
+        let actual = search
+            .search(&SymbolSearchInput {
+                name_match: NameMatch::Contains,
+                path: Some(temp_dir.path().join("src/client.ts").display().to_string()),
+                ..input("reposit", Some(SymbolRole::Definition))
+            })
+            .await
+            .unwrap();
+
+        let expected = vec![
+            ("client.ts".to_string(), "class".to_string(), 1),
+            ("client.ts".to_string(), "variable".to_string(), 2),
+        ];
+        assert_eq!(locations(&actual), expected);
+    }
+
+    #[tokio::test]
+    async fn test_limit_reports_omitted_matches() {
+        let temp_dir = fixture().await;
+        let search = SymbolSearch::new(temp_dir.path().to_path_buf(), 2);
+
+        let actual = search
+            .search(&SymbolSearchInput { limit: Some(2), ..input("Repository", None) })
+            .await
+            .unwrap();
+
+        // Definitions come first, the references are cut off
+        assert_eq!(actual.matches.len(), 2);
+        assert!(actual.matches.iter().all(|m| m.symbol.is_definition()));
+        assert_eq!(actual.omitted, 4);
+    }
+
+    #[tokio::test]
+    async fn test_output_does_not_depend_on_workers() {
+        let temp_dir = fixture().await;
+        let search = |workers| SymbolSearch::new(temp_dir.path().to_path_buf(), workers);
+
+        let sequential = search(1).search(&input("Repository", None)).await.unwrap();
+        let parallel = [
+            search(8).search(&input("Repository", None)).await.unwrap(),
+            search(8).search(&input("Repository", None)).await.unwrap(),
+        ];
+
+        for actual in parallel {
+            assert_eq!(actual.matches, sequential.matches);
+            assert_eq!(actual.notes, sequential.notes);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relative_path_is_rejected() {
+        let search = SymbolSearch::default();
+
+        let actual = search
+            .search(&SymbolSearchInput {
+                path: Some("src".to_string()),
+                ..input("Repository", None)
+            })
+            .await;
+
+        assert!(actual.is_err());
+    }
+}
@@ -0,0 +1,267 @@
+mod parse;
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use forge_display::TitleFormat;
+use forge_domain::{
+    Environment, ExecutableTool, NamedTool, ToolContent, ToolDescription, ToolName,
+};
+use forge_tool_macros::ToolDescription;
+pub use parse::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::tools::shell::CommandExecutor;
+use crate::tools::utils::{assert_absolute_path, display_command, display_paths, split_args};
+
+/// Lines of output returned when it couldn't be parsed
+const OUTPUT_TAIL_LINES: usize = 40;
+
+/// Seconds after which the tests are stopped when the call sets no timeout
+const DEFAULT_TIMEOUT_SECS: u64 = 10 * 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TestRunner {
+    /// `cargo test`
+    Cargo,
+    /// `npm test`, its output is parsed as Jest's
+    Npm,
+}
+
+impl TestRunner {
+    /// Picks the runner from the manifest found in the project's directory
+    fn detect(path: &Path) -> Option<Self> {
+        if path.join("Cargo.toml").is_file() {
+            Some(TestRunner::Cargo)
+        } else if path.join("package.json").is_file() {
+            Some(TestRunner::Npm)
+        } else {
+            None
+        }
+    }
+
+    /// The program and its arguments, see [`split_args`]
+    fn command(self, args: Option<&str>) -> anyhow::Result<Vec<String>> {
+        let args = split_args(args)?;
+        let mut command = match self {
+            TestRunner::Cargo => vec!["cargo".to_string(), "test".to_string()],
+            TestRunner::Npm => vec![NPM.to_string(), "test".to_string()],
+        };
+        if self == TestRunner::Npm && !args.is_empty() {
+            command.push("--".to_string());
+        }
+        command.extend(args);
+        Ok(command)
+    }
+
+    /// Files the runner itself writes in the project, not counting its build
+    /// output
+    fn written_paths(self, path: &Path) -> Vec<PathBuf> {
+        match self {
+            // Updated when the manifest changed
+            TestRunner::Cargo => vec![path.join("Cargo.lock")],
+            // The scripts of package.json can't be inspected
+            TestRunner::Npm => Vec::new(),
+        }
+    }
+
+    fn parse(self, output: &str) -> Option<TestReport> {
+        match self {
+            TestRunner::Cargo => parse_cargo(output),
+            TestRunner::Npm => parse_jest(output),
+        }
+    }
+}
+
+/// npm is a batch script on Windows, which can't be run without its extension
+const NPM: &str = if cfg!(windows) { "npm.cmd" } else { "npm" };
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RunTestsInput {
+    /// Absolute path of the project's directory, the tests run there
+    pub path: String,
+    /// 'cargo' or 'npm', detected from the project's Cargo.toml or
+    /// package.json when unset
+    pub runner: Option<TestRunner>,
+    /// Arguments added to the test command, e.g. the name of the tests to
+    /// run. They are passed to the runner as is, not through a shell.
+    pub args: Option<String>,
+    /// Optional number of seconds after which the tests are stopped, 600 by
+    /// default
+    pub timeout: Option<u64>,
+}
+
+impl RunTestsInput {
+    fn runner(&self, path: &Path) -> anyhow::Result<TestRunner> {
+        self.runner
+            .or_else(|| TestRunner::detect(path))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Found neither Cargo.toml nor package.json in '{}', set the runner",
+                    path.display()
+                )
+            })
+    }
+
+    /// Paths the test run modifies, checked against the protected paths
+    pub(crate) fn written_paths(&self) -> Vec<PathBuf> {
+        let path = PathBuf::from(&self.path);
+        self.runner(&path)
+            .map(|runner| runner.written_paths(&path))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunTestsOutput {
+    pub command: String,
+    /// Whether the test command exited successfully
+    pub success: bool,
+    /// Seconds after which the tests were stopped, when they didn't finish
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timed_out_after: Option<u64>,
+    #[serde(flatten)]
+    pub report: Option<TestReport>,
+    /// End of the output when no test results could be found in it, e.g.
+    /// because the tests didn't build
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+/// Runs the tests of a Rust or JavaScript project with `cargo test` or `npm
+/// test`. Returns how many tests passed, failed and were ignored, and the
+/// name, `file:line:column` and message of each failure. Prefer it over
+/// running the tests with the shell tool. When the output has no test results,
+/// e.g. because the tests didn't build, the end of the output is returned.
+#[derive(ToolDescription)]
+pub struct RunTests {
+    env: Environment,
+}
+
+impl RunTests {
+    pub fn new(env: Environment) -> Self {
+        Self { env }
+    }
+
+    async fn run(&self, input: &RunTestsInput) -> anyhow::Result<RunTestsOutput> {
+        let path = PathBuf::from(&input.path);
+        assert_absolute_path(&path)?;
+        let runner = input.runner(&path)?;
+        let command = runner.command(input.args.as_deref())?;
+        let (program, args) = command.split_first().expect("the command has a program");
+        let display = display_command(&command);
+
+        forge_display::echo!(
+            "{}",
+            TitleFormat::execute("test")
+                .sub_title(format!(
+                    "{} in {}",
+                    display,
+                    display_paths(&self.env).format(&path)
+                ))
+                .format()
+        );
+
+        let mut process = Command::new(program);
+        process
+            .args(args)
+            .current_dir(&path)
+            .kill_on_drop(true)
+            // Plain output is parsed, and the runners mustn't wait for input
+            .envs([
+                ("CARGO_TERM_COLOR", "never"),
+                ("NO_COLOR", "1"),
+                ("FORCE_COLOR", "0"),
+                ("CI", "true"),
+            ]);
+        let timeout = input.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS);
+        let output = CommandExecutor::new(process)
+            .stdin("")
+            .timeout(Duration::from_secs(timeout))
+            .execute()
+            .await
+            .with_context(|| format!("Failed to run {display}"))?;
+
+        let text = format!("{}\n{}", output.stdout, output.stderr);
+        let report = runner.parse(&text);
+        let output_tail = report.is_none().then(|| {
+            let lines = text.trim_end().lines().collect::<Vec<_>>();
+            lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..].join("\n")
+        });
+
+        Ok(RunTestsOutput {
+            command: display,
+            success: output.success,
+            timed_out_after: output.cancelled_after.map(|_| timeout),
+            report,
+            output: output_tail,
+        })
+    }
+}
+
+impl NamedTool for RunTests {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_process_test")
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecutableTool for RunTests {
+    type Input = RunTestsInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        Ok(self.call_content(input).await?.to_string())
+    }
+
+    async fn call_content(&self, input: Self::Input) -> anyhow::Result<ToolContent> {
+        let output = self.run(&input).await?;
+        Ok(ToolContent::json(serde_json::to_value(&output)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::tools::utils::TempDir;
+
+    #[test]
+    fn test_runner_is_detected_from_the_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let cargo = root.join("cargo");
+        let npm = root.join("npm");
+        std::fs::create_dir_all(&cargo).unwrap();
+        std::fs::create_dir_all(&npm).unwrap();
+        std::fs::write(cargo.join("Cargo.toml"), "[package]").unwrap();
+        std::fs::write(npm.join("package.json"), "{}").unwrap();
+
+        let actual = [cargo.as_path(), npm.as_path(), root.as_path()].map(TestRunner::detect);
+
+        assert_eq!(
+            actual,
+            [Some(TestRunner::Cargo), Some(TestRunner::Npm), None]
+        );
+    }
+
+    #[test]
+    fn test_command_with_args() {
+        let actual = [
+            TestRunner::Cargo.command(Some("parse")).unwrap(),
+            TestRunner::Cargo.command(Some(" ")).unwrap(),
+            TestRunner::Npm.command(Some("-t 'subtracts two'")).unwrap(),
+        ];
+
+        let expected = [
+            vec!["cargo", "test", "parse"],
+            vec!["cargo", "test"],
+            vec![NPM, "test", "--", "-t", "subtracts two"],
+        ];
+        assert_eq!(actual, expected);
+    }
+}
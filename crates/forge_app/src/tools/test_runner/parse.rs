@@ -0,0 +1,318 @@
+use regex::Regex;
+use serde::Serialize;
+
+/// Counts and failures of a test run, parsed from the test command's output
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct TestReport {
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    pub failures: Vec<TestFailure>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TestFailure {
+    pub name: String,
+    /// `file:line:column` where the test failed, when the output tells
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    pub message: String,
+}
+
+/// Parses the output of `cargo test`. The counts are summed over every test
+/// binary. Returns `None` when no binary reported its results, e.g. when the
+/// tests didn't build.
+pub fn parse_cargo(output: &str) -> Option<TestReport> {
+    let summary =
+        Regex::new(r"test result: \w+\. (\d+) passed; (\d+) failed; (\d+) ignored").unwrap();
+    let section = Regex::new(r"^---- (.+) stdout ----$").unwrap();
+    // Rust 1.73 moved the message after the location
+    let panic = Regex::new(r"^thread '.*' panicked at (\S+:\d+:\d+):$").unwrap();
+    let old_panic = Regex::new(r"^thread '.*' panicked at '(.*)', (\S+:\d+:\d+)$").unwrap();
+
+    let mut report = TestReport::default();
+    let mut found = false;
+    for captures in summary.captures_iter(output) {
+        found = true;
+        report.passed += captures[1].parse::<usize>().unwrap_or_default();
+        report.failed += captures[2].parse::<usize>().unwrap_or_default();
+        report.ignored += captures[3].parse::<usize>().unwrap_or_default();
+    }
+    if !found {
+        return None;
+    }
+
+    let lines = output.lines().collect::<Vec<_>>();
+    let mut i = 0;
+    while i < lines.len() {
+        let Some(name) = section.captures(lines[i]).map(|c| c[1].to_string()) else {
+            i += 1;
+            continue;
+        };
+        i += 1;
+        let start = i;
+        while i < lines.len() && !section.is_match(lines[i]) && lines[i] != "failures:" {
+            i += 1;
+        }
+        let body = &lines[start..i];
+
+        let failure = body.iter().enumerate().find_map(|(at, line)| {
+            if let Some(captures) = panic.captures(line) {
+                let message = body[at + 1..]
+                    .iter()
+                    .take_while(|line| !line.starts_with("note: "))
+                    .copied()
+                    .collect::<Vec<_>>();
+                Some((Some(captures[1].to_string()), join(&message)))
+            } else {
+                old_panic
+                    .captures(line)
+                    .map(|captures| (Some(captures[2].to_string()), captures[1].to_string()))
+            }
+        });
+        let (location, message) = failure.unwrap_or_else(|| (None, join(body)));
+        report
+            .failures
+            .push(TestFailure { name, location, message });
+    }
+
+    Some(report)
+}
+
+/// Parses the output of Jest, which `npm test` commonly runs. Returns `None`
+/// when there's no summary of the tests.
+pub fn parse_jest(output: &str) -> Option<TestReport> {
+    let summary = Regex::new(r"(?m)^Tests:\s+(.+)$").unwrap();
+    let count = Regex::new(r"(\d+) (passed|failed|skipped|todo)").unwrap();
+    let header = Regex::new(r"^\s*● (.+)$").unwrap();
+    let stack = Regex::new(r"^\s*at .*?\(?([^\s()]+:\d+:\d+)\)?$").unwrap();
+    let code_frame = Regex::new(r"^\s*>?\s*\d+ \|").unwrap();
+
+    let summary = summary.captures(output)?;
+    let mut report = TestReport::default();
+    for captures in count.captures_iter(&summary[1]) {
+        let n = captures[1].parse::<usize>().unwrap_or_default();
+        match &captures[2] {
+            "passed" => report.passed += n,
+            "failed" => report.failed += n,
+            _ => report.ignored += n,
+        }
+    }
+
+    let lines = output.lines().collect::<Vec<_>>();
+    let mut i = 0;
+    while i < lines.len() {
+        let Some(name) = header.captures(lines[i]).map(|c| c[1].to_string()) else {
+            i += 1;
+            continue;
+        };
+        i += 1;
+        let start = i;
+        while i < lines.len()
+            && !header.is_match(lines[i])
+            && !lines[i].starts_with("Test Suites:")
+            && !lines[i].starts_with("PASS ")
+            && !lines[i].starts_with("FAIL ")
+        {
+            i += 1;
+        }
+        let body = &lines[start..i];
+
+        let message = body
+            .iter()
+            .take_while(|line| !code_frame.is_match(line) && !stack.is_match(line))
+            .map(|line| line.trim())
+            .collect::<Vec<_>>();
+        let location = body
+            .iter()
+            .filter_map(|line| stack.captures(line))
+            .map(|captures| captures[1].to_string())
+            .find(|location| !location.contains("node_modules"));
+        report
+            .failures
+            .push(TestFailure { name, location, message: join(&message) });
+    }
+
+    Some(report)
+}
+
+/// Joins the lines, leaving out the blank lines around them
+fn join(lines: &[&str]) -> String {
+    lines.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const CARGO_FAILED: &str = r#"
+running 3 tests
+test tests::test_add ... ok
+test tests::test_sub ... FAILED
+test tests::test_div ... FAILED
+
+failures:
+
+---- tests::test_sub stdout ----
+
+thread 'tests::test_sub' panicked at src/lib.rs:21:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+---- tests::test_div stdout ----
+thread 'tests::test_div' panicked at src/lib.rs:26:21:
+attempt to divide by zero
+
+
+failures:
+    tests::test_div
+    tests::test_sub
+
+test result: FAILED. 1 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+error: test failed, to rerun pass `--lib`
+"#;
+
+    const CARGO_PASSED: &str = r#"
+running 2 tests
+test tests::test_add ... ok
+test tests::test_slow ... ignored
+
+test result: ok. 1 passed; 0 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+     Running tests/api.rs (target/debug/deps/api-1a2b3c)
+
+running 4 tests
+test test_get ... ok
+test test_list ... ok
+test test_create ... ok
+test test_delete ... ok
+
+test result: ok. 4 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.01s
+
+   Doc-tests calc
+
+running 0 tests
+
+test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+"#;
+
+    const JEST_FAILED: &str = r#"
+FAIL src/calc.test.js
+  Calculator
+    ✓ adds numbers (2 ms)
+    ✕ subtracts numbers (3 ms)
+
+  ● Calculator › subtracts numbers
+
+    expect(received).toBe(expected) // Object.is equality
+
+    Expected: 2
+    Received: 1
+
+      10 |
+      11 | test('subtracts numbers', () => {
+    > 12 |   expect(sub(3, 2)).toBe(2);
+         |                     ^
+      13 | });
+
+      at Object.toBe (src/calc.test.js:12:21)
+
+Test Suites: 1 failed, 1 total
+Tests:       1 failed, 1 skipped, 1 passed, 3 total
+Snapshots:   0 total
+Time:        0.512 s
+"#;
+
+    #[test]
+    fn test_cargo_failures() {
+        let actual = parse_cargo(CARGO_FAILED).unwrap();
+
+        let expected = TestReport {
+            passed: 1,
+            failed: 2,
+            ignored: 0,
+            failures: vec![
+                TestFailure {
+                    name: "tests::test_sub".to_string(),
+                    location: Some("src/lib.rs:21:9".to_string()),
+                    message: "assertion `left == right` failed\n  left: 1\n right: 2".to_string(),
+                },
+                TestFailure {
+                    name: "tests::test_div".to_string(),
+                    location: Some("src/lib.rs:26:21".to_string()),
+                    message: "attempt to divide by zero".to_string(),
+                },
+            ],
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_cargo_counts_are_summed_over_test_binaries() {
+        let actual = parse_cargo(CARGO_PASSED).unwrap();
+
+        let expected = TestReport { passed: 5, failed: 0, ignored: 1, failures: vec![] };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_cargo_panic_message_before_location() {
+        let fixture = r#"
+---- tests::test_parse stdout ----
+thread 'tests::test_parse' panicked at 'called `Option::unwrap()` on a `None` value', src/parse.rs:8:30
+
+failures:
+    tests::test_parse
+
+test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+"#;
+
+        let actual = parse_cargo(fixture).unwrap().failures;
+
+        let expected = vec![TestFailure {
+            name: "tests::test_parse".to_string(),
+            location: Some("src/parse.rs:8:30".to_string()),
+            message: "called `Option::unwrap()` on a `None` value".to_string(),
+        }];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_cargo_build_error_is_not_parsed() {
+        let fixture = r#"
+   Compiling calc v0.1.0 (/home/user/calc)
+error[E0308]: mismatched types
+ --> src/lib.rs:2:5
+  |
+2 |     "two"
+  |     ^^^^^ expected `u32`, found `&str`
+
+error: could not compile `calc` (lib test) due to 1 previous error
+"#;
+
+        assert_eq!(parse_cargo(fixture), None);
+    }
+
+    #[test]
+    fn test_jest_failures() {
+        let actual = parse_jest(JEST_FAILED).unwrap();
+
+        let expected = TestReport {
+            passed: 1,
+            failed: 1,
+            ignored: 1,
+            failures: vec![TestFailure {
+                name: "Calculator › subtracts numbers".to_string(),
+                location: Some("src/calc.test.js:12:21".to_string()),
+                message: "expect(received).toBe(expected) // Object.is equality\n\nExpected: 2\nReceived: 1"
+                    .to_string(),
+            }],
+        };
+        assert_eq!(actual, expected);
+    }
+}
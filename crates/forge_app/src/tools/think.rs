@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use anyhow::{Context, Result};
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_domain::{ExecutableTool, NamedTool, ToolContent, ToolDescription, ToolName};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -142,11 +142,15 @@ impl NamedTool for Think {
 impl ExecutableTool for Think {
     type Input = ThoughtInput;
     async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        Ok(self.call_content(input).await?.to_string())
+    }
+
+    async fn call_content(&self, input: Self::Input) -> anyhow::Result<ToolContent> {
         let mut thinker = self.clone();
         let thought_number = input.thought_number;
         let thought_result = thinker
             .process_thought(input)
             .with_context(|| format!("Failed to process thought #{}", thought_number))?;
-        Ok(serde_json::to_string(&thought_result)?)
+        Ok(ToolContent::json(serde_json::to_value(&thought_result)?))
     }
 }
@@ -0,0 +1,53 @@
+use anyhow::anyhow;
+
+/// Splits the arguments a tool adds to a command like a shell would. Nothing
+/// is expanded or redirected, since the command runs without a shell.
+pub fn split_args(args: Option<&str>) -> anyhow::Result<Vec<String>> {
+    match args.map(str::trim).filter(|args| !args.is_empty()) {
+        Some(args) => shlex::split(args)
+            .ok_or_else(|| anyhow!("Invalid arguments, check their quotes: {args}")),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// The command as it would be typed in a shell
+pub fn display_command(command: &[String]) -> String {
+    shlex::try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_split_args() {
+        let actual = [
+            split_args(Some("parse; rm -rf src > .env")).unwrap(),
+            split_args(Some("-t 'subtracts two'")).unwrap(),
+            split_args(Some(" ")).unwrap(),
+            split_args(None).unwrap(),
+        ];
+
+        let expected = [
+            vec!["parse;", "rm", "-rf", "src", ">", ".env"],
+            vec!["-t", "subtracts two"],
+            vec![],
+            vec![],
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_unbalanced_quotes_fail() {
+        assert!(split_args(Some("'parse")).is_err());
+    }
+
+    #[test]
+    fn test_display_command() {
+        let actual = display_command(&["npm".into(), "test".into(), "subtracts two".into()]);
+
+        assert_eq!(actual, "npm test 'subtracts two'");
+    }
+}
@@ -0,0 +1,8 @@
+use forge_display::PathFormat;
+use forge_domain::Environment;
+
+/// Creates a formatter that renders paths relative to the environment's
+/// working directory and workspace roots
+pub fn display_paths(env: &Environment) -> PathFormat {
+    PathFormat::new(env.cwd.clone()).roots(env.workspace_roots.clone())
+}
@@ -1,7 +1,13 @@
+mod args;
+mod display_paths;
 mod path_validation;
 #[cfg(test)]
 mod temp_dir;
+mod text_format;
 
+pub use args::*;
+pub use display_paths::*;
 pub use path_validation::*;
 #[cfg(test)]
 pub use temp_dir::*;
+pub use text_format::*;
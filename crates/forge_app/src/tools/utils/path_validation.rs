@@ -1,6 +1,7 @@
-use std::path::Path;
+use std::path::{Component, Path};
 
 use anyhow::bail;
+use forge_domain::Environment;
 
 /// Ensures that the given path is absolute
 ///
@@ -18,6 +19,26 @@ pub fn assert_absolute_path(path: &Path) -> anyhow::Result<()> {
     }
 }
 
+/// Ensures that the given path lies within the workspace, ie. the current
+/// working directory, one of the configured workspace roots or a scratch
+/// directory. Paths with `..` components are rejected since they could escape
+/// it.
+pub fn assert_in_workspace(path: &Path, env: &Environment) -> anyhow::Result<()> {
+    if path.components().any(|c| c == Component::ParentDir) {
+        bail!("Path must not contain '..': {}", path.display())
+    }
+
+    let inside = std::iter::once(&env.cwd)
+        .chain(env.workspace_roots.iter())
+        .chain(std::iter::once(&env.scratch_path()))
+        .any(|root| path.starts_with(root));
+    if !inside {
+        bail!("Path is outside the workspace: {}", path.display())
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,175 @@
+use forge_domain::LineEnding;
+
+/// UTF-8 byte order mark
+const BOM: char = '\u{feff}';
+
+/// How a text file is laid out on disk, so what tools write to it keeps the
+/// same layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextFormat {
+    pub line_ending: LineEnding,
+    /// Whether the file starts with a UTF-8 byte order mark
+    pub bom: bool,
+}
+
+impl TextFormat {
+    /// Format of a new file, `configured` being the line ending set in the
+    /// environment
+    pub fn new_file(configured: Option<LineEnding>) -> Self {
+        Self {
+            line_ending: configured.unwrap_or_else(LineEnding::platform),
+            bom: false,
+        }
+    }
+
+    /// Detects the dominant line ending and the byte order mark of existing
+    /// content. Content without line endings has none to match.
+    pub fn detect(content: &str) -> Self {
+        let crlf = content.matches("\r\n").count();
+        let lf = content.matches('\n').count() - crlf;
+        let line_ending = match (crlf, lf) {
+            (0, 0) => LineEnding::Preserve,
+            (crlf, lf) if crlf > lf => LineEnding::Crlf,
+            _ => LineEnding::Lf,
+        };
+        Self { line_ending, bom: content.starts_with(BOM) }
+    }
+
+    /// Uses the line ending the caller asked for instead, if any
+    pub fn line_ending(mut self, line_ending: Option<LineEnding>) -> Self {
+        if let Some(line_ending) = line_ending {
+            self.line_ending = line_ending;
+        }
+        self
+    }
+
+    /// Converts the line endings of `text` to this format's
+    pub fn convert_line_endings(&self, text: &str) -> String {
+        match self.line_ending {
+            LineEnding::Lf => text.replace("\r\n", "\n"),
+            LineEnding::Crlf => text.replace("\r\n", "\n").replace('\n', "\r\n"),
+            LineEnding::Preserve => text.to_string(),
+        }
+    }
+
+    /// Content without its byte order mark, if it has one
+    pub fn strip_bom(content: &str) -> &str {
+        content.strip_prefix(BOM).unwrap_or(content)
+    }
+
+    /// Puts the byte order mark back in front of content it was stripped
+    /// from, when the format has one
+    pub fn restore_bom(&self, text: &str) -> String {
+        if self.bom {
+            format!("{BOM}{text}")
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Lays out the content of a whole file in this format. Also returns what
+    /// had to change, to tell the agent.
+    pub fn apply(&self, content: &str) -> (String, Option<String>) {
+        let mut notes = Vec::new();
+        let converted = self.convert_line_endings(content);
+        if converted != content {
+            notes.push(format!("line endings were converted to {}", self.name()));
+        }
+
+        let text = converted.strip_prefix(BOM).unwrap_or(&converted);
+        let had_bom = text.len() != converted.len();
+        let formatted = if self.bom {
+            format!("{BOM}{text}")
+        } else {
+            text.to_string()
+        };
+        match (had_bom, self.bom) {
+            (false, true) => notes.push("the file's UTF-8 byte order mark was kept".to_string()),
+            (true, false) => {
+                notes.push("the byte order mark was removed, the file has none".to_string())
+            }
+            _ => {}
+        }
+
+        let note = (!notes.is_empty()).then(|| notes.join(", "));
+        (formatted, note)
+    }
+
+    /// Name of the line ending, as shown to the agent
+    pub fn name(&self) -> &'static str {
+        match self.line_ending {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+            LineEnding::Preserve => "the original",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_detect_dominant_line_ending() {
+        let actual = [
+            TextFormat::detect("a\r\nb\r\nc\n"),
+            TextFormat::detect("a\nb\nc\r\n"),
+            TextFormat::detect("\u{feff}single line"),
+        ];
+
+        let expected = [
+            TextFormat { line_ending: LineEnding::Crlf, bom: false },
+            TextFormat { line_ending: LineEnding::Lf, bom: false },
+            TextFormat { line_ending: LineEnding::Preserve, bom: true },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_apply_converts_and_reports() {
+        let fixture = TextFormat { line_ending: LineEnding::Crlf, bom: true };
+
+        let actual = fixture.apply("a\nb\r\n");
+
+        let expected = (
+            "\u{feff}a\r\nb\r\n".to_string(),
+            Some(
+                "line endings were converted to CRLF, the file's UTF-8 byte order mark was kept"
+                    .to_string(),
+            ),
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_apply_matching_content_is_untouched() {
+        let fixture = TextFormat { line_ending: LineEnding::Lf, bom: false };
+
+        let actual = fixture.apply("a\nb\n");
+
+        assert_eq!(actual, ("a\nb\n".to_string(), None));
+    }
+
+    #[test]
+    fn test_bom_round_trip() {
+        let fixture = "\u{feff}a\n";
+        let format = TextFormat::detect(fixture);
+
+        let actual = format.restore_bom(TextFormat::strip_bom(fixture));
+
+        assert_eq!(TextFormat::strip_bom(fixture), "a\n");
+        assert_eq!(actual, fixture);
+    }
+
+    #[test]
+    fn test_new_file_uses_the_configured_line_ending() {
+        let actual = [
+            TextFormat::new_file(Some(LineEnding::Crlf)).line_ending,
+            TextFormat::new_file(None).line_ending,
+        ];
+
+        assert_eq!(actual, [LineEnding::Crlf, LineEnding::platform()]);
+    }
+}
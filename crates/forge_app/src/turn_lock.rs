@@ -0,0 +1,92 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+/// Advisory lock on the forge directory, released once dropped. Chat turns
+/// hold it shared, any number of them at once, while maintenance that
+/// rewrites derived data holds it exclusively. Either side refuses to start
+/// while the other holds the lock, in this process or in another one.
+#[derive(Debug)]
+pub struct TurnLock {
+    _file: File,
+}
+
+impl TurnLock {
+    /// Taken by a chat turn for as long as it runs
+    pub fn turn(path: &Path) -> Result<Self> {
+        let file = Self::open(path)?;
+        match file.try_lock_shared() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(TryLockError::WouldBlock) => {
+                bail!("forge is rebuilding its data, start the turn again once it's done")
+            }
+            Err(TryLockError::Error(err)) => {
+                Err(err).with_context(|| format!("Failed to lock {}", path.display()))
+            }
+        }
+    }
+
+    /// Taken by maintenance, fails while a turn is running
+    pub fn maintenance(path: &Path) -> Result<Self> {
+        let file = Self::open(path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(TryLockError::WouldBlock) => {
+                bail!("A chat turn is running, try again once it has finished")
+            }
+            Err(TryLockError::Error(err)) => {
+                Err(err).with_context(|| format!("Failed to lock {}", path.display()))
+            }
+        }
+    }
+
+    fn open(path: &Path) -> Result<File> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_maintenance_is_refused_during_a_turn() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("turn.lock");
+
+        let turn = TurnLock::turn(&path).unwrap();
+        let other_turn = TurnLock::turn(&path);
+        let actual = TurnLock::maintenance(&path);
+
+        assert!(other_turn.is_ok());
+        assert_eq!(
+            actual.unwrap_err().to_string(),
+            "A chat turn is running, try again once it has finished"
+        );
+
+        drop((turn, other_turn));
+        assert!(TurnLock::maintenance(&path).is_ok());
+    }
+
+    #[test]
+    fn test_turns_are_refused_during_maintenance() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("turn.lock");
+
+        let _maintenance = TurnLock::maintenance(&path).unwrap();
+
+        assert!(TurnLock::turn(&path).is_err());
+        assert!(TurnLock::maintenance(&path).is_err());
+    }
+}
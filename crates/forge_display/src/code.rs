@@ -0,0 +1,227 @@
+use std::sync::OnceLock;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+const FENCE: &str = "```";
+const THEME: &str = "base16-ocean.dark";
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    &THEMES.get_or_init(ThemeSet::load_defaults).themes[THEME]
+}
+
+/// Highlights `code` as `language`, `None` when the language isn't known
+pub fn highlight(code: &str, language: &str) -> Option<String> {
+    if language.is_empty() {
+        return None;
+    }
+    let syntaxes = syntaxes();
+    let syntax = syntaxes.find_syntax_by_token(language)?;
+    let mut lines = HighlightLines::new(syntax, theme());
+    let mut highlighted = String::new();
+    for line in LinesWithEndings::from(code) {
+        let ranges = lines.highlight_line(line, syntaxes).ok()?;
+        highlighted.push_str(&as_24_bit_terminal_escaped(&ranges, false));
+    }
+    highlighted.push_str("\x1b[0m");
+    Some(highlighted)
+}
+
+/// A piece of streamed text, ready to be printed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fragment {
+    /// Text outside of code blocks, including the fences, as it was streamed
+    Text(String),
+    /// The content of a code block, highlighted with terminal colors
+    Code(String),
+}
+
+/// Highlights the fenced code blocks of text that arrives in chunks. Text is
+/// passed through as it arrives, except for the lines of a code block, which
+/// are held back until the block is complete.
+#[derive(Default)]
+pub struct CodeHighlighter {
+    /// Start of a line that may turn out to be a fence
+    pending: String,
+    /// Whether the next chunk starts a new line
+    mid_line: bool,
+    /// Language and content of the open code block
+    block: Option<(String, String)>,
+}
+
+impl CodeHighlighter {
+    /// Takes the next chunk of text and returns what can be printed so far
+    pub fn push(&mut self, chunk: &str) -> Vec<Fragment> {
+        let mut fragments = Vec::new();
+        for line in LinesWithEndings::from(chunk) {
+            self.push_line(line, &mut fragments);
+        }
+        merge(fragments)
+    }
+
+    /// Returns what's held back once the text is complete. A code block that
+    /// was never closed is highlighted as is.
+    pub fn finish(&mut self) -> Vec<Fragment> {
+        let mut fragments = Vec::new();
+        let pending = std::mem::take(&mut self.pending);
+        match self.block.take() {
+            Some((language, mut code)) => {
+                code.push_str(&pending);
+                fragments.push(code_fragment(code, &language));
+            }
+            None if !pending.is_empty() => fragments.push(Fragment::Text(pending)),
+            None => {}
+        }
+        self.mid_line = false;
+        merge(fragments)
+    }
+
+    fn push_line(&mut self, part: &str, fragments: &mut Vec<Fragment>) {
+        let complete = part.ends_with('\n');
+
+        if self.block.is_none() && self.mid_line {
+            fragments.push(Fragment::Text(part.to_string()));
+            self.mid_line = !complete;
+            return;
+        }
+
+        self.pending.push_str(part);
+        if !complete {
+            // A partial line is held back only while it can still be a fence
+            let start = self.pending.trim_start();
+            let maybe_fence = start.starts_with(FENCE) || FENCE.starts_with(start);
+            if self.block.is_none() && !maybe_fence {
+                fragments.push(Fragment::Text(std::mem::take(&mut self.pending)));
+                self.mid_line = true;
+            }
+            return;
+        }
+
+        let line = std::mem::take(&mut self.pending);
+        let fence = line.trim().strip_prefix(FENCE);
+        match (self.block.take(), fence) {
+            (None, Some(language)) => {
+                self.block = Some((language.trim().to_string(), String::new()));
+                fragments.push(Fragment::Text(line));
+            }
+            (None, None) => fragments.push(Fragment::Text(line)),
+            (Some((language, code)), Some("")) => {
+                fragments.push(code_fragment(code, &language));
+                fragments.push(Fragment::Text(line));
+            }
+            (Some((language, mut code)), _) => {
+                code.push_str(&line);
+                self.block = Some((language, code));
+            }
+        }
+        self.mid_line = false;
+    }
+}
+
+/// Highlights the block, unknown languages stay plain text
+fn code_fragment(code: String, language: &str) -> Fragment {
+    if code.is_empty() {
+        return Fragment::Text(code);
+    }
+    match highlight(&code, language) {
+        Some(highlighted) => Fragment::Code(highlighted),
+        None => Fragment::Text(code),
+    }
+}
+
+/// Joins consecutive text fragments and drops empty ones
+fn merge(fragments: Vec<Fragment>) -> Vec<Fragment> {
+    let mut merged: Vec<Fragment> = Vec::new();
+    for fragment in fragments {
+        match (merged.last_mut(), fragment) {
+            (_, Fragment::Text(text)) if text.is_empty() => {}
+            (Some(Fragment::Text(last)), Fragment::Text(text)) => last.push_str(&text),
+            (_, fragment) => merged.push(fragment),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn stream(chunks: &[&str]) -> Vec<Fragment> {
+        let mut highlighter = CodeHighlighter::default();
+        let mut fragments = chunks
+            .iter()
+            .flat_map(|chunk| highlighter.push(chunk))
+            .collect::<Vec<_>>();
+        fragments.extend(highlighter.finish());
+        merge(fragments)
+    }
+
+    #[test]
+    fn test_rust_block_split_across_chunks_is_highlighted() {
+        let actual = stream(&[
+            "Here is the fix:\n``",
+            "`rust\nfn main() {\n    println!(\"hi\");",
+            "\n}\n``",
+            "`\nDone.",
+        ]);
+
+        let code = "fn main() {\n    println!(\"hi\");\n}\n";
+        let expected = vec![
+            Fragment::Text("Here is the fix:\n```rust\n".to_string()),
+            Fragment::Code(highlight(code, "rust").unwrap()),
+            Fragment::Text("```\nDone.".to_string()),
+        ];
+        assert_eq!(actual, expected);
+        let Fragment::Code(highlighted) = &actual[1] else {
+            unreachable!()
+        };
+        assert!(highlighted.contains("\x1b[38;2;"));
+        assert_eq!(strip_ansi_escapes::strip_str(highlighted), code);
+    }
+
+    #[test]
+    fn test_unknown_language_stays_plain() {
+        let actual = stream(&["```klingon\nqapla'\n```\n"]);
+
+        let expected = vec![Fragment::Text("```klingon\nqapla'\n```\n".to_string())];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_text_is_passed_through_as_it_arrives() {
+        let mut highlighter = CodeHighlighter::default();
+
+        let actual = [
+            highlighter.push("Reading the "),
+            highlighter.push("file `a.rs`"),
+        ];
+
+        let expected = [
+            vec![Fragment::Text("Reading the ".to_string())],
+            vec![Fragment::Text("file `a.rs`".to_string())],
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_unclosed_block_is_highlighted_on_finish() {
+        let mut highlighter = CodeHighlighter::default();
+
+        let pushed = highlighter.push("```rust\nlet x = 1;");
+        let actual = highlighter.finish();
+
+        assert_eq!(pushed, vec![Fragment::Text("```rust\n".to_string())]);
+        let expected = vec![Fragment::Code(highlight("let x = 1;", "rust").unwrap())];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,23 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ECHO: AtomicBool = AtomicBool::new(true);
+
+/// Turns on or off what tools print on the terminal while they run, eg. the
+/// compact display shows a line per tool call instead
+pub fn set_echo(enabled: bool) {
+    ECHO.store(enabled, Ordering::SeqCst);
+}
+
+pub fn is_echo_enabled() -> bool {
+    ECHO.load(Ordering::SeqCst)
+}
+
+/// Prints like `println!` unless echoing was turned off with [`set_echo`]
+#[macro_export]
+macro_rules! echo {
+    ($($arg:tt)*) => {
+        if $crate::is_echo_enabled() {
+            println!($($arg)*);
+        }
+    };
+}
@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+const SIZE_UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+/// Formats a size in bytes, eg. `512 B` or `17.8 KB`. Units are multiples of
+/// 1024 and the next one is used from 1000 on, so sizes never take four
+/// digits.
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 1;
+    // 999.95 would be shown as 1000.0
+    while size >= 999.95 && unit < SIZE_UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", SIZE_UNITS[unit])
+}
+
+/// Formats a count with thousands separators, eg. `12,800`
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/// Formats a duration with the precision that matters at its scale:
+/// `850ms`, `12.4s`, `3m 02s` or `1h 05m`
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        return format!("{millis}ms");
+    }
+
+    let tenths = (millis + 50) / 100;
+    if tenths < 600 {
+        return format!("{}.{}s", tenths / 10, tenths % 10);
+    }
+
+    let seconds = (millis + 500) / 1000;
+    if seconds < 3600 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60)
+    }
+}
+
+/// How a point in time is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeFormat {
+    /// Relative to now, eg. `2 hours ago`, for people reading a terminal
+    Relative,
+    /// ISO 8601, for JSON and output that isn't read in a terminal
+    Absolute,
+}
+
+/// Formats `time`, `now` being the time relative ones are counted from
+pub fn format_time(time: DateTime<Utc>, now: DateTime<Utc>, format: TimeFormat) -> String {
+    match format {
+        TimeFormat::Absolute => time.to_rfc3339_opts(SecondsFormat::Secs, true),
+        TimeFormat::Relative => format_relative(time, now),
+    }
+}
+
+fn format_relative(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const MONTH: i64 = 30 * DAY;
+    const YEAR: i64 = 365 * DAY;
+
+    let elapsed = (now - time).num_seconds();
+    let seconds = elapsed.abs();
+    let (amount, unit) = match seconds {
+        ..MINUTE => return "just now".to_string(),
+        ..HOUR => (seconds / MINUTE, "minute"),
+        ..DAY => (seconds / HOUR, "hour"),
+        ..MONTH => (seconds / DAY, "day"),
+        ..YEAR => (seconds / MONTH, "month"),
+        _ => (seconds / YEAR, "year"),
+    };
+
+    let plural = if amount == 1 { "" } else { "s" };
+    if elapsed < 0 {
+        format!("in {amount} {unit}{plural}")
+    } else {
+        format!("{amount} {unit}{plural} ago")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        let actual = [0, 999, 1000, 18_234, 1_023_948, 1_023_950, 5 * 1024 * 1024].map(format_size);
+
+        let expected = [
+            "0 B", "999 B", "1.0 KB", "17.8 KB", "999.9 KB", "1.0 MB", "5.0 MB",
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_count() {
+        let actual = [0, 999, 1000, 12_800, 1_234_567].map(format_count);
+
+        let expected = ["0", "999", "1,000", "12,800", "1,234,567"];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        let actual = [
+            850, 999, 1000, 12_440, 59_900, 59_960, 60_000, 182_000, 3_900_000,
+        ]
+        .map(|millis| format_duration(Duration::from_millis(millis)));
+
+        let expected = [
+            "850ms", "999ms", "1.0s", "12.4s", "59.9s", "1m 00s", "1m 00s", "3m 02s", "1h 05m",
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_relative_time() {
+        let now = "2025-04-10T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let actual = [
+            TimeDelta::seconds(30),
+            TimeDelta::seconds(60),
+            TimeDelta::hours(2),
+            TimeDelta::days(1),
+            TimeDelta::days(45),
+            TimeDelta::days(800),
+            TimeDelta::minutes(-5),
+        ]
+        .map(|ago| format_time(now - ago, now, TimeFormat::Relative));
+
+        let expected = [
+            "just now",
+            "1 minute ago",
+            "2 hours ago",
+            "1 day ago",
+            "1 month ago",
+            "2 years ago",
+            "in 5 minutes",
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_absolute_time() {
+        let now = "2025-04-10T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let actual = format_time(now - TimeDelta::hours(2), now, TimeFormat::Absolute);
+
+        assert_eq!(actual, "2025-04-10T10:00:00Z");
+    }
+}
@@ -8,50 +8,50 @@ use regex::Regex;
 pub struct GrepFormat(Vec<String>);
 
 /// Represents a parsed line from grep-like output format
-/// (path:line_num:content)
+/// (path:line_num:content or path:line_num:column:content)
 #[derive(Debug)]
 struct ParsedLine<'a> {
     /// File path where the match was found
     path: &'a str,
-    /// Line number of the match
-    line_num: &'a str,
+    /// Line number of the match, followed by its column when known
+    line_num: std::borrow::Cow<'a, str>,
     /// Content of the matching line
     content: &'a str,
 }
 
 impl<'a> ParsedLine<'a> {
-    /// Parse a line in the format "path:line_num:content"
+    /// Parse a line in the format "path:line_num:content" or
+    /// "path:line_num:column:content"
     ///
     /// # Arguments
-    /// * `line` - The line to parse in the format "path:line_num:content"
+    /// * `line` - The line to parse in one of the formats above
     ///
     /// # Returns
     /// * `Some(ParsedLine)` if the line matches the expected format
     /// * `None` if the line is malformed
     pub fn parse(line: &'a str) -> Option<Self> {
+        let is_number = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
         let parts: Vec<_> = line.split(':').collect();
-        if parts.len() != 3 {
+        let (line_num, content) = match *parts.as_slice() {
+            [_, line_num, content] => (std::borrow::Cow::Borrowed(line_num.trim()), content),
+            [_, line_num, column, content] if is_number(column) => (
+                std::borrow::Cow::Owned(format!("{}:{}", line_num.trim(), column)),
+                content,
+            ),
+            _ => return None,
+        };
+
+        // Validate that the path is not empty and that the line number
+        // contains only digits
+        if parts[0].is_empty() || !is_number(parts[1]) {
             return None;
         }
 
-        // Validate that path and line number parts are not empty
-        // and that line number contains only digits
-        if parts[0].is_empty()
-            || parts[1].is_empty()
-            || !parts[1].chars().all(|c| c.is_ascii_digit())
-        {
-            return None;
-        }
-
-        Some(Self {
-            path: parts[0].trim(),
-            line_num: parts[1].trim(),
-            content: parts[2].trim(),
-        })
+        Some(Self { path: parts[0].trim(), line_num, content: content.trim() })
     }
 }
 
-type Lines<'a> = Vec<(&'a str, &'a str)>;
+type Lines<'a> = Vec<(std::borrow::Cow<'a, str>, &'a str)>;
 impl GrepFormat {
     pub fn new(lines: Vec<String>) -> Self {
         Self(lines)
@@ -94,16 +94,11 @@ impl GrepFormat {
     }
 
     /// Format a group of lines for a single file
-    fn format_file_group(
-        path: &str,
-        group: Vec<(&str, &str)>,
-        regex: &Regex,
-        max_num_width: usize,
-    ) -> String {
+    fn format_file_group(path: &str, group: Lines, regex: &Regex, max_num_width: usize) -> String {
         let file_header = style(path).cyan();
         let formatted_lines = group
             .into_iter()
-            .map(|(num, content)| Self::format_line(num, content, regex, max_num_width))
+            .map(|(num, content)| Self::format_line(&num, content, regex, max_num_width))
             .collect::<String>();
         format!("{}\n{}", file_header, formatted_lines)
     }
@@ -262,4 +257,18 @@ mod tests {
 
         assert_snapshot!(suite);
     }
+
+    #[test]
+    fn test_column_is_shown_after_the_line_number() {
+        let fixture = GrepFormat::new(vec![
+            "file.txt:2:7:second match".to_string(),
+            "file.txt:10:1:match again".to_string(),
+        ]);
+
+        let actual = strip_ansi_escapes::strip_str(fixture.format(&Regex::new("match").unwrap()))
+            .to_string();
+
+        let expected = "file.txt\n 2:7: second match\n10:1: match again\n";
+        assert_eq!(actual, expected);
+    }
 }
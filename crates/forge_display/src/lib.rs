@@ -1,7 +1,17 @@
+pub mod code;
 pub mod diff;
+pub mod echo;
+pub mod format;
 pub mod grep;
+pub mod markdown;
+pub mod path;
 pub mod title;
 
+pub use code::{CodeHighlighter, Fragment};
 pub use diff::DiffFormat;
+pub use echo::*;
+pub use format::*;
 pub use grep::GrepFormat;
+pub use markdown::MarkdownRenderer;
+pub use path::PathFormat;
 pub use title::*;
@@ -0,0 +1,193 @@
+const BOLD: (&str, &str) = ("\x1b[1m", "\x1b[22m");
+const ITALIC: (&str, &str) = ("\x1b[3m", "\x1b[23m");
+const UNDERLINE: (&str, &str) = ("\x1b[4m", "\x1b[24m");
+const CODE: (&str, &str) = ("\x1b[36m", "\x1b[39m");
+const QUOTE: &str = "\x1b[2m│\x1b[22m ";
+
+/// Renders the markdown of text that arrives in chunks, a line at a time.
+/// The lines of code blocks are left as they are.
+#[derive(Default)]
+pub struct MarkdownRenderer {
+    /// Start of the line that hasn't ended yet
+    line: String,
+    in_code_block: bool,
+}
+
+impl MarkdownRenderer {
+    /// Takes the next chunk of text and returns the lines it completed,
+    /// rendered
+    pub fn push(&mut self, chunk: &str) -> String {
+        self.line.push_str(chunk);
+        let Some(end) = self.line.rfind('\n') else {
+            return String::new();
+        };
+
+        let rest = self.line.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.line, rest);
+        complete
+            .split_inclusive('\n')
+            .map(|line| self.render(line))
+            .collect()
+    }
+
+    /// Returns the last line once the text is complete
+    pub fn finish(&mut self) -> String {
+        let line = std::mem::take(&mut self.line);
+        let rendered = self.render(&line);
+        self.in_code_block = false;
+        rendered
+    }
+
+    fn render(&mut self, line: &str) -> String {
+        let (content, newline) = match line.strip_suffix('\n') {
+            Some(content) => (content, "\n"),
+            None => (line, ""),
+        };
+        if content.trim_start().starts_with("```") {
+            self.in_code_block = !self.in_code_block;
+            return line.to_string();
+        }
+        if self.in_code_block {
+            return line.to_string();
+        }
+        format!("{}{newline}", render_line(content))
+    }
+}
+
+/// Renders a line of markdown outside of code blocks: headers, list items,
+/// quotes and inline emphasis and code
+pub fn render_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+        let title = render_inline(trimmed[level..].trim());
+        return format!("{indent}{}", wrap(&wrap(&title, UNDERLINE), BOLD));
+    }
+
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = trimmed.strip_prefix(bullet) {
+            return format!("{indent}• {}", render_inline(item));
+        }
+    }
+
+    if let Some(quote) = trimmed.strip_prefix('>') {
+        return format!("{indent}{QUOTE}{}", render_inline(quote.trim_start()));
+    }
+
+    format!("{indent}{}", render_inline(trimmed))
+}
+
+/// Renders `**bold**`, `*italic*`, `_italic_` and `` `code` ``. Markers
+/// without a closing one are kept as they are.
+fn render_inline(text: &str) -> String {
+    let mut rendered = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let span = match c {
+            '`' => closed(rest, "`").map(|(code, len)| (wrap(code, CODE), len)),
+            '*' if rest.starts_with("**") => {
+                closed(rest, "**").map(|(inner, len)| (wrap(&render_inline(inner), BOLD), len))
+            }
+            '*' => closed(rest, "*").map(|(inner, len)| (wrap(&render_inline(inner), ITALIC), len)),
+            // Underscores within words, as in snake_case, aren't emphasis
+            '_' if !rendered.ends_with(|c: char| c.is_alphanumeric()) => closed(rest, "_")
+                .filter(|(_, len)| !rest[*len..].starts_with(|c: char| c.is_alphanumeric()))
+                .map(|(inner, len)| (wrap(&render_inline(inner), ITALIC), len)),
+            _ => None,
+        };
+
+        match span {
+            Some((span, len)) => {
+                rendered.push_str(&span);
+                rest = &rest[len..];
+            }
+            None => {
+                rendered.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    rendered
+}
+
+/// Finds the text between `marker` at the start of `text` and the next
+/// `marker`, along with the length of the whole span. Emphasis has to hug
+/// its text, as in `*this*` but not `* this *`.
+fn closed<'a>(text: &'a str, marker: &str) -> Option<(&'a str, usize)> {
+    let after = &text[marker.len()..];
+    let end = after.find(marker)?;
+    let inner = &after[..end];
+    let hugs = !inner.starts_with(char::is_whitespace) && !inner.ends_with(char::is_whitespace);
+    if inner.is_empty() || (marker != "`" && !hugs) {
+        return None;
+    }
+    Some((inner, marker.len() * 2 + end))
+}
+
+fn wrap(text: &str, (start, end): (&str, &str)) -> String {
+    format!("{start}{text}{end}")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_render_header() {
+        let actual = render_line("## Summary of **changes**");
+
+        let expected = "\x1b[1m\x1b[4mSummary of \x1b[1mchanges\x1b[22m\x1b[24m\x1b[22m";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_emphasis_and_code() {
+        let actual = render_line("Use **bold**, *italic*, _also_ and `a * b` in snake_case_name");
+
+        let expected = "Use \x1b[1mbold\x1b[22m, \x1b[3mitalic\x1b[23m, \x1b[3malso\x1b[23m and \x1b[36ma * b\x1b[39m in snake_case_name";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_list_and_quote() {
+        let actual = [render_line("  - first *item*"), render_line("> note")];
+
+        let expected = [
+            "  • first \x1b[3mitem\x1b[23m".to_string(),
+            format!("{QUOTE}note"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_unclosed_markers_are_kept() {
+        let actual = render_line("2 * 3 = 6 and **not bold");
+
+        assert_eq!(actual, "2 * 3 = 6 and **not bold");
+    }
+
+    #[test]
+    fn test_streamed_lines_are_rendered_once_complete() {
+        let mut fixture = MarkdownRenderer::default();
+
+        let actual = [
+            fixture.push("# Ti"),
+            fixture.push("tle\n```md\n# not a header\n```\n**do"),
+            fixture.push("ne**"),
+            fixture.finish(),
+        ];
+
+        let expected = [
+            String::new(),
+            "\x1b[1m\x1b[4mTitle\x1b[24m\x1b[22m\n```md\n# not a header\n```\n".to_string(),
+            String::new(),
+            "\x1b[1mdone\x1b[22m".to_string(),
+        ];
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,170 @@
+use std::path::{Path, PathBuf};
+
+use derive_setters::Setters;
+
+/// Renders paths relative to the current working directory, or to the closest
+/// matching workspace root. Paths outside of all known roots are left
+/// absolute.
+#[derive(Clone, Debug, Default, Setters)]
+#[setters(into)]
+pub struct PathFormat {
+    /// The working directory that paths are primarily relativized against
+    cwd: PathBuf,
+    /// Additional workspace roots, rendered as `[<root name>] <relative path>`
+    roots: Vec<PathBuf>,
+}
+
+impl PathFormat {
+    pub fn new(cwd: impl Into<PathBuf>) -> Self {
+        Self { cwd: cwd.into(), roots: Vec::new() }
+    }
+
+    /// Formats a single path for display
+    pub fn format(&self, path: impl AsRef<Path>) -> String {
+        let path = path.as_ref();
+
+        if let Ok(relative) = path.strip_prefix(&self.cwd) {
+            if !self.cwd.as_os_str().is_empty() {
+                return Self::display(relative);
+            }
+        }
+
+        // Pick the most specific workspace root that contains the path
+        self.roots
+            .iter()
+            .filter(|root| !root.as_os_str().is_empty())
+            .filter_map(|root| path.strip_prefix(root).ok().map(|rel| (root, rel)))
+            .max_by_key(|(root, _)| root.components().count())
+            .map(|(root, relative)| {
+                let label = root
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| root.display().to_string());
+                format!("[{}] {}", label, Self::display(relative))
+            })
+            .unwrap_or_else(|| path.display().to_string())
+    }
+
+    /// Rewrites the paths under the working directory inside free-form text
+    /// (eg: tool output) so that they become relative. Only a path that
+    /// starts the text, or follows whitespace or a quote, is rewritten, and
+    /// nothing is when the working directory is the filesystem root.
+    pub fn relativize(&self, text: &str) -> String {
+        if self.cwd.as_os_str().is_empty() || self.cwd.parent().is_none() {
+            return text.to_string();
+        }
+
+        let cwd = self.cwd.display().to_string();
+        let cwd = cwd.trim_end_matches(std::path::MAIN_SEPARATOR);
+        let prefix = format!("{}{}", cwd, std::path::MAIN_SEPARATOR);
+
+        let mut relative = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(index) = rest.find(&prefix) {
+            let starts_path = rest[..index]
+                .chars()
+                .next_back()
+                .or_else(|| relative.chars().next_back())
+                .is_none_or(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '`'));
+            relative.push_str(&rest[..index]);
+            if !starts_path {
+                relative.push_str(&prefix);
+            }
+            rest = &rest[index + prefix.len()..];
+        }
+        relative.push_str(rest);
+        relative
+    }
+
+    fn display(relative: &Path) -> String {
+        if relative.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            relative.display().to_string()
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_path_inside_cwd_is_relative() {
+        let fixture = PathFormat::new("/home/user/project");
+        let actual = fixture.format("/home/user/project/src/main.rs");
+        let expected = "src/main.rs";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_cwd_itself_is_dot() {
+        let fixture = PathFormat::new("/home/user/project");
+        let actual = fixture.format("/home/user/project");
+        let expected = ".";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_path_outside_roots_stays_absolute() {
+        let fixture = PathFormat::new("/home/user/project").roots(vec![PathBuf::from("/opt/libs")]);
+        let actual = fixture.format("/etc/hosts");
+        let expected = "/etc/hosts";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_sibling_prefix_is_not_relativized() {
+        let fixture = PathFormat::new("/home/user/project");
+        let actual = fixture.format("/home/user/project-old/main.rs");
+        let expected = "/home/user/project-old/main.rs";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_workspace_root_is_labeled() {
+        let fixture = PathFormat::new("/home/user/monorepo/services/api").roots(vec![
+            PathBuf::from("/home/user/monorepo"),
+            PathBuf::from("/home/user/monorepo/services/web"),
+        ]);
+
+        let actual = vec![
+            fixture.format("/home/user/monorepo/services/api/src/lib.rs"),
+            fixture.format("/home/user/monorepo/services/web/index.ts"),
+            fixture.format("/home/user/monorepo/Cargo.toml"),
+        ];
+        let expected = vec![
+            "src/lib.rs".to_string(),
+            "[web] index.ts".to_string(),
+            "[monorepo] Cargo.toml".to_string(),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_relativize_text() {
+        let fixture = PathFormat::new("/home/user/project/");
+        let actual = fixture
+            .relativize("/home/user/project/src/a.rs:1:fn a()\n/home/user/other/b.rs:2:fn b()");
+        let expected = "src/a.rs:1:fn a()\n/home/user/other/b.rs:2:fn b()";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_relativize_only_rewrites_path_starts() {
+        let fixture = PathFormat::new("/src");
+        let actual = fixture.relativize("read \"/src/a.rs\", not /old/src/b.rs or x/src/c.rs");
+        let expected = "read \"a.rs\", not /old/src/b.rs or x/src/c.rs";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_relativize_in_root_keeps_text() {
+        let fixture = PathFormat::new("/");
+        let actual = fixture.relativize("/etc/hosts and /usr/bin/env");
+        let expected = "/etc/hosts and /usr/bin/env";
+        assert_eq!(actual, expected);
+    }
+}
@@ -7,6 +7,7 @@ use derive_setters::Setters;
 pub enum Kind {
     Execute,
     Success,
+    Warning,
     Failed,
 }
 
@@ -15,6 +16,7 @@ impl Kind {
         match self {
             Kind::Execute => "⚙",
             Kind::Success => "✓",
+            Kind::Warning => "⚠",
             Kind::Failed => "✗",
         }
     }
@@ -23,6 +25,7 @@ impl Kind {
         match self {
             Kind::Execute => "execute",
             Kind::Success => "success",
+            Kind::Warning => "warning",
             Kind::Failed => "error",
         }
     }
@@ -71,6 +74,16 @@ impl TitleFormat {
         }
     }
 
+    /// Create a warning status, for failures that don't stop the operation
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            kind: Kind::Warning,
+            title: message.into(),
+            error: None,
+            sub_title: Default::default(),
+        }
+    }
+
     /// Create a failure status
     pub fn failed(message: impl Into<String>) -> Self {
         Self {
@@ -93,6 +106,11 @@ impl TitleFormat {
                 self.label().bold().green(),
                 self.title.to_string(),
             ),
+            Kind::Warning => (
+                self.icon().yellow(),
+                self.label().bold().yellow(),
+                self.title.to_string(),
+            ),
             Kind::Failed => {
                 let error_suffix = self
                     .error
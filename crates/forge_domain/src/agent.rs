@@ -1,11 +1,14 @@
+use std::collections::HashMap;
+
 use derive_more::derive::Display;
 use derive_setters::Setters;
 use merge::Merge;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::merge::Key;
 use crate::template::Template;
-use crate::{Environment, EventContext, ModelId, ToolName};
+use crate::{Environment, EventContext, ModelId, ToolName, ToolPolicy};
 
 #[derive(Debug, Default, Setters, Clone, Serialize, Deserialize)]
 #[setters(strip_option)]
@@ -23,6 +26,12 @@ pub struct SystemContext {
     pub readme: String,
     #[serde(skip_serializing_if = "String::is_empty")]
     pub project_rules: String,
+    /// Directory for intermediate artifacts of the conversation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scratch_dir: Option<String>,
+    /// The workflow's variables, eg. the user's name or project guidelines
+    #[serde(default)]
+    pub variables: HashMap<String, Value>,
 }
 
 #[derive(Debug, Display, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
@@ -105,6 +114,12 @@ pub struct Agent {
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub max_turns: Option<u64>,
 
+    /// Maximum number of requests the agent can make to the provider in a
+    /// single turn before the user is asked whether it carries on, defaults
+    /// to [`DEFAULT_MAX_ITERATIONS`](crate::DEFAULT_MAX_ITERATIONS)
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_iterations: Option<u64>,
+
     /// Maximum depth to which the file walker should traverse for this agent
     /// If not provided, the maximum possible depth will be used
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -114,6 +129,40 @@ pub struct Agent {
     #[serde(skip_serializing_if = "String::is_empty", default)]
     #[merge(strategy = crate::merge::string::concat)]
     pub project_rules: String,
+
+    /// Approval policy for individual tools. Tools that aren't listed are
+    /// executed without asking.
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    #[merge(strategy = crate::merge::hashmap::extend)]
+    pub tool_policies: HashMap<ToolName, ToolPolicy>,
+}
+
+/// Tool whose policy applies to the [`COMMAND_TOOLS`]
+const SHELL_TOOL: &str = "tool_forge_process_shell";
+
+/// Tools that run commands of the project, eg. its test scripts or the
+/// workflow's verify commands, which can do anything a shell command can
+const COMMAND_TOOLS: &[&str] = &[
+    "tool_forge_process_test",
+    "tool_forge_process_lint",
+    "tool_forge_verify",
+];
+
+impl Agent {
+    /// Returns the approval policy that applies to the given tool. Tools that
+    /// run commands of the project follow the shell tool's policy unless
+    /// they have one of their own.
+    pub fn tool_policy(&self, name: &ToolName) -> ToolPolicy {
+        let policy = |name: &str| self.tool_policies.get(&ToolName::new(name)).copied();
+        policy(name.as_str())
+            .or_else(|| {
+                COMMAND_TOOLS
+                    .contains(&name.as_str())
+                    .then(|| policy(SHELL_TOOL))
+                    .flatten()
+            })
+            .unwrap_or_default()
+    }
 }
 
 impl Key for Agent {
@@ -171,8 +220,10 @@ mod tests {
                 transforms: Vec::new(),
                 subscribe: Vec::new(),
                 max_turns: None,
+                max_iterations: None,
                 max_walker_depth: None,
                 project_rules: String::new(),
+                tool_policies: HashMap::new(),
             }
         }
     }
@@ -216,4 +267,45 @@ mod tests {
         base.merge(other);
         assert_eq!(base.project_rules, "Rule 1: Be concise");
     }
+
+    #[test]
+    fn test_command_tools_follow_the_shell_policy() {
+        let agent = Agent {
+            tool_policies: HashMap::from([
+                (
+                    ToolName::new("tool_forge_process_shell"),
+                    ToolPolicy::Prompt,
+                ),
+                (ToolName::new("tool_forge_fs_remove"), ToolPolicy::Deny),
+            ]),
+            ..Agent::default()
+        };
+        let own = Agent {
+            tool_policies: HashMap::from([
+                (
+                    ToolName::new("tool_forge_process_shell"),
+                    ToolPolicy::Prompt,
+                ),
+                (ToolName::new("tool_forge_process_test"), ToolPolicy::Auto),
+            ]),
+            ..Agent::default()
+        };
+
+        let actual = [
+            agent.tool_policy(&ToolName::new("tool_forge_process_test")),
+            agent.tool_policy(&ToolName::new("tool_forge_verify")),
+            agent.tool_policy(&ToolName::new("tool_forge_fs_read")),
+            own.tool_policy(&ToolName::new("tool_forge_process_test")),
+        ];
+
+        assert_eq!(
+            actual,
+            [
+                ToolPolicy::Prompt,
+                ToolPolicy::Prompt,
+                ToolPolicy::Auto,
+                ToolPolicy::Auto
+            ]
+        );
+    }
 }
@@ -1,17 +1,46 @@
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 
-use crate::{ConversationId, Event};
+use crate::{
+    CompactionPolicy, ConversationId, Event, IterationLimitAction, RefusalPolicy, ToolResultPolicy,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Setters)]
 #[setters(into, strip_option)]
 pub struct ChatRequest {
     pub event: Event,
     pub conversation_id: ConversationId,
+    /// When enabled, tool calls are reported as a plan instead of being
+    /// executed.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// What happens when the turn reaches its iteration limit, the user is
+    /// asked when unset
+    #[serde(default)]
+    pub on_iteration_limit: Option<IterationLimitAction>,
+    /// How responses declining the request are recognized and handled
+    #[serde(default)]
+    pub refusal: RefusalPolicy,
+    /// Whether requests exceeding the model's context are compacted and sent
+    /// again
+    #[serde(default)]
+    pub compaction: CompactionPolicy,
+    /// How tool results are cut and rewritten before they are added to the
+    /// context
+    #[serde(default)]
+    pub tool_results: ToolResultPolicy,
 }
 
 impl ChatRequest {
     pub fn new(content: Event, conversation_id: ConversationId) -> Self {
-        Self { event: content, conversation_id }
+        Self {
+            event: content,
+            conversation_id,
+            dry_run: false,
+            on_iteration_limit: None,
+            refusal: RefusalPolicy::default(),
+            compaction: CompactionPolicy::default(),
+            tool_results: ToolResultPolicy::default(),
+        }
     }
 }
@@ -1,6 +1,9 @@
 use serde::Serialize;
 
-use crate::{Event, ToolCallFull, ToolResult, Usage};
+use crate::{
+    Compaction, ConversationSuggestions, Event, IterationLimit, ModelId, ProviderToolCall, Refusal,
+    ToolCallFull, ToolCallId, ToolResult, Usage, UserInteraction,
+};
 
 /// Events that are emitted by the agent for external consumption. This includes
 /// events for all internal state changes.
@@ -10,6 +13,90 @@ pub enum ChatResponse {
     Text(String),
     ToolCallStart(ToolCallFull),
     ToolCallEnd(ToolResult),
+    /// A tool the provider ran itself as part of the reply, eg. web search
+    ProviderTool(ProviderToolCall),
     Usage(Usage),
     Custom(Event),
+    /// Tool calls that the agent intended to make while running in dry-run
+    /// mode. None of these calls were executed.
+    Plan(Vec<ToolCallFull>),
+    /// A tool call that is waiting for the user's approval. The decision is
+    /// sent back using the call's id.
+    ApprovalRequired(ToolCallFull),
+    /// A question the agent asked the user. The answer is sent back using the
+    /// call's id.
+    Interaction {
+        call_id: ToolCallId,
+        interaction: UserInteraction,
+    },
+    /// The model hasn't produced its first token yet, the provider may be
+    /// queueing the request. `elapsed` is in seconds.
+    Waiting {
+        model: ModelId,
+        elapsed: u64,
+    },
+    /// Tags and title suggested for the conversation once it completed. They
+    /// are not applied.
+    Suggestions(ConversationSuggestions),
+    /// The turn reached its iteration limit, it carries on only if the user
+    /// says so
+    IterationLimit(IterationLimit),
+    /// The model declined the request, `message` being its reply
+    Refusal(Refusal),
+    /// The provider rejected a request for exceeding the model's context, it
+    /// was compacted and sent again
+    Compacted(Compaction),
+    /// Progress of reading a large attached file
+    ReadProgress(ReadProgress),
+    /// An error that occurred during the turn. Only fatal errors end the turn,
+    /// the stream keeps going after warnings and recoverable errors.
+    Error {
+        severity: ErrorSeverity,
+        message: String,
+    },
+}
+
+/// How much of an attached file was read, in bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadProgress {
+    pub path: String,
+    pub read: u64,
+    pub total: u64,
+}
+
+/// How much an error affects the turn it occurred in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorSeverity {
+    /// Advisory, nothing failed that the agent relies on
+    Warning,
+    /// Something failed, eg. a tool call, but the agent carries on
+    Recoverable,
+    /// The turn can't continue
+    Fatal,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_error_serializes_severity() {
+        let fixture = ChatResponse::Error {
+            severity: ErrorSeverity::Recoverable,
+            message: "Tool 'tool_forge_fs_read' failed".to_string(),
+        };
+        let actual = serde_json::to_value(&fixture).unwrap();
+        let expected = json!({
+            "error": {
+                "severity": "recoverable",
+                "message": "Tool 'tool_forge_fs_read' failed"
+            }
+        });
+        assert_eq!(actual, expected);
+    }
 }
@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+
+/// Line prefixes that start a definition, which makes the line a good place
+/// to split a chunk. Headings and attributes are included since they
+/// introduce what follows them.
+const DEFINITION_PREFIXES: &[&str] = &[
+    "fn ",
+    "pub fn ",
+    "pub(crate) fn ",
+    "async fn ",
+    "pub async fn ",
+    "impl ",
+    "impl<",
+    "def ",
+    "async def ",
+    "class ",
+    "function ",
+    "export ",
+    "#",
+];
+
+/// Controls how documents are split before they are embedded. Sizes are in
+/// bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkConfig {
+    /// Maximum size of a chunk. A single line longer than this becomes a
+    /// chunk of its own.
+    pub size: usize,
+    /// Size of the trailing lines of a chunk that are repeated at the start
+    /// of the next one, so that context isn't lost at the boundary
+    pub overlap: usize,
+}
+
+impl Default for ChunkConfig {
+    fn default() -> Self {
+        Self { size: 1500, overlap: 200 }
+    }
+}
+
+/// A piece of a document. Lines are 1-based and inclusive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    pub content: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// Splits text into chunks of whole lines of at most `config.size` bytes.
+/// When a chunk has to be cut, it ends before a paragraph or a definition if
+/// there is one in its second half, otherwise it is cut at the last line that
+/// fits.
+pub fn chunk_text(text: &str, config: &ChunkConfig) -> Vec<Chunk> {
+    let lines = text.split_inclusive('\n').collect::<Vec<_>>();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < lines.len() {
+        let mut end = start;
+        let mut size = 0;
+        while end < lines.len() && (end == start || size + lines[end].len() <= config.size) {
+            size += lines[end].len();
+            end += 1;
+        }
+
+        if end < lines.len() {
+            let mut prefix = size;
+            for boundary in (start + 1..end).rev() {
+                prefix -= lines[boundary].len();
+                if prefix < config.size / 2 {
+                    break;
+                }
+                if is_boundary(&lines, boundary) {
+                    end = boundary;
+                    break;
+                }
+            }
+        }
+
+        chunks.push(Chunk {
+            content: lines[start..end].concat(),
+            start_line: start + 1,
+            end_line: end,
+        });
+
+        if end == lines.len() {
+            break;
+        }
+
+        // Step back over whole lines for the overlap, always making progress
+        let mut next = end;
+        let mut overlap = 0;
+        while next > start + 1 && overlap + lines[next - 1].len() <= config.overlap {
+            next -= 1;
+            overlap += lines[next].len();
+        }
+        start = next;
+    }
+
+    chunks
+}
+
+/// Whether a chunk may start at the given line, ie. after a blank line or at
+/// the start of a definition
+fn is_boundary(lines: &[&str], index: usize) -> bool {
+    let line = lines[index].trim_start();
+    lines[index - 1].trim().is_empty()
+        || DEFINITION_PREFIXES
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn lines(chunks: &[Chunk]) -> Vec<(usize, usize)> {
+        chunks
+            .iter()
+            .map(|chunk| (chunk.start_line, chunk.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_small_text_is_a_single_chunk() {
+        let fixture = "one\ntwo\n";
+        let actual = chunk_text(fixture, &ChunkConfig { size: 100, overlap: 10 });
+        let expected = vec![Chunk { content: fixture.to_string(), start_line: 1, end_line: 2 }];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_prefers_paragraph_boundaries() {
+        // 10 bytes per line, paragraphs of 3 lines
+        let fixture = "aaaaaaaaa\naaaaaaaaa\naaaaaaaaa\n\nbbbbbbbbb\nbbbbbbbbb\nbbbbbbbbb\n";
+        let actual = chunk_text(fixture, &ChunkConfig { size: 50, overlap: 0 });
+
+        // A naive cut would end after the first line of the second paragraph
+        assert_eq!(lines(&actual), vec![(1, 4), (5, 7)]);
+        assert_eq!(actual[1].content, "bbbbbbbbb\n".repeat(3));
+    }
+
+    #[test]
+    fn test_prefers_definition_boundaries() {
+        let fixture = "fn a() {\n    one();\n}\nfn b() {\n    two();\n}\n";
+        let actual = chunk_text(fixture, &ChunkConfig { size: 40, overlap: 0 });
+        assert_eq!(lines(&actual), vec![(1, 3), (4, 6)]);
+    }
+
+    #[test]
+    fn test_ignores_boundaries_that_make_tiny_chunks() {
+        let fixture = "aaaaaaaaa\n\nccccccccc\nddddddddd\neeeeeeeee\nfffffffff\n";
+        let actual = chunk_text(fixture, &ChunkConfig { size: 40, overlap: 0 });
+        assert_eq!(lines(&actual), vec![(1, 4), (5, 6)]);
+    }
+
+    #[test]
+    fn test_chunks_overlap() {
+        let fixture = (1..=6)
+            .map(|i| format!("line {i:04}\n"))
+            .collect::<String>();
+        let actual = chunk_text(&fixture, &ChunkConfig { size: 33, overlap: 11 });
+
+        assert_eq!(lines(&actual), vec![(1, 3), (3, 5), (5, 6)]);
+        assert!(actual[1].content.starts_with("line 0003\n"));
+    }
+
+    #[test]
+    fn test_overlap_always_makes_progress() {
+        let fixture = "aaaaaaaaa\nbbbbbbbbb\nccccccccc\n";
+        let actual = chunk_text(fixture, &ChunkConfig { size: 5, overlap: 100 });
+        assert_eq!(lines(&actual), vec![(1, 1), (2, 2), (3, 3)]);
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use crate::summarize::token_count;
+use crate::{Context, ContextMessage, ToolContent};
+
+/// Replaces the tool outputs left out of a compacted request
+pub const ELIDED_TOOL_OUTPUT: &str =
+    "[Output removed to fit the model's context window, call the tool again if it's still needed]";
+
+/// Whether requests that exceed the model's context are compacted and sent
+/// again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompactionPolicy {
+    /// Compact a request the provider rejected for exceeding the model's
+    /// context and send it once more. The stored context is left as it is.
+    pub auto: bool,
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        Self { auto: true }
+    }
+}
+
+/// A request that was compacted to fit the model's context, sizes being
+/// estimated in tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Compaction {
+    pub before: usize,
+    pub after: usize,
+}
+
+/// Ballpark number of tokens of the context
+pub fn estimate_tokens(context: &Context) -> usize {
+    token_count(&context.to_text())
+}
+
+/// Shrinks a request by replacing the outputs of the tool calls the model
+/// already responded to with [`ELIDED_TOOL_OUTPUT`]. The results of the last
+/// step, which the model hasn't seen yet, and every other message are kept.
+/// Returns `None` when there's nothing left to remove.
+pub fn compact_request(context: &Context) -> Option<Context> {
+    let last_step = context
+        .messages
+        .iter()
+        .rposition(|message| !matches!(message, ContextMessage::ToolMessage(_)))
+        .unwrap_or_default();
+
+    let mut context = context.clone();
+    let mut compacted = false;
+    for message in &mut context.messages[..last_step] {
+        if let ContextMessage::ToolMessage(result) = message {
+            if result.content.to_string().len() > ELIDED_TOOL_OUTPUT.len() {
+                result.content = ToolContent::from(ELIDED_TOOL_OUTPUT);
+                compacted = true;
+            }
+        }
+    }
+    compacted.then_some(context)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{ToolCallFull, ToolCallId, ToolName, ToolResult};
+
+    fn result(id: &str, output: &str) -> ToolResult {
+        ToolResult::new(ToolName::new("tool_forge_fs_read"))
+            .call_id(ToolCallId::new(id))
+            .success(output)
+    }
+
+    fn call(id: &str) -> ToolCallFull {
+        ToolCallFull::new(ToolName::new("tool_forge_fs_read")).call_id(ToolCallId::new(id))
+    }
+
+    #[test]
+    fn test_outputs_the_model_responded_to_are_removed() {
+        let large = "fn main() {}\n".repeat(100);
+        let fixture = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("fix the build"))
+            .add_message(ContextMessage::assistant(
+                "",
+                Some(vec![call("1"), call("2")]),
+            ))
+            .add_tool_results(vec![result("1", &large), result("2", "ok")])
+            .add_message(ContextMessage::assistant("", Some(vec![call("3")])))
+            .add_tool_results(vec![result("3", &large)]);
+
+        let actual = compact_request(&fixture).unwrap();
+
+        let expected = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("fix the build"))
+            .add_message(ContextMessage::assistant(
+                "",
+                Some(vec![call("1"), call("2")]),
+            ))
+            .add_tool_results(vec![result("1", ELIDED_TOOL_OUTPUT), result("2", "ok")])
+            .add_message(ContextMessage::assistant("", Some(vec![call("3")])))
+            .add_tool_results(vec![result("3", &large)]);
+        assert_eq!(actual, expected);
+        assert!(estimate_tokens(&actual) < estimate_tokens(&fixture));
+        assert_eq!(compact_request(&actual), None);
+    }
+
+    #[test]
+    fn test_nothing_to_compact() {
+        let large = "fn main() {}\n".repeat(100);
+        let fixture = Context::default()
+            .add_message(ContextMessage::user(&large))
+            .add_message(ContextMessage::assistant("", Some(vec![call("1")])))
+            .add_tool_results(vec![result("1", &large)]);
+
+        let actual = compact_request(&fixture);
+
+        assert_eq!(actual, None);
+    }
+}
@@ -1,12 +1,18 @@
 use std::collections::HashSet;
+use std::fmt;
 
 use derive_more::derive::{Display, From};
 use derive_setters::Setters;
-use serde::{Deserialize, Serialize};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
 use tracing::debug;
 
 use super::{ToolCallFull, ToolResult};
-use crate::{ToolChoice, ToolDefinition};
+use crate::{
+    is_verify_note, ToolCallId, ToolChoice, ToolDefinition, ToolName, INTERRUPTED_TURN_NOTE,
+    REFUSAL_RETRY_NOTE, VERIFY_SUCCESS_TAG,
+};
 
 #[derive(
     Debug, schemars::JsonSchema, serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Hash,
@@ -120,6 +126,7 @@ impl ContextMessage {
             role: Role::User,
             content: content.to_string(),
             tool_calls: None,
+            evidence: None,
         }
         .into()
     }
@@ -129,6 +136,7 @@ impl ContextMessage {
             role: Role::System,
             content: content.to_string(),
             tool_calls: None,
+            evidence: None,
         }
         .into()
     }
@@ -140,6 +148,7 @@ impl ContextMessage {
             role: Role::Assistant,
             content: content.to_string(),
             tool_calls,
+            evidence: None,
         }
         .into()
     }
@@ -148,6 +157,17 @@ impl ContextMessage {
         Self::ToolMessage(result)
     }
 
+    /// Records the tool calls an assistant message can draw on, other messages
+    /// are left as they are
+    pub fn with_evidence(mut self, evidence: Vec<ToolCallId>) -> Self {
+        if let ContextMessage::ContentMessage(message) = &mut self {
+            if message.role == Role::Assistant && !evidence.is_empty() {
+                message.evidence = Some(evidence);
+            }
+        }
+        self
+    }
+
     pub fn has_role(&self, role: Role) -> bool {
         match self {
             ContextMessage::ContentMessage(message) => message.role == role,
@@ -164,6 +184,11 @@ pub struct ContentMessage {
     pub role: Role,
     pub content: String,
     pub tool_calls: Option<Vec<ToolCallFull>>,
+    /// Tool calls of the same turn whose results preceded this assistant
+    /// message. It's bookkeeping for UIs linking claims to their evidence and
+    /// is never sent to the model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<Vec<ToolCallId>>,
 }
 
 impl ContentMessage {
@@ -172,6 +197,7 @@ impl ContentMessage {
             role: Role::Assistant,
             content: content.to_string(),
             tool_calls: None,
+            evidence: None,
         }
     }
 }
@@ -185,16 +211,165 @@ pub enum Role {
 
 /// Represents a request being made to the LLM provider. By default the request
 /// is created with assuming the model supports use of external tools.
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, Setters, Default)]
+///
+/// The system message is kept apart from the other messages, so transforming
+/// the messages, eg. to compact them, can't drop or duplicate it. Providers
+/// put it back at the head of the messages when sending the request.
+///
+/// A context is stored with the version of its form, older contexts are
+/// migrated to the current form when they are read.
+#[derive(Clone, Debug, PartialEq, Setters, Default)]
 #[setters(into, strip_option)]
 pub struct Context {
+    pub system: Option<String>,
     pub messages: Vec<ContextMessage>,
     pub tools: Vec<ToolDefinition>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<ToolChoice>,
 }
 
+/// Version of the form contexts are stored in. Raise it along with a
+/// migration in [`MIGRATIONS`] whenever a change to the form would break
+/// reading contexts stored before.
+pub const CONTEXT_VERSION: u64 = 1;
+
+/// Migrations of stored contexts, the one at index `n` upgrades a context of
+/// version `n` to version `n + 1`. Contexts stored before they had a version
+/// are version 0.
+const MIGRATIONS: [fn(&mut Map<String, Value>); CONTEXT_VERSION as usize] = [system_field];
+
+/// Version 1 keeps the system message in a field of its own, contexts stored
+/// before have it among their messages
+fn system_field(context: &mut Map<String, Value>) {
+    let Some(Value::Array(messages)) = context.get_mut("messages") else {
+        return;
+    };
+    let mut system = None;
+    messages.retain(|message| {
+        let content = message
+            .get("content_message")
+            .filter(|content| content.get("role") == Some(&Value::from("System")));
+        match content {
+            Some(content) => {
+                system = content.get("content").cloned();
+                false
+            }
+            None => true,
+        }
+    });
+    if let Some(system) = system {
+        context.insert("system".to_string(), system);
+    }
+}
+
+/// Upgrades a stored context to the current version
+fn migrate(context: &mut Map<String, Value>) -> crate::Result<()> {
+    let version = match context.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .filter(|version| *version <= CONTEXT_VERSION)
+            .ok_or_else(|| crate::Error::UnsupportedContextVersion(version.to_string()))?,
+    };
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(context);
+    }
+    context.insert("version".to_string(), CONTEXT_VERSION.into());
+    Ok(())
+}
+
+/// Context as it's stored, once migrated to the current version
+#[derive(Deserialize)]
+struct StoredContext {
+    #[serde(default)]
+    system: Option<String>,
+    messages: Vec<ContextMessage>,
+    tools: Vec<ToolDefinition>,
+    #[serde(default)]
+    tool_choice: Option<ToolChoice>,
+}
+
+impl From<StoredContext> for Context {
+    fn from(stored: StoredContext) -> Self {
+        Context {
+            system: stored.system,
+            messages: stored.messages,
+            tools: stored.tools,
+            tool_choice: stored.tool_choice,
+        }
+    }
+}
+
+/// Context as it's written, tagged with the current version
+#[derive(Serialize)]
+struct StoringContext<'a> {
+    version: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<&'a str>,
+    messages: &'a [ContextMessage],
+    tools: &'a [ToolDefinition],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<&'a ToolChoice>,
+}
+
+impl Serialize for Context {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StoringContext {
+            version: CONTEXT_VERSION,
+            system: self.system.as_deref(),
+            messages: &self.messages,
+            tools: &self.tools,
+            tool_choice: self.tool_choice.as_ref(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Context {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut stored = Value::deserialize(deserializer)?;
+        if let Value::Object(context) = &mut stored {
+            migrate(context).map_err(D::Error::custom)?;
+        }
+        StoredContext::deserialize(stored)
+            .map(Context::from)
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Opening tag of the user message holding an attached file
+fn file_tag(path: &str) -> String {
+    format!("<file_content path=\"{path}\">")
+}
+
+fn file_message(path: &str, content: &str) -> String {
+    format!("{}{content}</file_content>", file_tag(path))
+}
+
+fn user_content(message: &ContextMessage) -> Option<&str> {
+    match message {
+        ContextMessage::ContentMessage(ContentMessage { role: Role::User, content, .. }) => {
+            Some(content)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the message is a note the orchestrator added, eg. about a
+/// verification, an interrupted turn or a retried refusal, rather than a
+/// prompt of the user
+fn is_note(message: &ContextMessage) -> bool {
+    user_content(message).is_some_and(|content| {
+        content == INTERRUPTED_TURN_NOTE || content == REFUSAL_RETRY_NOTE || is_verify_note(content)
+    })
+}
+
 impl Context {
+    /// A builder that checks the order of the messages, for contexts that
+    /// are sent as they are built
+    pub fn builder() -> ContextBuilder {
+        ContextBuilder::default()
+    }
+
     pub fn add_url(mut self, url: &str) -> Self {
         self.messages.push(ContextMessage::Image(url.to_string()));
         self
@@ -206,11 +381,69 @@ impl Context {
         self
     }
 
+    /// Adds a message to the context. A system message replaces the system
+    /// message of the context instead of being added to the messages.
     pub fn add_message(mut self, content: impl Into<ContextMessage>) -> Self {
         let message = content.into();
         debug!(message = ?message, "Adding message to context");
-        self.messages.push(message);
+        self.push(message);
+
+        self
+    }
+
+    /// Adds the message a turn starts with. A verification note the previous
+    /// turn ended with is folded into it, so that the request doesn't carry
+    /// two user messages in a row.
+    pub fn add_user_message(mut self, content: impl ToString) -> Self {
+        let content = content.to_string();
+        let note = self
+            .messages
+            .last()
+            .and_then(user_content)
+            .filter(|note| note.starts_with(VERIFY_SUCCESS_TAG))
+            .map(str::to_string);
+        match note {
+            Some(note) => {
+                self.messages.pop();
+                self.add_message(ContextMessage::user(format!("{note}\n\n{content}")))
+            }
+            None => self.add_message(ContextMessage::user(content)),
+        }
+    }
+
+    fn push(&mut self, message: ContextMessage) {
+        match message {
+            ContextMessage::ContentMessage(ContentMessage {
+                role: Role::System, content, ..
+            }) => {
+                self.system = Some(content);
+            }
+            message => self.messages.push(message),
+        }
+    }
+
+    /// Whether the context holds the attached file with this content
+    pub fn has_file(&self, path: &str, content: &str) -> bool {
+        let message = file_message(path, content);
+        self.messages
+            .iter()
+            .any(|existing| user_content(existing) == Some(message.as_str()))
+    }
+
+    /// Adds the content of an attached file. The context holds one copy of
+    /// every file: the file is left as is when its content didn't change, and
+    /// an older copy is removed when it did.
+    pub fn add_file(mut self, path: &str, content: &str) -> Self {
+        if self.has_file(path, content) {
+            return self;
+        }
 
+        let tag = file_tag(path);
+        self.messages.retain(|existing| {
+            !user_content(existing).is_some_and(|existing| existing.starts_with(&tag))
+        });
+        self.messages
+            .push(ContextMessage::user(file_message(path, content)));
         self
     }
 
@@ -229,30 +462,90 @@ impl Context {
         self
     }
 
-    /// Updates the set system message
-    pub fn set_first_system_message(mut self, content: impl Into<String>) -> Self {
-        if self.messages.is_empty() {
-            self.add_message(ContextMessage::system(content.into()))
-        } else {
-            if let Some(ContextMessage::ContentMessage(content_message)) = self.messages.get_mut(0)
-            {
-                if content_message.role == Role::System {
-                    content_message.content = content.into();
-                } else {
-                    self.messages
-                        .insert(0, ContextMessage::system(content.into()));
-                }
-            }
+    /// The messages to send to the provider, headed by the system message
+    /// when there is one
+    pub fn request_messages(self) -> Vec<ContextMessage> {
+        self.system
+            .map(ContextMessage::system)
+            .into_iter()
+            .chain(self.messages)
+            .collect()
+    }
 
-            self
+    /// Ids of the tool calls whose results were added since the last user
+    /// message, ie. during the current turn
+    pub fn turn_evidence(&self) -> Vec<ToolCallId> {
+        let mut evidence = self
+            .messages
+            .iter()
+            .rev()
+            .take_while(|message| !message.has_role(Role::User))
+            .filter_map(|message| match message {
+                ContextMessage::ToolMessage(result) => result.call_id.clone(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        evidence.reverse();
+        evidence
+    }
+
+    /// Keeps the system message and the last `n` complete exchanges, an
+    /// exchange being the user messages that start it along with the replies,
+    /// tool calls and results that follow. An exchange still in progress
+    /// is kept on top of them so the pending prompt isn't lost. Messages are
+    /// only dropped at the start of an exchange, so every tool result that's
+    /// kept keeps its call. Notes the orchestrator added belong to the
+    /// exchange they follow, they never start one.
+    pub fn last_exchanges(mut self, n: usize) -> Self {
+        let starts = self
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(position, message)| {
+                message.has_role(Role::User)
+                    && !is_note(message)
+                    && (*position == 0 || {
+                        let previous = &self.messages[position - 1];
+                        !previous.has_role(Role::User) || is_note(previous)
+                    })
+            })
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+
+        let in_progress = !starts.is_empty()
+            && !self
+                .messages
+                .iter()
+                .rfind(|message| !is_note(message))
+                .is_some_and(|message| {
+                    matches!(
+                        message,
+                        ContextMessage::ContentMessage(ContentMessage {
+                            role: Role::Assistant,
+                            tool_calls,
+                            ..
+                        }) if tool_calls.as_ref().is_none_or(Vec::is_empty)
+                    )
+                });
+        let keep = n + usize::from(in_progress);
+        if keep >= starts.len() {
+            return self;
         }
+
+        let cut = match keep {
+            0 => self.messages.len(),
+            keep => starts[starts.len() - keep],
+        };
+        self.messages.drain(..cut);
+        self
     }
 
     /// Converts the context to textual format
     pub fn to_text(&self) -> String {
         let mut lines = String::new();
+        let system = self.system.clone().map(ContextMessage::system);
 
-        for message in self.messages.iter() {
+        for message in system.iter().chain(self.messages.iter()) {
             match message {
                 ContextMessage::ContentMessage(message) => {
                     lines.push_str(&format!("<message role=\"{}\">", message.role));
@@ -289,11 +582,397 @@ impl Context {
     }
 }
 
+/// Builds a context whose messages are in an order providers accept: at most
+/// one system message, no assistant message directly following another one,
+/// and every tool call answered by a result right after it. Consecutive user
+/// messages are fine, attached files are sent that way.
+#[derive(Clone, Debug, Default)]
+pub struct ContextBuilder {
+    systems: Vec<String>,
+    messages: Vec<ContextMessage>,
+    tools: Vec<ToolDefinition>,
+    tool_choice: Option<ToolChoice>,
+}
+
+impl ContextBuilder {
+    pub fn system(self, content: impl ToString) -> Self {
+        self.message(ContextMessage::system(content))
+    }
+
+    pub fn user(self, content: impl ToString) -> Self {
+        self.message(ContextMessage::user(content))
+    }
+
+    pub fn assistant(self, content: impl ToString, tool_calls: Vec<ToolCallFull>) -> Self {
+        self.message(ContextMessage::assistant(content, Some(tool_calls)))
+    }
+
+    pub fn tool_result(self, result: ToolResult) -> Self {
+        self.message(ContextMessage::tool_result(result))
+    }
+
+    pub fn image(self, url: impl ToString) -> Self {
+        self.message(ContextMessage::Image(url.to_string()))
+    }
+
+    /// Adds a message of any kind, unlike [`Context::add_message`] a second
+    /// system message is an error rather than a replacement
+    pub fn message(mut self, message: impl Into<ContextMessage>) -> Self {
+        match message.into() {
+            ContextMessage::ContentMessage(ContentMessage {
+                role: Role::System, content, ..
+            }) => self.systems.push(content),
+            message => self.messages.push(message),
+        }
+        self
+    }
+
+    pub fn tool(mut self, tool: impl Into<ToolDefinition>) -> Self {
+        self.tools.push(tool.into());
+        self
+    }
+
+    pub fn tool_choice(mut self, tool_choice: ToolChoice) -> Self {
+        self.tool_choice = Some(tool_choice);
+        self
+    }
+
+    /// The context, or every problem with the order of its messages
+    pub fn build(self) -> crate::Result<Context> {
+        let mut issues = Vec::new();
+        if self.systems.len() > 1 {
+            issues.push(ContextIssue::MultipleSystemMessages(self.systems.len()));
+        }
+        issues.extend(message_issues(&self.messages));
+        if !issues.is_empty() {
+            return Err(crate::Error::InvalidContext(issues));
+        }
+
+        Ok(Context {
+            system: self.systems.into_iter().next(),
+            messages: self.messages,
+            tools: self.tools,
+            tool_choice: self.tool_choice,
+        })
+    }
+}
+
+fn message_issues(messages: &[ContextMessage]) -> Vec<ContextIssue> {
+    let mut issues = Vec::new();
+    // Calls of the last assistant message that weren't answered yet
+    let mut pending: Vec<&ToolCallFull> = Vec::new();
+    let mut called_at = 0;
+    let mut follows_assistant = false;
+
+    for (position, message) in messages.iter().enumerate() {
+        if let ContextMessage::ToolMessage(result) = message {
+            match pending.iter().position(|call| answers(result, call)) {
+                Some(index) => {
+                    pending.remove(index);
+                }
+                None => issues
+                    .push(ContextIssue::OrphanToolResult { position, name: result.name.clone() }),
+            }
+            follows_assistant = false;
+            continue;
+        }
+
+        issues.extend(
+            pending
+                .drain(..)
+                .map(|call| ContextIssue::MissingToolResult {
+                    position: called_at,
+                    name: call.name.clone(),
+                }),
+        );
+        let is_assistant = message.has_role(Role::Assistant);
+        if is_assistant && follows_assistant {
+            issues.push(ContextIssue::ConsecutiveAssistantMessages { position });
+        }
+        follows_assistant = is_assistant;
+        if let ContextMessage::ContentMessage(ContentMessage { tool_calls: Some(calls), .. }) =
+            message
+        {
+            pending = calls.iter().collect();
+            called_at = position;
+        }
+    }
+
+    issues.extend(
+        pending
+            .drain(..)
+            .map(|call| ContextIssue::MissingToolResult {
+                position: called_at,
+                name: call.name.clone(),
+            }),
+    );
+    issues
+}
+
+/// Results are paired with calls by id, or by name when the calls have no
+/// ids, as is the case of calls parsed from the model's text
+fn answers(result: &ToolResult, call: &ToolCallFull) -> bool {
+    match (&result.call_id, &call.call_id) {
+        (Some(result_id), Some(call_id)) => result_id == call_id,
+        (None, None) => result.name == call.name,
+        _ => false,
+    }
+}
+
+/// A single problem found while building a context. Positions are those of
+/// the messages, the system message excluded.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContextIssue {
+    MultipleSystemMessages(usize),
+    ConsecutiveAssistantMessages { position: usize },
+    OrphanToolResult { position: usize, name: ToolName },
+    MissingToolResult { position: usize, name: ToolName },
+}
+
+impl fmt::Display for ContextIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContextIssue::MultipleSystemMessages(count) => {
+                write!(f, "{} system messages were given, at most one is allowed", count)
+            }
+            ContextIssue::ConsecutiveAssistantMessages { position } => {
+                write!(f, "message {} is an assistant message following another one", position)
+            }
+            ContextIssue::OrphanToolResult { position, name } => write!(
+                f,
+                "the result of '{}' at message {} doesn't answer a call of the assistant message before it",
+                name.as_str(),
+                position
+            ),
+            ContextIssue::MissingToolResult { position, name } => write!(
+                f,
+                "the call of '{}' at message {} isn't followed by its result",
+                name.as_str(),
+                position
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::{ToolName, VERIFY_FAILURE_TAG};
+
+    #[test]
+    fn test_evidence_defaults_to_none() {
+        let fixture = r#"{"role":"Assistant","content":"done","tool_calls":null}"#;
+
+        let actual: ContentMessage = serde_json::from_str(fixture).unwrap();
+
+        assert_eq!(actual.evidence, None);
+        assert_eq!(serde_json::to_string(&actual).unwrap(), fixture);
+    }
+
+    #[test]
+    fn test_turn_evidence_starts_at_the_last_user_message() {
+        let result = |id: &str| {
+            ToolResult::new(ToolName::new("tool_forge_fs_read"))
+                .call_id(ToolCallId::new(id))
+                .success("")
+        };
+        let fixture = Context::default()
+            .add_message(ContextMessage::user("read main.rs"))
+            .add_tool_results(vec![result("call_1")])
+            .add_message(ContextMessage::user("and lib.rs"))
+            .add_message(ContextMessage::assistant("", None))
+            .add_tool_results(vec![result("call_2"), result("call_3")]);
+
+        let actual = fixture.turn_evidence();
+
+        let expected = vec![ToolCallId::new("call_2"), ToolCallId::new("call_3")];
+        assert_eq!(actual, expected);
+    }
+
+    fn file_messages(context: &Context) -> Vec<&str> {
+        context
+            .messages
+            .iter()
+            .filter_map(user_content)
+            .filter(|content| content.starts_with("<file_content"))
+            .collect()
+    }
+
+    /// Three exchanges calling tools, the first one with an attached file
+    fn exchanges() -> Context {
+        Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("explain @main.rs"))
+            .add_file("main.rs", "fn main() {}")
+            .add_message(ContextMessage::assistant(
+                "",
+                Some(vec![read_call("call_1")]),
+            ))
+            .add_tool_results(vec![read_result("call_1")])
+            .add_message(ContextMessage::assistant("It does nothing", None))
+            .add_message(ContextMessage::user("and lib.rs?"))
+            .add_message(ContextMessage::assistant(
+                "",
+                Some(vec![read_call("call_2"), read_call("call_3")]),
+            ))
+            .add_tool_results(vec![read_result("call_2"), read_result("call_3")])
+            .add_message(ContextMessage::assistant("It declares a module", None))
+            .add_message(ContextMessage::user("thanks"))
+            .add_message(ContextMessage::assistant("You're welcome", None))
+    }
+
+    fn orphans(context: &Context) -> Vec<ContextIssue> {
+        message_issues(&context.messages)
+            .into_iter()
+            .filter(|issue| matches!(issue, ContextIssue::OrphanToolResult { .. }))
+            .collect()
+    }
+
+    #[test]
+    fn test_last_exchanges_keeps_tool_results_with_their_calls() {
+        let actual = exchanges().last_exchanges(2);
+
+        let expected = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("and lib.rs?"))
+            .add_message(ContextMessage::assistant(
+                "",
+                Some(vec![read_call("call_2"), read_call("call_3")]),
+            ))
+            .add_tool_results(vec![read_result("call_2"), read_result("call_3")])
+            .add_message(ContextMessage::assistant("It declares a module", None))
+            .add_message(ContextMessage::user("thanks"))
+            .add_message(ContextMessage::assistant("You're welcome", None));
+        assert_eq!(actual, expected);
+        assert_eq!(orphans(&actual), vec![]);
+    }
+
+    #[test]
+    fn test_last_exchanges_keeps_the_exchange_in_progress() {
+        let fixture = exchanges()
+            .add_message(ContextMessage::user("read it again"))
+            .add_message(ContextMessage::assistant(
+                "",
+                Some(vec![read_call("call_4")]),
+            ))
+            .add_tool_results(vec![read_result("call_4")]);
+
+        let actual = fixture.last_exchanges(1);
+
+        let expected = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("thanks"))
+            .add_message(ContextMessage::assistant("You're welcome", None))
+            .add_message(ContextMessage::user("read it again"))
+            .add_message(ContextMessage::assistant(
+                "",
+                Some(vec![read_call("call_4")]),
+            ))
+            .add_tool_results(vec![read_result("call_4")]);
+        assert_eq!(actual, expected);
+        assert_eq!(orphans(&actual), vec![]);
+    }
+
+    #[test]
+    fn test_last_exchanges_keeps_notes_with_the_exchange_they_follow() {
+        let passed = format!("{VERIFY_SUCCESS_TAG}Checks run after your changes passed: `cargo test`</verification_passed>");
+        let fixture = exchanges()
+            .add_message(ContextMessage::user(REFUSAL_RETRY_NOTE))
+            .add_message(ContextMessage::assistant("Sure, here it is", None))
+            .add_message(ContextMessage::user(INTERRUPTED_TURN_NOTE))
+            .add_message(ContextMessage::user("fix the tests"))
+            .add_message(ContextMessage::assistant("Fixed", None))
+            .add_message(ContextMessage::user(format!(
+                "{VERIFY_FAILURE_TAG}\ncargo test failed\n</verification_failed>"
+            )))
+            .add_message(ContextMessage::assistant("Fixed for real", None))
+            .add_message(ContextMessage::user(passed.clone()));
+
+        let actual = fixture.clone().last_exchanges(1);
+
+        let expected = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("fix the tests"))
+            .add_message(ContextMessage::assistant("Fixed", None))
+            .add_message(ContextMessage::user(format!(
+                "{VERIFY_FAILURE_TAG}\ncargo test failed\n</verification_failed>"
+            )))
+            .add_message(ContextMessage::assistant("Fixed for real", None))
+            .add_message(ContextMessage::user(passed.clone()));
+        assert_eq!(actual, expected);
+
+        // A success note folded into the next prompt starts an exchange
+        let actual = fixture.add_user_message("now the docs").last_exchanges(1);
+
+        let expected = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("fix the tests"))
+            .add_message(ContextMessage::assistant("Fixed", None))
+            .add_message(ContextMessage::user(format!(
+                "{VERIFY_FAILURE_TAG}\ncargo test failed\n</verification_failed>"
+            )))
+            .add_message(ContextMessage::assistant("Fixed for real", None))
+            .add_message(ContextMessage::user(format!("{passed}\n\nnow the docs")));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_last_exchanges_keeps_attached_files_with_their_prompt() {
+        let actual = exchanges().last_exchanges(3);
+
+        assert_eq!(actual, exchanges());
+
+        let actual = exchanges().last_exchanges(0);
+
+        assert_eq!(actual, Context::default().system("Be brief"));
+    }
+
+    #[test]
+    fn test_add_same_file_twice() {
+        let fixture = Context::default()
+            .add_message(ContextMessage::user("explain @src/lib.rs"))
+            .add_file("src/lib.rs", "mod a;")
+            .add_message(ContextMessage::user("and now @src/lib.rs"));
+
+        let actual = fixture.clone().add_file("src/lib.rs", "mod a;");
+
+        assert_eq!(actual, fixture);
+        assert_eq!(
+            file_messages(&actual),
+            vec!["<file_content path=\"src/lib.rs\">mod a;</file_content>"]
+        );
+    }
+
+    #[test]
+    fn test_add_modified_file_replaces_it() {
+        let fixture = Context::default()
+            .add_file("src/lib.rs", "mod a;")
+            .add_file("src/main.rs", "fn main() {}")
+            .add_message(ContextMessage::user("I changed @src/lib.rs"));
+
+        let actual = fixture.add_file("src/lib.rs", "mod a;\nmod b;");
+
+        assert_eq!(
+            file_messages(&actual),
+            vec![
+                "<file_content path=\"src/main.rs\">fn main() {}</file_content>",
+                "<file_content path=\"src/lib.rs\">mod a;\nmod b;</file_content>",
+            ]
+        );
+        assert!(actual.has_file("src/lib.rs", "mod a;\nmod b;"));
+        assert!(!actual.has_file("src/lib.rs", "mod a;"));
+    }
+
+    #[test]
+    fn test_add_file_keeps_files_sharing_a_prefix() {
+        let actual = Context::default()
+            .add_file("src/lib.rs", "mod a;")
+            .add_file("src/lib.rs.bak", "mod old;");
+
+        assert_eq!(file_messages(&actual).len(), 2);
+    }
 
     #[test]
     fn test_attachment_parse_all_empty() {
@@ -356,33 +1035,247 @@ mod tests {
     fn test_override_system_message() {
         let request = Context::default()
             .add_message(ContextMessage::system("Initial system message"))
-            .set_first_system_message("Updated system message");
+            .system("Updated system message");
 
-        assert_eq!(
-            request.messages[0],
-            ContextMessage::system("Updated system message")
-        );
+        assert_eq!(request.system.as_deref(), Some("Updated system message"));
+        assert_eq!(request.messages, vec![]);
     }
 
     #[test]
-    fn test_set_system_message() {
-        let request = Context::default().set_first_system_message("A system message");
+    fn test_add_system_message() {
+        let request = Context::default()
+            .add_message(ContextMessage::user("Do something"))
+            .add_message(ContextMessage::system("A system message"));
 
-        assert_eq!(
-            request.messages[0],
-            ContextMessage::system("A system message")
-        );
+        assert_eq!(request.system.as_deref(), Some("A system message"));
+        assert_eq!(request.messages, vec![ContextMessage::user("Do something")]);
     }
 
     #[test]
-    fn test_insert_system_message() {
+    fn test_request_messages_start_with_system_message() {
         let request = Context::default()
             .add_message(ContextMessage::user("Do something"))
-            .set_first_system_message("A system message");
+            .system("A system message");
+
+        let actual = request.request_messages();
+
+        let expected = vec![
+            ContextMessage::system("A system message"),
+            ContextMessage::user("Do something"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_deserialize_system_message_stored_as_first_message() {
+        let fixture = r#"{"messages":[{"content_message":{"role":"System","content":"Be brief","tool_calls":null}},{"content_message":{"role":"User","content":"Hi","tool_calls":null}}],"tools":[]}"#;
+
+        let actual: Context = serde_json::from_str(fixture).unwrap();
+
+        let expected = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("Hi"));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_serialized_context_round_trips() {
+        let fixture = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("Hi"));
+
+        let json = serde_json::to_string(&fixture).unwrap();
+        let actual: Context = serde_json::from_str(&json).unwrap();
+
+        assert!(json.starts_with(r#"{"version":1,"system":"Be brief","messages":[{"#));
+        assert_eq!(actual, fixture);
+    }
+
+    #[test]
+    fn test_unversioned_context_is_upgraded() {
+        let fixture = r#"{"messages":[{"content_message":{"role":"System","content":"Be brief","tool_calls":null}},{"content_message":{"role":"User","content":"Read main.rs","tool_calls":null}},{"content_message":{"role":"Assistant","content":"Reading it","tool_calls":[{"name":"tool_forge_fs_read","call_id":"call_1","arguments":{"path":"main.rs"}}]}},{"tool_message":{"name":"tool_forge_fs_read","call_id":"call_1","content":"fn main() {}","is_error":false}}],"tools":[]}"#;
+
+        let actual: Context = serde_json::from_str(fixture).unwrap();
+
+        let call = read_call("call_1").arguments(serde_json::json!({"path": "main.rs"}));
+        let expected = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("Read main.rs"))
+            .add_message(ContextMessage::assistant("Reading it", Some(vec![call])))
+            .add_tool_results(vec![read_result("call_1")]);
+        assert_eq!(actual, expected);
+        let stored = serde_json::to_value(&actual).unwrap();
+        assert_eq!(stored["version"], serde_json::json!(CONTEXT_VERSION));
+    }
+
+    #[test]
+    fn test_newer_context_version_is_rejected() {
+        let fixture = r#"{"version":99,"messages":[],"tools":[]}"#;
+
+        let actual = serde_json::from_str::<Context>(fixture).unwrap_err();
+
+        assert!(actual
+            .to_string()
+            .starts_with("Unsupported context version 99"));
+    }
+
+    fn read_call(id: &str) -> ToolCallFull {
+        ToolCallFull::new(ToolName::new("tool_forge_fs_read")).call_id(ToolCallId::new(id))
+    }
+
+    fn read_result(id: &str) -> ToolResult {
+        ToolResult::new(ToolName::new("tool_forge_fs_read"))
+            .call_id(ToolCallId::new(id))
+            .success("fn main() {}")
+    }
+
+    fn issues(actual: crate::Result<Context>) -> Vec<ContextIssue> {
+        match actual {
+            Err(crate::Error::InvalidContext(issues)) => issues,
+            other => panic!("Expected an invalid context, got {other:?}"),
+        }
+    }
 
+    #[test]
+    fn test_builder_builds_a_valid_context() {
+        let actual = Context::builder()
+            .system("Be brief")
+            .user("explain main.rs")
+            .user("<file_content path=\"lib.rs\">mod a;</file_content>")
+            .assistant("", vec![read_call("call_1"), read_call("call_2")])
+            .tool_result(read_result("call_2"))
+            .tool_result(read_result("call_1"))
+            .assistant("It prints nothing", vec![])
+            .user("thanks")
+            .build()
+            .unwrap();
+
+        let expected = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("explain main.rs"))
+            .add_message(ContextMessage::user(
+                "<file_content path=\"lib.rs\">mod a;</file_content>",
+            ))
+            .add_message(ContextMessage::assistant(
+                "",
+                Some(vec![read_call("call_1"), read_call("call_2")]),
+            ))
+            .add_tool_results(vec![read_result("call_2"), read_result("call_1")])
+            .add_message(ContextMessage::assistant("It prints nothing", None))
+            .add_message(ContextMessage::user("thanks"));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_builder_pairs_calls_without_ids_by_name() {
+        let call = ToolCallFull::new(ToolName::new("tool_forge_fs_read"));
+        let result = ToolResult::new(ToolName::new("tool_forge_fs_read")).success("");
+
+        let actual = Context::builder()
+            .user("read main.rs")
+            .assistant("", vec![call])
+            .tool_result(result)
+            .build();
+
+        assert!(actual.is_ok());
+    }
+
+    #[test]
+    fn test_builder_rejects_an_orphan_tool_result() {
+        let actual = Context::builder()
+            .user("read main.rs")
+            .assistant("", vec![read_call("call_1")])
+            .tool_result(read_result("call_1"))
+            .tool_result(read_result("call_1"))
+            .user("and lib.rs")
+            .tool_result(read_result("call_2"))
+            .build();
+
+        let expected = vec![
+            ContextIssue::OrphanToolResult {
+                position: 3,
+                name: ToolName::new("tool_forge_fs_read"),
+            },
+            ContextIssue::OrphanToolResult {
+                position: 5,
+                name: ToolName::new("tool_forge_fs_read"),
+            },
+        ];
+        assert_eq!(issues(actual), expected);
+    }
+
+    #[test]
+    fn test_builder_rejects_two_system_messages() {
+        let actual = Context::builder()
+            .system("Be brief")
+            .user("Hi")
+            .message(ContextMessage::system("Be thorough"))
+            .build();
+
+        let actual = issues(actual);
+
+        assert_eq!(actual, vec![ContextIssue::MultipleSystemMessages(2)]);
         assert_eq!(
-            request.messages[0],
-            ContextMessage::system("A system message")
+            crate::Error::InvalidContext(actual).to_string(),
+            "Invalid context: 2 system messages were given, at most one is allowed"
         );
     }
+
+    #[test]
+    fn test_builder_reports_every_issue() {
+        let actual = Context::builder()
+            .user("read main.rs")
+            .assistant("Sure", vec![])
+            .assistant("", vec![read_call("call_1")])
+            .user("well?")
+            .assistant("", vec![read_call("call_2")])
+            .build();
+
+        let expected = vec![
+            ContextIssue::ConsecutiveAssistantMessages { position: 2 },
+            ContextIssue::MissingToolResult {
+                position: 2,
+                name: ToolName::new("tool_forge_fs_read"),
+            },
+            ContextIssue::MissingToolResult {
+                position: 4,
+                name: ToolName::new("tool_forge_fs_read"),
+            },
+        ];
+        assert_eq!(issues(actual), expected);
+    }
+
+    #[test]
+    fn test_user_message_takes_in_the_verification_note() {
+        let fixture = Context::default()
+            .add_message(ContextMessage::user("fix the bug"))
+            .add_message(ContextMessage::assistant("fixed", None))
+            .add_message(ContextMessage::user(
+                "<verification_passed>Checks passed</verification_passed>",
+            ));
+
+        let actual = fixture.add_user_message("thanks").messages;
+
+        let expected = vec![
+            ContextMessage::user("fix the bug"),
+            ContextMessage::assistant("fixed", None),
+            ContextMessage::user(
+                "<verification_passed>Checks passed</verification_passed>\n\nthanks",
+            ),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_user_message_after_other_user_message_is_kept_apart() {
+        let fixture = Context::default().add_message(ContextMessage::user("first"));
+
+        let actual = fixture.add_user_message("second").messages;
+
+        let expected = vec![
+            ContextMessage::user("first"),
+            ContextMessage::user("second"),
+        ];
+        assert_eq!(actual, expected);
+    }
 }
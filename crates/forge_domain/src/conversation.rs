@@ -1,13 +1,18 @@
+use std::cmp::Reverse;
 use std::collections::HashMap;
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use derive_more::derive::Display;
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
-use crate::{Agent, AgentId, Context, Error, Event, Workflow};
+use crate::{
+    Agent, AgentId, Context, ContextMessage, ConversationSuggestions, Error, Event, ImportSource,
+    TurnTiming, Workflow, MAX_TURN_TIMINGS,
+};
 
 #[derive(Debug, Display, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[serde(transparent)]
@@ -37,6 +42,23 @@ pub struct Conversation {
     pub events: Vec<Event>,
     pub workflow: Workflow,
     pub variables: HashMap<String, Value>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Tags and title suggested from the conversation's tool activity
+    #[serde(default)]
+    pub suggestions: Option<ConversationSuggestions>,
+    /// Pinned conversations are listed before all others
+    #[serde(default)]
+    pub pinned: bool,
+    /// When an event was last added to the conversation
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    /// Timing breakdown of the most recent turns, oldest first
+    #[serde(default)]
+    pub timings: Vec<TurnTiming>,
+    /// Where the conversation was imported from, when it was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported: Option<ImportSource>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -53,10 +75,22 @@ impl Conversation {
             state: Default::default(),
             events: Default::default(),
             variables: workflow.variables.clone().unwrap_or_default(),
+            tags: Default::default(),
+            suggestions: None,
+            pinned: false,
+            updated_at: Utc::now(),
+            timings: Default::default(),
+            imported: None,
             workflow,
         }
     }
 
+    /// Sorts conversations into the order they are listed in, pinned ones
+    /// first and then the most recently updated
+    pub fn sort(conversations: &mut [Conversation]) {
+        conversations.sort_by_key(|c| (Reverse(c.pinned), Reverse(c.updated_at)));
+    }
+
     pub fn turn_count(&self, id: &AgentId) -> Option<u64> {
         self.state.get(id).map(|s| s.turn_count)
     }
@@ -101,4 +135,95 @@ impl Conversation {
     pub fn delete_variable(&mut self, key: &str) -> bool {
         self.variables.remove(key).is_some()
     }
+
+    /// Add a tag, surrounding whitespace is ignored
+    ///
+    /// Returns false if the tag is blank or the conversation already has it
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        let tag = tag.trim();
+        if tag.is_empty() || self.has_tag(tag) {
+            return false;
+        }
+        self.tags.push(tag.to_string());
+        true
+    }
+
+    /// Remove a tag
+    ///
+    /// Returns true if the tag was present and removed, false otherwise
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let len = self.tags.len();
+        self.tags.retain(|t| t != tag.trim());
+        self.tags.len() != len
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag.trim())
+    }
+
+    /// The title it was imported with, or the one suggested for it
+    pub fn title(&self) -> Option<&str> {
+        self.imported
+            .as_ref()
+            .map(|source| source.title.as_str())
+            .or_else(|| self.suggestions.as_ref()?.title.as_deref())
+    }
+
+    /// Whether the title or any message of the conversation contains the
+    /// text, ignoring case
+    pub fn mentions(&self, text: &str) -> bool {
+        let text = text.trim().to_lowercase();
+        if self
+            .title()
+            .is_some_and(|title| title.to_lowercase().contains(&text))
+        {
+            return true;
+        }
+        self.state
+            .values()
+            .filter_map(|state| state.context.as_ref())
+            .flat_map(|context| context.messages.iter())
+            .any(|message| match message {
+                ContextMessage::ContentMessage(message) => {
+                    message.content.to_lowercase().contains(&text)
+                }
+                _ => false,
+            })
+    }
+
+    /// Records the timing of a completed turn, keeping the last
+    /// [`MAX_TURN_TIMINGS`]
+    pub fn add_turn_timing(&mut self, timing: TurnTiming) {
+        self.timings.push(timing);
+        let excess = self.timings.len().saturating_sub(MAX_TURN_TIMINGS);
+        self.timings.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mentions_matches_title_and_messages() {
+        let mut fixture = Conversation::new(ConversationId::generate(), Workflow::default());
+        fixture.suggestions = Some(ConversationSuggestions {
+            tags: vec![],
+            title: Some("Fix the Billing export".to_string()),
+        });
+        fixture.state.insert(
+            AgentId::new("developer"),
+            AgentState {
+                turn_count: 1,
+                context: Some(
+                    Context::default()
+                        .add_message(ContextMessage::user("why does the parser panic?")),
+                ),
+            },
+        );
+
+        assert!(fixture.mentions("billing"));
+        assert!(fixture.mentions(" PARSER "));
+        assert!(!fixture.mentions("invoice"));
+    }
 }
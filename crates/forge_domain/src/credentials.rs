@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// API keys stored by forge, in the same `NAME=value` format as a `.env`
+/// file. Stored keys take precedence over the environment, since they are
+/// only written when the user replaces a key the provider rejected.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Credentials {
+    vars: BTreeMap<String, String>,
+}
+
+impl Credentials {
+    /// Parses the stored keys, blank lines and comments are ignored
+    pub fn parse(content: &str) -> Self {
+        let vars = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(name, value)| {
+                let value = value.trim().trim_matches('"');
+                (name.trim().to_string(), value.to_string())
+            })
+            .collect();
+        Self { vars }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(String::as_str)
+    }
+
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.vars.insert(name.into(), value.into());
+    }
+}
+
+impl fmt::Display for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.vars {
+            writeln!(f, "{name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_credentials_round_trip() {
+        let fixture = "# forge\nOPENROUTER_API_KEY=\"sk-old\"\n\nFORGE_KEY = fk-1\n";
+        let mut credentials = Credentials::parse(fixture);
+        assert_eq!(credentials.get("OPENROUTER_API_KEY"), Some("sk-old"));
+        assert_eq!(credentials.get("FORGE_KEY"), Some("fk-1"));
+
+        credentials.set("OPENROUTER_API_KEY", "sk-new");
+        let actual = credentials.to_string();
+        let expected = "FORGE_KEY=fk-1\nOPENROUTER_API_KEY=sk-new\n";
+        assert_eq!(actual, expected);
+        assert_eq!(Credentials::parse(&actual), credentials);
+    }
+}
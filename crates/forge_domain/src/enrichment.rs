@@ -0,0 +1,306 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ContextMessage, Conversation, ConversationId, ToolCallFull};
+
+/// Tools that modify files, with the arguments holding the paths they touch
+const MODIFYING_TOOLS: &[(&str, &[&str])] = &[
+    ("tool_forge_fs_create", &["path"]),
+    ("tool_forge_fs_patch", &["path"]),
+    ("tool_forge_fs_remove", &["path"]),
+    ("tool_forge_fs_move", &["source", "destination"]),
+    ("tool_forge_fs_replace", &["path"]),
+];
+
+/// File names that say nothing about the module they belong to
+const GENERIC_FILE_STEMS: &[&str] = &["mod", "lib", "main", "index"];
+
+/// Maximum number of tags that are suggested
+const MAX_TAGS: usize = 3;
+
+/// Tags and title suggested for a conversation based on the files that were
+/// modified in it. They are only stored alongside the conversation, it's up to
+/// the user to apply them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationSuggestions {
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub title: Option<String>,
+}
+
+/// Files modified by tool calls over the course of a conversation
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ToolActivity {
+    pub files: BTreeSet<PathBuf>,
+}
+
+impl ToolActivity {
+    /// Collects the activity from the tool calls stored in every agent's
+    /// context
+    pub fn from_conversation(conversation: &Conversation) -> Self {
+        let mut activity = Self::default();
+        conversation
+            .state
+            .values()
+            .filter_map(|state| state.context.as_ref())
+            .flat_map(|context| context.messages.iter())
+            .filter_map(|message| match message {
+                ContextMessage::ContentMessage(message) => message.tool_calls.as_ref(),
+                _ => None,
+            })
+            .flatten()
+            .for_each(|call| activity.record(call));
+        activity
+    }
+
+    pub fn record(&mut self, call: &ToolCallFull) {
+        let Some((_, keys)) = MODIFYING_TOOLS
+            .iter()
+            .find(|(name, _)| *name == call.name.as_str())
+        else {
+            return;
+        };
+
+        self.files.extend(
+            keys.iter()
+                .filter_map(|key| call.arguments.get(key)?.as_str())
+                .map(PathBuf::from),
+        );
+    }
+
+    /// Derives tags from the areas that were worked on, ie. crate names (or
+    /// top-level directories) and module names, most touched first. Paths
+    /// outside of `cwd` are ignored.
+    pub fn suggest(&self, cwd: &Path) -> Option<ConversationSuggestions> {
+        let mut areas = HashMap::<String, usize>::new();
+        let mut modules = HashMap::<String, usize>::new();
+
+        for file in &self.files {
+            let Ok(relative) = file.strip_prefix(cwd) else {
+                continue;
+            };
+            let dirs = relative
+                .parent()
+                .into_iter()
+                .flat_map(Path::components)
+                .filter_map(|component| match component {
+                    Component::Normal(name) => name.to_str(),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            let area = match dirs.as_slice() {
+                ["crates" | "packages", name, ..] => Some(*name),
+                [name, ..] => Some(*name),
+                [] => None,
+            };
+            if let Some(area) = area {
+                *areas.entry(area.to_string()).or_default() += 1;
+            }
+
+            let module = relative
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .filter(|stem| !GENERIC_FILE_STEMS.contains(stem));
+            if let Some(module) = module {
+                *modules.entry(module.to_string()).or_default() += 1;
+            }
+        }
+
+        let areas = ranked(areas);
+        let modules = ranked(modules);
+        if areas.is_empty() && modules.is_empty() {
+            return None;
+        }
+
+        let title = match (areas.first(), modules.first()) {
+            (Some(area), Some(module)) => format!("Work on {area} {module}"),
+            (Some(name), None) | (None, Some(name)) => format!("Work on {name}"),
+            (None, None) => unreachable!(),
+        };
+
+        let mut tags = Vec::new();
+        for tag in areas.iter().take(MAX_TAGS - 1).chain(modules.iter()) {
+            if tags.len() == MAX_TAGS {
+                break;
+            }
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+
+        Some(ConversationSuggestions { tags, title: Some(title) })
+    }
+}
+
+/// Orders names by how often they occur, then alphabetically
+fn ranked(counts: HashMap<String, usize>) -> Vec<String> {
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    counts.into_iter().map(|(name, _)| name).collect()
+}
+
+/// Runs the end-of-conversation enrichment. Conversations complete in quick
+/// succession when events are chained, so the enrichment of a conversation
+/// runs at most once per debounce interval.
+pub struct ConversationEnricher {
+    cwd: PathBuf,
+    debounce: Duration,
+    last_run: Mutex<HashMap<ConversationId, Instant>>,
+}
+
+impl ConversationEnricher {
+    pub const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(30);
+
+    pub fn new(cwd: PathBuf, debounce: Duration) -> Self {
+        Self { cwd, debounce, last_run: Default::default() }
+    }
+
+    /// Returns new suggestions for the conversation, if any. Nothing is
+    /// suggested when the feature is disabled in the workflow, when the
+    /// conversation already has tags or when the suggestions are unchanged.
+    pub fn enrich(
+        &self,
+        conversation: &Conversation,
+        now: Instant,
+    ) -> Option<ConversationSuggestions> {
+        if !conversation.workflow.suggest_tags.unwrap_or(true) || !conversation.tags.is_empty() {
+            return None;
+        }
+
+        if !self.debounced(&conversation.id, now) {
+            return None;
+        }
+
+        ToolActivity::from_conversation(conversation)
+            .suggest(&self.cwd)
+            .filter(|suggestions| conversation.suggestions.as_ref() != Some(suggestions))
+    }
+
+    fn debounced(&self, id: &ConversationId, now: Instant) -> bool {
+        let mut last_run = self.last_run.lock().unwrap();
+        match last_run.get(id) {
+            Some(last) if now.saturating_duration_since(*last) < self.debounce => false,
+            _ => {
+                last_run.insert(id.clone(), now);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{AgentId, Context, ToolName, Workflow};
+
+    fn call(name: &str, arguments: serde_json::Value) -> ToolCallFull {
+        ToolCallFull::new(ToolName::new(name)).arguments(arguments)
+    }
+
+    fn activity() -> Vec<ToolCallFull> {
+        vec![
+            call(
+                "tool_forge_fs_patch",
+                json!({"path": "/repo/crates/forge_app/src/chat_service.rs"}),
+            ),
+            call(
+                "tool_forge_fs_create",
+                json!({"path": "/repo/crates/forge_app/src/streaming.rs"}),
+            ),
+            call(
+                "tool_forge_fs_move",
+                json!({
+                    "source": "/repo/crates/forge_stream/src/lib.rs",
+                    "destination": "/repo/crates/forge_stream/src/streaming.rs"
+                }),
+            ),
+            call("tool_forge_fs_read", json!({"path": "/repo/README.md"})),
+            call(
+                "tool_forge_fs_create",
+                json!({"path": "/elsewhere/notes.md"}),
+            ),
+        ]
+    }
+
+    fn conversation(calls: Vec<ToolCallFull>) -> Conversation {
+        let mut conversation = Conversation::new(ConversationId::generate(), Workflow::default());
+        let context = Context::default().add_message(ContextMessage::assistant("", Some(calls)));
+        conversation
+            .state
+            .entry(AgentId::new("developer"))
+            .or_default()
+            .context = Some(context);
+        conversation
+    }
+
+    #[test]
+    fn test_suggest_tags_from_activity() {
+        let mut fixture = ToolActivity::default();
+        activity().iter().for_each(|call| fixture.record(call));
+
+        let actual = fixture.suggest(Path::new("/repo"));
+        let expected = Some(ConversationSuggestions {
+            tags: vec![
+                "forge_app".to_string(),
+                "forge_stream".to_string(),
+                "streaming".to_string(),
+            ],
+            title: Some("Work on forge_app streaming".to_string()),
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_activity_ignores_reads() {
+        let mut fixture = ToolActivity::default();
+        fixture.record(&call("tool_forge_fs_read", json!({"path": "/repo/a.rs"})));
+        assert_eq!(fixture.suggest(Path::new("/repo")), None);
+    }
+
+    #[test]
+    fn test_enrich_keeps_existing_tags() {
+        let enricher = ConversationEnricher::new("/repo".into(), Duration::from_secs(30));
+        let fixture = conversation(activity()).tags(vec!["mine".to_string()]);
+
+        let actual = enricher.enrich(&fixture, Instant::now());
+        assert_eq!(actual, None);
+        assert_eq!(fixture.tags, vec!["mine".to_string()]);
+    }
+
+    #[test]
+    fn test_enrich_disabled_by_workflow() {
+        let enricher = ConversationEnricher::new("/repo".into(), Duration::from_secs(30));
+        let mut fixture = conversation(activity());
+        fixture.workflow.suggest_tags = Some(false);
+
+        assert_eq!(enricher.enrich(&fixture, Instant::now()), None);
+    }
+
+    #[test]
+    fn test_enrich_is_debounced() {
+        let enricher = ConversationEnricher::new("/repo".into(), Duration::from_secs(30));
+        let fixture = conversation(activity());
+        let now = Instant::now();
+
+        let actual = [
+            now,
+            now + Duration::from_millis(10),
+            now + Duration::from_secs(5),
+        ]
+        .map(|at| enricher.enrich(&fixture, at).is_some());
+        assert_eq!(actual, [true, false, false]);
+
+        // Once the interval has passed, it runs again
+        let actual = enricher.enrich(&fixture, now + Duration::from_secs(31));
+        assert!(actual.is_some());
+    }
+}
@@ -1,9 +1,13 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 
-use crate::Provider;
+use crate::{
+    FirstTokenTimeout, HttpTimeout, LineEnding, Provider, ProxyConfig, TlsConfig,
+    DEFAULT_QUESTION_TIMEOUT,
+};
 
 #[derive(Debug, Setters, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -30,6 +34,58 @@ pub struct Environment {
     pub provider: Provider,
     /// The OpenAI API key required to use embedding models.
     pub openai_key: Option<String>,
+    /// Additional workspace roots that paths are displayed relative to.
+    #[serde(default)]
+    pub workspace_roots: Vec<PathBuf>,
+    /// Whether forge was started in restricted mode
+    #[serde(default)]
+    pub restricted: bool,
+    /// Whether forge was started in read-only mode, where the agent has no
+    /// tools that change files or run commands
+    #[serde(default)]
+    pub read_only: bool,
+    /// How long to wait for the first token of a response
+    #[serde(default)]
+    pub first_token_timeout: FirstTokenTimeout,
+    /// How long to wait for the user to answer a question the agent asked, or
+    /// to approve a tool call, before carrying on without them
+    #[serde(default = "default_question_timeout")]
+    pub question_timeout: Duration,
+    /// Line ending of the files tools create, the platform's when unset.
+    /// Existing files keep theirs.
+    #[serde(default)]
+    pub line_ending: Option<LineEnding>,
+    /// Proxies of the requests to the provider and of fetched URLs
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    /// Certificates trusted by the requests to the provider and of fetched
+    /// URLs
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Connect and read timeouts of the requests to the provider and of
+    /// fetched URLs
+    #[serde(default)]
+    pub http_timeout: HttpTimeout,
+    /// JSON of OpenRouter's provider routing preferences, eg. the order of
+    /// the providers or those to ignore
+    #[serde(default)]
+    pub provider_preferences: Option<String>,
+    /// Whether requests to Anthropic models through OpenRouter mark the system
+    /// prompt and the last user message for caching
+    #[serde(default)]
+    pub prompt_cache: bool,
+    /// Request transformers of OpenAI compatible providers in the order they
+    /// run, see [`crate::TransformerPipeline::parse`]. The built-in pipeline
+    /// is used when unset.
+    #[serde(default)]
+    pub transformers: Option<String>,
+    /// Threads parsing files when searching symbols, one per CPU when unset
+    #[serde(default)]
+    pub scan_workers: Option<usize>,
+}
+
+fn default_question_timeout() -> Duration {
+    DEFAULT_QUESTION_TIMEOUT
 }
 
 impl Environment {
@@ -47,4 +103,60 @@ impl Environment {
     pub fn snapshot_path(&self) -> PathBuf {
         self.base_path.join("snapshots")
     }
+
+    pub fn recovery_path(&self) -> PathBuf {
+        self.base_path.join("recovery")
+    }
+
+    /// Directory holding the scratch directory of each conversation
+    pub fn scratch_path(&self) -> PathBuf {
+        self.base_path.join("scratch")
+    }
+
+    pub fn stats_path(&self) -> PathBuf {
+        self.base_path.join("stats.jsonl")
+    }
+
+    /// Messages queued while a turn ran, kept across restarts in a file
+    /// per working directory
+    pub fn queues_path(&self) -> PathBuf {
+        self.base_path.join("queues")
+    }
+
+    pub fn index_path(&self) -> PathBuf {
+        self.base_path.join("index.json")
+    }
+
+    /// Advisory lock held by chat turns, and by maintenance that mustn't
+    /// run along with them
+    pub fn turn_lock_path(&self) -> PathBuf {
+        self.base_path.join("turn.lock")
+    }
+
+    /// Conversations imported from the history of other agent tools, one
+    /// file each
+    pub fn imports_path(&self) -> PathBuf {
+        self.base_path.join("imports")
+    }
+
+    /// Key file of the encrypted conversation store, present only once the
+    /// store is encrypted
+    pub fn store_key_path(&self) -> PathBuf {
+        self.base_path.join("store.key")
+    }
+
+    /// Secrets the shell tool can pass to commands, encrypted with the key
+    /// at [`Environment::secrets_key_path`]
+    pub fn secrets_path(&self) -> PathBuf {
+        self.base_path.join("secrets.json")
+    }
+
+    pub fn secrets_key_path(&self) -> PathBuf {
+        self.base_path.join("secrets.key")
+    }
+
+    /// API keys entered in forge, see [`crate::Credentials`]
+    pub fn credentials_path(&self) -> PathBuf {
+        self.base_path.join("credentials.env")
+    }
 }
@@ -1,8 +1,12 @@
+use std::path::PathBuf;
 use std::pin::Pin;
 
 use thiserror::Error;
 
-use crate::{AgentId, ConversationId};
+use crate::{
+    AgentId, ContextIssue, ConversationId, ErrorSeverity, ModelId, ToolName, TransformerIssue,
+    VariableIssue,
+};
 
 // NOTE: Deriving From for error is a really bad idea. This is because you end
 // up converting errors incorrectly without much context. For eg: You don't want
@@ -39,6 +43,98 @@ pub enum Error {
 
     #[error("Missing model for agent: {0}")]
     MissingModel(AgentId),
+
+    #[error("Tool '{0}' is not allowed to run by the agent's tool policy")]
+    ToolDenied(ToolName),
+
+    #[error("Tool call '{0}' was rejected by the user")]
+    ToolRejected(ToolName),
+
+    #[error("Invalid workflow variables: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    InvalidVariables(Vec<VariableIssue>),
+
+    #[error("Invalid request transformers: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    InvalidTransformers(Vec<TransformerIssue>),
+
+    #[error("Invalid context: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    InvalidContext(Vec<ContextIssue>),
+
+    #[error("Unsupported context version {0}, this version of forge reads contexts up to version {}. Upgrade forge to read it.", crate::CONTEXT_VERSION)]
+    UnsupportedContextVersion(String),
+
+    #[error("No response from model '{0}' after {1}s. The provider may be overloaded, retry the request or switch to a different model.")]
+    FirstTokenTimeout(ModelId, u64),
+
+    #[error("[{}] The provider rejected the API key ({0}). It may have expired or been revoked, set a new key and try again.", Error::PROVIDER_AUTH)]
+    ProviderAuth(String),
+
+    #[error("The request exceeds the context window of the model ({0}). Run /compact to summarize the conversation, or switch to a model with a larger context window.")]
+    ContextLengthExceeded(String),
+
+    #[error("The conversation doesn't fit the context window of model '{0}', even after compacting the request from ~{1} to ~{2} tokens ({3}). Run /compact to summarize the conversation, or switch to a model with a larger context window.")]
+    ContextOverflow(ModelId, usize, usize, String),
+
+    #[error("Invalid answer: {0}")]
+    InvalidAnswer(String),
+
+    #[error("'{}' is protected by the rule '{1}' in {}. Don't try to modify it another way, ask the user to lift the rule with '/unprotect {1}' instead.", .0.display(), .2.display())]
+    ProtectedPath(PathBuf, String, PathBuf),
+
+    #[error("Model '{0}' matches several models: {}. Use one of their ids instead.", join(.1))]
+    AmbiguousModel(ModelId, Vec<ModelId>),
+
+    #[error("Model '{0}' not found, the closest models are: {}", join(.1))]
+    UnknownModel(ModelId, Vec<ModelId>),
+}
+
+fn join(ids: &[ModelId]) -> String {
+    ids.iter()
+        .map(ModelId::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Error {
+    pub const PROVIDER_AUTH: &'static str = "FORGE-PROV-AUTH";
+
+    /// Stable code that identifies errors users can act on
+    pub fn code(&self) -> Option<&'static str> {
+        match self {
+            Error::ProviderAuth(_) => Some(Self::PROVIDER_AUTH),
+            _ => None,
+        }
+    }
+
+    /// How the error affects the turn it occurred in. Errors that only fail a
+    /// single tool call are reported back to the agent, which carries on.
+    pub fn severity(&self) -> ErrorSeverity {
+        match self {
+            Error::ToolDenied(_)
+            | Error::ToolRejected(_)
+            | Error::InvalidAnswer(_)
+            | Error::ProtectedPath(_, _, _) => ErrorSeverity::Recoverable,
+            Error::ToolCallMissingName
+            | Error::ToolCallArgument(_)
+            | Error::ToolCallParse(_)
+            | Error::ConversationId(_)
+            | Error::AgentUndefined(_)
+            | Error::UndefinedVariable(_)
+            | Error::HeadAgentUndefined
+            | Error::MaxTurnsReached(_, _)
+            | Error::ConversationNotFound(_)
+            | Error::MissingModel(_)
+            | Error::InvalidVariables(_)
+            | Error::InvalidTransformers(_)
+            | Error::InvalidContext(_)
+            | Error::UnsupportedContextVersion(_)
+            | Error::FirstTokenTimeout(_, _)
+            | Error::ProviderAuth(_)
+            | Error::ContextLengthExceeded(_)
+            | Error::ContextOverflow(_, _, _, _)
+            | Error::AmbiguousModel(_, _)
+            | Error::UnknownModel(_, _) => ErrorSeverity::Fatal,
+        }
+    }
 }
 
 pub type Result<A> = std::result::Result<A, Error>;
@@ -46,3 +142,21 @@ pub type BoxStream<A, E> =
     Pin<Box<dyn tokio_stream::Stream<Item = std::result::Result<A, E>> + Send>>;
 
 pub type ResultStream<A, E> = std::result::Result<BoxStream<A, E>, E>;
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_provider_auth_is_fatal_with_a_code() {
+        let fixture = Error::ProviderAuth("401 Unauthorized".to_string());
+
+        assert_eq!(fixture.code(), Some("FORGE-PROV-AUTH"));
+        assert_eq!(fixture.severity(), ErrorSeverity::Fatal);
+        assert!(fixture.to_string().starts_with("[FORGE-PROV-AUTH]"));
+        assert!(fixture.to_string().contains("set a new key"));
+        assert_eq!(Error::HeadAgentUndefined.code(), None);
+    }
+}
@@ -46,6 +46,7 @@ impl Event {
             description: "Dispatches an event with the provided name and value".to_string(),
             input_schema: schema_for!(Self),
             output_schema: None,
+            examples: Vec::new(),
         }
     }
 
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Controls how long the orchestrator waits for the first token of a
+/// response. Some providers queue requests for a long time before they start
+/// streaming, so the user is notified while waiting and the request is
+/// eventually abandoned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FirstTokenTimeout {
+    /// Time without a token after which the first waiting notice is sent.
+    /// The interval doubles after every notice.
+    pub notice_after: Duration,
+    /// Time without a token after which the request is aborted
+    pub abort_after: Duration,
+}
+
+impl Default for FirstTokenTimeout {
+    fn default() -> Self {
+        Self {
+            notice_after: Duration::from_secs(10),
+            abort_after: Duration::from_secs(120),
+        }
+    }
+}
+
+impl FirstTokenTimeout {
+    /// Returns the elapsed times at which waiting notices are due, ie.
+    /// `notice_after`, then doubling, up to `abort_after` (exclusive)
+    pub fn notices(&self) -> impl Iterator<Item = Duration> + '_ {
+        std::iter::successors(Some(self.notice_after), |previous| previous.checked_mul(2))
+            .take_while(|notice| !notice.is_zero() && *notice < self.abort_after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_notices_double_until_abort() {
+        let fixture = FirstTokenTimeout::default();
+        let actual = fixture.notices().map(|d| d.as_secs()).collect::<Vec<_>>();
+        let expected = vec![10, 20, 40, 80];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_zero_notice_disables_notices() {
+        let fixture = FirstTokenTimeout {
+            notice_after: Duration::ZERO,
+            abort_after: Duration::from_secs(5),
+        };
+        assert_eq!(fixture.notices().count(), 0);
+    }
+}
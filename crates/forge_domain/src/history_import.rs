@@ -0,0 +1,168 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Context;
+
+/// Agent tools whose history can be imported
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportFormat {
+    /// The `.aider.chat.history.md` file aider keeps in a repository
+    Aider,
+    /// The session files Claude Code keeps in `~/.claude/projects`
+    ClaudeCode,
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 2] = [ImportFormat::Aider, ImportFormat::ClaudeCode];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImportFormat::Aider => "aider",
+            ImportFormat::ClaudeCode => "claude-code",
+        }
+    }
+
+    /// Tag of the conversations imported from this format
+    pub fn tag(&self) -> String {
+        format!("imported:{}", self.name())
+    }
+}
+
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name() == name)
+            .ok_or_else(|| {
+                let known = Self::ALL.map(|format| format.name()).join(", ");
+                format!("'{name}' is not a history format, use one of {known}")
+            })
+    }
+}
+
+/// Reads the history files of another agent tool
+pub trait HistoryImporter: Send + Sync {
+    fn format(&self) -> ImportFormat;
+
+    /// Whether a file found in an imported directory holds history
+    fn accepts(&self, path: &Path) -> bool;
+
+    /// Reads the conversations of a history file. Entries that can't be read
+    /// are left out and reported rather than failing the whole file.
+    fn parse(&self, content: &str) -> ParsedHistory;
+}
+
+/// The conversations read from a history file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParsedHistory {
+    pub conversations: Vec<ImportedConversation>,
+    pub skipped: Vec<SkippedEntry>,
+}
+
+/// A conversation read from another agent's history, before it's stored
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportedConversation {
+    pub title: String,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub context: Context,
+    /// Parts of the history that have no equivalent in a context
+    pub notes: Vec<String>,
+}
+
+/// An entry of a history file that couldn't be read
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkippedEntry {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl SkippedEntry {
+    pub fn new(line: usize, reason: impl ToString) -> Self {
+        Self { line, reason: reason.to_string() }
+    }
+}
+
+impl fmt::Display for SkippedEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+/// Where an imported conversation came from
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ImportSource {
+    pub format: ImportFormat,
+    pub path: PathBuf,
+    pub title: String,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Content hash of the imported messages, the same conversation is
+    /// imported once
+    pub hash: String,
+    /// Parts of the history that have no equivalent in a context
+    #[serde(default)]
+    pub notes: Vec<String>,
+}
+
+/// What importing a history file did
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileImport {
+    pub path: PathBuf,
+    pub imported: usize,
+    /// Conversations that were imported before
+    pub duplicates: usize,
+    pub skipped: Vec<SkippedEntry>,
+    /// Why the file couldn't be imported at all
+    pub error: Option<String>,
+}
+
+/// What importing a history file, or a directory of them, did
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportReport {
+    pub files: Vec<FileImport>,
+}
+
+impl ImportReport {
+    pub fn imported(&self) -> usize {
+        self.files.iter().map(|file| file.imported).sum()
+    }
+
+    pub fn duplicates(&self) -> usize {
+        self.files.iter().map(|file| file.duplicates).sum()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.files.iter().map(|file| file.skipped.len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!("claude-code".parse(), Ok(ImportFormat::ClaudeCode));
+        assert_eq!(
+            "cursor".parse::<ImportFormat>(),
+            Err("'cursor' is not a history format, use one of aider, claude-code".to_string())
+        );
+        assert_eq!(ImportFormat::Aider.tag(), "imported:aider");
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// How long HTTP requests wait on the network. There's no deadline for a
+/// whole request, so that long streaming responses are never cut off, only
+/// for connecting and for the gaps between reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpTimeout {
+    /// Time to establish a connection, TLS handshake included
+    pub connect: Duration,
+    /// Time without receiving anything, for the response to start as well as
+    /// between the chunks of a stream
+    pub read: Duration,
+}
+
+impl Default for HttpTimeout {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(30),
+            read: Duration::from_secs(300),
+        }
+    }
+}
+
+impl HttpTimeout {
+    /// Reads FORGE_CONNECT_TIMEOUT_SECS and FORGE_READ_TIMEOUT_SECS through
+    /// `var`, falling back to the defaults for unset or invalid values
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let secs = |key: &str| {
+            var(key)
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+        };
+        let default = Self::default();
+
+        Self {
+            connect: secs("FORGE_CONNECT_TIMEOUT_SECS").unwrap_or(default.connect),
+            read: secs("FORGE_READ_TIMEOUT_SECS").unwrap_or(default.read),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn from_vars(vars: &[(&str, &str)]) -> HttpTimeout {
+        let vars = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        HttpTimeout::from_env(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_timeouts_from_env() {
+        let actual = from_vars(&[
+            ("FORGE_CONNECT_TIMEOUT_SECS", "5"),
+            ("FORGE_READ_TIMEOUT_SECS", "60"),
+        ]);
+
+        let expected = HttpTimeout {
+            connect: Duration::from_secs(5),
+            read: Duration::from_secs(60),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_invalid_timeouts_fall_back_to_the_defaults() {
+        let actual = from_vars(&[
+            ("FORGE_CONNECT_TIMEOUT_SECS", "soon"),
+            ("FORGE_READ_TIMEOUT_SECS", "0"),
+        ]);
+
+        assert_eq!(actual, HttpTimeout::default());
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ChunkConfig;
+
+/// A chunk of an indexed file, as stored in the document index
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentChunk {
+    /// Absolute path of the file, so that files of different indexed
+    /// directories never share chunks
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub content: String,
+    /// User provided tags, followed by the relative path
+    pub tags: Vec<String>,
+}
+
+/// What to index
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexRequest {
+    /// Directory that is walked, ignore files are respected
+    pub path: PathBuf,
+    /// Only files whose path relative to `path` matches are indexed
+    pub glob: Option<String>,
+    pub tags: Vec<String>,
+    pub chunk: ChunkConfig,
+}
+
+/// Content hashes of the files that were indexed, keyed by absolute path.
+/// Files whose hash didn't change are skipped when indexing again.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexState {
+    pub files: BTreeMap<String, String>,
+    /// Number of chunks stored for each file, so that the chunks of a file
+    /// can be removed before it is indexed again. Absent from the state of
+    /// older versions.
+    #[serde(default)]
+    pub chunks: BTreeMap<String, usize>,
+    /// Every directory indexed so far, so the index can be rebuilt from the
+    /// files on disk. Absent from the state of older versions.
+    #[serde(default)]
+    pub requests: Vec<IndexRequest>,
+}
+
+impl IndexState {
+    /// Records what was indexed, replacing an earlier request for the same
+    /// files
+    pub fn record_request(&mut self, request: &IndexRequest) {
+        self.requests
+            .retain(|known| known.path != request.path || known.glob != request.glob);
+        self.requests.push(request.clone());
+    }
+
+    pub fn is_unchanged(&self, path: &str, hash: &str) -> bool {
+        self.files.get(path).is_some_and(|known| known == hash)
+    }
+
+    /// Number of chunks stored for the file by the last run that indexed it
+    pub fn chunk_count(&self, path: &str) -> usize {
+        self.chunks.get(path).copied().unwrap_or_default()
+    }
+
+    /// Records a file once all of its chunks have been stored
+    pub fn record(&mut self, path: impl Into<String>, hash: impl Into<String>, chunks: usize) {
+        let path = path.into();
+        self.chunks.insert(path.clone(), chunks);
+        self.files.insert(path, hash.into());
+    }
+}
+
+/// Progress of an indexing run
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IndexProgress {
+    /// The files to index were collected
+    Started {
+        total: usize,
+    },
+    Indexed {
+        path: String,
+        chunks: usize,
+    },
+    Skipped {
+        path: String,
+    },
+    /// The run ended, either after all files or because it was cancelled
+    Finished(IndexSummary),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexSummary {
+    pub indexed: usize,
+    pub skipped: usize,
+    pub chunks: usize,
+    pub cancelled: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_index_state_skips_unchanged_files() {
+        let mut fixture = IndexState::default();
+        assert!(!fixture.is_unchanged("/repo/a.md", "1"));
+
+        fixture.record("/repo/a.md", "1", 2);
+        assert!(fixture.is_unchanged("/repo/a.md", "1"));
+        assert_eq!(fixture.chunk_count("/repo/a.md"), 2);
+        assert_eq!(fixture.chunk_count("/repo/b.md"), 0);
+        assert!(!fixture.is_unchanged("/repo/a.md", "2"));
+        assert!(!fixture.is_unchanged("/repo/b.md", "1"));
+    }
+
+    #[test]
+    fn test_index_state_replaces_requests_for_the_same_files() {
+        let request = |glob: &str, tag: &str| IndexRequest {
+            path: PathBuf::from("/repo"),
+            glob: Some(glob.to_string()),
+            tags: vec![tag.to_string()],
+            chunk: ChunkConfig::default(),
+        };
+        let mut fixture = IndexState::default();
+
+        fixture.record_request(&request("**/*.md", "docs"));
+        fixture.record_request(&request("**/*.rs", "code"));
+        fixture.record_request(&request("**/*.md", "notes"));
+
+        assert_eq!(
+            fixture.requests,
+            vec![request("**/*.rs", "code"), request("**/*.md", "notes")]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+
+/// A key/value row of an info section
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InfoRow {
+    pub key: String,
+    pub value: String,
+    /// Highlighted rows are emphasized and also shown in the banner
+    pub highlight: bool,
+}
+
+/// A titled group of rows shown by `/info`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InfoSection {
+    pub title: String,
+    /// Sections are shown in ascending order, ties are broken by title
+    pub order: u32,
+    pub rows: Vec<InfoRow>,
+}
+
+impl InfoSection {
+    /// Order of sections that don't set one, after the built-in sections
+    pub const DEFAULT_ORDER: u32 = 100;
+
+    pub fn new(title: impl ToString) -> Self {
+        Self {
+            title: title.to_string(),
+            order: Self::DEFAULT_ORDER,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn order(mut self, order: u32) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn row(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.rows.push(InfoRow {
+            key: key.to_string(),
+            value: value.to_string(),
+            highlight: false,
+        });
+        self
+    }
+
+    pub fn highlight(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.rows.push(InfoRow {
+            key: key.to_string(),
+            value: value.to_string(),
+            highlight: true,
+        });
+        self
+    }
+
+    /// Sorts sections into the order they are shown in
+    pub fn sort(sections: &mut [InfoSection]) {
+        sections.sort_by(|a, b| (a.order, &a.title).cmp(&(b.order, &b.title)));
+    }
+}
+
+/// Contributes a section to `/info`. Sections are computed each time they are
+/// shown, so they reflect the current state. They are computed on a blocking
+/// thread, providers may run commands such as git.
+pub trait InfoProvider: Send + Sync {
+    fn info(&self) -> InfoSection;
+}
+
+/// The providers whose sections are shown by `/info`
+#[derive(Clone, Default)]
+pub struct InfoRegistry {
+    providers: Vec<Arc<dyn InfoProvider>>,
+}
+
+impl InfoRegistry {
+    pub fn register(mut self, provider: impl InfoProvider + 'static) -> Self {
+        self.providers.push(Arc::new(provider));
+        self
+    }
+
+    /// Sections of all providers, in the order they are shown
+    pub fn sections(&self) -> Vec<InfoSection> {
+        let mut sections = self
+            .providers
+            .iter()
+            .map(|provider| provider.info())
+            .collect::<Vec<_>>();
+        InfoSection::sort(&mut sections);
+        sections
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    struct Fixed(InfoSection);
+
+    impl InfoProvider for Fixed {
+        fn info(&self) -> InfoSection {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_sections_are_ordered() {
+        let fixture = InfoRegistry::default()
+            .register(Fixed(InfoSection::new("Usage").order(60)))
+            .register(Fixed(InfoSection::new("Sandbox")))
+            .register(Fixed(InfoSection::new("Environment").order(10)))
+            .register(Fixed(InfoSection::new("Git").order(60)));
+
+        let actual = fixture
+            .sections()
+            .into_iter()
+            .map(|section| section.title)
+            .collect::<Vec<_>>();
+
+        let expected = vec!["Environment", "Git", "Usage", "Sandbox"];
+        assert_eq!(actual, expected);
+    }
+}
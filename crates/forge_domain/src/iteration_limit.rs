@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumString;
+
+use crate::{ToolCallFull, UserAnswer, UserInteraction};
+
+/// Requests an agent can make to the provider in one turn unless its
+/// `max_iterations` says otherwise
+pub const DEFAULT_MAX_ITERATIONS: u64 = 25;
+
+/// Number of tool calls summarized when a turn reaches its iteration limit
+pub const RECENT_TOOL_CALLS: usize = 5;
+
+/// What happens to a turn that reached its iteration limit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum IterationLimitAction {
+    /// Allows the turn as many iterations again
+    Continue,
+    /// Ends the turn, keeping what it added to the context
+    #[default]
+    Stop,
+    /// Ends the turn and restores the context it started from
+    Rollback,
+}
+
+impl IterationLimitAction {
+    const ALL: [IterationLimitAction; 3] = [
+        IterationLimitAction::Continue,
+        IterationLimitAction::Stop,
+        IterationLimitAction::Rollback,
+    ];
+
+    fn label(&self, max_iterations: u64) -> String {
+        match self {
+            IterationLimitAction::Continue => {
+                format!("Continue for another {max_iterations} iterations")
+            }
+            IterationLimitAction::Stop => "Stop and keep the context".to_string(),
+            IterationLimitAction::Rollback => "Stop and roll back the turn".to_string(),
+        }
+    }
+
+    /// The question asked to the user once the limit is reached
+    pub fn interaction(iterations: u64, max_iterations: u64) -> UserInteraction {
+        UserInteraction::Select {
+            prompt: format!("The agent made {iterations} requests in this turn, what next?"),
+            options: Self::ALL
+                .iter()
+                .map(|action| action.label(max_iterations))
+                .collect(),
+        }
+    }
+
+    /// The action the user picked, `None` when the answer isn't one of the
+    /// options
+    pub fn from_answer(answer: &UserAnswer) -> Option<Self> {
+        match answer {
+            UserAnswer::Selected(index) => Self::ALL.get(*index).copied(),
+            UserAnswer::Text(_) => None,
+        }
+    }
+}
+
+/// Emitted when a turn reached its iteration limit, before deciding whether it
+/// carries on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IterationLimit {
+    pub iterations: u64,
+    /// The last tool calls of the turn, oldest first
+    pub recent_calls: Vec<ToolCallFull>,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_answer_maps_to_the_option() {
+        let interaction = IterationLimitAction::interaction(25, 25);
+        let UserInteraction::Select { options, .. } = &interaction else {
+            panic!("expected a selection");
+        };
+
+        let actual = (0..options.len())
+            .map(|index| IterationLimitAction::from_answer(&UserAnswer::Selected(index)))
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            Some(IterationLimitAction::Continue),
+            Some(IterationLimitAction::Stop),
+            Some(IterationLimitAction::Rollback),
+        ];
+        assert_eq!(actual, expected);
+        assert_eq!(options[0], "Continue for another 25 iterations");
+    }
+
+    #[test]
+    fn test_invalid_answer_maps_to_nothing() {
+        let actual = [
+            IterationLimitAction::from_answer(&UserAnswer::Selected(3)),
+            IterationLimitAction::from_answer(&UserAnswer::Text("go on".to_string())),
+        ];
+
+        assert_eq!(actual, [None, None]);
+    }
+}
@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AgentId, Context, ContextMessage, ConversationId, ToolCallFull, ToolResult, REFUSAL_RETRY_NOTE,
+};
+
+/// Message appended to a recovered context so that the model knows the turn
+/// did not run to completion.
+pub const INTERRUPTED_TURN_NOTE: &str =
+    "The previous turn was interrupted before it could complete. Review the context above and continue from the last completed step.";
+
+/// A single write-ahead record of an in-flight agent turn.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// The context the turn started from
+    Begin { context: Context },
+    /// The user message that initiated the turn
+    UserMessage { content: String },
+    /// A file attached to the user message, see [`Context::add_file`]
+    File { path: String, content: String },
+    /// A completed step, ie. an assistant response along with the results of
+    /// the tools it called
+    Step {
+        content: String,
+        tool_calls: Vec<ToolCallFull>,
+        tool_results: Vec<ToolResult>,
+    },
+    /// A response taken for a refusal, recorded before the step it belongs
+    /// to. When retried the request was sent again with
+    /// [`REFUSAL_RETRY_NOTE`] instead of the response.
+    Refusal { content: String, retried: bool },
+    /// A request was compacted to fit the model's context. Only the request
+    /// sent was compacted, the context recovers unchanged.
+    Compacted { before: usize, after: usize },
+    /// The agent's transforms rewrote the context, eg. summarized it
+    Transformed { context: Context },
+    /// The turn reached its iteration limit and was rolled back to the
+    /// context it started from
+    RolledBack,
+}
+
+impl JournalEntry {
+    /// Applies the entry to a context the way the turn did. Rolling back
+    /// needs the context the turn started from, see [`Journal::context`].
+    pub fn apply(&self, context: Context) -> Context {
+        match self {
+            JournalEntry::Begin { context } | JournalEntry::Transformed { context } => {
+                context.clone()
+            }
+            JournalEntry::UserMessage { content } => context.add_user_message(content),
+            JournalEntry::File { path, content } => context.add_file(path, content),
+            JournalEntry::Step { content, tool_calls, tool_results } => {
+                let evidence = context.turn_evidence();
+                context
+                    .add_message(
+                        ContextMessage::assistant(content, Some(tool_calls.clone()))
+                            .with_evidence(evidence),
+                    )
+                    .add_tool_results(tool_results.clone())
+            }
+            JournalEntry::Refusal { retried: true, .. } => {
+                context.add_message(ContextMessage::user(REFUSAL_RETRY_NOTE))
+            }
+            JournalEntry::Refusal { retried: false, .. }
+            | JournalEntry::Compacted { .. }
+            | JournalEntry::RolledBack => context,
+        }
+    }
+}
+
+/// Recovery data left behind by a turn that never completed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Journal {
+    pub conversation_id: ConversationId,
+    pub agent: AgentId,
+    pub entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    /// Rebuilds the context the journal leads to. Returns `None` if the
+    /// journal never recorded a starting context.
+    pub fn context(&self) -> Option<Context> {
+        let mut entries = self.entries.iter();
+        let start = match entries.next() {
+            Some(JournalEntry::Begin { context }) => context.clone(),
+            _ => return None,
+        };
+        Some(entries.fold(start.clone(), |context, entry| match entry {
+            JournalEntry::RolledBack => start.clone(),
+            entry => entry.apply(context),
+        }))
+    }
+
+    /// Rebuilds the context up to the last completed step. Returns `None` if
+    /// the journal never recorded the starting context.
+    pub fn recover(&self) -> Option<Context> {
+        Some(
+            self.context()?
+                .add_message(ContextMessage::user(INTERRUPTED_TURN_NOTE)),
+        )
+    }
+
+    /// Returns the user message of the interrupted turn, if it was recorded
+    pub fn user_message(&self) -> Option<&str> {
+        self.entries.iter().find_map(|entry| match entry {
+            JournalEntry::UserMessage { content } => Some(content.as_str()),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{ToolCallId, ToolName};
+
+    fn tool_call() -> ToolCallFull {
+        ToolCallFull::new(ToolName::new("tool_forge_fs_read"))
+            .call_id(ToolCallId::new("call_1"))
+            .arguments(json!({"path": "/a.txt"}))
+    }
+
+    fn journal(entries: Vec<JournalEntry>) -> Journal {
+        Journal {
+            conversation_id: ConversationId::generate(),
+            agent: AgentId::new("developer"),
+            entries,
+        }
+    }
+
+    #[test]
+    fn test_recover_after_crash_between_steps() {
+        let initial = Context::default().add_message(ContextMessage::system("system"));
+        let fixture = journal(vec![
+            JournalEntry::Begin { context: initial.clone() },
+            JournalEntry::UserMessage { content: "read a.txt".to_string() },
+            JournalEntry::Step {
+                content: "reading".to_string(),
+                tool_calls: vec![tool_call()],
+                tool_results: vec![ToolResult::from(tool_call()).success("hello")],
+            },
+        ]);
+
+        let actual = fixture.recover().unwrap();
+
+        let expected = initial
+            .add_message(ContextMessage::user("read a.txt"))
+            .add_message(ContextMessage::assistant(
+                "reading",
+                Some(vec![tool_call()]),
+            ))
+            .add_tool_results(vec![ToolResult::from(tool_call()).success("hello")])
+            .add_message(ContextMessage::user(INTERRUPTED_TURN_NOTE));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_recover_before_first_step() {
+        let fixture = journal(vec![
+            JournalEntry::Begin { context: Context::default() },
+            JournalEntry::UserMessage { content: "hello".to_string() },
+        ]);
+
+        let actual = fixture.recover().unwrap();
+
+        let expected = Context::default()
+            .add_message(ContextMessage::user("hello"))
+            .add_message(ContextMessage::user(INTERRUPTED_TURN_NOTE));
+        assert_eq!(actual, expected);
+        assert_eq!(fixture.user_message(), Some("hello"));
+    }
+
+    #[test]
+    fn test_recover_replaces_modified_file() {
+        let initial = Context::default().add_file("a.txt", "old");
+        let fixture = journal(vec![
+            JournalEntry::Begin { context: initial },
+            JournalEntry::UserMessage { content: "read @a.txt again".to_string() },
+            JournalEntry::File { path: "a.txt".to_string(), content: "new".to_string() },
+        ]);
+
+        let actual = fixture.recover().unwrap();
+
+        let expected = Context::default()
+            .add_message(ContextMessage::user("read @a.txt again"))
+            .add_file("a.txt", "new")
+            .add_message(ContextMessage::user(INTERRUPTED_TURN_NOTE));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_recover_after_retried_refusal() {
+        let fixture = journal(vec![
+            JournalEntry::Begin { context: Context::default() },
+            JournalEntry::UserMessage { content: "harden the login".to_string() },
+            JournalEntry::Refusal { content: "I'm sorry, I can't.".to_string(), retried: true },
+        ]);
+
+        let actual = fixture.recover().unwrap();
+
+        let expected = Context::default()
+            .add_message(ContextMessage::user("harden the login"))
+            .add_message(ContextMessage::user(REFUSAL_RETRY_NOTE))
+            .add_message(ContextMessage::user(INTERRUPTED_TURN_NOTE));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_recover_without_begin() {
+        let fixture = journal(vec![JournalEntry::UserMessage {
+            content: "hello".to_string(),
+        }]);
+        assert_eq!(fixture.recover(), None);
+    }
+
+    #[test]
+    fn test_recover_transformed_context() {
+        let summary = Context::default().add_message(ContextMessage::user("summary"));
+        let fixture = journal(vec![
+            JournalEntry::Begin { context: Context::default() },
+            JournalEntry::UserMessage { content: "read a.txt".to_string() },
+            JournalEntry::Transformed { context: summary.clone() },
+            JournalEntry::Step {
+                content: "reading".to_string(),
+                tool_calls: vec![tool_call()],
+                tool_results: vec![ToolResult::from(tool_call()).success("hello")],
+            },
+        ]);
+
+        let actual = fixture.recover().unwrap();
+
+        let expected = summary
+            .add_message(ContextMessage::assistant(
+                "reading",
+                Some(vec![tool_call()]),
+            ))
+            .add_tool_results(vec![ToolResult::from(tool_call()).success("hello")])
+            .add_message(ContextMessage::user(INTERRUPTED_TURN_NOTE));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_recover_rolled_back_turn() {
+        let initial = Context::default().add_message(ContextMessage::user("earlier"));
+        let fixture = journal(vec![
+            JournalEntry::Begin { context: initial.clone() },
+            JournalEntry::UserMessage { content: "read a.txt".to_string() },
+            JournalEntry::Step {
+                content: "reading".to_string(),
+                tool_calls: vec![tool_call()],
+                tool_results: vec![ToolResult::from(tool_call()).success("hello")],
+            },
+            JournalEntry::RolledBack,
+        ]);
+
+        let actual = fixture.context();
+
+        assert_eq!(actual, Some(initial));
+    }
+}
@@ -1,59 +1,113 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use serde_json::Value;
 mod agent;
 mod chat_request;
 mod chat_response;
+mod chunk;
+mod compact;
 mod context;
 mod conversation;
+mod credentials;
+mod enrichment;
 mod env;
 mod error;
 mod event;
 mod file;
+mod first_token;
+mod history_import;
+mod http_timeout;
+mod index;
+mod info;
+mod iteration_limit;
+mod journal;
+mod line_ending;
 mod merge;
 mod message;
 mod model;
 mod orch;
 mod point;
 mod provider;
+mod provider_tool;
+mod proxy;
+mod redact;
+mod refusal;
+mod result_limit;
+mod scratch;
+mod secret;
 mod suggestion;
 mod summarize;
 mod template;
+mod timing;
+mod tls;
 mod tool;
 mod tool_call;
 mod tool_call_parser;
 mod tool_choice;
 mod tool_definition;
 mod tool_name;
+mod tool_policy;
 mod tool_result;
 mod tool_usage;
+mod transformer;
+mod user_interaction;
+mod variable;
+mod verify;
 mod workflow;
 
 pub use agent::*;
 pub use chat_request::*;
 pub use chat_response::*;
+pub use chunk::*;
+pub use compact::*;
 pub use context::*;
 pub use conversation::*;
+pub use credentials::*;
+pub use enrichment::*;
 pub use env::*;
 pub use error::*;
 pub use event::*;
 pub use file::*;
+pub use first_token::*;
+pub use history_import::*;
+pub use http_timeout::*;
+pub use index::*;
+pub use info::*;
+pub use iteration_limit::*;
+pub use journal::*;
+pub use line_ending::*;
 pub use message::*;
 pub use model::*;
 pub use orch::*;
 pub use point::*;
 pub use provider::*;
+pub use provider_tool::*;
+pub use proxy::*;
+pub use redact::*;
+pub use refusal::*;
+pub use result_limit::*;
+pub use scratch::*;
+pub use secret::*;
 pub use suggestion::*;
 pub use summarize::*;
 pub use template::*;
+pub use timing::*;
+pub use tls::*;
 pub use tool::*;
 pub use tool_call::*;
 pub use tool_call_parser::*;
 pub use tool_choice::*;
 pub use tool_definition::*;
 pub use tool_name::*;
+pub use tool_policy::*;
 pub use tool_result::*;
 pub use tool_usage::*;
+pub use transformer::*;
+pub use user_interaction::*;
+pub use variable::*;
+pub use verify::*;
 pub use workflow::*;
 
 #[async_trait::async_trait]
@@ -64,12 +118,26 @@ pub trait ProviderService: Send + Sync + 'static {
         context: Context,
     ) -> ResultStream<ChatCompletionMessage, anyhow::Error>;
     async fn models(&self) -> anyhow::Result<Vec<Model>>;
+
+    /// Starts using a different API key once the provider accepted it
+    async fn set_key(&self, _key: String) -> anyhow::Result<()> {
+        anyhow::bail!("Changing the API key is not supported by this provider")
+    }
+
+    /// The transformers applied to requests and what they did to the last
+    /// one, `None` for providers that send requests as they are
+    fn transformers(&self) -> Option<TransformerReport> {
+        None
+    }
 }
 
 #[async_trait::async_trait]
 pub trait ToolService: Send + Sync {
     // TODO: should take `call` by reference
     async fn call(&self, call: ToolCallFull) -> ToolResult;
+    /// Makes the paths under the working directory in a successful result
+    /// relative
+    fn relative_paths(&self, result: ToolResult) -> ToolResult;
     fn list(&self) -> Vec<ToolDefinition>;
     fn usage_prompt(&self) -> String;
 }
@@ -98,6 +166,24 @@ pub trait ConversationService: Send + Sync {
         value: Value,
     ) -> anyhow::Result<()>;
     async fn delete_variable(&self, id: &ConversationId, key: &str) -> anyhow::Result<bool>;
+    async fn set_suggestions(
+        &self,
+        id: &ConversationId,
+        suggestions: ConversationSuggestions,
+    ) -> anyhow::Result<()>;
+    /// Returns false if the conversation already has the tag
+    async fn add_tag(&self, id: &ConversationId, tag: &str) -> anyhow::Result<bool>;
+    /// Returns false if the conversation doesn't have the tag
+    async fn remove_tag(&self, id: &ConversationId, tag: &str) -> anyhow::Result<bool>;
+    /// Conversations with the tag
+    async fn list_by_tag(&self, tag: &str) -> anyhow::Result<Vec<Conversation>>;
+    /// Returns false if the conversation doesn't exist
+    async fn delete(&self, id: &ConversationId) -> anyhow::Result<bool>;
+    async fn set_pinned(&self, id: &ConversationId, pinned: bool) -> anyhow::Result<()>;
+    /// Records the timing breakdown of a completed turn
+    async fn add_turn_timing(&self, id: &ConversationId, timing: TurnTiming) -> anyhow::Result<()>;
+    /// All conversations, pinned ones first and then the most recently updated
+    async fn list(&self) -> anyhow::Result<Vec<Conversation>>;
 }
 
 #[async_trait::async_trait]
@@ -106,6 +192,8 @@ pub trait TemplateService: Send + Sync {
         &self,
         agent: &Agent,
         prompt: &Template<SystemContext>,
+        scratch_dir: &Path,
+        variables: &HashMap<String, Value>,
     ) -> anyhow::Result<String>;
 
     async fn render_event(
@@ -119,7 +207,114 @@ pub trait TemplateService: Send + Sync {
 
 #[async_trait::async_trait]
 pub trait AttachmentService {
-    async fn attachments(&self, url: &str) -> anyhow::Result<Vec<Attachment>>;
+    /// Reads the files mentioned in the message, `progress` is called while
+    /// large files are read
+    async fn attachments(
+        &self,
+        url: &str,
+        progress: &(dyn Fn(ReadProgress) + Send + Sync),
+    ) -> anyhow::Result<Vec<Attachment>>;
+}
+
+/// Tracks tool calls that are waiting for the user's approval
+#[async_trait::async_trait]
+pub trait ApprovalService: Send + Sync {
+    /// Registers a tool call that needs approval. The returned receiver
+    /// resolves with the user's decision.
+    async fn register(&self, call_id: &ToolCallId) -> tokio::sync::oneshot::Receiver<bool>;
+    /// Resolves a pending tool call with the user's decision
+    async fn respond(&self, call_id: &ToolCallId, approved: bool) -> anyhow::Result<()>;
+    /// Stops waiting for the decision on a tool call that timed out
+    async fn cancel(&self, call_id: &ToolCallId);
+}
+
+/// Tracks questions the agent asked that are waiting for the user's answer
+#[async_trait::async_trait]
+pub trait InteractionService: Send + Sync {
+    /// Registers a question asked by a tool call. The returned receiver
+    /// resolves with the user's answer.
+    async fn register(
+        &self,
+        call_id: &ToolCallId,
+        interaction: &UserInteraction,
+    ) -> tokio::sync::oneshot::Receiver<UserAnswer>;
+    /// Resolves a pending question with the user's answer. An answer that
+    /// doesn't fit the question is rejected with [`Error::InvalidAnswer`] and
+    /// the question keeps waiting.
+    async fn respond(&self, call_id: &ToolCallId, answer: UserAnswer) -> anyhow::Result<()>;
+    /// Stops waiting for the answer to a question that timed out
+    async fn cancel(&self, call_id: &ToolCallId);
+}
+
+/// Per-conversation directories where agents keep intermediate artifacts
+#[async_trait::async_trait]
+pub trait ScratchService: Send + Sync {
+    /// Path of the conversation's scratch directory, which is only created on
+    /// first use
+    fn path(&self, id: &ConversationId) -> PathBuf;
+    /// Creates the conversation's scratch directory unless it exists
+    async fn create(&self, id: &ConversationId) -> anyhow::Result<PathBuf>;
+    /// Writes a file at a path relative to the conversation's scratch
+    /// directory, creating the directories it needs
+    async fn write(
+        &self,
+        id: &ConversationId,
+        relative: &Path,
+        content: &str,
+    ) -> anyhow::Result<PathBuf>;
+    /// Removes the conversation's scratch directory. Returns false if it was
+    /// never created.
+    async fn remove(&self, id: &ConversationId) -> anyhow::Result<bool>;
+    /// Removes the scratch directories of conversations that weren't touched
+    /// within `max_age`, returns how many were removed
+    async fn purge(&self, max_age: Duration) -> anyhow::Result<usize>;
+}
+
+/// Gitignore-style rules for paths that tools are not allowed to modify
+pub trait ProtectionService: Send + Sync {
+    /// Fails with [`Error::ProtectedPath`] if the path, once canonicalized, or
+    /// anything inside it is protected by a rule that is still in effect
+    fn check(&self, path: &Path) -> anyhow::Result<()>;
+    /// Lifts the rule with the given pattern for the rest of the session
+    fn unprotect(&self, pattern: &str) -> anyhow::Result<()>;
+}
+
+/// Write-ahead journal of in-flight turns, used to recover from crashes.
+/// Along with the conversations imported from other tools it makes up the
+/// conversation store, which can be encrypted.
+#[async_trait::async_trait]
+pub trait JournalService: Send + Sync {
+    /// Appends an entry to the journal of the agent's turns. A turn that
+    /// begins with the context the journal already leads to may be recorded
+    /// without it.
+    async fn append(
+        &self,
+        id: &ConversationId,
+        agent: &AgentId,
+        entry: JournalEntry,
+    ) -> anyhow::Result<()>;
+    /// Removes the journal of the agent, eg. once it was recovered
+    async fn clear(&self, id: &ConversationId, agent: &AgentId) -> anyhow::Result<()>;
+    /// Lists the journals left behind by turns that never completed
+    async fn pending(&self) -> anyhow::Result<Vec<Journal>>;
+    /// Whether the journals are encrypted, they can't be read or written
+    /// until unlocked then
+    fn is_encrypted(&self) -> bool;
+    /// Unlocks encrypted journals, fails when the passphrase is wrong
+    async fn unlock(&self, passphrase: &str) -> anyhow::Result<()>;
+    /// Encrypts the existing journals and imported conversations, and every
+    /// entry appended from now on, with a key derived from the passphrase.
+    /// Returns how many files were converted.
+    async fn encrypt(&self, passphrase: &str) -> anyhow::Result<usize>;
+    /// Converts the journals and imported conversations back to plain text,
+    /// returns how many files were converted
+    async fn decrypt(&self, passphrase: &str) -> anyhow::Result<usize>;
+    /// Stores a conversation imported from another tool under the content
+    /// hash of its messages. Returns false when one with the same hash was
+    /// stored before.
+    async fn save_import(&self, hash: &str, conversation: &Conversation) -> anyhow::Result<bool>;
+    /// The conversations imported from other tools
+    async fn imports(&self) -> anyhow::Result<Vec<Conversation>>;
 }
 /// Core app trait providing access to services and repositories.
 /// This trait follows clean architecture principles for dependency management
@@ -130,10 +325,20 @@ pub trait App: Send + Sync + 'static {
     type ConversationService: ConversationService;
     type TemplateService: TemplateService;
     type AttachmentService: AttachmentService;
+    type JournalService: JournalService;
+    type ApprovalService: ApprovalService;
+    type InteractionService: InteractionService;
+    type ScratchService: ScratchService;
+    type ProtectionService: ProtectionService;
 
     fn tool_service(&self) -> &Self::ToolService;
     fn provider_service(&self) -> &Self::ProviderService;
     fn conversation_service(&self) -> &Self::ConversationService;
     fn template_service(&self) -> &Self::TemplateService;
     fn attachment_service(&self) -> &Self::AttachmentService;
+    fn journal_service(&self) -> &Self::JournalService;
+    fn approval_service(&self) -> &Self::ApprovalService;
+    fn interaction_service(&self) -> &Self::InteractionService;
+    fn scratch_service(&self) -> &Self::ScratchService;
+    fn protection_service(&self) -> &Self::ProtectionService;
 }
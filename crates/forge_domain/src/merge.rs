@@ -43,6 +43,16 @@ pub mod bool {
     pub use merge::bool::*;
 }
 
+pub mod hashmap {
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    /// Inserts all entries of `other` into `base`, overwriting existing keys
+    pub fn extend<K: Eq + Hash, V>(base: &mut HashMap<K, V>, other: HashMap<K, V>) {
+        base.extend(other);
+    }
+}
+
 pub trait Key {
     type Id: Eq;
     fn key(&self) -> &Self::Id;
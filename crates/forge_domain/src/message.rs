@@ -3,7 +3,7 @@ use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 use strum_macros::EnumString;
 
-use super::ToolCall;
+use super::{ProviderToolEvent, ToolCall};
 
 #[derive(Default, Clone, Debug, Serialize, PartialEq, Eq)]
 pub struct Usage {
@@ -12,6 +12,15 @@ pub struct Usage {
     pub total_tokens: u64,
 }
 
+/// Adds up the usage of several responses, eg. every response of a turn
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
 /// Represents a message that was received from the LLM provider
 /// NOTE: Tool call messages are part of the larger Response object and not part
 /// of the message.
@@ -22,7 +31,13 @@ pub struct ChatCompletionMessage {
     // TODO: rename to tool_calls (plural)
     pub tool_call: Vec<ToolCall>,
     pub finish_reason: Option<FinishReason>,
+    /// Why the model declined the request, for providers that report it
+    /// apart from the content
+    pub refusal: Option<String>,
     pub usage: Option<Usage>,
+    /// Calls and results of the tools the provider ran itself. They are part
+    /// of the reply and are never dispatched to forge's tools.
+    pub provider_tools: Vec<ProviderToolEvent>,
 }
 
 /// Represents partial or full content of a message
@@ -82,6 +97,9 @@ pub enum FinishReason {
     /// The model stopped generating output because it made a tool call.
     #[strum(serialize = "tool_calls")]
     ToolCalls,
+    /// The model declined the request.
+    #[strum(serialize = "refusal")]
+    Refusal,
     /// The model stopped generating output normally.
     #[strum(serialize = "stop", serialize = "end_turn")]
     Stop,
@@ -97,6 +115,11 @@ impl ChatCompletionMessage {
         self
     }
 
+    pub fn add_provider_tool(mut self, event: ProviderToolEvent) -> Self {
+        self.provider_tools.push(event);
+        self
+    }
+
     pub fn extend_calls(mut self, calls: Vec<impl Into<ToolCall>>) -> Self {
         self.tool_call.extend(calls.into_iter().map(Into::into));
         self
@@ -141,6 +164,10 @@ mod tests {
             FinishReason::ToolCalls
         );
         assert_eq!(FinishReason::from_str("stop").unwrap(), FinishReason::Stop);
+        assert_eq!(
+            FinishReason::from_str("refusal").unwrap(),
+            FinishReason::Refusal
+        );
         assert_eq!(
             FinishReason::from_str("end_turn").unwrap(),
             FinishReason::Stop
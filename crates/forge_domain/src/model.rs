@@ -2,15 +2,33 @@ use derive_more::derive::Display;
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 
+use crate::{Error, Result, Usage};
+
 #[derive(Clone, Debug, Deserialize, Serialize, Setters)]
 pub struct Model {
     pub id: ModelId,
     pub name: String,
     pub description: Option<String>,
     pub context_length: Option<u64>,
+    /// Not every provider lists the prices of its models
+    pub pricing: Option<ModelPricing>,
     // TODO: add provider information to the model
 }
 
+/// Price of a model in US dollars per token
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ModelPricing {
+    pub prompt: f64,
+    pub completion: f64,
+}
+
+impl ModelPricing {
+    /// Dollar cost of the tokens in `usage`
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        usage.prompt_tokens as f64 * self.prompt + usage.completion_tokens as f64 * self.completion
+    }
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Parameters {
     pub tool_supported: bool,
@@ -37,3 +55,323 @@ impl ModelId {
         &self.0
     }
 }
+
+/// Loose names users type for popular models, mapped to the name the models
+/// are listed under
+const MODEL_ALIASES: &[(&str, &str)] = &[
+    ("sonnet", "claude-3.7-sonnet"),
+    ("claude sonnet", "claude-3.7-sonnet"),
+    ("sonnet-3.7", "claude-3.7-sonnet"),
+    ("sonnet-3.5", "claude-3.5-sonnet"),
+    ("opus", "claude-3-opus"),
+    ("haiku", "claude-3.5-haiku"),
+    ("4o", "gpt-4o"),
+    ("4o-mini", "gpt-4o-mini"),
+];
+
+/// Number of candidates listed when a model isn't found
+const CLOSEST_MODELS: usize = 3;
+
+/// How a model name supplied by the user was matched against the available
+/// models
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModelResolution {
+    /// The name is the id of an available model
+    Exact(ModelId),
+    /// The name loosely matched a single available model
+    Resolved(ModelId),
+    /// The name looks like a full id but isn't available, possibly because
+    /// the list of models is out of date. It is used as is.
+    Unlisted(ModelId),
+}
+
+impl ModelResolution {
+    pub fn id(&self) -> &ModelId {
+        match self {
+            ModelResolution::Exact(id)
+            | ModelResolution::Resolved(id)
+            | ModelResolution::Unlisted(id) => id,
+        }
+    }
+
+    /// Tells the user how the requested name was resolved, unless it was
+    /// used as it is
+    pub fn notice(&self, requested: &ModelId) -> Option<String> {
+        match self {
+            ModelResolution::Exact(_) => None,
+            ModelResolution::Resolved(id) => Some(format!("'{requested}' resolved to '{id}'")),
+            ModelResolution::Unlisted(id) => Some(format!(
+                "'{id}' is not in the list of available models, using it as is"
+            )),
+        }
+    }
+}
+
+impl ModelId {
+    /// Matches a model name typed by the user against the available models.
+    /// Ids are used as they are, whether the model is available or the list
+    /// of models is out of date: names with a provider prefix, and names
+    /// written like the ids of a provider whose ids have none. Other names are
+    /// looked up in a table of aliases and matched ignoring case, separators
+    /// and the provider prefix, eg. "GPT 4o" matches "openai/gpt-4o". Names
+    /// that don't match a single model fail with the closest candidates.
+    pub fn resolve(&self, models: &[Model]) -> Result<ModelResolution> {
+        if models.iter().any(|model| model.id == *self) {
+            return Ok(ModelResolution::Exact(self.clone()));
+        }
+        if self.as_str().contains('/') {
+            return Ok(ModelResolution::Unlisted(self.clone()));
+        }
+
+        let query = match_key(self.as_str());
+        let query = MODEL_ALIASES
+            .iter()
+            .find(|(alias, _)| match_key(alias) == query)
+            .map(|(_, name)| match_key(name))
+            .unwrap_or(query);
+
+        let exact = matching(models, |key| key == query);
+        let candidates = if exact.is_empty() {
+            matching(models, |key| key.starts_with(&query))
+        } else {
+            exact
+        };
+
+        let unprefixed_ids = !models.iter().any(|model| model.id.as_str().contains('/'));
+        match candidates.as_slice() {
+            [id] => Ok(ModelResolution::Resolved(id.clone())),
+            [] if unprefixed_ids && is_id_like(self.as_str()) => {
+                Ok(ModelResolution::Unlisted(self.clone()))
+            }
+            [] => Err(Error::UnknownModel(self.clone(), closest(models, &query))),
+            _ => Err(Error::AmbiguousModel(self.clone(), candidates)),
+        }
+    }
+}
+
+/// Whether the name is written like a model id rather than typed loosely,
+/// eg. "claude-3-7-sonnet-20250219" but not "Claude Sonnet"
+fn is_id_like(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-.:_".contains(c))
+}
+
+/// Form of a model name that is compared when matching loosely
+fn match_key(name: &str) -> String {
+    name.trim().to_lowercase().replace([' ', '_', '.'], "-")
+}
+
+/// The name of the model without its provider prefix, eg. "gpt-4o" for
+/// "openai/gpt-4o"
+fn unprefixed(id: &ModelId) -> &str {
+    id.as_str()
+        .rsplit_once('/')
+        .map_or(id.as_str(), |(_, name)| name)
+}
+
+fn matching(models: &[Model], matches: impl Fn(&str) -> bool) -> Vec<ModelId> {
+    models
+        .iter()
+        .filter(|model| {
+            matches(&match_key(model.id.as_str())) || matches(&match_key(unprefixed(&model.id)))
+        })
+        .map(|model| model.id.clone())
+        .collect()
+}
+
+/// The models whose names are the fewest edits away from the query
+fn closest(models: &[Model], query: &str) -> Vec<ModelId> {
+    let mut ranked = models
+        .iter()
+        .map(|model| {
+            (
+                edit_distance(query, &match_key(unprefixed(&model.id))),
+                &model.id,
+            )
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by(|(a, a_id), (b, b_id)| a.cmp(b).then_with(|| a_id.as_str().cmp(b_id.as_str())));
+    ranked
+        .into_iter()
+        .take(CLOSEST_MODELS)
+        .map(|(_, id)| id.clone())
+        .collect()
+}
+
+/// Levenshtein distance between two strings
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn models(ids: &[&str]) -> Vec<Model> {
+        ids.iter()
+            .map(|id| Model {
+                id: ModelId::new(*id),
+                name: id.to_string(),
+                description: None,
+                context_length: None,
+                pricing: None,
+            })
+            .collect()
+    }
+
+    fn open_router() -> Vec<Model> {
+        models(&[
+            "anthropic/claude-3.5-sonnet",
+            "anthropic/claude-3.7-sonnet",
+            "anthropic/claude-3.7-sonnet:thinking",
+            "openai/gpt-4o",
+            "openai/gpt-4o-mini",
+            "openai/o3-mini",
+        ])
+    }
+
+    fn resolve(name: &str, models: &[Model]) -> Result<ModelResolution> {
+        ModelId::new(name).resolve(models)
+    }
+
+    #[test]
+    fn test_exact_id_is_untouched() {
+        let actual = resolve("openai/gpt-4o", &open_router()).unwrap();
+
+        let expected = ModelResolution::Exact(ModelId::new("openai/gpt-4o"));
+        assert_eq!(actual, expected);
+        assert_eq!(actual.notice(&ModelId::new("openai/gpt-4o")), None);
+    }
+
+    #[test]
+    fn test_provider_prefix_and_case_are_optional() {
+        let actual = ["gpt-4o", "GPT 4o", "Claude-3.5-Sonnet"]
+            .map(|name| resolve(name, &open_router()).unwrap());
+
+        let expected = [
+            ModelResolution::Resolved(ModelId::new("openai/gpt-4o")),
+            ModelResolution::Resolved(ModelId::new("openai/gpt-4o")),
+            ModelResolution::Resolved(ModelId::new("anthropic/claude-3.5-sonnet")),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_aliases() {
+        let actual = ["sonnet", "claude sonnet", "sonnet-3.5", "4o-mini"]
+            .map(|name| resolve(name, &open_router()).unwrap().id().clone());
+
+        let expected = [
+            ModelId::new("anthropic/claude-3.7-sonnet"),
+            ModelId::new("anthropic/claude-3.7-sonnet"),
+            ModelId::new("anthropic/claude-3.5-sonnet"),
+            ModelId::new("openai/gpt-4o-mini"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_alias_matches_dated_ids() {
+        let fixture = models(&["claude-3-7-sonnet-20250219", "claude-3-5-haiku-20241022"]);
+
+        let actual = resolve("sonnet", &fixture).unwrap();
+
+        let expected = ModelResolution::Resolved(ModelId::new("claude-3-7-sonnet-20250219"));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ambiguous_name_lists_candidates() {
+        let actual = resolve("claude-3", &open_router()).unwrap_err().to_string();
+
+        let expected = "Model 'claude-3' matches several models: anthropic/claude-3.5-sonnet, anthropic/claude-3.7-sonnet, anthropic/claude-3.7-sonnet:thinking. Use one of their ids instead.";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_unknown_name_lists_closest_models() {
+        let actual = resolve("o3-mimi", &open_router()).unwrap_err();
+
+        let expected = vec![
+            ModelId::new("openai/o3-mini"),
+            ModelId::new("openai/gpt-4o"),
+            ModelId::new("openai/gpt-4o-mini"),
+        ];
+        assert!(matches!(actual, Error::UnknownModel(_, candidates) if candidates == expected));
+    }
+
+    #[test]
+    fn test_unlisted_full_id_passes_through_with_a_warning() {
+        let actual = resolve("openai/gpt-5", &open_router()).unwrap();
+
+        let expected = ModelResolution::Unlisted(ModelId::new("openai/gpt-5"));
+        assert_eq!(actual, expected);
+        assert_eq!(
+            actual.notice(&ModelId::new("openai/gpt-5")).unwrap(),
+            "'openai/gpt-5' is not in the list of available models, using it as is"
+        );
+    }
+
+    #[test]
+    fn test_prefixed_id_is_not_matched_loosely() {
+        // "openai/o3" is a prefix of a listed id, the list is merely stale
+        let actual = resolve("openai/o3", &open_router()).unwrap();
+
+        let expected = ModelResolution::Unlisted(ModelId::new("openai/o3"));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_unlisted_id_of_unprefixed_provider_passes_through() {
+        let fixture = models(&["claude-3-7-sonnet-20250219", "claude-3-5-haiku-20241022"]);
+
+        let actual = ["claude-opus-4-20250514", "Claude Opus"]
+            .map(|name| resolve(name, &fixture).map_err(|_| ()));
+
+        let expected = [
+            Ok(ModelResolution::Unlisted(ModelId::new(
+                "claude-opus-4-20250514",
+            ))),
+            Err(()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_cost_from_usage() {
+        let fixture = ModelPricing { prompt: 0.000003, completion: 0.000015 };
+        let usage = Usage {
+            prompt_tokens: 12_000,
+            completion_tokens: 800,
+            total_tokens: 12_800,
+        };
+
+        let actual = fixture.cost(&usage);
+
+        let expected = 0.048;
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_cost_of_no_usage_is_zero() {
+        let fixture = ModelPricing { prompt: 0.000003, completion: 0.000015 };
+
+        let actual = fixture.cost(&Usage::default());
+
+        assert_eq!(actual, 0.0);
+    }
+}
@@ -11,6 +11,12 @@ impl PointId {
         Self(Uuid::new_v4())
     }
 
+    /// Creates an id from a digest, so that storing a point with the same
+    /// digest again replaces it
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(Uuid::from_bytes(bytes))
+    }
+
     pub fn into_uuid(self) -> Uuid {
         self.0
     }
@@ -50,6 +50,26 @@ impl Provider {
             Provider::Anthropic { key } => Some(key),
         }
     }
+
+    /// The same provider authenticating with a different key
+    pub fn with_key(&self, key: impl Into<String>) -> Provider {
+        match self {
+            Provider::OpenAI { url, .. } => {
+                Provider::OpenAI { url: url.clone(), key: Some(key.into()) }
+            }
+            Provider::Anthropic { .. } => Provider::Anthropic { key: key.into() },
+        }
+    }
+
+    /// Name of the environment variable the provider's key is read from
+    pub fn key_var(&self) -> &'static str {
+        match self {
+            Provider::Anthropic { .. } => "ANTHROPIC_API_KEY",
+            provider if provider.is_antinomy() => "FORGE_KEY",
+            provider if provider.is_open_router() => "OPENROUTER_API_KEY",
+            Provider::OpenAI { .. } => "OPENAI_API_KEY",
+        }
+    }
 }
 
 impl Provider {
@@ -86,4 +106,14 @@ impl Provider {
             Provider::Anthropic { .. } => false,
         }
     }
+
+    /// Whether tool results with JSON content can be sent as JSON. The chat
+    /// completions API, followed by every OpenAI compatible provider, and the
+    /// Anthropic API take tool results as text.
+    pub fn accepts_json_tool_results(&self) -> bool {
+        match self {
+            Provider::OpenAI { .. } => false,
+            Provider::Anthropic { .. } => false,
+        }
+    }
 }
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tracing::debug;
+
+use crate::{Context, ContextMessage, ToolCallFull, ToolCallId, ToolContent, ToolResult};
+
+/// Result given to a call of a provider tool that the provider never sent the
+/// result of
+pub const MISSING_PROVIDER_RESULT: &str = "The provider didn't send the result of this call";
+
+/// Part of a reply about a tool the provider ran itself, eg. web search or a
+/// code interpreter. Its call and result are streamed apart, forge never runs
+/// the tool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProviderToolEvent {
+    Call(ToolCallFull),
+    Result {
+        call_id: ToolCallId,
+        content: ToolContent,
+        is_error: bool,
+    },
+}
+
+/// A call of a tool the provider ran itself, along with its result. It's kept
+/// in the context like the calls of forge's tools, so later requests carry
+/// what the tool found.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderToolCall {
+    pub call: ToolCallFull,
+    pub result: ToolResult,
+}
+
+/// Pairs the provider tool events of a reply with one another
+#[derive(Debug, Default)]
+pub struct ProviderTools {
+    /// Calls waiting for their result
+    pending: Vec<ToolCallFull>,
+}
+
+impl ProviderTools {
+    /// Takes an event of the reply, returns the call it completes if it's a
+    /// result. Results of calls that weren't seen are dropped.
+    pub fn push(&mut self, event: ProviderToolEvent) -> Option<ProviderToolCall> {
+        match event {
+            ProviderToolEvent::Call(call) => {
+                self.pending.push(call);
+                None
+            }
+            ProviderToolEvent::Result { call_id, content, is_error } => {
+                let Some(position) = self
+                    .pending
+                    .iter()
+                    .position(|call| call.call_id.as_ref() == Some(&call_id))
+                else {
+                    debug!(call_id = %call_id.as_str(), "Dropping the result of an unknown provider tool call");
+                    return None;
+                };
+                let call = self.pending.remove(position);
+                let mut result = ToolResult::from(call.clone()).success(content);
+                result.is_error = is_error;
+                result.provider = true;
+                Some(ProviderToolCall { call, result })
+            }
+        }
+    }
+
+    /// Ends the reply, the calls the provider sent no result for are given
+    /// a failed one so the context keeps a result for every call
+    pub fn finish(self) -> Vec<ProviderToolCall> {
+        self.pending
+            .into_iter()
+            .map(|call| {
+                let mut result = ToolResult::from(call.clone()).success(MISSING_PROVIDER_RESULT);
+                result.is_error = true;
+                result.provider = true;
+                ProviderToolCall { call, result }
+            })
+            .collect()
+    }
+}
+
+/// The calls and results of a step, those of the provider's tools ahead of
+/// forge's as they ran while the model replied
+pub fn with_provider_tools(
+    provider_tools: &[ProviderToolCall],
+    tool_calls: Vec<ToolCallFull>,
+    tool_results: Vec<ToolResult>,
+) -> (Vec<ToolCallFull>, Vec<ToolResult>) {
+    let calls = provider_tools
+        .iter()
+        .map(|tool| tool.call.clone())
+        .chain(tool_calls)
+        .collect();
+    let results = provider_tools
+        .iter()
+        .map(|tool| tool.result.clone())
+        .chain(tool_results)
+        .collect();
+    (calls, results)
+}
+
+impl Context {
+    /// Moves the calls of the provider's tools, with their results, into the
+    /// text of the assistant message that made them. Requests may only call
+    /// the tools they define, and the provider's own tools are never among
+    /// them.
+    pub fn inline_provider_tools(mut self) -> Self {
+        let mut results = self
+            .messages
+            .iter()
+            .filter_map(|message| match message {
+                ContextMessage::ToolMessage(result) if result.provider => {
+                    Some((result.call_id.clone()?, result.clone()))
+                }
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+        if results.is_empty() {
+            return self;
+        }
+
+        self.messages = std::mem::take(&mut self.messages)
+            .into_iter()
+            .filter_map(|message| match message {
+                ContextMessage::ToolMessage(result) if result.provider => None,
+                ContextMessage::ContentMessage(mut message) => {
+                    if let Some(calls) = message.tool_calls.take() {
+                        let mut own = Vec::new();
+                        for call in calls {
+                            match call.call_id.as_ref().and_then(|id| results.remove(id)) {
+                                Some(result) => message.content.push_str(&format!(
+                                    "\n<provider_tool_call><tool_name>{}</tool_name><arguments>{}</arguments></provider_tool_call>{}",
+                                    call.name.as_str(),
+                                    call.arguments,
+                                    result
+                                )),
+                                None => own.push(call),
+                            }
+                        }
+                        message.tool_calls = (!own.is_empty()).then_some(own);
+                    }
+                    Some(ContextMessage::ContentMessage(message))
+                }
+                message => Some(message),
+            })
+            .collect();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::ToolName;
+
+    fn search(id: &str) -> ToolCallFull {
+        ToolCallFull::new(ToolName::new("web_search"))
+            .call_id(ToolCallId::new(id))
+            .arguments(json!({"query": "rust 2024 edition"}))
+    }
+
+    fn found(id: &str) -> ProviderToolEvent {
+        ProviderToolEvent::Result {
+            call_id: ToolCallId::new(id),
+            content: ToolContent::from("3 results"),
+            is_error: false,
+        }
+    }
+
+    #[test]
+    fn test_results_complete_their_calls() {
+        let mut fixture = ProviderTools::default();
+
+        let first = fixture.push(ProviderToolEvent::Call(search("srv_1")));
+        let second = fixture.push(ProviderToolEvent::Call(search("srv_2")));
+        let actual = fixture.push(found("srv_2")).unwrap();
+
+        assert_eq!((first, second), (None, None));
+        assert_eq!(actual.call, search("srv_2"));
+        assert_eq!(actual.result, fixture_result("srv_2"));
+        assert_eq!(fixture.pending, vec![search("srv_1")]);
+    }
+
+    #[test]
+    fn test_unknown_results_are_dropped() {
+        let mut fixture = ProviderTools::default();
+
+        let actual = fixture.push(found("srv_1"));
+
+        assert_eq!(actual, None);
+        assert!(fixture.finish().is_empty());
+    }
+
+    #[test]
+    fn test_provider_calls_are_inlined_into_the_assistant_message() {
+        let read = ToolCallFull::new(ToolName::new("tool_forge_fs_read"))
+            .call_id(ToolCallId::new("call_1"));
+        let mut fixture = ProviderTools::default();
+        fixture.push(ProviderToolEvent::Call(search("srv_1")));
+        let provider = fixture.push(found("srv_1")).unwrap();
+        let (calls, results) = with_provider_tools(
+            &[provider],
+            vec![read.clone()],
+            vec![ToolResult::from(read.clone()).success("fn main() {}")],
+        );
+        let context = Context::default()
+            .add_message(ContextMessage::assistant("Searching", Some(calls)))
+            .add_tool_results(results);
+
+        let actual = context.inline_provider_tools();
+
+        let expected = Context::default()
+            .add_message(ContextMessage::assistant(
+                format!(
+                    "Searching\n<provider_tool_call><tool_name>web_search</tool_name><arguments>{}</arguments></provider_tool_call>{}",
+                    json!({"query": "rust 2024 edition"}),
+                    fixture_result("srv_1")
+                ),
+                Some(vec![read.clone()]),
+            ))
+            .add_tool_results(vec![ToolResult::from(read).success("fn main() {}")]);
+        assert_eq!(actual, expected);
+    }
+
+    fn fixture_result(id: &str) -> ToolResult {
+        let mut result = ToolResult::from(search(id)).success("3 results");
+        result.provider = true;
+        result
+    }
+
+    #[test]
+    fn test_calls_without_result_fail() {
+        let mut fixture = ProviderTools::default();
+        fixture.push(ProviderToolEvent::Call(search("srv_1")));
+
+        let actual = fixture.finish();
+
+        assert_eq!(actual.len(), 1);
+        assert!(actual[0].result.is_error);
+        assert_eq!(
+            actual[0].result.content,
+            ToolContent::from(MISSING_PROVIDER_RESULT)
+        );
+    }
+}
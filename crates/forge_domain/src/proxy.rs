@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+/// Proxies outbound HTTP requests go through
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyConfig {
+    /// Proxy of plain HTTP requests
+    pub http: Option<String>,
+    /// Proxy of HTTPS requests
+    pub https: Option<String>,
+    /// Comma separated hosts, domains and IP ranges reached without a proxy
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Reads the standard HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY
+    /// variables, or their lowercase forms, through `var`. ALL_PROXY applies
+    /// to the schemes without a proxy of their own. No proxy is used when
+    /// FORGE_DISABLE_PROXY is set.
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let lookup = |key: &str| {
+            [key.to_string(), key.to_lowercase()]
+                .iter()
+                .filter_map(|key| var(key))
+                .find(|value| !value.trim().is_empty())
+        };
+
+        let disabled = var("FORGE_DISABLE_PROXY")
+            .is_some_and(|value| value == "1" || value.eq_ignore_ascii_case("true"));
+        if disabled {
+            return Self::default();
+        }
+
+        let all = lookup("ALL_PROXY");
+        Self {
+            http: lookup("HTTP_PROXY").or_else(|| all.clone()),
+            https: lookup("HTTPS_PROXY").or(all),
+            no_proxy: lookup("NO_PROXY"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn from_vars(vars: &[(&str, &str)]) -> ProxyConfig {
+        let vars = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        ProxyConfig::from_env(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_proxy_from_env() {
+        let actual = from_vars(&[
+            ("HTTP_PROXY", "http://proxy.corp:3128"),
+            ("https_proxy", "http://secure.corp:3128"),
+            ("NO_PROXY", "localhost,.internal"),
+        ]);
+
+        let expected = ProxyConfig {
+            http: Some("http://proxy.corp:3128".to_string()),
+            https: Some("http://secure.corp:3128".to_string()),
+            no_proxy: Some("localhost,.internal".to_string()),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_all_proxy_is_the_fallback() {
+        let actual = from_vars(&[
+            ("ALL_PROXY", "socks5://proxy.corp:1080"),
+            ("HTTPS_PROXY", "http://secure.corp:3128"),
+            ("HTTP_PROXY", ""),
+        ]);
+
+        let expected = ProxyConfig {
+            http: Some("socks5://proxy.corp:1080".to_string()),
+            https: Some("http://secure.corp:3128".to_string()),
+            no_proxy: None,
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_proxy_can_be_disabled() {
+        let actual = from_vars(&[
+            ("HTTPS_PROXY", "http://secure.corp:3128"),
+            ("FORGE_DISABLE_PROXY", "true"),
+        ]);
+
+        assert_eq!(actual, ProxyConfig::default());
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+/// Directory within the conversation's scratch directory that keeps the full
+/// output of truncated tool results
+pub const TOOL_OUTPUT_DIR: &str = "tool-outputs";
+
+/// Longest file name, before the extension, given to a saved output
+const MAX_OUTPUT_NAME_LEN: usize = 64;
+
+/// Path within the scratch directory where the full output of a tool call is
+/// saved. Call ids come from the provider, so only their letters, digits,
+/// `-` and `_` are kept and a path can't be made to point elsewhere. A call
+/// without an id, or one with nothing left of it, is given a random name.
+pub fn tool_output_path(call_id: Option<&str>) -> String {
+    let name = call_id
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(MAX_OUTPUT_NAME_LEN)
+        .collect::<String>();
+    let name = if name.is_empty() {
+        uuid::Uuid::new_v4().to_string()
+    } else {
+        name
+    };
+    format!("{TOOL_OUTPUT_DIR}/{name}.txt")
+}
+
+/// How tool results are prepared before they're added to the context
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolResultPolicy {
+    /// Size of a tool's output past which it's cut, the full output is saved
+    /// to the conversation's scratch directory
+    pub max_bytes: usize,
+    /// Make the paths under the working directory relative, which saves the
+    /// model tokens
+    pub relative_paths: bool,
+}
+
+impl Default for ToolResultPolicy {
+    fn default() -> Self {
+        Self { max_bytes: 64 * 1024, relative_paths: false }
+    }
+}
+
+impl ToolResultPolicy {
+    pub fn exceeds(&self, output: &str) -> bool {
+        output.len() > self.max_bytes
+    }
+
+    /// Cuts the output after `max_bytes`, at a character boundary, and tells
+    /// the agent where the full output was saved
+    pub fn truncate(&self, output: &str, saved_at: &str) -> String {
+        let mut end = self.max_bytes.min(output.len());
+        while !output.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!(
+            "{}\n\n[Output truncated after {} of {} bytes. The full output was saved to {}, find what's needed with tool_forge_fs_search on its directory rather than reading all of it]",
+            &output[..end],
+            end,
+            output.len(),
+            saved_at
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_truncate_at_char_boundary() {
+        let fixture = ToolResultPolicy { max_bytes: 4, ..Default::default() };
+
+        let actual = fixture.truncate("abcé and more", "scratch:tool-outputs/1.txt");
+
+        let expected = "abc\n\n[Output truncated after 3 of 14 bytes. The full output was saved to scratch:tool-outputs/1.txt, find what's needed with tool_forge_fs_search on its directory rather than reading all of it]";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_exceeds() {
+        let fixture = ToolResultPolicy { max_bytes: 3, ..Default::default() };
+
+        assert!(!fixture.exceeds("abc"));
+        assert!(fixture.exceeds("abcd"));
+    }
+
+    #[test]
+    fn test_tool_output_path_keeps_call_id() {
+        let actual = tool_output_path(Some("toolu_01-AbC"));
+        let expected = "tool-outputs/toolu_01-AbC.txt";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_tool_output_path_stays_in_its_directory() {
+        let actual = [
+            tool_output_path(Some("../../.bashrc")),
+            tool_output_path(Some("/etc/passwd")),
+            tool_output_path(Some("a\\..\\b")),
+        ];
+        let expected = [
+            "tool-outputs/bashrc.txt",
+            "tool-outputs/etcpasswd.txt",
+            "tool-outputs/ab.txt",
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_tool_output_path_without_usable_call_id() {
+        for call_id in [None, Some(""), Some("../..")] {
+            let actual = tool_output_path(call_id);
+            let name = actual
+                .strip_prefix("tool-outputs/")
+                .and_then(|name| name.strip_suffix(".txt"))
+                .unwrap();
+            assert!(uuid::Uuid::parse_str(name).is_ok(), "{actual}");
+        }
+    }
+}
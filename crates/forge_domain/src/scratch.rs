@@ -0,0 +1,146 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::bail;
+use serde_json::Value;
+
+/// Prefix of paths within the conversation's scratch directory, e.g.
+/// `scratch:build.sh`
+pub const SCRATCH_PREFIX: &str = "scratch:";
+
+/// Placeholder in tool descriptions that is replaced with the path of the
+/// conversation's scratch directory
+pub const SCRATCH_DIR_VARIABLE: &str = "{{scratch_dir}}";
+
+/// Tool arguments that hold paths and may use the scratch prefix
+const PATH_ARGUMENTS: [&str; 4] = ["path", "source", "destination", "cwd"];
+
+/// Expands a `scratch:` path to a path within `scratch_dir`. Returns `None`
+/// for paths without the prefix.
+pub fn expand_scratch_path(value: &str, scratch_dir: &Path) -> anyhow::Result<Option<PathBuf>> {
+    let Some(relative) = value.strip_prefix(SCRATCH_PREFIX) else {
+        return Ok(None);
+    };
+
+    let relative = Path::new(relative.trim_start_matches(['/', '\\']));
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        bail!("Scratch path must stay within the scratch directory: {value}")
+    }
+
+    Ok(Some(scratch_dir.join(relative)))
+}
+
+/// Expands the `scratch:` paths among the path arguments of a tool call.
+/// Returns whether any argument was expanded. Fails for paths within the
+/// scratch directory of another conversation, the tools only check that
+/// paths are under the directory holding all of them.
+pub fn expand_scratch_arguments(arguments: &mut Value, scratch_dir: &Path) -> anyhow::Result<bool> {
+    let Some(arguments) = arguments.as_object_mut() else {
+        return Ok(false);
+    };
+
+    let mut expanded = false;
+    for key in PATH_ARGUMENTS {
+        if let Some(Value::String(value)) = arguments.get_mut(key) {
+            if let Some(path) = expand_scratch_path(value, scratch_dir)? {
+                *value = path.display().to_string();
+                expanded = true;
+            }
+            assert_own_scratch(Path::new(value), scratch_dir)?;
+        }
+    }
+    Ok(expanded)
+}
+
+/// Ensures a path within the scratch root is within `scratch_dir`
+fn assert_own_scratch(path: &Path, scratch_dir: &Path) -> anyhow::Result<()> {
+    let Some(root) = scratch_dir.parent() else {
+        return Ok(());
+    };
+    if !path.starts_with(root) {
+        return Ok(());
+    }
+
+    let escapes = path.components().any(|c| c == Component::ParentDir);
+    if escapes || !path.starts_with(scratch_dir) {
+        bail!(
+            "Path is in the scratch directory of another conversation: {}",
+            path.display()
+        )
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_expand_scratch_path() {
+        let scratch_dir = Path::new("/forge/scratch/1");
+
+        assert_eq!(
+            expand_scratch_path("scratch:build/run.sh", scratch_dir).unwrap(),
+            Some(PathBuf::from("/forge/scratch/1/build/run.sh"))
+        );
+        assert_eq!(
+            expand_scratch_path("scratch:/run.sh", scratch_dir).unwrap(),
+            Some(PathBuf::from("/forge/scratch/1/run.sh"))
+        );
+        assert_eq!(
+            expand_scratch_path("scratch:", scratch_dir).unwrap(),
+            Some(PathBuf::from("/forge/scratch/1/"))
+        );
+        assert_eq!(
+            expand_scratch_path("/src/main.rs", scratch_dir).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_expand_scratch_path_rejects_escapes() {
+        let actual = expand_scratch_path("scratch:../2/secret", Path::new("/forge/scratch/1"));
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn test_expand_scratch_arguments() {
+        let mut fixture = json!({
+            "command": "sh scratch:run.sh",
+            "cwd": "scratch:",
+            "source": "/src/main.rs",
+            "destination": "scratch:main.rs"
+        });
+
+        let actual = expand_scratch_arguments(&mut fixture, Path::new("/forge/scratch/1")).unwrap();
+
+        let expected = json!({
+            "command": "sh scratch:run.sh",
+            "cwd": "/forge/scratch/1/",
+            "source": "/src/main.rs",
+            "destination": "/forge/scratch/1/main.rs"
+        });
+        assert!(actual);
+        assert_eq!(fixture, expected);
+    }
+
+    #[test]
+    fn test_expand_scratch_arguments_rejects_other_conversations() {
+        let scratch_dir = Path::new("/forge/scratch/1");
+
+        let mut own = json!({"path": "/forge/scratch/1/run.sh"});
+        assert!(expand_scratch_arguments(&mut own, scratch_dir).is_ok());
+
+        let mut other = json!({"path": "/forge/scratch/2/secret"});
+        assert!(expand_scratch_arguments(&mut other, scratch_dir).is_err());
+
+        let mut escaping = json!({"source": "/forge/scratch/1/../2/secret"});
+        assert!(expand_scratch_arguments(&mut escaping, scratch_dir).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Prefix of a value that refers to a stored secret, eg. `secret:NPM_TOKEN`
+pub const SECRET_PREFIX: &str = "secret:";
+
+/// A stored secret as it is listed, its value is never part of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretInfo {
+    pub name: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The name of the secret the value refers to, `None` for a plain value
+pub fn secret_ref(value: &str) -> Option<&str> {
+    value.strip_prefix(SECRET_PREFIX).map(str::trim)
+}
+
+/// Secret names are restricted to what can be an environment variable name
+pub fn is_valid_secret_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_secret_ref() {
+        let actual = ["secret:NPM_TOKEN", "secret: DB_URL", "postgres://localhost"].map(secret_ref);
+
+        assert_eq!(actual, [Some("NPM_TOKEN"), Some("DB_URL"), None]);
+    }
+
+    #[test]
+    fn test_secret_names() {
+        let actual = ["NPM_TOKEN", "_db2", "2FA", "DB-URL", ""].map(is_valid_secret_name);
+
+        assert_eq!(actual, [true, true, false, false, false]);
+    }
+}
@@ -68,7 +68,7 @@ impl Summary<'_, '_> {
 }
 
 // TODO: this is a quick hack to get a ballpark token count
-fn token_count(text: &str) -> usize {
+pub(crate) fn token_count(text: &str) -> usize {
     text.split_whitespace().count() * 75 / 100
 }
 
@@ -91,3 +91,31 @@ fn turns(context: &Context) -> Vec<Range<usize>> {
         .map(|(start, end)| start..end)
         .collect::<Vec<_>>()
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_summarizing_every_turn_keeps_the_system_message() {
+        let mut fixture = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("read a.txt"))
+            .add_message(ContextMessage::assistant("a.txt says hello", None))
+            .add_message(ContextMessage::user("read b.txt"))
+            .add_message(ContextMessage::assistant("b.txt says bye", None));
+
+        let mut summarize = Summarize::new(&mut fixture, 0);
+        while let Some(mut summary) = summarize.summarize() {
+            summary.set("read a file");
+        }
+
+        assert_eq!(fixture.system.as_deref(), Some("Be brief"));
+        assert!(!fixture
+            .messages
+            .iter()
+            .any(|message| message.has_role(Role::System)));
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{AgentId, ToolName};
+
+/// Number of turn timings kept in a conversation, older ones are dropped
+pub const MAX_TURN_TIMINGS: usize = 20;
+
+/// Where the time of an agent's turn went. Durations of the same kind are
+/// summed over every request the turn made to the provider.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnTiming {
+    pub agent: AgentId,
+    pub started_at: DateTime<Utc>,
+    /// Rendering the system and user prompts, including attachments
+    pub prompt: Duration,
+    /// Waiting for the first token of the provider's responses
+    pub first_token: Duration,
+    /// Requesting and streaming the provider's responses, including the wait
+    /// for the first token
+    pub provider: Duration,
+    /// Every tool call, in the order they were executed
+    pub tools: Vec<ToolTiming>,
+    /// Applying the agent's context transforms
+    pub transform: Duration,
+    /// Storing the context and recording recovery data
+    pub persistence: Duration,
+    pub total: Duration,
+    /// Requests made to the provider
+    #[serde(default)]
+    pub iterations: u64,
+    /// Requests compacted to fit the model's context
+    #[serde(default)]
+    pub compactions: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolTiming {
+    pub name: ToolName,
+    pub duration: Duration,
+}
+
+impl TurnTiming {
+    pub fn new(agent: AgentId) -> Self {
+        Self {
+            agent,
+            started_at: Utc::now(),
+            prompt: Duration::ZERO,
+            first_token: Duration::ZERO,
+            provider: Duration::ZERO,
+            tools: Vec::new(),
+            transform: Duration::ZERO,
+            persistence: Duration::ZERO,
+            total: Duration::ZERO,
+            iterations: 0,
+            compactions: 0,
+        }
+    }
+
+    /// Time spent in tool calls
+    pub fn tools_total(&self) -> Duration {
+        self.tools.iter().map(|tool| tool.duration).sum()
+    }
+
+    /// Time spent neither waiting for the provider nor running tools
+    pub fn other(&self) -> Duration {
+        self.total
+            .saturating_sub(self.provider)
+            .saturating_sub(self.tools_total())
+    }
+}
+
+impl fmt::Display for TurnTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "turn: {:.1}s (provider {:.1}s, tools {:.1}s, other {:.1}s)",
+            self.total.as_secs_f64(),
+            self.provider.as_secs_f64(),
+            self.tools_total().as_secs_f64(),
+            self.other().as_secs_f64()
+        )?;
+        match self.compactions {
+            0 => Ok(()),
+            1 => write!(f, ", 1 request compacted"),
+            compactions => write!(f, ", {compactions} requests compacted"),
+        }
+    }
+}
+
+/// Awaits the future, adding the time it took to `bucket`
+pub(crate) async fn timed<F: Future>(bucket: &mut Duration, future: F) -> F::Output {
+    let start = tokio::time::Instant::now();
+    let output = future.await;
+    *bucket += start.elapsed();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_display_footer() {
+        let mut fixture = TurnTiming::new(AgentId::new("developer"));
+        fixture.total = Duration::from_millis(14_200);
+        fixture.provider = Duration::from_millis(11_800);
+        fixture.tools = vec![
+            ToolTiming {
+                name: ToolName::new("tool_forge_fs_read"),
+                duration: Duration::from_millis(600),
+            },
+            ToolTiming {
+                name: ToolName::new("tool_forge_process_shell"),
+                duration: Duration::from_millis(1_500),
+            },
+        ];
+
+        let actual = fixture.to_string();
+
+        let expected = "turn: 14.2s (provider 11.8s, tools 2.1s, other 0.3s)";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_display_footer_with_compactions() {
+        let mut fixture = TurnTiming::new(AgentId::new("developer"));
+        fixture.total = Duration::from_millis(2_000);
+        fixture.provider = Duration::from_millis(2_000);
+        fixture.compactions = 2;
+
+        let actual = fixture.to_string();
+
+        let expected = "turn: 2.0s (provider 2.0s, tools 0.0s, other 0.0s), 2 requests compacted";
+        assert_eq!(actual, expected);
+    }
+}
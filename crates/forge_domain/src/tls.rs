@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// How the certificates of HTTPS servers are verified
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsConfig {
+    /// PEM bundle of root certificates trusted along with the built-in ones,
+    /// eg. the CA of a corporate proxy or of a self-hosted provider
+    pub ca_bundle: Option<PathBuf>,
+    /// Accept any certificate. Only meant for development against servers
+    /// with self-signed certificates.
+    pub accept_invalid_certs: bool,
+}
+
+impl TlsConfig {
+    /// Reads FORGE_CA_BUNDLE, or the standard SSL_CERT_FILE, and
+    /// FORGE_ACCEPT_INVALID_CERTS through `var`. SSL_CERT_FILE is often set
+    /// for other tools, so one that isn't a file is ignored with a warning,
+    /// while a bad FORGE_CA_BUNDLE fails the clients.
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let set = |key: &str| var(key).filter(|value| !value.trim().is_empty());
+        let ca_bundle = set("FORGE_CA_BUNDLE").map(PathBuf::from).or_else(|| {
+            let path = PathBuf::from(set("SSL_CERT_FILE")?);
+            if path.is_file() {
+                Some(path)
+            } else {
+                tracing::warn!(path = %path.display(), "Ignoring SSL_CERT_FILE, it isn't a file");
+                None
+            }
+        });
+        let accept_invalid_certs = var("FORGE_ACCEPT_INVALID_CERTS")
+            .is_some_and(|value| value == "1" || value.eq_ignore_ascii_case("true"));
+
+        Self { ca_bundle, accept_invalid_certs }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn from_vars(vars: &[(&str, &str)]) -> TlsConfig {
+        let vars = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        TlsConfig::from_env(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_tls_from_env() {
+        let actual = from_vars(&[
+            ("SSL_CERT_FILE", "/etc/ssl/certs/ca-certificates.crt"),
+            ("FORGE_CA_BUNDLE", "/opt/corp/ca.pem"),
+            ("FORGE_ACCEPT_INVALID_CERTS", "true"),
+        ]);
+
+        let expected = TlsConfig {
+            ca_bundle: Some(PathBuf::from("/opt/corp/ca.pem")),
+            accept_invalid_certs: true,
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ssl_cert_file_is_the_fallback() {
+        // Any file will do, it's only read by the clients
+        let file = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let actual = from_vars(&[
+            ("FORGE_CA_BUNDLE", " "),
+            ("SSL_CERT_FILE", file),
+            ("FORGE_ACCEPT_INVALID_CERTS", "no"),
+        ]);
+
+        let expected = TlsConfig {
+            ca_bundle: Some(PathBuf::from(file)),
+            accept_invalid_certs: false,
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_missing_ssl_cert_file_is_ignored() {
+        let actual = from_vars(&[("SSL_CERT_FILE", "/nonexistent/ca-certificates.crt")]);
+
+        assert_eq!(actual, TlsConfig::default());
+    }
+
+    #[test]
+    fn test_missing_forge_ca_bundle_is_kept() {
+        let actual = from_vars(&[("FORGE_CA_BUNDLE", "/nonexistent/ca.pem")]);
+
+        let expected = TlsConfig {
+            ca_bundle: Some(PathBuf::from("/nonexistent/ca.pem")),
+            accept_invalid_certs: false,
+        };
+        assert_eq!(actual, expected);
+    }
+}
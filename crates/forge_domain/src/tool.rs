@@ -1,7 +1,7 @@
 use schemars::JsonSchema;
 use serde_json::Value;
 
-use crate::{ExecutableTool, NamedTool, ToolDefinition, ToolDescription};
+use crate::{ExecutableTool, NamedTool, ToolContent, ToolDefinition, ToolDescription};
 
 struct JsonTool<T>(T);
 
@@ -22,6 +22,11 @@ where
         let input: T::Input = serde_json::from_value(input)?;
         self.0.call(input).await
     }
+
+    async fn call_content(&self, input: Self::Input) -> anyhow::Result<ToolContent> {
+        let input: T::Input = serde_json::from_value(input)?;
+        self.0.call_content(input).await
+    }
 }
 
 pub struct Tool {
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use derive_more::derive::From;
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
@@ -7,7 +9,7 @@ use super::tool_call_parser::parse;
 use crate::{Error, Result, ToolName};
 
 /// Unique identifier for a using a tool
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Hash, Serialize)]
 #[serde(transparent)]
 pub struct ToolCallId(pub(crate) String);
 
@@ -16,6 +18,10 @@ impl ToolCallId {
         ToolCallId(value.to_string())
     }
 
+    pub fn generate() -> Self {
+        ToolCallId(uuid::Uuid::new_v4().to_string())
+    }
+
     pub fn as_str(&self) -> &str {
         &self.0
     }
@@ -74,6 +80,19 @@ impl ToolCallFull {
         Self { name: tool_name, call_id: None, arguments: Value::default() }
     }
 
+    /// The file the call creates or edits, its content before the call is
+    /// kept in [`crate::ToolResult::before`]
+    pub fn written_path(&self) -> Option<PathBuf> {
+        match self.name.as_str() {
+            "tool_forge_fs_create" | "tool_forge_fs_patch" | "tool_forge_fs_replace" => self
+                .arguments
+                .get("path")
+                .and_then(Value::as_str)
+                .map(PathBuf::from),
+            _ => None,
+        }
+    }
+
     pub fn try_from_parts(parts: &[ToolCallPart]) -> Result<Vec<Self>> {
         if parts.is_empty() {
             return Ok(vec![]);
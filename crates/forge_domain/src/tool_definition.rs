@@ -1,12 +1,13 @@
 use std::collections::BTreeSet;
 
 use derive_setters::Setters;
-use schemars::schema::RootSchema;
+use schemars::schema::{InstanceType, RootSchema, Schema, SingleOrVec};
 use schemars::JsonSchema;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::{NamedTool, ToolName, UsageParameterPrompt, UsagePrompt};
+use crate::{NamedTool, ToolContent, ToolName, UsageParameterPrompt, UsagePrompt};
 
 ///
 /// Refer to the specification over here:
@@ -18,6 +19,9 @@ pub struct ToolDefinition {
     pub description: String,
     pub input_schema: RootSchema,
     pub output_schema: Option<RootSchema>,
+    /// Example inputs showing the model how the tool is called
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<Value>,
 }
 
 impl ToolDefinition {
@@ -28,9 +32,78 @@ impl ToolDefinition {
             description: String::new(),
             input_schema: schemars::schema_for!(()), // Empty input schema
             output_schema: None,
+            examples: Vec::new(),
         }
     }
 
+    /// The examples in a compact form, one JSON object per line, for
+    /// providers that can't be sent the examples along with the schema
+    pub fn examples_prompt(&self) -> Option<String> {
+        if self.examples.is_empty() {
+            return None;
+        }
+
+        let examples = self
+            .examples
+            .iter()
+            .map(|example| example.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some(format!("Examples:\n{examples}"))
+    }
+
+    /// The description followed by the examples
+    pub fn description_with_examples(&self) -> String {
+        match self.examples_prompt() {
+            Some(examples) => format!("{}\n\n{examples}", self.description),
+            None => self.description.clone(),
+        }
+    }
+
+    /// Checks that every example is an object that sets the required
+    /// parameters, only the parameters of the schema, with values of the
+    /// declared types
+    pub fn check_examples(&self) -> anyhow::Result<()> {
+        let object = self.input_schema.schema.object.as_ref();
+        for example in &self.examples {
+            let Value::Object(parameters) = example else {
+                anyhow::bail!(
+                    "Example of {} isn't an object: {example}",
+                    self.name.as_str()
+                );
+            };
+
+            if let Some(object) = object {
+                if let Some(missing) = object
+                    .required
+                    .iter()
+                    .find(|name| !parameters.contains_key(*name))
+                {
+                    anyhow::bail!(
+                        "Example of {} misses the required parameter {missing}",
+                        self.name.as_str()
+                    );
+                }
+            }
+
+            for (name, value) in parameters {
+                let Some(schema) = object.and_then(|object| object.properties.get(name)) else {
+                    anyhow::bail!(
+                        "Example of {} sets the unknown parameter {name}",
+                        self.name.as_str()
+                    );
+                };
+                if !matches_type(schema, value) {
+                    anyhow::bail!(
+                        "Example of {} sets {name} to a value of the wrong type: {value}",
+                        self.name.as_str()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Usage prompt method (existing implementation)
     pub fn usage_prompt(&self) -> UsagePrompt {
         let input_parameters = self
@@ -54,10 +127,34 @@ impl ToolDefinition {
             tool_name: self.name.clone().into_string(),
             input_parameters,
             description: self.description.to_string(),
+            examples: self.examples_prompt(),
         }
     }
 }
 
+/// Whether the value has one of the types the schema declares. Schemas
+/// without a type, eg. those of enums or references, match any value.
+fn matches_type(schema: &Schema, value: &Value) -> bool {
+    let Schema::Object(schema) = schema else {
+        return true;
+    };
+    let types = match &schema.instance_type {
+        Some(SingleOrVec::Single(instance_type)) => vec![**instance_type],
+        Some(SingleOrVec::Vec(instance_types)) => instance_types.clone(),
+        None => return true,
+    };
+
+    types.into_iter().any(|instance_type| match instance_type {
+        InstanceType::Null => value.is_null(),
+        InstanceType::Boolean => value.is_boolean(),
+        InstanceType::Object => value.is_object(),
+        InstanceType::Array => value.is_array(),
+        InstanceType::Number => value.is_number(),
+        InstanceType::String => value.is_string(),
+        InstanceType::Integer => value.is_i64() || value.is_u64(),
+    })
+}
+
 impl<T> From<&T> for ToolDefinition
 where
     T: NamedTool + ExecutableTool + ToolDescription + Send + Sync + 'static,
@@ -113,17 +210,108 @@ where
             description: full_description,
             input_schema: input,
             output_schema: Some(output),
+            examples: t.examples(),
         }
     }
 }
 
 pub trait ToolDescription {
     fn description(&self) -> String;
+
+    /// Example inputs of the tool, set with `#[example = "..."]` when the
+    /// trait is derived
+    fn examples(&self) -> Vec<Value> {
+        Vec::new()
+    }
 }
 
 #[async_trait::async_trait]
 pub trait ExecutableTool {
-    type Input: DeserializeOwned;
+    type Input: DeserializeOwned + Send;
 
     async fn call(&self, input: Self::Input) -> anyhow::Result<String>;
+
+    /// Calls the tool for the agent. The output is text, tools answering with
+    /// structured data override it to keep their output JSON.
+    async fn call_content(&self, input: Self::Input) -> anyhow::Result<ToolContent> {
+        self.call(input).await.map(ToolContent::Text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    struct Input {
+        path: String,
+        limit: Option<u64>,
+    }
+
+    fn fixture(examples: Vec<Value>) -> ToolDefinition {
+        ToolDefinition::new("read")
+            .description("Reads a file")
+            .input_schema(schemars::schema_for!(Input))
+            .examples(examples)
+    }
+
+    #[test]
+    fn test_check_valid_examples() {
+        let fixture = fixture(vec![
+            json!({"path": "/src/main.rs"}),
+            json!({"path": "/src/main.rs", "limit": 10}),
+        ]);
+
+        assert!(fixture.check_examples().is_ok());
+    }
+
+    #[test]
+    fn test_check_invalid_examples() {
+        let actual = [
+            json!("/src/main.rs"),
+            json!({"limit": 10}),
+            json!({"path": "/src/main.rs", "lines": 10}),
+            json!({"path": 42}),
+        ]
+        .into_iter()
+        .map(|example| {
+            fixture(vec![example])
+                .check_examples()
+                .unwrap_err()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+
+        let expected = vec![
+            "Example of read isn't an object: \"/src/main.rs\"",
+            "Example of read misses the required parameter path",
+            "Example of read sets the unknown parameter lines",
+            "Example of read sets path to a value of the wrong type: 42",
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_examples_in_usage_prompt() {
+        let fixture = fixture(vec![json!({"path": "/src/main.rs"})]);
+
+        let actual = fixture.usage_prompt().to_string();
+
+        let expected = "read\nReads a file\n\nUsage:\n<tool_call>\n<read>\n<limit>...</limit>\n<path>...</path>\n</read>\n</tool_call>\n\nExamples:\n{\"path\":\"/src/main.rs\"}\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_usage_prompt_without_examples() {
+        let fixture = fixture(vec![]);
+
+        let actual = fixture.usage_prompt().to_string();
+
+        assert!(actual.ends_with("</tool_call>\n"));
+        assert_eq!(fixture.description_with_examples(), "Reads a file");
+    }
 }
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Decides how a tool call is handled before it is executed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolPolicy {
+    /// Execute the tool call without asking
+    #[default]
+    Auto,
+    /// Ask the user to approve the tool call before executing it
+    Prompt,
+    /// Never execute the tool call
+    Deny,
+}
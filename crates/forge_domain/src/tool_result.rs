@@ -1,17 +1,108 @@
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::{ToolCallFull, ToolCallId, ToolName};
 
+/// Output of a tool call, either text or the JSON of tools answering with
+/// structured data. Providers only taking text get the JSON serialized.
+///
+/// Results stored before the content was typed hold a string, which is read
+/// as text.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ToolContent {
+    Text(String),
+    Json(Value),
+}
+
+impl ToolContent {
+    /// JSON content, a JSON string is text
+    pub fn json(value: Value) -> Self {
+        match value {
+            Value::String(text) => Self::Text(text),
+            value => Self::Json(value),
+        }
+    }
+
+    pub fn is_json(&self) -> bool {
+        matches!(self, Self::Json(_))
+    }
+
+    /// Rewrites the text of the content, ie. the text itself or every string
+    /// of the JSON
+    pub fn map_text(self, f: impl Fn(&str) -> String) -> Self {
+        fn map_value(value: Value, f: &impl Fn(&str) -> String) -> Value {
+            match value {
+                Value::String(text) => Value::String(f(&text)),
+                Value::Array(values) => Value::Array(
+                    values
+                        .into_iter()
+                        .map(|value| map_value(value, f))
+                        .collect(),
+                ),
+                Value::Object(map) => Value::Object(
+                    map.into_iter()
+                        .map(|(key, value)| (key, map_value(value, f)))
+                        .collect(),
+                ),
+                value => value,
+            }
+        }
+
+        match self {
+            Self::Text(text) => Self::Text(f(&text)),
+            Self::Json(value) => Self::Json(map_value(value, &f)),
+        }
+    }
+}
+
+impl Default for ToolContent {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl From<String> for ToolContent {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&str> for ToolContent {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl std::fmt::Display for ToolContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(text) => write!(f, "{text}"),
+            Self::Json(value) => write!(f, "{value}"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, Setters)]
 #[setters(strip_option, into)]
 pub struct ToolResult {
     pub name: ToolName,
     pub call_id: Option<ToolCallId>,
     #[setters(skip)]
-    pub content: String,
+    pub content: ToolContent,
     #[setters(skip)]
     pub is_error: bool,
+    /// Content of the file the call wrote, read before the call ran, see
+    /// [`ToolCallFull::written_path`]. Only used to show what the call
+    /// changed, it's neither stored nor sent.
+    #[serde(skip)]
+    pub before: Option<String>,
+    /// Whether the provider ran the tool itself, see
+    /// [`crate::Context::inline_provider_tools`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[setters(skip)]
+    pub provider: bool,
 }
 
 impl ToolResult {
@@ -19,12 +110,14 @@ impl ToolResult {
         Self {
             name,
             call_id: None,
-            content: String::default(),
+            content: ToolContent::default(),
             is_error: false,
+            before: None,
+            provider: false,
         }
     }
 
-    pub fn success(mut self, content: impl Into<String>) -> Self {
+    pub fn success(mut self, content: impl Into<ToolContent>) -> Self {
         self.content = content.into();
         self.is_error = false;
         self
@@ -38,7 +131,7 @@ impl ToolResult {
             output.push_str(&format!("Caused by: {}\n", cause));
         }
 
-        self.content = output;
+        self.content = ToolContent::Text(output);
         self.is_error = true;
         self
     }
@@ -49,8 +142,10 @@ impl From<ToolCallFull> for ToolResult {
         Self {
             name: value.name,
             call_id: value.call_id,
-            content: String::default(),
+            content: ToolContent::default(),
             is_error: false,
+            before: None,
+            provider: false,
         }
     }
 }
@@ -146,11 +241,56 @@ mod tests {
     fn test_success_and_failure_content() {
         let success = ToolResult::new(ToolName::new("test_tool")).success("success message");
         assert!(!success.is_error);
-        assert_eq!(success.content, "success message");
+        assert_eq!(success.content, ToolContent::from("success message"));
 
         let failure =
             ToolResult::new(ToolName::new("test_tool")).failure(anyhow::anyhow!("error message"));
         assert!(failure.is_error);
-        assert_eq!(failure.content, "\nERROR:\nCaused by: error message\n");
+        assert_eq!(
+            failure.content,
+            ToolContent::from("\nERROR:\nCaused by: error message\n")
+        );
+    }
+
+    #[test]
+    fn test_content_serialization() {
+        let fixture = ToolResult::new(ToolName::new("tool_forge_symbol_search"))
+            .success(ToolContent::json(json!({"matches": [], "omitted": 0})));
+
+        let actual = serde_json::to_value(&fixture).unwrap();
+
+        assert_eq!(actual["content"], json!({"matches": [], "omitted": 0}));
+        assert_eq!(
+            serde_json::from_value::<ToolResult>(actual).unwrap(),
+            fixture
+        );
+    }
+
+    #[test]
+    fn test_stored_string_content_is_text() {
+        let fixture = r#"{"name":"tool_forge_fs_read","call_id":"call_1","content":"{\"a\": 1}","is_error":false}"#;
+
+        let actual: ToolResult = serde_json::from_str(fixture).unwrap();
+
+        assert_eq!(actual.content, ToolContent::Text(r#"{"a": 1}"#.to_string()));
+    }
+
+    #[test]
+    fn test_json_string_is_text() {
+        let actual = ToolContent::json(json!("done"));
+
+        assert_eq!(actual, ToolContent::Text("done".to_string()));
+    }
+
+    #[test]
+    fn test_map_text_of_json() {
+        let fixture = ToolContent::json(json!({"path": "/project/a.rs", "line": 3}));
+
+        let actual = fixture.map_text(|text| text.replace("/project/", ""));
+
+        assert_eq!(
+            actual,
+            ToolContent::json(json!({"path": "a.rs", "line": 3}))
+        );
     }
 }
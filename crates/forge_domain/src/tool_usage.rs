@@ -5,6 +5,7 @@ pub struct UsagePrompt {
     pub tool_name: String,
     pub input_parameters: Vec<UsageParameterPrompt>,
     pub description: String,
+    pub examples: Option<String>,
 }
 
 impl Display for UsagePrompt {
@@ -30,6 +31,12 @@ impl Display for UsagePrompt {
         f.write_str(">\n")?;
         f.write_str("</tool_call>\n")?;
 
+        if let Some(examples) = &self.examples {
+            f.write_str("\n")?;
+            f.write_str(examples)?;
+            f.write_str("\n")?;
+        }
+
         Ok(())
     }
 }
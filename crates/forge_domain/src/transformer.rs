@@ -0,0 +1,428 @@
+use std::fmt;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// A built-in transformer of the requests sent to OpenAI compatible
+/// providers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformerKind {
+    /// Turns JSON tool results into text, for providers that only take text
+    StringifyToolResults,
+    /// Turns tool calls and their results into plain messages, OpenRouter only
+    DropToolCalls,
+    /// Lets the model choose whether to call tools, OpenRouter only
+    SetToolChoice,
+    /// Marks the system prompt and the last user message as cache
+    /// breakpoints, OpenRouter only
+    SetCache,
+    /// Removes the fields OpenAI doesn't support, OpenAI only
+    OpenAi,
+}
+
+impl TransformerKind {
+    pub const ALL: [TransformerKind; 5] = [
+        TransformerKind::StringifyToolResults,
+        TransformerKind::DropToolCalls,
+        TransformerKind::SetToolChoice,
+        TransformerKind::SetCache,
+        TransformerKind::OpenAi,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransformerKind::StringifyToolResults => "stringify_tool_results",
+            TransformerKind::DropToolCalls => "drop_tool_calls",
+            TransformerKind::SetToolChoice => "set_tool_choice",
+            TransformerKind::SetCache => "set_cache",
+            TransformerKind::OpenAi => "open_ai",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Whether every pipeline must run the transformer for every model.
+    /// Without `stringify_tool_results`, providers that only take text would
+    /// be sent JSON tool results.
+    fn is_required(&self) -> bool {
+        matches!(self, TransformerKind::StringifyToolResults)
+    }
+
+    /// Transformers that must run earlier in the pipeline.
+    /// `drop_tool_calls` turns tool results into user messages, which
+    /// `stringify_tool_results` no longer recognizes.
+    fn requires(&self) -> &'static [TransformerKind] {
+        match self {
+            TransformerKind::DropToolCalls => &[TransformerKind::StringifyToolResults],
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for TransformerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A transformer of the pipeline, limited to the models matching `models`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransformerStep {
+    pub kind: TransformerKind,
+    /// Regex of the models the transformer applies to, every model when
+    /// unset
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub models: Option<String>,
+}
+
+impl TransformerStep {
+    pub fn new(kind: TransformerKind) -> Self {
+        Self { kind, models: None }
+    }
+
+    pub fn models(mut self, pattern: impl ToString) -> Self {
+        self.models = Some(pattern.to_string());
+        self
+    }
+
+    /// Whether the transformer applies to requests of the model, requests
+    /// without a model included
+    pub fn matches_model(&self, model: Option<&str>) -> bool {
+        match (&self.models, model) {
+            (Some(pattern), Some(model)) => Regex::new(pattern)
+                .map(|regex| regex.is_match(model))
+                .unwrap_or(false),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for TransformerStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.models {
+            Some(pattern) => write!(f, "{}:{}", self.kind, pattern),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+/// The transformers applied to requests, in the order they run
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransformerPipeline {
+    pub steps: Vec<TransformerStep>,
+}
+
+impl TransformerPipeline {
+    /// The pipeline used when none is configured. Cache breakpoints are only
+    /// set with `prompt_cache`.
+    pub fn builtin(prompt_cache: bool) -> Self {
+        let steps = [
+            Some(TransformerStep::new(TransformerKind::StringifyToolResults)),
+            Some(TransformerStep::new(TransformerKind::DropToolCalls).models("mistral")),
+            Some(TransformerStep::new(TransformerKind::SetToolChoice).models("gemini")),
+            prompt_cache.then(|| {
+                TransformerStep::new(TransformerKind::SetCache).models("anthropic|claude")
+            }),
+            Some(TransformerStep::new(TransformerKind::OpenAi)),
+        ];
+        Self { steps: steps.into_iter().flatten().collect() }
+    }
+
+    /// Adds the cache breakpoints of the built-in pipeline ahead of
+    /// `open_ai`, unless the pipeline already sets them
+    pub fn with_prompt_cache(mut self) -> Self {
+        if self
+            .steps
+            .iter()
+            .any(|step| step.kind == TransformerKind::SetCache)
+        {
+            return self;
+        }
+        let position = self
+            .steps
+            .iter()
+            .position(|step| step.kind == TransformerKind::OpenAi)
+            .unwrap_or(self.steps.len());
+        self.steps.insert(
+            position,
+            TransformerStep::new(TransformerKind::SetCache).models("anthropic|claude"),
+        );
+        self
+    }
+
+    /// Parses a comma separated list of transformer names, each optionally
+    /// followed by `:<regex>` of the models it applies to, eg.
+    /// `stringify_tool_results,set_cache:claude`. Every problem of the list
+    /// is reported at once.
+    pub fn parse(spec: &str) -> crate::Result<Self> {
+        let mut steps = Vec::new();
+        let mut issues = Vec::new();
+
+        for entry in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (name, models) = match entry.split_once(':') {
+                Some((name, models)) => (name.trim(), Some(models.trim().to_string())),
+                None => (entry, None),
+            };
+            let Some(kind) = TransformerKind::from_name(name) else {
+                issues.push(TransformerIssue::Unknown(name.to_string()));
+                continue;
+            };
+            if let Some(pattern) = models.as_deref() {
+                if Regex::new(pattern).is_err() {
+                    issues.push(TransformerIssue::InvalidPattern {
+                        kind,
+                        pattern: pattern.to_string(),
+                    });
+                    continue;
+                }
+            }
+            steps.push(TransformerStep { kind, models });
+        }
+
+        let pipeline = Self { steps };
+        issues.extend(pipeline.validate());
+        if issues.is_empty() {
+            Ok(pipeline)
+        } else {
+            Err(crate::Error::InvalidTransformers(issues))
+        }
+    }
+
+    /// Problems of the order of the steps. A transformer may be listed more
+    /// than once for different models, but not twice for the same ones.
+    /// Required transformers must be listed, without a regex.
+    fn validate(&self) -> Vec<TransformerIssue> {
+        let mut issues = Vec::new();
+        for (position, step) in self.steps.iter().enumerate() {
+            if step.kind.is_required() && step.models.is_some() {
+                issues.push(TransformerIssue::Restricted(step.kind));
+            }
+            let earlier = &self.steps[..position];
+            if earlier
+                .iter()
+                .any(|other| other.kind == step.kind && other.models == step.models)
+            {
+                issues.push(TransformerIssue::Duplicate(step.kind));
+            }
+            for required in step.kind.requires() {
+                if !earlier.iter().any(|other| other.kind == *required) {
+                    issues
+                        .push(TransformerIssue::Requires { kind: step.kind, required: *required });
+                }
+            }
+        }
+        for kind in TransformerKind::ALL {
+            if kind.is_required() && !self.steps.iter().any(|step| step.kind == kind) {
+                issues.push(TransformerIssue::Missing(kind));
+            }
+        }
+        issues
+    }
+}
+
+impl fmt::Display for TransformerPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steps = self
+            .steps
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "{}", steps.join(","))
+    }
+}
+
+/// A single problem found while parsing a transformer pipeline
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransformerIssue {
+    Unknown(String),
+    InvalidPattern {
+        kind: TransformerKind,
+        pattern: String,
+    },
+    Duplicate(TransformerKind),
+    Missing(TransformerKind),
+    Restricted(TransformerKind),
+    Requires {
+        kind: TransformerKind,
+        required: TransformerKind,
+    },
+}
+
+impl fmt::Display for TransformerIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransformerIssue::Unknown(name) => {
+                let known = TransformerKind::ALL.map(|kind| kind.name()).join(", ");
+                write!(f, "'{}' is not a transformer, use one of {}", name, known)
+            }
+            TransformerIssue::InvalidPattern { kind, pattern } => {
+                write!(f, "'{}' of '{}' is not a valid regex", pattern, kind)
+            }
+            TransformerIssue::Duplicate(kind) => {
+                write!(f, "'{}' is listed twice for the same models", kind)
+            }
+            TransformerIssue::Missing(kind) => {
+                write!(f, "'{}' is required", kind)
+            }
+            TransformerIssue::Restricted(kind) => {
+                write!(
+                    f,
+                    "'{}' applies to every model and can't take a regex",
+                    kind
+                )
+            }
+            TransformerIssue::Requires { kind, required } => {
+                write!(f, "'{}' must come after '{}'", kind, required)
+            }
+        }
+    }
+}
+
+/// What a transformer did to the last request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransformerStage {
+    pub step: TransformerStep,
+    /// Whether the transformer applied to the provider and the model
+    pub applied: bool,
+    pub changed: bool,
+    pub messages_before: usize,
+    pub messages_after: usize,
+    /// Size of the serialized request
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+}
+
+/// The pipeline of a provider and what each of its transformers did to the
+/// last request, which is empty until a request was sent
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransformerReport {
+    pub pipeline: TransformerPipeline,
+    pub last_request: Vec<TransformerStage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let actual =
+            TransformerPipeline::parse(" stringify_tool_results, drop_tool_calls:mistral|llama ")
+                .unwrap();
+
+        let expected = TransformerPipeline {
+            steps: vec![
+                TransformerStep::new(TransformerKind::StringifyToolResults),
+                TransformerStep::new(TransformerKind::DropToolCalls).models("mistral|llama"),
+            ],
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_reports_every_issue() {
+        let actual = TransformerPipeline::parse(
+            "drop_tool_calls,set_cache:(claude,set_cache,set_cache,clamp,stringify_tool_results",
+        );
+
+        match actual {
+            Err(crate::Error::InvalidTransformers(issues)) => assert_eq!(
+                issues,
+                vec![
+                    TransformerIssue::InvalidPattern {
+                        kind: TransformerKind::SetCache,
+                        pattern: "(claude".to_string(),
+                    },
+                    TransformerIssue::Unknown("clamp".to_string()),
+                    TransformerIssue::Requires {
+                        kind: TransformerKind::DropToolCalls,
+                        required: TransformerKind::StringifyToolResults,
+                    },
+                    TransformerIssue::Duplicate(TransformerKind::SetCache),
+                ]
+            ),
+            other => panic!("Expected invalid transformers, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_same_transformer_for_different_models() {
+        let actual = TransformerPipeline::parse(
+            "stringify_tool_results,set_tool_choice:gemini,set_tool_choice:qwen",
+        );
+
+        assert!(actual.is_ok());
+    }
+
+    #[test]
+    fn test_stringify_tool_results_is_required_for_every_model() {
+        let actual = ["set_cache:claude", "stringify_tool_results:mistral"].map(|spec| {
+            match TransformerPipeline::parse(spec) {
+                Err(crate::Error::InvalidTransformers(issues)) => issues,
+                other => panic!("Expected invalid transformers, got {other:?}"),
+            }
+        });
+
+        let expected = [
+            vec![TransformerIssue::Missing(
+                TransformerKind::StringifyToolResults,
+            )],
+            vec![TransformerIssue::Restricted(
+                TransformerKind::StringifyToolResults,
+            )],
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_with_prompt_cache() {
+        let fixture = TransformerPipeline::parse("stringify_tool_results,open_ai").unwrap();
+
+        let actual = fixture.with_prompt_cache().to_string();
+
+        let expected = "stringify_tool_results,set_cache:anthropic|claude,open_ai";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_with_prompt_cache_keeps_configured_cache() {
+        let fixture =
+            TransformerPipeline::parse("stringify_tool_results,set_cache:claude").unwrap();
+
+        let actual = fixture.clone().with_prompt_cache();
+
+        assert_eq!(actual, fixture);
+    }
+
+    #[test]
+    fn test_builtin_round_trips() {
+        let fixture = TransformerPipeline::builtin(true);
+
+        let actual = TransformerPipeline::parse(&fixture.to_string()).unwrap();
+
+        assert_eq!(actual, fixture);
+        assert_eq!(
+            TransformerPipeline::builtin(false).to_string(),
+            "stringify_tool_results,drop_tool_calls:mistral,set_tool_choice:gemini,open_ai"
+        );
+    }
+
+    #[test]
+    fn test_matches_model() {
+        let fixture = TransformerStep::new(TransformerKind::SetCache).models("anthropic|claude");
+
+        assert!(fixture.matches_model(Some("anthropic/claude-3.5-sonnet")));
+        assert!(!fixture.matches_model(Some("google/gemini-pro")));
+        assert!(fixture.matches_model(None));
+        assert!(TransformerStep::new(TransformerKind::OpenAi).matches_model(Some("gpt-4o")));
+    }
+}
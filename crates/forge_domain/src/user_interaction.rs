@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, NamedTool, Result, ToolCallFull, ToolDefinition, ToolName};
+
+/// How long a question waits for the user's answer unless configured
+/// otherwise
+pub const DEFAULT_QUESTION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Something the agent asks the user in the middle of a turn. The turn waits
+/// until the user answers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UserInteraction {
+    /// A question answered with free-form text
+    Question { prompt: String },
+    /// A question answered with yes or no
+    Confirm { prompt: String },
+    /// A question answered by picking one of the options
+    Select {
+        prompt: String,
+        options: Vec<String>,
+    },
+}
+
+/// The user's answer to a [`UserInteraction`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UserAnswer {
+    Text(String),
+    /// Zero-based index of the chosen option
+    Selected(usize),
+}
+
+impl UserInteraction {
+    pub fn prompt(&self) -> &str {
+        match self {
+            UserInteraction::Question { prompt } => prompt,
+            UserInteraction::Confirm { prompt } => prompt,
+            UserInteraction::Select { prompt, .. } => prompt,
+        }
+    }
+
+    /// Checks that the answer fits the question. The error tells the user
+    /// what a valid answer looks like.
+    pub fn validate(&self, answer: &UserAnswer) -> Result<()> {
+        match (self, answer) {
+            (UserInteraction::Question { .. }, UserAnswer::Text(text)) => {
+                if text.trim().is_empty() {
+                    return Err(Error::InvalidAnswer(
+                        "the answer is empty, enter some text".to_string(),
+                    ));
+                }
+            }
+            (UserInteraction::Confirm { .. }, UserAnswer::Text(text)) => {
+                if parse_yes_no(text).is_none() {
+                    return Err(Error::InvalidAnswer(format!(
+                        "'{text}' is neither yes nor no, answer 'yes' or 'no'"
+                    )));
+                }
+            }
+            (UserInteraction::Select { options, .. }, UserAnswer::Selected(index)) => {
+                if *index >= options.len() {
+                    return Err(Error::InvalidAnswer(format!(
+                        "option {index} doesn't exist, select an index from 0 to {}",
+                        options.len().saturating_sub(1)
+                    )));
+                }
+            }
+            (UserInteraction::Select { .. }, UserAnswer::Text(_)) => {
+                return Err(Error::InvalidAnswer(
+                    "select one of the options by its index instead of answering with text"
+                        .to_string(),
+                ));
+            }
+            (_, UserAnswer::Selected(_)) => {
+                return Err(Error::InvalidAnswer(
+                    "the question has no options, answer with text".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Turns a valid answer into the text the agent receives. A selection
+    /// resolves to the chosen option and a confirmation to `yes` or `no`.
+    pub fn resolve(&self, answer: UserAnswer) -> Result<String> {
+        self.validate(&answer)?;
+        Ok(match (self, answer) {
+            (UserInteraction::Select { options, .. }, UserAnswer::Selected(index)) => {
+                options[index].clone()
+            }
+            (UserInteraction::Confirm { .. }, UserAnswer::Text(text)) => {
+                let yes = parse_yes_no(&text).unwrap_or_default();
+                if yes { "yes" } else { "no" }.to_string()
+            }
+            (_, UserAnswer::Text(text)) => text,
+            (_, UserAnswer::Selected(index)) => index.to_string(),
+        })
+    }
+}
+
+fn parse_yes_no(text: &str) -> Option<bool> {
+    match text.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(true),
+        "n" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Asks the user a question and waits for the answer. Provide `options` when
+/// the user should pick one of them, e.g. which of several files they meant.
+#[derive(Debug, Clone, JsonSchema, Deserialize)]
+pub struct UserQuestion {
+    /// The question to ask
+    pub prompt: String,
+    /// Options the user picks from, leave empty for a free-form answer
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// Set for a question answered with yes or no
+    #[serde(default)]
+    pub yes_no: bool,
+}
+
+impl NamedTool for UserQuestion {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_user_ask")
+    }
+}
+
+impl UserQuestion {
+    pub fn tool_definition() -> ToolDefinition {
+        ToolDefinition {
+            name: Self::tool_name(),
+            description: "Asks the user a question and waits for the answer. Provide options \
+                          when the user should pick one of them, e.g. which of several files \
+                          they meant, or set yes_no for a yes/no question. Only ask when the answer can't be found otherwise."
+                .to_string(),
+            input_schema: schema_for!(Self),
+            output_schema: None,
+            examples: Vec::new(),
+        }
+    }
+
+    pub fn parse(tool_call: &ToolCallFull) -> Option<UserInteraction> {
+        if tool_call.name != Self::tool_name() {
+            return None;
+        }
+        let question: Self = serde_json::from_value(tool_call.arguments.clone()).ok()?;
+        Some(question.into())
+    }
+}
+
+impl From<UserQuestion> for UserInteraction {
+    fn from(question: UserQuestion) -> Self {
+        if !question.options.is_empty() {
+            UserInteraction::Select { prompt: question.prompt, options: question.options }
+        } else if question.yes_no {
+            UserInteraction::Confirm { prompt: question.prompt }
+        } else {
+            UserInteraction::Question { prompt: question.prompt }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn select() -> UserInteraction {
+        UserInteraction::Select {
+            prompt: "Which file did you mean?".to_string(),
+            options: vec!["src/main.rs".to_string(), "src/lib.rs".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_select_serialization() {
+        let actual = serde_json::to_value(select()).unwrap();
+        let expected = json!({
+            "select": {
+                "prompt": "Which file did you mean?",
+                "options": ["src/main.rs", "src/lib.rs"]
+            }
+        });
+        assert_eq!(actual, expected);
+
+        let answer = serde_json::to_value(UserAnswer::Selected(1)).unwrap();
+        assert_eq!(answer, json!({ "selected": 1 }));
+        assert_eq!(
+            serde_json::from_value::<UserAnswer>(answer).unwrap(),
+            UserAnswer::Selected(1)
+        );
+    }
+
+    #[test]
+    fn test_resolve_selection() {
+        let fixture = select();
+        assert_eq!(
+            fixture.resolve(UserAnswer::Selected(1)).unwrap(),
+            "src/lib.rs"
+        );
+        assert!(fixture.resolve(UserAnswer::Selected(2)).is_err());
+        assert!(fixture
+            .resolve(UserAnswer::Text("src/lib.rs".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate_yes_no() {
+        let fixture = UserInteraction::Confirm { prompt: "Overwrite the file?".to_string() };
+
+        let actual = fixture
+            .validate(&UserAnswer::Text("maybe".to_string()))
+            .unwrap_err();
+        assert!(matches!(actual, Error::InvalidAnswer(_)));
+        assert!(actual.to_string().contains("answer 'yes' or 'no'"));
+        assert!(fixture.validate(&UserAnswer::Selected(0)).is_err());
+
+        assert_eq!(
+            fixture
+                .resolve(UserAnswer::Text(" Y ".to_string()))
+                .unwrap(),
+            "yes"
+        );
+        assert_eq!(
+            fixture.resolve(UserAnswer::Text("no".to_string())).unwrap(),
+            "no"
+        );
+    }
+
+    #[test]
+    fn test_parse_tool_call() {
+        let fixture = ToolCallFull::new(UserQuestion::tool_name()).arguments(json!({
+            "prompt": "Which file did you mean?",
+            "options": ["src/main.rs", "src/lib.rs"]
+        }));
+        assert_eq!(UserQuestion::parse(&fixture), Some(select()));
+
+        let fixture = ToolCallFull::new(UserQuestion::tool_name())
+            .arguments(json!({ "prompt": "What should the module be called?" }));
+        let expected =
+            UserInteraction::Question { prompt: "What should the module be called?".to_string() };
+        assert_eq!(UserQuestion::parse(&fixture), Some(expected));
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use merge::Merge;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::merge::Key;
+
+/// The type a workflow variable is expected to have
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariableType {
+    #[default]
+    String,
+    Number,
+    Boolean,
+    Array,
+    Object,
+    /// Accepts any value
+    Any,
+}
+
+impl VariableType {
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            VariableType::String => value.is_string(),
+            VariableType::Number => value.is_number(),
+            VariableType::Boolean => value.is_boolean(),
+            VariableType::Array => value.is_array(),
+            VariableType::Object => value.is_object(),
+            VariableType::Any => true,
+        }
+    }
+
+    fn of(value: &Value) -> &'static str {
+        match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }
+    }
+}
+
+impl fmt::Display for VariableType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            VariableType::String => "string",
+            VariableType::Number => "number",
+            VariableType::Boolean => "boolean",
+            VariableType::Array => "array",
+            VariableType::Object => "object",
+            VariableType::Any => "any",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Declares a variable that a workflow expects to be provided
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Merge)]
+pub struct VariableDefinition {
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub name: String,
+
+    #[serde(rename = "type", default)]
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub kind: VariableType,
+
+    #[serde(default)]
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub required: bool,
+
+    /// Value used when the variable isn't provided
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub default: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[merge(strategy = crate::merge::std::overwrite)]
+    pub description: Option<String>,
+}
+
+impl Key for VariableDefinition {
+    type Id = String;
+
+    fn key(&self) -> &Self::Id {
+        &self.name
+    }
+}
+
+/// A single problem found while validating variables against their schema
+#[derive(Clone, Debug, PartialEq)]
+pub enum VariableIssue {
+    Missing(String),
+    Mistyped {
+        name: String,
+        expected: VariableType,
+        actual: &'static str,
+    },
+    Unknown(String),
+}
+
+impl fmt::Display for VariableIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariableIssue::Missing(name) => write!(f, "'{}' is required but missing", name),
+            VariableIssue::Mistyped { name, expected, actual } => {
+                write!(f, "'{}' should be a {} but is a {}", name, expected, actual)
+            }
+            VariableIssue::Unknown(name) => write!(f, "'{}' is not declared by the workflow", name),
+        }
+    }
+}
+
+/// Validates `variables` against `schema`, filling in defaults for anything
+/// that wasn't provided. Unknown variables are returned as warnings, unless
+/// `strict` is set in which case they are treated as errors.
+pub fn validate_variables(
+    schema: &[VariableDefinition],
+    mut variables: HashMap<String, Value>,
+    strict: bool,
+) -> crate::Result<(HashMap<String, Value>, Vec<VariableIssue>)> {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    for definition in schema {
+        match variables.get(&definition.name) {
+            Some(value) if !definition.kind.matches(value) => {
+                errors.push(VariableIssue::Mistyped {
+                    name: definition.name.clone(),
+                    expected: definition.kind,
+                    actual: VariableType::of(value),
+                });
+            }
+            Some(_) => {}
+            None => match &definition.default {
+                Some(default) => {
+                    variables.insert(definition.name.clone(), default.clone());
+                }
+                None if definition.required => {
+                    errors.push(VariableIssue::Missing(definition.name.clone()));
+                }
+                None => {}
+            },
+        }
+    }
+
+    // Without a schema every variable is "unknown", so only check when one
+    // has been declared
+    if !schema.is_empty() {
+        let mut unknown = variables
+            .keys()
+            .filter(|name| !schema.iter().any(|d| &d.name == *name))
+            .cloned()
+            .map(VariableIssue::Unknown)
+            .collect::<Vec<_>>();
+        unknown.sort_by_key(|issue| issue.to_string());
+
+        if strict {
+            errors.extend(unknown);
+        } else {
+            warnings.extend(unknown);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok((variables, warnings))
+    } else {
+        Err(crate::Error::InvalidVariables(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> Vec<VariableDefinition> {
+        vec![
+            VariableDefinition {
+                name: "task".to_string(),
+                kind: VariableType::String,
+                required: true,
+                default: None,
+                description: Some("What the agent should do".to_string()),
+            },
+            VariableDefinition {
+                name: "mode".to_string(),
+                kind: VariableType::String,
+                required: false,
+                default: Some(json!("ACT")),
+                description: None,
+            },
+        ]
+    }
+
+    fn variables(value: Value) -> HashMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_missing_required_variable() {
+        let actual = validate_variables(&schema(), variables(json!({"taks": "x"})), false)
+            .unwrap_err()
+            .to_string();
+        assert!(actual.contains("'task' is required but missing"));
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        let actual = validate_variables(&schema(), variables(json!({"task": 1})), false);
+
+        match actual {
+            Err(crate::Error::InvalidVariables(issues)) => assert_eq!(
+                issues,
+                vec![VariableIssue::Mistyped {
+                    name: "task".to_string(),
+                    expected: VariableType::String,
+                    actual: "number",
+                }]
+            ),
+            other => panic!("Expected invalid variables, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_default_is_applied() {
+        let (actual, warnings) =
+            validate_variables(&schema(), variables(json!({"task": "fix"})), false).unwrap();
+
+        let expected = variables(json!({"task": "fix", "mode": "ACT"}));
+        assert_eq!(actual, expected);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_variable_is_a_warning() {
+        let (_, actual) = validate_variables(
+            &schema(),
+            variables(json!({"task": "fix", "extra": 1})),
+            false,
+        )
+        .unwrap();
+
+        let expected = vec![VariableIssue::Unknown("extra".to_string())];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_unknown_variable_is_an_error_in_strict_mode() {
+        let actual = validate_variables(
+            &schema(),
+            variables(json!({"task": "fix", "extra": 1})),
+            true,
+        );
+
+        assert!(matches!(
+            actual,
+            Err(crate::Error::InvalidVariables(issues))
+                if issues == vec![VariableIssue::Unknown("extra".to_string())]
+        ));
+    }
+
+    #[test]
+    fn test_workflow_variables_are_resolved_once() {
+        let mut workflow = crate::Workflow {
+            variables: Some(variables(json!({"task": "fix", "extra": 1}))),
+            variable_schema: schema(),
+            ..Default::default()
+        };
+
+        let actual = [
+            workflow.resolve_variables().unwrap(),
+            workflow.resolve_variables().unwrap(),
+        ];
+
+        let expected = [vec![VariableIssue::Unknown("extra".to_string())], vec![]];
+        assert_eq!(actual, expected);
+        assert_eq!(
+            workflow.variables,
+            Some(variables(json!({"task": "fix", "extra": 1, "mode": "ACT"})))
+        );
+    }
+
+    #[test]
+    fn test_schema_serialization() {
+        let actual = serde_json::to_value(&schema()).unwrap();
+        let expected = json!([
+            {
+                "name": "task",
+                "type": "string",
+                "required": true,
+                "description": "What the agent should do"
+            },
+            {"name": "mode", "type": "string", "required": false, "default": "ACT"}
+        ]);
+        assert_eq!(actual, expected);
+
+        let round_trip: Vec<VariableDefinition> = serde_json::from_value(actual).unwrap();
+        assert_eq!(round_trip, schema());
+    }
+}
@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Name the verify commands are reported under while they run, they are
+/// executed by the shell tool
+pub const VERIFY_TOOL: &str = "tool_forge_verify";
+
+/// Tool the verify commands are run with
+pub const VERIFY_SHELL_TOOL: &str = "tool_forge_process_shell";
+
+/// How long a verify command may run when it doesn't set a timeout
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// How many times a turn is sent back to the agent when `max_cycles` is unset
+const DEFAULT_MAX_CYCLES: usize = 3;
+
+/// Lines of a failed command's output that are reported to the agent
+const OUTPUT_TAIL_LINES: usize = 40;
+
+/// Checks run once the agent finishes a turn that modified files. A failure
+/// is reported back to the agent, which carries on fixing it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerifyPolicy {
+    /// Commands run in order in the working directory, eg. `cargo test -q`
+    pub commands: Vec<VerifyCommand>,
+    /// Times a turn is sent back to the agent to fix failures before it ends
+    /// anyway, 3 when unset
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_cycles: Option<usize>,
+}
+
+impl VerifyPolicy {
+    pub fn max_cycles(&self) -> usize {
+        self.max_cycles.unwrap_or(DEFAULT_MAX_CYCLES)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerifyCommand {
+    pub command: String,
+    /// Seconds the command may run before it's stopped and counted as
+    /// failed, 300 when unset
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timeout: Option<u64>,
+}
+
+impl VerifyCommand {
+    pub fn new(command: impl ToString) -> Self {
+        Self { command: command.to_string(), timeout: None }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS))
+    }
+}
+
+/// A verify command that didn't succeed
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyFailure {
+    pub command: String,
+    /// `None` when the command timed out or its exit code is unknown
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// End of the command's output
+    pub output: String,
+}
+
+impl VerifyFailure {
+    /// A failure from the output of the shell tool, which ends with the exit
+    /// code of commands that failed
+    pub fn from_output(command: &str, output: &str) -> Self {
+        let exit_code_tag = Regex::new(r"\s*<exit_code>(-?\d+)</exit_code>\s*$").unwrap();
+        let exit_code = exit_code_tag
+            .captures(output)
+            .and_then(|captures| captures[1].parse().ok());
+        Self {
+            command: command.to_string(),
+            exit_code,
+            timed_out: false,
+            output: tail(&exit_code_tag.replace(output, "")),
+        }
+    }
+
+    pub fn timed_out(command: &VerifyCommand) -> Self {
+        Self {
+            command: command.command.clone(),
+            exit_code: None,
+            timed_out: true,
+            output: format!(
+                "The command didn't finish within {}s",
+                command.timeout().as_secs()
+            ),
+        }
+    }
+}
+
+fn tail(output: &str) -> String {
+    let lines = output.trim_end().lines().collect::<Vec<_>>();
+    lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..].join("\n")
+}
+
+/// Tag the report of failed verify commands starts with
+pub const VERIFY_FAILURE_TAG: &str = "<verification_failed>";
+
+/// The message that sends the failures back to the agent
+pub fn verify_failure_report(failures: &[VerifyFailure]) -> String {
+    let mut report = format!(
+        "{VERIFY_FAILURE_TAG}\nThe checks run after your changes failed. Fix the failures, then finish again.\n",
+    );
+    for failure in failures {
+        report.push_str("<check>\n");
+        report.push_str(&format!("<command>{}</command>\n", failure.command));
+        if let Some(exit_code) = failure.exit_code {
+            report.push_str(&format!("<exit_code>{exit_code}</exit_code>\n"));
+        }
+        if failure.timed_out {
+            report.push_str("<timed_out>true</timed_out>\n");
+        }
+        report.push_str(&format!("<output>\n{}\n</output>\n", failure.output));
+        report.push_str("</check>\n");
+    }
+    report.push_str("</verification_failed>");
+    report
+}
+
+/// Tag the note added once every verify command succeeded starts with
+pub const VERIFY_SUCCESS_TAG: &str = "<verification_passed>";
+
+/// The note added once every verify command succeeded
+pub fn verify_success_note(commands: &[VerifyCommand]) -> String {
+    let commands = commands
+        .iter()
+        .map(|command| format!("`{}`", command.command))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{VERIFY_SUCCESS_TAG}Checks run after your changes passed: {commands}</verification_passed>")
+}
+
+/// Whether the message is a verification note or report on its own, rather
+/// than a prompt a success note was folded into
+pub fn is_verify_note(content: &str) -> bool {
+    content.starts_with(VERIFY_FAILURE_TAG)
+        || (content.starts_with(VERIFY_SUCCESS_TAG) && content.ends_with("</verification_passed>"))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_failure_from_shell_output() {
+        let output = format!(
+            "<stdout>{}</stdout>\n<exit_code>101</exit_code>",
+            (1..=50).map(|n| format!("line {n}\n")).collect::<String>()
+        );
+
+        let actual = VerifyFailure::from_output("cargo test -q", &output);
+
+        assert_eq!(actual.exit_code, Some(101));
+        assert!(!actual.timed_out);
+        assert_eq!(actual.output.lines().count(), OUTPUT_TAIL_LINES);
+        assert!(actual.output.starts_with("line 12\n"));
+        assert!(actual.output.ends_with("line 50\n</stdout>"));
+    }
+
+    #[test]
+    fn test_failure_report() {
+        let fixture = [
+            VerifyFailure::from_output("cargo check", "error[E0308]\n<exit_code>101</exit_code>"),
+            VerifyFailure::timed_out(&VerifyCommand {
+                command: "cargo test -q".to_string(),
+                timeout: Some(60),
+            }),
+        ];
+
+        let actual = verify_failure_report(&fixture);
+
+        let expected = r#"<verification_failed>
+The checks run after your changes failed. Fix the failures, then finish again.
+<check>
+<command>cargo check</command>
+<exit_code>101</exit_code>
+<output>
+error[E0308]
+</output>
+</check>
+<check>
+<command>cargo test -q</command>
+<timed_out>true</timed_out>
+<output>
+The command didn't finish within 60s
+</output>
+</check>
+</verification_failed>"#;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_policy_deserializes() {
+        let fixture = serde_json::json!({
+            "commands": [
+                {"command": "cargo check"},
+                {"command": "cargo test -q", "timeout": 600}
+            ],
+            "max_cycles": 2
+        });
+
+        let actual: VerifyPolicy = serde_json::from_value(fixture).unwrap();
+
+        let expected = VerifyPolicy {
+            commands: vec![
+                VerifyCommand::new("cargo check"),
+                VerifyCommand { command: "cargo test -q".to_string(), timeout: Some(600) },
+            ],
+            max_cycles: Some(2),
+        };
+        assert_eq!(actual, expected);
+        assert_eq!(actual.commands[1].timeout(), Duration::from_secs(600));
+    }
+}
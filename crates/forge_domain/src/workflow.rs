@@ -4,13 +4,39 @@ use merge::Merge;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::{Agent, AgentId};
+use crate::{validate_variables, Agent, AgentId, VariableDefinition, VariableIssue, VerifyPolicy};
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, Merge)]
 pub struct Workflow {
     #[merge(strategy = crate::merge::vec::unify_by_key)]
     pub agents: Vec<Agent>,
     pub variables: Option<HashMap<String, Value>>,
+
+    /// Variables the workflow expects, used to validate and fill in defaults
+    /// for `variables` when a conversation is created
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[merge(strategy = crate::merge::vec::unify_by_key)]
+    pub variable_schema: Vec<VariableDefinition>,
+
+    /// Treat variables that aren't declared in `variable_schema` as errors
+    /// instead of warnings
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub strict_variables: Option<bool>,
+
+    /// Set once `variables` were validated and their defaults filled in
+    #[serde(skip)]
+    #[merge(skip)]
+    pub variables_resolved: bool,
+
+    /// Suggest tags and a title from the files modified once a conversation
+    /// completes, enabled by default
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub suggest_tags: Option<bool>,
+
+    /// Commands run once an agent finishes a turn that modified files, their
+    /// failures are sent back to the agent to fix
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub verify: Option<VerifyPolicy>,
 }
 
 impl Workflow {
@@ -21,6 +47,23 @@ impl Workflow {
             .find(|a| a.id == *id)
     }
 
+    /// Validates the workflow's variables against its schema and fills in
+    /// defaults. Returns the issues that were only worth a warning, which are
+    /// only reported the first time.
+    pub fn resolve_variables(&mut self) -> crate::Result<Vec<VariableIssue>> {
+        if self.variables_resolved {
+            return Ok(vec![]);
+        }
+        let (variables, warnings) = validate_variables(
+            &self.variable_schema,
+            self.variables.take().unwrap_or_default(),
+            self.strict_variables.unwrap_or_default(),
+        )?;
+        self.variables = Some(variables);
+        self.variables_resolved = true;
+        Ok(warnings)
+    }
+
     pub fn get_agent(&self, id: &AgentId) -> crate::Result<&Agent> {
         self.find_agent(id)
             .ok_or_else(|| crate::Error::AgentUndefined(id.clone()))
@@ -8,10 +8,13 @@
 //! the format "Failed to [operation] [path]", ensuring uniform error reporting
 //! throughout the application while preserving the original error cause.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 
+/// Size of the chunks [`ForgeFS::read_with_progress`] reads at once
+const READ_CHUNK_SIZE: usize = 1024 * 1024;
+
 pub struct ForgeFS;
 
 impl ForgeFS {
@@ -30,11 +33,145 @@ impl ForgeFS {
             .await
             .with_context(|| format!("Failed to read file {}", path.as_ref().display()))
     }
+    /// Reads the file in chunks, calling `progress` with the bytes read so far
+    /// and the size of the file after every chunk
+    pub async fn read_with_progress<T: AsRef<Path>>(
+        path: T,
+        mut progress: impl FnMut(u64, u64),
+    ) -> Result<Vec<u8>> {
+        use tokio::io::AsyncReadExt;
+
+        let path = path.as_ref();
+        let mut file = tokio::fs::File::open(path)
+            .await
+            .with_context(|| format!("Failed to read file {}", path.display()))?;
+        let total = file
+            .metadata()
+            .await
+            .with_context(|| format!("Failed to read file {}", path.display()))?
+            .len();
+
+        let mut content = Vec::with_capacity(total as usize);
+        let mut chunk = vec![0; READ_CHUNK_SIZE];
+        loop {
+            let read = file
+                .read(&mut chunk)
+                .await
+                .with_context(|| format!("Failed to read file {}", path.display()))?;
+            if read == 0 {
+                break;
+            }
+            content.extend_from_slice(&chunk[..read]);
+            progress(content.len() as u64, total);
+        }
+        Ok(content)
+    }
     pub async fn remove_file<T: AsRef<Path>>(path: T) -> Result<()> {
         tokio::fs::remove_file(path.as_ref())
             .await
             .with_context(|| format!("Failed to remove file {}", path.as_ref().display()))
     }
+    pub async fn remove_dir<T: AsRef<Path>>(path: T) -> Result<()> {
+        tokio::fs::remove_dir(path.as_ref())
+            .await
+            .with_context(|| format!("Failed to remove dir {}", path.as_ref().display()))
+    }
+    pub async fn remove_dir_all<T: AsRef<Path>>(path: T) -> Result<()> {
+        tokio::fs::remove_dir_all(path.as_ref())
+            .await
+            .with_context(|| format!("Failed to remove dir {}", path.as_ref().display()))
+    }
+    pub async fn copy<T: AsRef<Path>, U: AsRef<Path>>(from: T, to: U) -> Result<u64> {
+        tokio::fs::copy(from.as_ref(), to.as_ref())
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to copy {} to {}",
+                    from.as_ref().display(),
+                    to.as_ref().display()
+                )
+            })
+    }
+    /// Copies a directory and everything below it. Symlinks are recreated
+    /// rather than followed.
+    pub async fn copy_dir_all<T: AsRef<Path>, U: AsRef<Path>>(from: T, to: U) -> Result<()> {
+        let mut pending = vec![(from.as_ref().to_path_buf(), to.as_ref().to_path_buf())];
+        while let Some((from, to)) = pending.pop() {
+            Self::create_dir_all(&to).await?;
+            let mut entries = tokio::fs::read_dir(&from)
+                .await
+                .with_context(|| format!("Failed to read dir {}", from.display()))?;
+            while let Some(entry) = entries.next_entry().await? {
+                let target = to.join(entry.file_name());
+                let file_type = entry.file_type().await?;
+                if file_type.is_dir() {
+                    pending.push((entry.path(), target));
+                } else if file_type.is_symlink() {
+                    Self::copy_symlink(&entry.path(), &target).await?;
+                } else {
+                    Self::copy(entry.path(), target).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+    #[cfg(unix)]
+    async fn copy_symlink(from: &Path, to: &Path) -> Result<()> {
+        let link = tokio::fs::read_link(from)
+            .await
+            .with_context(|| format!("Failed to read link {}", from.display()))?;
+        tokio::fs::symlink(&link, to)
+            .await
+            .with_context(|| format!("Failed to create link {}", to.display()))
+    }
+    #[cfg(not(unix))]
+    async fn copy_symlink(from: &Path, to: &Path) -> Result<()> {
+        Self::copy(from, to).await.map(|_| ())
+    }
+    pub async fn rename<T: AsRef<Path>, U: AsRef<Path>>(from: T, to: U) -> Result<()> {
+        tokio::fs::rename(from.as_ref(), to.as_ref())
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to rename {} to {}",
+                    from.as_ref().display(),
+                    to.as_ref().display()
+                )
+            })
+    }
+    /// Whether a rename failed because source and destination are on
+    /// different devices, in which case the file has to be copied instead
+    pub fn is_cross_device(error: &std::io::Error) -> bool {
+        // EXDEV on unix, ERROR_NOT_SAME_DEVICE on windows
+        let code = if cfg!(windows) { 17 } else { 18 };
+        error.raw_os_error() == Some(code)
+    }
+    pub async fn is_empty_dir<T: AsRef<Path>>(path: T) -> Result<bool> {
+        let mut entries = tokio::fs::read_dir(path.as_ref())
+            .await
+            .with_context(|| format!("Failed to read dir {}", path.as_ref().display()))?;
+        Ok(entries.next_entry().await?.is_none())
+    }
+    /// Lists the files under a directory recursively, without following
+    /// symlinks
+    pub async fn files_recursive<T: AsRef<Path>>(path: T) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut pending = vec![path.as_ref().to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let mut entries = tokio::fs::read_dir(&dir)
+                .await
+                .with_context(|| format!("Failed to read dir {}", dir.display()))?;
+            while let Some(entry) = entries.next_entry().await? {
+                let file_type = entry.file_type().await?;
+                if file_type.is_dir() {
+                    pending.push(entry.path());
+                } else if file_type.is_file() {
+                    files.push(entry.path());
+                }
+            }
+        }
+        Ok(files)
+    }
     pub fn exists<T: AsRef<Path>>(path: T) -> bool {
         path.as_ref().exists()
     }
@@ -42,3 +179,60 @@ impl ForgeFS {
         path.as_ref().is_file()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_copy_dir_all_copies_nested_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        ForgeFS::create_dir_all(from.join("nested/deeper"))
+            .await
+            .unwrap();
+        ForgeFS::write(from.join("a.txt"), "a").await.unwrap();
+        ForgeFS::write(from.join("nested/deeper/b.txt"), "b")
+            .await
+            .unwrap();
+
+        let to = dir.path().join("to");
+        ForgeFS::copy_dir_all(&from, &to).await.unwrap();
+
+        let actual = [
+            ForgeFS::read(to.join("a.txt")).await.unwrap(),
+            ForgeFS::read(to.join("nested/deeper/b.txt")).await.unwrap(),
+        ];
+        assert_eq!(actual, [b"a".to_vec(), b"b".to_vec()]);
+    }
+    #[tokio::test]
+    async fn test_read_with_progress_reports_every_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("large.bin");
+        let fixture: Vec<u8> = (0..READ_CHUNK_SIZE * 2 + 10).map(|i| i as u8).collect();
+        ForgeFS::write(&path, &fixture).await.unwrap();
+
+        let mut reports = Vec::new();
+        let actual = ForgeFS::read_with_progress(&path, |read, total| reports.push((read, total)))
+            .await
+            .unwrap();
+
+        let total = fixture.len() as u64;
+        assert_eq!(actual, fixture);
+        assert!(reports.len() >= 3);
+        assert!(reports.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(reports.iter().all(|(_, reported)| *reported == total));
+        assert_eq!(reports.last(), Some(&(total, total)));
+    }
+
+    #[tokio::test]
+    async fn test_read_with_progress_of_a_missing_file_fails() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let actual = ForgeFS::read_with_progress(dir.path().join("missing"), |_, _| {}).await;
+
+        assert!(actual.is_err());
+    }
+}
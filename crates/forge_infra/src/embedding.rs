@@ -21,14 +21,18 @@ struct EmbeddingData {
 }
 
 pub struct OpenAIEmbeddingService {
-    client: reqwest::Client,
+    /// The error of an invalid network config, reported by every request
+    client: Result<reqwest::Client, String>,
     env: Environment,
 }
 
 impl OpenAIEmbeddingService {
     pub const EMBEDDING_MODEL: &str = "text-embedding-ada-002";
+    /// Requests go through the configured proxies, trusting the configured
+    /// certificates and with the configured timeouts
     pub fn new(env: Environment) -> Self {
-        let client = reqwest::Client::new();
+        let client = forge_app::http_client(&env.proxy, &env.tls, &env.http_timeout)
+            .map_err(|err| format!("{err:#}"));
         Self { client, env }
     }
 }
@@ -54,8 +58,11 @@ impl EmbeddingService for OpenAIEmbeddingService {
             input: sentence.to_string(),
         };
 
-        let response: EmbeddingResponse = self
+        let client = self
             .client
+            .as_ref()
+            .map_err(|err| anyhow::anyhow!("Invalid network config: {err}"))?;
+        let response: EmbeddingResponse = client
             .post("https://api.openai.com/v1/embeddings")
             .headers(headers)
             .json(&request)
@@ -1,10 +1,17 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use forge_app::EnvironmentService;
-use forge_domain::{Environment, Provider};
+use forge_domain::{
+    Credentials, Environment, FirstTokenTimeout, HttpTimeout, Provider, ProxyConfig, TlsConfig,
+    DEFAULT_QUESTION_TIMEOUT,
+};
 
 pub struct ForgeEnvironmentService {
     restricted: bool,
+    read_only: bool,
+    /// Returned as it is instead of reading the process' environment
+    fixed: Option<Environment>,
 }
 
 type ProviderSearch = (&'static str, Box<dyn FnOnce(&str) -> Provider>);
@@ -15,8 +22,20 @@ impl ForgeEnvironmentService {
     /// # Arguments
     /// * `unrestricted` - If true, use unrestricted shell mode (sh/bash) If
     ///   false, use restricted shell mode (rbash)
-    pub fn new(restricted: bool) -> Self {
-        Self { restricted }
+    /// * `read_only` - If true, leave out the tools that change files or run
+    ///   commands
+    pub fn new(restricted: bool, read_only: bool) -> Self {
+        Self { restricted, read_only, fixed: None }
+    }
+
+    /// Always returns the given environment, nothing is read from the process'
+    /// environment variables or working directory
+    pub fn fixed(environment: Environment) -> Self {
+        Self {
+            restricted: environment.restricted,
+            read_only: environment.read_only,
+            fixed: Some(environment),
+        }
     }
 
     /// Get path to appropriate shell based on platform and mode
@@ -32,11 +51,12 @@ impl ForgeEnvironmentService {
         }
     }
 
-    /// Resolves the provider key and provider from environment variables
+    /// Resolves the provider key and provider from the stored credentials and
+    /// environment variables, a stored key takes precedence
     ///
     /// Returns a tuple of (provider_key, provider)
     /// Panics if no API key is found in the environment
-    fn resolve_provider(&self) -> Provider {
+    fn resolve_provider(&self, credentials: &Credentials) -> Provider {
         let keys: [ProviderSearch; 4] = [
             ("FORGE_KEY", Box::new(Provider::antinomy)),
             ("OPENROUTER_API_KEY", Box::new(Provider::open_router)),
@@ -52,44 +72,102 @@ impl ForgeEnvironmentService {
 
         keys.into_iter()
             .find_map(|(key, fun)| {
-                std::env::var(key).ok().map(|key| {
-                    let mut provider = fun(&key);
+                credentials
+                    .get(key)
+                    .map(str::to_string)
+                    .or_else(|| std::env::var(key).ok())
+                    .map(|key| {
+                        let mut provider = fun(&key);
 
-                    if let Ok(url) = std::env::var("OPENAI_URL") {
-                        provider.open_ai_url(url);
-                    }
+                        if let Ok(url) = std::env::var("OPENAI_URL") {
+                            provider.open_ai_url(url);
+                        }
 
-                    provider
-                })
+                        provider
+                    })
             })
             .unwrap_or_else(|| panic!("No API key found. Please set one of: {}", env_variables))
     }
 
+    /// Reads the first token notice and abort thresholds, in seconds, from
+    /// FORGE_FIRST_TOKEN_NOTICE_SECS and FORGE_FIRST_TOKEN_TIMEOUT_SECS
+    fn first_token_timeout() -> FirstTokenTimeout {
+        let secs = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs)
+        };
+        let default = FirstTokenTimeout::default();
+
+        FirstTokenTimeout {
+            notice_after: secs("FORGE_FIRST_TOKEN_NOTICE_SECS").unwrap_or(default.notice_after),
+            abort_after: secs("FORGE_FIRST_TOKEN_TIMEOUT_SECS").unwrap_or(default.abort_after),
+        }
+    }
+
     fn get(&self) -> Environment {
         dotenv::dotenv().ok();
         let cwd = std::env::current_dir().unwrap_or(PathBuf::from("."));
-        let provider = self.resolve_provider();
+        let base_path = dirs::config_dir()
+            .map(|a| a.join("forge"))
+            .unwrap_or(PathBuf::from(".").join(".forge"));
+        let credentials = std::fs::read_to_string(base_path.join("credentials.env"))
+            .map(|content| Credentials::parse(&content))
+            .unwrap_or_default();
+        let provider = self.resolve_provider(&credentials);
 
         Environment {
             os: std::env::consts::OS.to_string(),
             pid: std::process::id(),
             cwd,
             shell: self.get_shell_path(),
-            base_path: dirs::config_dir()
-                .map(|a| a.join("forge"))
-                .unwrap_or(PathBuf::from(".").join(".forge")),
+            base_path,
             home: dirs::home_dir(),
 
             qdrant_key: std::env::var("QDRANT_KEY").ok(),
             qdrant_cluster: std::env::var("QDRANT_CLUSTER").ok(),
             openai_key: std::env::var("OPENAI_API_KEY").ok(),
             provider,
+            workspace_roots: std::env::var_os("FORGE_WORKSPACE_ROOTS")
+                .map(|roots| std::env::split_paths(&roots).collect())
+                .unwrap_or_default(),
+            restricted: self.restricted,
+            read_only: self.read_only,
+            first_token_timeout: Self::first_token_timeout(),
+            question_timeout: std::env::var("FORGE_QUESTION_TIMEOUT_SECS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_QUESTION_TIMEOUT),
+            line_ending: std::env::var("FORGE_LINE_ENDING")
+                .ok()
+                .and_then(|value| value.parse().ok()),
+            proxy: ProxyConfig::from_env(|key| std::env::var(key).ok()),
+            tls: TlsConfig::from_env(|key| std::env::var(key).ok()),
+            http_timeout: HttpTimeout::from_env(|key| std::env::var(key).ok()),
+            provider_preferences: std::env::var("FORGE_OPENROUTER_PROVIDER")
+                .ok()
+                .filter(|value| !value.trim().is_empty()),
+            prompt_cache: std::env::var("FORGE_PROMPT_CACHE")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            transformers: std::env::var("FORGE_TRANSFORMERS")
+                .ok()
+                .filter(|value| !value.trim().is_empty()),
+            scan_workers: std::env::var("FORGE_SCAN_WORKERS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|workers| *workers > 0),
         }
     }
 }
 
 impl EnvironmentService for ForgeEnvironmentService {
     fn get_environment(&self) -> Environment {
-        self.get()
+        match &self.fixed {
+            Some(environment) => environment.clone(),
+            None => self.get(),
+        }
     }
 }
@@ -0,0 +1,44 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use forge_app::{FsCopyService, FsSnapshotService};
+
+pub struct ForgeFileCopyService<S> {
+    snaps: Arc<S>,
+}
+
+impl<S> ForgeFileCopyService<S> {
+    pub fn new(snaps: Arc<S>) -> Self {
+        Self { snaps }
+    }
+}
+
+impl<S: FsSnapshotService> ForgeFileCopyService<S> {
+    async fn copy_file(&self, from: &Path, to: &Path) -> anyhow::Result<()> {
+        // Files that are about to be overwritten are snapshotted first
+        if forge_fs::ForgeFS::is_file(to) {
+            let _ = self.snaps.create_snapshot(to).await?;
+        }
+        if let Some(parent) = to.parent() {
+            forge_fs::ForgeFS::create_dir_all(parent).await?;
+        }
+        forge_fs::ForgeFS::copy(from, to).await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: FsSnapshotService> FsCopyService for ForgeFileCopyService<S> {
+    async fn copy(&self, from: &Path, to: &Path, recursive: bool) -> anyhow::Result<()> {
+        if !recursive || forge_fs::ForgeFS::is_file(from) {
+            return self.copy_file(from, to).await;
+        }
+
+        forge_fs::ForgeFS::create_dir_all(to).await?;
+        for file in forge_fs::ForgeFS::files_recursive(from).await? {
+            let relative = file.strip_prefix(from)?;
+            self.copy_file(&file, &to.join(relative)).await?;
+        }
+        Ok(())
+    }
+}
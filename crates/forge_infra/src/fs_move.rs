@@ -0,0 +1,58 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use forge_app::{FsMoveService, FsSnapshotService};
+
+pub struct ForgeFileMoveService<S> {
+    snaps: Arc<S>,
+}
+
+impl<S> ForgeFileMoveService<S> {
+    pub fn new(snaps: Arc<S>) -> Self {
+        Self { snaps }
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: FsSnapshotService> FsMoveService for ForgeFileMoveService<S> {
+    async fn rename(&self, from: &Path, to: &Path) -> anyhow::Result<()> {
+        if forge_fs::ForgeFS::is_file(from) {
+            let _ = self.snaps.create_snapshot(from).await?;
+        }
+
+        match tokio::fs::rename(from, to).await {
+            Ok(()) => {}
+            // Renaming only works within a device, a move to another one is a
+            // copy followed by removing the original
+            Err(e) if forge_fs::ForgeFS::is_cross_device(&e) => {
+                if forge_fs::ForgeFS::is_file(from) {
+                    forge_fs::ForgeFS::copy(from, to).await?;
+                    forge_fs::ForgeFS::remove_file(from).await?;
+                } else {
+                    forge_fs::ForgeFS::copy_dir_all(from, to).await?;
+                    forge_fs::ForgeFS::remove_dir_all(from).await?;
+                }
+            }
+            Err(e) => {
+                return Err(anyhow::Error::new(e).context(format!(
+                    "Failed to move {} to {}",
+                    from.display(),
+                    to.display()
+                )))
+            }
+        }
+
+        // Keep the history reachable under the new path, so that the snapshot
+        // taken above can be restored from there. The file is already moved by
+        // now, so failing here would only report a move that happened as failed.
+        if let Err(error) = self.snaps.rename_snapshots(from, to).await {
+            tracing::warn!(
+                error = ?error,
+                from = %from.display(),
+                to = %to.display(),
+                "Failed to move the snapshots"
+            );
+        }
+        Ok(())
+    }
+}
@@ -23,4 +23,14 @@ impl FsReadService for ForgeFileReadService {
     async fn read(&self, path: &Path) -> Result<Bytes> {
         Ok(forge_fs::ForgeFS::read(path).await.map(Bytes::from)?)
     }
+
+    async fn read_with_progress(
+        &self,
+        path: &Path,
+        progress: &(dyn Fn(u64, u64) + Send + Sync),
+    ) -> Result<Bytes> {
+        Ok(forge_fs::ForgeFS::read_with_progress(path, progress)
+            .await
+            .map(Bytes::from)?)
+    }
 }
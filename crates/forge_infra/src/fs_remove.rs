@@ -20,4 +20,22 @@ impl<S: FsSnapshotService> FileRemoveService for ForgeFileRemoveService<S> {
         let _ = self.snaps.create_snapshot(path).await?;
         Ok(forge_fs::ForgeFS::remove_file(path).await?)
     }
+
+    async fn remove_dir(&self, path: &Path, recursive: bool) -> anyhow::Result<()> {
+        if !recursive {
+            if !forge_fs::ForgeFS::is_empty_dir(path).await? {
+                anyhow::bail!(
+                    "Directory is not empty: {}, set recursive to true to remove it along with its contents",
+                    path.display()
+                );
+            }
+            return forge_fs::ForgeFS::remove_dir(path).await;
+        }
+
+        // Snapshot every file first so that the removal can be undone
+        for file in forge_fs::ForgeFS::files_recursive(path).await? {
+            let _ = self.snaps.create_snapshot(&file).await?;
+        }
+        forge_fs::ForgeFS::remove_dir_all(path).await
+    }
 }
@@ -3,7 +3,7 @@ use std::path::{Path, PathBuf};
 use anyhow::Result;
 use forge_app::FsSnapshotService;
 use forge_domain::Environment;
-use forge_snaps::{SnapshotInfo, SnapshotMetadata};
+use forge_snaps::{SnapshotInfo, SnapshotMetadata, SnapshotSelector};
 
 pub struct ForgeFileSnapshotService {
     inner: forge_snaps::SnapshotService,
@@ -49,6 +49,18 @@ impl FsSnapshotService for ForgeFileSnapshotService {
         self.inner.restore_by_index(file_path, 1).await
     }
 
+    async fn restore_snapshot(&self, file_path: &Path, selector: &SnapshotSelector) -> Result<()> {
+        self.inner.restore_snapshot(file_path, selector).await
+    }
+
+    async fn read_snapshot(
+        &self,
+        file_path: &Path,
+        selector: &SnapshotSelector,
+    ) -> Result<Vec<u8>> {
+        Ok(self.inner.get_snapshot(file_path, selector).await?.content)
+    }
+
     // Metadata access
     async fn get_snapshot_by_timestamp(
         &self,
@@ -71,4 +83,8 @@ impl FsSnapshotService for ForgeFileSnapshotService {
     async fn purge_older_than(&self, days: u32) -> Result<usize> {
         self.inner.purge_older_than(days).await
     }
+
+    async fn rename_snapshots(&self, from: &Path, to: &Path) -> Result<usize> {
+        self.inner.rename(from, to).await
+    }
 }
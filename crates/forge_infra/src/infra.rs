@@ -1,11 +1,14 @@
 use std::sync::Arc;
 
 use forge_app::{EnvironmentService, Infrastructure};
+use forge_domain::Environment;
 
 use crate::embedding::OpenAIEmbeddingService;
 use crate::env::ForgeEnvironmentService;
+use crate::fs_copy::ForgeFileCopyService;
 use crate::fs_create_dirs::ForgeCreateDirsService;
 use crate::fs_meta::ForgeFileMetaService;
+use crate::fs_move::ForgeFileMoveService;
 use crate::fs_read::ForgeFileReadService;
 use crate::fs_remove::ForgeFileRemoveService;
 use crate::fs_snap::ForgeFileSnapshotService;
@@ -17,16 +20,28 @@ pub struct ForgeInfra {
     file_write_service: ForgeFileWriteService<ForgeFileSnapshotService>,
     environment_service: ForgeEnvironmentService,
     information_repo: QdrantVectorIndex,
+    document_index: QdrantVectorIndex,
     embedding_service: OpenAIEmbeddingService,
     file_snapshot_service: Arc<ForgeFileSnapshotService>,
     file_meta_service: ForgeFileMetaService,
     file_remove_service: ForgeFileRemoveService<ForgeFileSnapshotService>,
+    file_move_service: ForgeFileMoveService<ForgeFileSnapshotService>,
+    file_copy_service: ForgeFileCopyService<ForgeFileSnapshotService>,
     create_dirs_service: ForgeCreateDirsService,
 }
 
 impl ForgeInfra {
-    pub fn new(restricted: bool) -> Self {
-        let environment_service = ForgeEnvironmentService::new(restricted);
+    pub fn new(restricted: bool, read_only: bool) -> Self {
+        Self::from_service(ForgeEnvironmentService::new(restricted, read_only))
+    }
+
+    /// Creates the infrastructure for a given environment instead of the
+    /// process' one, eg. to run forge in a sandbox directory
+    pub fn with_environment(environment: Environment) -> Self {
+        Self::from_service(ForgeEnvironmentService::fixed(environment))
+    }
+
+    fn from_service(environment_service: ForgeEnvironmentService) -> Self {
         let env = environment_service.get_environment();
         let file_snapshot_service = Arc::new(ForgeFileSnapshotService::new(env.clone()));
         Self {
@@ -34,8 +49,11 @@ impl ForgeInfra {
             file_write_service: ForgeFileWriteService::new(file_snapshot_service.clone()),
             file_meta_service: ForgeFileMetaService,
             file_remove_service: ForgeFileRemoveService::new(file_snapshot_service.clone()),
+            file_move_service: ForgeFileMoveService::new(file_snapshot_service.clone()),
+            file_copy_service: ForgeFileCopyService::new(file_snapshot_service.clone()),
             environment_service,
             information_repo: QdrantVectorIndex::new(env.clone(), "user_feedback"),
+            document_index: QdrantVectorIndex::new(env.clone(), "documents"),
             embedding_service: OpenAIEmbeddingService::new(env.clone()),
             file_snapshot_service,
             create_dirs_service: ForgeCreateDirsService,
@@ -48,10 +66,13 @@ impl Infrastructure for ForgeInfra {
     type FsReadService = ForgeFileReadService;
     type FsWriteService = ForgeFileWriteService<ForgeFileSnapshotService>;
     type VectorIndex = QdrantVectorIndex;
+    type DocumentIndex = QdrantVectorIndex;
     type EmbeddingService = OpenAIEmbeddingService;
     type FsMetaService = ForgeFileMetaService;
     type FsSnapshotService = ForgeFileSnapshotService;
     type FsRemoveService = ForgeFileRemoveService<ForgeFileSnapshotService>;
+    type FsMoveService = ForgeFileMoveService<ForgeFileSnapshotService>;
+    type FsCopyService = ForgeFileCopyService<ForgeFileSnapshotService>;
     type FsCreateDirsService = ForgeCreateDirsService;
 
     fn environment_service(&self) -> &Self::EnvironmentService {
@@ -66,6 +87,10 @@ impl Infrastructure for ForgeInfra {
         &self.information_repo
     }
 
+    fn document_index(&self) -> &Self::DocumentIndex {
+        &self.document_index
+    }
+
     fn embedding_service(&self) -> &Self::EmbeddingService {
         &self.embedding_service
     }
@@ -86,6 +111,14 @@ impl Infrastructure for ForgeInfra {
         &self.file_remove_service
     }
 
+    fn file_move_service(&self) -> &Self::FsMoveService {
+        &self.file_move_service
+    }
+
+    fn file_copy_service(&self) -> &Self::FsCopyService {
+        &self.file_copy_service
+    }
+
     fn create_dirs_service(&self) -> &Self::FsCreateDirsService {
         &self.create_dirs_service
     }
@@ -1,7 +1,9 @@
 mod embedding;
 mod env;
+mod fs_copy;
 mod fs_create_dirs;
 mod fs_meta;
+mod fs_move;
 mod fs_read;
 mod fs_remove;
 mod fs_snap;
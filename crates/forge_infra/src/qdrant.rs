@@ -2,8 +2,11 @@ use std::sync::Arc;
 
 use anyhow::{anyhow, Context};
 use forge_app::VectorIndex;
-use forge_domain::{Environment, Point, Query};
-use qdrant_client::qdrant::{PointStruct, SearchPointsBuilder, UpsertPointsBuilder};
+use forge_domain::{Environment, Point, PointId, Query};
+use qdrant_client::qdrant::{
+    DeletePointsBuilder, Filter, PointStruct, PointsIdsList, SearchPointsBuilder,
+    UpsertPointsBuilder,
+};
 use qdrant_client::{Payload, Qdrant};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -103,4 +106,45 @@ impl<T: Serialize + DeserializeOwned + Send + Sync + 'static> VectorIndex<T> for
             })
             .collect::<anyhow::Result<Vec<_>>>()
     }
+
+    async fn delete(&self, ids: Vec<PointId>) -> anyhow::Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let ids = ids
+            .into_iter()
+            .map(|id| id.into_uuid().to_string().into())
+            .collect();
+        self.client()
+            .await?
+            .delete_points(
+                DeletePointsBuilder::new(self.collection.clone())
+                    .points(PointsIdsList { ids })
+                    .wait(true),
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to delete points from collection: {}",
+                    self.collection
+                )
+            })?;
+
+        Ok(())
+    }
+
+    async fn clear(&self) -> anyhow::Result<()> {
+        // An empty filter matches every point, the collection itself is kept
+        self.client()
+            .await?
+            .delete_points(
+                DeletePointsBuilder::new(self.collection.clone())
+                    .points(Filter::default())
+                    .wait(true),
+            )
+            .await
+            .with_context(|| format!("Failed to clear collection: {}", self.collection))?;
+
+        Ok(())
+    }
 }
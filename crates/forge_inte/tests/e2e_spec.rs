@@ -0,0 +1,222 @@
+mod harness;
+
+use forge_api::{
+    ChatCompletionMessage, Content, Context, ContextMessage, ModelId, ToolName, API,
+    INTERRUPTED_TURN_NOTE,
+};
+use harness::*;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tokio_stream::StreamExt;
+
+const ENGINEER: &str = "software-engineer";
+
+/// The title agent's reply, dispatching the title as an event
+fn title(value: &str) -> Reply {
+    tool_call(
+        "tool_forge_event_dispatch",
+        "title-1",
+        json!({
+            "id": "title-1",
+            "name": "title",
+            "value": value,
+            "timestamp": "2025-01-01T00:00:00Z",
+        }),
+    )
+}
+
+#[tokio::test]
+async fn test_question_is_answered_and_titled() {
+    let harness = Harness::new();
+    harness.provider.reply(TITLE_MODEL, title("Rust edition"));
+    harness
+        .provider
+        .reply(ENGINEER_MODEL, text("The workspace uses the 2021 edition."));
+    let id = harness.start().await;
+
+    let turn = harness
+        .chat(&id, "user_task_init", "Which Rust edition is used?")
+        .await;
+
+    turn.assert_ok();
+    assert_eq!(turn.text(ENGINEER), "The workspace uses the 2021 edition.");
+    assert_eq!(
+        turn.events(),
+        vec![("title".to_string(), "Rust edition".to_string())]
+    );
+
+    let conversation = harness.conversation(&id).await;
+    assert_eq!(
+        transcript(&conversation, ENGINEER),
+        vec![
+            "User: Which Rust edition is used?",
+            "Assistant: The workspace uses the 2021 edition.",
+        ]
+    );
+    assert!(conversation
+        .events
+        .iter()
+        .any(|event| event.name == "title" && event.value == "Rust edition"));
+    assert_eq!(harness.provider.unused(), 0);
+}
+
+#[tokio::test]
+async fn test_file_is_read_then_patched() {
+    let harness = Harness::new();
+    harness.write("src/greeting.txt", "Hello, world!\n").await;
+    let path = harness.path("src/greeting.txt").display().to_string();
+    harness.provider.reply(TITLE_MODEL, title("Greeting"));
+    harness.provider.reply(
+        ENGINEER_MODEL,
+        tool_call("tool_forge_fs_read", "call-1", json!({ "path": path })),
+    );
+    harness.provider.reply(
+        ENGINEER_MODEL,
+        tool_call(
+            "tool_forge_fs_patch",
+            "call-2",
+            json!({
+                "path": path,
+                "patches": [{ "search": "Hello", "operation": "replace", "content": "Goodbye" }],
+            }),
+        ),
+    );
+    harness
+        .provider
+        .reply(ENGINEER_MODEL, text("Changed the greeting."));
+    let id = harness.start().await;
+
+    let turn = harness
+        .chat(&id, "user_task_init", "Say goodbye instead")
+        .await;
+
+    turn.assert_ok();
+    assert_eq!(harness.read("src/greeting.txt").await, "Goodbye, world!\n");
+    assert_eq!(
+        turn.tool_results(ENGINEER),
+        vec![
+            (ToolName::new("tool_forge_fs_read"), false),
+            (ToolName::new("tool_forge_fs_patch"), false),
+        ]
+    );
+
+    // The patch was written after the model saw the file
+    let requests = harness.provider.requests(ENGINEER_MODEL);
+    assert_eq!(requests.len(), 3);
+    assert!(requests[1].to_text().contains("Hello, world!"));
+
+    let conversation = harness.conversation(&id).await;
+    assert_eq!(
+        transcript(&conversation, ENGINEER),
+        vec![
+            "User: Say goodbye instead",
+            "Assistant: [tool_forge_fs_read]",
+            "Tool: tool_forge_fs_read",
+            "Assistant: [tool_forge_fs_patch]",
+            "Tool: tool_forge_fs_patch",
+            "Assistant: Changed the greeting.",
+        ]
+    );
+    assert_eq!(conversation.suggestions.as_ref(), turn.suggestions());
+    assert!(turn.suggestions().is_some());
+}
+
+#[tokio::test]
+async fn test_turn_failing_mid_stream_is_restored() {
+    let harness = Harness::new();
+    harness
+        .write("notes.md", "The cat is called Juniper\n")
+        .await;
+    let path = harness.path("notes.md").display().to_string();
+    harness.provider.reply(TITLE_MODEL, title("Cat name"));
+    harness.provider.reply(
+        ENGINEER_MODEL,
+        tool_call("tool_forge_fs_read", "call-1", json!({ "path": path })),
+    );
+    harness.provider.reply(
+        ENGINEER_MODEL,
+        vec![
+            Ok(ChatCompletionMessage::assistant(Content::part("The cat "))),
+            Err(anyhow::anyhow!("connection reset by peer")),
+        ],
+    );
+    let id = harness.start().await;
+
+    let turn = harness
+        .chat(&id, "user_task_init", "What is the cat called?")
+        .await;
+
+    assert_eq!(turn.text(ENGINEER), "The cat ");
+    assert_eq!(turn.errors.len(), 1);
+    assert!(turn.errors[0].contains("connection reset by peer"));
+
+    // Only the turn that failed is left to restore, up to its completed step
+    let journals = harness.api.interrupted().await.unwrap();
+    let agents = journals
+        .iter()
+        .map(|journal| journal.agent.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(agents, vec![ENGINEER]);
+    let restored = harness
+        .api
+        .restore(harness.workflow(), &journals)
+        .await
+        .unwrap();
+    assert!(harness.api.interrupted().await.unwrap().is_empty());
+
+    harness
+        .provider
+        .reply(ENGINEER_MODEL, text("The cat is called Juniper."));
+    let turn = harness
+        .chat(&restored, "user_task_update", "Carry on")
+        .await;
+
+    turn.assert_ok();
+    let conversation = harness.conversation(&restored).await;
+    assert_eq!(
+        transcript(&conversation, ENGINEER),
+        vec![
+            "User: What is the cat called?".to_string(),
+            "Assistant: [tool_forge_fs_read]".to_string(),
+            "Tool: tool_forge_fs_read".to_string(),
+            format!("User: {INTERRUPTED_TURN_NOTE}"),
+            "User: Carry on".to_string(),
+            "Assistant: The cat is called Juniper.".to_string(),
+        ]
+    );
+    assert_eq!(harness.provider.unused(), 0);
+}
+
+#[tokio::test]
+async fn test_raw_chat_streams_the_reply_without_running_tools() {
+    let harness = Harness::new();
+    let path = harness.path("created.txt").display().to_string();
+    let reply = || {
+        let mut reply = text("Creating the file ");
+        reply.extend(tool_call(
+            "tool_forge_fs_create",
+            "call-1",
+            json!({ "path": path, "content": "hello" }),
+        ));
+        reply
+    };
+    harness.provider.reply(ENGINEER_MODEL, reply());
+    let context = Context::default()
+        .system("Solve the task")
+        .add_message(ContextMessage::user("Create a file"));
+
+    let actual = harness
+        .api
+        .raw_chat(&ModelId::new(ENGINEER_MODEL), context.clone())
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+
+    let expected = reply().into_iter().map(Result::unwrap).collect::<Vec<_>>();
+    assert_eq!(actual, expected);
+    assert_eq!(harness.provider.requests(ENGINEER_MODEL), vec![context]);
+    assert!(!harness.path("created.txt").exists());
+    assert!(harness.api.interrupted().await.unwrap().is_empty());
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use forge_api::App;
+use forge_app::{ForgeApp, Infrastructure};
+use forge_infra::ForgeInfra;
+
+use super::provider::ScriptedProvider;
+
+type Inner = ForgeApp<ForgeInfra>;
+
+/// The application as it's shipped, with every service real except the
+/// provider, which replays scripted replies
+pub struct ScriptedApp {
+    app: Inner,
+    provider: Arc<ScriptedProvider>,
+}
+
+impl ScriptedApp {
+    pub fn new(infra: Arc<ForgeInfra>, provider: Arc<ScriptedProvider>) -> Self {
+        Self { app: ForgeApp::new(infra).unwrap(), provider }
+    }
+}
+
+impl App for ScriptedApp {
+    type ToolService = <Inner as App>::ToolService;
+    type ProviderService = ScriptedProvider;
+    type ConversationService = <Inner as App>::ConversationService;
+    type TemplateService = <Inner as App>::TemplateService;
+    type AttachmentService = <Inner as App>::AttachmentService;
+    type JournalService = <Inner as App>::JournalService;
+    type ApprovalService = <Inner as App>::ApprovalService;
+    type InteractionService = <Inner as App>::InteractionService;
+    type ScratchService = <Inner as App>::ScratchService;
+    type ProtectionService = <Inner as App>::ProtectionService;
+
+    fn tool_service(&self) -> &Self::ToolService {
+        self.app.tool_service()
+    }
+
+    fn provider_service(&self) -> &Self::ProviderService {
+        &self.provider
+    }
+
+    fn conversation_service(&self) -> &Self::ConversationService {
+        self.app.conversation_service()
+    }
+
+    fn template_service(&self) -> &Self::TemplateService {
+        self.app.template_service()
+    }
+
+    fn attachment_service(&self) -> &Self::AttachmentService {
+        self.app.attachment_service()
+    }
+
+    fn journal_service(&self) -> &Self::JournalService {
+        self.app.journal_service()
+    }
+
+    fn approval_service(&self) -> &Self::ApprovalService {
+        self.app.approval_service()
+    }
+
+    fn interaction_service(&self) -> &Self::InteractionService {
+        self.app.interaction_service()
+    }
+
+    fn scratch_service(&self) -> &Self::ScratchService {
+        self.app.scratch_service()
+    }
+
+    fn protection_service(&self) -> &Self::ProtectionService {
+        self.app.protection_service()
+    }
+}
+
+impl Infrastructure for ScriptedApp {
+    type EnvironmentService = <Inner as Infrastructure>::EnvironmentService;
+    type FsReadService = <Inner as Infrastructure>::FsReadService;
+    type FsWriteService = <Inner as Infrastructure>::FsWriteService;
+    type VectorIndex = <Inner as Infrastructure>::VectorIndex;
+    type DocumentIndex = <Inner as Infrastructure>::DocumentIndex;
+    type EmbeddingService = <Inner as Infrastructure>::EmbeddingService;
+    type FsMetaService = <Inner as Infrastructure>::FsMetaService;
+    type FsSnapshotService = <Inner as Infrastructure>::FsSnapshotService;
+    type FsRemoveService = <Inner as Infrastructure>::FsRemoveService;
+    type FsMoveService = <Inner as Infrastructure>::FsMoveService;
+    type FsCopyService = <Inner as Infrastructure>::FsCopyService;
+    type FsCreateDirsService = <Inner as Infrastructure>::FsCreateDirsService;
+
+    fn environment_service(&self) -> &Self::EnvironmentService {
+        self.app.environment_service()
+    }
+
+    fn file_read_service(&self) -> &Self::FsReadService {
+        self.app.file_read_service()
+    }
+
+    fn file_write_service(&self) -> &Self::FsWriteService {
+        self.app.file_write_service()
+    }
+
+    fn vector_index(&self) -> &Self::VectorIndex {
+        self.app.vector_index()
+    }
+
+    fn document_index(&self) -> &Self::DocumentIndex {
+        self.app.document_index()
+    }
+
+    fn embedding_service(&self) -> &Self::EmbeddingService {
+        self.app.embedding_service()
+    }
+
+    fn file_meta_service(&self) -> &Self::FsMetaService {
+        self.app.file_meta_service()
+    }
+
+    fn file_snapshot_service(&self) -> &Self::FsSnapshotService {
+        self.app.file_snapshot_service()
+    }
+
+    fn file_remove_service(&self) -> &Self::FsRemoveService {
+        self.app.file_remove_service()
+    }
+
+    fn file_move_service(&self) -> &Self::FsMoveService {
+        self.app.file_move_service()
+    }
+
+    fn file_copy_service(&self) -> &Self::FsCopyService {
+        self.app.file_copy_service()
+    }
+
+    fn create_dirs_service(&self) -> &Self::FsCreateDirsService {
+        self.app.create_dirs_service()
+    }
+}
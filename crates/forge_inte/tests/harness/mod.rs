@@ -0,0 +1,262 @@
+//! Runs forge end to end against a scripted model, inside a temporary
+//! directory and without any network access
+
+mod app;
+mod provider;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use forge_api::{
+    AgentId, AgentMessage, ChatRequest, ChatResponse, ContextMessage, Conversation, ConversationId,
+    ConversationSuggestions, Environment, Event, ForgeAPI, Provider, Role, ToolName, Workflow, API,
+};
+use forge_infra::ForgeInfra;
+pub use provider::*;
+use tempfile::TempDir;
+use tokio_stream::StreamExt;
+
+use self::app::ScriptedApp;
+
+/// Model of the agent generating titles
+pub const TITLE_MODEL: &str = "scripted/title";
+/// Model of the agent working on the task
+pub const ENGINEER_MODEL: &str = "scripted/engineer";
+
+/// A trimmed down version of the default workflow: an agent that dispatches a
+/// title for each task and one that works on it
+const WORKFLOW: &str = r#"
+agents:
+  - id: title_generation_worker
+    model: scripted/title
+    tool_supported: true
+    tools:
+      - tool_forge_event_dispatch
+    subscribe:
+      - user_task_init
+    system_prompt: Generate a title for the task
+    user_prompt: "{{event.value}}"
+
+  - id: software-engineer
+    model: scripted/engineer
+    tool_supported: true
+    tools:
+      - tool_forge_fs_read
+      - tool_forge_fs_create
+      - tool_forge_fs_patch
+    subscribe:
+      - user_task_init
+      - user_task_update
+    ephemeral: false
+    system_prompt: Solve the task
+    user_prompt: "{{event.value}}"
+"#;
+
+pub struct Harness {
+    pub api: ForgeAPI<ScriptedApp>,
+    pub provider: Arc<ScriptedProvider>,
+    sandbox: TempDir,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    /// Starts forge in a new sandbox, with an empty project as the working
+    /// directory and the stored data next to it
+    pub fn new() -> Self {
+        let sandbox = TempDir::new().unwrap();
+        let cwd = sandbox.path().join("project");
+        std::fs::create_dir_all(&cwd).unwrap();
+
+        let provider = Arc::new(ScriptedProvider::default());
+        let infra = Arc::new(ForgeInfra::with_environment(environment(
+            cwd,
+            sandbox.path().join("forge"),
+        )));
+        let app = Arc::new(ScriptedApp::new(infra, provider.clone()));
+
+        Self { api: ForgeAPI::new(app), provider, sandbox }
+    }
+
+    pub fn workflow(&self) -> Workflow {
+        serde_yaml::from_str(WORKFLOW).unwrap()
+    }
+
+    /// Absolute path of a file of the project
+    pub fn path(&self, relative: &str) -> PathBuf {
+        self.sandbox.path().join("project").join(relative)
+    }
+
+    pub async fn write(&self, relative: &str, content: &str) {
+        let path = self.path(relative);
+        tokio::fs::create_dir_all(path.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(path, content).await.unwrap();
+    }
+
+    pub async fn read(&self, relative: &str) -> String {
+        tokio::fs::read_to_string(self.path(relative))
+            .await
+            .unwrap()
+    }
+
+    pub async fn start(&self) -> ConversationId {
+        self.api.init(self.workflow()).await.unwrap()
+    }
+
+    /// Dispatches the event and waits for the turn to end
+    pub async fn chat(&self, id: &ConversationId, event: &str, value: &str) -> Turn {
+        let request = ChatRequest::new(Event::new(event, value), id.clone());
+        let mut stream = self.api.chat(request).await.unwrap();
+        let mut turn = Turn::default();
+        while let Some(message) = stream.next().await {
+            match message {
+                Ok(message) => turn.responses.push(message),
+                Err(err) => turn.errors.push(format!("{err:#}")),
+            }
+        }
+        turn
+    }
+
+    pub async fn conversation(&self, id: &ConversationId) -> Conversation {
+        self.api.conversation(id).await.unwrap().unwrap()
+    }
+}
+
+/// Environment of forge running in the sandbox, without any of the variables
+/// of the process running the tests
+fn environment(cwd: PathBuf, base_path: PathBuf) -> Environment {
+    Environment {
+        os: std::env::consts::OS.to_string(),
+        pid: std::process::id(),
+        cwd,
+        home: None,
+        shell: "/bin/sh".to_string(),
+        qdrant_key: None,
+        qdrant_cluster: None,
+        base_path,
+        provider: Provider::open_router("scripted"),
+        openai_key: None,
+        workspace_roots: vec![],
+        restricted: false,
+        read_only: false,
+        first_token_timeout: Default::default(),
+        question_timeout: Duration::from_secs(1),
+        line_ending: None,
+        proxy: Default::default(),
+        tls: Default::default(),
+        http_timeout: Default::default(),
+        provider_preferences: None,
+        prompt_cache: false,
+        transformers: None,
+        scan_workers: Some(1),
+    }
+}
+
+/// Everything a turn sent back, in the order it was received
+#[derive(Default)]
+pub struct Turn {
+    pub responses: Vec<AgentMessage<ChatResponse>>,
+    /// Errors that ended the stream
+    pub errors: Vec<String>,
+}
+
+impl Turn {
+    /// Fails with every error of the turn, if there were any
+    pub fn assert_ok(&self) {
+        assert!(self.errors.is_empty(), "turn failed: {:?}", self.errors);
+        let errors = self
+            .responses
+            .iter()
+            .filter(|response| matches!(response.message, ChatResponse::Error { .. }))
+            .map(|response| format!("{:?}", response.message))
+            .collect::<Vec<_>>();
+        assert!(errors.is_empty(), "turn reported errors: {errors:?}");
+    }
+
+    /// The text the agent streamed, joined together
+    pub fn text(&self, agent: &str) -> String {
+        self.of(agent)
+            .filter_map(|message| match message {
+                ChatResponse::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Tools the agent called, and whether each call failed
+    pub fn tool_results(&self, agent: &str) -> Vec<(ToolName, bool)> {
+        self.of(agent)
+            .filter_map(|message| match message {
+                ChatResponse::ToolCallEnd(result) => Some((result.name.clone(), result.is_error)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Names and values of the events the agents dispatched
+    pub fn events(&self) -> Vec<(String, String)> {
+        self.responses
+            .iter()
+            .filter_map(|response| match &response.message {
+                ChatResponse::Custom(event) => Some((event.name.clone(), event.value.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn suggestions(&self) -> Option<&ConversationSuggestions> {
+        self.responses
+            .iter()
+            .find_map(|response| match &response.message {
+                ChatResponse::Suggestions(suggestions) => Some(suggestions),
+                _ => None,
+            })
+    }
+
+    fn of<'a>(&'a self, agent: &'a str) -> impl Iterator<Item = &'a ChatResponse> {
+        self.responses
+            .iter()
+            .filter(move |response| response.agent.as_str() == agent)
+            .map(|response| &response.message)
+    }
+}
+
+/// The stored context of the agent as one line per message, without the
+/// system prompt. Tool outputs are left out, only the tool is named.
+pub fn transcript(conversation: &Conversation, agent: &str) -> Vec<String> {
+    let Some(context) = conversation.context(&AgentId::new(agent)) else {
+        return vec![];
+    };
+
+    context
+        .messages
+        .iter()
+        .filter_map(|message| match message {
+            ContextMessage::ContentMessage(message) if message.role == Role::System => None,
+            ContextMessage::ContentMessage(message) => {
+                let line = Some(message.content.clone())
+                    .filter(|content| !content.is_empty())
+                    .into_iter()
+                    .chain(
+                        message
+                            .tool_calls
+                            .iter()
+                            .flatten()
+                            .map(|call| format!("[{}]", call.name.as_str())),
+                    )
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                Some(format!("{:?}: {line}", message.role))
+            }
+            ContextMessage::ToolMessage(result) => Some(format!("Tool: {}", result.name.as_str())),
+            ContextMessage::Image(_) => Some("Image".to_string()),
+        })
+        .collect()
+}
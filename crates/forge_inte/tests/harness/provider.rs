@@ -0,0 +1,93 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use forge_api::{
+    ChatCompletionMessage, Content, Context, Model, ModelId, ProviderService, ResultStream,
+    ToolCallFull, ToolCallId, ToolName,
+};
+use serde_json::Value;
+
+/// A scripted response, streamed one chunk at a time. An `Err` chunk fails the
+/// stream at that point, like a dropped connection.
+pub type Reply = Vec<anyhow::Result<ChatCompletionMessage>>;
+
+/// Replays scripted replies instead of calling a model. Replies are queued per
+/// model so that agents running side by side each get theirs, in order.
+#[derive(Default)]
+pub struct ScriptedProvider {
+    replies: Mutex<HashMap<ModelId, VecDeque<Reply>>>,
+    requests: Mutex<Vec<(ModelId, Context)>>,
+}
+
+impl ScriptedProvider {
+    /// Queues the next reply of the model
+    pub fn reply(&self, model: &str, reply: Reply) {
+        self.replies
+            .lock()
+            .unwrap()
+            .entry(ModelId::new(model))
+            .or_default()
+            .push_back(reply);
+    }
+
+    /// Contexts the model was sent, oldest first
+    pub fn requests(&self, model: &str) -> Vec<Context> {
+        let model = ModelId::new(model);
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, _)| *id == model)
+            .map(|(_, context)| context.clone())
+            .collect()
+    }
+
+    /// Replies that were scripted but never requested
+    pub fn unused(&self) -> usize {
+        self.replies
+            .lock()
+            .unwrap()
+            .values()
+            .map(VecDeque::len)
+            .sum()
+    }
+}
+
+#[async_trait::async_trait]
+impl ProviderService for ScriptedProvider {
+    async fn chat(
+        &self,
+        id: &ModelId,
+        context: Context,
+    ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
+        self.requests.lock().unwrap().push((id.clone(), context));
+        let reply = self
+            .replies
+            .lock()
+            .unwrap()
+            .get_mut(id)
+            .and_then(VecDeque::pop_front)
+            .ok_or_else(|| anyhow::anyhow!("No reply scripted for model '{id}'"))?;
+        Ok(Box::pin(futures::stream::iter(reply)))
+    }
+
+    async fn models(&self) -> anyhow::Result<Vec<Model>> {
+        Ok(vec![])
+    }
+}
+
+/// A reply with the given text, streamed in one chunk per word
+pub fn text(content: &str) -> Reply {
+    content
+        .split_inclusive(' ')
+        .map(|word| Ok(ChatCompletionMessage::assistant(Content::part(word))))
+        .collect()
+}
+
+/// A reply calling a single tool
+pub fn tool_call(name: &str, id: &str, arguments: Value) -> Reply {
+    let call = ToolCallFull::new(ToolName::new(name))
+        .call_id(ToolCallId::new(id))
+        .arguments(arguments);
+    vec![Ok(ChatCompletionMessage::default().add_tool_call(call))]
+}
@@ -18,6 +18,12 @@ fn main() {
     // Make version available to the application
     println!("cargo:rustc-env=CARGO_PKG_NAME=forge");
 
+    // Target triple, used to pick the release binary when upgrading
+    println!(
+        "cargo:rustc-env=FORGE_TARGET={}",
+        std::env::var("TARGET").unwrap()
+    );
+
     // Ensure rebuild when environment changes
     println!("cargo:rerun-if-env-changed=APP_VERSION");
 }
@@ -0,0 +1,526 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use forge_api::{ProviderToolCall, ToolCallFull, ToolCallId, ToolResult};
+use forge_display::{format_duration, PathFormat};
+use serde_json::Value;
+
+/// Longest shell command shown on an activity line
+const MAX_COMMAND_WIDTH: usize = 60;
+
+/// How a turn is shown while it runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    /// The agent's text is streamed and tools print what they do
+    #[default]
+    Full,
+    /// One line per tool call, the agent's text is held back until the turn
+    /// ends
+    Compact,
+}
+
+/// A tool call that hasn't finished yet
+#[derive(Debug, Clone, PartialEq)]
+struct InFlight {
+    call_id: Option<ToolCallId>,
+    name: String,
+    subject: Option<String>,
+    started: Instant,
+    /// File the call writes, see [`ToolCallFull::written_path`]
+    written: Option<PathBuf>,
+    /// The agent's text that led to the call, shown if the turn fails while
+    /// the call runs
+    text: String,
+}
+
+/// Bookkeeping of the compact display. The terminal shows one line per
+/// finished tool call, and the call in flight on a line updated in place.
+/// The text the agent streams is buffered from its last tool call on, and
+/// shown when the turn ends. When it fails, the text that led to the calls
+/// still in flight is shown too.
+#[derive(Debug, Clone, Default)]
+pub struct Activity {
+    mode: DisplayMode,
+    paths: PathFormat,
+    text: String,
+    in_flight: Vec<InFlight>,
+}
+
+impl Activity {
+    pub fn new(mode: DisplayMode, paths: PathFormat) -> Self {
+        Self { mode, paths, ..Default::default() }
+    }
+
+    pub fn mode(&self) -> DisplayMode {
+        self.mode
+    }
+
+    pub fn is_compact(&self) -> bool {
+        self.mode == DisplayMode::Compact
+    }
+
+    /// Switches the display, returns the buffered text when leaving the
+    /// compact display so it isn't lost
+    pub fn set_mode(&mut self, mode: DisplayMode) -> Option<String> {
+        self.mode = mode;
+        match mode {
+            DisplayMode::Full => {
+                self.in_flight.clear();
+                self.take_text()
+            }
+            DisplayMode::Compact => None,
+        }
+    }
+
+    /// Holds back a chunk of the agent's text
+    pub fn buffer(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    /// Records the start of a tool call, returns the line shown in place
+    /// while it runs. The text the agent wrote before the call is only shown
+    /// if the turn fails before the call ends.
+    pub fn start(&mut self, call: &ToolCallFull, now: Instant) -> String {
+        let in_flight = InFlight {
+            call_id: call.call_id.clone(),
+            name: call.name.as_str().to_string(),
+            subject: self.subject(call),
+            started: now,
+            written: call.written_path(),
+            text: std::mem::take(&mut self.text),
+        };
+        let line = running_line(&in_flight);
+        self.in_flight.push(in_flight);
+        line
+    }
+
+    /// Records the end of a tool call, returns its finished line along with
+    /// the line of the call that's still in flight, if any. `after` is the
+    /// content of the written file once the call ended, compared with the one
+    /// the result kept from before.
+    pub fn end(
+        &mut self,
+        result: &ToolResult,
+        after: Option<String>,
+        now: Instant,
+    ) -> (String, Option<String>) {
+        let line = match self
+            .find(result)
+            .map(|position| self.in_flight.remove(position))
+        {
+            Some(call) => {
+                let (_, done) = verbs(&call.name);
+                let mut details = Vec::new();
+                if let (Some(before), Some(after)) = (&result.before, &after) {
+                    let (added, removed) = changed_lines(before, after);
+                    details.push(format!("+{added}/-{removed}"));
+                }
+                details.push(format_duration(now.saturating_duration_since(call.started)));
+                format!(
+                    "{} {} ({})",
+                    mark(result),
+                    label(done, call.subject.as_deref()),
+                    details.join(", ")
+                )
+            }
+            // The call started before the compact display was turned on
+            None => {
+                let (_, done) = verbs(result.name.as_str());
+                format!("{} {}", mark(result), label(done, None))
+            }
+        };
+
+        (line, self.in_flight.last().map(running_line))
+    }
+
+    /// The file written by the call that ended with `result`, its content is
+    /// read again to count the changed lines
+    pub fn written_by(&self, result: &ToolResult) -> Option<&Path> {
+        self.find(result)
+            .and_then(|position| self.in_flight[position].written.as_deref())
+    }
+
+    fn find(&self, result: &ToolResult) -> Option<usize> {
+        self.in_flight
+            .iter()
+            .position(|call| result.call_id.is_some() && call.call_id == result.call_id)
+            .or_else(|| {
+                self.in_flight
+                    .iter()
+                    .position(|call| call.name == result.name.as_str())
+            })
+    }
+
+    /// Ends the turn, returns the text held back since the last tool call
+    pub fn finish(&mut self) -> Option<String> {
+        self.in_flight.clear();
+        self.take_text()
+    }
+
+    /// Ends a turn that failed, returns the text held back since the last
+    /// tool call along with the text that led to the calls cut short
+    pub fn fail(&mut self) -> Option<String> {
+        let mut text = self
+            .in_flight
+            .drain(..)
+            .map(|call| call.text)
+            .collect::<String>();
+        text.push_str(&self.text);
+        self.text = text;
+        self.take_text()
+    }
+
+    /// Whether a line is currently shown in place
+    pub fn has_in_flight(&self) -> bool {
+        !self.in_flight.is_empty()
+    }
+
+    fn take_text(&mut self) -> Option<String> {
+        let text = std::mem::take(&mut self.text);
+        (!text.trim().is_empty()).then_some(text)
+    }
+
+    /// What the tool call acts on, paths are shown relative to the working
+    /// directory or the workspace root they're in
+    fn subject(&self, call: &ToolCallFull) -> Option<String> {
+        let arg = |key: &str| call.arguments.get(key).and_then(Value::as_str);
+        let path = |key: &str| arg(key).map(|path| self.paths.format(path));
+        match call.name.as_str() {
+            "tool_forge_process_shell" | "tool_forge_verify" => {
+                arg("command").map(shorten_command)
+            }
+            "tool_forge_net_fetch" => arg("url").map(str::to_string),
+            "tool_forge_symbol_search" => arg("name").map(str::to_string),
+            "tool_forge_git_commit" => arg("message").map(shorten_command),
+            "tool_forge_fs_move" | "tool_forge_fs_copy" => {
+                Some(format!("{} to {}", path("source")?, path("destination")?))
+            }
+            _ => path("path"),
+        }
+    }
+}
+
+/// The verbs of a tool's activity, while it runs and once it's done
+fn verbs(name: &str) -> (&str, &str) {
+    match name {
+        "tool_forge_fs_read" => ("reading", "read"),
+        "tool_forge_fs_create" => ("writing", "wrote"),
+        "tool_forge_fs_patch" | "tool_forge_fs_replace" => ("editing", "edited"),
+        "tool_forge_fs_remove" => ("removing", "removed"),
+        "tool_forge_fs_move" => ("moving", "moved"),
+        "tool_forge_fs_copy" => ("copying", "copied"),
+        "tool_forge_fs_search" => ("searching", "searched"),
+        "tool_forge_fs_list" => ("listing", "listed"),
+        "tool_forge_fs_info" => ("inspecting", "inspected"),
+        "tool_forge_process_shell" => ("running", "ran"),
+        "tool_forge_process_test" => ("testing", "tested"),
+        "tool_forge_process_lint" => ("linting", "linted"),
+        "tool_forge_verify" => ("verifying with", "verified with"),
+        "tool_forge_git_status" => ("checking the status of", "checked the status of"),
+        "tool_forge_git_diff" => ("diffing", "diffed"),
+        "tool_forge_git_commit" => ("committing", "committed"),
+        "tool_forge_net_fetch" => ("fetching", "fetched"),
+        "tool_forge_symbol_search" => ("looking up", "looked up"),
+        "tool_forge_process_think" => ("thinking", "thought"),
+        "tool_forge_user_ask" => ("asking", "asked"),
+        name => {
+            let name = name.strip_prefix("tool_forge_").unwrap_or(name);
+            (name, name)
+        }
+    }
+}
+
+/// The line of a tool the provider ran itself. The provider reports it once
+/// it's done, so there's no line in place and no duration.
+pub fn provider_line(tool: &ProviderToolCall) -> String {
+    let query = tool.call.arguments.get("query").and_then(Value::as_str);
+    format!(
+        "{} {} (provider)",
+        mark(&tool.result),
+        label(tool.call.name.as_str(), query)
+    )
+}
+
+fn label(verb: &str, subject: Option<&str>) -> String {
+    match subject {
+        Some(subject) => format!("{verb} {subject}"),
+        None => verb.to_string(),
+    }
+}
+
+fn running_line(call: &InFlight) -> String {
+    let (running, _) = verbs(&call.name);
+    format!("▶ {}…", label(running, call.subject.as_deref()))
+}
+
+fn mark(result: &ToolResult) -> &'static str {
+    if result.is_error {
+        "✗"
+    } else {
+        "✓"
+    }
+}
+
+fn shorten_command(command: &str) -> String {
+    let first_line = command.lines().next().unwrap_or_default().trim();
+    let mut short = first_line
+        .chars()
+        .take(MAX_COMMAND_WIDTH)
+        .collect::<String>();
+    if short.len() < command.trim().len() {
+        short.push('…');
+    }
+    short
+}
+
+/// Lines added and removed between two versions of a file
+fn changed_lines(before: &str, after: &str) -> (usize, usize) {
+    similar::TextDiff::from_lines(before, after)
+        .iter_all_changes()
+        .fold((0, 0), |(added, removed), change| match change.tag() {
+            similar::ChangeTag::Insert => (added + 1, removed),
+            similar::ChangeTag::Delete => (added, removed + 1),
+            similar::ChangeTag::Equal => (added, removed),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use forge_api::ToolName;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn call(name: &str, id: &str, arguments: Value) -> ToolCallFull {
+        ToolCallFull::new(ToolName::new(name))
+            .call_id(ToolCallId::new(id))
+            .arguments(arguments)
+    }
+
+    fn result(name: &str, id: &str) -> ToolResult {
+        ToolResult::new(ToolName::new(name)).call_id(ToolCallId::new(id))
+    }
+
+    fn fixture() -> Activity {
+        Activity::new(DisplayMode::Compact, PathFormat::new("/project"))
+    }
+
+    #[test]
+    fn test_read_line() {
+        let mut activity = fixture();
+        let now = Instant::now();
+
+        let running = activity.start(
+            &call(
+                "tool_forge_fs_read",
+                "1",
+                json!({"path": "/project/src/lib.rs"}),
+            ),
+            now,
+        );
+        let (done, still_running) = activity.end(
+            &result("tool_forge_fs_read", "1"),
+            None,
+            now + Duration::from_millis(1200),
+        );
+
+        assert_eq!(running, "▶ reading src/lib.rs…");
+        assert_eq!(done, "✓ read src/lib.rs (1.2s)");
+        assert_eq!(still_running, None);
+    }
+
+    #[test]
+    fn test_edit_line_counts_changes() {
+        let mut activity = fixture();
+        let now = Instant::now();
+        let fixture = call(
+            "tool_forge_fs_patch",
+            "1",
+            json!({"path": "/project/chat_service.rs"}),
+        );
+
+        activity.start(&fixture, now);
+        assert_eq!(
+            activity.written_by(&result("tool_forge_fs_patch", "1")),
+            Some(Path::new("/project/chat_service.rs"))
+        );
+        let (actual, _) = activity.end(
+            &result("tool_forge_fs_patch", "1").before("a\nb\nc\n"),
+            Some("a\nB\nc\nd\n".to_string()),
+            now,
+        );
+
+        assert_eq!(actual, "✓ edited chat_service.rs (+2/-1, 0ms)");
+        assert_eq!(
+            fixture.written_path(),
+            Some(PathBuf::from("/project/chat_service.rs"))
+        );
+    }
+
+    #[test]
+    fn test_shell_line() {
+        let mut activity = fixture();
+
+        let actual = activity.start(
+            &call(
+                "tool_forge_process_shell",
+                "1",
+                json!({"command": "cargo test"}),
+            ),
+            Instant::now(),
+        );
+
+        assert_eq!(actual, "▶ running cargo test…");
+    }
+
+    #[test]
+    fn test_failed_call_line() {
+        let mut activity = fixture();
+        let now = Instant::now();
+        activity.start(
+            &call(
+                "tool_forge_net_fetch",
+                "1",
+                json!({"url": "https://example.com"}),
+            ),
+            now,
+        );
+
+        let fixture = result("tool_forge_net_fetch", "1").failure(anyhow::anyhow!("timeout"));
+        let (actual, _) = activity.end(&fixture, None, now);
+
+        assert_eq!(actual, "✗ fetched https://example.com (0ms)");
+    }
+
+    #[test]
+    fn test_provider_line() {
+        let search = call("web_search", "srv_1", json!({"query": "rust editions"}));
+        let fixture = ProviderToolCall {
+            call: search.clone(),
+            result: ToolResult::from(search).success("3 results"),
+        };
+
+        let actual = provider_line(&fixture);
+
+        assert_eq!(actual, "✓ web_search rust editions (provider)");
+    }
+
+    #[test]
+    fn test_in_place_line_follows_the_calls_in_flight() {
+        let mut activity = fixture();
+        let now = Instant::now();
+        activity.start(
+            &call("tool_forge_fs_read", "1", json!({"path": "/project/a.rs"})),
+            now,
+        );
+        activity.start(
+            &call("tool_forge_fs_read", "2", json!({"path": "/project/b.rs"})),
+            now,
+        );
+
+        let (done, still_running) = activity.end(&result("tool_forge_fs_read", "2"), None, now);
+
+        assert_eq!(done, "✓ read b.rs (0ms)");
+        assert_eq!(still_running, Some("▶ reading a.rs…".to_string()));
+        assert!(activity.has_in_flight());
+
+        activity.end(&result("tool_forge_fs_read", "1"), None, now);
+        assert!(!activity.has_in_flight());
+    }
+
+    #[test]
+    fn test_end_without_start() {
+        let mut activity = fixture();
+
+        let actual = activity.end(&result("tool_forge_fs_read", "1"), None, Instant::now());
+
+        assert_eq!(actual, ("✓ read".to_string(), None));
+    }
+
+    #[test]
+    fn test_finish_flushes_text_since_last_call() {
+        let mut activity = fixture();
+        activity.buffer("Let me look at the code.");
+        activity.start(
+            &call("tool_forge_fs_read", "1", json!({"path": "/project/a.rs"})),
+            Instant::now(),
+        );
+        activity.buffer("All tests ");
+        activity.buffer("pass.");
+
+        let actual = activity.finish();
+
+        assert_eq!(actual, Some("All tests pass.".to_string()));
+        assert_eq!(activity.finish(), None);
+        assert!(!activity.has_in_flight());
+    }
+
+    #[test]
+    fn test_failure_flushes_the_text_of_calls_cut_short() {
+        let mut activity = fixture();
+        activity.buffer("Let me run the tests.");
+        activity.start(
+            &call(
+                "tool_forge_process_shell",
+                "1",
+                json!({"command": "cargo test"}),
+            ),
+            Instant::now(),
+        );
+        activity.buffer("ignored");
+        activity.start(
+            &call("tool_forge_fs_read", "2", json!({"path": "/project/a.rs"})),
+            Instant::now(),
+        );
+        activity.end(&result("tool_forge_fs_read", "2"), None, Instant::now());
+
+        let actual = activity.fail();
+
+        assert_eq!(actual, Some("Let me run the tests.".to_string()));
+        assert_eq!(activity.fail(), None);
+        assert!(!activity.has_in_flight());
+    }
+
+    #[test]
+    fn test_finish_on_error_flushes_text() {
+        let mut activity = fixture();
+        activity.start(
+            &call("tool_forge_fs_read", "1", json!({"path": "/project/a.rs"})),
+            Instant::now(),
+        );
+        activity.buffer("I couldn't find");
+
+        let actual = activity.finish();
+
+        assert_eq!(actual, Some("I couldn't find".to_string()));
+        assert!(!activity.has_in_flight());
+    }
+
+    #[test]
+    fn test_switching_to_full_flushes_text() {
+        let mut activity = fixture();
+        activity.start(
+            &call("tool_forge_fs_read", "1", json!({"path": "/project/a.rs"})),
+            Instant::now(),
+        );
+        activity.buffer("Reading");
+
+        let actual = activity.set_mode(DisplayMode::Full);
+
+        assert_eq!(actual, Some("Reading".to_string()));
+        assert_eq!(activity.mode(), DisplayMode::Full);
+        assert!(!activity.has_in_flight());
+        assert_eq!(activity.set_mode(DisplayMode::Compact), None);
+        assert!(activity.is_compact());
+    }
+
+    #[test]
+    fn test_long_command_is_shortened() {
+        let actual = shorten_command(&format!("{}\necho done", "x".repeat(80)));
+
+        assert_eq!(actual, format!("{}…", "x".repeat(MAX_COMMAND_WIDTH)));
+    }
+}
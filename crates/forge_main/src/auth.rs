@@ -0,0 +1,172 @@
+use anyhow::Result;
+use forge_api::Error;
+
+/// Whether the turn failed because the provider rejected the API key
+pub fn is_auth_error(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| matches!(cause.downcast_ref::<Error>(), Some(Error::ProviderAuth(_))))
+}
+
+/// A turn that can recover from the provider rejecting the API key
+#[async_trait::async_trait]
+pub trait RecoverableTurn {
+    async fn run(&mut self) -> Result<()>;
+
+    /// Asks the user for a new key after the turn failed with `err`, `None`
+    /// when they decline
+    async fn ask_key(&mut self, err: &anyhow::Error) -> Result<Option<String>>;
+
+    /// Starts using the key once the provider accepted it
+    async fn update_key(&mut self, key: String) -> Result<()>;
+}
+
+/// Runs the turn, and when the provider rejects the API key offers to
+/// replace it and retries the turn with the new key. Without a user to ask,
+/// the authentication error is returned as is.
+pub async fn run_turn(turn: &mut impl RecoverableTurn, interactive: bool) -> Result<()> {
+    loop {
+        let err = match turn.run().await {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+
+        if !interactive || !is_auth_error(&err) {
+            return Err(err);
+        }
+
+        let Some(key) = turn.ask_key(&err).await? else {
+            return Err(err);
+        };
+        turn.update_key(key).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use anyhow::Context;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// Rejects every key but `accepted`
+    struct StubProvider {
+        accepted: String,
+        key: String,
+    }
+
+    impl StubProvider {
+        fn check(&self, key: &str) -> Result<()> {
+            if key == self.accepted {
+                Ok(())
+            } else {
+                Err(Error::ProviderAuth("401 Unauthorized".to_string()).into())
+            }
+        }
+
+        fn chat(&self) -> Result<()> {
+            self.check(&self.key)
+                .context("Failed to chat with model: test-model")
+        }
+
+        fn models(&self, key: &str) -> Result<()> {
+            self.check(key)
+        }
+    }
+
+    struct StubTurn {
+        provider: StubProvider,
+        answers: VecDeque<Option<String>>,
+        runs: usize,
+        asked: usize,
+    }
+
+    impl StubTurn {
+        fn new(answers: Vec<Option<&str>>) -> Self {
+            Self {
+                provider: StubProvider {
+                    accepted: "sk-new".to_string(),
+                    key: "sk-old".to_string(),
+                },
+                answers: answers
+                    .into_iter()
+                    .map(|answer| answer.map(str::to_string))
+                    .collect(),
+                runs: 0,
+                asked: 0,
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl RecoverableTurn for StubTurn {
+        async fn run(&mut self) -> Result<()> {
+            self.runs += 1;
+            self.provider.chat()
+        }
+
+        async fn ask_key(&mut self, _err: &anyhow::Error) -> Result<Option<String>> {
+            self.asked += 1;
+            Ok(self.answers.pop_front().flatten())
+        }
+
+        async fn update_key(&mut self, key: String) -> Result<()> {
+            self.provider.models(&key)?;
+            self.provider.key = key;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_is_auth_error() {
+        let auth = anyhow::Error::from(Error::ProviderAuth("401".to_string()))
+            .context("Failed to chat with model: test-model");
+        assert!(is_auth_error(&auth));
+        assert!(!is_auth_error(&anyhow::anyhow!("connection reset")));
+        assert!(!is_auth_error(&Error::HeadAgentUndefined.into()));
+    }
+
+    #[tokio::test]
+    async fn test_turn_is_retried_with_the_new_key() {
+        let mut fixture = StubTurn::new(vec![Some("sk-new")]);
+
+        run_turn(&mut fixture, true).await.unwrap();
+
+        assert_eq!(fixture.provider.key, "sk-new");
+        assert_eq!((fixture.runs, fixture.asked), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_new_key_ends_the_turn() {
+        let mut fixture = StubTurn::new(vec![Some("sk-typo")]);
+
+        let actual = run_turn(&mut fixture, true).await.unwrap_err();
+
+        assert!(is_auth_error(&actual));
+        assert_eq!(fixture.provider.key, "sk-old");
+        assert_eq!((fixture.runs, fixture.asked), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_declining_returns_the_error() {
+        let mut fixture = StubTurn::new(vec![None]);
+
+        let actual = run_turn(&mut fixture, true).await.unwrap_err();
+
+        assert!(is_auth_error(&actual));
+        assert_eq!((fixture.runs, fixture.asked), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_non_interactive_does_not_ask() {
+        let mut fixture = StubTurn::new(vec![Some("sk-new")]);
+
+        let actual = run_turn(&mut fixture, false).await.unwrap_err();
+
+        assert_eq!((fixture.runs, fixture.asked), (1, 0));
+        let message = format!("{:#}", actual);
+        assert!(message.contains("[FORGE-PROV-AUTH]"));
+        assert!(message.contains("set a new key"));
+    }
+}
@@ -1,14 +1,56 @@
 use std::io;
 
 use colored::Colorize;
+use forge_api::InfoSection;
 
 use crate::model::Command;
 
 const BANNER: &str = include_str!("banner");
 
-pub fn display() -> io::Result<()> {
-    let commands = Command::available_commands();
+/// Shows the banner with the built-in commands followed by the `plugins`
+pub fn display(sections: &[InfoSection], plugins: &[String]) -> io::Result<()> {
+    let mut commands = Command::available_commands();
+    commands.extend_from_slice(plugins);
     // Split the banner into lines and display each line dimmed
     println!("{} {}", BANNER.dimmed(), commands.join(", ").bold());
+
+    let highlights = highlights(sections);
+    if !highlights.is_empty() {
+        println!("{}\n", highlights.dimmed());
+    }
     Ok(())
 }
+
+/// The highlighted rows of all sections on a single line
+fn highlights(sections: &[InfoSection]) -> String {
+    sections
+        .iter()
+        .flat_map(|section| &section.rows)
+        .filter(|row| row.highlight)
+        .map(|row| format!("{}: {}", row.key, row.value))
+        .collect::<Vec<_>>()
+        .join("  ·  ")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_highlights() {
+        let fixture = vec![
+            InfoSection::new("Session")
+                .row("Version", "0.1.0")
+                .highlight("Model", "sonnet"),
+            InfoSection::new("Git").highlight("Branch", "main"),
+            InfoSection::new("Usage").row("Total", 0),
+        ];
+
+        let actual = highlights(&fixture);
+
+        let expected = "Model: sonnet  ·  Branch: main";
+        assert_eq!(actual, expected);
+    }
+}
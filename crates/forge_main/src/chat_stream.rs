@@ -0,0 +1,162 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use forge_api::{AgentId, AgentMessage, ChatResponse, Error, ErrorSeverity};
+use forge_display::TitleFormat;
+
+/// What the UI does with the next item of a chat stream
+#[derive(Debug)]
+pub enum StreamStep {
+    /// A response to render, the turn continues
+    Handle(AgentMessage<ChatResponse>),
+    /// The turn is over, because the stream ended or after a fatal error
+    Stop(Result<()>),
+}
+
+impl StreamStep {
+    /// Fatal errors stop the turn, whether they are sent in-band or through
+    /// the stream itself. Warnings and recoverable errors are rendered like
+    /// any other response and the turn goes on.
+    pub fn next(item: Option<Result<AgentMessage<ChatResponse>>>) -> Self {
+        match item {
+            Some(Ok(AgentMessage {
+                message: ChatResponse::Error { severity: ErrorSeverity::Fatal, message },
+                ..
+            })) => StreamStep::Stop(Err(anyhow!(message))),
+            Some(Ok(message)) => StreamStep::Handle(message),
+            Some(Err(err)) => match error_severity(&err) {
+                ErrorSeverity::Fatal => StreamStep::Stop(Err(err)),
+                severity => StreamStep::Handle(AgentMessage {
+                    agent: AgentId::new("forge"),
+                    message: ChatResponse::Error { severity, message: format!("{err:#}") },
+                }),
+            },
+            None => StreamStep::Stop(Ok(())),
+        }
+    }
+}
+
+/// Severity of an error that ended up outside the stream's in-band errors,
+/// see [`Error::severity`]. Errors that aren't forge's own are fatal.
+pub fn error_severity(err: &anyhow::Error) -> ErrorSeverity {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<Error>())
+        .map_or(ErrorSeverity::Fatal, Error::severity)
+}
+
+/// Formats an in-band error: warnings are dimmed, recoverable errors are a
+/// yellow status and fatal errors a red one
+pub fn format_error(severity: ErrorSeverity, message: &str) -> String {
+    match severity {
+        ErrorSeverity::Warning => format!("warning: {message}").dimmed().to_string(),
+        ErrorSeverity::Recoverable => TitleFormat::warning(message).format(),
+        ErrorSeverity::Fatal => TitleFormat::failed(message).format(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_api::ToolName;
+    use pretty_assertions::assert_eq;
+    use tokio_stream::StreamExt;
+
+    use super::*;
+
+    fn message(message: ChatResponse) -> Result<AgentMessage<ChatResponse>> {
+        Ok(AgentMessage { agent: AgentId::new("developer"), message })
+    }
+
+    fn error(severity: ErrorSeverity) -> Result<AgentMessage<ChatResponse>> {
+        message(ChatResponse::Error { severity, message: format!("{severity:?}") })
+    }
+
+    /// Drives a scripted stream the way the UI does, returning the responses
+    /// that were handled, how the turn ended and how many items were left
+    /// unread
+    async fn drive(
+        items: Vec<Result<AgentMessage<ChatResponse>>>,
+    ) -> (Vec<String>, Result<()>, usize) {
+        let mut stream = tokio_stream::iter(items);
+        let mut handled = Vec::new();
+        loop {
+            match StreamStep::next(stream.next().await) {
+                StreamStep::Handle(message) => handled.push(match message.message {
+                    ChatResponse::Text(text) => text,
+                    ChatResponse::Error { severity, .. } => format!("{severity:?}"),
+                    other => format!("{other:?}"),
+                }),
+                StreamStep::Stop(result) => {
+                    let remaining = stream.collect::<Vec<_>>().await.len();
+                    return (handled, result, remaining);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warnings_and_recoverable_errors_continue() {
+        let (handled, result, remaining) = drive(vec![
+            message(ChatResponse::Text("a".to_string())),
+            error(ErrorSeverity::Warning),
+            error(ErrorSeverity::Recoverable),
+            message(ChatResponse::Text("b".to_string())),
+        ])
+        .await;
+
+        assert_eq!(handled, vec!["a", "Warning", "Recoverable", "b"]);
+        assert!(result.is_ok());
+        assert_eq!(remaining, 0);
+    }
+
+    #[tokio::test]
+    async fn test_fatal_error_stops_consumption() {
+        let (handled, result, remaining) = drive(vec![
+            message(ChatResponse::Text("a".to_string())),
+            error(ErrorSeverity::Fatal),
+            message(ChatResponse::Text("b".to_string())),
+        ])
+        .await;
+
+        assert_eq!(handled, vec!["a"]);
+        assert_eq!(result.unwrap_err().to_string(), "Fatal");
+        assert_eq!(remaining, 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_is_fatal() {
+        let (handled, result, remaining) = drive(vec![
+            Err(anyhow!("connection reset")),
+            message(ChatResponse::Text("a".to_string())),
+        ])
+        .await;
+
+        assert!(handled.is_empty());
+        assert_eq!(result.unwrap_err().to_string(), "connection reset");
+        assert_eq!(remaining, 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_takes_the_severity_of_forge_errors() {
+        let denied = anyhow::Error::from(Error::ToolDenied(ToolName::new("tool_forge_fs_remove")));
+        let (handled, result, remaining) = drive(vec![
+            Err(denied.context("Failed to run the tool")),
+            message(ChatResponse::Text("a".to_string())),
+            Err(Error::MissingModel(AgentId::new("developer")).into()),
+        ])
+        .await;
+
+        assert_eq!(handled, vec!["Recoverable", "a"]);
+        assert!(result.is_err());
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn test_format_error() {
+        let warning = format_error(ErrorSeverity::Warning, "slow provider");
+        let recoverable = format_error(ErrorSeverity::Recoverable, "Tool 'x' failed");
+        let fatal = format_error(ErrorSeverity::Fatal, "Missing model");
+
+        assert!(warning.contains("warning: slow provider"));
+        assert!(recoverable.contains("warning") && recoverable.contains("Tool 'x' failed"));
+        assert!(fatal.contains("error") && fatal.contains("Missing model"));
+    }
+}
@@ -1,5 +1,9 @@
 use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
+use forge_api::{ChunkConfig, ImportFormat, IterationLimitAction};
+
+use crate::rebuild::RebuildTarget;
 
 /// Command-line interface for the application.
 #[derive(Parser, Debug)]
@@ -18,6 +22,15 @@ pub struct Cli {
     #[arg(long, short = 'p')]
     pub prompt: Option<String>,
 
+    /// Run the !`command` occurrences of the direct prompt and send their
+    /// output along with it.
+    ///
+    /// Off by default since direct prompts often come from scripts rather
+    /// than being typed. Interactive messages follow the inline_commands
+    /// setting.
+    #[arg(long, default_value_t = false)]
+    pub inline_commands: bool,
+
     /// Enable verbose output mode.
     ///
     /// When enabled, shows additional debugging information and tool execution
@@ -41,27 +54,165 @@ pub struct Cli {
     #[arg(long, default_value_t = false, short = 'r')]
     pub restricted: bool,
 
+    /// Start in read-only mode, for reviews.
+    ///
+    /// The agent can read, search and outline files but has no tools to
+    /// write, patch, move, copy or remove them, nor to run commands.
+    #[arg(long = "safe", visible_alias = "read-only", default_value_t = false)]
+    pub read_only: bool,
+
+    /// Preview the agent's intended tool calls without executing them.
+    ///
+    /// When enabled, tool calls are listed as a plan so they can be reviewed
+    /// before running the same prompt without this flag.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Show one line per tool call instead of streaming the agent's text.
+    ///
+    /// The agent's final text is shown once the turn ends. Use '/display full'
+    /// to switch back during the session.
+    #[arg(long, default_value_t = false)]
+    pub compact: bool,
+
+    /// What happens when a turn reaches the agent's iteration limit:
+    /// continue, stop or rollback.
+    ///
+    /// Interactive sessions ask when unset, a direct prompt stops the turn
+    /// and keeps its context.
+    #[arg(long)]
+    pub on_iteration_limit: Option<IterationLimitAction>,
+
     /// Path to a file containing the workflow to execute.
     #[arg(long, short = 'w')]
     pub workflow: Option<PathBuf>,
 
-    /// Subcommand for managing snapshots.
-    #[command(subcommand)]
-    pub snapshot_command: Option<Snapshot>,
-
-    /// Subcommand for compacting the context.
     #[command(subcommand)]
-    pub compact_command: Option<Compact>,
+    pub subcommand: Option<TopLevelCommand>,
 }
 
-/// Subcommands for managing snapshots.
+/// Top-level subcommands that run instead of the interactive session.
 #[derive(Subcommand, Debug)]
-pub enum Snapshot {
+pub enum TopLevelCommand {
     /// Manage file snapshots.
     Snapshot {
         #[command(subcommand)]
         sub_command: SnapshotCommand,
     },
+
+    /// Show usage statistics recorded on this machine.
+    Stats {
+        /// Only include turns from this period, eg. 30d, 2w or 12h.
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Print the statistics as JSON.
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+
+    /// Index files into the document index. Files that didn't change since
+    /// they were last indexed are skipped.
+    Index {
+        /// Directory to index, defaults to the current directory.
+        path: Option<PathBuf>,
+
+        /// Only index files matching this pattern, eg. "**/*.md".
+        #[arg(long)]
+        glob: Option<String>,
+
+        /// Tag to attach to the indexed chunks, can be repeated.
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        /// Maximum size of a chunk in bytes.
+        #[arg(long, default_value_t = ChunkConfig::default().size)]
+        chunk_size: usize,
+
+        /// Size in bytes of the text repeated between consecutive chunks.
+        #[arg(long, default_value_t = ChunkConfig::default().overlap)]
+        chunk_overlap: usize,
+    },
+
+    /// Import the history of another agent tool as stored conversations.
+    /// Conversations that were imported before are skipped.
+    Import {
+        /// Format of the history, aider or claude-code.
+        #[arg(long)]
+        format: ImportFormat,
+
+        /// History file, or a directory to search for history files.
+        path: PathBuf,
+    },
+
+    /// List the stored conversations, including the imported ones.
+    Conversations {
+        /// Only list the conversations with this tag, eg. imported:aider.
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Only list the conversations whose title or messages contain this
+        /// text, ignoring case.
+        #[arg(long)]
+        query: Option<String>,
+    },
+
+    /// Rebuild data forge derives from other sources, eg. after an upgrade
+    /// or when it looks inconsistent. Refused while a chat turn is running.
+    Rebuild {
+        /// What to rebuild.
+        #[arg(long, value_enum, default_value_t = RebuildTarget::All)]
+        what: RebuildTarget,
+    },
+
+    /// Remove scratch directories left behind by conversations.
+    Cleanup {
+        /// Remove scratch directories not touched in this many days.
+        #[arg(long, default_value_t = 7)]
+        older_than: u32,
+    },
+
+    /// Encrypt the conversation data kept on disk with a passphrase. Set
+    /// FORGE_STORE_PASSPHRASE or enter it when asked.
+    EncryptStore,
+
+    /// Convert the encrypted conversation data back to plain text.
+    DecryptStore,
+
+    /// Manage the secrets the shell tool passes to commands. Agents refer to
+    /// a secret as `secret:NAME` in a command's environment and never see its
+    /// value.
+    Secret {
+        #[command(subcommand)]
+        sub_command: SecretCommand,
+    },
+
+    /// Upgrade forge to the latest release.
+    Upgrade {
+        /// Only report whether a newer release is available.
+        #[arg(long, default_value_t = false)]
+        check: bool,
+    },
+}
+
+/// Operations for managing secrets.
+#[derive(Subcommand, Debug)]
+pub enum SecretCommand {
+    /// Store a secret, replacing its value when it exists. The value is read
+    /// from the terminal without being shown, or from stdin when piped.
+    Set {
+        /// Name of the secret, letters, digits and underscores.
+        name: String,
+    },
+
+    /// List the names of the stored secrets and when they were set.
+    List,
+
+    /// Remove a secret.
+    Remove {
+        /// Name of the secret.
+        name: String,
+    },
 }
 
 /// Operations for managing file snapshots.
@@ -85,6 +236,14 @@ pub enum SnapshotCommand {
         /// Restore by index.
         #[arg(long, short)]
         index: Option<usize>,
+
+        /// Show the changes and ask before restoring.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Restore without asking after a dry run.
+        #[arg(long, short)]
+        yes: bool,
     },
 
     /// Show differences between versions of a file.
@@ -96,7 +255,7 @@ pub enum SnapshotCommand {
         #[arg(long)]
         timestamp: Option<u64>,
 
-        /// Restore by index.
+        /// Show diff for a specific index.
         #[arg(long, short)]
         index: Option<usize>,
     },
@@ -108,14 +267,3 @@ pub enum SnapshotCommand {
         older_than: u32,
     },
 }
-
-/// Subcommands for compacting the context.
-#[derive(Subcommand, Debug)]
-pub enum Compact {
-    /// Compact the current context.
-    Compact {
-        /// Path to the file containing the context to compact.
-        #[arg(long, short)]
-        context_file: Option<PathBuf>,
-    },
-}
@@ -2,13 +2,23 @@ use reedline::{Completer, Span, Suggestion};
 
 use crate::model::Command;
 
-#[derive(Default)]
-pub struct CommandCompleter;
+#[derive(Clone, Default)]
+pub struct CommandCompleter {
+    /// Commands of the plugins, completed after the built-in ones
+    plugins: Vec<String>,
+}
+
+impl CommandCompleter {
+    pub fn new(plugins: Vec<String>) -> Self {
+        Self { plugins }
+    }
+}
 
 impl Completer for CommandCompleter {
     fn complete(&mut self, line: &str, _: usize) -> Vec<reedline::Suggestion> {
         Command::available_commands()
             .into_iter()
+            .chain(self.plugins.iter().cloned())
             .filter(|cmd| cmd.starts_with(line))
             .map(|cmd| Suggestion {
                 value: cmd,
@@ -21,3 +31,23 @@ impl Completer for CommandCompleter {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_plugins_are_completed() {
+        let mut fixture = CommandCompleter::new(vec!["/lint".to_string(), "/todo".to_string()]);
+
+        let actual = fixture
+            .complete("/l", 2)
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, vec!["/lint"]);
+    }
+}
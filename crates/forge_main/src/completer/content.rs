@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use forge_walker::File;
+use regex::Regex;
+
+/// Marks a completion term as a content query, eg. `@?TODO`
+pub const CONTENT_QUERY: char = '?';
+/// Files larger than this aren't searched
+const MAX_FILE_SIZE: u64 = 256 * 1024;
+/// Files searched for one query
+const MAX_FILES: usize = 2000;
+/// Paths suggested for one query
+const MAX_MATCHES: usize = 50;
+
+/// Finds files by their content. The query is a regex, matched literally
+/// when it isn't a valid one.
+pub struct ContentQuery {
+    regex: Regex,
+}
+
+impl ContentQuery {
+    pub fn new(query: &str) -> Option<Self> {
+        if query.is_empty() {
+            return None;
+        }
+        let regex = Regex::new(query)
+            .or_else(|_| Regex::new(&regex::escape(query)))
+            .ok()?;
+        Some(Self { regex })
+    }
+
+    /// Paths of the files with content matching the query, `files` being
+    /// relative to `cwd`
+    pub fn matches(&self, cwd: &Path, files: &[File]) -> Vec<String> {
+        files
+            .iter()
+            .filter(|file| !file.is_dir() && file.size <= MAX_FILE_SIZE)
+            .take(MAX_FILES)
+            .filter(|file| {
+                std::fs::read_to_string(cwd.join(&file.path))
+                    .is_ok_and(|content| self.regex.is_match(&content))
+            })
+            .take(MAX_MATCHES)
+            .map(|file| file.path.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_walker::Walker;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn fixture() -> (tempfile::TempDir, Vec<File>) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "// TODO: split\nfn a() {}\n").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(dir.path().join("notes.md"), "- [ ] TODO(ci) fix\n").unwrap();
+        let files = Walker::max_all()
+            .cwd(dir.path().to_path_buf())
+            .get_blocking()
+            .unwrap();
+        (dir, files)
+    }
+
+    #[test]
+    fn test_content_query_returns_matching_files() {
+        let (dir, files) = fixture();
+
+        let mut actual = ContentQuery::new("TODO")
+            .unwrap()
+            .matches(dir.path(), &files);
+        actual.sort();
+
+        assert_eq!(actual, vec!["notes.md", "src/lib.rs"]);
+    }
+
+    #[test]
+    fn test_content_query_is_a_regex() {
+        let (dir, files) = fixture();
+
+        let actual = [r"^fn \w+\(\) \{\}\n", "TODO(ci"].map(|query| {
+            ContentQuery::new(query)
+                .unwrap()
+                .matches(dir.path(), &files)
+        });
+
+        let expected = [
+            vec!["src/main.rs".to_string()],
+            vec!["notes.md".to_string()],
+        ];
+        assert_eq!(actual, expected);
+    }
+}
@@ -3,18 +3,23 @@ use std::path::PathBuf;
 use forge_walker::Walker;
 use reedline::{Completer, Suggestion};
 
+use crate::completer::content::{ContentQuery, CONTENT_QUERY};
 use crate::completer::search_term::SearchTerm;
 use crate::completer::CommandCompleter;
 
 #[derive(Clone)]
 pub struct InputCompleter {
+    cwd: PathBuf,
     walker: Walker,
+    commands: CommandCompleter,
 }
 
 impl InputCompleter {
-    pub fn new(cwd: PathBuf) -> Self {
-        let walker = Walker::max_all().cwd(cwd).skip_binary(true);
-        Self { walker }
+    /// `plugins` are the commands of the plugins, completed along with the
+    /// built-in ones
+    pub fn new(cwd: PathBuf, plugins: Vec<String>) -> Self {
+        let walker = Walker::max_all().cwd(cwd.clone()).skip_binary(true);
+        Self { cwd, walker, commands: CommandCompleter::new(plugins) }
     }
 }
 
@@ -23,7 +28,7 @@ impl Completer for InputCompleter {
         if line.starts_with("/") {
             // if the line starts with '/' it's probably a command, so we delegate to the
             // command completer.
-            let result = CommandCompleter.complete(line, pos);
+            let result = self.commands.complete(line, pos);
             if !result.is_empty() {
                 return result;
             }
@@ -31,6 +36,23 @@ impl Completer for InputCompleter {
 
         if let Some(query) = SearchTerm::new(line, pos).process() {
             let files = self.walker.get_blocking().unwrap_or_default();
+            if let Some(pattern) = query.term.strip_prefix(CONTENT_QUERY) {
+                let Some(content) = ContentQuery::new(pattern) else {
+                    return vec![];
+                };
+                return content
+                    .matches(&self.cwd, &files)
+                    .into_iter()
+                    .map(|path| Suggestion {
+                        value: path,
+                        description: None,
+                        style: None,
+                        extra: None,
+                        span: query.span,
+                        append_whitespace: true,
+                    })
+                    .collect();
+            }
             files
                 .into_iter()
                 .filter(|file| !file.is_dir())
@@ -4,16 +4,28 @@ use std::sync::Mutex;
 use lazy_static::lazy_static;
 
 use crate::normalize::NewLine;
+use crate::wrap::{terminal_width, Wrap};
 
 lazy_static! {
     /// Global console instance for standardized output handling
     pub static ref CONSOLE: Console = Console::new();
 }
 
-/// Console state containing both the output stream and normalizer
+/// Console state containing the output stream, normalizer and wrapping
 struct ConsoleState {
     stdout: io::Stdout,
     normalizer: NewLine,
+    wrap: Wrap,
+}
+
+impl ConsoleState {
+    /// Normalizes the newlines of the content and wraps it to `width`
+    /// columns. Without a width lines are left whole, the column is still
+    /// tracked for the writes that follow.
+    fn render(&mut self, content: &str, width: Option<usize>) -> String {
+        let normalized = self.normalizer.normalize(content);
+        self.wrap.wrap(&normalized, width.unwrap_or(usize::MAX))
+    }
 }
 
 /// A specialized console that provides enhanced printing capabilities
@@ -32,21 +44,17 @@ impl Console {
     /// Creates a new Console instance
     pub fn new() -> Self {
         Self {
-            state: Mutex::new(ConsoleState { stdout: io::stdout(), normalizer: NewLine::new() }),
+            state: Mutex::new(ConsoleState {
+                stdout: io::stdout(),
+                normalizer: NewLine::new(),
+                wrap: Wrap::default(),
+            }),
         }
     }
 
-    /// Writes the given content
+    /// Writes the given content, wrapped to the width of the terminal
     pub fn write(&self, content: impl AsRef<str>) -> io::Result<()> {
-        let content = content.as_ref();
-        let mut state = self.state.lock().unwrap();
-        if content.is_empty() {
-            return Ok(());
-        }
-
-        let normalized = state.normalizer.normalize(content);
-        write!(state.stdout, "{}", normalized)?;
-        state.stdout.flush()
+        self.write_with_width(content.as_ref(), terminal_width())
     }
 
     /// Writes the given content with a newline
@@ -55,8 +63,63 @@ impl Console {
         self.write(content)
     }
 
+    /// Writes the given content with a newline and without wrapping it, for
+    /// output that is read by programs such as JSON
+    pub fn writeln_raw(&self, content: impl AsRef<str>) -> io::Result<()> {
+        let content = format!("{}\n", content.as_ref());
+        self.write_with_width(&content, None)
+    }
+
+    fn write_with_width(&self, content: &str, width: Option<usize>) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if content.is_empty() {
+            return Ok(());
+        }
+
+        let rendered = state.render(content, width);
+        write!(state.stdout, "{}", rendered)?;
+        state.stdout.flush()
+    }
+
     /// Writes a newline
     pub fn newline(&self) -> io::Result<()> {
         self.write("\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::wrap::DEFAULT_WIDTH;
+
+    fn fixture() -> ConsoleState {
+        ConsoleState {
+            stdout: io::stdout(),
+            normalizer: NewLine::new(),
+            wrap: Wrap::default(),
+        }
+    }
+
+    #[test]
+    fn test_long_json_line_is_written_unchanged() {
+        let json = serde_json::json!({
+            "messages": (0..20).map(|i| format!("message number {i}")).collect::<Vec<_>>()
+        })
+        .to_string();
+        let content = format!("{json}\n");
+
+        let actual = fixture().render(&content, None);
+
+        assert!(json.len() > DEFAULT_WIDTH);
+        assert_eq!(actual, content);
+    }
+
+    #[test]
+    fn test_terminal_output_is_wrapped() {
+        let actual = fixture().render("the quick brown fox jumps\n", Some(10));
+
+        assert_eq!(actual, "the quick\nbrown fox\njumps\n");
+    }
+}
@@ -0,0 +1,371 @@
+use std::fmt::{self, Write};
+
+use forge_api::{AgentId, ContentMessage, Context, ContextMessage, Conversation, Role};
+use forge_display::PathFormat;
+use serde_json::{Map, Value};
+
+/// Style of the HTML transcript, kept inline so the page can be shared as a
+/// single file
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:60rem;margin:2rem auto;padding:0 1rem;color:#1f2328;background:#fff}\
+h1{font-size:1.2rem;border-bottom:1px solid #d0d7de;padding-bottom:.3rem}\
+.message{border:1px solid #d0d7de;border-radius:6px;margin:1rem 0;padding:.5rem 1rem}\
+.role{font-weight:600;font-size:.85rem;text-transform:uppercase;color:#59636e}\
+.system{background:#f6f8fa}.user{background:#ddf4ff}.assistant{background:#fff}.tool{background:#f6f8fa}.failed{border-color:#cf222e}\
+pre{white-space:pre-wrap;word-wrap:break-word;font-size:.9rem;margin:.5rem 0}\
+.call{color:#8250df}img{max-width:100%}";
+
+/// The contexts of a conversation's agents, as they are sent to the provider
+pub struct ContextView<'a> {
+    contexts: Vec<(&'a AgentId, &'a Context)>,
+    /// Relativizes the paths of the text and HTML views, the JSON view keeps
+    /// the context as it is
+    paths: PathFormat,
+}
+
+impl<'a> ContextView<'a> {
+    /// Takes the agents with a context, in the order of the workflow
+    pub fn new(conversation: &'a Conversation) -> Self {
+        let contexts = conversation
+            .workflow
+            .agents
+            .iter()
+            .filter_map(|agent| {
+                conversation
+                    .context(&agent.id)
+                    .map(|context| (&agent.id, context))
+            })
+            .collect();
+        Self { contexts, paths: PathFormat::default() }
+    }
+
+    pub fn paths(mut self, paths: PathFormat) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contexts.is_empty()
+    }
+
+    /// The contexts as JSON, keyed by agent
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let mut contexts = Map::new();
+        for (agent, context) in &self.contexts {
+            contexts.insert(agent.as_str().to_string(), serde_json::to_value(context)?);
+        }
+        serde_json::to_string_pretty(&Value::Object(contexts))
+    }
+
+    /// The contexts as a standalone HTML page, to share the conversation
+    pub fn to_html(&self, title: &str) -> String {
+        let mut html = String::new();
+        let _ = writeln!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>",
+            escape(title)
+        );
+
+        for (agent, context) in &self.contexts {
+            let _ = writeln!(html, "<section>\n<h1>{}</h1>", escape(agent.as_str()));
+            if let Some(system) = &context.system {
+                html_message(&mut html, "system", "System", system, &[]);
+            }
+
+            for message in &context.messages {
+                match message {
+                    ContextMessage::ContentMessage(ContentMessage {
+                        role,
+                        content,
+                        tool_calls,
+                        ..
+                    }) => {
+                        let calls = tool_calls
+                            .iter()
+                            .flatten()
+                            .map(|call| {
+                                let arguments = self.paths.relativize(&call.arguments.to_string());
+                                format!("→ {} {}", call.name.as_str(), arguments)
+                            })
+                            .collect::<Vec<_>>();
+                        let label = role.to_string();
+                        let content = self.paths.relativize(content);
+                        html_message(&mut html, &label.to_lowercase(), &label, &content, &calls);
+                    }
+                    ContextMessage::ToolMessage(result) => {
+                        let (class, label) = if result.is_error {
+                            (
+                                "tool failed",
+                                format!("Tool {} failed", result.name.as_str()),
+                            )
+                        } else {
+                            ("tool", format!("Tool {}", result.name.as_str()))
+                        };
+                        let content = self.paths.relativize(&result.content.to_string());
+                        html_message(&mut html, class, &label, &content, &[]);
+                    }
+                    ContextMessage::Image(url) => {
+                        let _ = writeln!(
+                            html,
+                            "<div class=\"message user\"><div class=\"role\">Image</div><img src=\"{}\"></div>",
+                            escape(url)
+                        );
+                    }
+                }
+            }
+            html.push_str("</section>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+fn html_message(html: &mut String, class: &str, label: &str, content: &str, calls: &[String]) {
+    let _ = write!(
+        html,
+        "<div class=\"message {class}\"><div class=\"role\">{}</div>",
+        escape(label)
+    );
+    if !content.is_empty() {
+        let _ = write!(html, "<pre>{}</pre>", escape(content));
+    }
+    for call in calls {
+        let _ = write!(html, "<pre class=\"call\">{}</pre>", escape(call));
+    }
+    html.push_str("</div>\n");
+}
+
+/// Longest part of a file name taken from a conversation's title
+const MAX_SLUG_LEN: usize = 60;
+
+/// Turns a conversation's title into a part of a file name: lowercase
+/// letters and digits, separated by single dashes. `None` when the title
+/// has neither.
+pub fn slug(title: &str) -> Option<String> {
+    let mut slug = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug
+        .chars()
+        .take(MAX_SLUG_LEN)
+        .collect::<String>()
+        .trim_end_matches('-')
+        .to_string();
+    (!slug.is_empty()).then_some(slug)
+}
+
+/// Escapes text for HTML content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders the messages role by role, with the tool calls of the assistant
+/// and the tools the agent can call
+impl fmt::Display for ContextView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (agent, context)) in self.contexts.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "# {}", agent.as_str())?;
+
+            if let Some(system) = &context.system {
+                writeln!(f, "\n[system]\n{system}")?;
+            }
+
+            for message in &context.messages {
+                match message {
+                    ContextMessage::ContentMessage(ContentMessage {
+                        role,
+                        content,
+                        tool_calls,
+                        ..
+                    }) => {
+                        let role = match role {
+                            Role::System => "system",
+                            Role::User => "user",
+                            Role::Assistant => "assistant",
+                        };
+                        writeln!(f, "\n[{role}]")?;
+                        if !content.is_empty() {
+                            writeln!(f, "{}", self.paths.relativize(content))?;
+                        }
+                        for call in tool_calls.iter().flatten() {
+                            let arguments = self.paths.relativize(&call.arguments.to_string());
+                            writeln!(f, "→ {} {arguments}", call.name.as_str())?;
+                        }
+                    }
+                    ContextMessage::ToolMessage(result) => {
+                        let status = if result.is_error { " failed" } else { "" };
+                        writeln!(f, "\n[tool {}{status}]", result.name.as_str())?;
+                        writeln!(f, "{}", self.paths.relativize(&result.content.to_string()))?;
+                    }
+                    ContextMessage::Image(url) => {
+                        writeln!(f, "\n[image]\n{url}")?;
+                    }
+                }
+            }
+
+            if !context.tools.is_empty() {
+                let tools = context
+                    .tools
+                    .iter()
+                    .map(|tool| tool.name.as_str())
+                    .collect::<Vec<_>>();
+                writeln!(f, "\ntools: {}", tools.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_api::{ToolCallFull, ToolCallId, ToolName, ToolResult};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn fixture() -> Context {
+        let call = ToolCallFull::new(ToolName::new("tool_forge_fs_read"))
+            .call_id(ToolCallId::new("call_1"))
+            .arguments(json!({"path": "a.txt"}));
+        Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("What's in a.txt?"))
+            .add_message(ContextMessage::assistant("", Some(vec![call.clone()])))
+            .add_tool_results(vec![ToolResult::from(call).success("hello")])
+            .add_message(ContextMessage::assistant("It says hello", None))
+    }
+
+    #[test]
+    fn test_render_context() {
+        let agent = AgentId::new("developer");
+        let context = fixture();
+        let view = ContextView {
+            contexts: vec![(&agent, &context)],
+            paths: PathFormat::default(),
+        };
+
+        let actual = view.to_string();
+
+        let expected = r#"# developer
+
+[system]
+Be brief
+
+[user]
+What's in a.txt?
+
+[assistant]
+→ tool_forge_fs_read {"path":"a.txt"}
+
+[tool tool_forge_fs_read]
+hello
+
+[assistant]
+It says hello
+"#;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_render_context_as_html() {
+        let agent = AgentId::new("developer");
+        let context = fixture().add_message(ContextMessage::user("Is <b> & \"c\" escaped?"));
+        let view = ContextView {
+            contexts: vec![(&agent, &context)],
+            paths: PathFormat::default(),
+        };
+
+        let actual = view.to_html("Conversation 1");
+
+        let expected = [
+            "<title>Conversation 1</title>",
+            "<h1>developer</h1>",
+            "<div class=\"role\">System</div><pre>Be brief</pre>",
+            "<div class=\"role\">User</div><pre>What&#39;s in a.txt?</pre>",
+            "<pre class=\"call\">→ tool_forge_fs_read {&quot;path&quot;:&quot;a.txt&quot;}</pre>",
+            "<div class=\"role\">Tool tool_forge_fs_read</div><pre>hello</pre>",
+            "<div class=\"role\">Assistant</div><pre>It says hello</pre>",
+            "<pre>Is &lt;b&gt; &amp; &quot;c&quot; escaped?</pre>",
+        ];
+        for expected in expected {
+            assert!(actual.contains(expected), "{expected} is missing");
+        }
+        assert!(actual.starts_with("<!DOCTYPE html>"));
+        assert!(actual.ends_with("</html>\n"));
+    }
+
+    #[test]
+    fn test_render_context_as_json() {
+        let agent = AgentId::new("developer");
+        let context = fixture();
+        let view = ContextView {
+            contexts: vec![(&agent, &context)],
+            paths: PathFormat::default(),
+        };
+
+        let actual: Value = serde_json::from_str(&view.to_json().unwrap()).unwrap();
+
+        assert_eq!(actual["developer"], serde_json::to_value(&context).unwrap());
+    }
+
+    #[test]
+    fn test_render_context_as_html_with_relative_paths() {
+        let agent = AgentId::new("developer");
+        let call = ToolCallFull::new(ToolName::new("tool_forge_fs_read"))
+            .call_id(ToolCallId::new("call_1"))
+            .arguments(json!({"path": "/project/src/a.rs"}));
+        let context = Context::default()
+            .add_message(ContextMessage::assistant("", Some(vec![call.clone()])))
+            .add_tool_results(vec![
+                ToolResult::from(call).success("Read /project/src/a.rs")
+            ]);
+        let view = ContextView {
+            contexts: vec![(&agent, &context)],
+            paths: PathFormat::new("/project"),
+        };
+
+        let actual = view.to_html("Conversation 1");
+
+        assert!(actual.contains("{&quot;path&quot;:&quot;src/a.rs&quot;}"));
+        assert!(actual.contains("<pre>Read src/a.rs</pre>"));
+        assert!(!actual.contains("/project/"));
+    }
+
+    #[test]
+    fn test_slug() {
+        let actual = [
+            slug("Fix the retry / backoff logic!"),
+            slug("  Ünïcode título  "),
+            slug("?!"),
+            slug(&"word ".repeat(30)),
+        ];
+
+        let expected = [
+            Some("fix-the-retry-backoff-logic".to_string()),
+            Some("ünïcode-título".to_string()),
+            None,
+            Some(format!("{}word", "word-".repeat(11))),
+        ];
+        assert_eq!(actual, expected);
+    }
+}
@@ -60,7 +60,7 @@ impl ForgeEditor {
         keybindings
     }
 
-    pub fn start(env: Environment) -> Self {
+    pub fn start(env: Environment, plugins: Vec<String>) -> Self {
         // Store file history in system config directory
         let history_file = env.history_path();
 
@@ -78,7 +78,7 @@ impl ForgeEditor {
         let edit_mode = Box::new(Emacs::new(Self::init()));
 
         let editor = Reedline::create()
-            .with_completer(Box::new(InputCompleter::new(env.cwd)))
+            .with_completer(Box::new(InputCompleter::new(env.cwd, plugins)))
             .with_history(history)
             .with_hinter(Box::new(
                 DefaultHinter::default().with_style(Style::new().fg(Color::DarkGray)),
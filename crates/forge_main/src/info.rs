@@ -1,16 +1,16 @@
 use std::fmt;
 
 use colored::Colorize;
-use forge_api::{Environment, Usage};
+use forge_api::{ConversationId, InfoProvider, InfoRow, InfoSection, ModelId, Usage, Workflow};
+use forge_display::format_count;
 use forge_tracker::VERSION;
 
-pub enum Section {
-    Title(String),
-    Items(String, String),
-}
+use crate::state::Mode;
 
+/// Sections of key/value rows, rendered with the values of each section
+/// aligned
 pub struct Info {
-    sections: Vec<Section>,
+    sections: Vec<InfoSection>,
 }
 
 impl Info {
@@ -18,66 +18,226 @@ impl Info {
         Info { sections: Vec::new() }
     }
 
+    /// Renders the sections in the order they are shown by `/info`. Sections
+    /// without rows are left out.
+    pub fn from_sections(mut sections: Vec<InfoSection>) -> Self {
+        InfoSection::sort(&mut sections);
+        sections.retain(|section| !section.rows.is_empty());
+        Info { sections }
+    }
+
     pub fn add_title(mut self, title: impl ToString) -> Self {
-        self.sections.push(Section::Title(title.to_string()));
+        self.sections.push(InfoSection::new(title));
         self
     }
 
     pub fn add_item(mut self, key: impl ToString, value: impl ToString) -> Self {
-        self.sections
-            .push(Section::Items(key.to_string(), value.to_string()));
+        let row = InfoRow {
+            key: key.to_string(),
+            value: value.to_string(),
+            highlight: false,
+        };
+        match self.sections.last_mut() {
+            Some(section) => section.rows.push(row),
+            None => self
+                .sections
+                .push(InfoSection { rows: vec![row], ..InfoSection::new("") }),
+        }
         self
     }
+}
 
-    pub fn extend(mut self, other: Info) -> Self {
-        self.sections.extend(other.sections);
-        self
+impl fmt::Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for section in &self.sections {
+            if !section.title.is_empty() {
+                writeln!(f)?;
+                writeln!(f, "{}", section.title.bold().bright_yellow())?;
+            }
+
+            let width = section
+                .rows
+                .iter()
+                .map(|row| row.key.chars().count())
+                .max()
+                .unwrap_or_default();
+            for InfoRow { key, value, highlight } in &section.rows {
+                let key = format!("{key}:");
+                let value = if *highlight {
+                    value.bold().to_string()
+                } else {
+                    value.dimmed().to_string()
+                };
+                writeln!(f, "{key:<0$} {value}", width + 1)?;
+            }
+        }
+        Ok(())
     }
 }
 
-impl From<&Usage> for Info {
-    fn from(usage: &Usage) -> Self {
-        Info::new()
-            .add_title("Usage".to_string())
-            .add_item("Prompt", usage.prompt_tokens)
-            .add_item("Completion", usage.completion_tokens)
-            .add_item("Total", usage.total_tokens)
+/// The state of the interactive session
+pub struct SessionInfo {
+    pub mode: Mode,
+    pub model: Option<ModelId>,
+    pub conversation_id: Option<ConversationId>,
+    pub dry_run: bool,
+}
+
+impl SessionInfo {
+    /// The model of the agent that handles the user's messages, ie. the one
+    /// that receives follow-up messages
+    pub fn main_model(workflow: &Workflow) -> Option<ModelId> {
+        ["user_task_update", "user_task_init"]
+            .iter()
+            .find_map(|event| {
+                workflow
+                    .agents
+                    .iter()
+                    .find(|agent| agent.subscribe.iter().any(|name| name == event))
+            })
+            .and_then(|agent| agent.model.clone())
     }
 }
 
-impl From<&Environment> for Info {
-    fn from(env: &Environment) -> Self {
-        Info::new()
-            .add_title("Environment")
-            .add_item("Version", VERSION)
-            .add_item("OS", &env.os)
-            .add_item("PID", env.pid)
-            .add_item("Working Directory", env.cwd.display())
-            .add_item("Shell", &env.shell)
-            .add_title("Paths")
-            .add_item("Config", env.base_path.display())
-            .add_item("Logs", env.log_path().display())
-            .add_item("Database", env.db_path().display())
-            .add_item("History", env.history_path().display())
+impl InfoProvider for SessionInfo {
+    fn info(&self) -> InfoSection {
+        let mut section = InfoSection::new("Session").order(0).row("Version", VERSION);
+        if let Some(model) = &self.model {
+            section = section.highlight("Model", model);
+        }
+        section = section.highlight("Mode", &self.mode);
+        if self.dry_run {
+            section = section.highlight("Dry Run", "yes");
+        }
+        if let Some(id) = &self.conversation_id {
+            section = section.row("Conversation", id);
+        }
+        section
     }
 }
 
-impl fmt::Display for Info {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for section in &self.sections {
-            match section {
-                Section::Title(title) => {
-                    writeln!(f)?;
-                    writeln!(f, "{}", title.bold().bright_yellow())?
-                }
-                Section::Items(key, value) => {
-                    writeln!(f, "{}: {}", key, value.dimmed())?;
-                }
+/// Usage of the latest turn along with the dollar cost of the conversation,
+/// when it's known
+pub struct UsageInfo(pub Usage, pub Option<f64>);
+
+impl InfoProvider for UsageInfo {
+    fn info(&self) -> InfoSection {
+        InfoSection::new("Usage")
+            .order(60)
+            .row("Prompt", format_count(self.0.prompt_tokens))
+            .row("Completion", format_count(self.0.completion_tokens))
+            .row("Total", format_count(self.0.total_tokens))
+            .row("Cost", format_cost(self.1))
+    }
+}
+
+fn format_cost(cost: Option<f64>) -> String {
+    match cost {
+        Some(cost) => format!("${cost:.4}"),
+        None => "unknown, the model's pricing isn't listed".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_api::{Agent, AgentId, InfoRegistry};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn plain(info: &Info) -> String {
+        strip_ansi_escapes::strip_str(info.to_string())
+    }
+
+    #[test]
+    fn test_values_are_aligned() {
+        let fixture = Info::from_sections(vec![InfoSection::new("Paths")
+            .row("Config", "/home/user/.config/forge")
+            .row("Working Directory", "/src")
+            .highlight("OS", "linux")]);
+
+        let actual = plain(&fixture);
+
+        let expected = "\nPaths\nConfig:            /home/user/.config/forge\nWorking Directory: /src\nOS:                linux\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_sections_are_ordered_and_empty_ones_skipped() {
+        let fixture = Info::from_sections(vec![
+            UsageInfo(Usage::default(), None).info(),
+            InfoSection::new("Git").order(40),
+            SessionInfo {
+                mode: Mode::Plan,
+                model: None,
+                conversation_id: None,
+                dry_run: true,
+            }
+            .info(),
+        ]);
+
+        let actual = plain(&fixture)
+            .lines()
+            .filter(|line| !line.is_empty() && !line.contains(':'))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, vec!["Session", "Usage"]);
+        assert!(plain(&fixture).contains("Dry Run: yes"));
+    }
+
+    #[test]
+    fn test_registered_provider_is_rendered() {
+        struct Sandbox;
+        impl InfoProvider for Sandbox {
+            fn info(&self) -> InfoSection {
+                InfoSection::new("Sandbox").highlight("Network", "disabled")
             }
         }
-        Ok(())
+
+        let registry = InfoRegistry::default().register(Sandbox);
+        let actual = plain(&Info::from_sections(registry.sections()));
+
+        assert!(actual.contains("Sandbox\nNetwork: disabled"));
     }
-}
 
-// The display_info function has been removed and its implementation will be
-// inlined in the caller
+    #[test]
+    fn test_main_model() {
+        let agent = |id: &str, model: &str, events: &[&str]| Agent {
+            id: AgentId::new(id),
+            model: Some(ModelId::new(model)),
+            subscribe: events.iter().map(|event| event.to_string()).collect(),
+            ..Agent::default()
+        };
+        let fixture = Workflow {
+            agents: vec![
+                agent("title", "haiku", &["user_task_init"]),
+                agent(
+                    "engineer",
+                    "sonnet",
+                    &["user_task_init", "user_task_update"],
+                ),
+            ],
+            ..Workflow::default()
+        };
+
+        assert_eq!(
+            SessionInfo::main_model(&fixture),
+            Some(ModelId::new("sonnet"))
+        );
+    }
+
+    #[test]
+    fn test_usage_cost() {
+        let actual = [Some(0.04812), None].map(|cost| {
+            plain(&Info::from_sections(vec![UsageInfo(
+                Usage::default(),
+                cost,
+            )
+            .info()]))
+        });
+
+        assert!(actual[0].contains("Cost:       $0.0481"));
+        assert!(actual[1].contains("Cost:       unknown, the model's pricing isn't listed"));
+    }
+}
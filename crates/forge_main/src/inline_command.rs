@@ -0,0 +1,230 @@
+//! Expands !`command` in messages into the output of the command, so that it
+//! can be shown to the agent without pasting it. Commands containing
+//! backticks are delimited with more of them, eg. !``echo `date` ``, and
+//! `\!` keeps the text as it is.
+
+use std::future::Future;
+
+use forge_api::InlineCommands;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    Command(String),
+}
+
+/// Splits a message into text and the commands to run
+pub fn parse(message: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = message;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("\\!`") {
+            text.push('!');
+            rest = &rest[2..];
+            continue;
+        }
+
+        if let Some((command, remaining)) = rest.strip_prefix('!').and_then(command) {
+            if !text.is_empty() {
+                segments.push(Segment::Text(std::mem::take(&mut text)));
+            }
+            segments.push(Segment::Command(command.to_string()));
+            rest = remaining;
+            continue;
+        }
+
+        text.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    segments
+}
+
+/// Reads a command delimited by a run of backticks at the start of the text,
+/// returns it with the text that follows
+fn command(text: &str) -> Option<(&str, &str)> {
+    let fence = text.len() - text.trim_start_matches('`').len();
+    if fence == 0 {
+        return None;
+    }
+
+    let body = &text[fence..];
+    let mut offset = 0;
+    while let Some(start) = body[offset..].find('`') {
+        let start = offset + start;
+        let run = body[start..].len() - body[start..].trim_start_matches('`').len();
+        if run == fence {
+            let command = body[..start].trim();
+            return (!command.is_empty()).then_some((command, &body[start + run..]));
+        }
+        offset = start + run;
+    }
+    None
+}
+
+/// Formats the output of a command as a fenced block headed by the command.
+/// The fence is made longer than any run of backticks in the output.
+pub fn format_output(command: &str, output: &str, failed: bool, max_bytes: usize) -> String {
+    let output = truncate(output.trim_end(), max_bytes);
+    let longest_run = output
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat((longest_run + 1).max(3));
+    let status = if failed { " (failed)" } else { "" };
+
+    format!("\n{fence}text\n$ {command}{status}\n{output}\n{fence}\n")
+}
+
+/// Cuts the output after `max_bytes`, at a character boundary
+fn truncate(output: &str, max_bytes: usize) -> String {
+    if output.len() <= max_bytes {
+        return output.to_string();
+    }
+
+    let mut end = max_bytes;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "{}\n… output truncated, {} more bytes",
+        &output[..end],
+        output.len() - end
+    )
+}
+
+/// Runs the commands in the message one after another and substitutes their
+/// output. A command that fails is substituted with its error, the message is
+/// sent regardless.
+pub async fn expand<F, Fut>(message: &str, config: &InlineCommands, mut run: F) -> String
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+{
+    if !config.enabled {
+        return message.to_string();
+    }
+
+    let mut expanded = String::new();
+    for segment in parse(message) {
+        match segment {
+            Segment::Text(text) => expanded.push_str(&text),
+            Segment::Command(command) => {
+                let (output, failed) = match run(command.clone()).await {
+                    Ok(output) => (output, false),
+                    Err(error) => (error.to_string(), true),
+                };
+                expanded.push_str(&format_output(
+                    &command,
+                    &output,
+                    failed,
+                    config.max_output_bytes,
+                ));
+            }
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn text(value: &str) -> Segment {
+        Segment::Text(value.to_string())
+    }
+
+    fn command(value: &str) -> Segment {
+        Segment::Command(value.to_string())
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let actual = parse("Tests fail: !`cargo test` and !`git status` here");
+
+        let expected = vec![
+            text("Tests fail: "),
+            command("cargo test"),
+            text(" and "),
+            command("git status"),
+            text(" here"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_backticks_inside_command() {
+        let actual = parse("Run !`` echo `date` `` now");
+
+        let expected = vec![text("Run "), command("echo `date`"), text(" now")];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_escaped_and_unterminated() {
+        let actual = parse("Type \\!`ls`, a `code` span or !`pwd");
+
+        let expected = vec![text("Type !`ls`, a `code` span or !`pwd")];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_output() {
+        let actual = format_output("cat README.md", "```rust\nfn main() {}\n```\n", false, 100);
+
+        let expected = "\n````text\n$ cat README.md\n```rust\nfn main() {}\n```\n````\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_output_caps_size() {
+        let actual = format_output("yes", "ééé", true, 3);
+
+        let expected = "\n```text\n$ yes (failed)\né\n… output truncated, 4 more bytes\n```\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_expand_runs_commands_in_order() {
+        let config = InlineCommands::default();
+        let mut ran = Vec::new();
+
+        let actual = expand("a !`one` b !`two`", &config, |command| {
+            ran.push(command.clone());
+            async move {
+                match command.as_str() {
+                    "one" => Ok("1".to_string()),
+                    _ => Err(anyhow::anyhow!("not found")),
+                }
+            }
+        })
+        .await;
+
+        let expected = "a \n```text\n$ one\n1\n```\n b \n```text\n$ two (failed)\nnot found\n```\n";
+        assert_eq!(actual, expected);
+        assert_eq!(ran, vec!["one", "two"]);
+    }
+
+    #[tokio::test]
+    async fn test_expand_disabled_passes_message_through() {
+        let config = InlineCommands { enabled: false, ..Default::default() };
+        let mut ran = 0;
+
+        let actual = expand("keep \\!`this` and !`that`", &config, |_| {
+            ran += 1;
+            async { Ok::<_, anyhow::Error>(String::new()) }
+        })
+        .await;
+
+        assert_eq!(actual, "keep \\!`this` and !`that`");
+        assert_eq!(ran, 0);
+    }
+}
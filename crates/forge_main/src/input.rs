@@ -8,6 +8,7 @@ use tokio::fs;
 use crate::console::CONSOLE;
 use crate::editor::{ForgeEditor, ReadResult};
 use crate::model::{Command, UserInput};
+use crate::plugin::Plugins;
 use crate::prompt::ForgePrompt;
 use crate::state::Mode;
 
@@ -37,7 +38,9 @@ impl UserInput for Console {
 
     async fn prompt(&self, input: Option<Self::PromptInput>) -> anyhow::Result<Command> {
         CONSOLE.writeln("")?;
-        let mut engine = ForgeEditor::start(self.env.clone());
+        // Discovered on every prompt so plugins added meanwhile can be used
+        let plugins = Plugins::discover(&self.env);
+        let mut engine = ForgeEditor::start(self.env.clone(), plugins.commands());
         let prompt: ForgePrompt = input.map(Into::into).unwrap_or_default();
 
         loop {
@@ -50,7 +53,7 @@ impl UserInput for Console {
                     tokio::spawn(
                         crate::ui::TRACKER.dispatch(forge_tracker::EventKind::Prompt(text.clone())),
                     );
-                    return Ok(Command::parse(&text));
+                    return Ok(plugins.parse(&text));
                 }
                 Err(e) => {
                     CONSOLE.writeln(TitleFormat::failed(e.to_string()).format())?;
@@ -65,15 +68,17 @@ pub enum PromptInput {
         title: Option<String>,
         usage: Option<Usage>,
         mode: Mode,
+        queued: usize,
     },
 }
 
 impl From<PromptInput> for ForgePrompt {
     fn from(input: PromptInput) -> Self {
         match input {
-            PromptInput::Update { title, usage, mode } => {
+            PromptInput::Update { title, usage, mode, queued } => {
                 let mut prompt = ForgePrompt::default();
                 prompt.mode(mode);
+                prompt.queued(queued);
                 if let Some(title) = title {
                     prompt.title(title);
                 }
@@ -1,15 +1,27 @@
+mod activity;
+mod auth;
 mod banner;
+mod chat_stream;
 mod cli;
 mod completer;
 mod console;
+mod context_view;
 mod editor;
 mod info;
+mod inline_command;
 mod input;
 mod model;
 mod normalize;
+mod plugin;
 mod prompt;
+mod queue;
+mod rebuild;
+mod snapshot;
 mod state;
+mod stats;
 mod ui;
+mod upgrade;
+mod wrap;
 
-pub use cli::Cli;
+pub use cli::{Cli, TopLevelCommand};
 pub use ui::UI;
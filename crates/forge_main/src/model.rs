@@ -1,10 +1,13 @@
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use async_trait::async_trait;
-use forge_api::Model;
+use forge_api::{Model, ModelPricing, TransformerReport, Usage};
+use forge_display::format_count;
 
+use crate::activity::DisplayMode;
 use crate::info::Info;
+use crate::plugin::Plugin;
 
 fn humanize_context_length(length: u64) -> String {
     if length >= 1_000_000 {
@@ -50,6 +53,124 @@ impl From<&[Model]> for Info {
     }
 }
 
+/// Dollar cost of prompt and completion tokens
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TokenCost {
+    pub prompt: f64,
+    pub completion: f64,
+}
+
+impl TokenCost {
+    pub fn new(usage: &Usage, pricing: &ModelPricing) -> Self {
+        Self {
+            prompt: usage.prompt_tokens as f64 * pricing.prompt,
+            completion: usage.completion_tokens as f64 * pricing.completion,
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.prompt + self.completion
+    }
+}
+
+impl std::ops::Add for TokenCost {
+    type Output = TokenCost;
+
+    fn add(self, other: TokenCost) -> TokenCost {
+        TokenCost {
+            prompt: self.prompt + other.prompt,
+            completion: self.completion + other.completion,
+        }
+    }
+}
+
+/// Tokens used by the current conversation and their cost, unknown once a
+/// response came from a model without pricing
+pub struct ConversationCost {
+    pub usage: Usage,
+    pub cost: Option<TokenCost>,
+}
+
+impl From<&ConversationCost> for Info {
+    fn from(cost: &ConversationCost) -> Self {
+        let usage = &cost.usage;
+        let info = Info::new().add_title("Cost");
+        match cost.cost {
+            Some(TokenCost { prompt, completion }) => info
+                .add_item(
+                    "Prompt",
+                    format!("{} tokens, ${prompt:.4}", format_count(usage.prompt_tokens)),
+                )
+                .add_item(
+                    "Completion",
+                    format!(
+                        "{} tokens, ${completion:.4}",
+                        format_count(usage.completion_tokens)
+                    ),
+                )
+                .add_item(
+                    "Total",
+                    format!(
+                        "{} tokens, ${:.4}",
+                        format_count(usage.total_tokens),
+                        prompt + completion
+                    ),
+                ),
+            None => info
+                .add_item(
+                    "Prompt",
+                    format!("{} tokens", format_count(usage.prompt_tokens)),
+                )
+                .add_item(
+                    "Completion",
+                    format!("{} tokens", format_count(usage.completion_tokens)),
+                )
+                .add_item(
+                    "Total",
+                    format!(
+                        "{} tokens, cost unknown since the model's pricing isn't listed",
+                        format_count(usage.total_tokens)
+                    ),
+                ),
+        }
+    }
+}
+
+impl From<&TransformerReport> for Info {
+    fn from(report: &TransformerReport) -> Self {
+        let mut info = Info::new().add_title("Transformers");
+        for step in &report.pipeline.steps {
+            let models = match &step.models {
+                Some(pattern) => format!("models matching '{pattern}'"),
+                None => "every model".to_string(),
+            };
+            info = info.add_item(step.kind, models);
+        }
+
+        info = info.add_title("Last request");
+        if report.last_request.is_empty() {
+            return info.add_item("Sent", "no request yet");
+        }
+        for stage in &report.last_request {
+            let outcome = if !stage.applied {
+                "skipped".to_string()
+            } else if !stage.changed {
+                "unchanged".to_string()
+            } else {
+                format!(
+                    "changed, {} → {} messages, {} → {} bytes",
+                    stage.messages_before,
+                    stage.messages_after,
+                    format_count(stage.bytes_before as u64),
+                    format_count(stage.bytes_after as u64)
+                )
+            };
+            info = info.add_item(stage.step.kind, outcome);
+        }
+        info
+    }
+}
+
 /// Represents user input types in the chat application.
 ///
 /// This enum encapsulates all forms of input including:
@@ -80,8 +201,88 @@ pub enum Command {
     /// Switch to "help" mode.
     /// This can be triggered with the '/help' command.
     Help,
-    /// Dumps the current conversation into a json file
-    Dump,
+    /// Dumps the current conversation into a json file, or a shareable html
+    /// transcript when set.
+    /// This can be triggered with the '/dump' and '/dump html' commands.
+    Dump { html: bool },
+    /// Shows the context of the current conversation as it's sent to the
+    /// provider, as JSON when set.
+    /// This can be triggered with the '/context' and '/context json' commands.
+    Context { json: bool },
+    /// Compacts the context of the current conversation.
+    /// This can be triggered with the '/compact' command.
+    Compact,
+    /// Shows the tokens used by the current conversation and their cost.
+    /// This can be triggered with the '/cost' command.
+    Cost,
+    /// Adds tags to the current conversation.
+    /// This can be triggered with the '/tag add <tag> [tag...]' command.
+    Tag(Vec<String>),
+    /// Lifts a write-protection rule until the session ends.
+    /// This can be triggered with the '/unprotect <pattern>' command.
+    Unprotect(String),
+    /// Lists, clears or reorders the messages queued while a turn runs.
+    /// This can be triggered with the '/queue' command.
+    Queue(QueueCommand),
+    /// Lists or changes the settings of config.yaml, or moves them to or from
+    /// a file.
+    /// This can be triggered with the '/config', '/config set <key> <value>',
+    /// '/config export <path>' and '/config import <path>' commands.
+    Config(ConfigCommand),
+    /// Switches between streaming the agent's text and one line per tool call.
+    /// This can be triggered with the '/display full' and '/display compact'
+    /// commands.
+    Display(DisplayMode),
+    /// Shows internals of the session that help tracking down problems.
+    /// This can be triggered with the '/debug transformers' command.
+    Debug(DebugCommand),
+    /// Runs a plugin found in `.forge/commands` or the global config
+    /// directory.
+    /// This can be triggered with '/<name> [args]'.
+    Plugin { plugin: Plugin, args: String },
+}
+
+/// What '/config' does with the settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigCommand {
+    /// '/config' or '/config list' shows the settings, their types and
+    /// whether they were changed
+    List,
+    /// '/config set <key> <value>' validates and stores a setting
+    Set { key: String, value: String },
+    /// '/config export <path>' writes the settings to the file
+    Export(PathBuf),
+    /// '/config import <path>' validates the settings in the file and uses
+    /// them from now on
+    Import(PathBuf),
+}
+
+/// Resolves a leading '~' of a path typed at the prompt to the home
+/// directory, the shell isn't there to do it
+pub fn expand_home(path: &Path, home: Option<&Path>) -> PathBuf {
+    match (path.strip_prefix("~"), home) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// What '/debug' shows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugCommand {
+    /// '/debug transformers' shows the request transformers in the order they
+    /// run and what each did to the last request
+    Transformers,
+}
+
+/// What '/queue' does with the queued messages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueueCommand {
+    /// '/queue' or '/queue list'
+    List,
+    /// '/queue clear'
+    Clear,
+    /// '/queue jump <n>' cancels the running turn and sends message n next
+    Jump(usize),
 }
 
 impl Command {
@@ -101,6 +302,15 @@ impl Command {
             "/plan".to_string(),
             "/help".to_string(),
             "/dump".to_string(),
+            "/context".to_string(),
+            "/compact".to_string(),
+            "/cost".to_string(),
+            "/tag".to_string(),
+            "/unprotect".to_string(),
+            "/queue".to_string(),
+            "/config".to_string(),
+            "/display".to_string(),
+            "/debug".to_string(),
         ]
     }
 
@@ -122,11 +332,59 @@ impl Command {
             "/info" => Command::Info,
             "/exit" => Command::Exit,
             "/models" => Command::Models,
-            "/dump" => Command::Dump,
+            "/dump" => Command::Dump { html: false },
+            "/dump html" => Command::Dump { html: true },
+            "/context" => Command::Context { json: false },
+            "/context json" => Command::Context { json: true },
             "/act" => Command::Act,
             "/plan" => Command::Plan,
             "/help" => Command::Help,
-            text => Command::Message(text.to_string()),
+            "/compact" => Command::Compact,
+            "/cost" => Command::Cost,
+            "/queue" | "/queue list" => Command::Queue(QueueCommand::List),
+            "/config" | "/config list" => Command::Config(ConfigCommand::List),
+            "/queue clear" => Command::Queue(QueueCommand::Clear),
+            "/display full" => Command::Display(DisplayMode::Full),
+            "/display compact" => Command::Display(DisplayMode::Compact),
+            "/debug transformers" => Command::Debug(DebugCommand::Transformers),
+            text if text.starts_with("/queue jump ") => {
+                match text["/queue jump ".len()..].trim().parse() {
+                    Ok(position) => Command::Queue(QueueCommand::Jump(position)),
+                    Err(_) => Command::Message(text.to_string()),
+                }
+            }
+            text => {
+                if let Some(tags) = text.strip_prefix("/tag add ") {
+                    let tags = tags
+                        .split([',', ' '])
+                        .filter(|tag| !tag.is_empty())
+                        .map(str::to_string)
+                        .collect::<Vec<_>>();
+                    if !tags.is_empty() {
+                        return Command::Tag(tags);
+                    }
+                }
+                if let Some(pattern) = text.strip_prefix("/unprotect ") {
+                    if !pattern.trim().is_empty() {
+                        return Command::Unprotect(pattern.trim().to_string());
+                    }
+                }
+                if let Some(setting) = text.strip_prefix("/config set ") {
+                    if let Some((key, value)) = setting.trim().split_once(char::is_whitespace) {
+                        return Command::Config(ConfigCommand::Set {
+                            key: key.to_string(),
+                            value: value.trim().to_string(),
+                        });
+                    }
+                }
+                if let Some(path) = text.strip_prefix("/config export ") {
+                    return Command::Config(ConfigCommand::Export(PathBuf::from(path.trim())));
+                }
+                if let Some(path) = text.strip_prefix("/config import ") {
+                    return Command::Config(ConfigCommand::Import(PathBuf::from(path.trim())));
+                }
+                Command::Message(text.to_string())
+            }
         }
     }
 }
@@ -160,3 +418,218 @@ pub trait UserInput {
     /// * `Err` - An error occurred during input processing
     async fn prompt(&self, input: Option<Self::PromptInput>) -> anyhow::Result<Command>;
 }
+
+#[cfg(test)]
+mod tests {
+    use forge_api::{TransformerKind, TransformerPipeline, TransformerStage, TransformerStep};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn usage() -> Usage {
+        Usage {
+            prompt_tokens: 12_000,
+            completion_tokens: 800,
+            total_tokens: 12_800,
+        }
+    }
+
+    fn plain(info: Info) -> String {
+        strip_ansi_escapes::strip_str(info.to_string())
+    }
+
+    #[test]
+    fn test_parse_cost() {
+        let actual = [" /cost ", "/cost now"].map(Command::parse);
+
+        let expected = [Command::Cost, Command::Message("/cost now".to_string())];
+        assert_eq!(actual, expected);
+        assert!(Command::available_commands().contains(&"/cost".to_string()));
+    }
+
+    #[test]
+    fn test_parse_queue() {
+        let actual = [
+            "/queue",
+            "/queue list",
+            "/queue clear",
+            "/queue jump 2",
+            "/queue jump x",
+        ]
+        .map(Command::parse);
+
+        let expected = [
+            Command::Queue(QueueCommand::List),
+            Command::Queue(QueueCommand::List),
+            Command::Queue(QueueCommand::Clear),
+            Command::Queue(QueueCommand::Jump(2)),
+            Command::Message("/queue jump x".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_dump() {
+        let actual = ["/dump", "/dump html"].map(Command::parse);
+
+        let expected = [Command::Dump { html: false }, Command::Dump { html: true }];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_context() {
+        let actual = ["/context", " /context json ", "/context yaml"].map(Command::parse);
+
+        let expected = [
+            Command::Context { json: false },
+            Command::Context { json: true },
+            Command::Message("/context yaml".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_display() {
+        let actual = ["/display compact", "/display full", "/display"].map(Command::parse);
+
+        let expected = [
+            Command::Display(DisplayMode::Compact),
+            Command::Display(DisplayMode::Full),
+            Command::Message("/display".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        let actual = ["/tag add forge_main, ui", "/tag add  ", "/tag"].map(Command::parse);
+
+        let expected = [
+            Command::Tag(vec!["forge_main".to_string(), "ui".to_string()]),
+            Command::Message("/tag add".to_string()),
+            Command::Message("/tag".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_debug() {
+        let actual = ["/debug transformers", "/debug"].map(Command::parse);
+
+        let expected = [
+            Command::Debug(DebugCommand::Transformers),
+            Command::Message("/debug".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let actual = [
+            "/config export ~/forge.yaml",
+            "/config import  backup/config.yaml ",
+            "/config",
+            "/config list",
+            "/config set queue.max_cost  2.5",
+            "/config set markdown.enabled",
+        ]
+        .map(Command::parse);
+
+        let expected = [
+            Command::Config(ConfigCommand::Export(PathBuf::from("~/forge.yaml"))),
+            Command::Config(ConfigCommand::Import(PathBuf::from("backup/config.yaml"))),
+            Command::Config(ConfigCommand::List),
+            Command::Config(ConfigCommand::List),
+            Command::Config(ConfigCommand::Set {
+                key: "queue.max_cost".to_string(),
+                value: "2.5".to_string(),
+            }),
+            Command::Message("/config set markdown.enabled".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = Some(Path::new("/home/user"));
+
+        let actual = [
+            expand_home(Path::new("~/forge.yaml"), home),
+            expand_home(Path::new("~"), home),
+            expand_home(Path::new("backup/~.yaml"), home),
+            expand_home(Path::new("~/forge.yaml"), None),
+        ];
+
+        let expected = [
+            PathBuf::from("/home/user/forge.yaml"),
+            PathBuf::from("/home/user"),
+            PathBuf::from("backup/~.yaml"),
+            PathBuf::from("~/forge.yaml"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_cost_breakdown() {
+        let pricing = ModelPricing { prompt: 0.000003, completion: 0.000015 };
+        let fixture = ConversationCost {
+            usage: usage(),
+            cost: Some(TokenCost::new(&usage(), &pricing)),
+        };
+
+        let actual = plain(Info::from(&fixture));
+
+        let expected = "\nCost\nPrompt:     12,000 tokens, $0.0360\nCompletion: 800 tokens, $0.0120\nTotal:      12,800 tokens, $0.0480\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_cost_breakdown_without_pricing() {
+        let fixture = ConversationCost { usage: usage(), cost: None };
+
+        let actual = plain(Info::from(&fixture));
+
+        let expected = "\nCost\nPrompt:     12,000 tokens\nCompletion: 800 tokens\nTotal:      12,800 tokens, cost unknown since the model's pricing isn't listed\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_transformer_report() {
+        let step = |kind, models: Option<&str>| TransformerStep {
+            kind,
+            models: models.map(str::to_string),
+        };
+        let stage = |step, applied, changed, bytes_after| TransformerStage {
+            step,
+            applied,
+            changed,
+            messages_before: 4,
+            messages_after: 4,
+            bytes_before: 1200,
+            bytes_after,
+        };
+        let stringify = step(TransformerKind::StringifyToolResults, None);
+        let drop = step(TransformerKind::DropToolCalls, Some("mistral"));
+        let fixture = TransformerReport {
+            pipeline: TransformerPipeline { steps: vec![stringify.clone(), drop.clone()] },
+            last_request: vec![stage(stringify, true, true, 1350), stage(drop, false, false, 1350)],
+        };
+
+        let actual = plain(Info::from(&fixture));
+
+        let expected = "\nTransformers\nstringify_tool_results: every model\ndrop_tool_calls:        models matching 'mistral'\n\nLast request\nstringify_tool_results: changed, 4 → 4 messages, 1,200 → 1,350 bytes\ndrop_tool_calls:        skipped\n";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_transformer_report_before_a_request() {
+        let fixture = TransformerReport {
+            pipeline: TransformerPipeline { steps: vec![] },
+            last_request: vec![],
+        };
+
+        let actual = plain(Info::from(&fixture));
+
+        assert!(actual.ends_with("\nLast request\nSent: no request yet\n"));
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use forge_api::{ConversationId, Environment, ModelId, PROJECT_CONFIG_DIR};
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command as Process;
+
+use crate::model::Command;
+
+/// Directory holding the plugins, in `.forge` of the project and in the
+/// global config directory
+pub const PLUGIN_DIR: &str = "commands";
+
+/// Exit code with which a plugin asks for its output to be sent to the agent
+/// as a message instead of being printed
+pub const SUBMIT_EXIT_CODE: i32 = 10;
+
+/// How long a plugin may run before it's killed
+pub const PLUGIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An executable run by typing `/<name>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// What the plugin is told about the session, as JSON on its stdin
+#[derive(Debug, Serialize)]
+pub struct PluginContext {
+    pub conversation_id: Option<ConversationId>,
+    pub cwd: PathBuf,
+    pub model: Option<ModelId>,
+}
+
+/// What to do with the output of a plugin
+#[derive(Debug, PartialEq, Eq)]
+pub enum PluginOutput {
+    Print(String),
+    Submit(String),
+}
+
+/// The plugins found in the project and global directories
+#[derive(Debug, Default)]
+pub struct Plugins {
+    plugins: BTreeMap<String, PathBuf>,
+    /// Plugins that aren't used since a built-in command has their name
+    shadowed: Vec<Plugin>,
+}
+
+impl Plugins {
+    /// Finds the plugins of the session's project and config directory
+    pub fn discover(env: &Environment) -> Self {
+        Self::discover_in(&[
+            env.cwd.join(PROJECT_CONFIG_DIR).join(PLUGIN_DIR),
+            env.base_path.join(PLUGIN_DIR),
+        ])
+    }
+
+    /// Finds the executables of `dirs`, a plugin of an earlier directory
+    /// taking precedence over one with the same name in a later one
+    fn discover_in(dirs: &[PathBuf]) -> Self {
+        let builtins = Command::available_commands();
+        let mut found = Self::default();
+        for dir in dirs {
+            for plugin in executables(dir) {
+                if builtins.contains(&format!("/{}", plugin.name)) {
+                    if !found.shadowed.iter().any(|p| p.name == plugin.name) {
+                        found.shadowed.push(plugin);
+                    }
+                } else {
+                    found.plugins.entry(plugin.name).or_insert(plugin.path);
+                }
+            }
+        }
+        found
+    }
+
+    /// The commands running the plugins, eg. `/lint`
+    pub fn commands(&self) -> Vec<String> {
+        self.plugins.keys().map(|name| format!("/{name}")).collect()
+    }
+
+    /// Plugins named like a built-in command, which are never run
+    pub fn shadowed(&self) -> &[Plugin] {
+        &self.shadowed
+    }
+
+    /// Parses the input, resolving `/<name> args` to the plugin when no
+    /// built-in command matches
+    pub fn parse(&self, input: &str) -> Command {
+        let command = Command::parse(input);
+        let Command::Message(text) = &command else {
+            return command;
+        };
+        let Some(call) = text.strip_prefix('/') else {
+            return command;
+        };
+        let (name, args) = call.split_once(char::is_whitespace).unwrap_or((call, ""));
+        match self.plugins.get(name) {
+            Some(path) => Command::Plugin {
+                plugin: Plugin { name: name.to_string(), path: path.clone() },
+                args: args.trim().to_string(),
+            },
+            None => command,
+        }
+    }
+}
+
+/// The executable files of `dir`, none when it doesn't exist
+fn executables(dir: &Path) -> Vec<Plugin> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut plugins = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_executable(path))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            Some(Plugin { name, path })
+        })
+        .collect::<Vec<_>>();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+impl Plugin {
+    /// Runs the plugin in `cwd` with the arguments split on whitespace and
+    /// the context on stdin. Fails when it exits with an error or runs longer
+    /// than `timeout`.
+    pub async fn run(
+        &self,
+        args: &str,
+        context: &PluginContext,
+        timeout: Duration,
+    ) -> anyhow::Result<PluginOutput> {
+        let mut child = Process::new(&self.path)
+            .args(args.split_whitespace())
+            .current_dir(&context.cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", self.path.display()))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            // A plugin that doesn't read its stdin closes it, which isn't an error
+            let _ = stdin.write_all(&serde_json::to_vec(context)?).await;
+        }
+
+        let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+            Ok(output) => output?,
+            Err(_) => bail!("/{} didn't finish within {timeout:?}", self.name),
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string();
+        match output.status.code() {
+            Some(0) => Ok(PluginOutput::Print(stdout)),
+            Some(SUBMIT_EXIT_CODE) => Ok(PluginOutput::Submit(stdout)),
+            _ => bail!(
+                "/{} failed with {}: {}",
+                self.name,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn script(dir: &Path, name: &str, body: &str) -> PathBuf {
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn context(cwd: &Path) -> PluginContext {
+        PluginContext {
+            conversation_id: None,
+            cwd: cwd.to_path_buf(),
+            model: Some(ModelId::new("anthropic/claude-3.7-sonnet")),
+        }
+    }
+
+    #[test]
+    fn test_discovery_prefers_the_project() {
+        let temp = tempfile::tempdir().unwrap();
+        let project = temp.path().join("project");
+        let global = temp.path().join("global");
+        let project_lint = script(&project, "lint", "echo project");
+        script(&global, "lint", "echo global");
+        let global_todo = script(&global, "todo.sh", "echo todo");
+        std::fs::write(global.join("notes.txt"), "not executable").unwrap();
+
+        let actual = Plugins::discover_in(&[project, global]);
+
+        assert_eq!(actual.commands(), vec!["/lint", "/todo"]);
+        assert_eq!(actual.plugins["lint"], project_lint);
+        assert_eq!(actual.plugins["todo"], global_todo);
+    }
+
+    #[test]
+    fn test_builtin_names_are_shadowed() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = script(temp.path(), "cost", "echo plugin");
+
+        let fixture = Plugins::discover_in(&[temp.path().to_path_buf()]);
+
+        assert_eq!(fixture.commands(), Vec::<String>::new());
+        assert_eq!(
+            fixture.shadowed(),
+            &[Plugin { name: "cost".to_string(), path }]
+        );
+        assert_eq!(fixture.parse("/cost"), Command::Cost);
+    }
+
+    #[test]
+    fn test_parse_plugin_call() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = script(temp.path(), "lint", "echo");
+        let fixture = Plugins::discover_in(&[temp.path().to_path_buf()]);
+
+        let actual =
+            ["/lint src --fix", "/lint", "/linter", "lint"].map(|input| fixture.parse(input));
+
+        let plugin = Plugin { name: "lint".to_string(), path };
+        let expected = [
+            Command::Plugin { plugin: plugin.clone(), args: "src --fix".to_string() },
+            Command::Plugin { plugin, args: String::new() },
+            Command::Message("/linter".to_string()),
+            Command::Message("lint".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_output_is_printed() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = script(temp.path(), "echo", r#"echo "$1 $2"; cat; echo; pwd"#);
+        let plugin = Plugin { name: "echo".to_string(), path };
+
+        let actual = plugin
+            .run("a b", &context(temp.path()), PLUGIN_TIMEOUT)
+            .await
+            .unwrap();
+
+        let PluginOutput::Print(output) = actual else {
+            panic!("expected the output to be printed");
+        };
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "a b");
+        let stdin: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(stdin["model"], "anthropic/claude-3.7-sonnet");
+        assert_eq!(stdin["conversation_id"], serde_json::Value::Null);
+        assert_eq!(
+            Path::new(lines[2]).canonicalize().unwrap(),
+            temp.path().canonicalize().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_output_is_submitted_with_the_exit_code() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = script(
+            temp.path(),
+            "review",
+            &format!("echo 'Review the diff'; exit {SUBMIT_EXIT_CODE}"),
+        );
+        let plugin = Plugin { name: "review".to_string(), path };
+
+        let actual = plugin
+            .run("", &context(temp.path()), PLUGIN_TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(actual, PluginOutput::Submit("Review the diff".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_failure_and_timeout() {
+        let temp = tempfile::tempdir().unwrap();
+        let failing = Plugin {
+            name: "fail".to_string(),
+            path: script(temp.path(), "fail", "echo broken >&2; exit 1"),
+        };
+        let slow = Plugin {
+            name: "slow".to_string(),
+            path: script(temp.path(), "slow", "sleep 5"),
+        };
+
+        let failed = failing
+            .run("", &context(temp.path()), PLUGIN_TIMEOUT)
+            .await
+            .unwrap_err();
+        let timed_out = slow
+            .run("", &context(temp.path()), Duration::from_millis(200))
+            .await
+            .unwrap_err();
+
+        assert!(failed.to_string().contains("broken"));
+        assert_eq!(timed_out.to_string(), "/slow didn't finish within 200ms");
+    }
+}
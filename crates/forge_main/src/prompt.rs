@@ -2,6 +2,7 @@ use std::borrow::Cow;
 
 use derive_setters::Setters;
 use forge_api::Usage;
+use forge_display::format_count;
 use nu_ansi_term::{Color, Style};
 use reedline::{Prompt, PromptHistorySearchStatus};
 
@@ -19,6 +20,8 @@ pub struct ForgePrompt {
     title: Option<String>,
     usage: Option<Usage>,
     mode: Mode,
+    /// Number of messages waiting to be sent
+    queued: usize,
 }
 
 impl Prompt for ForgePrompt {
@@ -40,7 +43,10 @@ impl Prompt for ForgePrompt {
             .as_ref()
             .unwrap_or(&Usage::default())
             .total_tokens;
-        let usage_text = format!("[{}/{}]", self.mode, usage);
+        let mut usage_text = format!("[{}/{}]", self.mode, format_count(usage));
+        if self.queued > 0 {
+            usage_text.push_str(&format!(" queued ({})", self.queued));
+        }
         Cow::Owned(
             Style::new()
                 .bold()
@@ -127,6 +133,19 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_render_prompt_right_with_queued_messages() {
+        let mut prompt = ForgePrompt::default();
+        prompt.queued(2);
+        let actual = prompt.render_prompt_right();
+        let expected = Style::new()
+            .bold()
+            .fg(Color::DarkGray)
+            .paint("[ACT/0] queued (2)")
+            .to_string();
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_render_prompt_indicator_with_title() {
         let mut prompt = ForgePrompt::default();
@@ -0,0 +1,328 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// How often the reader checks whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How a turn ended, which decides whether the next queued message is sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TurnEnd {
+    /// The stream ran to its end
+    #[default]
+    Completed,
+    /// The user pressed Ctrl-C
+    Cancelled,
+    /// The user cancelled the turn to send a queued message right away
+    Jumped,
+    /// The turn stopped on a fatal error
+    Failed,
+}
+
+/// Messages typed while a turn runs. They are sent one after the other as
+/// turns complete, and wait for the user after a turn was cancelled or
+/// failed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromptQueue {
+    messages: VecDeque<String>,
+}
+
+impl PromptQueue {
+    /// File holding the queue of the sessions started in `cwd`, so sessions
+    /// in other projects don't send each other's messages
+    pub fn path(queues_dir: &Path, cwd: &Path) -> PathBuf {
+        let digest = Sha256::digest(cwd.as_os_str().as_encoded_bytes());
+        queues_dir.join(format!("{digest:x}.json"))
+    }
+
+    /// Reads the queue left by the previous session, an empty queue when
+    /// there is none
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse queue {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => {
+                Err(err).with_context(|| format!("Failed to read queue {}", path.display()))
+            }
+        }
+    }
+
+    /// Writes the queue so a restart doesn't lose it, the file is removed
+    /// once the queue is empty
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if self.messages.is_empty() {
+            return match std::fs::remove_file(path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    Err(err).with_context(|| format!("Failed to remove queue {}", path.display()))
+                }
+                _ => Ok(()),
+            };
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write queue {}", path.display()))
+    }
+
+    /// Adds a message to the end of the queue, returns how many are queued
+    pub fn enqueue(&mut self, message: impl Into<String>) -> usize {
+        self.messages.push_back(message.into());
+        self.messages.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn messages(&self) -> impl Iterator<Item = &str> {
+        self.messages.iter().map(String::as_str)
+    }
+
+    /// Removes every queued message, returns how many there were
+    pub fn clear(&mut self) -> usize {
+        let count = self.messages.len();
+        self.messages.clear();
+        count
+    }
+
+    /// Moves the message at the one-based `position` to the front so it's
+    /// sent next
+    pub fn jump(&mut self, position: usize) -> Result<()> {
+        let message = position
+            .checked_sub(1)
+            .and_then(|index| self.messages.remove(index))
+            .with_context(|| {
+                format!(
+                    "No queued message {position}, {} are queued",
+                    self.messages.len()
+                )
+            })?;
+        self.messages.push_front(message);
+        Ok(())
+    }
+
+    /// The message to send after a turn that ended with `end`. Nothing is
+    /// sent after a cancelled or failed turn, or once the budget is spent.
+    pub fn next(&mut self, end: TurnEnd, within_budget: bool) -> Option<String> {
+        match end {
+            TurnEnd::Jumped => self.messages.pop_front(),
+            TurnEnd::Completed if within_budget => self.messages.pop_front(),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the lines typed while a turn runs. Reading stops as soon as the
+/// reader is stopped, so prompts asked during the turn get the user's input.
+pub struct ReadAhead {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+    lines: mpsc::UnboundedReceiver<String>,
+}
+
+impl ReadAhead {
+    /// Starts reading from the terminal. The terminal is left in line mode,
+    /// so typed text is echoed and lines arrive once Enter is pressed.
+    pub fn start() -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (tx, lines) = mpsc::unbounded_channel();
+        let handle = tokio::task::spawn_blocking({
+            let stop = stop.clone();
+            move || {
+                use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind};
+
+                let mut line = String::new();
+                while !stop.load(Ordering::SeqCst) {
+                    if !poll(POLL_INTERVAL).unwrap_or(false) {
+                        continue;
+                    }
+                    let Ok(Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. })) = read()
+                    else {
+                        continue;
+                    };
+                    match code {
+                        KeyCode::Enter => {
+                            let text = std::mem::take(&mut line);
+                            if !text.trim().is_empty() && tx.send(text.trim().to_string()).is_err()
+                            {
+                                break;
+                            }
+                        }
+                        KeyCode::Char(c) => line.push(c),
+                        KeyCode::Tab => line.push('\t'),
+                        _ => {}
+                    }
+                }
+            }
+        });
+
+        Self { stop, handle, lines }
+    }
+
+    /// The next line typed, waits forever once the reader stopped
+    pub async fn next_line(&mut self) -> String {
+        match self.lines.recv().await {
+            Some(line) => line,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Stops reading and waits for the terminal to be released
+    pub async fn stop(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = self.handle.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn fixture(messages: &[&str]) -> PromptQueue {
+        let mut queue = PromptQueue::default();
+        for message in messages {
+            queue.enqueue(*message);
+        }
+        queue
+    }
+
+    #[test]
+    fn test_enqueue_during_stream() {
+        let mut queue = PromptQueue::default();
+
+        let actual = [queue.enqueue("add tests"), queue.enqueue("run them")];
+
+        assert_eq!(actual, [1, 2]);
+        assert_eq!(
+            queue.messages().collect::<Vec<_>>(),
+            vec!["add tests", "run them"]
+        );
+    }
+
+    #[test]
+    fn test_dispatches_in_order_on_completion() {
+        let mut queue = fixture(&["one", "two"]);
+
+        let actual = [
+            queue.next(TurnEnd::Completed, true),
+            queue.next(TurnEnd::Completed, true),
+            queue.next(TurnEnd::Completed, true),
+        ];
+
+        let expected = [Some("one".to_string()), Some("two".to_string()), None];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_stops_on_fatal_error() {
+        let mut queue = fixture(&["one", "two"]);
+
+        let actual = queue.next(TurnEnd::Failed, true);
+
+        assert_eq!(actual, None);
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_waits_after_cancel() {
+        let mut queue = fixture(&["one"]);
+
+        let actual = queue.next(TurnEnd::Cancelled, true);
+
+        assert_eq!(actual, None);
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test_waits_once_budget_is_spent() {
+        let mut queue = fixture(&["one"]);
+
+        let actual = queue.next(TurnEnd::Completed, false);
+
+        assert_eq!(actual, None);
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test_jump_sends_the_message_next() {
+        let mut queue = fixture(&["one", "two", "three"]);
+
+        queue.jump(3).unwrap();
+        let actual = queue.next(TurnEnd::Jumped, false);
+
+        assert_eq!(actual, Some("three".to_string()));
+        assert_eq!(queue.messages().collect::<Vec<_>>(), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn test_jump_out_of_range() {
+        let mut queue = fixture(&["one"]);
+
+        let actual = [queue.jump(0).is_err(), queue.jump(2).is_err()];
+
+        assert_eq!(actual, [true, true]);
+        assert_eq!(queue, fixture(&["one"]));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut queue = fixture(&["one", "two"]);
+
+        let actual = queue.clear();
+
+        assert_eq!(actual, 2);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_persistence_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.json");
+        let queue = fixture(&["one", "two"]);
+
+        queue.save(&path).unwrap();
+        let actual = PromptQueue::load(&path).unwrap();
+
+        assert_eq!(actual, queue);
+    }
+
+    #[test]
+    fn test_saving_an_empty_queue_removes_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.json");
+        fixture(&["one"]).save(&path).unwrap();
+
+        PromptQueue::default().save(&path).unwrap();
+
+        assert!(!path.exists());
+        assert_eq!(PromptQueue::load(&path).unwrap(), PromptQueue::default());
+    }
+
+    #[test]
+    fn test_each_working_directory_has_its_own_queue() {
+        let queues = Path::new("/forge/queues");
+
+        let actual = PromptQueue::path(queues, Path::new("/src/one"));
+
+        assert_eq!(actual, PromptQueue::path(queues, Path::new("/src/one")));
+        assert_ne!(actual, PromptQueue::path(queues, Path::new("/src/two")));
+        assert!(actual.starts_with(queues));
+    }
+}
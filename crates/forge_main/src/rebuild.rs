@@ -0,0 +1,134 @@
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+/// Derived data that `forge rebuild` reconstructs from its source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RebuildTarget {
+    /// The document index, from the files on disk
+    Embeddings,
+    /// The stats log, from the records that can still be read
+    Stats,
+    /// Everything above, one after the other
+    All,
+}
+
+impl RebuildTarget {
+    /// The rebuilders to run, in order
+    pub fn expand(self) -> Vec<RebuildTarget> {
+        match self {
+            RebuildTarget::All => vec![RebuildTarget::Embeddings, RebuildTarget::Stats],
+            target => vec![target],
+        }
+    }
+}
+
+impl fmt::Display for RebuildTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RebuildTarget::Embeddings => write!(f, "embeddings"),
+            RebuildTarget::Stats => write!(f, "stats"),
+            RebuildTarget::All => write!(f, "all"),
+        }
+    }
+}
+
+/// How a rebuilder went, its summary or the error it failed with
+#[derive(Debug)]
+pub struct RebuildOutcome {
+    pub target: RebuildTarget,
+    pub duration: Duration,
+    pub result: anyhow::Result<String>,
+}
+
+/// Runs the rebuilders one after the other. A failing rebuilder doesn't
+/// stop the ones after it, each outcome is reported as it comes. Only a
+/// failure to report stops the run.
+pub async fn run_rebuilders<R, Fut, O>(
+    targets: Vec<RebuildTarget>,
+    rebuild: R,
+    mut report: O,
+) -> anyhow::Result<Vec<RebuildOutcome>>
+where
+    R: Fn(RebuildTarget) -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+    O: FnMut(&RebuildOutcome) -> anyhow::Result<()>,
+{
+    let mut outcomes = Vec::new();
+    for target in targets {
+        let started = Instant::now();
+        let result = rebuild(target).await;
+        let outcome = RebuildOutcome { target, duration: started.elapsed(), result };
+        report(&outcome)?;
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_failing_rebuilder_does_not_stop_the_others() {
+        let fixture = vec![
+            RebuildTarget::Embeddings,
+            RebuildTarget::Stats,
+            RebuildTarget::Embeddings,
+        ];
+        let mut reported = Vec::new();
+
+        let actual = run_rebuilders(
+            fixture,
+            |target| async move {
+                match target {
+                    RebuildTarget::Stats => Err(anyhow::anyhow!("stats log is unreadable")),
+                    target => Ok(format!("{target} rebuilt")),
+                }
+            },
+            |outcome| {
+                reported.push(outcome.target);
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        let results = actual
+            .iter()
+            .map(|outcome| match &outcome.result {
+                Ok(summary) => summary.clone(),
+                Err(err) => err.to_string(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            vec![
+                "embeddings rebuilt",
+                "stats log is unreadable",
+                "embeddings rebuilt"
+            ]
+        );
+        assert_eq!(
+            reported,
+            vec![
+                RebuildTarget::Embeddings,
+                RebuildTarget::Stats,
+                RebuildTarget::Embeddings
+            ]
+        );
+    }
+
+    #[test]
+    fn test_all_expands_to_every_rebuilder() {
+        assert_eq!(
+            RebuildTarget::All.expand(),
+            vec![RebuildTarget::Embeddings, RebuildTarget::Stats]
+        );
+        assert_eq!(RebuildTarget::Stats.expand(), vec![RebuildTarget::Stats]);
+    }
+}
@@ -0,0 +1,277 @@
+use std::future::Future;
+use std::path::Path;
+
+use anyhow::Result;
+use colored::Colorize;
+use forge_display::{format_size, DiffFormat};
+use forge_snaps::SnapshotSelector;
+use similar::{ChangeTag, TextDiff};
+
+use crate::console::CONSOLE;
+
+/// What restoring a snapshot would change in a file
+#[derive(Debug)]
+pub struct RestorePreview {
+    current: Vec<u8>,
+    snapshot: Vec<u8>,
+}
+
+impl RestorePreview {
+    /// `current` is the content of the file, empty when it was deleted
+    pub fn new(current: Vec<u8>, snapshot: Vec<u8>) -> Self {
+        Self { current, snapshot }
+    }
+
+    /// Reads the file and compares it with the snapshot, a missing file being
+    /// empty
+    pub async fn read(path: &Path, snapshot: Vec<u8>) -> Result<Self> {
+        let current = match tokio::fs::read(path).await {
+            Ok(current) => current,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self::new(current, snapshot))
+    }
+
+    /// Both contents as text, `None` when either is binary
+    fn texts(&self) -> Option<(&str, &str)> {
+        Some((text(&self.current)?, text(&self.snapshot)?))
+    }
+
+    /// Lines the restore adds and removes, `None` when either side is binary
+    pub fn line_changes(&self) -> Option<(usize, usize)> {
+        let (current, snapshot) = self.texts()?;
+        let diff = TextDiff::from_lines(current, snapshot);
+        Some(
+            diff.iter_all_changes()
+                .fold((0, 0), |(added, removed), change| match change.tag() {
+                    ChangeTag::Insert => (added + 1, removed),
+                    ChangeTag::Delete => (added, removed + 1),
+                    ChangeTag::Equal => (added, removed),
+                }),
+        )
+    }
+
+    /// The diff from the current content to the snapshot's. Binary content
+    /// is only described by its type and size.
+    pub fn diff(&self, title: &str, path: &Path) -> String {
+        match self.texts() {
+            Some((current, snapshot)) => {
+                DiffFormat::format(title, path.to_path_buf(), current, snapshot)
+            }
+            None => format!(
+                "{} is binary, {} {} → {} {}\n",
+                path.display(),
+                kind(&self.current),
+                format_size(self.current.len() as u64),
+                kind(&self.snapshot),
+                format_size(self.snapshot.len() as u64)
+            ),
+        }
+    }
+
+    /// Lines added and removed and the size change, eg.
+    /// `+3 -1 lines, 1.2 KB → 1.0 KB`
+    pub fn summary(&self) -> String {
+        let sizes = format!(
+            "{} → {}",
+            format_size(self.current.len() as u64),
+            format_size(self.snapshot.len() as u64)
+        );
+        match self.line_changes() {
+            Some((added, removed)) => format!(
+                "{} {} lines, {sizes}",
+                format!("+{added}").green(),
+                format!("-{removed}").red()
+            ),
+            None => format!("binary, {sizes}"),
+        }
+    }
+}
+
+/// The content as text, `None` when it holds a NUL byte or isn't UTF-8
+fn text(content: &[u8]) -> Option<&str> {
+    if content.contains(&0) {
+        return None;
+    }
+    std::str::from_utf8(content).ok()
+}
+
+fn kind(content: &[u8]) -> &'static str {
+    if text(content).is_some() {
+        "text"
+    } else {
+        "binary"
+    }
+}
+
+/// Whether a restore goes ahead. Restores without `--dry-run` do right away,
+/// a dry run needs `--yes` or the user's confirmation, which is only asked
+/// for then.
+pub async fn confirm_restore(
+    dry_run: bool,
+    yes: bool,
+    ask: impl Future<Output = Result<bool>>,
+) -> Result<bool> {
+    if !dry_run || yes {
+        return Ok(true);
+    }
+    ask.await
+}
+
+/// The snapshot given by `--timestamp`, else by `--index`, else the one at
+/// `default_index`
+pub fn selector(
+    timestamp: Option<u64>,
+    index: Option<usize>,
+    default_index: isize,
+) -> SnapshotSelector {
+    match (timestamp, index) {
+        (Some(timestamp), _) => SnapshotSelector::Timestamp(timestamp.to_string()),
+        (None, Some(index)) => SnapshotSelector::Index(index as isize),
+        (None, None) => SnapshotSelector::Index(default_index),
+    }
+}
+
+/// Restores a snapshot, `read` and `restore` being given the same selector.
+/// A dry run first shows what the restore changes and goes ahead only when
+/// confirmed. Returns whether the file was restored.
+pub async fn restore(
+    path: &Path,
+    dry_run: bool,
+    yes: bool,
+    read: impl Future<Output = Result<Vec<u8>>>,
+    ask: impl Future<Output = Result<bool>>,
+    restore: impl Future<Output = Result<()>>,
+) -> Result<bool> {
+    if dry_run {
+        let preview = RestorePreview::read(path, read.await?).await?;
+        CONSOLE.write(preview.diff("restore", path))?;
+        CONSOLE.writeln(preview.summary())?;
+    }
+
+    if !confirm_restore(dry_run, yes, ask).await? {
+        return Ok(false);
+    }
+    restore.await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_diff_goes_from_the_file_to_the_snapshot() {
+        let fixture = RestorePreview::new(
+            b"fn main() {\n    todo!()\n}\n".to_vec(),
+            b"fn main() {\n    println!(\"hi\");\n    run();\n}\n".to_vec(),
+        );
+
+        let diff = strip_ansi_escapes::strip_str(fixture.diff("restore", Path::new("main.rs")));
+        let summary = strip_ansi_escapes::strip_str(fixture.summary());
+
+        assert_eq!(fixture.line_changes(), Some((2, 1)));
+        assert!(diff.contains("|-    todo!()"), "{diff}");
+        assert!(diff.contains("|+    run();"), "{diff}");
+        assert_eq!(summary, "+2 -1 lines, 26 B → 45 B");
+    }
+
+    #[test]
+    fn test_binary_content_is_summarized() {
+        let fixture =
+            RestorePreview::new(b"plain text\n".to_vec(), vec![0x89, b'P', b'N', b'G', 0, 0]);
+
+        let actual = fixture.diff("restore", Path::new("logo.png"));
+
+        assert_eq!(actual, "logo.png is binary, text 11 B → binary 6 B\n");
+        assert_eq!(fixture.line_changes(), None);
+        assert_eq!(fixture.summary(), "binary, 11 B → 6 B");
+    }
+
+    #[tokio::test]
+    async fn test_deleted_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let fixture = RestorePreview::read(&dir.path().join("gone.txt"), b"back\n".to_vec())
+            .await
+            .unwrap();
+
+        assert_eq!(fixture.line_changes(), Some((1, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_needs_confirmation() {
+        let asked = |answer: bool| std::future::ready(Ok(answer));
+        // Fails the restore if it's awaited
+        let never = || std::future::ready(Err(anyhow::anyhow!("the user was asked")));
+
+        let actual = [
+            confirm_restore(true, false, asked(false)).await.unwrap(),
+            confirm_restore(true, false, asked(true)).await.unwrap(),
+            confirm_restore(true, true, never()).await.unwrap(),
+            confirm_restore(false, false, never()).await.unwrap(),
+        ];
+
+        assert_eq!(actual, [false, true, true, true]);
+    }
+
+    /// A file with snapshots of `one` and `two`, now holding `three`
+    async fn snapshotted_file(dir: &Path) -> (forge_snaps::SnapshotService, std::path::PathBuf) {
+        let snaps = forge_snaps::SnapshotService::new(dir.join("snapshots"));
+        let path = dir.join("notes.txt");
+        for content in ["one\n", "two\n"] {
+            tokio::fs::write(&path, content).await.unwrap();
+            snaps.create_snapshot(&path).await.unwrap();
+            // Timestamps are in milliseconds
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        tokio::fs::write(&path, "three\n").await.unwrap();
+        (snaps, path)
+    }
+
+    #[tokio::test]
+    async fn test_declined_dry_run_leaves_the_file_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let (snaps, path) = snapshotted_file(dir.path()).await;
+        let selector = selector(None, None, 1);
+
+        let actual = restore(
+            &path,
+            true,
+            false,
+            async { Ok(snaps.get_snapshot(&path, &selector).await?.content) },
+            std::future::ready(Ok(false)),
+            snaps.restore_snapshot(&path, &selector),
+        )
+        .await
+        .unwrap();
+
+        assert!(!actual);
+        assert_eq!(tokio::fs::read_to_string(&path).await.unwrap(), "three\n");
+    }
+
+    #[tokio::test]
+    async fn test_confirmed_dry_run_restores_the_previewed_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let (snaps, path) = snapshotted_file(dir.path()).await;
+        let selector = selector(None, None, 1);
+        let previewed = snaps.get_snapshot(&path, &selector).await.unwrap().content;
+
+        let actual = restore(
+            &path,
+            true,
+            false,
+            std::future::ready(Ok(previewed.clone())),
+            std::future::ready(Ok(true)),
+            snaps.restore_snapshot(&path, &selector),
+        )
+        .await
+        .unwrap();
+
+        assert!(actual);
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), previewed);
+    }
+}
@@ -1,6 +1,9 @@
-use forge_api::{ConversationId, Usage};
+use forge_api::{ConversationId, ModelId, ModelPricing, Usage};
 
+use crate::info::UsageInfo;
 use crate::input::PromptInput;
+use crate::model::{ConversationCost, TokenCost};
+use crate::queue::{PromptQueue, TurnEnd};
 
 #[derive(Clone, Default)]
 pub enum Mode {
@@ -21,12 +24,103 @@ impl std::fmt::Display for Mode {
 }
 
 /// State information for the UI
-#[derive(Default)]
 pub struct UIState {
     pub current_title: Option<String>,
     pub conversation_id: Option<ConversationId>,
+    /// Usage summed over every response of the current turn
     pub usage: Usage,
+    /// Usage summed over every response of the conversation
+    pub conversation_usage: Usage,
+    /// Prompt tokens of the latest response, ie. the size of the context
+    pub context_tokens: u64,
+    /// Dollar cost of the conversation so far, unknown once a response came
+    /// from a model without pricing
+    pub cost: Option<TokenCost>,
+    /// Model of the workflow's main agent
+    pub model: Option<ModelId>,
     pub mode: Mode,
+    pub is_first: bool,
+    /// Names of the tools called during the current turn
+    pub turn_tools: Vec<String>,
+    /// Whether the user was warned that the context is nearly full
+    pub context_warned: bool,
+    /// Messages typed while a turn ran, waiting to be sent
+    pub queue: PromptQueue,
+    /// How the latest turn ended
+    pub turn_end: TurnEnd,
+}
+
+impl Default for UIState {
+    fn default() -> Self {
+        Self {
+            current_title: None,
+            conversation_id: None,
+            usage: Usage::default(),
+            conversation_usage: Usage::default(),
+            context_tokens: 0,
+            cost: Some(TokenCost::default()),
+            model: None,
+            mode: Mode::default(),
+            is_first: true,
+            turn_tools: Vec::new(),
+            context_warned: false,
+            queue: PromptQueue::default(),
+            turn_end: TurnEnd::default(),
+        }
+    }
+}
+
+/// Share of the model's context window at which the user is warned
+pub const CONTEXT_WARNING_THRESHOLD: f64 = 0.8;
+
+impl UIState {
+    /// Adds the usage of a response to the turn's totals
+    pub fn add_usage(&mut self, usage: Usage) {
+        self.context_tokens = usage.prompt_tokens;
+        self.conversation_usage += usage.clone();
+        self.usage += usage;
+    }
+
+    /// Usage and cost of the conversation, shown by /cost
+    pub fn conversation_cost(&self) -> ConversationCost {
+        ConversationCost { usage: self.conversation_usage.clone(), cost: self.cost }
+    }
+
+    /// Usage section of /info, with the same totals as /cost
+    pub fn usage_info(&self) -> UsageInfo {
+        UsageInfo(
+            self.conversation_usage.clone(),
+            self.cost.map(|cost| cost.total()),
+        )
+    }
+
+    /// Adds the cost of a response to the conversation's cost
+    pub fn add_cost(&mut self, usage: &Usage, pricing: Option<&ModelPricing>) {
+        self.cost = self
+            .cost
+            .zip(pricing)
+            .map(|(cost, pricing)| cost + TokenCost::new(usage, pricing));
+    }
+
+    /// Returns the share of the context window in use when it first crosses
+    /// [`CONTEXT_WARNING_THRESHOLD`]. The warning is given again only after
+    /// the context shrank below the threshold, eg. once it was compacted.
+    pub fn context_warning(&mut self, context_length: u64) -> Option<f64> {
+        if context_length == 0 {
+            return None;
+        }
+
+        let share = self.context_tokens as f64 / context_length as f64;
+        if share < CONTEXT_WARNING_THRESHOLD {
+            self.context_warned = false;
+            None
+        } else if self.context_warned {
+            None
+        } else {
+            self.context_warned = true;
+            Some(share)
+        }
+    }
 }
 
 impl From<&UIState> for PromptInput {
@@ -35,6 +129,124 @@ impl From<&UIState> for PromptInput {
             title: state.current_title.clone(),
             usage: Some(state.usage.clone()),
             mode: state.mode.clone(),
+            queued: state.queue.len(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn with_prompt_tokens(state: &mut UIState, prompt_tokens: u64) -> Option<f64> {
+        state.add_usage(Usage { prompt_tokens, ..Usage::default() });
+        state.context_warning(1000)
+    }
+
+    #[test]
+    fn test_usage_adds_up_over_a_turn() {
+        let mut fixture = UIState::default();
+
+        fixture.add_usage(Usage { prompt_tokens: 100, completion_tokens: 20, total_tokens: 120 });
+        fixture.add_usage(Usage { prompt_tokens: 150, completion_tokens: 30, total_tokens: 180 });
+
+        let expected = Usage { prompt_tokens: 250, completion_tokens: 50, total_tokens: 300 };
+        assert_eq!(fixture.usage, expected);
+        assert_eq!(fixture.context_tokens, 150);
+    }
+
+    #[test]
+    fn test_conversation_usage_outlives_the_turn() {
+        let mut fixture = UIState::default();
+
+        fixture.add_usage(Usage { prompt_tokens: 100, completion_tokens: 20, total_tokens: 120 });
+        fixture.usage = Usage::default();
+        fixture.add_usage(Usage { prompt_tokens: 150, completion_tokens: 30, total_tokens: 180 });
+
+        let expected = Usage { prompt_tokens: 250, completion_tokens: 50, total_tokens: 300 };
+        assert_eq!(fixture.conversation_usage, expected);
+    }
+
+    #[test]
+    fn test_context_warning_fires_once_at_threshold() {
+        let mut state = UIState::default();
+
+        let actual = [799, 800, 850, 990]
+            .into_iter()
+            .map(|tokens| with_prompt_tokens(&mut state, tokens))
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, vec![None, Some(0.8), None, None]);
+    }
+
+    #[test]
+    fn test_context_warning_fires_again_after_compaction() {
+        let mut state = UIState::default();
+        with_prompt_tokens(&mut state, 900);
+
+        assert_eq!(with_prompt_tokens(&mut state, 300), None);
+        assert_eq!(with_prompt_tokens(&mut state, 950), Some(0.95));
+    }
+
+    #[test]
+    fn test_context_warning_without_context_length() {
+        let mut state = UIState { context_tokens: 10, ..UIState::default() };
+
+        assert_eq!(state.context_warning(0), None);
+    }
+
+    #[test]
+    fn test_cost_adds_up_over_a_conversation() {
+        let pricing = ModelPricing { prompt: 0.000003, completion: 0.000015 };
+        let usage = |prompt_tokens, completion_tokens| Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        };
+        let mut fixture = UIState::default();
+
+        fixture.add_cost(&usage(1_000, 200), Some(&pricing));
+        fixture.add_cost(&usage(4_000, 1_000), Some(&pricing));
+
+        let actual = fixture.cost.unwrap().total();
+        let expected = 0.033;
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_cost_is_unknown_without_pricing() {
+        let pricing = ModelPricing { prompt: 0.000003, completion: 0.000015 };
+        let mut fixture = UIState::default();
+
+        fixture.add_cost(&Usage::default(), Some(&pricing));
+        fixture.add_cost(&Usage::default(), None);
+        fixture.add_cost(&Usage::default(), Some(&pricing));
+
+        assert_eq!(fixture.cost, None);
+    }
+
+    #[test]
+    fn test_cost_and_info_show_the_same_totals() {
+        let pricing = ModelPricing { prompt: 0.000003, completion: 0.000015 };
+        let usage = Usage {
+            prompt_tokens: 1_000,
+            completion_tokens: 200,
+            total_tokens: 1_200,
+        };
+        let mut fixture = UIState::default();
+        for _ in 0..2 {
+            fixture.usage = Usage::default();
+            fixture.add_cost(&usage, Some(&pricing));
+            fixture.add_usage(usage.clone());
+        }
+
+        let cost = fixture.conversation_cost();
+        let UsageInfo(info_usage, info_cost) = fixture.usage_info();
+
+        assert_eq!(info_usage, cost.usage);
+        assert_eq!(info_usage.total_tokens, 2_400);
+        assert_eq!(info_cost, cost.cost.map(|cost| cost.total()));
+    }
+}
@@ -0,0 +1,544 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use colored::Colorize;
+use forge_display::{format_count, format_duration};
+use serde::{Deserialize, Serialize};
+
+/// Number of entries shown in each of the "top" tables
+const TOP_LIMIT: usize = 5;
+
+/// A single completed turn, appended to the local stats log. Nothing in here
+/// ever leaves the machine.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TurnRecord {
+    pub timestamp: DateTime<Utc>,
+    pub conversation_id: String,
+    pub cwd: String,
+    pub model: Option<String>,
+    pub tools: Vec<String>,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub latency_ms: u64,
+    /// Dollar cost of the turn, unknown when the model's pricing isn't
+    /// listed or the record was written before costs were recorded
+    #[serde(default)]
+    pub cost: Option<f64>,
+}
+
+impl TurnRecord {
+    /// Appends the record as a single JSON line to the stats log
+    pub fn append(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open stats log {}", path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Records kept and dropped when the stats log was rebuilt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogRebuild {
+    pub kept: usize,
+    pub dropped: usize,
+}
+
+/// Rewrites the stats log with the records that can be read, in the order
+/// they happened. Lines left behind by interrupted writes or an older
+/// format are dropped. The log is replaced atomically, a missing one is
+/// left missing.
+pub fn rebuild_log(path: &Path) -> anyhow::Result<LogRebuild> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(LogRebuild::default()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read stats log {}", path.display()))
+        }
+    };
+
+    let mut rebuild = LogRebuild::default();
+    let mut records = Vec::new();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<TurnRecord>(line) {
+            Ok(record) => records.push(record),
+            Err(_) => rebuild.dropped += 1,
+        }
+    }
+    records.sort_by_key(|record| record.timestamp);
+    rebuild.kept = records.len();
+
+    let mut log = String::new();
+    for record in &records {
+        log.push_str(&serde_json::to_string(record)?);
+        log.push('\n');
+    }
+    let temp = path.with_extension("jsonl.tmp");
+    std::fs::write(&temp, log)?;
+    std::fs::rename(&temp, path)
+        .with_context(|| format!("Failed to replace stats log {}", path.display()))?;
+    Ok(rebuild)
+}
+
+/// Parses durations such as `30d`, `2w` or `12h`
+pub fn parse_since(value: &str) -> anyhow::Result<Duration> {
+    let value = value.trim();
+    let (amount, unit) = value.split_at(value.len().saturating_sub(1));
+    let amount: i64 = amount
+        .parse()
+        .with_context(|| format!("Invalid duration '{}', expected eg. 30d", value))?;
+
+    match unit {
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => bail!(
+            "Invalid duration unit in '{}', expected one of h, d or w",
+            value
+        ),
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct DayStats {
+    pub conversations: usize,
+    pub turns: u64,
+    pub tokens: u64,
+    /// Dollar cost of the turns that were priced
+    pub cost: f64,
+}
+
+/// Aggregated usage statistics
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct Stats {
+    pub conversations: usize,
+    pub turns: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub average_tokens_per_turn: u64,
+    pub average_latency_ms: u64,
+    /// Dollar cost of the turns that were priced
+    pub cost: f64,
+    /// Turns left out of the cost because their pricing is unknown
+    pub unpriced_turns: u64,
+    pub days: BTreeMap<NaiveDate, DayStats>,
+    pub top_tools: Vec<(String, u64)>,
+    pub top_models: Vec<(String, u64)>,
+    pub top_repos: Vec<(String, u64)>,
+    pub notes: Vec<String>,
+}
+
+/// Accumulates records one at a time so the log never has to be loaded into
+/// memory as a whole
+#[derive(Default)]
+struct Aggregator {
+    conversations: HashSet<String>,
+    day_conversations: HashMap<NaiveDate, HashSet<String>>,
+    days: BTreeMap<NaiveDate, DayStats>,
+    turns: u64,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    total_tokens: u64,
+    latency_ms: u64,
+    cost: f64,
+    unpriced_turns: u64,
+    tools: HashMap<String, u64>,
+    models: HashMap<String, u64>,
+    repos: HashMap<String, u64>,
+}
+
+impl Aggregator {
+    fn add(&mut self, record: TurnRecord) {
+        let day = record.timestamp.date_naive();
+
+        self.turns += 1;
+        self.prompt_tokens += record.prompt_tokens;
+        self.completion_tokens += record.completion_tokens;
+        self.total_tokens += record.total_tokens;
+        self.latency_ms += record.latency_ms;
+        match record.cost {
+            Some(cost) => self.cost += cost,
+            None => self.unpriced_turns += 1,
+        }
+
+        let day_stats = self.days.entry(day).or_default();
+        day_stats.turns += 1;
+        day_stats.tokens += record.total_tokens;
+        day_stats.cost += record.cost.unwrap_or_default();
+        self.day_conversations
+            .entry(day)
+            .or_default()
+            .insert(record.conversation_id.clone());
+        self.conversations.insert(record.conversation_id);
+
+        for tool in record.tools {
+            *self.tools.entry(tool).or_default() += 1;
+        }
+        if let Some(model) = record.model {
+            *self.models.entry(model).or_default() += 1;
+        }
+        *self.repos.entry(record.cwd).or_default() += 1;
+    }
+
+    fn top(counts: HashMap<String, u64>) -> Vec<(String, u64)> {
+        let mut counts = counts.into_iter().collect::<Vec<_>>();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts.truncate(TOP_LIMIT);
+        counts
+    }
+
+    fn finish(mut self) -> Stats {
+        for (day, conversations) in self.day_conversations {
+            if let Some(day_stats) = self.days.get_mut(&day) {
+                day_stats.conversations = conversations.len();
+            }
+        }
+
+        Stats {
+            conversations: self.conversations.len(),
+            turns: self.turns,
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            total_tokens: self.total_tokens,
+            average_tokens_per_turn: self.total_tokens.checked_div(self.turns).unwrap_or(0),
+            average_latency_ms: self.latency_ms.checked_div(self.turns).unwrap_or(0),
+            cost: self.cost,
+            unpriced_turns: self.unpriced_turns,
+            days: self.days,
+            top_tools: Self::top(self.tools),
+            top_models: Self::top(self.models),
+            top_repos: Self::top(self.repos),
+            notes: vec![],
+        }
+    }
+}
+
+impl Stats {
+    /// Aggregates the records in the stats log that were created after `since`
+    pub fn from_log(path: &Path, since: Option<DateTime<Utc>>) -> anyhow::Result<Self> {
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Stats::default().note(format!(
+                    "No usage has been recorded yet, skipped {}",
+                    path.display()
+                )));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        Self::from_reader(BufReader::new(file), since)
+    }
+
+    fn from_reader(reader: impl BufRead, since: Option<DateTime<Utc>>) -> anyhow::Result<Self> {
+        let mut aggregator = Aggregator::default();
+        let mut skipped = 0;
+
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<TurnRecord>(&line) {
+                Ok(record) if since.is_none_or(|since| record.timestamp >= since) => {
+                    aggregator.add(record)
+                }
+                Ok(_) => {}
+                Err(_) => skipped += 1,
+            }
+        }
+
+        let mut stats = aggregator.finish();
+        if stats.unpriced_turns > 0 {
+            stats = stats.note(format!(
+                "Cost leaves out {} turns, their model's pricing isn't listed",
+                stats.unpriced_turns
+            ));
+        }
+        if skipped > 0 {
+            stats = stats.note(format!("Skipped {} unreadable records", skipped));
+        }
+        Ok(stats)
+    }
+
+    fn note(mut self, note: impl ToString) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+}
+
+/// Renders rows as a table with left-aligned, padded columns
+fn table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let widths = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .chain(std::iter::once(header.len()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+
+    let format_row = |cells: Vec<String>| {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut output = format_row(headers.iter().map(|h| h.to_string()).collect())
+        .bold()
+        .to_string();
+    for row in rows {
+        output.push('\n');
+        output.push_str(&format_row(row));
+    }
+    output
+}
+
+fn format_cost(cost: f64) -> String {
+    format!("${cost:.4}")
+}
+
+fn counts(rows: &[(String, u64)]) -> Vec<Vec<String>> {
+    rows.iter()
+        .map(|(name, count)| vec![name.clone(), count.to_string()])
+        .collect()
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", "Totals".bold().bright_yellow())?;
+        writeln!(
+            f,
+            "{}",
+            table(
+                &["Metric", "Value"],
+                vec![
+                    vec!["Conversations".into(), self.conversations.to_string()],
+                    vec!["Turns".into(), self.turns.to_string()],
+                    vec!["Prompt tokens".into(), format_count(self.prompt_tokens)],
+                    vec![
+                        "Completion tokens".into(),
+                        format_count(self.completion_tokens)
+                    ],
+                    vec!["Total tokens".into(), format_count(self.total_tokens)],
+                    vec![
+                        "Avg tokens per turn".into(),
+                        format_count(self.average_tokens_per_turn)
+                    ],
+                    vec![
+                        "Avg turn latency".into(),
+                        format_duration(std::time::Duration::from_millis(self.average_latency_ms))
+                    ],
+                    vec!["Cost".into(), format_cost(self.cost)],
+                ],
+            )
+        )?;
+
+        writeln!(f, "\n{}", "Per day".bold().bright_yellow())?;
+        let days = self
+            .days
+            .iter()
+            .map(|(day, stats)| {
+                vec![
+                    day.to_string(),
+                    stats.conversations.to_string(),
+                    stats.turns.to_string(),
+                    format_count(stats.tokens),
+                    format_cost(stats.cost),
+                ]
+            })
+            .collect();
+        writeln!(
+            f,
+            "{}",
+            table(&["Date", "Conversations", "Turns", "Tokens", "Cost"], days)
+        )?;
+
+        for (title, header, rows) in [
+            ("Top tools", "Tool", &self.top_tools),
+            ("Top models", "Model", &self.top_models),
+            ("Busiest repos", "Directory", &self.top_repos),
+        ] {
+            writeln!(f, "\n{}", title.bold().bright_yellow())?;
+            writeln!(f, "{}", table(&[header, "Count"], counts(rows)))?;
+        }
+
+        for note in &self.notes {
+            writeln!(f, "\n{}", note.dimmed())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn record(timestamp: &str, conversation: &str, tools: &[&str], tokens: u64) -> TurnRecord {
+        TurnRecord {
+            timestamp: timestamp.parse().unwrap(),
+            conversation_id: conversation.to_string(),
+            cwd: "/repo".to_string(),
+            model: Some("anthropic/claude-3.7-sonnet".to_string()),
+            tools: tools.iter().map(|t| t.to_string()).collect(),
+            prompt_tokens: tokens / 2,
+            completion_tokens: tokens / 2,
+            total_tokens: tokens,
+            latency_ms: 1000,
+            cost: Some(tokens as f64 / 100.0),
+        }
+    }
+
+    fn fixture() -> String {
+        [
+            record("2025-01-31T10:00:00Z", "a", &["fs_read", "fs_read"], 100),
+            record("2025-01-31T23:59:59Z", "a", &["shell"], 200),
+            record("2025-02-01T00:00:00Z", "b", &["fs_read"], 300),
+            record("2025-02-01T12:00:00Z", "c", &[], 400),
+        ]
+        .iter()
+        .map(|r| serde_json::to_string(r).unwrap())
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+
+    #[test]
+    fn test_aggregates_totals() {
+        let actual = Stats::from_reader(fixture().as_bytes(), None).unwrap();
+
+        assert_eq!(actual.conversations, 3);
+        assert_eq!(actual.turns, 4);
+        assert_eq!(actual.total_tokens, 1000);
+        assert_eq!(actual.average_tokens_per_turn, 250);
+        assert_eq!(actual.average_latency_ms, 1000);
+        assert_eq!(
+            actual.top_tools,
+            vec![("fs_read".to_string(), 3), ("shell".to_string(), 1)]
+        );
+        assert_eq!(
+            actual.top_models,
+            vec![("anthropic/claude-3.7-sonnet".to_string(), 4)]
+        );
+    }
+
+    #[test]
+    fn test_buckets_days_across_month_boundary() {
+        let actual = Stats::from_reader(fixture().as_bytes(), None).unwrap().days;
+
+        let expected = BTreeMap::from([
+            (
+                NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+                DayStats { conversations: 1, turns: 2, tokens: 300, cost: 3.0 },
+            ),
+            (
+                NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+                DayStats { conversations: 2, turns: 2, tokens: 700, cost: 7.0 },
+            ),
+        ]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_unpriced_turns_are_left_out_of_the_cost() {
+        let unpriced = TurnRecord {
+            cost: None,
+            ..record("2025-02-01T13:00:00Z", "c", &[], 500)
+        };
+        let fixture = format!("{}\n{}", fixture(), serde_json::to_string(&unpriced).unwrap());
+
+        let actual = Stats::from_reader(fixture.as_bytes(), None).unwrap();
+
+        assert_eq!(actual.cost, 10.0);
+        assert_eq!(actual.unpriced_turns, 1);
+        assert_eq!(
+            actual.notes,
+            vec!["Cost leaves out 1 turns, their model's pricing isn't listed".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_since_filters_records() {
+        let since = "2025-02-01T00:00:00Z".parse().unwrap();
+        let actual = Stats::from_reader(fixture().as_bytes(), Some(since)).unwrap();
+
+        assert_eq!(actual.turns, 2);
+        assert_eq!(actual.conversations, 2);
+    }
+
+    #[test]
+    fn test_missing_log_is_skipped_with_note() {
+        let actual = Stats::from_log(Path::new("/non/existent/stats.jsonl"), None).unwrap();
+
+        assert_eq!(actual.turns, 0);
+        assert_eq!(actual.notes.len(), 1);
+    }
+
+    #[test]
+    fn test_rebuild_log_keeps_readable_records_in_order() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("stats.jsonl");
+        let early = record("2025-01-31T10:00:00Z", "a", &["fs_read"], 100);
+        let late = record("2025-02-01T12:00:00Z", "b", &[], 400);
+        let fixture = format!(
+            "{}\n{{\"timestamp\":\"2025-02-01\"}}\n\n{}\n{{\"timest",
+            serde_json::to_string(&late).unwrap(),
+            serde_json::to_string(&early).unwrap()
+        );
+        std::fs::write(&path, fixture).unwrap();
+
+        let actual = rebuild_log(&path).unwrap();
+
+        assert_eq!(actual, LogRebuild { kept: 2, dropped: 2 });
+        let expected = format!(
+            "{}\n{}\n",
+            serde_json::to_string(&early).unwrap(),
+            serde_json::to_string(&late).unwrap()
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+        let stats = Stats::from_log(&path, None).unwrap();
+        assert_eq!(stats.turns, 2);
+        assert!(!stats.notes.iter().any(|note| note.contains("unreadable")));
+    }
+
+    #[test]
+    fn test_rebuild_log_leaves_a_missing_log_missing() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("stats.jsonl");
+
+        let actual = rebuild_log(&path).unwrap();
+
+        assert_eq!(actual, LogRebuild::default());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(parse_since("30d").unwrap(), Duration::days(30));
+        assert_eq!(parse_since("2w").unwrap(), Duration::weeks(2));
+        assert!(parse_since("30x").is_err());
+    }
+}
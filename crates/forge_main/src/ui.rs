@@ -1,21 +1,44 @@
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use anyhow::Result;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
 use colored::Colorize;
-use forge_api::{AgentMessage, ChatRequest, ChatResponse, ConversationId, Event, Model, API};
-use forge_display::TitleFormat;
-use forge_snaps::SnapshotInfo;
-use forge_tracker::EventKind;
+use forge_api::{
+    AgentMessage, ChatRequest, ChatResponse, ChunkConfig, Config, ConversationId, Error, Event,
+    ImportFormat, IndexProgress, IndexRequest, IndexSummary, InfoProvider, InfoSection,
+    IterationLimitAction, Model, ModelPricing, ModelResolution, Usage, UserAnswer, UserInteraction,
+    Workflow, API, CONFIG_FILE,
+};
+use forge_display::{
+    format_duration, format_size, format_time, CodeHighlighter, DiffFormat, Fragment,
+    MarkdownRenderer, PathFormat, TimeFormat, TitleFormat,
+};
+use forge_snaps::{SnapshotInfo, SnapshotUsage};
 use lazy_static::lazy_static;
 use serde_json::Value;
-use tokio_stream::StreamExt;
+use tokio_stream::{Stream, StreamExt};
 
+use crate::activity::{provider_line, Activity, DisplayMode};
+use crate::auth::{run_turn, RecoverableTurn};
 use crate::banner;
-use crate::cli::{Cli, Snapshot, SnapshotCommand};
+use crate::chat_stream::{error_severity, format_error, StreamStep};
+use crate::cli::{Cli, SecretCommand, SnapshotCommand, TopLevelCommand};
 use crate::console::CONSOLE;
-use crate::info::Info;
+use crate::context_view::{slug, ContextView};
+use crate::info::{Info, SessionInfo};
+use crate::inline_command;
 use crate::input::Console;
-use crate::model::{Command, UserInput};
+use crate::model::{expand_home, Command, ConfigCommand, DebugCommand, QueueCommand, UserInput};
+use crate::plugin::{Plugin, PluginContext, PluginOutput, Plugins, PLUGIN_TIMEOUT};
+use crate::queue::{PromptQueue, ReadAhead, TurnEnd};
+use crate::rebuild::{run_rebuilders, RebuildTarget};
+use crate::snapshot;
 use crate::state::{Mode, UIState};
+use crate::stats::{parse_since, rebuild_log, Stats, TurnRecord};
+use crate::upgrade::{self, GitHubReleases, Outcome};
 
 // Event type constants moved to UI layer
 pub const EVENT_USER_TASK_INIT: &str = "user_task_init";
@@ -24,6 +47,13 @@ pub const EVENT_USER_HELP_QUERY: &str = "user_help_query";
 pub const EVENT_TITLE: &str = "title";
 pub const EVENT_USER_COMPACT_INIT: &str = "user_compact_init"; // New event for /compact
 
+/// Environment variable holding the passphrase of the encrypted conversation
+/// store
+const STORE_PASSPHRASE_VAR: &str = "FORGE_STORE_PASSPHRASE";
+
+/// How long the models aren't listed again after listing them failed
+const MODELS_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(60);
+
 lazy_static! {
     pub static ref TRACKER: forge_tracker::Tracker = forge_tracker::Tracker::default();
 }
@@ -33,7 +63,18 @@ pub struct UI<F> {
     api: Arc<F>,
     console: Console,
     cli: Cli,
+    config: Config,
     models: Option<Vec<Model>>,
+    /// When listing the models last failed
+    models_failed_at: Option<std::time::Instant>,
+    activity: Activity,
+    /// Renders the paths shown to the user relative to the working directory
+    paths: PathFormat,
+    code: CodeHighlighter,
+    markdown: MarkdownRenderer,
+    /// Whether the output is a terminal, the agent's text is only rendered
+    /// and highlighted there so that piped output stays plain
+    styled: bool,
     #[allow(dead_code)] // The guard is kept alive by being held in the struct
     _guard: forge_tracker::Guard,
 }
@@ -93,32 +134,140 @@ impl<F: API> UI<F> {
     pub fn init(cli: Cli, api: Arc<F>) -> Result<Self> {
         // Parse CLI arguments first to get flags
         let env = api.environment();
+        TRACKER.set_client(forge_api::http_client(
+            &env.proxy,
+            &env.tls,
+            &env.http_timeout,
+        )?);
+        let queue_path = PromptQueue::path(&env.queues_path(), &env.cwd);
+        let queue = PromptQueue::load(&queue_path).unwrap_or_else(|err| {
+            tracing::warn!(error = ?err, "Failed to load the queued messages");
+            PromptQueue::default()
+        });
+        let display = if cli.compact {
+            DisplayMode::Compact
+        } else {
+            DisplayMode::Full
+        };
+        forge_display::set_echo(display == DisplayMode::Full);
+        let paths = PathFormat::new(env.cwd.clone()).roots(env.workspace_roots.clone());
         Ok(Self {
-            state: Default::default(),
+            state: UIState { queue, ..Default::default() },
+            activity: Activity::new(display, paths.clone()),
+            paths,
+            code: CodeHighlighter::default(),
+            markdown: MarkdownRenderer::default(),
+            styled: std::io::stdout().is_terminal(),
             api,
             console: Console::new(env.clone()),
             cli,
+            config: Config::load(&env.base_path.join(CONFIG_FILE))?,
             models: None,
+            models_failed_at: None,
             _guard: forge_tracker::init_tracing(env.log_path())?,
         })
     }
 
     pub async fn run(&mut self) -> Result<()> {
-        if let Some(snapshot_command) = self.cli.snapshot.as_ref() {
-            return match snapshot_command {
-                Snapshot::Snapshot { sub_command } => self.handle_snaps(sub_command).await,
-            };
+        match self.cli.subcommand.as_ref() {
+            Some(TopLevelCommand::Snapshot { sub_command }) => {
+                return self.handle_snaps(sub_command).await
+            }
+            Some(TopLevelCommand::Stats { since, json }) => {
+                return self.handle_stats(since.as_deref(), *json)
+            }
+            Some(TopLevelCommand::Index { path, glob, tags, chunk_size, chunk_overlap }) => {
+                let request = IndexRequest {
+                    path: path.clone().unwrap_or_else(|| self.api.environment().cwd),
+                    glob: glob.clone(),
+                    tags: tags.clone(),
+                    chunk: ChunkConfig { size: *chunk_size, overlap: *chunk_overlap },
+                };
+                return self.handle_index(request).await;
+            }
+            Some(TopLevelCommand::Import { format, path }) => {
+                self.unlock_store().await?;
+                return self.handle_import(*format, path).await;
+            }
+            Some(TopLevelCommand::Conversations { tag, query }) => {
+                self.unlock_store().await?;
+                return self
+                    .handle_conversations(tag.as_deref(), query.as_deref())
+                    .await;
+            }
+            Some(TopLevelCommand::Rebuild { what }) => return self.handle_rebuild(*what).await,
+            Some(TopLevelCommand::Cleanup { older_than }) => {
+                let count = self.api.purge_scratch(*older_than).await?;
+                CONSOLE.writeln(
+                    TitleFormat::success("cleanup")
+                        .sub_title(format!("{count} scratch directories removed"))
+                        .format(),
+                )?;
+                return Ok(());
+            }
+            Some(TopLevelCommand::Upgrade { check }) => return self.handle_upgrade(*check).await,
+            Some(TopLevelCommand::EncryptStore) => return self.handle_encrypt_store().await,
+            Some(TopLevelCommand::DecryptStore) => return self.handle_decrypt_store().await,
+            Some(TopLevelCommand::Secret { sub_command }) => {
+                return self.handle_secret(sub_command).await
+            }
+            None => {}
         }
 
+        // Unlock the conversation store before anything is read from or written to it
+        self.unlock_store().await?;
+
         // Handle direct prompt if provided
         let prompt = self.cli.prompt.clone();
         if let Some(prompt) = prompt {
-            self.chat(prompt).await?;
-            return Ok(());
+            let content = if self.cli.inline_commands {
+                self.expand_inline_commands(&prompt).await
+            } else {
+                prompt
+            };
+            let mut turn = ChatTurn::new(self, content);
+            return run_turn(&mut turn, false).await;
         }
 
         // Display the banner in dimmed colors since we're in interactive mode
-        banner::display()?;
+        let plugins = Plugins::discover(&self.api.environment());
+        banner::display(&self.info_sections().await?, &plugins.commands())?;
+        if !self.config.unknown_keys.is_empty() {
+            CONSOLE.writeln(
+                TitleFormat::warning("config")
+                    .sub_title(format!(
+                        "ignoring unknown keys of {}: {}",
+                        CONFIG_FILE,
+                        self.config.unknown_keys.join(", ")
+                    ))
+                    .format(),
+            )?;
+        }
+        for plugin in plugins.shadowed() {
+            CONSOLE.writeln(
+                TitleFormat::failed("plugin")
+                    .sub_title(format!(
+                        "{} isn't used, /{} is a built-in command",
+                        plugin.path.display(),
+                        plugin.name
+                    ))
+                    .format(),
+            )?;
+        }
+
+        // Offer to recover turns that were interrupted in a previous session
+        self.handle_recovery().await?;
+
+        if !self.state.queue.is_empty() {
+            CONSOLE.writeln(
+                TitleFormat::success("queue")
+                    .sub_title(format!(
+                        "{} message(s) queued in the previous session are sent after your next message, see /queue list",
+                        self.state.queue.len()
+                    ))
+                    .format(),
+            )?;
+        }
 
         // Get initial input from file or prompt
         let mut input = match &self.cli.command {
@@ -128,21 +277,44 @@ impl<F: API> UI<F> {
 
         loop {
             match input {
-                Command::Dump => {
-                    self.handle_dump().await?;
+                Command::Context { json } => {
+                    self.handle_context(json).await?;
+
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Dump { html } => {
+                    self.handle_dump(html).await?;
                     let prompt_input = Some((&self.state).into());
                     input = self.console.prompt(prompt_input).await?;
                     continue;
                 }
                 Command::New => {
-                    banner::display()?;
+                    if let Some(id) = self.state.conversation_id.take() {
+                        self.api.delete_conversation(&id).await?;
+                    }
+                    // Queued messages are carried over to the new conversation
+                    let queue = std::mem::take(&mut self.state.queue);
                     self.state = Default::default();
+                    self.state.queue = queue;
+                    let plugins = Plugins::discover(&self.api.environment()).commands();
+                    banner::display(&self.info_sections().await?, &plugins)?;
+                    if !self.state.queue.is_empty() {
+                        CONSOLE.writeln(
+                            TitleFormat::warning("queue")
+                                .sub_title(format!(
+                                    "{} queued message(s) will be sent in the new conversation, use /queue clear to drop them",
+                                    self.state.queue.len()
+                                ))
+                                .format(),
+                        )?;
+                    }
                     input = self.console.prompt(None).await?;
                     continue;
                 }
                 Command::Info => {
-                    let info =
-                        Info::from(&self.api.environment()).extend(Info::from(&self.state.usage));
+                    let info = Info::from_sections(self.info_sections().await?);
 
                     CONSOLE.writeln(info.to_string())?;
 
@@ -151,12 +323,23 @@ impl<F: API> UI<F> {
                     continue;
                 }
                 Command::Message(ref content) => {
-                    let chat_result = match self.state.mode {
-                        Mode::Help => self.help_chat(content.clone()).await,
-                        _ => self.chat(content.clone()).await,
+                    let interactive = std::io::stdin().is_terminal();
+                    let content = self.expand_inline_commands(content).await;
+                    let mut turn = ChatTurn::new(self, content);
+                    let chat_result = run_turn(&mut turn, interactive).await;
+                    let end = match chat_result {
+                        Ok(()) => self.state.turn_end,
+                        Err(err) => {
+                            CONSOLE.writeln(format_error(
+                                error_severity(&err),
+                                &format!("{:?}", err),
+                            ))?;
+                            TurnEnd::Failed
+                        }
                     };
-                    if let Err(err) = chat_result {
-                        CONSOLE.writeln(TitleFormat::failed(format!("{:?}", err)).format())?;
+                    if let Some(next) = self.next_queued(end)? {
+                        input = Command::Message(next);
+                        continue;
                     }
                     let prompt_input = Some((&self.state).into());
                     input = self.console.prompt(prompt_input).await?;
@@ -177,6 +360,14 @@ impl<F: API> UI<F> {
                 }
                 Command::Help => {
                     self.handle_mode_change(Mode::Help).await?;
+                    let plugins = Plugins::discover(&self.api.environment()).commands();
+                    if !plugins.is_empty() {
+                        CONSOLE.writeln(
+                            TitleFormat::success("plugins")
+                                .sub_title(plugins.join(", "))
+                                .format(),
+                        )?;
+                    }
 
                     let prompt_input = Some((&self.state).into());
                     input = self.console.prompt(prompt_input).await?;
@@ -186,14 +377,10 @@ impl<F: API> UI<F> {
                     break;
                 }
                 Command::Models => {
-                    let models = if let Some(models) = self.models.as_ref() {
-                        models
-                    } else {
-                        let models = self.api.models().await?;
-                        self.models = Some(models);
-                        self.models.as_ref().unwrap()
-                    };
-                    let info: Info = models.as_slice().into();
+                    // Asking for the models lists them again even right after
+                    // a failure
+                    self.models_failed_at = None;
+                    let info: Info = self.models().await?.into();
                     CONSOLE.writeln(info.to_string())?;
 
                     input = self.console.prompt(None).await?;
@@ -201,6 +388,83 @@ impl<F: API> UI<F> {
                 Command::Compact => {
                     self.handle_compact().await?;
 
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Cost => {
+                    let cost = self.state.conversation_cost();
+                    CONSOLE.writeln(Info::from(&cost).to_string())?;
+
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Debug(DebugCommand::Transformers) => {
+                    match self.api.transformers() {
+                        Some(report) => CONSOLE.writeln(Info::from(&report).to_string())?,
+                        None => CONSOLE.writeln(
+                            TitleFormat::warning("debug")
+                                .sub_title("The provider sends requests without transforming them")
+                                .format(),
+                        )?,
+                    }
+
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Tag(ref tags) => {
+                    self.handle_tag(tags).await?;
+
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Unprotect(ref pattern) => {
+                    let title = match self.api.unprotect(pattern) {
+                        Ok(()) => TitleFormat::success("unprotect")
+                            .sub_title(format!("'{pattern}' can be modified until you exit")),
+                        Err(err) => TitleFormat::failed("unprotect").error(err.to_string()),
+                    };
+                    CONSOLE.writeln(title.format())?;
+
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Display(mode) => {
+                    self.set_display(mode)?;
+
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Config(ref command) => {
+                    self.handle_config(command).await?;
+
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Plugin { ref plugin, ref args } => {
+                    if let Some(message) = self.handle_plugin(plugin, args).await? {
+                        input = Command::Message(message);
+                        continue;
+                    }
+
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Queue(ref command) => {
+                    if self.handle_queue(command.clone())? {
+                        if let Some(next) = self.next_queued(TurnEnd::Jumped)? {
+                            input = Command::Message(next);
+                            continue;
+                        }
+                    }
+
                     let prompt_input = Some((&self.state).into());
                     input = self.console.prompt(prompt_input).await?;
                     continue;
@@ -211,17 +475,270 @@ impl<F: API> UI<F> {
         Ok(())
     }
 
-    async fn handle_compact(&mut self) -> Result<()> {
-        // Get the current context from the state
-        let context = self.state.context.clone();
+    /// Lists or changes the settings, writes them to a file, or validates and
+    /// applies those of a file
+    async fn handle_config(&mut self, command: &ConfigCommand) -> Result<()> {
+        let title = match command {
+            ConfigCommand::List => {
+                let info = self
+                    .config
+                    .entries()?
+                    .iter()
+                    .fold(Info::new().add_title("Config"), |info, entry| {
+                        info.add_item(
+                            entry.key.name,
+                            format!("{entry} - {}", entry.key.description),
+                        )
+                    });
+                CONSOLE.writeln(info.to_string())?;
+                return Ok(());
+            }
+            ConfigCommand::Set { key, value } => match self.api.set_config(key, value).await {
+                Ok(config) => {
+                    self.config = config;
+                    TitleFormat::success("config").sub_title(format!("{key} set to {value}"))
+                }
+                Err(err) => TitleFormat::failed("config").error(format!("{err:#}")),
+            },
+            ConfigCommand::Export(path) => match self.export_config(path).await {
+                Ok(()) => TitleFormat::success("config")
+                    .sub_title(format!("exported to {}", path.display())),
+                Err(err) => TitleFormat::failed("config").error(format!("{err:#}")),
+            },
+            ConfigCommand::Import(path) => match self.import_config(path).await {
+                Ok(config) => {
+                    self.config = config;
+                    TitleFormat::success("config")
+                        .sub_title(format!("imported from {}", path.display()))
+                }
+                Err(err) => TitleFormat::failed("config").error(format!("{err:#}")),
+            },
+        };
+        CONSOLE.writeln(title.format())?;
+        Ok(())
+    }
+
+    async fn export_config(&self, path: &std::path::Path) -> Result<()> {
+        let path = expand_home(path, self.api.environment().home.as_deref());
+        let content = self.api.export_config().await?;
+        tokio::fs::write(&path, content)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    async fn import_config(&self, path: &std::path::Path) -> Result<Config> {
+        let path = expand_home(path, self.api.environment().home.as_deref());
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        self.api.import_config(&content).await
+    }
+
+    /// Lists, clears or reorders the queued messages, returns whether a
+    /// message was moved to the front to be sent right away
+    fn handle_queue(&mut self, command: QueueCommand) -> Result<bool> {
+        match command {
+            QueueCommand::List => {
+                if self.state.queue.is_empty() {
+                    CONSOLE.writeln(TitleFormat::success("queue").sub_title("empty").format())?;
+                } else {
+                    let info = self
+                        .state
+                        .queue
+                        .messages()
+                        .enumerate()
+                        .fold(Info::new().add_title("Queue"), |info, (i, message)| {
+                            info.add_item(i + 1, message)
+                        });
+                    CONSOLE.writeln(info.to_string())?;
+                }
+                Ok(false)
+            }
+            QueueCommand::Clear => {
+                let count = self.state.queue.clear();
+                self.save_queue();
+                CONSOLE.writeln(
+                    TitleFormat::success("queue")
+                        .sub_title(format!("{count} queued message(s) removed"))
+                        .format(),
+                )?;
+                Ok(false)
+            }
+            QueueCommand::Jump(position) => match self.state.queue.jump(position) {
+                Ok(()) => {
+                    self.save_queue();
+                    Ok(true)
+                }
+                Err(err) => {
+                    CONSOLE
+                        .writeln(TitleFormat::failed("queue").error(err.to_string()).format())?;
+                    Ok(false)
+                }
+            },
+        }
+    }
+
+    /// Queues a line typed while a turn runs, returns whether the turn should
+    /// be cancelled to send a queued message right away. Plugin commands are
+    /// resolved like at the prompt, so they aren't queued as messages.
+    fn queue_line(&mut self, line: &str) -> Result<bool> {
+        match Plugins::discover(&self.api.environment()).parse(line) {
+            Command::Message(message) => {
+                let count = self.state.queue.enqueue(message);
+                self.save_queue();
+                CONSOLE.writeln(format!("queued ({count})").dimmed().to_string())?;
+                Ok(false)
+            }
+            Command::Queue(command) => self.handle_queue(command),
+            Command::Display(mode) => {
+                self.set_display(mode)?;
+                Ok(false)
+            }
+            _ => {
+                CONSOLE.writeln(
+                    TitleFormat::warning(
+                        "Only messages, /queue and /display can be used while a turn runs, other commands and plugins can once it ends",
+                    )
+                    .format(),
+                )?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Switches the display, also while a turn runs. The text held back by the
+    /// compact display is shown when leaving it.
+    fn set_display(&mut self, mode: DisplayMode) -> Result<()> {
+        if self.activity.has_in_flight() {
+            CONSOLE.write("\r\x1b[2K")?;
+        }
+        if let Some(text) = self.activity.set_mode(mode) {
+            CONSOLE.writeln(text.dimmed().to_string())?;
+        }
+        forge_display::set_echo(mode == DisplayMode::Full);
+
+        let name = match mode {
+            DisplayMode::Full => "full",
+            DisplayMode::Compact => "compact",
+        };
+        CONSOLE.writeln(
+            TitleFormat::success("display")
+                .sub_title(format!("{name} from now on"))
+                .format(),
+        )?;
+        Ok(())
+    }
 
-        // Summarize the context
-        let summarized_context = self.summarize_context(context);
+    /// Prints the agent's text, rendering its markdown unless the config
+    /// turned it off, and its code blocks highlighted
+    fn write_fragments(&mut self, fragments: Vec<Fragment>) -> Result<()> {
+        for fragment in fragments {
+            match fragment {
+                Fragment::Text(text) if self.config.markdown.enabled => {
+                    CONSOLE.write(self.markdown.push(&text))?
+                }
+                Fragment::Text(text) => CONSOLE.write(text.dimmed().to_string())?,
+                Fragment::Code(code) => CONSOLE.write(code)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints what's left of the agent's text once it stopped streaming
+    fn finish_text(&mut self) -> Result<()> {
+        let fragments = self.code.finish();
+        self.write_fragments(fragments)?;
+        CONSOLE.write(self.markdown.finish())?;
+        Ok(())
+    }
+
+    /// Ends the compact display of a turn: clears the line of the call in
+    /// flight and shows the text held back since the last tool call, along
+    /// with the text that led to the calls cut short when the turn `failed`
+    fn finish_activity(&mut self, failed: bool) -> Result<()> {
+        if !self.activity.is_compact() {
+            return Ok(());
+        }
+        if self.activity.has_in_flight() {
+            CONSOLE.write("\r\x1b[2K")?;
+        }
+        let text = if failed {
+            self.activity.fail()
+        } else {
+            self.activity.finish()
+        };
+        if let Some(text) = text {
+            CONSOLE.newline()?;
+            CONSOLE.writeln(text.dimmed().to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Takes the queued message to send after a turn that ended with `end`,
+    /// and tells the user why the queue waits for them otherwise
+    fn next_queued(&mut self, end: TurnEnd) -> Result<Option<String>> {
+        if self.state.queue.is_empty() {
+            return Ok(None);
+        }
+
+        let within_budget = self
+            .config
+            .queue
+            .within_budget(self.state.cost.map(|cost| cost.total()));
+        let next = self.state.queue.next(end, within_budget);
+        match &next {
+            Some(message) => {
+                self.save_queue();
+                CONSOLE.newline()?;
+                CONSOLE.writeln(TitleFormat::execute("queued").sub_title(message).format())?;
+            }
+            None => {
+                let reason = if end == TurnEnd::Completed {
+                    "the conversation reached the queue's cost budget"
+                } else {
+                    "the turn didn't complete"
+                };
+                CONSOLE.writeln(
+                    TitleFormat::warning(format!(
+                        "{} queued message(s) wait since {reason}, use /queue jump <n> to send one",
+                        self.state.queue.len()
+                    ))
+                    .format(),
+                )?;
+            }
+        }
+        Ok(next)
+    }
 
-        // Replace the context with the summarized version
-        self.state.context = summarized_context;
+    /// Persists the queue, failing to do so is never fatal to the chat
+    fn save_queue(&self) {
+        let env = self.api.environment();
+        let path = PromptQueue::path(&env.queues_path(), &env.cwd);
+        if let Err(err) = self.state.queue.save(&path) {
+            tracing::warn!(error = ?err, "Failed to save the queued messages");
+        }
+    }
+
+    /// Replaces !`command` in the message with the output of the command
+    async fn expand_inline_commands(&self, message: &str) -> String {
+        inline_command::expand(
+            message,
+            &self.config.inline_commands,
+            |command| async move {
+                CONSOLE.writeln(TitleFormat::execute(&command).format())?;
+                self.api.run_command(&command).await
+            },
+        )
+        .await
+    }
+
+    async fn handle_compact(&mut self) -> Result<()> {
+        let conversation_id = self.init_conversation().await?;
+        let chat = ChatRequest::new(Self::create_user_compact_init_event(""), conversation_id);
+
+        let mut stream = self.api.chat(chat).await?;
+        self.handle_chat_stream(&mut stream).await?;
 
-        // Notify the user
         CONSOLE.writeln(
             TitleFormat::success("Context Compacted")
                 .sub_title("The context has been summarized and replaced.")
@@ -231,12 +748,1274 @@ impl<F: API> UI<F> {
         Ok(())
     }
 
-    fn summarize_context(&self, context: HashMap<String, Value>) -> HashMap<String, Value> {
-        // Example summarization logic
-        let mut summarized = HashMap::new();
-        summarized.insert("summary".to_string(), json!("This is a summarized context"));
-        summarized
+    async fn handle_tag(&mut self, tags: &[String]) -> Result<()> {
+        let title = match self.state.conversation_id.clone() {
+            None => TitleFormat::warning("tag").sub_title("There is no conversation to tag yet"),
+            Some(conversation_id) => match self.api.add_tags(&conversation_id, tags).await {
+                Ok(added) if added.is_empty() => {
+                    TitleFormat::warning("tag").sub_title("The conversation already has these tags")
+                }
+                Ok(added) => TitleFormat::success("tag").sub_title(added.join(", ")),
+                Err(err) => TitleFormat::failed("tag").error(err.to_string()),
+            },
+        };
+        CONSOLE.writeln(title.format())?;
+        Ok(())
+    }
+
+    async fn handle_dump(&mut self, html: bool) -> Result<()> {
+        if let Some(conversation_id) = self.state.conversation_id.clone() {
+            let conversation = self.api.conversation(&conversation_id).await?;
+            if let Some(conversation) = conversation {
+                let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+                let extension = if html { "html" } else { "json" };
+                let suggested = conversation
+                    .suggestions
+                    .as_ref()
+                    .and_then(|suggestions| suggestions.title.clone());
+                let name = suggested
+                    .as_deref()
+                    .filter(|_| html)
+                    .and_then(slug)
+                    .unwrap_or_else(|| "dump".to_string());
+                let path = self
+                    .api
+                    .environment()
+                    .cwd
+                    .join(format!("{timestamp}-{name}.{extension}"));
+
+                let content = if html {
+                    let title =
+                        suggested.unwrap_or_else(|| format!("Conversation {conversation_id}"));
+                    ContextView::new(&conversation)
+                        .paths(self.paths.clone())
+                        .to_html(&title)
+                } else {
+                    serde_json::to_string_pretty(&conversation)?
+                };
+                tokio::fs::write(path.as_path(), content).await?;
+
+                CONSOLE.writeln(
+                    TitleFormat::success("dump")
+                        .sub_title(path.display().to_string())
+                        .format(),
+                )?;
+            } else {
+                CONSOLE.writeln(
+                    TitleFormat::failed("dump")
+                        .error(format!("Conversation {conversation_id} was not found"))
+                        .format(),
+                )?;
+            }
+        } else {
+            CONSOLE.writeln(
+                TitleFormat::failed("dump")
+                    .error("No conversation initiated yet")
+                    .format(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Shows the context of every agent of the current conversation
+    async fn handle_context(&mut self, json: bool) -> Result<()> {
+        let Some(conversation_id) = self.state.conversation_id.clone() else {
+            CONSOLE.writeln(
+                TitleFormat::failed("context")
+                    .error("No conversation initiated yet")
+                    .format(),
+            )?;
+            return Ok(());
+        };
+        let Some(conversation) = self.api.conversation(&conversation_id).await? else {
+            CONSOLE.writeln(
+                TitleFormat::failed("context")
+                    .error(format!("Conversation {conversation_id} was not found"))
+                    .format(),
+            )?;
+            return Ok(());
+        };
+
+        let view = ContextView::new(&conversation).paths(self.paths.clone());
+        if view.is_empty() {
+            CONSOLE.writeln(
+                TitleFormat::failed("context")
+                    .error("No agent has a context yet")
+                    .format(),
+            )?;
+        } else if json {
+            CONSOLE.writeln_raw(view.to_json()?)?;
+        } else {
+            CONSOLE.writeln(view.to_string().trim_end())?;
+        }
+        Ok(())
+    }
+
+    async fn handle_snaps(&self, snapshot_command: &SnapshotCommand) -> Result<()> {
+        match snapshot_command {
+            SnapshotCommand::List { path } => {
+                let snapshots = self.api.list_snapshots(path).await?;
+                let usage = SnapshotUsage::from(snapshots.as_slice());
+                let now = chrono::Utc::now();
+                let time_format = if std::io::stdout().is_terminal() {
+                    TimeFormat::Relative
+                } else {
+                    TimeFormat::Absolute
+                };
+                let info = snapshots
+                    .iter()
+                    .fold(
+                        Info::new().add_title(path.display()),
+                        |info, snapshot: &SnapshotInfo| {
+                            let taken = snapshot
+                                .timestamp
+                                .parse()
+                                .ok()
+                                .and_then(chrono::DateTime::from_timestamp_millis)
+                                .map(|time| format_time(time, now, time_format))
+                                .unwrap_or_else(|| snapshot.timestamp.clone());
+                            info.add_item(
+                                snapshot.index,
+                                format!("{taken} ({})", format_size(snapshot.size)),
+                            )
+                        },
+                    )
+                    .add_title("Usage")
+                    .add_item("Logical", format_size(usage.logical_size))
+                    .add_item("Stored", format_size(usage.stored_size));
+                CONSOLE.writeln(info.to_string())?;
+            }
+            SnapshotCommand::Restore { path, timestamp, index, dry_run, yes } => {
+                // Defaults to the previous version
+                let selector = snapshot::selector(*timestamp, *index, 1);
+                let question = format!("Restore {}?", path.display());
+                let restored = snapshot::restore(
+                    path,
+                    *dry_run,
+                    *yes,
+                    self.api.read_snapshot(path, &selector),
+                    Self::confirm(&question),
+                    self.api.restore_snapshot(path, &selector),
+                )
+                .await?;
+                if !restored {
+                    CONSOLE.writeln(
+                        TitleFormat::warning("restore")
+                            .sub_title("cancelled, the file is unchanged")
+                            .format(),
+                    )?;
+                    return Ok(());
+                }
+                CONSOLE.writeln(
+                    TitleFormat::success("restore")
+                        .sub_title(path.display().to_string())
+                        .format(),
+                )?;
+            }
+            SnapshotCommand::Diff { path, timestamp, index } => {
+                let selector = snapshot::selector(*timestamp, *index, 0);
+                let content = self.api.read_snapshot(path, &selector).await?;
+                let old = String::from_utf8_lossy(&content);
+                let new = tokio::fs::read_to_string(path).await?;
+                CONSOLE.writeln(DiffFormat::format("diff", path.clone(), &old, &new))?;
+            }
+            SnapshotCommand::Purge { older_than } => {
+                let count = self.api.purge_older_than(*older_than).await?;
+                CONSOLE.writeln(
+                    TitleFormat::success("purge")
+                        .sub_title(format!("{count} snapshots removed"))
+                        .format(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_recovery(&mut self) -> Result<()> {
+        let journals = self.api.interrupted().await?;
+        let Some(conversation_id) = journals.first().map(|j| j.conversation_id.clone()) else {
+            return Ok(());
+        };
+
+        // Only the turns of a single conversation are restored at a time
+        let journals = journals
+            .into_iter()
+            .filter(|journal| journal.conversation_id == conversation_id)
+            .collect::<Vec<_>>();
+
+        let prompt = journals
+            .iter()
+            .find_map(|journal| journal.user_message())
+            .unwrap_or_default()
+            .to_string();
+
+        CONSOLE.writeln(
+            TitleFormat::failed("interrupted")
+                .sub_title(format!("Previous turn did not complete: {}", prompt.trim()))
+                .format(),
+        )?;
+        if Self::confirm("Restore the context up to the last completed step?").await? {
+            let workflow = self.api.load(self.cli.workflow.as_deref()).await?;
+            let conversation_id = self.api.restore(workflow, &journals).await?;
+            self.state.conversation_id = Some(conversation_id);
+            self.state.is_first = false;
+            CONSOLE.writeln(TitleFormat::success("restored").format())?;
+        } else {
+            self.api.discard(&journals).await?;
+            CONSOLE.writeln(TitleFormat::success("discarded").format())?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_stats(&self, since: Option<&str>, json: bool) -> Result<()> {
+        let since = since
+            .map(parse_since)
+            .transpose()?
+            .map(|duration| chrono::Utc::now() - duration);
+        let stats = Stats::from_log(&self.api.environment().stats_path(), since)?;
+
+        if json {
+            CONSOLE.writeln_raw(serde_json::to_string_pretty(&stats)?)?;
+        } else {
+            CONSOLE.write(stats.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_upgrade(&self, check: bool) -> Result<()> {
+        let env_var = std::env::var(upgrade::NO_SELF_UPDATE_VAR).ok();
+        if let Some(reason) = upgrade::disabled_reason(&self.config.self_update, env_var.as_deref())
+        {
+            anyhow::bail!(reason);
+        }
+
+        let exe = std::env::current_exe()?;
+        let outcome = upgrade::upgrade(
+            &GitHubReleases::new(&self.api.environment())?,
+            upgrade::VERSION,
+            upgrade::TARGET,
+            &exe,
+            check,
+        )
+        .await?;
+
+        let message = match outcome {
+            Outcome::UpToDate { current } => {
+                TitleFormat::success("upgrade").sub_title(format!("forge {current} is up to date"))
+            }
+            Outcome::Available { current, latest } => TitleFormat::success("upgrade").sub_title(
+                format!("forge {latest} is available, {current} is installed"),
+            ),
+            Outcome::Upgraded { previous, latest } => TitleFormat::success("upgrade")
+                .sub_title(format!("forge upgraded from {previous} to {latest}")),
+        };
+        CONSOLE.writeln(message.format())?;
+        Ok(())
+    }
+
+    /// Indexes files while showing which one is being processed. Ctrl-C stops
+    /// the run after the current file, so what was indexed so far is kept.
+    async fn handle_index(&self, request: IndexRequest) -> Result<()> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let stream = self.api.index(request, cancelled.clone());
+        let summary = self.follow_index(stream, &cancelled).await?;
+        let title = if summary.cancelled { "index cancelled" } else { "index" };
+        CONSOLE.writeln(
+            TitleFormat::success(title)
+                .sub_title(format!(
+                    "{} files indexed ({} chunks), {} unchanged",
+                    summary.indexed, summary.chunks, summary.skipped
+                ))
+                .format(),
+        )?;
+        Ok(())
+    }
+
+    /// Shows the progress of an indexing run, returns its summary. Pressing
+    /// Ctrl-C sets `cancelled`.
+    async fn follow_index(
+        &self,
+        mut stream: impl Stream<Item = Result<IndexProgress>> + Unpin,
+        cancelled: &AtomicBool,
+    ) -> Result<IndexSummary> {
+        let mut total = 0;
+        let mut done = 0;
+
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c(), if !cancelled.load(Ordering::SeqCst) => {
+                    cancelled.store(true, Ordering::SeqCst);
+                    CONSOLE.write("\r\x1b[2K")?;
+                    CONSOLE.writeln("Stopping after the current file...".dimmed().to_string())?;
+                }
+                maybe_progress = stream.next() => {
+                    match maybe_progress {
+                        Some(Ok(IndexProgress::Started { total: count })) => total = count,
+                        Some(Ok(IndexProgress::Indexed { path, .. } | IndexProgress::Skipped { path })) => {
+                            done += 1;
+                            CONSOLE.write(format!("\r\x1b[2K[{done}/{total}] {path}"))?;
+                        }
+                        Some(Ok(IndexProgress::Finished(summary))) => {
+                            CONSOLE.write("\r\x1b[2K")?;
+                            return Ok(summary);
+                        }
+                        Some(Err(err)) => {
+                            CONSOLE.write("\r\x1b[2K")?;
+                            return Err(err);
+                        }
+                        None => anyhow::bail!("Indexing stopped before it finished"),
+                    }
+                }
+            }
+        }
+    }
+
+    async fn handle_rebuild(&self, what: RebuildTarget) -> Result<()> {
+        let _lock = self.api.lock_turns()?;
+        let outcomes = run_rebuilders(
+            what.expand(),
+            |target| self.rebuild(target),
+            |outcome| {
+                let took = format_duration(outcome.duration);
+                let title = match &outcome.result {
+                    Ok(summary) => TitleFormat::success("rebuild")
+                        .sub_title(format!("{}: {summary} in {took}", outcome.target)),
+                    Err(err) => TitleFormat::failed("rebuild")
+                        .sub_title(format!("{} after {took}", outcome.target))
+                        .error(format!("{err:#}")),
+                };
+                CONSOLE.writeln(title.format())?;
+                Ok(())
+            },
+        )
+        .await?;
+
+        let failed = outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_err())
+            .count();
+        if failed > 0 {
+            anyhow::bail!("{failed} of {} rebuilds failed", outcomes.len());
+        }
+        Ok(())
+    }
+
+    /// Runs a single rebuilder, returns what it rebuilt
+    async fn rebuild(&self, target: RebuildTarget) -> Result<String> {
+        match target {
+            RebuildTarget::Embeddings => {
+                let cancelled = Arc::new(AtomicBool::new(false));
+                let stream = self.api.rebuild_index(cancelled.clone());
+                let summary = self.follow_index(stream, &cancelled).await?;
+                let cancelled = if summary.cancelled { ", cancelled" } else { "" };
+                Ok(format!(
+                    "{} files indexed ({} chunks){cancelled}",
+                    summary.indexed, summary.chunks
+                ))
+            }
+            RebuildTarget::Stats => {
+                let rebuild = rebuild_log(&self.api.environment().stats_path())?;
+                Ok(format!(
+                    "{} records kept, {} unreadable dropped",
+                    rebuild.kept, rebuild.dropped
+                ))
+            }
+            RebuildTarget::All => anyhow::bail!("'all' is expanded before rebuilding"),
+        }
+    }
+
+    async fn handle_import(&self, format: ImportFormat, path: &Path) -> Result<()> {
+        let report = self.api.import_history(format, path).await?;
+        for file in &report.files {
+            if let Some(err) = &file.error {
+                CONSOLE.writeln(
+                    TitleFormat::failed("import")
+                        .sub_title(file.path.display().to_string())
+                        .error(err)
+                        .format(),
+                )?;
+            }
+            for skipped in &file.skipped {
+                CONSOLE.writeln(
+                    TitleFormat::warning("import")
+                        .sub_title(format!("{}, {}", file.path.display(), skipped))
+                        .format(),
+                )?;
+            }
+        }
+        CONSOLE.writeln(
+            TitleFormat::success("import")
+                .sub_title(format!(
+                    "{} conversations imported, {} imported before, {} entries skipped",
+                    report.imported(),
+                    report.duplicates(),
+                    report.skipped()
+                ))
+                .format(),
+        )?;
+        Ok(())
+    }
+
+    async fn handle_conversations(&self, tag: Option<&str>, query: Option<&str>) -> Result<()> {
+        let conversations = self.api.conversations(tag, query).await?;
+        for conversation in &conversations {
+            CONSOLE.writeln(format!(
+                "{}  {}  {}",
+                conversation.id,
+                conversation.updated_at.format("%Y-%m-%d %H:%M"),
+                conversation.title().unwrap_or("(untitled)")
+            ))?;
+        }
+        CONSOLE.writeln(
+            TitleFormat::success("conversations")
+                .sub_title(format!("{} listed", conversations.len()))
+                .format(),
+        )?;
+        Ok(())
+    }
+
+    /// Appends the statistics of the turn that just completed to the local
+    /// stats log, priced with the model's pricing when the provider lists
+    /// it. Failing to record is never fatal to the chat.
+    async fn record_turn(&mut self, event_name: &str, started_at: std::time::Instant) {
+        let Some(conversation_id) = self.state.conversation_id.clone() else {
+            return;
+        };
+        let model = match self.api.conversation(&conversation_id).await {
+            Ok(conversation) => conversation
+                .and_then(|c| c.entries(event_name).into_iter().find_map(|a| a.model))
+                .map(|model| model.as_str().to_string()),
+            Err(_) => None,
+        };
+        let usage = self.state.usage.clone();
+        let cost = match model.as_deref() {
+            Some(model) => self
+                .models()
+                .await
+                .ok()
+                .and_then(|models| models.iter().find(|m| m.id.as_str() == model))
+                .and_then(|m| m.pricing.as_ref())
+                .map(|pricing| pricing.cost(&usage)),
+            None => None,
+        };
+
+        let env = self.api.environment();
+        let record = TurnRecord {
+            timestamp: chrono::Utc::now(),
+            conversation_id: conversation_id.into_string(),
+            cwd: env.cwd.display().to_string(),
+            model,
+            tools: self.state.turn_tools.clone(),
+            prompt_tokens: self.state.usage.prompt_tokens,
+            completion_tokens: self.state.usage.completion_tokens,
+            total_tokens: self.state.usage.total_tokens,
+            latency_ms: started_at.elapsed().as_millis() as u64,
+            cost,
+        };
+
+        if let Err(e) = record.append(&env.stats_path()) {
+            tracing::warn!(error = ?e, "Failed to record turn statistics");
+        }
+    }
+
+    /// The passphrase of the conversation store, taken from
+    /// [`STORE_PASSPHRASE_VAR`] or asked for when running in a terminal.
+    /// A new passphrase is asked for twice.
+    async fn store_passphrase(new: bool) -> Result<String> {
+        if let Some(passphrase) = std::env::var(STORE_PASSPHRASE_VAR)
+            .ok()
+            .filter(|passphrase| !passphrase.is_empty())
+        {
+            return Ok(passphrase);
+        }
+        if !std::io::stdin().is_terminal() {
+            anyhow::bail!("Set {STORE_PASSPHRASE_VAR} to the passphrase of the conversation store");
+        }
+
+        let passphrase = Self::read_secret("Store passphrase: ").await?;
+        if passphrase.is_empty() {
+            anyhow::bail!("No passphrase entered");
+        }
+        if new && Self::read_secret("Repeat the passphrase: ").await? != passphrase {
+            anyhow::bail!("The passphrases don't match");
+        }
+        Ok(passphrase)
+    }
+
+    /// Unlocks an encrypted conversation store, failing before any chat when
+    /// the passphrase is missing or wrong
+    async fn unlock_store(&self) -> Result<()> {
+        if !self.api.is_store_encrypted() {
+            return Ok(());
+        }
+
+        let passphrase = Self::store_passphrase(false).await?;
+        self.api.unlock_store(&passphrase).await
+    }
+
+    async fn handle_encrypt_store(&self) -> Result<()> {
+        if self.api.is_store_encrypted() {
+            anyhow::bail!("The conversation store is already encrypted");
+        }
+
+        let passphrase = Self::store_passphrase(true).await?;
+        let count = self.api.encrypt_store(&passphrase).await?;
+        CONSOLE.writeln(
+            TitleFormat::success("encrypt-store")
+                .sub_title(format!("{count} file(s) encrypted"))
+                .format(),
+        )?;
+        Ok(())
+    }
+
+    async fn handle_decrypt_store(&self) -> Result<()> {
+        if !self.api.is_store_encrypted() {
+            anyhow::bail!("The conversation store isn't encrypted");
+        }
+
+        let passphrase = Self::store_passphrase(false).await?;
+        let count = self.api.decrypt_store(&passphrase).await?;
+        CONSOLE.writeln(
+            TitleFormat::success("decrypt-store")
+                .sub_title(format!("{count} file(s) decrypted"))
+                .format(),
+        )?;
+        Ok(())
+    }
+
+    async fn handle_secret(&self, command: &SecretCommand) -> Result<()> {
+        match command {
+            SecretCommand::Set { name } => {
+                let value = if std::io::stdin().is_terminal() {
+                    Self::read_secret(&format!("Value of {name}: ")).await?
+                } else {
+                    let value =
+                        tokio::task::spawn_blocking(|| std::io::read_to_string(std::io::stdin()))
+                            .await??;
+                    value.trim_end_matches(['\r', '\n']).to_string()
+                };
+                self.api.set_secret(name, &value).await?;
+                CONSOLE.writeln(TitleFormat::success("secret").sub_title(name).format())?;
+            }
+            SecretCommand::List => {
+                let secrets = self.api.secrets().await?;
+                if secrets.is_empty() {
+                    CONSOLE.writeln("No secrets stored, add one with `forge secret set NAME`")?;
+                    return Ok(());
+                }
+                let now = chrono::Utc::now();
+                let time_format = if std::io::stdout().is_terminal() {
+                    TimeFormat::Relative
+                } else {
+                    TimeFormat::Absolute
+                };
+                let info = secrets
+                    .iter()
+                    .fold(Info::new().add_title("Secrets"), |info, secret| {
+                        info.add_item(
+                            &secret.name,
+                            format!("set {}", format_time(secret.updated_at, now, time_format)),
+                        )
+                    });
+                CONSOLE.writeln(info.to_string())?;
+            }
+            SecretCommand::Remove { name } => {
+                if !self.api.remove_secret(name).await? {
+                    anyhow::bail!("Unknown secret '{name}'");
+                }
+                CONSOLE.writeln(
+                    TitleFormat::success("secret")
+                        .sub_title(format!("{name} removed"))
+                        .format(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a line from stdin after printing the prompt
+    async fn read_line(prompt: &str) -> Result<String> {
+        CONSOLE.write(prompt)?;
+        let answer = tokio::task::spawn_blocking(|| {
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer).map(|_| answer)
+        })
+        .await??;
+
+        Ok(answer.trim().to_string())
+    }
+
+    /// Asks the user a yes/no question, defaulting to no
+    async fn confirm(question: &str) -> Result<bool> {
+        let answer = Self::read_line(&format!("{} [y/N] ", question)).await?;
+        Ok(answer.eq_ignore_ascii_case("y"))
+    }
+
+    /// Asks the user to pick one of the options until they enter a valid
+    /// number, returns the zero-based index of the option
+    async fn select(options: &[String]) -> Result<usize> {
+        for (i, option) in options.iter().enumerate() {
+            CONSOLE.writeln(format!("  {}. {}", i + 1, option))?;
+        }
+
+        loop {
+            let answer = Self::read_line(&format!("Select 1-{}: ", options.len())).await?;
+            match answer.parse::<usize>() {
+                Ok(n) if (1..=options.len()).contains(&n) => return Ok(n - 1),
+                _ => CONSOLE.writeln(
+                    format!("Enter a number between 1 and {}", options.len())
+                        .dimmed()
+                        .to_string(),
+                )?,
+            }
+        }
+    }
+
+    /// Reads a line without echoing it, printing a `*` for every character.
+    /// Pressing Ctrl-C returns an empty line.
+    async fn read_secret(prompt: &str) -> Result<String> {
+        CONSOLE.write(prompt)?;
+        let secret = tokio::task::spawn_blocking(|| -> std::io::Result<String> {
+            use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+            crossterm::terminal::enable_raw_mode()?;
+            let mut stdout = std::io::stdout();
+            let mut secret = String::new();
+            let result = loop {
+                let event = match read() {
+                    Ok(event) => event,
+                    Err(err) => break Err(err),
+                };
+                let Event::Key(KeyEvent { code, modifiers, kind: KeyEventKind::Press, .. }) = event
+                else {
+                    continue;
+                };
+
+                match code {
+                    KeyCode::Enter => break Ok(()),
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                        secret.clear();
+                        break Ok(());
+                    }
+                    KeyCode::Backspace => {
+                        if secret.pop().is_some() {
+                            let _ = write!(stdout, "\x08 \x08");
+                        }
+                    }
+                    KeyCode::Char(c) => {
+                        secret.push(c);
+                        let _ = write!(stdout, "*");
+                    }
+                    _ => {}
+                }
+                let _ = stdout.flush();
+            };
+            crossterm::terminal::disable_raw_mode()?;
+            result.map(|_| secret)
+        })
+        .await??;
+
+        CONSOLE.newline()?;
+        Ok(secret.trim().to_string())
+    }
+
+    /// The available models, fetched on first use. After a failure they
+    /// aren't fetched again for a while, since they are looked up on every
+    /// turn.
+    async fn models(&mut self) -> Result<&[Model]> {
+        if self.models.is_none() {
+            if let Some(failed_at) = self
+                .models_failed_at
+                .filter(|failed_at| failed_at.elapsed() < MODELS_RETRY_AFTER)
+            {
+                anyhow::bail!(
+                    "Listing the models failed {}s ago",
+                    failed_at.elapsed().as_secs()
+                );
+            }
+            match self.api.models().await {
+                Ok(models) => self.models = Some(models),
+                Err(err) => {
+                    self.models_failed_at = Some(std::time::Instant::now());
+                    return Err(err);
+                }
+            }
+        }
+        Ok(self.models.as_deref().unwrap_or_default())
+    }
+
+    /// Size of the main model's context window, if it's known
+    async fn context_length(&mut self) -> Option<u64> {
+        let model = self.state.model.clone()?;
+        self.models()
+            .await
+            .ok()?
+            .iter()
+            .find(|m| m.id == model)
+            .and_then(|m| m.context_length)
+    }
+
+    /// Price of the main model, if the provider lists it. Responses are priced
+    /// as if they all came from the main model.
+    async fn pricing(&mut self) -> Option<ModelPricing> {
+        let model = self.state.model.clone()?;
+        self.models()
+            .await
+            .ok()?
+            .iter()
+            .find(|m| m.id == model)
+            .and_then(|m| m.pricing.clone())
+    }
+
+    /// Sections contributed by the API along with the state of the session
+    async fn info_sections(&mut self) -> Result<Vec<InfoSection>> {
+        if self.state.model.is_none() {
+            if let Ok(workflow) = self.api.load(self.cli.workflow.as_deref()).await {
+                self.state.model = SessionInfo::main_model(&workflow);
+            }
+        }
+
+        let mut sections = self.api.info_sections().await?;
+        sections.push(
+            SessionInfo {
+                mode: self.state.mode.clone(),
+                model: self.state.model.clone(),
+                conversation_id: self.state.conversation_id.clone(),
+                dry_run: self.cli.dry_run,
+            }
+            .info(),
+        );
+        sections.push(self.state.usage_info().info());
+        Ok(sections)
+    }
+
+    /// Runs the plugin and prints its output, or returns it when the plugin
+    /// asked for it to be sent as a message
+    async fn handle_plugin(&mut self, plugin: &Plugin, args: &str) -> Result<Option<String>> {
+        // Loads the main model when no conversation was started yet
+        self.info_sections().await?;
+        let context = PluginContext {
+            conversation_id: self.state.conversation_id.clone(),
+            cwd: self.api.environment().cwd,
+            model: self.state.model.clone(),
+        };
+        match plugin.run(args, &context, PLUGIN_TIMEOUT).await {
+            Ok(PluginOutput::Print(output)) => {
+                if !output.is_empty() {
+                    CONSOLE.writeln(output)?;
+                }
+                Ok(None)
+            }
+            Ok(PluginOutput::Submit(message)) => Ok(Some(message)),
+            Err(err) => {
+                CONSOLE.writeln(
+                    TitleFormat::failed(format!("/{}", plugin.name))
+                        .error(err.to_string())
+                        .format(),
+                )?;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn init_conversation(&mut self) -> Result<ConversationId> {
+        match self.state.conversation_id {
+            Some(ref id) => Ok(id.clone()),
+            None => {
+                let mut workflow = self.api.load(self.cli.workflow.as_deref()).await?;
+                for warning in workflow.resolve_variables()? {
+                    CONSOLE.writeln(
+                        TitleFormat::failed("variables")
+                            .sub_title(warning.to_string())
+                            .format(),
+                    )?;
+                }
+                self.resolve_models(&mut workflow).await?;
+                self.state.model = SessionInfo::main_model(&workflow);
+                let conversation_id = self.api.init(workflow).await?;
+                self.state.conversation_id = Some(conversation_id.clone());
+                Ok(conversation_id)
+            }
+        }
+    }
+
+    /// Replaces loosely typed model names in the workflow, eg. "sonnet", with
+    /// the ids of the provider's models. Models are used as they are when the
+    /// provider's models can't be listed.
+    async fn resolve_models(&mut self, workflow: &mut Workflow) -> Result<()> {
+        let models = match self.models().await {
+            Ok(models) if !models.is_empty() => models.to_vec(),
+            Ok(_) => return Ok(()),
+            Err(err) => {
+                CONSOLE.writeln(
+                    TitleFormat::failed("model")
+                        .sub_title(format!("Couldn't list models to check against: {err}"))
+                        .format(),
+                )?;
+                return Ok(());
+            }
+        };
+
+        for agent in workflow.agents.iter_mut() {
+            let Some(model) = agent.model.as_ref() else {
+                continue;
+            };
+            let resolution = model.resolve(&models)?;
+            if let Some(notice) = resolution.notice(model) {
+                let title = match resolution {
+                    ModelResolution::Unlisted(_) => TitleFormat::failed("model"),
+                    _ => TitleFormat::success("model"),
+                };
+                CONSOLE.writeln(title.sub_title(notice).format())?;
+            }
+            agent.model = Some(resolution.id().clone());
+        }
+        Ok(())
+    }
+
+    async fn chat(&mut self, content: String) -> Result<()> {
+        let conversation_id = self.init_conversation().await?;
+
+        let event = if self.state.is_first {
+            self.state.is_first = false;
+            Self::create_task_init_event(content)
+        } else {
+            Self::create_task_update_event(content)
+        };
+
+        let event_name = event.name.clone();
+        let mut chat = ChatRequest::new(event, conversation_id.clone())
+            .dry_run(self.cli.dry_run)
+            .refusal(self.config.refusal.clone())
+            .compaction(self.config.compaction.clone())
+            .tool_results(self.config.tool_results.clone());
+        // Nobody is there to answer when forge runs a single prompt
+        let on_iteration_limit = self.cli.on_iteration_limit.or_else(|| {
+            self.cli
+                .prompt
+                .is_some()
+                .then_some(IterationLimitAction::Stop)
+        });
+        if let Some(action) = on_iteration_limit {
+            chat = chat.on_iteration_limit(action);
+        }
+        let started_at = std::time::Instant::now();
+        self.state.turn_tools.clear();
+        self.state.usage = Usage::default();
+
+        let turn_started_at = chrono::Utc::now();
+        let mut stream = self.api.chat(chat).await?;
+        let result = self.handle_chat_stream(&mut stream).await;
+        self.record_turn(&event_name, started_at).await;
+        if self.cli.verbose {
+            self.show_turn_timing(&conversation_id, turn_started_at)
+                .await?;
+        }
+        result
     }
 
-    // Rest of the code remains unchanged...
+    /// Prints where the time of the turn that just completed went
+    async fn show_turn_timing(
+        &self,
+        conversation_id: &ConversationId,
+        turn_started_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let timings = self.api.turn_timings(conversation_id).await?;
+        if let Some(timing) = timings
+            .iter()
+            .rev()
+            .find(|timing| timing.started_at >= turn_started_at)
+        {
+            CONSOLE.writeln(timing.to_string().dimmed().to_string())?;
+        }
+        Ok(())
+    }
+
+    async fn help_chat(&mut self, content: String) -> Result<()> {
+        let conversation_id = self.init_conversation().await?;
+        let chat = ChatRequest::new(Self::create_user_help_query_event(content), conversation_id);
+
+        let mut stream = self.api.chat(chat).await?;
+        self.handle_chat_stream(&mut stream).await
+    }
+
+    async fn handle_chat_stream(
+        &mut self,
+        stream: &mut (impl StreamExt<Item = Result<AgentMessage<ChatResponse>>> + Unpin),
+    ) -> Result<()> {
+        // Lines typed during the turn are queued, unless forge runs a single
+        // prompt and exits
+        let queueing = self.cli.prompt.is_none() && std::io::stdin().is_terminal();
+        let mut read_ahead = queueing.then(ReadAhead::start);
+        self.state.turn_end = TurnEnd::Completed;
+
+        let result = loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {
+                    self.state.turn_end = TurnEnd::Cancelled;
+                    break Ok(());
+                }
+                line = async {
+                    match read_ahead.as_mut() {
+                        Some(read_ahead) => read_ahead.next_line().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    match self.queue_line(&line) {
+                        Ok(true) => {
+                            self.state.turn_end = TurnEnd::Jumped;
+                            break Ok(());
+                        }
+                        Ok(false) => {}
+                        Err(err) => break Err(err),
+                    }
+                }
+                maybe_message = stream.next() => {
+                    match StreamStep::next(maybe_message) {
+                        StreamStep::Handle(message) => {
+                            // Questions are answered on the terminal, which the reader
+                            // lets go of meanwhile
+                            let asks = matches!(
+                                message.message,
+                                ChatResponse::ApprovalRequired(_) | ChatResponse::Interaction { .. }
+                            );
+                            if asks {
+                                if let Some(read_ahead) = read_ahead.take() {
+                                    read_ahead.stop().await;
+                                }
+                            }
+                            let handled = self.handle_chat_response(message).await;
+                            if asks && queueing {
+                                read_ahead = Some(ReadAhead::start());
+                            }
+                            if let Err(err) = handled {
+                                break Err(err);
+                            }
+                        }
+                        StreamStep::Stop(result) => break result,
+                    }
+                }
+            }
+        };
+
+        if let Some(read_ahead) = read_ahead {
+            read_ahead.stop().await;
+        }
+        self.finish_text()?;
+        self.finish_activity(result.is_err())?;
+        if result.is_err() {
+            self.state.turn_end = TurnEnd::Failed;
+        }
+        result
+    }
+
+    async fn handle_chat_response(&mut self, message: AgentMessage<ChatResponse>) -> Result<()> {
+        match message.message {
+            ChatResponse::Text(text) if self.activity.is_compact() => {
+                self.activity.buffer(&text);
+            }
+            ChatResponse::Text(text) if !self.styled => {
+                CONSOLE.write(text)?;
+            }
+            ChatResponse::Text(text) => {
+                let fragments = self.code.push(&text);
+                self.write_fragments(fragments)?;
+            }
+            ChatResponse::ToolCallStart(tool_call) if self.activity.is_compact() => {
+                self.state
+                    .turn_tools
+                    .push(tool_call.name.as_str().to_string());
+                let line = self.activity.start(&tool_call, Instant::now());
+                CONSOLE.write(format!("\r\x1b[2K{}", line.dimmed()))?;
+            }
+            ChatResponse::ToolCallStart(tool_call) => {
+                self.finish_text()?;
+                self.state
+                    .turn_tools
+                    .push(tool_call.name.as_str().to_string());
+                CONSOLE.newline()?;
+                CONSOLE.newline()?;
+            }
+            ChatResponse::ToolCallEnd(tool_result) if self.activity.is_compact() => {
+                let after = match self.activity.written_by(&tool_result) {
+                    Some(path) => Some(tokio::fs::read_to_string(path).await.unwrap_or_default()),
+                    None => None,
+                };
+                let (line, in_flight) = self.activity.end(&tool_result, after, Instant::now());
+                CONSOLE.writeln(format!("\r\x1b[2K{line}"))?;
+                if let Some(in_flight) = in_flight {
+                    CONSOLE.write(in_flight.dimmed().to_string())?;
+                }
+            }
+            ChatResponse::ToolCallEnd(tool_result) => {
+                let output = &self.config.tool_output;
+                if !self.cli.verbose && !output.verbose {
+                    return Ok(());
+                }
+
+                let tool_name = tool_result.name.as_str();
+                let content = self.paths.relativize(&tool_result.content.to_string());
+                CONSOLE.writeln(format!("{}", output.clip(&content).dimmed()))?;
+
+                if tool_result.is_error {
+                    CONSOLE.writeln(TitleFormat::failed(tool_name).format())?;
+                } else {
+                    CONSOLE.writeln(TitleFormat::success(tool_name).format())?;
+                }
+            }
+            ChatResponse::ProviderTool(tool) if self.activity.is_compact() => {
+                CONSOLE.writeln(provider_line(&tool))?;
+            }
+            ChatResponse::ProviderTool(tool) => {
+                self.finish_text()?;
+                let tool_name = tool.call.name.as_str();
+                let title = if tool.result.is_error {
+                    TitleFormat::failed(tool_name)
+                } else {
+                    TitleFormat::success(tool_name)
+                };
+                CONSOLE.newline()?;
+                CONSOLE.writeln(title.sub_title("(provider)").format())?;
+            }
+            ChatResponse::Plan(tool_calls) => {
+                CONSOLE.newline()?;
+                CONSOLE.writeln(
+                    TitleFormat::success("plan")
+                        .sub_title(format!(
+                            "{} tool call(s) were not executed (dry-run)",
+                            tool_calls.len()
+                        ))
+                        .format(),
+                )?;
+                for (i, tool_call) in tool_calls.iter().enumerate() {
+                    CONSOLE.writeln(format!(
+                        "  {}. {} {}",
+                        i + 1,
+                        tool_call.name.as_str().bold(),
+                        tool_call.arguments.to_string().dimmed()
+                    ))?;
+                }
+            }
+            ChatResponse::IterationLimit(limit) => {
+                self.finish_activity(false)?;
+                CONSOLE.newline()?;
+                CONSOLE.writeln(
+                    TitleFormat::warning("iteration limit")
+                        .sub_title(format!(
+                            "{} requests in this turn, the last tool calls were:",
+                            limit.iterations
+                        ))
+                        .format(),
+                )?;
+                for tool_call in &limit.recent_calls {
+                    CONSOLE.writeln(format!(
+                        "  {} {}",
+                        tool_call.name.as_str().bold(),
+                        tool_call.arguments.to_string().dimmed()
+                    ))?;
+                }
+            }
+            ChatResponse::Refusal(refusal) => {
+                self.finish_activity(false)?;
+                CONSOLE.newline()?;
+                let sub_title = if refusal.retrying {
+                    "the model declined the request, asking again with the development context"
+                } else {
+                    "the model declined the request"
+                };
+                CONSOLE.writeln(
+                    TitleFormat::warning("refused")
+                        .sub_title(sub_title)
+                        .format(),
+                )?;
+            }
+            ChatResponse::Compacted(compaction) => {
+                self.finish_activity(false)?;
+                CONSOLE.newline()?;
+                CONSOLE.writeln(
+                    TitleFormat::warning("compacted")
+                        .sub_title(format!(
+                            "the request exceeded the model's context, sent again without older tool outputs (~{} → ~{} tokens)",
+                            compaction.before, compaction.after
+                        ))
+                        .format(),
+                )?;
+            }
+            ChatResponse::ApprovalRequired(tool_call) => {
+                CONSOLE.newline()?;
+                CONSOLE.writeln(
+                    TitleFormat::execute(tool_call.name.as_str())
+                        .sub_title(tool_call.arguments.to_string())
+                        .format(),
+                )?;
+                let approved = Self::confirm("Allow this tool call?").await?;
+                // The call may have timed out while the user was deciding
+                if let Some(call_id) = tool_call.call_id {
+                    if let Err(err) = self.api.approve(&call_id, approved).await {
+                        CONSOLE.writeln(TitleFormat::warning(err.to_string()).format())?;
+                    }
+                }
+            }
+            ChatResponse::Interaction { call_id, interaction } => {
+                CONSOLE.newline()?;
+                CONSOLE.writeln(
+                    TitleFormat::execute("question")
+                        .sub_title(interaction.prompt())
+                        .format(),
+                )?;
+                loop {
+                    let answer = match &interaction {
+                        UserInteraction::Question { .. } => {
+                            UserAnswer::Text(Self::read_line("> ").await?)
+                        }
+                        UserInteraction::Confirm { .. } => {
+                            let yes = Self::confirm(">").await?;
+                            UserAnswer::Text(if yes { "yes" } else { "no" }.to_string())
+                        }
+                        UserInteraction::Select { options, .. } => {
+                            UserAnswer::Selected(Self::select(options).await?)
+                        }
+                    };
+
+                    match self.api.answer(&call_id, answer).await {
+                        Ok(()) => break,
+                        Err(err) => {
+                            CONSOLE.writeln(TitleFormat::warning(err.to_string()).format())?;
+                            // The question may also have timed out while the user was
+                            // answering, only an invalid answer is asked again
+                            if !matches!(err.downcast_ref::<Error>(), Some(Error::InvalidAnswer(_)))
+                            {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            ChatResponse::Waiting { model, elapsed } => {
+                CONSOLE.newline()?;
+                CONSOLE.writeln(
+                    TitleFormat::execute("waiting")
+                        .sub_title(format!(
+                            "still waiting for {}, {}s elapsed, the provider may be queueing",
+                            model, elapsed
+                        ))
+                        .format(),
+                )?;
+            }
+            ChatResponse::Error { severity, message } => {
+                self.finish_activity(false)?;
+                CONSOLE.newline()?;
+                CONSOLE.writeln(format_error(severity, &message))?;
+            }
+            ChatResponse::ReadProgress(progress) => {
+                // The line is updated in place and cleared once the file is read
+                let percent = progress.read * 100 / progress.total.max(1);
+                CONSOLE.write(format!(
+                    "\r\x1b[2K{}",
+                    format!(
+                        "reading {} {percent}% ({}/{})",
+                        progress.path,
+                        format_size(progress.read),
+                        format_size(progress.total)
+                    )
+                    .dimmed()
+                ))?;
+                if progress.read >= progress.total {
+                    CONSOLE.write("\r\x1b[2K")?;
+                }
+            }
+            ChatResponse::Suggestions(suggestions) => {
+                CONSOLE.newline()?;
+                if !suggestions.tags.is_empty() {
+                    CONSOLE.writeln(
+                        format!(
+                            "suggested tags: {} — /tag add to apply",
+                            suggestions.tags.join(", ")
+                        )
+                        .dimmed()
+                        .to_string(),
+                    )?;
+                }
+                if let Some(title) = suggestions.title {
+                    CONSOLE.writeln(format!("suggested title: {title}").dimmed().to_string())?;
+                }
+            }
+            ChatResponse::Custom(event) => {
+                if event.name == EVENT_TITLE {
+                    self.state.current_title = Some(event.value);
+                }
+            }
+            ChatResponse::Usage(usage) => {
+                let pricing = self.pricing().await;
+                self.state.add_cost(&usage, pricing.as_ref());
+                self.state.add_usage(usage);
+                if let Some(share) = self
+                    .context_length()
+                    .await
+                    .and_then(|length| self.state.context_warning(length))
+                {
+                    CONSOLE.writeln(
+                        TitleFormat::warning(format!(
+                            "The context is {:.0}% full, use /compact to free up space",
+                            share * 100.0
+                        ))
+                        .format(),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A chat turn that offers to replace the API key when the provider rejects
+/// it
+struct ChatTurn<'a, F> {
+    ui: &'a mut UI<F>,
+    content: String,
+    /// Whether the turn starts the conversation, a retry sends the same event
+    is_first: bool,
+}
+
+impl<'a, F> ChatTurn<'a, F> {
+    fn new(ui: &'a mut UI<F>, content: String) -> Self {
+        let is_first = ui.state.is_first;
+        Self { ui, content, is_first }
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: API> RecoverableTurn for ChatTurn<'_, F> {
+    async fn run(&mut self) -> Result<()> {
+        // A rejected turn is rolled back, so it's sent again as it was the
+        // first time
+        self.ui.state.is_first = self.is_first;
+        match self.ui.state.mode {
+            Mode::Help => self.ui.help_chat(self.content.clone()).await,
+            _ => self.ui.chat(self.content.clone()).await,
+        }
+    }
+
+    async fn ask_key(&mut self, err: &anyhow::Error) -> Result<Option<String>> {
+        CONSOLE.newline()?;
+        CONSOLE.writeln(TitleFormat::failed(err.to_string()).format())?;
+        if !UI::<F>::confirm("Enter a new API key now?").await? {
+            return Ok(None);
+        }
+
+        let key = UI::<F>::read_secret("API key: ").await?;
+        Ok(Some(key).filter(|key| !key.is_empty()))
+    }
+
+    async fn update_key(&mut self, key: String) -> Result<()> {
+        self.ui.api.update_provider_key(key).await?;
+        CONSOLE.writeln(
+            TitleFormat::success("api key")
+                .sub_title("updated, retrying the turn")
+                .format(),
+        )?;
+        Ok(())
+    }
 }
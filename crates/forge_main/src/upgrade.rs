@@ -0,0 +1,467 @@
+//! Replaces the running binary with the latest build published on GitHub
+//! releases.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use forge_api::{Environment, SelfUpdate};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Version the binary was built as
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Target triple the binary was built for
+pub const TARGET: &str = env!("FORGE_TARGET");
+
+/// Set to disable `forge upgrade`, eg. for installs managed by a package
+/// manager
+pub const NO_SELF_UPDATE_VAR: &str = "FORGE_NO_SELF_UPDATE";
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/antinomyhq/forge/releases/latest";
+
+/// Names of the files that can hold the sha256 checksums of a release's
+/// binaries, in `sha256sum` format
+const CHECKSUM_FILES: [&str; 2] = ["checksums.txt", "SHA256SUMS"];
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+/// Where releases are looked up and downloaded from
+#[async_trait::async_trait]
+pub trait ReleaseSource {
+    async fn latest(&self) -> Result<Release>;
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>>;
+}
+
+/// Releases published on GitHub
+pub struct GitHubReleases {
+    client: reqwest::Client,
+}
+
+impl GitHubReleases {
+    /// Looks up and downloads releases through the configured proxies,
+    /// trusting the configured certificates and with the configured timeouts
+    pub fn new(env: &Environment) -> Result<Self> {
+        let client = forge_api::http_client(&env.proxy, &env.tls, &env.http_timeout)?;
+        Ok(Self { client })
+    }
+
+    fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        self.client
+            .get(url)
+            .header(reqwest::header::USER_AGENT, format!("forge/{VERSION}"))
+    }
+}
+
+#[async_trait::async_trait]
+impl ReleaseSource for GitHubReleases {
+    async fn latest(&self) -> Result<Release> {
+        self.get(LATEST_RELEASE_URL)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("Failed to look up the latest release on GitHub")?
+            .json()
+            .await
+            .context("Failed to read the latest release from GitHub")
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        let bytes = self
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to download {url}"))?
+            .bytes()
+            .await
+            .with_context(|| format!("Failed to download {url}"))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    UpToDate { current: String },
+    Available { current: String, latest: String },
+    Upgraded { previous: String, latest: String },
+}
+
+/// Tells why self-update is disabled, if it is
+pub fn disabled_reason(config: &SelfUpdate, env_var: Option<&str>) -> Option<String> {
+    if env_var.is_some_and(|value| !value.is_empty()) {
+        return Some(format!(
+            "Self-update is disabled by {NO_SELF_UPDATE_VAR}, upgrade forge with the tool it was installed with"
+        ));
+    }
+    if !config.enabled {
+        return Some(
+            "Self-update is disabled in config.yaml, upgrade forge with the tool it was installed with"
+                .to_string(),
+        );
+    }
+    None
+}
+
+/// Whether `latest` is a newer version than `current`. Tags may start with
+/// `v`.
+pub fn is_newer(current: &str, latest: &str) -> Result<bool> {
+    let parse = |version: &str| {
+        semver::Version::parse(version.trim_start_matches('v'))
+            .with_context(|| format!("Invalid version '{version}'"))
+    };
+    Ok(parse(latest)? > parse(current)?)
+}
+
+/// Name of the release asset built for the target, as published by the
+/// release workflow
+pub fn asset_name(target: &str) -> String {
+    if target.contains("windows") {
+        format!("forge-{target}.exe")
+    } else {
+        format!("forge-{target}")
+    }
+}
+
+pub fn select_asset<'a>(release: &'a Release, target: &str) -> Result<&'a Asset> {
+    let name = asset_name(target);
+    release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .with_context(|| {
+            format!(
+                "Release {} has no binary for {target} (expected {name})",
+                release.tag_name
+            )
+        })
+}
+
+/// Looks up the checksum of the asset in a checksums file of the release,
+/// `None` when the release doesn't publish one
+async fn expected_checksum(
+    source: &impl ReleaseSource,
+    release: &Release,
+    asset: &Asset,
+) -> Result<Option<String>> {
+    let Some(file) = release
+        .assets
+        .iter()
+        .find(|candidate| CHECKSUM_FILES.contains(&candidate.name.as_str()))
+    else {
+        return Ok(None);
+    };
+
+    let content = source.download(&file.browser_download_url).await?;
+    let checksum = String::from_utf8_lossy(&content)
+        .lines()
+        .find_map(|line| {
+            let (hash, name) = line.split_once(char::is_whitespace)?;
+            (name.trim().trim_start_matches('*') == asset.name).then(|| hash.to_lowercase())
+        })
+        .with_context(|| format!("{} has no checksum for {}", file.name, asset.name))?;
+    Ok(Some(checksum))
+}
+
+pub fn verify_checksum(bytes: &[u8], expected: &str) -> Result<()> {
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    if actual != expected {
+        bail!("Checksum mismatch for the downloaded binary: expected {expected}, got {actual}");
+    }
+    Ok(())
+}
+
+/// Replaces the executable with the given content. The new binary is written
+/// next to it and renamed over it, so the executable is never left half
+/// written. Windows doesn't allow replacing a running executable, so there it
+/// is first moved aside.
+pub fn replace_executable(exe: &Path, bytes: &[u8]) -> Result<()> {
+    let staged = sibling(exe, "new");
+    std::fs::write(&staged, bytes)
+        .with_context(|| format!("Failed to write {}", staged.display()))?;
+
+    let permissions = std::fs::metadata(exe)
+        .with_context(|| format!("Failed to read {}", exe.display()))?
+        .permissions();
+    std::fs::set_permissions(&staged, permissions)
+        .with_context(|| format!("Failed to set permissions of {}", staged.display()))?;
+
+    let result = swap(exe, &staged);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&staged);
+    }
+    result
+}
+
+#[cfg(not(windows))]
+fn swap(exe: &Path, staged: &Path) -> Result<()> {
+    std::fs::rename(staged, exe).with_context(|| format!("Failed to replace {}", exe.display()))
+}
+
+#[cfg(windows)]
+fn swap(exe: &Path, staged: &Path) -> Result<()> {
+    let old = sibling(exe, "old");
+    // Left behind by a previous upgrade, once that binary stopped running
+    let _ = std::fs::remove_file(&old);
+
+    std::fs::rename(exe, &old)
+        .with_context(|| format!("Failed to move {} aside", exe.display()))?;
+    if let Err(err) = std::fs::rename(staged, exe) {
+        let _ = std::fs::rename(&old, exe);
+        return Err(err).with_context(|| format!("Failed to replace {}", exe.display()));
+    }
+    Ok(())
+}
+
+/// A file next to `path` with `suffix` appended to its name
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{suffix}"));
+    path.with_file_name(name)
+}
+
+/// Checks for a newer release and, unless `check` is set, replaces the
+/// executable with its binary for the target
+pub async fn upgrade(
+    source: &impl ReleaseSource,
+    current: &str,
+    target: &str,
+    exe: &Path,
+    check: bool,
+) -> Result<Outcome> {
+    let release = source.latest().await?;
+    let latest = release.tag_name.trim_start_matches('v').to_string();
+    if !is_newer(current, &latest)? {
+        return Ok(Outcome::UpToDate { current: current.to_string() });
+    }
+    if check {
+        return Ok(Outcome::Available { current: current.to_string(), latest });
+    }
+
+    let asset = select_asset(&release, target)?;
+    let checksum = expected_checksum(source, &release, asset).await?;
+    let bytes = source.download(&asset.browser_download_url).await?;
+    if let Some(checksum) = checksum {
+        verify_checksum(&bytes, &checksum)?;
+    }
+    replace_executable(exe, &bytes)?;
+
+    Ok(Outcome::Upgraded { previous: current.to_string(), latest })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct StubSource {
+        tag: String,
+        files: HashMap<String, Vec<u8>>,
+    }
+
+    impl StubSource {
+        fn new(tag: &str) -> Self {
+            Self { tag: tag.to_string(), ..Default::default() }
+        }
+
+        fn file(mut self, name: &str, content: &[u8]) -> Self {
+            self.files.insert(name.to_string(), content.to_vec());
+            self
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ReleaseSource for StubSource {
+        async fn latest(&self) -> Result<Release> {
+            let assets = self
+                .files
+                .keys()
+                .map(|name| Asset {
+                    name: name.clone(),
+                    browser_download_url: format!("https://example.com/{name}"),
+                })
+                .collect();
+            Ok(Release { tag_name: self.tag.clone(), assets })
+        }
+
+        async fn download(&self, url: &str) -> Result<Vec<u8>> {
+            let name = url.trim_start_matches("https://example.com/");
+            self.files.get(name).cloned().context("not found")
+        }
+    }
+
+    fn executable(dir: &Path) -> PathBuf {
+        let exe = dir.join("forge");
+        std::fs::write(&exe, "old").unwrap();
+        exe
+    }
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("0.9.0", "v0.10.0").unwrap());
+        assert!(is_newer("1.0.0-rc.1", "1.0.0").unwrap());
+        assert!(!is_newer("0.10.0", "v0.10.0").unwrap());
+        assert!(!is_newer("0.10.1", "0.10.0").unwrap());
+        assert!(is_newer("0.10.0", "latest").is_err());
+    }
+
+    #[test]
+    fn test_asset_name_per_target() {
+        let actual = [
+            "x86_64-unknown-linux-musl",
+            "aarch64-apple-darwin",
+            "x86_64-pc-windows-msvc",
+        ]
+        .map(asset_name);
+
+        let expected = [
+            "forge-x86_64-unknown-linux-musl",
+            "forge-aarch64-apple-darwin",
+            "forge-x86_64-pc-windows-msvc.exe",
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_select_asset_missing_target() {
+        let fixture = Release {
+            tag_name: "v0.2.0".to_string(),
+            assets: vec![Asset {
+                name: "forge-x86_64-unknown-linux-gnu".to_string(),
+                browser_download_url: String::new(),
+            }],
+        };
+
+        let actual = select_asset(&fixture, "riscv64gc-unknown-linux-gnu")
+            .unwrap_err()
+            .to_string();
+
+        let expected = "Release v0.2.0 has no binary for riscv64gc-unknown-linux-gnu (expected forge-riscv64gc-unknown-linux-gnu)";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_disabled_reason() {
+        let enabled = SelfUpdate::default();
+        let disabled = SelfUpdate { enabled: false };
+
+        assert_eq!(disabled_reason(&enabled, None), None);
+        assert_eq!(disabled_reason(&enabled, Some("")), None);
+        assert!(disabled_reason(&enabled, Some("1")).is_some());
+        assert!(disabled_reason(&disabled, None).is_some());
+    }
+
+    #[test]
+    fn test_replace_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = executable(dir.path());
+
+        replace_executable(&exe, b"new").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "new");
+        assert!(!sibling(&exe, "new").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replace_executable_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let exe = executable(dir.path());
+        std::fs::set_permissions(&exe, std::fs::Permissions::from_mode(0o750)).unwrap();
+
+        replace_executable(&exe, b"new").unwrap();
+
+        let actual = std::fs::metadata(&exe).unwrap().permissions().mode() & 0o777;
+        assert_eq!(actual, 0o750);
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_verifies_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = executable(dir.path());
+        let checksum = format!("{:x}", Sha256::digest(b"new"));
+        let source = StubSource::new("v0.2.0")
+            .file("forge-x86_64-unknown-linux-gnu", b"new")
+            .file(
+                "checksums.txt",
+                format!("{checksum}  forge-x86_64-unknown-linux-gnu\n").as_bytes(),
+            );
+
+        let actual = upgrade(&source, "0.1.0", "x86_64-unknown-linux-gnu", &exe, false)
+            .await
+            .unwrap();
+
+        let expected =
+            Outcome::Upgraded { previous: "0.1.0".to_string(), latest: "0.2.0".to_string() };
+        assert_eq!(actual, expected);
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "new");
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_rejects_checksum_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = executable(dir.path());
+        let source = StubSource::new("v0.2.0")
+            .file("forge-x86_64-unknown-linux-gnu", b"tampered")
+            .file(
+                "checksums.txt",
+                format!(
+                    "{:x}  forge-x86_64-unknown-linux-gnu\n",
+                    Sha256::digest(b"new")
+                )
+                .as_bytes(),
+            );
+
+        let actual = upgrade(&source, "0.1.0", "x86_64-unknown-linux-gnu", &exe, false).await;
+
+        assert!(actual.is_err());
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "old");
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_check_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = executable(dir.path());
+        let source = StubSource::new("v0.2.0").file("forge-x86_64-unknown-linux-gnu", b"new");
+
+        let actual = upgrade(&source, "0.1.0", "x86_64-unknown-linux-gnu", &exe, true)
+            .await
+            .unwrap();
+
+        let expected =
+            Outcome::Available { current: "0.1.0".to_string(), latest: "0.2.0".to_string() };
+        assert_eq!(actual, expected);
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "old");
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_up_to_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = executable(dir.path());
+        let source = StubSource::new("v0.2.0");
+
+        let actual = upgrade(&source, "0.2.0", "x86_64-unknown-linux-gnu", &exe, false)
+            .await
+            .unwrap();
+
+        assert_eq!(actual, Outcome::UpToDate { current: "0.2.0".to_string() });
+    }
+}
@@ -0,0 +1,245 @@
+//! Wraps console output to the width of the terminal.
+//!
+//! Output is wrapped as it's written, so text streamed in chunks wraps like
+//! text written at once. ANSI escape sequences take no room and are never
+//! split.
+
+use std::io::IsTerminal;
+
+/// Width used when the size of the terminal can't be read
+pub const DEFAULT_WIDTH: usize = 80;
+
+const ESC: char = '\x1b';
+const TAB_WIDTH: usize = 8;
+
+/// Width of the terminal stdout writes to, none when it's redirected to a
+/// file or a pipe, whose lines are left whole
+pub fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    match crossterm::terminal::size() {
+        Ok((columns, _)) if columns > 0 => Some(columns as usize),
+        _ => Some(DEFAULT_WIDTH),
+    }
+}
+
+/// A piece of text to wrap
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    /// An escape sequence, printed as is
+    Escape(&'a str),
+    Newline,
+    /// A carriage return, moving back to the start of the line
+    Return,
+    Space(char),
+    /// A visible character of a word
+    Char(char),
+}
+
+/// Splits text into escape sequences and characters
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let len = if c == ESC {
+            escape_len(rest)
+        } else {
+            c.len_utf8()
+        };
+        tokens.push(match c {
+            ESC => Token::Escape(&rest[..len]),
+            '\n' => Token::Newline,
+            '\r' => Token::Return,
+            ' ' | '\t' => Token::Space(c),
+            c => Token::Char(c),
+        });
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+/// Length of the escape sequence `text` starts with: a CSI sequence such as
+/// a color ends with a byte from `@` to `~`, other sequences take the
+/// character after the escape
+fn escape_len(text: &str) -> usize {
+    let mut chars = text.char_indices().skip(1);
+    match chars.next() {
+        Some((_, '[')) => chars
+            .find(|(_, c)| ('@'..='~').contains(c))
+            .map(|(index, c)| index + c.len_utf8())
+            .unwrap_or(text.len()),
+        Some((index, c)) => index + c.len_utf8(),
+        None => text.len(),
+    }
+}
+
+/// Wraps text at word boundaries, remembering the column between writes
+#[derive(Debug, Default)]
+pub struct Wrap {
+    column: usize,
+    /// Whether the last character written was part of a word
+    in_word: bool,
+}
+
+impl Wrap {
+    /// Wraps `text` to `width` columns. A word that doesn't fit on the rest
+    /// of the line moves to the next one and is broken where lines end when
+    /// it's longer than a line, as is a word continuing the previous write.
+    pub fn wrap(&mut self, text: &str, width: usize) -> String {
+        let width = width.max(1);
+        let tokens = tokens(text);
+        let mut wrapped = String::with_capacity(text.len());
+        // Spaces, and the escapes among them, held back until it's known
+        // whether the line breaks after them
+        let mut spaces: Vec<&Token> = Vec::new();
+
+        let mut index = 0;
+        while let Some(token) = tokens.get(index) {
+            match token {
+                Token::Escape(escape) if spaces.is_empty() => wrapped.push_str(escape),
+                Token::Escape(_) => spaces.push(token),
+                Token::Space(_) => {
+                    spaces.push(token);
+                    self.in_word = false;
+                }
+                Token::Newline | Token::Return => {
+                    self.write_spaces(&spaces, usize::MAX, &mut wrapped);
+                    spaces.clear();
+                    wrapped.push(if *token == Token::Newline { '\n' } else { '\r' });
+                    self.column = 0;
+                    self.in_word = false;
+                }
+                Token::Char(_) => {
+                    let word = tokens[index..]
+                        .iter()
+                        .take_while(|token| matches!(token, Token::Char(_) | Token::Escape(_)))
+                        .collect::<Vec<_>>();
+                    let word_width = word
+                        .iter()
+                        .filter(|token| matches!(token, Token::Char(_)))
+                        .count();
+
+                    let overflows = advance(self.column, &spaces) + word_width > width;
+                    if overflows && !self.in_word && self.column > 0 {
+                        // The word moves to the next line, without the spaces before it
+                        for token in &spaces {
+                            if let Token::Escape(escape) = token {
+                                wrapped.push_str(escape);
+                            }
+                        }
+                        wrapped.push('\n');
+                        self.column = 0;
+                    } else {
+                        self.write_spaces(&spaces, width, &mut wrapped);
+                    }
+                    spaces.clear();
+
+                    for token in &word {
+                        match token {
+                            Token::Char(c) => {
+                                if self.column >= width {
+                                    wrapped.push('\n');
+                                    self.column = 0;
+                                }
+                                wrapped.push(*c);
+                                self.column += 1;
+                            }
+                            Token::Escape(escape) => wrapped.push_str(escape),
+                            _ => {}
+                        }
+                    }
+                    self.in_word = true;
+                    index += word.len();
+                    continue;
+                }
+            }
+            index += 1;
+        }
+
+        self.write_spaces(&spaces, width, &mut wrapped);
+        wrapped
+    }
+
+    /// Writes the spaces that fit on the line and all escapes
+    fn write_spaces(&mut self, spaces: &[&Token], width: usize, wrapped: &mut String) {
+        for token in spaces {
+            match token {
+                Token::Escape(escape) => wrapped.push_str(escape),
+                Token::Space(c) if self.column < width => {
+                    wrapped.push(*c);
+                    self.column = advance(self.column, &[*token]);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Column after the spaces
+fn advance(column: usize, spaces: &[&Token]) -> usize {
+    spaces.iter().fold(column, |column, token| match token {
+        Token::Space('\t') => (column / TAB_WIDTH + 1) * TAB_WIDTH,
+        Token::Space(_) => column + 1,
+        _ => column,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn wrap(chunks: &[&str], width: usize) -> String {
+        let mut wrap = Wrap::default();
+        chunks.iter().map(|chunk| wrap.wrap(chunk, width)).collect()
+    }
+
+    #[test]
+    fn test_wrap_at_word_boundaries() {
+        let actual = wrap(&["the quick brown fox jumps over the lazy dog"], 16);
+
+        let expected = "the quick brown\nfox jumps over\nthe lazy dog";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_short_lines_are_untouched() {
+        let actual = wrap(&["short\nlines\n"], 16);
+
+        assert_eq!(actual, "short\nlines\n");
+    }
+
+    #[test]
+    fn test_long_word_is_broken_at_the_width() {
+        let actual = wrap(&["see abcdefghijklmnop"], 8);
+
+        assert_eq!(actual, "see\nabcdefgh\nijklmnop");
+    }
+
+    #[test]
+    fn test_column_is_kept_between_writes() {
+        let actual = wrap(&["the quick ", "brown fox", " jumps"], 12);
+
+        assert_eq!(actual, "the quick \nbrown fox\njumps");
+    }
+
+    #[test]
+    fn test_escape_codes_are_never_split() {
+        let colored = "\x1b[1mbold\x1b[22m \x1b[38;2;192;197;206mcolored words\x1b[0m and \x1b[36mmore_text_than_fits\x1b[39m";
+
+        let actual = wrap(&[colored], 10);
+
+        let expected = "\x1b[1mbold\x1b[22m\x1b[38;2;192;197;206m\ncolored\nwords\x1b[0m and\x1b[36m\nmore_text_\nthan_fits\x1b[39m";
+        assert_eq!(actual, expected);
+        for token in tokens(colored) {
+            if let Token::Escape(escape) = token {
+                assert!(actual.contains(escape), "{escape:?} was split");
+            }
+        }
+        for line in actual.lines() {
+            assert!(strip_ansi_escapes::strip_str(line).chars().count() <= 10);
+        }
+    }
+}
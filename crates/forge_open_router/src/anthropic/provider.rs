@@ -8,6 +8,11 @@ use tokio_stream::StreamExt;
 
 use super::request::Request;
 use super::response::{EventData, ListModelResponse};
+use crate::http::transport_error;
+use crate::status::{auth_error, check_status, is_auth_failure, provider_error};
+
+/// Beta that lets tools be sent with input examples
+const TOOL_EXAMPLES_BETA: &str = "advanced-tool-use-2025-11-20";
 
 #[derive(Clone, Builder)]
 pub struct Anthropic {
@@ -68,10 +73,18 @@ impl ProviderService for Anthropic {
             .stream(true)
             .max_tokens(4000u64);
 
+        let mut headers = self.headers();
+        if request.has_tool_examples() {
+            headers.insert(
+                "anthropic-beta",
+                HeaderValue::from_static(TOOL_EXAMPLES_BETA),
+            );
+        }
+
         let es = self
             .client
             .post(self.url("/messages")?)
-            .headers(self.headers())
+            .headers(headers)
             .json(&request)
             .eventsource()?;
 
@@ -94,6 +107,17 @@ impl ProviderService for Anthropic {
                         ),
                     },
                     Err(reqwest_eventsource::Error::StreamEnded) => None,
+                    Err(reqwest_eventsource::Error::InvalidStatusCode(status, response))
+                        if is_auth_failure(status) =>
+                    {
+                        Some(Err(auth_error(response).await))
+                    }
+                    Err(reqwest_eventsource::Error::InvalidStatusCode(_, response)) => {
+                        Some(Err(provider_error(response).await))
+                    }
+                    Err(reqwest_eventsource::Error::Transport(err)) => {
+                        Some(Err(transport_error(err)))
+                    }
                     Err(err) => Some(Err(err.into())),
                 }
             });
@@ -101,16 +125,14 @@ impl ProviderService for Anthropic {
         Ok(Box::pin(stream.filter_map(|x| x)))
     }
     async fn models(&self) -> anyhow::Result<Vec<Model>> {
-        let text = self
+        let response = self
             .client
             .get(self.url("models")?)
             .headers(self.headers())
             .send()
-            .await?
-            .error_for_status()
-            .with_context(|| "Failed because of a non 200 status code".to_string())?
-            .text()
-            .await?;
+            .await
+            .map_err(transport_error)?;
+        let text = check_status(response).await?.text().await?;
         let response: ListModelResponse = serde_json::from_str(&text)?;
         Ok(response.data.into_iter().map(Into::into).collect())
     }
@@ -119,7 +141,8 @@ impl ProviderService for Anthropic {
 #[cfg(test)]
 mod tests {
     use forge_domain::{
-        Context, ContextMessage, ToolCallFull, ToolCallId, ToolChoice, ToolName, ToolResult,
+        Context, ContextMessage, ToolCallFull, ToolCallId, ToolChoice, ToolContent, ToolName,
+        ToolResult,
     };
 
     use super::*;
@@ -157,8 +180,10 @@ mod tests {
             .add_tool_results(vec![ToolResult {
                 name: ToolName::new("math"),
                 call_id: Some(ToolCallId::new("math-1")),
-                content: serde_json::json!({"result": 4}).to_string(),
+                content: ToolContent::json(serde_json::json!({"result": 4})),
                 is_error: false,
+                before: None,
+                provider: false,
             }])
             .tool_choice(ToolChoice::Call(ToolName::new("math")));
         let request = Request::try_from(context)
@@ -168,4 +193,14 @@ mod tests {
             .max_tokens(4000u64);
         insta::assert_snapshot!(serde_json::to_string_pretty(&request).unwrap());
     }
+
+    #[test]
+    fn test_stray_system_message_is_rejected() {
+        let mut context = Context::default().system("Be brief");
+        context.messages.push(ContextMessage::system("Be verbose"));
+
+        let actual = Request::try_from(context);
+
+        assert!(actual.is_err());
+    }
 }
@@ -32,33 +32,13 @@ pub struct Request {
 impl TryFrom<forge_domain::Context> for Request {
     type Error = anyhow::Error;
     fn try_from(request: forge_domain::Context) -> std::result::Result<Self, Self::Error> {
-        // note: Anthropic only supports 1 system message in context, so from the
-        // context we pick the first system message available.
+        let request = request.inline_provider_tools();
+        // note: Anthropic takes the system message in a field of its own.
         // ref: https://docs.anthropic.com/en/api/messages#body-system
-        let system = request.messages.iter().find_map(|message| {
-            if let ContextMessage::ContentMessage(chat_message) = message {
-                if chat_message.role == forge_domain::Role::System {
-                    Some(chat_message.content.clone())
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        });
-
         Ok(Self {
             messages: request
                 .messages
                 .into_iter()
-                .filter(|message| {
-                    // note: Anthropic does not support system messages in message field.
-                    if let ContextMessage::ContentMessage(chat_message) = message {
-                        chat_message.role != forge_domain::Role::System
-                    } else {
-                        true
-                    }
-                })
                 .map(Message::try_from)
                 .collect::<std::result::Result<Vec<_>, _>>()?,
             tools: request
@@ -66,13 +46,23 @@ impl TryFrom<forge_domain::Context> for Request {
                 .into_iter()
                 .map(ToolDefinition::try_from)
                 .collect::<std::result::Result<Vec<_>, _>>()?,
-            system,
+            system: request.system,
             tool_choice: request.tool_choice.map(ToolChoice::from),
             ..Default::default()
         })
     }
 }
 
+impl Request {
+    /// Whether a tool comes with input examples, which the API only accepts
+    /// with the tool examples beta enabled
+    pub fn has_tool_examples(&self) -> bool {
+        self.tools
+            .iter()
+            .any(|tool| !tool.input_examples.is_empty())
+    }
+}
+
 #[derive(Serialize)]
 pub struct Metadata {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -112,8 +102,8 @@ impl TryFrom<ContextMessage> for Message {
                     forge_domain::Role::User => Message { role: Role::User, content },
                     forge_domain::Role::Assistant => Message { role: Role::Assistant, content },
                     forge_domain::Role::System => {
-                        // note: Anthropic doesn't support system role messages and they're already
-                        // filtered out. so this state is unreachable.
+                        // note: Anthropic doesn't support system role messages and the context
+                        // keeps the system message apart. so this state is unreachable.
                         return Err(anyhow::anyhow!("system role messages are not supported in the context for anthropic provider".to_string()));
                     }
                 }
@@ -234,7 +224,8 @@ impl TryFrom<forge_domain::ToolResult> for Content {
         Ok(Content::ToolResult {
             tool_use_id: call_id.as_str().to_string(),
             cache_control: None,
-            content: Some(value.content),
+            // Anthropic takes text, JSON content is serialized
+            content: Some(value.content.to_string()),
             is_error: Some(value.is_error),
         })
     }
@@ -297,6 +288,8 @@ pub struct ToolDefinition {
     #[serde(skip_serializing_if = "Option::is_none")]
     cache_control: Option<CacheControl>,
     input_schema: serde_json::Value,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    input_examples: Vec<serde_json::Value>,
 }
 
 impl TryFrom<forge_domain::ToolDefinition> for ToolDefinition {
@@ -307,6 +300,46 @@ impl TryFrom<forge_domain::ToolDefinition> for ToolDefinition {
             description: Some(value.description),
             cache_control: None,
             input_schema: serde_json::to_value(value.input_schema)?,
+            input_examples: value.examples,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn tool(examples: Vec<serde_json::Value>) -> forge_domain::ToolDefinition {
+        forge_domain::ToolDefinition::new("tool_forge_fs_read")
+            .description("Reads a file")
+            .examples(examples)
+    }
+
+    #[test]
+    fn test_tool_examples_are_sent_natively() {
+        let fixture = tool(vec![json!({"path": "/project/main.rs"})]);
+
+        let actual = serde_json::to_value(ToolDefinition::try_from(fixture).unwrap()).unwrap();
+
+        assert_eq!(actual["description"], json!("Reads a file"));
+        assert_eq!(
+            actual["input_examples"],
+            json!([{"path": "/project/main.rs"}])
+        );
+    }
+
+    #[test]
+    fn test_tool_without_examples() {
+        let context = forge_domain::Context::default().add_tool(tool(vec![]));
+
+        let request = Request::try_from(context).unwrap();
+        let actual = serde_json::to_value(&request).unwrap();
+
+        assert!(!request.has_tool_examples());
+        assert_eq!(actual["tools"][0].get("input_examples"), None);
+        assert_eq!(actual["tools"][0]["name"], json!("tool_forge_fs_read"));
+    }
+}
@@ -4,6 +4,8 @@ use forge_domain::{ChatCompletionMessage, Content, ModelId, ToolCallId, ToolCall
 use serde::Deserialize;
 
 use super::request::Role;
+use crate::status::stream_error;
+use crate::ProviderError;
 
 #[derive(Deserialize)]
 pub struct ListModelResponse {
@@ -23,6 +25,7 @@ impl From<Model> for forge_domain::Model {
             name: value.display_name,
             description: None,
             context_length: None,
+            pricing: None,
         }
     }
 }
@@ -52,6 +55,7 @@ pub enum StopReason {
     MaxTokens,
     StopSequence,
     ToolUse,
+    Refusal,
 }
 
 impl From<StopReason> for forge_domain::FinishReason {
@@ -61,6 +65,7 @@ impl From<StopReason> for forge_domain::FinishReason {
             StopReason::MaxTokens => forge_domain::FinishReason::Length,
             StopReason::StopSequence => forge_domain::FinishReason::Stop,
             StopReason::ToolUse => forge_domain::FinishReason::ToolCalls,
+            StopReason::Refusal => forge_domain::FinishReason::Refusal,
         }
     }
 }
@@ -108,6 +113,14 @@ pub enum ErrorData {
     OverloadedError { message: String },
 }
 
+impl From<ErrorData> for ProviderError {
+    fn from(error: ErrorData) -> Self {
+        match error {
+            ErrorData::OverloadedError { .. } => ProviderError::ServerError(error.to_string()),
+        }
+    }
+}
+
 impl Display for ErrorData {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -167,7 +180,7 @@ impl TryFrom<Event> for ChatCompletionMessage {
                 ChatCompletionMessage::assistant(Content::part("")).finish_reason(delta.stop_reason)
             }
             Event::Error { error } => {
-                return Err(anyhow::anyhow!("Anthropic API error: {}", error));
+                return Err(stream_error(ProviderError::from(error)).context("Anthropic API error"));
             }
             _ => ChatCompletionMessage::assistant(Content::part("")),
         };
@@ -2,11 +2,12 @@
 
 use anyhow::{Context as _, Result};
 use forge_domain::{
-    ChatCompletionMessage, Context, Model, ModelId, Provider, ProviderService, ResultStream,
+    ChatCompletionMessage, Context, HttpTimeout, Model, ModelId, Provider, ProviderService,
+    ProxyConfig, ResultStream, TlsConfig, TransformerPipeline, TransformerReport,
 };
 
 use crate::anthropic::Anthropic;
-use crate::open_router::OpenRouter;
+use crate::open_router::{OpenRouter, ProviderPreferences};
 
 pub enum Client {
     OpenAICompat(OpenRouter),
@@ -14,14 +15,41 @@ pub enum Client {
 }
 
 impl Client {
-    pub fn new(provider: Provider) -> Result<Self> {
-        let client = reqwest::Client::builder().build()?;
+    pub fn new(
+        provider: Provider,
+        proxy: &ProxyConfig,
+        tls: &TlsConfig,
+        timeout: &HttpTimeout,
+        preferences: Option<&str>,
+        transformers: Option<&str>,
+        prompt_cache: bool,
+    ) -> Result<Self> {
+        let client = crate::http_client(proxy, tls, timeout)?;
 
         match &provider {
             Provider::OpenAI { url, .. } => Ok(Client::OpenAICompat(
                 OpenRouter::builder()
                     .client(client)
                     .provider(provider.clone())
+                    .preferences(
+                        preferences
+                            .filter(|_| provider.is_open_router())
+                            .map(ProviderPreferences::parse)
+                            .transpose()
+                            .context("Invalid FORGE_OPENROUTER_PROVIDER")?,
+                    )
+                    .transformers(match transformers {
+                        Some(spec) => {
+                            let pipeline = TransformerPipeline::parse(spec)
+                                .context("Invalid FORGE_TRANSFORMERS")?;
+                            if prompt_cache {
+                                pipeline.with_prompt_cache()
+                            } else {
+                                pipeline
+                            }
+                        }
+                        None => TransformerPipeline::builtin(prompt_cache),
+                    })
                     .build()
                     .with_context(|| format!("Failed to initialize: {}", url))?,
             )),
@@ -58,4 +86,68 @@ impl ProviderService for Client {
             Client::Anthropic(provider) => provider.models().await,
         }
     }
+
+    fn transformers(&self) -> Option<TransformerReport> {
+        match self {
+            Client::OpenAICompat(provider) => provider.transformers(),
+            Client::Anthropic(provider) => provider.transformers(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn client(provider: Provider, preferences: &str) -> Result<Client> {
+        Client::new(
+            provider,
+            &ProxyConfig::default(),
+            &TlsConfig::default(),
+            &HttpTimeout::default(),
+            Some(preferences),
+            None,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_invalid_provider_preferences_fail_the_client() {
+        let actual = client(Provider::open_router("key"), r#"{"orderr": ["anthropic"]}"#)
+            .err()
+            .map(|error| error.to_string());
+
+        assert_eq!(
+            actual,
+            Some("Invalid FORGE_OPENROUTER_PROVIDER".to_string())
+        );
+    }
+
+    #[test]
+    fn test_provider_preferences_are_ignored_by_other_providers() {
+        let actual = client(Provider::openai("key"), "not json");
+
+        assert!(actual.is_ok());
+    }
+
+    #[test]
+    fn test_prompt_cache_joins_configured_transformers() {
+        let fixture = Client::new(
+            Provider::open_router("key"),
+            &ProxyConfig::default(),
+            &TlsConfig::default(),
+            &HttpTimeout::default(),
+            None,
+            Some("stringify_tool_results,open_ai"),
+            true,
+        )
+        .unwrap();
+
+        let actual = fixture.transformers().unwrap().pipeline.to_string();
+
+        let expected = "stringify_tool_results,set_cache:anthropic|claude,open_ai";
+        assert_eq!(actual, expected);
+    }
 }
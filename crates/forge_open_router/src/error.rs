@@ -0,0 +1,274 @@
+use serde_json::Value;
+use thiserror::Error;
+
+/// Error returned by a provider, classified by its HTTP status and the error
+/// code in its payload so that callers can tell eg. a rate limit from a bad
+/// request. Every variant keeps the raw payload.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ProviderError {
+    #[error("Rate limited by the provider: {0}")]
+    RateLimited(String),
+
+    #[error("The provider rejected the credentials: {0}")]
+    Unauthorized(String),
+
+    #[error("The provider rejected the request: {0}")]
+    InvalidRequest(String),
+
+    #[error("The request exceeds the context window of the model: {0}")]
+    ContextLengthExceeded(String),
+
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+
+    #[error("The provider failed to handle the request: {0}")]
+    ServerError(String),
+
+    #[error("Provider error: {0}")]
+    Unknown(String),
+}
+
+impl ProviderError {
+    /// Classifies an error from the HTTP status of the response, when there is
+    /// one, and the error payload. Codes in the payload are more specific
+    /// than the status and take precedence over it.
+    pub fn classify(status: Option<u16>, payload: impl Into<String>) -> Self {
+        let payload = payload.into();
+        let json = serde_json::from_str::<Value>(&payload).ok();
+        let error = json.as_ref().map(|json| json.get("error").unwrap_or(json));
+
+        let code = error.and_then(|error| error.get("code"));
+        let kind = error.and_then(|error| error.get("type"));
+        let by_code = [code, kind]
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .find_map(Kind::from_code);
+
+        // Some providers, eg. OpenRouter, report the status as a numeric code,
+        // including for errors sent in the middle of a stream
+        let status = status.or_else(|| {
+            code.and_then(Value::as_u64)
+                .and_then(|code| u16::try_from(code).ok())
+        });
+
+        let kind = match by_code
+            .or_else(|| status.map(Kind::from_status))
+            .unwrap_or(Kind::Unknown)
+        {
+            // Most providers report an oversized request as any other bad
+            // request, only the message tells them apart
+            Kind::InvalidRequest if exceeds_context(&payload) => Kind::ContextLengthExceeded,
+            kind => kind,
+        };
+        kind.with_payload(payload)
+    }
+
+    /// The payload the provider responded with
+    pub fn payload(&self) -> &str {
+        match self {
+            ProviderError::RateLimited(payload)
+            | ProviderError::Unauthorized(payload)
+            | ProviderError::InvalidRequest(payload)
+            | ProviderError::ContextLengthExceeded(payload)
+            | ProviderError::ModelNotFound(payload)
+            | ProviderError::ServerError(payload)
+            | ProviderError::Unknown(payload) => payload,
+        }
+    }
+
+    /// Whether sending the same request again may succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::RateLimited(_) | ProviderError::ServerError(_)
+        )
+    }
+}
+
+/// Messages of OpenAI compatible APIs, OpenRouter and Anthropic for requests
+/// that don't fit the model's context
+fn exceeds_context(payload: &str) -> bool {
+    let payload = payload.to_lowercase();
+    [
+        "context length",
+        "context_length",
+        "context window",
+        "maximum context",
+        "prompt is too long",
+    ]
+    .iter()
+    .any(|pattern| payload.contains(pattern))
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    RateLimited,
+    Unauthorized,
+    InvalidRequest,
+    ContextLengthExceeded,
+    ModelNotFound,
+    ServerError,
+    Unknown,
+}
+
+impl Kind {
+    /// Error codes and types used by OpenAI compatible APIs and Anthropic
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "rate_limit_exceeded" | "rate_limit_error" => Some(Kind::RateLimited),
+            "invalid_api_key" | "authentication_error" | "unauthorized" => Some(Kind::Unauthorized),
+            "model_not_found" | "not_found_error" => Some(Kind::ModelNotFound),
+            "invalid_request_error" => Some(Kind::InvalidRequest),
+            "context_length_exceeded" => Some(Kind::ContextLengthExceeded),
+            "server_error" | "api_error" | "overloaded_error" => Some(Kind::ServerError),
+            _ => None,
+        }
+    }
+
+    fn from_status(status: u16) -> Self {
+        match status {
+            429 => Kind::RateLimited,
+            401 => Kind::Unauthorized,
+            404 => Kind::ModelNotFound,
+            400 | 403 | 413 | 422 => Kind::InvalidRequest,
+            500..=599 => Kind::ServerError,
+            _ => Kind::Unknown,
+        }
+    }
+
+    fn with_payload(self, payload: String) -> ProviderError {
+        match self {
+            Kind::RateLimited => ProviderError::RateLimited(payload),
+            Kind::Unauthorized => ProviderError::Unauthorized(payload),
+            Kind::InvalidRequest => ProviderError::InvalidRequest(payload),
+            Kind::ContextLengthExceeded => ProviderError::ContextLengthExceeded(payload),
+            Kind::ModelNotFound => ProviderError::ModelNotFound(payload),
+            Kind::ServerError => ProviderError::ServerError(payload),
+            Kind::Unknown => ProviderError::Unknown(payload),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_classify_by_status() {
+        let actual = [429, 401, 404, 400, 503, 529, 402]
+            .map(|status| std::mem::discriminant(&ProviderError::classify(Some(status), "oops")));
+
+        let expected = [
+            ProviderError::RateLimited(String::new()),
+            ProviderError::Unauthorized(String::new()),
+            ProviderError::ModelNotFound(String::new()),
+            ProviderError::InvalidRequest(String::new()),
+            ProviderError::ServerError(String::new()),
+            ProviderError::ServerError(String::new()),
+            ProviderError::Unknown(String::new()),
+        ]
+        .map(|error| std::mem::discriminant(&error));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_classify_openai_model_not_found() {
+        let fixture = r#"{"error":{"message":"The model `gpt-9` does not exist","type":"invalid_request_error","param":null,"code":"model_not_found"}}"#;
+
+        let actual = ProviderError::classify(Some(404), fixture);
+
+        let expected = ProviderError::ModelNotFound(fixture.to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_classify_openai_rate_limit_on_bad_request_status() {
+        let fixture = r#"{"error":{"message":"Rate limit reached","type":"requests","code":"rate_limit_exceeded"}}"#;
+
+        let actual = ProviderError::classify(Some(400), fixture);
+
+        let expected = ProviderError::RateLimited(fixture.to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_classify_anthropic_errors() {
+        let overloaded =
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        let auth = r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#;
+
+        let actual = [
+            ProviderError::classify(Some(529), overloaded),
+            ProviderError::classify(Some(401), auth),
+        ];
+
+        let expected = [
+            ProviderError::ServerError(overloaded.to_string()),
+            ProviderError::Unauthorized(auth.to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_classify_open_router_numeric_code_in_stream() {
+        let fixture = r#"{"error":{"code":429,"message":"Provider returned error","metadata":{"provider_name":"Anthropic"}}}"#;
+
+        let actual = ProviderError::classify(None, fixture);
+
+        let expected = ProviderError::RateLimited(fixture.to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_classify_context_length_exceeded() {
+        let openai = r#"{"error":{"message":"This model's maximum context length is 128000 tokens.","type":"invalid_request_error","param":"messages","code":"context_length_exceeded"}}"#;
+        let anthropic = r#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 215000 tokens > 200000 maximum"}}"#;
+        let open_router = r#"{"error":{"code":400,"message":"This endpoint's maximum context length is 65536 tokens. However, you requested about 70012 tokens."}}"#;
+        let other = r#"{"error":{"code":400,"message":"Invalid tool schema"}}"#;
+
+        let actual = [
+            ProviderError::classify(Some(400), openai),
+            ProviderError::classify(Some(400), anthropic),
+            ProviderError::classify(None, open_router),
+            ProviderError::classify(None, other),
+        ];
+
+        let expected = [
+            ProviderError::ContextLengthExceeded(openai.to_string()),
+            ProviderError::ContextLengthExceeded(anthropic.to_string()),
+            ProviderError::ContextLengthExceeded(open_router.to_string()),
+            ProviderError::InvalidRequest(other.to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_classify_unparsable_payload() {
+        let actual = [
+            ProviderError::classify(Some(502), "<html>Bad Gateway</html>"),
+            ProviderError::classify(None, "something went wrong"),
+        ];
+
+        let expected = [
+            ProviderError::ServerError("<html>Bad Gateway</html>".to_string()),
+            ProviderError::Unknown("something went wrong".to_string()),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        let actual = [
+            ProviderError::RateLimited(String::new()),
+            ProviderError::ServerError(String::new()),
+            ProviderError::InvalidRequest(String::new()),
+            ProviderError::Unauthorized(String::new()),
+        ]
+        .map(|error| error.is_transient());
+
+        assert_eq!(actual, [true, true, false, false]);
+    }
+}
@@ -0,0 +1,322 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use forge_domain::{HttpTimeout, ProxyConfig, TlsConfig};
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy};
+
+/// Builds an HTTP client whose requests go through the configured proxies,
+/// trust the configured certificates and give up on stalled connections
+pub fn http_client(
+    proxy: &ProxyConfig,
+    tls: &TlsConfig,
+    timeout: &HttpTimeout,
+) -> Result<reqwest::Client> {
+    let builder = with_proxy(reqwest::Client::builder(), proxy)?;
+    let builder = with_timeout(with_tls(builder, tls)?, timeout);
+    Ok(builder.build()?)
+}
+
+/// Limits the time to connect and the time between reads. There's no overall
+/// deadline, a streaming response can last as long as data keeps coming.
+pub fn with_timeout(builder: ClientBuilder, timeout: &HttpTimeout) -> ClientBuilder {
+    builder
+        .connect_timeout(timeout.connect)
+        .read_timeout(timeout.read)
+}
+
+/// Tells apart the timeouts reqwest reports alike, a server that couldn't be
+/// reached from one that stopped sending
+pub fn transport_error(error: reqwest::Error) -> anyhow::Error {
+    let context = match (error.is_timeout(), error.is_connect()) {
+        (true, true) => "Timed out connecting to the server",
+        (true, false) => "Timed out waiting for the server to send data",
+        _ => return error.into(),
+    };
+    anyhow::Error::from(error).context(context)
+}
+
+/// Routes the requests of the client through the configured proxies. The
+/// proxies reqwest would detect on its own are ignored, so that the config is
+/// all that counts, even when proxies are disabled.
+pub fn with_proxy(builder: ClientBuilder, config: &ProxyConfig) -> Result<ClientBuilder> {
+    let no_proxy = config.no_proxy.as_deref().and_then(NoProxy::from_string);
+    let mut builder = builder.no_proxy();
+    if let Some(url) = &config.http {
+        let proxy =
+            Proxy::http(url.as_str()).with_context(|| format!("Invalid HTTP proxy: {url}"))?;
+        builder = builder.proxy(proxy.no_proxy(no_proxy.clone()));
+    }
+    if let Some(url) = &config.https {
+        let proxy =
+            Proxy::https(url.as_str()).with_context(|| format!("Invalid HTTPS proxy: {url}"))?;
+        builder = builder.proxy(proxy.no_proxy(no_proxy));
+    }
+    Ok(builder)
+}
+
+/// Trusts the certificates of the CA bundle along with the built-in roots,
+/// and accepts invalid certificates when asked to
+pub fn with_tls(builder: ClientBuilder, config: &TlsConfig) -> Result<ClientBuilder> {
+    let mut builder = builder;
+    if let Some(path) = &config.ca_bundle {
+        for certificate in root_certificates(path)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if config.accept_invalid_certs {
+        tracing::warn!("TLS certificates are not verified");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder)
+}
+
+/// Reads the certificates of a PEM bundle
+fn root_certificates(path: &Path) -> Result<Vec<Certificate>> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("Failed to read CA bundle: {}", path.display()))?;
+    let certificates = Certificate::from_pem_bundle(&pem)
+        .with_context(|| format!("Invalid CA bundle: {}", path.display()))?;
+    if certificates.is_empty() {
+        anyhow::bail!("No certificates in CA bundle: {}", path.display());
+    }
+    Ok(certificates)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// A server that answers one request and returns its request line
+    async fn server() -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 4096];
+            let read = stream.read(&mut buffer).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+            request.lines().next().unwrap_or_default().to_string()
+        });
+        (address, handle)
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_the_proxy() {
+        let (proxy, request) = server().await;
+        let fixture = http_client(
+            &ProxyConfig { http: Some(format!("http://{proxy}")), ..Default::default() },
+            &TlsConfig::default(),
+            &HttpTimeout::default(),
+        )
+        .unwrap();
+
+        fixture
+            .get("http://forge.invalid/models")
+            .send()
+            .await
+            .unwrap();
+
+        let actual = request.await.unwrap();
+        assert_eq!(actual, "GET http://forge.invalid/models HTTP/1.1");
+    }
+
+    #[tokio::test]
+    async fn test_no_proxy_hosts_are_reached_directly() {
+        let (target, request) = server().await;
+        let fixture = http_client(
+            &ProxyConfig {
+                // Nothing listens there, the request would fail through it
+                http: Some("http://127.0.0.1:9".to_string()),
+                https: None,
+                no_proxy: Some("127.0.0.1".to_string()),
+            },
+            &TlsConfig::default(),
+            &HttpTimeout::default(),
+        )
+        .unwrap();
+
+        fixture
+            .get(format!("http://{target}/models"))
+            .send()
+            .await
+            .unwrap();
+
+        let actual = request.await.unwrap();
+        assert_eq!(actual, "GET /models HTTP/1.1");
+    }
+
+    #[test]
+    fn test_invalid_proxy_is_an_error() {
+        let actual = http_client(
+            &ProxyConfig { https: Some("http://[::1".to_string()), ..Default::default() },
+            &TlsConfig::default(),
+            &HttpTimeout::default(),
+        );
+
+        assert!(actual
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid HTTPS proxy"));
+    }
+
+    /// Writes the content to a file of a new temporary directory
+    fn bundle(content: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        std::fs::write(&path, content).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_ca_bundle_is_trusted() {
+        let (_dir, path) = bundle(include_str!("fixtures/ca.pem"));
+
+        let certificates = root_certificates(&path).unwrap();
+        let client = http_client(
+            &ProxyConfig::default(),
+            &TlsConfig { ca_bundle: Some(path), accept_invalid_certs: false },
+            &HttpTimeout::default(),
+        );
+
+        assert_eq!(certificates.len(), 2);
+        assert!(client.is_ok());
+    }
+
+    #[test]
+    fn test_invalid_ca_bundle_is_an_error() {
+        let (_dir, empty) = bundle("not a certificate\n");
+        let missing = empty.with_file_name("missing.pem");
+
+        let actual = [empty, missing].map(|path| {
+            http_client(
+                &ProxyConfig::default(),
+                &TlsConfig { ca_bundle: Some(path), accept_invalid_certs: true },
+                &HttpTimeout::default(),
+            )
+            .unwrap_err()
+            .to_string()
+        });
+
+        assert!(
+            actual[0].contains("No certificates in CA bundle"),
+            "{}",
+            actual[0]
+        );
+        assert!(
+            actual[1].contains("Failed to read CA bundle"),
+            "{}",
+            actual[1]
+        );
+    }
+
+    fn timeout_client(connect: u64, read: u64) -> reqwest::Client {
+        http_client(
+            &ProxyConfig::default(),
+            &TlsConfig::default(),
+            &HttpTimeout {
+                connect: std::time::Duration::from_millis(connect),
+                read: std::time::Duration::from_millis(read),
+            },
+        )
+        .unwrap()
+    }
+
+    /// A server that accepts a connection and sends the chunks of a chunked
+    /// response, `delay` apart, then stalls
+    async fn streaming_server(
+        chunks: &'static [&'static str],
+        delay: std::time::Duration,
+    ) -> (String, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 4096];
+            let _ = stream.read(&mut buffer).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n")
+                .await
+                .unwrap();
+            for chunk in chunks {
+                tokio::time::sleep(delay).await;
+                let frame = format!("{:x}\r\n{chunk}\r\n", chunk.len());
+                stream.write_all(frame.as_bytes()).await.unwrap();
+            }
+            if chunks.last() == Some(&"") {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        });
+        (address, handle)
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout() {
+        // The connection is accepted, but the TLS handshake never completes
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let fixture = timeout_client(200, 5_000);
+
+        let error = fixture
+            .get(format!("https://{address}/models"))
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(error.is_connect() && error.is_timeout(), "{error:?}");
+        let actual = transport_error(error).to_string();
+        assert_eq!(actual, "Timed out connecting to the server");
+        drop(listener);
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout() {
+        let (address, _server) =
+            streaming_server(&["data: 1\n\n"], std::time::Duration::ZERO).await;
+        let fixture = timeout_client(5_000, 200);
+
+        let response = fixture
+            .get(format!("http://{address}/stream"))
+            .send()
+            .await
+            .unwrap();
+        let error = response.text().await.unwrap_err();
+
+        assert!(error.is_timeout() && !error.is_connect(), "{error:?}");
+        let actual = transport_error(error).to_string();
+        assert_eq!(actual, "Timed out waiting for the server to send data");
+    }
+
+    #[tokio::test]
+    async fn test_slow_stream_outlasting_the_read_timeout_completes() {
+        let chunks = &[
+            "data: 1\n\n",
+            "data: 2\n\n",
+            "data: 3\n\n",
+            "data: 4\n\n",
+            "",
+        ];
+        let (address, _server) =
+            streaming_server(chunks, std::time::Duration::from_millis(100)).await;
+        let fixture = timeout_client(5_000, 300);
+
+        let actual = fixture
+            .get(format!("http://{address}/stream"))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        assert_eq!(actual, "data: 1\n\ndata: 2\n\ndata: 3\n\ndata: 4\n\n");
+    }
+}
@@ -1,6 +1,11 @@
 mod anthropic;
 mod builder;
+mod error;
+mod http;
 mod open_router;
+mod status;
 
 // Re-export from builder.rs
 pub use builder::Client;
+pub use error::ProviderError;
+pub use http::*;
@@ -1,8 +1,10 @@
+use std::sync::{Arc, Mutex};
+
 use anyhow::{Context as _, Result};
 use derive_builder::Builder;
 use forge_domain::{
     self, ChatCompletionMessage, Context as ChatContext, Model, ModelId, Provider, ProviderService,
-    ResultStream,
+    ResultStream, TransformerPipeline, TransformerReport,
 };
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use reqwest::{Client, Url};
@@ -10,15 +12,28 @@ use reqwest_eventsource::{Event, RequestBuilderExt};
 use tokio_stream::StreamExt;
 use tracing::debug;
 
+use super::error::Error;
 use super::model::{ListModelResponse, OpenRouterModel};
-use super::request::OpenRouterRequest;
+use super::request::{OpenRouterRequest, ProviderPreferences};
 use super::response::OpenRouterResponse;
+use crate::http::transport_error;
 use crate::open_router::transformers::{ProviderPipeline, Transformer};
+use crate::status::{auth_error, check_status, is_auth_failure, provider_error, stream_error};
 
 #[derive(Clone, Builder)]
 pub struct OpenRouter {
     client: Client,
     provider: Provider,
+    /// Routing preferences, only sent to OpenRouter
+    #[builder(default)]
+    preferences: Option<ProviderPreferences>,
+    /// Transformers applied to every request, in order
+    #[builder(default = "TransformerPipeline::builtin(false)")]
+    transformers: TransformerPipeline,
+    /// The last request as it was before the transformers ran, what they did
+    /// to it is only worked out when it's reported
+    #[builder(setter(skip))]
+    last_request: Arc<Mutex<Option<OpenRouterRequest>>>,
 }
 
 impl OpenRouter {
@@ -67,7 +82,11 @@ impl ProviderService for OpenRouter {
         let mut request = OpenRouterRequest::from(request)
             .model(model.clone())
             .stream(true);
-        request = ProviderPipeline::new(&self.provider).transform(request);
+        *self.last_request.lock().unwrap() = Some(request.clone());
+        request = ProviderPipeline::new(&self.provider, &self.transformers).transform(request);
+        if let Some(preferences) = self.preferences.as_ref() {
+            request = request.provider(preferences.clone());
+        }
 
         let url = self.url("chat/completions")?;
         debug!(url = %url, model = %model, "Connecting to OpenRouter API");
@@ -90,35 +109,29 @@ impl ProviderService for OpenRouter {
                         Event::Message(event) => Some(
                             serde_json::from_str::<OpenRouterResponse>(&event.data)
                                 .with_context(|| "Failed to parse OpenRouter response")
-                                .and_then(|message| {
-                                    ChatCompletionMessage::try_from(message.clone())
-                                        .with_context(|| "Failed to create completion message")
-                                }),
+                                .and_then(completion),
                         ),
                     },
                     Err(reqwest_eventsource::Error::StreamEnded) => None,
-                    Err(reqwest_eventsource::Error::InvalidStatusCode(_, response)) => Some(
-                        response
-                            .json::<OpenRouterResponse>()
-                            .await
-                            .with_context(|| "Failed to parse OpenRouter response")
-                            .and_then(|message| {
-                                ChatCompletionMessage::try_from(message.clone())
-                                    .with_context(|| "Failed to create completion message")
-                            })
-                            .with_context(|| "Failed with invalid status code"),
-                    ),
+                    Err(reqwest_eventsource::Error::InvalidStatusCode(status, response))
+                        if is_auth_failure(status) =>
+                    {
+                        Some(Err(auth_error(response).await))
+                    }
+                    Err(reqwest_eventsource::Error::InvalidStatusCode(_, response)) => {
+                        Some(Err(provider_error(response).await))
+                    }
                     Err(reqwest_eventsource::Error::InvalidContentType(_, response)) => Some(
                         response
                             .json::<OpenRouterResponse>()
                             .await
                             .with_context(|| "Failed to parse OpenRouter response")
-                            .and_then(|message| {
-                                ChatCompletionMessage::try_from(message.clone())
-                                    .with_context(|| "Failed to create completion message")
-                            })
+                            .and_then(completion)
                             .with_context(|| "Failed with invalid content type"),
                     ),
+                    Err(reqwest_eventsource::Error::Transport(err)) => {
+                        Some(Err(transport_error(err)))
+                    }
                     Err(err) => Some(Err(err.into())),
                 }
             });
@@ -132,20 +145,30 @@ impl ProviderService for OpenRouter {
             .get(self.url("models")?)
             .headers(self.headers())
             .send()
-            .await?
-            .error_for_status()
-            .with_context(|| "Failed because of a non 200 status code".to_string())?
-            .text()
-            .await?;
+            .await
+            .map_err(transport_error)?;
+        let text = check_status(response).await?.text().await?;
         if self.provider.is_open_router() | self.provider.is_antinomy() {
-            let data: Vec<OpenRouterModel> = serde_json::from_str(&response)?;
+            let data: Vec<OpenRouterModel> = serde_json::from_str(&text)?;
             Ok(data.into_iter().map(Into::into).collect())
         } else {
             // TODO: This could fail for some providers
-            let data: ListModelResponse = serde_json::from_str(&response)?;
+            let data: ListModelResponse = serde_json::from_str(&text)?;
             Ok(data.data.into_iter().map(Into::into).collect())
         }
     }
+
+    fn transformers(&self) -> Option<TransformerReport> {
+        let last_request = self.last_request.lock().unwrap().clone();
+        let stages = last_request
+            .map(|request| {
+                ProviderPipeline::new(&self.provider, &self.transformers)
+                    .run(request)
+                    .1
+            })
+            .unwrap_or_default();
+        Some(TransformerReport { pipeline: self.transformers.clone(), last_request: stages })
+    }
 }
 
 impl From<OpenRouterModel> for Model {
@@ -155,10 +178,23 @@ impl From<OpenRouterModel> for Model {
             name: value.name,
             description: value.description,
             context_length: Some(value.context_length),
+            pricing: value.pricing.per_token(),
         }
     }
 }
 
+/// Converts a response of the stream. Errors the provider sent in it are
+/// reported like failed responses, so that a request too large for the model
+/// can be compacted wherever it failed.
+fn completion(message: OpenRouterResponse) -> Result<ChatCompletionMessage> {
+    ChatCompletionMessage::try_from(message)
+        .map_err(|error| match error {
+            Error::Upstream(error) => stream_error(error),
+            error => error.into(),
+        })
+        .with_context(|| "Failed to create completion message")
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Context;
@@ -181,4 +217,34 @@ mod tests {
         assert!(message.is_err());
         Ok(())
     }
+
+    #[test]
+    fn test_context_length_error_in_stream() {
+        let chunk = r#"{"error":{"code":400,"message":"This endpoint's maximum context length is 65536 tokens. However, you requested about 70012 tokens."}}"#;
+        let message = serde_json::from_str::<OpenRouterResponse>(chunk).unwrap();
+
+        let actual = completion(message).unwrap_err();
+
+        assert!(
+            matches!(
+                actual.downcast_ref::<forge_domain::Error>(),
+                Some(forge_domain::Error::ContextLengthExceeded(_))
+            ),
+            "{actual:?}"
+        );
+    }
+
+    #[test]
+    fn test_other_errors_in_stream_are_kept() {
+        let chunk = r#"{"error":{"code":429,"message":"Provider returned error"}}"#;
+        let message = serde_json::from_str::<OpenRouterResponse>(chunk).unwrap();
+
+        let actual = completion(message).unwrap_err();
+
+        assert!(actual.downcast_ref::<forge_domain::Error>().is_none());
+        assert!(matches!(
+            actual.downcast_ref::<crate::ProviderError>(),
+            Some(crate::ProviderError::RateLimited(_))
+        ));
+    }
 }
@@ -1,13 +1,13 @@
 use derive_more::derive::Display;
 use thiserror::Error;
 
-use super::response::ErrorResponse;
+use crate::ProviderError;
 
 #[derive(Debug, Display, derive_more::From, Error)]
 pub enum Error {
     EmptyContent,
     #[display("Upstream: {_0}")]
-    Upstream(ErrorResponse),
+    Upstream(#[source] ProviderError),
     SerdeJson(serde_json::Error),
     ToolCallMissingName,
 }
@@ -8,3 +8,4 @@ mod transformers;
 
 mod api;
 pub use api::OpenRouter;
+pub use request::ProviderPreferences;
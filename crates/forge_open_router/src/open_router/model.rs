@@ -1,4 +1,4 @@
-use forge_domain::ModelId;
+use forge_domain::{ModelId, ModelPricing};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -29,6 +29,25 @@ pub struct Pricing {
     pub request: String,
 }
 
+impl Pricing {
+    /// Prices are sent as decimal strings in dollars per token. Models routed
+    /// dynamically, eg. openrouter/auto, have negative prices since their
+    /// price isn't known upfront.
+    pub fn per_token(&self) -> Option<ModelPricing> {
+        let price = |value: &str| {
+            value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|price| *price >= 0.0)
+        };
+        Some(ModelPricing {
+            prompt: price(&self.prompt)?,
+            completion: price(&self.completion)?,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TopProvider {
     pub context_length: Option<u64>,
@@ -40,3 +59,35 @@ pub struct TopProvider {
 pub struct ListModelResponse {
     pub data: Vec<OpenRouterModel>,
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn pricing(prompt: &str, completion: &str) -> Pricing {
+        Pricing {
+            prompt: prompt.to_string(),
+            completion: completion.to_string(),
+            image: "0".to_string(),
+            request: "0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_per_token_pricing() {
+        let actual = pricing("0.000003", "0.000015").per_token();
+
+        let expected = Some(ModelPricing { prompt: 0.000003, completion: 0.000015 });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_unknown_pricing() {
+        let actual =
+            [pricing("-1", "-1"), pricing("", "0.000015")].map(|pricing| pricing.per_token());
+
+        assert_eq!(actual, [None, None]);
+    }
+}
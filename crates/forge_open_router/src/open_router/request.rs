@@ -1,9 +1,11 @@
 use derive_more::derive::Display;
 use derive_setters::Setters;
 use forge_domain::{
-    Context, ContextMessage, ModelId, Role, ToolCallFull, ToolCallId, ToolDefinition, ToolName,
+    Context, ContextMessage, ModelId, Role, ToolCallFull, ToolCallId, ToolContent, ToolDefinition,
+    ToolName,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use super::response::{FunctionCall, OpenRouterToolCall};
 use super::tool_choice::{FunctionType, ToolChoice};
@@ -46,6 +48,9 @@ pub struct OpenRouterMessage {
 pub enum MessageContent {
     Text(String),
     Parts(Vec<ContentPart>),
+    /// Tool results of providers taking JSON, see
+    /// [`forge_domain::Provider::accepts_json_tool_results`]
+    Json(Value),
 }
 
 impl MessageContent {
@@ -110,9 +115,58 @@ pub struct Prediction {
     pub content: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// How OpenRouter picks the providers serving a model, sent as the `provider`
+/// object of a request. Unset fields keep OpenRouter's defaults.
+/// ref: https://openrouter.ai/docs/features/provider-routing
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct ProviderPreferences {
-    // Define fields as necessary
+    /// Providers to try first, in order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<Vec<String>>,
+    /// Whether other providers may serve the request when the preferred ones
+    /// are unavailable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_fallbacks: Option<bool>,
+    /// Only use providers supporting every parameter of the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_parameters: Option<bool>,
+    /// Whether providers that may store or train on the data can be used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_collection: Option<DataCollection>,
+    /// The only providers that can be used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub only: Option<Vec<String>>,
+    /// Providers that are never used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore: Option<Vec<String>>,
+    /// Ranks the providers by price, throughput or latency instead of
+    /// balancing the load
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<ProviderSort>,
+}
+
+impl ProviderPreferences {
+    /// Parses the preferences from JSON, rejecting unknown keys so that a typo
+    /// doesn't silently change the routing
+    pub fn parse(json: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DataCollection {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderSort {
+    Price,
+    Throughput,
+    Latency,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Setters, Default)]
@@ -185,7 +239,8 @@ impl From<ToolDefinition> for OpenRouterTool {
         OpenRouterTool {
             r#type: FunctionType,
             function: FunctionDescription {
-                description: Some(value.description),
+                // The examples go in the description, as the API has no field for them
+                description: Some(value.description_with_examples()),
                 name: value.name.into_string(),
                 parameters: serde_json::to_value(value.input_schema).unwrap(),
             },
@@ -195,19 +250,31 @@ impl From<ToolDefinition> for OpenRouterTool {
 
 impl From<Context> for OpenRouterRequest {
     fn from(request: Context) -> Self {
+        let mut request = request.inline_provider_tools();
+        let tools = std::mem::take(&mut request.tools);
+        let tool_choice = request.tool_choice.take();
         OpenRouterRequest {
             messages: {
+                let has_system = request.system.is_some();
                 let messages = request
-                    .messages
+                    .request_messages()
                     .into_iter()
                     .map(OpenRouterMessage::from)
                     .collect::<Vec<_>>();
 
+                let system_messages = messages
+                    .iter()
+                    .filter(|message| message.role == OpenRouterRole::System)
+                    .count();
+                debug_assert!(
+                    !has_system || system_messages == 1,
+                    "the request must carry exactly one system message, found {system_messages}"
+                );
+
                 Some(messages)
             },
             tools: {
-                let tools = request
-                    .tools
+                let tools = tools
                     .into_iter()
                     .map(OpenRouterTool::from)
                     .collect::<Vec<_>>();
@@ -224,7 +291,7 @@ impl From<Context> for OpenRouterRequest {
             stream: Default::default(),
             max_tokens: Default::default(),
             temperature: Default::default(),
-            tool_choice: request.tool_choice.map(|tc| tc.into()),
+            tool_choice: tool_choice.map(|tc| tc.into()),
             seed: Default::default(),
             top_p: Default::default(),
             top_k: Default::default(),
@@ -273,13 +340,23 @@ impl From<ContextMessage> for OpenRouterMessage {
                         .collect()
                 }),
             },
-            ContextMessage::ToolMessage(tool_result) => OpenRouterMessage {
-                role: OpenRouterRole::Tool,
-                content: Some(MessageContent::Text(tool_result.to_string())),
-                name: Some(tool_result.name),
-                tool_call_id: tool_result.call_id,
-                tool_calls: None,
-            },
+            ContextMessage::ToolMessage(tool_result) => {
+                // JSON is passed through, it's serialized for the providers taking
+                // text when the request is transformed
+                let content = match &tool_result.content {
+                    ToolContent::Json(value) if !tool_result.is_error => {
+                        MessageContent::Json(value.clone())
+                    }
+                    _ => MessageContent::Text(tool_result.to_string()),
+                };
+                OpenRouterMessage {
+                    role: OpenRouterRole::Tool,
+                    content: Some(content),
+                    name: Some(tool_result.name),
+                    tool_call_id: tool_result.call_id,
+                    tool_calls: None,
+                }
+            }
             ContextMessage::Image(url) => {
                 let content =
                     vec![ContentPart::ImageUrl { image_url: ImageUrl { url, detail: None } }];
@@ -317,7 +394,8 @@ pub enum OpenRouterRole {
 #[cfg(test)]
 mod tests {
     use forge_domain::{
-        ContentMessage, ContextMessage, Role, ToolCallFull, ToolCallId, ToolName, ToolResult,
+        with_provider_tools, ContentMessage, ContextMessage, ProviderToolEvent, ProviderTools,
+        Role, ToolCallFull, ToolCallId, ToolContent, ToolName, ToolResult,
     };
     use insta::assert_json_snapshot;
     use serde_json::json;
@@ -330,6 +408,7 @@ mod tests {
             role: Role::User,
             content: "Hello".to_string(),
             tool_calls: None,
+            evidence: None,
         });
         let router_message = OpenRouterMessage::from(user_message);
         assert_json_snapshot!(router_message);
@@ -351,6 +430,7 @@ mod tests {
             role: Role::User,
             content: xml_content.to_string(),
             tool_calls: None,
+            evidence: None,
         });
         let router_message = OpenRouterMessage::from(message);
         assert_json_snapshot!(router_message);
@@ -368,6 +448,7 @@ mod tests {
             role: Role::Assistant,
             content: "Using tool".to_string(),
             tool_calls: Some(vec![tool_call]),
+            evidence: None,
         });
         let router_message = OpenRouterMessage::from(assistant_message);
         assert_json_snapshot!(router_message);
@@ -421,6 +502,106 @@ mod tests {
         assert_json_snapshot!(router_message);
     }
 
+    #[test]
+    fn test_request_starts_with_system_message() {
+        let context = Context::default()
+            .add_message(ContextMessage::user("Hello"))
+            .system("Be brief");
+
+        let actual = OpenRouterRequest::from(context)
+            .messages
+            .unwrap()
+            .into_iter()
+            .map(|message| message.role)
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual, vec![OpenRouterRole::System, OpenRouterRole::User]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "exactly one system message, found 2")]
+    fn test_request_with_stray_system_message() {
+        let mut context = Context::default().system("Be brief");
+        context.messages.push(ContextMessage::system("Be verbose"));
+
+        let _ = OpenRouterRequest::from(context);
+    }
+
+    #[test]
+    fn test_follow_up_request_only_calls_defined_tools() {
+        let search = ToolCallFull::new(ToolName::new("web_search"))
+            .call_id(ToolCallId::new("srv_1"))
+            .arguments(json!({"query": "rust 2024 edition"}));
+        let read = ToolCallFull::new(ToolName::new("tool_forge_fs_read"))
+            .call_id(ToolCallId::new("call_1"))
+            .arguments(json!({"path": "/project/main.rs"}));
+        let mut provider_tools = ProviderTools::default();
+        provider_tools.push(ProviderToolEvent::Call(search));
+        let provider_tools = provider_tools
+            .push(ProviderToolEvent::Result {
+                call_id: ToolCallId::new("srv_1"),
+                content: ToolContent::from("3 results"),
+                is_error: false,
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+        let (calls, results) = with_provider_tools(
+            &provider_tools,
+            vec![read.clone()],
+            vec![ToolResult::from(read).success("fn main() {}")],
+        );
+        let context = Context::default()
+            .add_tool(ToolDefinition::new("tool_forge_fs_read"))
+            .add_message(ContextMessage::user("What's new in Rust 2024?"))
+            .add_message(ContextMessage::assistant("Searching", Some(calls)))
+            .add_tool_results(results);
+
+        let actual = OpenRouterRequest::from(context);
+
+        let tools = actual
+            .tools
+            .unwrap()
+            .into_iter()
+            .map(|tool| ToolName::new(tool.function.name))
+            .collect::<Vec<_>>();
+        let messages = actual.messages.unwrap();
+        let calls = messages
+            .iter()
+            .flat_map(|message| message.tool_calls.iter().flatten())
+            .collect::<Vec<_>>();
+        let answered = messages
+            .iter()
+            .filter_map(|message| message.tool_call_id.clone())
+            .collect::<Vec<_>>();
+        assert!(calls
+            .iter()
+            .all(|call| tools.contains(call.function.name.as_ref().unwrap())));
+        assert_eq!(
+            answered,
+            calls
+                .iter()
+                .filter_map(|call| call.id.clone())
+                .collect::<Vec<_>>()
+        );
+        assert!(matches!(
+            &messages[1].content,
+            Some(MessageContent::Text(text)) if text.contains("<tool_name>web_search</tool_name>")
+        ));
+    }
+
+    #[test]
+    fn test_tool_examples_are_appended_to_description() {
+        let fixture = ToolDefinition::new("tool_forge_fs_read")
+            .description("Reads a file")
+            .examples(vec![json!({"path": "/project/main.rs"})]);
+
+        let actual = OpenRouterTool::from(fixture).function.description;
+
+        let expected = "Reads a file\n\nExamples:\n{\"path\":\"/project/main.rs\"}";
+        assert_eq!(actual.as_deref(), Some(expected));
+    }
+
     #[test]
     fn test_transform_display() {
         assert_eq!(
@@ -428,4 +609,42 @@ mod tests {
             "\"middle-out\""
         );
     }
+
+    #[test]
+    fn test_provider_preferences_serialize_into_the_provider_object() {
+        let fixture = ProviderPreferences::parse(
+            r#"{"order":["anthropic","openai"],"allow_fallbacks":false,"ignore":["deepinfra"],"data_collection":"deny","sort":"throughput"}"#,
+        )
+        .unwrap();
+
+        let actual = serde_json::to_value(OpenRouterRequest::default().provider(fixture)).unwrap();
+
+        let expected = json!({
+            "provider": {
+                "order": ["anthropic", "openai"],
+                "allow_fallbacks": false,
+                "data_collection": "deny",
+                "ignore": ["deepinfra"],
+                "sort": "throughput"
+            }
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_provider_preferences_reject_unknown_keys() {
+        let actual = [r#"{"orders":["anthropic"]}"#, r#"{"sort":"cheapest"}"#]
+            .map(|json| ProviderPreferences::parse(json).unwrap_err().to_string());
+
+        assert!(
+            actual[0].contains("unknown field `orders`"),
+            "{}",
+            actual[0]
+        );
+        assert!(
+            actual[1].contains("unknown variant `cheapest`"),
+            "{}",
+            actual[1]
+        );
+    }
 }
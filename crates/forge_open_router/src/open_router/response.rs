@@ -3,13 +3,16 @@ use std::fmt::Display;
 use std::str::FromStr;
 
 use forge_domain::{
-    ChatCompletionMessage as ModelResponse, Content, FinishReason, ToolCallFull, ToolCallId,
-    ToolCallPart, ToolName, Usage,
+    ChatCompletionMessage as ModelResponse, Content, FinishReason, ProviderToolEvent, ToolCallFull,
+    ToolCallId, ToolCallPart, ToolContent, ToolName, Usage,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
 
 use super::error::Error;
 use super::tool_choice::FunctionType;
+use crate::ProviderError;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(untagged)]
@@ -66,6 +69,13 @@ pub struct ErrorResponse {
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
+impl From<ErrorResponse> for ProviderError {
+    fn from(error: ErrorResponse) -> Self {
+        let payload = serde_json::to_string(&error).unwrap_or_else(|_| error.to_string());
+        ProviderError::classify(None, payload)
+    }
+}
+
 impl Display for ErrorResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "message: {}", self.message)?;
@@ -84,6 +94,67 @@ pub struct ResponseMessage {
     pub role: Option<String>,
     pub tool_calls: Option<Vec<OpenRouterToolCall>>,
     pub refusal: Option<String>,
+    /// Events of the tools the provider runs itself, eg. web search. They
+    /// are read one by one so that events of unknown types can be skipped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_tool_events: Option<Vec<Value>>,
+}
+
+/// An event of a tool the provider runs itself. The call and its result
+/// arrive as events of their own, each in a single chunk.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerToolEvent {
+    ServerToolUse {
+        id: ToolCallId,
+        name: ToolName,
+        #[serde(default)]
+        input: Value,
+    },
+    ServerToolResult {
+        tool_use_id: ToolCallId,
+        #[serde(default)]
+        content: Value,
+        #[serde(default)]
+        is_error: bool,
+    },
+}
+
+impl From<ServerToolEvent> for ProviderToolEvent {
+    fn from(event: ServerToolEvent) -> Self {
+        match event {
+            ServerToolEvent::ServerToolUse { id, name, input } => {
+                ProviderToolEvent::Call(ToolCallFull::new(name).call_id(id).arguments(input))
+            }
+            ServerToolEvent::ServerToolResult { tool_use_id, content, is_error } => {
+                ProviderToolEvent::Result {
+                    call_id: tool_use_id,
+                    content: ToolContent::json(content),
+                    is_error,
+                }
+            }
+        }
+    }
+}
+
+impl ResponseMessage {
+    /// The server tool events of the message, events of other types are
+    /// skipped
+    fn provider_tools(&self) -> Vec<ProviderToolEvent> {
+        self.server_tool_events
+            .iter()
+            .flatten()
+            .filter_map(
+                |event| match serde_json::from_value::<ServerToolEvent>(event.clone()) {
+                    Ok(event) => Some(event.into()),
+                    Err(err) => {
+                        debug!(%event, %err, "Skipping an unknown server tool event");
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -134,6 +205,8 @@ impl TryFrom<OpenRouterResponse> for ModelResponse {
                                     .clone()
                                     .and_then(|s| FinishReason::from_str(&s).ok()),
                             );
+                            resp.refusal = message.refusal.clone();
+                            resp.provider_tools = message.provider_tools();
                             if let Some(tool_calls) = &message.tool_calls {
                                 for tool_call in tool_calls {
                                     resp = resp.add_tool_call(ToolCallFull {
@@ -160,6 +233,8 @@ impl TryFrom<OpenRouterResponse> for ModelResponse {
                                     .clone()
                                     .and_then(|s| FinishReason::from_str(&s).ok()),
                             );
+                            resp.refusal = delta.refusal.clone();
+                            resp.provider_tools = delta.provider_tools();
                             if let Some(tool_calls) = &delta.tool_calls {
                                 for tool_call in tool_calls {
                                     resp = resp.add_tool_call(ToolCallPart {
@@ -181,7 +256,7 @@ impl TryFrom<OpenRouterResponse> for ModelResponse {
                     Err(Error::EmptyContent)
                 }
             }
-            OpenRouterResponse::Failure { error } => Err(Error::Upstream(error)),
+            OpenRouterResponse::Failure { error } => Err(Error::Upstream(error.into())),
         }
     }
 }
@@ -190,6 +265,7 @@ impl TryFrom<OpenRouterResponse> for ModelResponse {
 mod tests {
     use anyhow::Context;
     use forge_domain::ChatCompletionMessage;
+    use pretty_assertions::assert_eq;
 
     use super::*;
 
@@ -219,4 +295,77 @@ mod tests {
         let event = "{\"id\":\"gen-1739949430-JZMcABaj4fg8oFDtRNDZ\",\"provider\":\"OpenAI\",\"model\":\"openai/gpt-4o-mini\",\"object\":\"chat.completion.chunk\",\"created\":1739949430,\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":null,\"tool_calls\":[{\"index\":0,\"id\":\"call_bhjvz9w48ov4DSRhM15qLMmh\",\"type\":\"function\",\"function\":{\"name\":\"tool_forge_process_shell\",\"arguments\":\"\"}}],\"refusal\":null},\"logprobs\":null,\"finish_reason\":null,\"native_finish_reason\":null}],\"system_fingerprint\":\"fp_00428b782a\"}";
         assert!(Fixture::test_response_compatibility(event));
     }
+
+    #[test]
+    fn test_failure_event_is_classified() {
+        let event = r#"{"error":{"code":429,"message":"Provider returned error"}}"#;
+        let response = serde_json::from_str::<OpenRouterResponse>(event).unwrap();
+
+        let actual = ChatCompletionMessage::try_from(response)
+            .context("Failed to create completion message")
+            .unwrap_err();
+
+        let error = actual
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<ProviderError>())
+            .unwrap();
+        assert!(matches!(error, ProviderError::RateLimited(_)));
+    }
+
+    fn chunk(delta: serde_json::Value) -> ChatCompletionMessage {
+        let event = serde_json::json!({
+            "id": "gen-1",
+            "model": "anthropic/claude-3.5-sonnet",
+            "object": "chat.completion.chunk",
+            "created": 1739949430,
+            "choices": [{"index": 0, "delta": delta, "finish_reason": null}]
+        });
+        let response = serde_json::from_value::<OpenRouterResponse>(event).unwrap();
+        ChatCompletionMessage::try_from(response).unwrap()
+    }
+
+    #[test]
+    fn test_server_tool_events_are_read_from_the_stream() {
+        let fixture = [
+            serde_json::json!({"role": "assistant", "content": "Searching. "}),
+            serde_json::json!({"content": null, "server_tool_events": [
+                {"type": "server_tool_use", "id": "srv_1", "name": "web_search", "input": {"query": "rust"}}
+            ]}),
+            serde_json::json!({"content": null, "server_tool_events": [
+                {"type": "server_tool_progress", "id": "srv_1", "percent": 50},
+                {"type": "server_tool_result", "tool_use_id": "srv_1", "content": [{"title": "Rust"}]}
+            ]}),
+            serde_json::json!({"content": "Found it.", "tool_calls": [
+                {"index": 0, "id": "call_1", "type": "function", "function": {"name": "forge_tool_fs_read", "arguments": ""}}
+            ]}),
+        ];
+
+        let actual = fixture.into_iter().map(chunk).collect::<Vec<_>>();
+
+        let events = actual
+            .iter()
+            .flat_map(|message| message.provider_tools.clone())
+            .collect::<Vec<_>>();
+        let expected = vec![
+            ProviderToolEvent::Call(
+                ToolCallFull::new(ToolName::new("web_search"))
+                    .call_id(ToolCallId::new("srv_1"))
+                    .arguments(serde_json::json!({"query": "rust"})),
+            ),
+            ProviderToolEvent::Result {
+                call_id: ToolCallId::new("srv_1"),
+                content: ToolContent::json(serde_json::json!([{"title": "Rust"}])),
+                is_error: false,
+            },
+        ];
+        assert_eq!(events, expected);
+
+        let content = actual
+            .iter()
+            .filter_map(|message| message.content.as_ref())
+            .map(|content| content.as_str())
+            .collect::<String>();
+        assert_eq!(content, "Searching. Found it.");
+        assert_eq!(actual[3].tool_call.len(), 1);
+    }
 }
@@ -49,11 +49,13 @@ mod tests {
             .success("test result");
 
         let context = Context {
+            system: None,
             messages: vec![
                 ContextMessage::ContentMessage(ContentMessage {
                     role: Role::Assistant,
                     content: "Using tool".to_string(),
                     tool_calls: Some(vec![tool_call]),
+                    evidence: None,
                 }),
                 ContextMessage::ToolMessage(tool_result),
             ],
@@ -71,4 +73,29 @@ mod tests {
         // Converted tool message
         assert_eq!(messages[1].role, OpenRouterRole::User);
     }
+
+    #[test]
+    fn test_system_message_is_kept() {
+        let context = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("Hello"))
+            .add_tool_results(vec![
+                ToolResult::new(ToolName::new("test_tool")).success("done")
+            ]);
+
+        let transformed = DropToolCalls.transform(OpenRouterRequest::from(context));
+
+        let actual = transformed
+            .messages
+            .unwrap()
+            .into_iter()
+            .map(|message| message.role)
+            .collect::<Vec<_>>();
+        let expected = vec![
+            OpenRouterRole::System,
+            OpenRouterRole::User,
+            OpenRouterRole::User,
+        ];
+        assert_eq!(actual, expected);
+    }
 }
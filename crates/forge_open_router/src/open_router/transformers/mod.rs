@@ -1,9 +1,9 @@
 mod combine;
 mod drop_tool_call;
-mod identity;
 mod open_ai;
 mod pipeline;
 mod set_cache;
+mod stringify_tool_results;
 mod tool_choice;
 mod transformer;
 mod when;
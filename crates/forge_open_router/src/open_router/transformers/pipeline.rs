@@ -1,36 +1,286 @@
-use forge_domain::Provider;
+use forge_domain::{
+    ModelId, Provider, TransformerKind, TransformerPipeline, TransformerStage, TransformerStep,
+};
 
 use super::drop_tool_call::DropToolCalls;
-use super::identity::Identity;
 use super::open_ai::OpenAITransformer;
 use super::set_cache::SetCache;
+use super::stringify_tool_results::StringifyToolResults;
 use super::tool_choice::SetToolChoice;
 use super::Transformer;
 use crate::open_router::request::OpenRouterRequest;
 use crate::open_router::tool_choice::ToolChoice;
 
-/// Pipeline for transforming requests based on the provider type
-pub struct ProviderPipeline<'a>(&'a Provider);
+/// Applies the steps of a transformer pipeline in order, each to the
+/// providers and models it's meant for
+pub struct ProviderPipeline<'a> {
+    provider: &'a Provider,
+    pipeline: &'a TransformerPipeline,
+}
 
 impl<'a> ProviderPipeline<'a> {
-    /// Creates a new provider pipeline for the given provider
-    pub fn new(provider: &'a Provider) -> Self {
-        Self(provider)
+    pub fn new(provider: &'a Provider, pipeline: &'a TransformerPipeline) -> Self {
+        Self { provider, pipeline }
+    }
+
+    /// Whether a transformer changes the requests of the provider
+    fn applies_to_provider(&self, kind: TransformerKind) -> bool {
+        match kind {
+            TransformerKind::StringifyToolResults => !self.provider.accepts_json_tool_results(),
+            TransformerKind::DropToolCalls
+            | TransformerKind::SetToolChoice
+            | TransformerKind::SetCache => self.provider.is_open_router(),
+            TransformerKind::OpenAi => self.provider.is_open_ai(),
+        }
+    }
+
+    /// Whether the step changes the request, given its provider and model
+    fn applies(&self, step: &TransformerStep, request: &OpenRouterRequest) -> bool {
+        let model = request.model.as_ref().map(ModelId::as_str);
+        self.applies_to_provider(step.kind) && step.matches_model(model)
+    }
+
+    /// Transforms the request and returns what each step did to it. Each step
+    /// serializes the request twice to measure it, so this is only run to
+    /// report on the pipeline.
+    pub fn run(
+        &self,
+        mut request: OpenRouterRequest,
+    ) -> (OpenRouterRequest, Vec<TransformerStage>) {
+        let mut stages = Vec::new();
+        for step in &self.pipeline.steps {
+            let (messages_before, json_before) = measure(&request);
+            let applied = self.applies(step, &request);
+            if applied {
+                request = apply(step.kind, request);
+            }
+            let (messages_after, json_after) = measure(&request);
+
+            stages.push(TransformerStage {
+                step: step.clone(),
+                applied,
+                changed: json_before != json_after,
+                messages_before,
+                messages_after,
+                bytes_before: json_before.len(),
+                bytes_after: json_after.len(),
+            });
+        }
+        (request, stages)
     }
 }
 
 impl Transformer for ProviderPipeline<'_> {
-    fn transform(&self, request: OpenRouterRequest) -> OpenRouterRequest {
-        let or_transformers = Identity
-            .combine(DropToolCalls.when_model("mistral"))
-            .combine(SetToolChoice::new(ToolChoice::Auto).when_model("gemini"))
-            .combine(SetCache.except_when_model("mistral|gemini|openai"))
-            .when(move |_| self.0.is_open_router());
+    fn transform(&self, mut request: OpenRouterRequest) -> OpenRouterRequest {
+        for step in &self.pipeline.steps {
+            if self.applies(step, &request) {
+                request = apply(step.kind, request);
+            }
+        }
+        request
+    }
+}
+
+fn apply(kind: TransformerKind, request: OpenRouterRequest) -> OpenRouterRequest {
+    match kind {
+        TransformerKind::StringifyToolResults => StringifyToolResults.transform(request),
+        TransformerKind::DropToolCalls => DropToolCalls.transform(request),
+        TransformerKind::SetToolChoice => SetToolChoice::new(ToolChoice::Auto).transform(request),
+        TransformerKind::SetCache => SetCache.transform(request),
+        TransformerKind::OpenAi => OpenAITransformer.transform(request),
+    }
+}
+
+/// The number of messages of the request and its JSON
+fn measure(request: &OpenRouterRequest) -> (usize, String) {
+    let messages = request.messages.as_ref().map_or(0, Vec::len);
+    (messages, serde_json::to_string(request).unwrap_or_default())
+}
 
-        let openai_transformers = OpenAITransformer.when(move |_| self.0.is_open_ai());
+#[cfg(test)]
+mod tests {
+    use forge_domain::{
+        Context, ContextMessage, ToolCallFull, ToolCallId, ToolContent, ToolName, ToolResult,
+    };
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
 
-        or_transformers
-            .combine(openai_transformers)
+    use super::*;
+    use crate::open_router::request::MessageContent;
+
+    fn is_cached(provider: &Provider, model: &str, prompt_cache: bool) -> bool {
+        let context = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("Hello"));
+        let request = OpenRouterRequest::from(context).model(ModelId::new(model));
+
+        let pipeline = TransformerPipeline::builtin(prompt_cache);
+        let messages = ProviderPipeline::new(provider, &pipeline)
             .transform(request)
+            .messages
+            .unwrap();
+
+        matches!(messages[0].content, Some(MessageContent::Parts(_)))
+    }
+
+    #[test]
+    fn test_prompt_cache_is_off_by_default() {
+        let provider = Provider::open_router("key");
+
+        assert!(!is_cached(&provider, "anthropic/claude-3.5-sonnet", false));
+        assert!(is_cached(&provider, "anthropic/claude-3.5-sonnet", true));
+    }
+
+    #[test]
+    fn test_prompt_cache_only_applies_to_anthropic_models() {
+        let provider = Provider::open_router("key");
+
+        assert!(!is_cached(&provider, "google/gemini-2.0-flash", true));
+        assert!(!is_cached(
+            &Provider::openai("key"),
+            "claude-3.5-sonnet",
+            true
+        ));
+    }
+
+    fn request(model: &str, content: ToolContent) -> OpenRouterRequest {
+        let call = ToolCallFull::new(ToolName::new("tool_forge_symbol_search"))
+            .call_id(ToolCallId::new("call_1"))
+            .arguments(json!({"name": "Pipeline"}));
+        let context = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("Find the pipeline"))
+            .add_message(ContextMessage::assistant("Searching", Some(vec![call])))
+            .add_tool_results(vec![ToolResult::new(ToolName::new(
+                "tool_forge_symbol_search",
+            ))
+            .call_id(ToolCallId::new("call_1"))
+            .success(content)]);
+        OpenRouterRequest::from(context).model(ModelId::new(model))
+    }
+
+    /// The transformers as they were combined before the pipeline could be
+    /// configured. `combine` applies its argument first, so they ran from
+    /// last to first.
+    fn hard_wired(provider: &Provider, prompt_cache: bool, request: OpenRouterRequest) -> Value {
+        let or_transformers = DropToolCalls
+            .when_model("mistral")
+            .combine(SetToolChoice::new(ToolChoice::Auto).when_model("gemini"))
+            .combine(
+                SetCache
+                    .when_model("anthropic|claude")
+                    .when(move |_| prompt_cache),
+            )
+            .when(|_| provider.is_open_router());
+        let openai_transformers = OpenAITransformer.when(|_| provider.is_open_ai());
+
+        let request = or_transformers
+            .combine(openai_transformers)
+            .combine(StringifyToolResults.when(|_| !provider.accepts_json_tool_results()))
+            .transform(request);
+        serde_json::to_value(request).unwrap()
+    }
+
+    fn piped(provider: &Provider, prompt_cache: bool, request: OpenRouterRequest) -> Value {
+        let pipeline = TransformerPipeline::builtin(prompt_cache);
+        serde_json::to_value(ProviderPipeline::new(provider, &pipeline).transform(request)).unwrap()
+    }
+
+    #[test]
+    fn test_builtin_matches_the_hard_wired_order() {
+        let providers = [
+            Provider::open_router("key"),
+            Provider::openai("key"),
+            Provider::antinomy("key"),
+        ];
+        let models = [
+            "mistralai/mistral-large",
+            "google/gemini-2.0-flash",
+            "anthropic/claude-3.5-sonnet",
+            "openai/gpt-4o",
+        ];
+        let text = ToolContent::from("2 matches");
+        let json = ToolContent::json(json!({"matches": [], "omitted": 0}));
+
+        for provider in &providers {
+            for model in models {
+                for prompt_cache in [false, true] {
+                    for content in [text.clone(), json.clone()] {
+                        let actual = piped(provider, prompt_cache, request(model, content.clone()));
+                        let expected = hard_wired(provider, prompt_cache, request(model, content));
+                        assert_eq!(actual, expected, "{model} with {provider:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_results_are_stringified_before_tool_calls_are_dropped() {
+        let fixture = request(
+            "mistralai/mistral-large",
+            ToolContent::json(json!({"matches": [], "omitted": 0})),
+        );
+
+        let actual = piped(&Provider::open_router("key"), false, fixture);
+
+        assert_eq!(actual["messages"][3]["role"], json!("user"));
+        assert!(actual["messages"][3]["content"].is_string());
+    }
+
+    #[test]
+    fn test_mistral_json_results_reach_open_router_as_text() {
+        let expected = json!({
+            "role": "user",
+            "content": r#"<tool_result><tool_name>tool_forge_symbol_search</tool_name><success><![CDATA[{"matches":[],"omitted":0}]]></success></tool_result>"#,
+        });
+
+        for prompt_cache in [false, true] {
+            let fixture = request(
+                "mistralai/mistral-large",
+                ToolContent::json(json!({"matches": [], "omitted": 0})),
+            );
+
+            let actual = piped(&Provider::open_router("key"), prompt_cache, fixture);
+
+            assert_eq!(actual["messages"][3], expected);
+        }
+    }
+
+    #[test]
+    fn test_stages_account_for_changes() {
+        let pipeline = TransformerPipeline {
+            steps: vec![
+                TransformerStep::new(TransformerKind::StringifyToolResults),
+                TransformerStep::new(TransformerKind::DropToolCalls).models("mistral"),
+                TransformerStep::new(TransformerKind::SetToolChoice).models("gemini"),
+                TransformerStep::new(TransformerKind::OpenAi),
+            ],
+        };
+        let fixture = request(
+            "mistralai/mistral-large",
+            ToolContent::json(json!({"matches": [], "omitted": 0})),
+        );
+
+        let (_, stages) =
+            ProviderPipeline::new(&Provider::open_router("key"), &pipeline).run(fixture);
+
+        let actual = stages
+            .iter()
+            .map(|stage| (stage.step.kind, stage.applied, stage.changed))
+            .collect::<Vec<_>>();
+        let expected = vec![
+            (TransformerKind::StringifyToolResults, true, true),
+            (TransformerKind::DropToolCalls, true, true),
+            (TransformerKind::SetToolChoice, false, false),
+            (TransformerKind::OpenAi, false, false),
+        ];
+        assert_eq!(actual, expected);
+        assert!(stages
+            .iter()
+            .all(|stage| stage.messages_before == 4 && stage.messages_after == 4));
+        assert!(stages[0].bytes_after != stages[0].bytes_before);
+        assert_eq!(stages[1].bytes_before, stages[0].bytes_after);
+        assert_eq!(stages[3].bytes_after, stages[3].bytes_before);
     }
 }
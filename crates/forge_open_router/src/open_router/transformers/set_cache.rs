@@ -1,22 +1,25 @@
 use crate::open_router::request::{OpenRouterRequest, OpenRouterRole};
 use crate::open_router::transformers::Transformer;
 
-/// Transformer that caches the last user/system message for supported models
+/// Transformer that marks the system prompt and the last user message as
+/// cache breakpoints. The provider caches the request up to each breakpoint,
+/// so the system prompt stays cached while the conversation after it changes.
 pub struct SetCache;
 
 impl Transformer for SetCache {
     fn transform(&self, mut request: OpenRouterRequest) -> OpenRouterRequest {
-        if let (Some(mut messages), Some(model)) = (request.messages.take(), request.model.take()) {
-            if let Some(msg) = messages
-                .iter_mut()
-                .rev()
-                .find(|msg| matches!(msg.role, OpenRouterRole::System | OpenRouterRole::User))
-            {
+        if let Some(messages) = request.messages.as_mut() {
+            let system = messages
+                .iter()
+                .position(|msg| msg.role == OpenRouterRole::System);
+            let last_user = messages
+                .iter()
+                .rposition(|msg| msg.role == OpenRouterRole::User);
+
+            for index in system.into_iter().chain(last_user) {
+                let msg = &mut messages[index];
                 msg.content = msg.content.take().map(|content| content.cached());
             }
-
-            request.messages = Some(messages);
-            request.model = Some(model);
         }
         request
     }
@@ -25,6 +28,8 @@ impl Transformer for SetCache {
 #[cfg(test)]
 mod tests {
     use forge_domain::{ContentMessage, Context, ContextMessage, ModelId, Role};
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
 
     use super::*;
     use crate::open_router::request::MessageContent;
@@ -32,10 +37,12 @@ mod tests {
     #[test]
     fn test_sonnet_transformer_caching() {
         let context = Context {
+            system: None,
             messages: vec![ContextMessage::ContentMessage(ContentMessage {
                 role: Role::User,
                 content: "test message".to_string(),
                 tool_calls: None,
+                evidence: None,
             })],
             tools: vec![],
             tool_choice: None,
@@ -53,4 +60,69 @@ mod tests {
             Some(MessageContent::Parts(_))
         ));
     }
+
+    #[test]
+    fn test_system_message_is_kept() {
+        let context = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("Hello"));
+        let request =
+            OpenRouterRequest::from(context).model(ModelId::new("anthropic/claude-3.5-sonnet"));
+
+        let messages = SetCache.transform(request).messages.unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, OpenRouterRole::System);
+    }
+
+    #[test]
+    fn test_breakpoints_are_annotated() {
+        let context = Context::default()
+            .system("Be brief")
+            .add_message(ContextMessage::user("Hello"))
+            .add_message(ContextMessage::assistant("Hi, what can I do?", None))
+            .add_message(ContextMessage::user("Fix the build"));
+        let request =
+            OpenRouterRequest::from(context).model(ModelId::new("anthropic/claude-3.5-sonnet"));
+
+        let request = serde_json::to_value(SetCache.transform(request)).unwrap();
+
+        let cached = |text: &str| {
+            json!([{
+                "type": "text",
+                "text": text,
+                "cache_control": { "type": "ephemeral" },
+            }])
+        };
+        let actual = request["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| message["content"].clone())
+            .collect::<Vec<_>>();
+        let expected = vec![
+            cached("Be brief"),
+            Value::from("Hello"),
+            Value::from("Hi, what can I do?"),
+            cached("Fix the build"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_request_without_system_prompt() {
+        let context = Context::default()
+            .add_message(ContextMessage::user("Hello"))
+            .add_message(ContextMessage::assistant("Hi", None));
+        let request =
+            OpenRouterRequest::from(context).model(ModelId::new("anthropic/claude-3.5-sonnet"));
+
+        let messages = SetCache.transform(request).messages.unwrap();
+
+        assert!(matches!(
+            messages[0].content,
+            Some(MessageContent::Parts(_))
+        ));
+        assert!(matches!(messages[1].content, Some(MessageContent::Text(_))));
+    }
 }
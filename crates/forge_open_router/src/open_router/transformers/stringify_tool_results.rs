@@ -0,0 +1,101 @@
+use forge_domain::{ToolContent, ToolResult};
+
+use super::transformer::Transformer;
+use crate::open_router::request::{MessageContent, OpenRouterRequest, OpenRouterRole};
+
+/// Serializes the JSON content of tool results, for providers that only take
+/// text. The results read the same as text results do.
+pub struct StringifyToolResults;
+
+impl Transformer for StringifyToolResults {
+    fn transform(&self, mut request: OpenRouterRequest) -> OpenRouterRequest {
+        if let Some(messages) = request.messages.as_mut() {
+            for message in messages
+                .iter_mut()
+                .filter(|message| message.role == OpenRouterRole::Tool)
+            {
+                if let (Some(MessageContent::Json(value)), Some(name)) =
+                    (&message.content, &message.name)
+                {
+                    let text = ToolResult::new(name.clone())
+                        .success(ToolContent::Json(value.clone()))
+                        .to_string();
+                    message.content = Some(MessageContent::Text(text));
+                }
+            }
+        }
+
+        request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_domain::{Context, ToolCallId, ToolName};
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn request(content: ToolContent) -> OpenRouterRequest {
+        let context = Context::default().add_tool_results(vec![ToolResult::new(ToolName::new(
+            "tool_forge_symbol_search",
+        ))
+        .call_id(ToolCallId::new("call_1"))
+        .success(content)]);
+        OpenRouterRequest::from(context)
+    }
+
+    fn content(request: OpenRouterRequest) -> Value {
+        let messages = request.messages.unwrap();
+        serde_json::to_value(&messages[0].content).unwrap()
+    }
+
+    #[test]
+    fn test_text_is_kept_as_text() {
+        let fixture = ToolContent::from("2 matches");
+        let expected = json!(
+            "<tool_result><tool_name>tool_forge_symbol_search</tool_name><success><![CDATA[2 matches]]></success></tool_result>"
+        );
+
+        let actual = [
+            content(request(fixture.clone())),
+            content(StringifyToolResults.transform(request(fixture))),
+        ];
+
+        assert_eq!(actual, [expected.clone(), expected]);
+    }
+
+    #[test]
+    fn test_json_is_passed_through() {
+        let fixture = ToolContent::json(json!({"matches": [], "omitted": 0}));
+
+        let actual = content(request(fixture));
+
+        assert_eq!(actual, json!({"matches": [], "omitted": 0}));
+    }
+
+    #[test]
+    fn test_json_is_stringified() {
+        let fixture = ToolContent::json(json!({"matches": [], "omitted": 0}));
+
+        let actual = content(StringifyToolResults.transform(request(fixture)));
+
+        let expected = json!(
+            r#"<tool_result><tool_name>tool_forge_symbol_search</tool_name><success><![CDATA[{"matches":[],"omitted":0}]]></success></tool_result>"#
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_failed_json_tool_is_text() {
+        let context = Context::default().add_tool_results(vec![ToolResult::new(ToolName::new(
+            "tool_forge_symbol_search",
+        ))
+        .failure(anyhow::anyhow!("no such directory"))]);
+
+        let actual = content(OpenRouterRequest::from(context));
+
+        assert!(actual.is_string());
+    }
+}
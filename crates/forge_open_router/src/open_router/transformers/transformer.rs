@@ -47,7 +47,7 @@ pub trait Transformer {
     fn transform(&self, request: OpenRouterRequest) -> OpenRouterRequest;
 
     /// Combines this transformer with another, creating a new transformer that
-    /// applies both transformations in sequence
+    /// applies both transformations in sequence: `other` first, then this one
     fn combine<Other>(self, other: Other) -> Combine<Self, Other>
     where
         Self: Sized,
@@ -0,0 +1,94 @@
+use reqwest::{Response, StatusCode};
+
+use crate::ProviderError;
+
+/// Whether the provider rejected the credentials. Other client errors, eg.
+/// 402 for missing credits or 403 for moderation, are not about the key.
+pub fn is_auth_failure(status: StatusCode) -> bool {
+    status == StatusCode::UNAUTHORIZED
+}
+
+/// Turns a response that rejected the credentials into an authentication
+/// error, so that callers can offer to replace the key
+pub async fn auth_error(response: Response) -> anyhow::Error {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    forge_domain::Error::ProviderAuth(describe(status, &body)).into()
+}
+
+/// Turns a non-success response into a [`ProviderError`] classified by its
+/// status and body. Requests too large for the model are also reported as
+/// [`forge_domain::Error::ContextLengthExceeded`], so that they can be
+/// compacted.
+pub async fn provider_error(response: Response) -> anyhow::Error {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let error = ProviderError::classify(Some(status.as_u16()), body.as_str());
+    if let ProviderError::ContextLengthExceeded(_) = error {
+        return anyhow::Error::from(error).context(forge_domain::Error::ContextLengthExceeded(
+            describe(status, &body),
+        ));
+    }
+    anyhow::Error::from(error).context(format!("Failed with status {status}"))
+}
+
+/// Turns an error sent in the middle of a stream, after a successful status,
+/// into an error reported like [`provider_error`] does
+pub fn stream_error(error: ProviderError) -> anyhow::Error {
+    if let ProviderError::ContextLengthExceeded(payload) = &error {
+        let reason = payload.clone();
+        return anyhow::Error::from(error)
+            .context(forge_domain::Error::ContextLengthExceeded(reason));
+    }
+    error.into()
+}
+
+/// Fails for non-success responses, authentication failures are reported as
+/// [`forge_domain::Error::ProviderAuth`] and others as [`ProviderError`]
+pub async fn check_status(response: Response) -> anyhow::Result<Response> {
+    if is_auth_failure(response.status()) {
+        return Err(auth_error(response).await);
+    }
+    if !response.status().is_success() {
+        return Err(provider_error(response).await);
+    }
+    Ok(response)
+}
+
+fn describe(status: StatusCode, body: &str) -> String {
+    match body.trim() {
+        "" => status.to_string(),
+        body => format!("{status}: {body}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_only_unauthorized_is_an_auth_failure() {
+        let actual = [
+            StatusCode::UNAUTHORIZED,
+            StatusCode::PAYMENT_REQUIRED,
+            StatusCode::FORBIDDEN,
+            StatusCode::TOO_MANY_REQUESTS,
+        ]
+        .map(is_auth_failure);
+        assert_eq!(actual, [true, false, false, false]);
+    }
+
+    #[test]
+    fn test_describe_includes_the_body() {
+        let actual = describe(
+            StatusCode::UNAUTHORIZED,
+            " {\"error\":\"No auth credentials found\"}\n",
+        );
+        let expected = "401 Unauthorized: {\"error\":\"No auth credentials found\"}";
+        assert_eq!(actual, expected);
+
+        assert_eq!(describe(StatusCode::UNAUTHORIZED, ""), "401 Unauthorized");
+    }
+}
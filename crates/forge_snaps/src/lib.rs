@@ -10,10 +10,16 @@ pub struct SnapshotInfo {
     pub timestamp: String,
     /// Original file path that was snapshotted
     pub original_path: PathBuf,
-    /// Path to the snapshot file
+    /// Path to the snapshot's manifest, or to the full copy of the file for
+    /// snapshots taken before content was deduplicated
     pub snapshot_path: PathBuf,
     /// Index of this snapshot in the list (0 = newest)
     pub index: usize,
+    /// Size of the snapshotted file in bytes
+    pub size: u64,
+    /// Blake3 hash of the content, which is stored once however many
+    /// snapshots share it. `None` for snapshots in the old format.
+    pub hash: Option<String>,
 }
 
 impl SnapshotInfo {
@@ -24,28 +30,50 @@ impl SnapshotInfo {
         snapshot_path: PathBuf,
         index: usize,
     ) -> Self {
-        Self { timestamp, original_path, snapshot_path, index }
+        Self {
+            timestamp,
+            original_path,
+            snapshot_path,
+            index,
+            size: 0,
+            hash: None,
+        }
     }
+}
 
-    /// Returns a formatted date string for the snapshot's timestamp
-    pub fn formatted_date(&self) -> String {
-        // In a real implementation, this would convert the Unix timestamp
-        // to a human-readable date string
-        self.timestamp.to_string()
-    }
+/// Picks one of the snapshots of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotSelector {
+    /// The snapshot taken at this unix timestamp, in milliseconds
+    Timestamp(String),
+    /// The snapshot at this position, 0 being the newest and -1 the oldest
+    Index(isize),
+}
 
-    /*    /// Returns a human-readable size string (e.g., "2.4K")
-    pub fn formatted_size(&self) -> String {
-        if self.size < 1024 {
-            format!("{}B", self.size)
-        } else if self.size < 1024 * 1024 {
-            format!("{:.1}K", self.size as f64 / 1024.0)
-        } else if self.size < 1024 * 1024 * 1024 {
-            format!("{:.1}M", self.size as f64 / (1024.0 * 1024.0))
-        } else {
-            format!("{:.1}G", self.size as f64 / (1024.0 * 1024.0 * 1024.0))
-        }
-    }*/
+/// Disk space taken by a list of snapshots
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotUsage {
+    /// Total size of the snapshotted files, as if each was stored in full
+    pub logical_size: u64,
+    /// Size actually stored, counting content shared by several snapshots
+    /// once
+    pub stored_size: u64,
+}
+
+impl From<&[SnapshotInfo]> for SnapshotUsage {
+    fn from(snapshots: &[SnapshotInfo]) -> Self {
+        let mut hashes = std::collections::HashSet::new();
+        snapshots.iter().fold(Self::default(), |usage, snapshot| {
+            let stored = match &snapshot.hash {
+                Some(hash) if !hashes.insert(hash) => 0,
+                _ => snapshot.size,
+            };
+            Self {
+                logical_size: usage.logical_size + snapshot.size,
+                stored_size: usage.stored_size + stored,
+            }
+        })
+    }
 }
 
 /// Contains metadata about a specific snapshot file
@@ -60,6 +88,8 @@ pub struct SnapshotMetadata {
     pub content: Vec<u8>,
     /// SHA-256 hash of the original file path, used for storage organization
     pub path_hash: String,
+    /// Unix permission bits of the file when it was snapshotted, if known
+    pub mode: Option<u32>,
 }
 
 // Export the service implementation
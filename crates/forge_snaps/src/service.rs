@@ -1,11 +1,41 @@
+use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
 use forge_fs::ForgeFS;
 use forge_walker::Walker;
-
-use crate::{SnapshotInfo, SnapshotMetadata};
+use serde::{Deserialize, Serialize};
+
+use crate::{SnapshotInfo, SnapshotMetadata, SnapshotSelector};
+
+/// Directory under the snapshot base dir that holds file contents, each stored
+/// once under its blake3 hash
+const OBJECTS_DIR: &str = "objects";
+/// Extension of the manifests that describe snapshots
+const MANIFEST_EXTENSION: &str = "json";
+/// Extension of snapshots that hold a full copy of the file, written before
+/// contents were deduplicated. They are still listed and restored.
+const LEGACY_EXTENSION: &str = "snap";
+/// File under the snapshot base dir that lists the paths snapshots were
+/// taken of, so that the files under a moved directory are found without
+/// reading every manifest
+const INDEX_FILE: &str = "paths.json";
+/// How long a stored content is kept even when no manifest refers to it, so
+/// that garbage collection leaves alone the contents of snapshots whose
+/// manifest is still being written
+const OBJECT_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// Describes a snapshot whose content is kept in the objects directory
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    timestamp: String,
+    original_path: PathBuf,
+    hash: String,
+    size: u64,
+    #[serde(default)]
+    mode: Option<u32>,
+}
 
 /// Implementation of `FileSnapshotService` that provides snapshot
 /// functionality for files with retention policies.
@@ -15,6 +45,8 @@ pub struct SnapshotService {
     snapshot_base_dir: PathBuf,
     /// Maximum number of snapshots to keep per file
     max_snapshots_per_file: usize,
+    /// How long unreferenced contents are kept
+    object_grace_period: Duration,
 }
 
 impl SnapshotService {
@@ -23,6 +55,7 @@ impl SnapshotService {
         Self {
             snapshot_base_dir,
             max_snapshots_per_file: 10, // Default from requirements
+            object_grace_period: OBJECT_GRACE_PERIOD,
         }
     }
 
@@ -49,20 +82,172 @@ impl SnapshotService {
         Ok(dir)
     }
 
-    /// Creates a snapshot filename based on the timestamp
+    /// Creates a manifest filename based on the timestamp
     fn create_snapshot_filename(&self, timestamp: &str) -> String {
-        format!("{}.snap", timestamp)
+        format!("{}.{}", timestamp, MANIFEST_EXTENSION)
     }
 
-    /// Gets the timestamp from a snapshot filename
+    /// Gets the timestamp from a manifest or legacy snapshot filename
     fn get_timestamp_from_filename(&self, filename: &str) -> Option<u128> {
-        if let Some(name) = filename.strip_suffix(".snap") {
+        let (name, extension) = filename.rsplit_once('.')?;
+        if extension == MANIFEST_EXTENSION || extension == LEGACY_EXTENSION {
             name.parse().ok()
         } else {
             None
         }
     }
 
+    /// Path where content with the given hash is stored
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.snapshot_base_dir.join(OBJECTS_DIR).join(hash)
+    }
+
+    /// Stores the content unless it already is and returns its hash
+    async fn store_object(&self, content: &[u8]) -> Result<String> {
+        let hash = blake3::hash(content).to_hex().to_string();
+        let path = self.object_path(&hash);
+        if path.exists() {
+            // Refreshed so that garbage collection running meanwhile leaves it
+            // to the manifest about to refer to it
+            touch(&path)
+                .await
+                .with_context(|| format!("Failed to refresh snapshot content: {:?}", path))?;
+            return Ok(hash);
+        }
+
+        ForgeFS::create_dir_all(self.snapshot_base_dir.join(OBJECTS_DIR)).await?;
+        // Written aside and renamed, so that an interrupted write never leaves a
+        // truncated object behind under a valid hash
+        let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+        ForgeFS::write(&partial, content).await?;
+        ForgeFS::rename(&partial, &path).await?;
+
+        Ok(hash)
+    }
+
+    /// Reads the content stored under the given hash and checks it still
+    /// matches it
+    async fn read_object(&self, hash: &str) -> Result<Vec<u8>> {
+        let content = ForgeFS::read(self.object_path(hash)).await?;
+        if blake3::hash(&content).to_hex().as_str() != hash {
+            anyhow::bail!("Snapshot content {} is corrupted", hash);
+        }
+        Ok(content)
+    }
+
+    async fn read_manifest(&self, path: &Path) -> Result<Manifest> {
+        let content = ForgeFS::read(path).await?;
+        serde_json::from_slice(&content)
+            .with_context(|| format!("Failed to parse snapshot manifest: {:?}", path))
+    }
+
+    /// Reads the paths that have snapshots. A store written before the index
+    /// existed has it built from its manifests once.
+    async fn read_index(&self) -> Result<BTreeSet<PathBuf>> {
+        let path = self.snapshot_base_dir.join(INDEX_FILE);
+        match tokio::fs::read(&path).await {
+            Ok(content) => serde_json::from_slice(&content)
+                .with_context(|| format!("Failed to parse snapshot index: {:?}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let index = self.build_index().await?;
+                self.write_index(&index).await?;
+                Ok(index)
+            }
+            Err(e) => {
+                Err(anyhow::Error::new(e)
+                    .context(format!("Failed to read snapshot index: {:?}", path)))
+            }
+        }
+    }
+
+    async fn build_index(&self) -> Result<BTreeSet<PathBuf>> {
+        let mut index = BTreeSet::new();
+        if !self.snapshot_base_dir.exists() {
+            return Ok(index);
+        }
+        for path in self.manifest_paths().await? {
+            if let Ok(manifest) = self.read_manifest(&path).await {
+                index.insert(manifest.original_path);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Lists the manifests of every file, which live in a directory per file
+    /// apart from the objects and the index
+    async fn manifest_paths(&self) -> Result<Vec<PathBuf>> {
+        let objects_dir = self.snapshot_base_dir.join(OBJECTS_DIR);
+        Ok(ForgeFS::files_recursive(&self.snapshot_base_dir)
+            .await?
+            .into_iter()
+            .filter(|path| {
+                Self::is_manifest(path)
+                    && !path.starts_with(&objects_dir)
+                    && path.parent() != Some(self.snapshot_base_dir.as_path())
+            })
+            .collect())
+    }
+
+    async fn write_index(&self, index: &BTreeSet<PathBuf>) -> Result<()> {
+        ForgeFS::create_dir_all(&self.snapshot_base_dir).await?;
+        let path = self.snapshot_base_dir.join(INDEX_FILE);
+        // Written aside and renamed, so that a reader never sees half of it
+        let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+        ForgeFS::write(&partial, serde_json::to_vec_pretty(index)?).await?;
+        ForgeFS::rename(&partial, &path).await?;
+        Ok(())
+    }
+
+    /// Adds a path to the index unless it's already listed
+    async fn index_path(&self, file_path: &Path) -> Result<()> {
+        let mut index = self.read_index().await?;
+        if index.insert(file_path.to_path_buf()) {
+            self.write_index(&index).await?;
+        }
+        Ok(())
+    }
+
+    fn is_manifest(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| ext == MANIFEST_EXTENSION)
+    }
+
+    /// Reads the content and permissions of a snapshot in either format
+    async fn read_snapshot(&self, path: &Path) -> Result<(Vec<u8>, Option<u32>)> {
+        if Self::is_manifest(path) {
+            let manifest = self.read_manifest(path).await?;
+            Ok((self.read_object(&manifest.hash).await?, manifest.mode))
+        } else {
+            let content = ForgeFS::read(path)
+                .await
+                .with_context(|| format!("Failed to ForgeFS::read snapshot: {:?}", path))?;
+            Ok((content, None))
+        }
+    }
+
+    async fn snapshot_info(
+        &self,
+        file_path: &Path,
+        timestamp: u128,
+        path: &Path,
+        index: usize,
+    ) -> Result<SnapshotInfo> {
+        let mut info = SnapshotInfo::with_timestamp(
+            timestamp.to_string(),
+            file_path.to_path_buf(),
+            path.to_path_buf(),
+            index,
+        );
+        if Self::is_manifest(path) {
+            let manifest = self.read_manifest(path).await?;
+            info.size = manifest.size;
+            info.hash = Some(manifest.hash);
+        } else {
+            info.size = tokio::fs::metadata(path).await?.len();
+        }
+        Ok(info)
+    }
+
     /// Retrieves all snapshot files for a given file, sorted by timestamp
     /// (newest first)
     async fn get_sorted_snapshots(&self, file_path: &Path) -> Result<Vec<(u128, PathBuf)>> {
@@ -86,7 +271,8 @@ impl SnapshotService {
         Ok(snapshots)
     }
 
-    /// Applies retention policy to snapshots, removing excess ones
+    /// Applies retention policy to snapshots, removing excess ones along
+    /// with the contents no other snapshot refers to
     async fn apply_retention_policy(&self, file_path: &Path) -> Result<()> {
         let snapshots = self.get_sorted_snapshots(file_path).await?;
 
@@ -97,12 +283,64 @@ impl SnapshotService {
                     .await
                     .with_context(|| format!("Failed to remove excess snapshot: {:?}", path))?;
             }
+            self.collect_garbage().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a snapshot back to the file, along with its permissions when
+    /// they were recorded
+    async fn restore(&self, file_path: &Path, snapshot: SnapshotMetadata) -> Result<()> {
+        // ForgeFS::write the content back to the original file
+        ForgeFS::write(file_path, &snapshot.content)
+            .await
+            .with_context(|| format!("Failed to restore file: {:?}", file_path))?;
+
+        if let Some(mode) = snapshot.mode {
+            set_mode(file_path, mode)
+                .await
+                .with_context(|| format!("Failed to restore permissions of {:?}", file_path))?;
         }
 
         Ok(())
     }
 }
 
+/// Sets the modification time of the file to now
+async fn touch(path: &Path) -> std::io::Result<()> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(path)?
+            .set_modified(SystemTime::now())
+    })
+    .await?
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &std::fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &std::fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+async fn set_mode(path: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).await
+}
+
+#[cfg(not(unix))]
+async fn set_mode(_path: &Path, _mode: u32) -> std::io::Result<()> {
+    Ok(())
+}
+
 impl SnapshotService {
     pub fn snapshot_dir(&self) -> PathBuf {
         self.snapshot_base_dir.clone()
@@ -118,6 +356,7 @@ impl SnapshotService {
         let content = ForgeFS::read(file_path)
             .await
             .with_context(|| format!("Failed to ForgeFS::read file: {:?}", file_path))?;
+        let mode = file_mode(&tokio::fs::metadata(file_path).await?);
 
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -125,26 +364,47 @@ impl SnapshotService {
             .as_millis()
             .to_string();
 
+        let hash = self.store_object(&content).await?;
+        let manifest = Manifest {
+            timestamp: timestamp.clone(),
+            original_path: file_path.to_path_buf(),
+            hash: hash.clone(),
+            size: content.len() as u64,
+            mode,
+        };
+
+        // A path that had no snapshot directory yet is new to the index
+        let is_new = !self
+            .snapshot_base_dir
+            .join(self.hash_path(file_path))
+            .exists();
+
         // Get the snapshot directory and create it if needed
         let snapshot_dir = self.get_file_snapshot_dir(file_path).await?;
         let snapshot_filename = self.create_snapshot_filename(&timestamp);
         let snapshot_path = snapshot_dir.join(&snapshot_filename);
 
-        // ForgeFS::write the snapshot
-        ForgeFS::write(&snapshot_path, &content)
+        // ForgeFS::write the manifest
+        ForgeFS::write(&snapshot_path, serde_json::to_vec_pretty(&manifest)?)
             .await
             .with_context(|| format!("Failed to ForgeFS::write snapshot: {:?}", snapshot_path))?;
 
+        if is_new {
+            self.index_path(file_path).await?;
+        }
+
         // Apply retention policy
         self.apply_retention_policy(file_path).await?;
 
         // Create and return the SnapshotInfo
-        let snapshot_info = SnapshotInfo::with_timestamp(
+        let mut snapshot_info = SnapshotInfo::with_timestamp(
             timestamp,
             file_path.to_path_buf(),
             snapshot_path,
             0, // This is the newest snapshot, so index is 0
         );
+        snapshot_info.size = manifest.size;
+        snapshot_info.hash = Some(hash);
 
         Ok(snapshot_info)
     }
@@ -154,14 +414,10 @@ impl SnapshotService {
         let mut result = vec![];
 
         for (index, (timestamp, path)) in snapshots.iter().enumerate() {
-            let snapshot_info = SnapshotInfo::with_timestamp(
-                timestamp.to_string(),
-                file_path.to_path_buf(),
-                path.clone(),
-                index,
+            result.push(
+                self.snapshot_info(file_path, *timestamp, path, index)
+                    .await?,
             );
-
-            result.push(snapshot_info);
         }
 
         Ok(result)
@@ -169,62 +425,61 @@ impl SnapshotService {
 
     pub async fn restore_by_timestamp(&self, file_path: &Path, timestamp: &str) -> Result<()> {
         let snapshot_metadata = self.get_snapshot_by_timestamp(file_path, timestamp).await?;
-
-        // ForgeFS::write the content back to the original file
-        ForgeFS::write(file_path, &snapshot_metadata.content)
-            .await
-            .with_context(|| format!("Failed to restore file: {:?}", file_path))?;
-
-        Ok(())
+        self.restore(file_path, snapshot_metadata).await
     }
 
     pub async fn restore_by_index(&self, file_path: &Path, index: isize) -> Result<()> {
         let snapshot_metadata = self.get_snapshot_by_index(file_path, index).await?;
-
-        // ForgeFS::write the content back to the original file
-        ForgeFS::write(file_path, &snapshot_metadata.content)
-            .await
-            .with_context(|| format!("Failed to restore file: {:?}", file_path))?;
-
-        Ok(())
+        self.restore(file_path, snapshot_metadata).await
     }
 
     pub async fn restore_previous(&self, file_path: &Path) -> Result<()> {
         self.restore_by_index(file_path, -1).await
     }
 
-    pub async fn get_snapshot_by_timestamp(
+    /// Restores the snapshot picked by `selector`
+    pub async fn restore_snapshot(
         &self,
         file_path: &Path,
-        timestamp: &str,
-    ) -> Result<SnapshotMetadata> {
-        let snapshot_dir = self.get_file_snapshot_dir(file_path).await?;
-        let snapshot_filename = self.create_snapshot_filename(timestamp);
-        let snapshot_path = snapshot_dir.join(snapshot_filename);
+        selector: &SnapshotSelector,
+    ) -> Result<()> {
+        let snapshot_metadata = self.get_snapshot(file_path, selector).await?;
+        self.restore(file_path, snapshot_metadata).await
+    }
 
-        if !snapshot_path.exists() {
-            anyhow::bail!("Snapshot does not exist for timestamp {}", timestamp);
+    pub async fn get_snapshot(
+        &self,
+        file_path: &Path,
+        selector: &SnapshotSelector,
+    ) -> Result<SnapshotMetadata> {
+        match selector {
+            SnapshotSelector::Timestamp(timestamp) => {
+                self.get_snapshot_by_timestamp(file_path, timestamp).await
+            }
+            SnapshotSelector::Index(index) => self.get_snapshot_by_index(file_path, *index).await,
         }
+    }
 
-        let content = ForgeFS::read(&snapshot_path)
-            .await
-            .with_context(|| format!("Failed to ForgeFS::read snapshot: {:?}", snapshot_path))?;
-
-        // Find the index of this snapshot
+    pub async fn get_snapshot_by_timestamp(
+        &self,
+        file_path: &Path,
+        timestamp: &str,
+    ) -> Result<SnapshotMetadata> {
         let snapshots = self.get_sorted_snapshots(file_path).await?;
-        let index = snapshots
+        let Some((index, (snapshot_timestamp, snapshot_path))) = snapshots
             .iter()
-            .position(|(t, _)| t.to_string() == timestamp)
-            .unwrap_or(0);
+            .enumerate()
+            .find(|(_, (t, _))| t.to_string() == timestamp)
+        else {
+            anyhow::bail!("Snapshot does not exist for timestamp {}", timestamp);
+        };
 
-        let info = SnapshotInfo::with_timestamp(
-            timestamp.to_string(),
-            file_path.to_path_buf(),
-            snapshot_path,
-            index,
-        );
+        let (content, mode) = self.read_snapshot(snapshot_path).await?;
+        let info = self
+            .snapshot_info(file_path, *snapshot_timestamp, snapshot_path, index)
+            .await?;
 
-        Ok(SnapshotMetadata { info, content, path_hash: self.hash_path(file_path) })
+        Ok(SnapshotMetadata { info, content, path_hash: self.hash_path(file_path), mode })
     }
 
     pub async fn get_snapshot_by_index(
@@ -246,8 +501,6 @@ impl SnapshotService {
             );
         }
 
-        dbg!(index);
-
         let (timestamp, _) = snapshots[index as usize];
         self.get_snapshot_by_timestamp(file_path, &timestamp.to_string())
             .await
@@ -296,6 +549,117 @@ impl SnapshotService {
             }
         }
 
+        self.collect_garbage().await?;
+
+        Ok(removed_count)
+    }
+
+    /// Moves the snapshots of a file that was renamed, or of every file
+    /// under a directory that was, to the new path so that its history
+    /// follows it. Returns how many snapshots were moved. Snapshots in the
+    /// old format don't record their path and stay where they are.
+    pub async fn rename(&self, from: &Path, to: &Path) -> Result<usize> {
+        if !self.snapshot_base_dir.exists() {
+            return Ok(0);
+        }
+
+        let mut index = self.read_index().await?;
+        // Paths are ordered by component, so the ones under `from` follow it
+        let mut old_paths = index
+            .range(from.to_path_buf()..)
+            .take_while(|path| path.starts_with(from))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        // A file snapshotted while another process rewrote the index may be
+        // missing from it, but its directory is still found by its path
+        old_paths.insert(from.to_path_buf());
+
+        let mut moved_count = 0;
+        for old_path in old_paths {
+            index.remove(&old_path);
+            if !self
+                .snapshot_base_dir
+                .join(self.hash_path(&old_path))
+                .exists()
+            {
+                continue;
+            }
+            let relative = old_path.strip_prefix(from)?;
+            let new_path = if relative.as_os_str().is_empty() {
+                to.to_path_buf()
+            } else {
+                to.join(relative)
+            };
+
+            let mut moved = false;
+            for (_, path) in self.get_sorted_snapshots(&old_path).await? {
+                if !Self::is_manifest(&path) {
+                    continue;
+                }
+                let mut manifest = self.read_manifest(&path).await?;
+                manifest.original_path = new_path.clone();
+
+                let snapshot_dir = self.get_file_snapshot_dir(&new_path).await?;
+                let snapshot_path =
+                    snapshot_dir.join(self.create_snapshot_filename(&manifest.timestamp));
+                ForgeFS::write(&snapshot_path, serde_json::to_vec_pretty(&manifest)?)
+                    .await
+                    .with_context(|| {
+                        format!("Failed to ForgeFS::write snapshot: {:?}", snapshot_path)
+                    })?;
+                ForgeFS::remove_file(&path)
+                    .await
+                    .with_context(|| format!("Failed to remove moved snapshot: {:?}", path))?;
+                moved = true;
+                moved_count += 1;
+            }
+
+            if moved {
+                index.insert(new_path.clone());
+                // The new path may already have had snapshots of its own
+                self.apply_retention_policy(&new_path).await?;
+            }
+        }
+        self.write_index(&index).await?;
+
+        Ok(moved_count)
+    }
+
+    /// Removes the contents that no manifest refers to anymore, returns how
+    /// many were removed. Contents still being written, or stored within the
+    /// grace period, are kept since their manifest may not exist yet.
+    pub async fn collect_garbage(&self) -> Result<usize> {
+        let objects_dir = self.snapshot_base_dir.join(OBJECTS_DIR);
+        if !objects_dir.exists() {
+            return Ok(0);
+        }
+
+        let mut referenced = std::collections::HashSet::new();
+        for path in self.manifest_paths().await? {
+            referenced.insert(self.read_manifest(&path).await?.hash);
+        }
+
+        let mut removed_count = 0;
+        for path in ForgeFS::files_recursive(&objects_dir).await? {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            if referenced.contains(name) || name.ends_with(".tmp") {
+                continue;
+            }
+
+            let age = tokio::fs::metadata(&path)
+                .await?
+                .modified()?
+                .elapsed()
+                .unwrap_or_default();
+            if age >= self.object_grace_period {
+                ForgeFS::remove_file(&path).await?;
+                removed_count += 1;
+            }
+        }
+
         Ok(removed_count)
     }
 }
@@ -427,4 +791,269 @@ mod tests {
 
         Ok(())
     }
+
+    /// Number of contents kept in the objects directory
+    fn object_count(service: &SnapshotService) -> usize {
+        std::fs::read_dir(service.snapshot_dir().join(OBJECTS_DIR))
+            .map(|entries| entries.count())
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_identical_snapshots_share_one_object() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let base_path = temp_dir.path().to_path_buf();
+        let service = SnapshotService::new(base_path.join("snapshots"));
+        let test_file_path = base_path.join("test.txt");
+        tokio::fs::write(&test_file_path, b"same content").await?;
+
+        let first = service.create_snapshot(&test_file_path).await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let second = service.create_snapshot(&test_file_path).await?;
+
+        let snapshots = service.list_snapshots(&test_file_path).await?;
+        let usage = crate::SnapshotUsage::from(snapshots.as_slice());
+        assert_eq!(first.hash, second.hash);
+        assert_eq!(object_count(&service), 1);
+        assert_eq!(
+            usage,
+            crate::SnapshotUsage { logical_size: 24, stored_size: 12 }
+        );
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_restore_preserves_permissions() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempdir()?;
+        let base_path = temp_dir.path().to_path_buf();
+        let service = SnapshotService::new(base_path.join("snapshots"));
+        let test_file_path = base_path.join("run.sh");
+        tokio::fs::write(&test_file_path, b"#!/bin/sh\necho hi\n").await?;
+        std::fs::set_permissions(&test_file_path, std::fs::Permissions::from_mode(0o750))?;
+
+        service.create_snapshot(&test_file_path).await?;
+        tokio::fs::write(&test_file_path, b"broken").await?;
+        std::fs::set_permissions(&test_file_path, std::fs::Permissions::from_mode(0o600))?;
+        service.restore_by_index(&test_file_path, 0).await?;
+
+        let content = tokio::fs::read_to_string(&test_file_path).await?;
+        let mode = std::fs::metadata(&test_file_path)?.permissions().mode() & 0o7777;
+        assert_eq!(content, "#!/bin/sh\necho hi\n");
+        assert_eq!(mode, 0o750);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_collect_garbage_removes_unreferenced_objects() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let base_path = temp_dir.path().to_path_buf();
+        let mut service = SnapshotService::new(base_path.join("snapshots"));
+        service.object_grace_period = Duration::ZERO;
+        let kept_path = base_path.join("kept.txt");
+        let dropped_path = base_path.join("dropped.txt");
+        tokio::fs::write(&kept_path, b"kept").await?;
+        tokio::fs::write(&dropped_path, b"dropped").await?;
+
+        service.create_snapshot(&kept_path).await?;
+        let dropped = service.create_snapshot(&dropped_path).await?;
+        tokio::fs::remove_file(&dropped.snapshot_path).await?;
+        let removed = service.collect_garbage().await?;
+
+        assert_eq!(removed, 1);
+        assert_eq!(object_count(&service), 1);
+        assert!(!service
+            .object_path(dropped.hash.as_deref().unwrap())
+            .exists());
+        let kept = service.get_snapshot_by_index(&kept_path, 0).await?;
+        assert_eq!(kept.content, b"kept");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_collect_garbage_keeps_recent_and_partial_objects() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let base_path = temp_dir.path().to_path_buf();
+        let service = SnapshotService::new(base_path.join("snapshots"));
+        let test_file_path = base_path.join("test.txt");
+        tokio::fs::write(&test_file_path, b"test content").await?;
+
+        // The content of a snapshot whose manifest isn't written yet
+        let snapshot = service.create_snapshot(&test_file_path).await?;
+        tokio::fs::remove_file(&snapshot.snapshot_path).await?;
+        let partial = service.object_path("0123.42.tmp");
+        tokio::fs::write(&partial, b"partial").await?;
+        let removed = service.collect_garbage().await?;
+
+        assert_eq!(removed, 0);
+        assert_eq!(object_count(&service), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retention_policy_removes_unreferenced_objects() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let base_path = temp_dir.path().to_path_buf();
+        let mut service = SnapshotService::new(base_path.join("snapshots"));
+        service.max_snapshots_per_file = 3;
+        service.object_grace_period = Duration::ZERO;
+        let test_file_path = base_path.join("test.txt");
+
+        for i in 1..=5 {
+            tokio::fs::write(&test_file_path, format!("content {i}")).await?;
+            service.create_snapshot(&test_file_path).await?;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(object_count(&service), 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_purge_collects_garbage() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let base_path = temp_dir.path().to_path_buf();
+        let mut service = SnapshotService::new(base_path.join("snapshots"));
+        service.object_grace_period = Duration::ZERO;
+        let test_file_path = base_path.join("test.txt");
+        tokio::fs::write(&test_file_path, b"test content").await?;
+
+        service.create_snapshot(&test_file_path).await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let removed = service.purge_older_than(0).await?;
+
+        assert_eq!(removed, 1);
+        assert_eq!(object_count(&service), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lists_old_and_new_format() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let base_path = temp_dir.path().to_path_buf();
+        let service = SnapshotService::new(base_path.join("snapshots"));
+        let test_file_path = base_path.join("test.txt");
+        let legacy_dir = service.get_file_snapshot_dir(&test_file_path).await?;
+        tokio::fs::write(legacy_dir.join("1000.snap"), b"old content").await?;
+        tokio::fs::write(&test_file_path, b"new content").await?;
+
+        service.create_snapshot(&test_file_path).await?;
+        let snapshots = service.list_snapshots(&test_file_path).await?;
+
+        let actual = snapshots
+            .iter()
+            .map(|snapshot| (snapshot.index, snapshot.size, snapshot.hash.is_some()))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, vec![(0, 11, true), (1, 11, false)]);
+        assert_eq!(snapshots[1].timestamp, "1000");
+
+        service
+            .restore_by_timestamp(&test_file_path, "1000")
+            .await?;
+        let content = tokio::fs::read_to_string(&test_file_path).await?;
+        assert_eq!(content, "old content");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_moves_history_to_new_path() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let base_path = temp_dir.path().to_path_buf();
+        let service = SnapshotService::new(base_path.join("snapshots"));
+        let old_path = base_path.join("old.txt");
+        let new_path = base_path.join("new.txt");
+        tokio::fs::write(&old_path, b"old content").await?;
+
+        service.create_snapshot(&old_path).await?;
+        tokio::fs::rename(&old_path, &new_path).await?;
+        let moved = service.rename(&old_path, &new_path).await?;
+
+        assert_eq!(moved, 1);
+        assert!(service.list_snapshots(&old_path).await?.is_empty());
+        let snapshot = service.get_snapshot_by_index(&new_path, 0).await?;
+        assert_eq!(snapshot.info.original_path, new_path);
+        assert_eq!(snapshot.content, b"old content");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_directory_moves_history_of_files_under_it() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let base_path = temp_dir.path().to_path_buf();
+        let service = SnapshotService::new(base_path.join("snapshots"));
+        let inside = base_path.join("src").join("lib.rs");
+        let sibling = base_path.join("src_old").join("lib.rs");
+        tokio::fs::create_dir_all(inside.parent().unwrap()).await?;
+        tokio::fs::create_dir_all(sibling.parent().unwrap()).await?;
+        tokio::fs::write(&inside, b"inside").await?;
+        tokio::fs::write(&sibling, b"sibling").await?;
+
+        service.create_snapshot(&inside).await?;
+        service.create_snapshot(&sibling).await?;
+        let moved = service
+            .rename(&base_path.join("src"), &base_path.join("lib"))
+            .await?;
+
+        assert_eq!(moved, 1);
+        let renamed = base_path.join("lib").join("lib.rs");
+        assert_eq!(service.list_snapshots(&renamed).await?.len(), 1);
+        assert_eq!(service.list_snapshots(&sibling).await?.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_leaves_manifests_of_other_files_unread() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let base_path = temp_dir.path().to_path_buf();
+        let service = SnapshotService::new(base_path.join("snapshots"));
+        let old_path = base_path.join("old.txt");
+        let new_path = base_path.join("new.txt");
+        let other_path = base_path.join("other.txt");
+        tokio::fs::write(&old_path, b"old content").await?;
+        tokio::fs::write(&other_path, b"other content").await?;
+        service.create_snapshot(&old_path).await?;
+        let other = service.create_snapshot(&other_path).await?;
+        tokio::fs::write(&other.snapshot_path, b"not a manifest").await?;
+
+        let moved = service.rename(&old_path, &new_path).await?;
+
+        assert_eq!(moved, 1);
+        assert_eq!(service.list_snapshots(&new_path).await?.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_builds_index_of_store_without_one() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let base_path = temp_dir.path().to_path_buf();
+        let service = SnapshotService::new(base_path.join("snapshots"));
+        let inside = base_path.join("src").join("lib.rs");
+        tokio::fs::create_dir_all(inside.parent().unwrap()).await?;
+        tokio::fs::write(&inside, b"inside").await?;
+        service.create_snapshot(&inside).await?;
+        tokio::fs::remove_file(base_path.join("snapshots").join(INDEX_FILE)).await?;
+
+        let moved = service
+            .rename(&base_path.join("src"), &base_path.join("lib"))
+            .await?;
+
+        assert_eq!(moved, 1);
+        let renamed = base_path.join("lib").join("lib.rs");
+        assert_eq!(service.list_snapshots(&renamed).await?.len(), 1);
+        assert_eq!(service.collect_garbage().await?, 0);
+
+        Ok(())
+    }
 }
@@ -1,9 +1,9 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenTree;
 use quote::{quote, ToTokens};
-use syn::{parse_macro_input, DeriveInput};
+use syn::{parse_macro_input, DeriveInput, Expr, ExprLit, Lit, Meta};
 
-#[proc_macro_derive(ToolDescription)]
+#[proc_macro_derive(ToolDescription, attributes(example))]
 pub fn derive_description(input: TokenStream) -> TokenStream {
     // Parse the input struct or enum
     let input = parse_macro_input!(input as DeriveInput);
@@ -45,6 +45,37 @@ pub fn derive_description(input: TokenStream) -> TokenStream {
     }
     let doc_string = doc_lines.join("\n").trim().to_string();
 
+    // Collect the `#[example = "..."]` inputs, checking they are valid JSON so a
+    // broken example fails the build
+    let mut examples = Vec::new();
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("example"))
+    {
+        let example = match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }) => lit.value(),
+                _ => panic!("Example of {} must be a string literal", name),
+            },
+            _ => panic!("Example of {} must be written #[example = \"...\"]", name),
+        };
+        if let Err(err) = serde_json::from_str::<serde_json::Value>(&example) {
+            panic!("Example of {} isn't valid JSON: {}", name, err);
+        }
+        examples.push(example);
+    }
+
+    let examples_fn = if examples.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            fn examples(&self) -> Vec<serde_json::Value> {
+                vec![#(serde_json::from_str(#examples).expect("Examples are checked at build time")),*]
+            }
+        }
+    };
+
     // Generate an implementation of `ToolDescription` that returns the doc string
     let expanded = if generics.params.is_empty() {
         quote! {
@@ -52,6 +83,7 @@ pub fn derive_description(input: TokenStream) -> TokenStream {
                 fn description(&self) -> String {
                     #doc_string.into()
                 }
+                #examples_fn
             }
         }
     } else {
@@ -60,6 +92,7 @@ pub fn derive_description(input: TokenStream) -> TokenStream {
                 fn description(&self) -> String {
                     #doc_string.into()
                 }
+                #examples_fn
             }
         }
     };
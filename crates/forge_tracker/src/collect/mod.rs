@@ -6,5 +6,5 @@ pub mod posthog;
 /// Defines the interface for an event collector.
 #[async_trait::async_trait]
 pub trait Collect: Send + Sync {
-    async fn collect(&self, event: Event) -> super::Result<()>;
+    async fn collect(&self, client: &reqwest::Client, event: Event) -> super::Result<()>;
 }
@@ -72,9 +72,8 @@ impl Tracker {
 #[async_trait::async_trait]
 impl Collect for Tracker {
     // TODO: move http request to a dispatch
-    async fn collect(&self, event: Event) -> Result<()> {
+    async fn collect(&self, client: &reqwest::Client, event: Event) -> Result<()> {
         let request = self.create_request(event)?;
-        let client = reqwest::Client::new();
         client.execute(request).await?;
 
         Ok(())
@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 use std::process::Output;
+use std::sync::OnceLock;
 
 use chrono::{DateTime, Utc};
 use machineid_rs::{Encryption, HWIDComponent, IdBuilder};
@@ -30,6 +31,8 @@ const DEFAULT_CLIENT_ID: &str = "<anonymous>";
 
 pub struct Tracker {
     collectors: Vec<Box<dyn Collect>>,
+    /// Client the events are sent with, see [`Tracker::set_client`]
+    client: OnceLock<reqwest::Client>,
     can_track: bool,
     start_time: DateTime<Utc>,
     email: Mutex<Option<Vec<String>>>,
@@ -42,6 +45,7 @@ impl Default for Tracker {
         let can_track = can_track();
         Self {
             collectors: vec![posthog_tracker],
+            client: OnceLock::new(),
             can_track,
             start_time,
             email: Mutex::new(None),
@@ -50,6 +54,13 @@ impl Default for Tracker {
 }
 
 impl Tracker {
+    /// Sets the client the events are sent with, built by the app with the
+    /// configured proxies and certificates. No event is sent before, the
+    /// client is only set once.
+    pub fn set_client(&self, client: reqwest::Client) {
+        let _ = self.client.set(client);
+    }
+
     pub async fn init_ping(&'static self, duration: Duration) {
         let mut interval = tokio::time::interval(duration);
         tokio::task::spawn(async move {
@@ -61,6 +72,9 @@ impl Tracker {
     }
 
     pub async fn dispatch(&'static self, event_kind: EventKind) -> Result<()> {
+        let Some(client) = self.client.get() else {
+            return Ok(());
+        };
         if self.can_track {
             // Create a new event
             let event = Event {
@@ -81,7 +95,7 @@ impl Tracker {
 
             // Dispatch the event to all collectors
             for collector in &self.collectors {
-                collector.collect(event.clone()).await?;
+                collector.collect(client, event.clone()).await?;
             }
 
             debug!(event = ?event, "Event dispatched");
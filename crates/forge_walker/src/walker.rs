@@ -43,6 +43,10 @@ pub struct Walker {
     skip_binary: bool,
 }
 
+/// Gitignore-style file listing paths that are hidden from the agent on top
+/// of the ones ignored by git
+pub const FORGE_IGNORE: &str = ".forgeignore";
+
 const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024; // 1MB
 const DEFAULT_MAX_FILES: usize = 100;
 const DEFAULT_MAX_TOTAL_SIZE: u64 = 10 * 1024 * 1024; // 10MB
@@ -118,6 +122,7 @@ impl Walker {
             .git_global(true) // Use global gitignore
             .git_ignore(true) // Use local .gitignore
             .ignore(true) // Use .ignore files
+            .add_custom_ignore_filename(FORGE_IGNORE) // Use .forgeignore files
             .max_depth(Some(self.max_depth))
             // TODO: use build_parallel() for better performance
             .build();
@@ -383,4 +388,27 @@ mod tests {
         assert!(dir.is_dir());
         assert!(dir.path.ends_with('/'));
     }
+
+    #[tokio::test]
+    async fn test_walker_respects_forgeignore() {
+        let fixture =
+            fixtures::create_sized_files(&[("main.rs".into(), 10), ("server.pem".into(), 10)])
+                .unwrap();
+        fs::write(fixture.path().join(FORGE_IGNORE), "*.pem\n").unwrap();
+
+        let actual = Walker::min_all()
+            .cwd(fixture.path().to_path_buf())
+            .get()
+            .await
+            .unwrap();
+
+        let expected = vec!["main.rs"];
+        let actual_files: Vec<_> = actual
+            .iter()
+            .filter(|f| !f.is_dir())
+            .map(|f| f.path.as_str())
+            .collect();
+
+        assert_eq!(actual_files, expected);
+    }
 }